@@ -0,0 +1,493 @@
+use serde::{Deserialize, Serialize};
+
+use crate::app::DrumMap;
+use crate::domain::{
+    BEATS_PER_BAR, GeneratedControlEvent, GeneratedNote, GenerationCandidate, PITCH_BEND_MIN,
+    TickResolution,
+};
+
+/// Per-note expression in CLAP units: `volume` is linear gain, `brightness` is 0.0..=1.0.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AppliedNoteExpression {
+    pub volume: f64,
+    pub brightness: f64,
+}
+
+impl AppliedNoteExpression {
+    /// Louder notes get more gain and brightness; short, detached notes get a brighter attack.
+    pub fn from_note(note: &GeneratedNote, resolution: TickResolution) -> Self {
+        let dynamics = f64::from(note.velocity.min(127)) / 127.0;
+        let duration_beats = resolution.ticks_to_beats(note.duration_tick);
+        let detached = 1.0 - duration_beats.clamp(0.0, 1.0);
+        Self {
+            volume: 0.5 + 0.5 * dynamics,
+            brightness: (0.7 * dynamics + 0.3 * detached).clamp(0.0, 1.0),
+        }
+    }
+}
+
+/// A MIDI message positioned on the clip timeline, in quarter-note beats.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AppliedClipEvent {
+    pub beat: f64,
+    pub data: [u8; 3],
+    /// Set on note-ons only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expression: Option<AppliedNoteExpression>,
+    /// Set on note-offs that were pulled forward because another drum in the group was hit.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub choke: bool,
+}
+
+impl AppliedClipEvent {
+    pub fn is_note_on(&self) -> bool {
+        self.data[0] & 0xF0 == 0x90 && self.data[2] > 0
+    }
+
+    pub fn is_note_off(&self) -> bool {
+        match self.data[0] & 0xF0 {
+            0x80 => true,
+            0x90 => self.data[2] == 0,
+            _ => false,
+        }
+    }
+
+    /// Whether the event starts or stops a voice; controllers and pitch bends do not.
+    pub fn is_note_event(&self) -> bool {
+        self.is_note_on() || self.is_note_off()
+    }
+
+    fn sort_rank(&self) -> u8 {
+        if self.is_note_on() {
+            2
+        } else if self.is_note_off() {
+            0
+        } else {
+            1
+        }
+    }
+
+    fn channel_and_key(&self) -> (u8, u8) {
+        (self.data[0] & 0x0F, self.data[1])
+    }
+}
+
+/// A candidate rendered to raw MIDI for looped playback on the plugin output.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppliedClip {
+    pub length_beats: f64,
+    pub events: Vec<AppliedClipEvent>,
+    /// Emit notes as CLAP note events with volume/brightness expressions instead of raw MIDI.
+    #[serde(default)]
+    pub note_expressions: bool,
+}
+
+impl AppliedClip {
+    pub fn from_candidate(candidate: &GenerationCandidate) -> Self {
+        Self::from_notes(
+            &candidate.notes,
+            &candidate.control_events,
+            candidate.bars,
+            candidate.tick_resolution,
+        )
+    }
+
+    /// Builds a `bars`-long loop from notes positioned at `resolution`, e.g. a reference being
+    /// auditioned before generation.
+    pub fn from_notes(
+        notes: &[GeneratedNote],
+        control_events: &[GeneratedControlEvent],
+        bars: u16,
+        resolution: TickResolution,
+    ) -> Self {
+        let length_beats = f64::from(u32::from(bars.max(1)) * BEATS_PER_BAR);
+
+        let mut timed = Vec::with_capacity(notes.len() * 2 + control_events.len());
+        for event in control_events {
+            let beat = resolution.ticks_to_beats(event.tick());
+            if beat >= length_beats {
+                continue;
+            }
+            let status_channel = event.channel().clamp(1, 16) - 1;
+            let data = match *event {
+                GeneratedControlEvent::ControlChange {
+                    controller, value, ..
+                } => [0xB0 | status_channel, controller.min(127), value.min(127)],
+                GeneratedControlEvent::PitchBend { value, .. } => {
+                    let bend =
+                        (i32::from(value) - i32::from(PITCH_BEND_MIN)).clamp(0, 0x3FFF) as u16;
+                    [
+                        0xE0 | status_channel,
+                        (bend & 0x7F) as u8,
+                        (bend >> 7) as u8,
+                    ]
+                }
+            };
+            timed.push((beat, 1u8, data, None));
+        }
+        for note in notes {
+            let start_beat = resolution.ticks_to_beats(note.start_tick);
+            // Notes that start past the clip end would never sound inside the loop.
+            if start_beat >= length_beats {
+                continue;
+            }
+            let end_beat = resolution
+                .ticks_to_beats(note.start_tick.saturating_add(note.duration_tick))
+                .min(length_beats);
+            let status_channel = note.channel.clamp(1, 16) - 1;
+            let pitch = note.pitch.min(127);
+            timed.push((
+                start_beat,
+                2u8,
+                [0x90 | status_channel, pitch, note.velocity.clamp(1, 127)],
+                Some(AppliedNoteExpression::from_note(note, resolution)),
+            ));
+            timed.push((end_beat, 0u8, [0x80 | status_channel, pitch, 0], None));
+        }
+        // Note-offs sort ahead of note-ons on the same beat so repeated pitches retrigger cleanly;
+        // controllers sit between so a new note starts with its expression already applied.
+        timed.sort_by(|left, right| left.0.total_cmp(&right.0).then(left.1.cmp(&right.1)));
+
+        Self {
+            length_beats,
+            events: timed
+                .into_iter()
+                .map(|(beat, _, data, expression)| AppliedClipEvent {
+                    beat,
+                    data,
+                    expression,
+                    choke: false,
+                })
+                .collect(),
+            note_expressions: false,
+        }
+    }
+
+    /// Cuts ringing notes short when another key in their choke group is hit on the same channel.
+    pub fn with_drum_chokes(mut self, drum_map: &DrumMap) -> Self {
+        let mut ringing: Vec<usize> = Vec::new();
+        for index in 0..self.events.len() {
+            let event = self.events[index];
+            if !event.is_note_on() {
+                continue;
+            }
+            let (channel, key) = event.channel_and_key();
+            ringing.retain(|off_index| self.events[*off_index].beat > event.beat);
+            ringing.retain(|off_index| {
+                let ringing_off = &mut self.events[*off_index];
+                let (ringing_channel, ringing_key) = ringing_off.channel_and_key();
+                if ringing_channel != channel || !drum_map.chokes(key, ringing_key) {
+                    return true;
+                }
+                ringing_off.beat = event.beat;
+                ringing_off.choke = true;
+                false
+            });
+            if let Some(off_index) = self.events[index + 1..]
+                .iter()
+                .position(|candidate| {
+                    candidate.is_note_off() && candidate.channel_and_key() == (channel, key)
+                })
+                .map(|offset| index + 1 + offset)
+            {
+                ringing.push(off_index);
+            }
+        }
+
+        // Stable, so a choked note-off stays ahead of the note-on that cut it.
+        self.events.sort_by(|left, right| {
+            left.beat
+                .total_cmp(&right.beat)
+                .then(left.sort_rank().cmp(&right.sort_rank()))
+        });
+        self
+    }
+
+    /// Visits events in `start_beat..end_beat` of the host timeline, looping the clip from beat 0.
+    pub fn for_each_event_between(
+        &self,
+        start_beat: f64,
+        end_beat: f64,
+        mut visit: impl FnMut(f64, &AppliedClipEvent),
+    ) {
+        if !(self.length_beats > 0.0) || !(end_beat > start_beat) || start_beat < 0.0 {
+            return;
+        }
+
+        // Note-offs may sit exactly on the clip end, so the previous cycle is scanned as well.
+        let first_cycle = ((start_beat / self.length_beats).floor() - 1.0).max(0.0);
+        let last_cycle = (end_beat / self.length_beats).floor();
+        let mut cycle = first_cycle;
+        while cycle <= last_cycle {
+            let cycle_start = cycle * self.length_beats;
+            let local_start = start_beat - cycle_start;
+            let local_end = end_beat - cycle_start;
+            let first = self
+                .events
+                .partition_point(|event| event.beat < local_start);
+            for event in self.events[first..]
+                .iter()
+                .take_while(|event| event.beat < local_end)
+            {
+                visit(cycle_start + event.beat, event);
+            }
+            cycle += 1.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AppliedClip, AppliedNoteExpression};
+    use crate::app::DrumMap;
+    use crate::domain::{
+        GeneratedControlEvent, GeneratedNote, GenerationCandidate, TickResolution,
+    };
+
+    fn candidate() -> GenerationCandidate {
+        GenerationCandidate {
+            id: "cand-1".to_string(),
+            bars: 1,
+            notes: vec![
+                GeneratedNote {
+                    pitch: 60,
+                    start_tick: 0,
+                    duration_tick: 480,
+                    velocity: 100,
+                    channel: 2,
+                },
+                GeneratedNote {
+                    pitch: 64,
+                    start_tick: 1440,
+                    duration_tick: 960,
+                    velocity: 90,
+                    channel: 2,
+                },
+            ],
+            tick_resolution: TickResolution::DEFAULT,
+            score_hint: None,
+            title: None,
+            control_events: Vec::new(),
+            processing: None,
+        }
+    }
+
+    #[test]
+    fn clip_from_notes_uses_the_given_resolution_and_length() {
+        let notes = [GeneratedNote {
+            pitch: 48,
+            start_tick: 96,
+            duration_tick: 192,
+            velocity: 80,
+            channel: 3,
+        }];
+
+        let clip = AppliedClip::from_notes(
+            &notes,
+            &[],
+            2,
+            TickResolution::new(96).expect("96 PPQ should be valid"),
+        );
+
+        assert_eq!(clip.length_beats, 8.0);
+        assert_eq!(clip.events.len(), 2);
+        assert_eq!(clip.events[0].beat, 1.0);
+        assert_eq!(clip.events[0].data, [0x92, 48, 80]);
+        assert_eq!(clip.events[1].beat, 3.0);
+    }
+
+    #[test]
+    fn candidate_is_rendered_to_beat_positioned_midi() {
+        let clip = AppliedClip::from_candidate(&candidate());
+
+        let events = clip
+            .events
+            .iter()
+            .map(|event| (event.beat, event.data))
+            .collect::<Vec<_>>();
+
+        assert_eq!(clip.length_beats, 4.0);
+        assert_eq!(
+            events,
+            vec![
+                (0.0, [0x91, 60, 100]),
+                (1.0, [0x81, 60, 0]),
+                (3.0, [0x91, 64, 90]),
+                (4.0, [0x81, 64, 0]),
+            ]
+        );
+    }
+
+    #[test]
+    fn note_off_on_the_clip_end_is_not_skipped_at_block_boundaries() {
+        let clip = AppliedClip::from_candidate(&candidate());
+        let mut visited = Vec::new();
+
+        clip.for_each_event_between(4.0, 4.5, |beat, event| visited.push((beat, event.data[0])));
+
+        assert_eq!(visited, vec![(4.0, 0x81), (4.0, 0x91)]);
+    }
+
+    #[test]
+    fn events_between_wrap_around_the_clip_loop() {
+        let clip = AppliedClip::from_candidate(&candidate());
+        let mut visited = Vec::new();
+
+        clip.for_each_event_between(3.5, 8.5, |beat, event| visited.push((beat, event.data[0])));
+
+        assert_eq!(
+            visited,
+            vec![
+                (4.0, 0x81),
+                (4.0, 0x91),
+                (5.0, 0x81),
+                (7.0, 0x91),
+                (8.0, 0x81),
+                (8.0, 0x91)
+            ]
+        );
+    }
+
+    #[test]
+    fn note_expression_follows_velocity_and_articulation() {
+        let clip = AppliedClip::from_candidate(&candidate());
+        let note_on_expressions = clip
+            .events
+            .iter()
+            .filter(|event| event.is_note_on())
+            .map(|event| event.expression.expect("note-ons carry an expression"))
+            .collect::<Vec<_>>();
+
+        assert_eq!(note_on_expressions.len(), 2);
+        assert!(
+            clip.events
+                .iter()
+                .all(|event| event.is_note_on() || event.expression.is_none())
+        );
+        assert!(note_on_expressions[0].volume > note_on_expressions[1].volume);
+
+        let staccato = AppliedNoteExpression::from_note(
+            &GeneratedNote {
+                pitch: 60,
+                start_tick: 0,
+                duration_tick: 120,
+                velocity: 90,
+                channel: 1,
+            },
+            TickResolution::DEFAULT,
+        );
+        let legato = AppliedNoteExpression::from_note(
+            &GeneratedNote {
+                pitch: 60,
+                start_tick: 0,
+                duration_tick: 960,
+                velocity: 90,
+                channel: 1,
+            },
+            TickResolution::DEFAULT,
+        );
+        assert!(staccato.brightness > legato.brightness);
+        assert_eq!(staccato.volume, legato.volume);
+    }
+
+    #[test]
+    fn control_events_are_rendered_ahead_of_note_ons_on_the_same_beat() {
+        let mut candidate = candidate();
+        candidate.control_events = vec![
+            GeneratedControlEvent::PitchBend {
+                tick: 1440,
+                value: 0,
+                channel: 2,
+            },
+            GeneratedControlEvent::ControlChange {
+                tick: 1440,
+                controller: 11,
+                value: 100,
+                channel: 2,
+            },
+            GeneratedControlEvent::ControlChange {
+                tick: 1920,
+                controller: 1,
+                value: 10,
+                channel: 2,
+            },
+        ];
+
+        let clip =
+            AppliedClip::from_candidate(&candidate).with_drum_chokes(&DrumMap::general_midi());
+        let events = clip
+            .events
+            .iter()
+            .map(|event| (event.beat, event.data))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            events,
+            vec![
+                (0.0, [0x91, 60, 100]),
+                (1.0, [0x81, 60, 0]),
+                (3.0, [0xE1, 0x00, 0x40]),
+                (3.0, [0xB1, 11, 100]),
+                (3.0, [0x91, 64, 90]),
+                (4.0, [0x81, 64, 0]),
+            ]
+        );
+        assert!(!clip.events[2].is_note_event());
+    }
+
+    #[test]
+    fn closed_hat_chokes_ringing_open_hat() {
+        let candidate = GenerationCandidate {
+            id: "drums".to_string(),
+            bars: 1,
+            notes: vec![
+                GeneratedNote {
+                    pitch: 46,
+                    start_tick: 0,
+                    duration_tick: 960,
+                    velocity: 100,
+                    channel: 10,
+                },
+                GeneratedNote {
+                    pitch: 42,
+                    start_tick: 480,
+                    duration_tick: 240,
+                    velocity: 100,
+                    channel: 10,
+                },
+                GeneratedNote {
+                    pitch: 36,
+                    start_tick: 1440,
+                    duration_tick: 480,
+                    velocity: 100,
+                    channel: 10,
+                },
+            ],
+            tick_resolution: TickResolution::DEFAULT,
+            score_hint: None,
+            title: None,
+            control_events: Vec::new(),
+            processing: None,
+        };
+
+        let clip =
+            AppliedClip::from_candidate(&candidate).with_drum_chokes(&DrumMap::general_midi());
+        let events = clip
+            .events
+            .iter()
+            .map(|event| (event.beat, event.data[0], event.data[1], event.choke))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            events,
+            vec![
+                (0.0, 0x99, 46, false),
+                (1.0, 0x89, 46, true),
+                (1.0, 0x99, 42, false),
+                (1.5, 0x89, 42, false),
+                (3.0, 0x99, 36, false),
+                (4.0, 0x89, 36, false),
+            ]
+        );
+    }
+}
@@ -0,0 +1,306 @@
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use super::candidate_autosave::encode_candidate;
+use super::stem_export::sanitize_file_label;
+use super::{AppliedClip, HelperControlIpcSender, HelperControlMessage};
+use crate::domain::{ChordLabel, GenerationCandidate, TickResolution};
+use crate::infra::midi::MidiConductor;
+
+/// Where Apply sends a candidate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ApplyDestination {
+    /// Loop the clip on the plugin's MIDI output.
+    #[default]
+    Plugin,
+    /// Write the candidate as a MIDI file.
+    File,
+    Both,
+}
+
+impl ApplyDestination {
+    pub const ALL: [Self; 3] = [Self::Plugin, Self::File, Self::Both];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Plugin => "Plugin Output",
+            Self::File => "MIDI File",
+            Self::Both => "Plugin + File",
+        }
+    }
+
+    /// `plugin`, `file`, or `both`, as taken on the command line.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "plugin" => Some(Self::Plugin),
+            "file" => Some(Self::File),
+            "both" => Some(Self::Both),
+            _ => None,
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let position = Self::ALL
+            .iter()
+            .position(|destination| *destination == self)
+            .unwrap_or_default();
+        Self::ALL[(position + 1) % Self::ALL.len()]
+    }
+
+    pub fn sends_to_plugin(self) -> bool {
+        matches!(self, Self::Plugin | Self::Both)
+    }
+
+    pub fn writes_file(self) -> bool {
+        matches!(self, Self::File | Self::Both)
+    }
+}
+
+/// Receives clips for the plugin output.
+pub trait AppliedClipSink {
+    fn send_applied_clip(&self, clip: AppliedClip) -> Result<(), ApplyError>;
+}
+
+impl AppliedClipSink for HelperControlIpcSender {
+    fn send_applied_clip(&self, clip: AppliedClip) -> Result<(), ApplyError> {
+        self.send(&HelperControlMessage::AppliedClip { clip: Some(clip) })
+            .map_err(|error| ApplyError::PluginSend {
+                message: error.to_string(),
+            })
+    }
+}
+
+/// How a candidate applied to a file is written.
+#[derive(Debug, Clone)]
+pub struct ApplyFileTarget<'a> {
+    pub dir: &'a Path,
+    /// Rendered from the export name template.
+    pub file_stem: &'a str,
+    pub chords: &'a [ChordLabel],
+    pub resolution: TickResolution,
+    pub conductor: &'a MidiConductor,
+    pub program: Option<u8>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApplyOutcome {
+    pub sent_to_plugin: bool,
+    pub file: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ApplyError {
+    #[error("no plugin is connected to play the clip")]
+    PluginUnavailable,
+    #[error("failed to send the clip to the plugin: {message}")]
+    PluginSend { message: String },
+    #[error("no folder is set for applied MIDI files")]
+    NoFileFolder,
+    #[error("failed to write the applied MIDI file: {message}")]
+    File { message: String },
+}
+
+/// `<file stem>-<candidate id>-applied.mid`
+pub fn applied_file_name(file_stem: &str, candidate_id: &str) -> String {
+    format!(
+        "{}-{}-applied.mid",
+        sanitize_file_label(file_stem),
+        sanitize_file_label(candidate_id)
+    )
+}
+
+/// Sends `candidate` where `destination` says. Every destination is checked before anything
+/// is applied, so a missing plugin or folder never leaves the candidate half applied.
+pub fn dispatch_apply(
+    destination: ApplyDestination,
+    candidate: &GenerationCandidate,
+    clip: AppliedClip,
+    plugin: Option<&dyn AppliedClipSink>,
+    file_target: Option<ApplyFileTarget<'_>>,
+) -> Result<ApplyOutcome, ApplyError> {
+    let plugin = match (destination.sends_to_plugin(), plugin) {
+        (true, None) => return Err(ApplyError::PluginUnavailable),
+        (true, Some(plugin)) => Some(plugin),
+        (false, _) => None,
+    };
+    let file_target = match (destination.writes_file(), file_target) {
+        (true, None) => return Err(ApplyError::NoFileFolder),
+        (true, Some(target)) => Some(target),
+        (false, _) => None,
+    };
+
+    let mut outcome = ApplyOutcome::default();
+    if let Some(target) = file_target {
+        outcome.file = Some(write_applied_file(candidate, &target)?);
+    }
+    if let Some(plugin) = plugin {
+        plugin.send_applied_clip(clip)?;
+        outcome.sent_to_plugin = true;
+    }
+    Ok(outcome)
+}
+
+fn write_applied_file(
+    candidate: &GenerationCandidate,
+    target: &ApplyFileTarget<'_>,
+) -> Result<PathBuf, ApplyError> {
+    let file_error = |message: String| ApplyError::File { message };
+    let bytes = encode_candidate(
+        candidate,
+        target.chords,
+        target.resolution,
+        target.conductor,
+        target.program,
+    )
+    .map_err(|error| file_error(error.to_string()))?;
+    std::fs::create_dir_all(target.dir).map_err(|error| file_error(error.to_string()))?;
+    let path = target
+        .dir
+        .join(applied_file_name(target.file_stem, &candidate.id));
+    std::fs::write(&path, bytes).map_err(|error| file_error(error.to_string()))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::{
+        AppliedClipSink, ApplyDestination, ApplyError, ApplyFileTarget, applied_file_name,
+        dispatch_apply,
+    };
+    use crate::app::AppliedClip;
+    use crate::domain::{GeneratedNote, GenerationCandidate, TickResolution};
+    use crate::infra::midi::{MidiConductor, parse_midi_reference};
+    use crate::test_support::TestDir;
+
+    #[derive(Default)]
+    struct RecordingSink {
+        clips: RefCell<Vec<AppliedClip>>,
+    }
+
+    impl AppliedClipSink for RecordingSink {
+        fn send_applied_clip(&self, clip: AppliedClip) -> Result<(), ApplyError> {
+            self.clips.borrow_mut().push(clip);
+            Ok(())
+        }
+    }
+
+    fn candidate() -> GenerationCandidate {
+        GenerationCandidate {
+            id: "cand-1".to_string(),
+            bars: 1,
+            notes: vec![GeneratedNote {
+                pitch: 60,
+                start_tick: 0,
+                duration_tick: 480,
+                velocity: 100,
+                channel: 1,
+            }],
+            tick_resolution: TickResolution::DEFAULT,
+            score_hint: None,
+            title: None,
+            control_events: Vec::new(),
+            processing: None,
+        }
+    }
+
+    #[test]
+    fn destinations_parse_and_cycle() {
+        assert_eq!(
+            ApplyDestination::parse(" Both "),
+            Some(ApplyDestination::Both)
+        );
+        assert_eq!(ApplyDestination::parse("daw"), None);
+        assert_eq!(ApplyDestination::Both.next(), ApplyDestination::Plugin);
+        assert!(ApplyDestination::Both.sends_to_plugin() && ApplyDestination::Both.writes_file());
+        assert!(!ApplyDestination::File.sends_to_plugin());
+    }
+
+    #[test]
+    fn missing_destinations_fail_before_anything_is_applied() {
+        let sink = RecordingSink::default();
+        let candidate = candidate();
+
+        let result = dispatch_apply(
+            ApplyDestination::Both,
+            &candidate,
+            AppliedClip::from_candidate(&candidate),
+            Some(&sink),
+            None,
+        );
+
+        assert_eq!(result, Err(ApplyError::NoFileFolder));
+        assert!(sink.clips.borrow().is_empty());
+        assert_eq!(
+            dispatch_apply(
+                ApplyDestination::Plugin,
+                &candidate,
+                AppliedClip::from_candidate(&candidate),
+                None,
+                None,
+            ),
+            Err(ApplyError::PluginUnavailable)
+        );
+    }
+
+    #[test]
+    fn a_failed_send_is_reported_instead_of_success() {
+        struct ClosedSink;
+        impl AppliedClipSink for ClosedSink {
+            fn send_applied_clip(&self, _clip: AppliedClip) -> Result<(), ApplyError> {
+                Err(ApplyError::PluginSend {
+                    message: "the plugin connection is closed".to_string(),
+                })
+            }
+        }
+        let candidate = candidate();
+
+        let result = dispatch_apply(
+            ApplyDestination::Plugin,
+            &candidate,
+            AppliedClip::from_candidate(&candidate),
+            Some(&ClosedSink),
+            None,
+        );
+
+        assert!(matches!(result, Err(ApplyError::PluginSend { .. })));
+    }
+
+    #[test]
+    fn both_writes_the_file_and_sends_the_clip() {
+        let sink = RecordingSink::default();
+        let candidate = candidate();
+        let test_dir = TestDir::new("apply-routing");
+        let dir = test_dir.path();
+        let conductor = MidiConductor::new(120);
+
+        let outcome = dispatch_apply(
+            ApplyDestination::Both,
+            &candidate,
+            AppliedClip::from_candidate(&candidate),
+            Some(&sink),
+            Some(ApplyFileTarget {
+                dir,
+                file_stem: "sonant-req-1",
+                chords: &[],
+                resolution: TickResolution::DEFAULT,
+                conductor: &conductor,
+                program: None,
+            }),
+        )
+        .expect("apply should succeed");
+        let bytes = std::fs::read(dir.join(applied_file_name("sonant-req-1", "cand-1")));
+
+        assert!(outcome.sent_to_plugin);
+        assert_eq!(sink.clips.borrow().len(), 1);
+        assert_eq!(
+            outcome.file,
+            Some(dir.join("sonant-req-1-cand-1-applied.mid"))
+        );
+        let reference = parse_midi_reference(&bytes.expect("applied file should exist"))
+            .expect("applied file should parse");
+        assert_eq!(reference.summary.note_count, 1);
+    }
+}
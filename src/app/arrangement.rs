@@ -0,0 +1,445 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::domain::{
+    BEATS_PER_BAR, ChordLabel, GeneratedControlEvent, GeneratedNote, GenerationCandidate,
+    MidiReferenceEvent, MidiReferenceSummary, ReferenceSlot, ReferenceSource, TickResolution,
+    calculate_reference_density_hint,
+};
+
+pub const ARRANGEMENT_SECTION_MAX_BARS: u16 = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArrangementSection {
+    pub name: String,
+    pub bars: u16,
+    pub prompt: String,
+}
+
+impl ArrangementSection {
+    pub fn validate(&self) -> Result<(), ArrangementError> {
+        if self.name.trim().is_empty() {
+            return Err(ArrangementError::EmptySectionName);
+        }
+        if !(1..=ARRANGEMENT_SECTION_MAX_BARS).contains(&self.bars) {
+            return Err(ArrangementError::SectionBarsOutOfRange {
+                name: self.name.clone(),
+                bars: self.bars,
+            });
+        }
+        if self.prompt.trim().is_empty() {
+            return Err(ArrangementError::EmptySectionPrompt {
+                name: self.name.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ArrangementError {
+    #[error("arrangement must contain at least one section")]
+    NoSections,
+    #[error("section name must not be empty")]
+    EmptySectionName,
+    #[error("section '{name}' must be 1..={ARRANGEMENT_SECTION_MAX_BARS} bars long (got {bars})")]
+    SectionBarsOutOfRange { name: String, bars: u16 },
+    #[error("section '{name}' needs a prompt")]
+    EmptySectionPrompt { name: String },
+    #[error("all arrangement sections have already been generated")]
+    AlreadyComplete,
+}
+
+/// Tracks a sequential generation pass over an arrangement, one section at a time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArrangementRun {
+    sections: Vec<ArrangementSection>,
+    completed: Vec<GenerationCandidate>,
+    completed_chords: Vec<Vec<ChordLabel>>,
+}
+
+impl ArrangementRun {
+    pub fn new(sections: Vec<ArrangementSection>) -> Result<Self, ArrangementError> {
+        if sections.is_empty() {
+            return Err(ArrangementError::NoSections);
+        }
+        for section in &sections {
+            section.validate()?;
+        }
+        Ok(Self {
+            sections,
+            completed: Vec::new(),
+            completed_chords: Vec::new(),
+        })
+    }
+
+    pub fn sections(&self) -> &[ArrangementSection] {
+        &self.sections
+    }
+
+    pub fn completed_count(&self) -> usize {
+        self.completed.len()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.completed.len() == self.sections.len()
+    }
+
+    pub fn current_section(&self) -> Option<&ArrangementSection> {
+        self.sections.get(self.completed.len())
+    }
+
+    /// Prompt for the next section, framed with its position in the song.
+    pub fn current_prompt(&self, overall_prompt: &str) -> Option<String> {
+        let section = self.current_section()?;
+        let index = self.completed.len();
+        let mut prompt = String::new();
+        if !overall_prompt.trim().is_empty() {
+            prompt.push_str(overall_prompt.trim());
+            prompt.push_str("\n\n");
+        }
+        prompt.push_str(&format!(
+            "Arrangement section {} of {}: \"{}\" ({} bars). {}",
+            index + 1,
+            self.sections.len(),
+            section.name.trim(),
+            section.bars,
+            section.prompt.trim()
+        ));
+        if index > 0 {
+            prompt.push_str(
+                "\nContinue seamlessly from the previous sections provided as the continuation seed.",
+            );
+        }
+        Some(prompt)
+    }
+
+    /// Previous sections, assembled end-to-end, as a continuation seed reference.
+    pub fn continuation_reference(&self) -> Option<MidiReferenceSummary> {
+        let notes = self.assembled_notes(TickResolution::DEFAULT);
+        let min_pitch = notes.iter().map(|note| note.pitch).min()?;
+        let max_pitch = notes.iter().map(|note| note.pitch).max()?;
+        let bars = self.completed_bars();
+        let note_count = u32::try_from(notes.len()).unwrap_or(u32::MAX);
+
+        let reference = MidiReferenceSummary {
+            slot: ReferenceSlot::ContinuationSeed,
+            source: ReferenceSource::Live,
+            file: None,
+            bars,
+            note_count,
+            density_hint: calculate_reference_density_hint(note_count, bars),
+            min_pitch,
+            max_pitch,
+            events: note_reference_events(&notes),
+            transposition: None,
+            tempo: None,
+        };
+        reference.validate().ok().map(|_| reference)
+    }
+
+    pub fn record_section_result(
+        &mut self,
+        candidate: GenerationCandidate,
+        chords: Vec<ChordLabel>,
+    ) -> Result<(), ArrangementError> {
+        if self.is_complete() {
+            return Err(ArrangementError::AlreadyComplete);
+        }
+        self.completed.push(candidate);
+        self.completed_chords.push(chords);
+        Ok(())
+    }
+
+    /// Completed sections laid end-to-end at `resolution`.
+    pub fn assembled_notes(&self, resolution: TickResolution) -> Vec<GeneratedNote> {
+        let ticks_per_bar = u32::from(resolution.ticks_per_beat()) * BEATS_PER_BAR;
+        let mut offset_tick = 0u32;
+        let mut notes = Vec::new();
+
+        for (section, candidate) in self.sections.iter().zip(&self.completed) {
+            let source_resolution = candidate.tick_resolution;
+            let section_end_tick =
+                offset_tick.saturating_add(u32::from(section.bars) * ticks_per_bar);
+            for note in &candidate.notes {
+                let converted = resolution.convert_note(note, source_resolution);
+                let start_tick = offset_tick.saturating_add(converted.start_tick);
+                // Notes that spill past the section length would overlap the next section.
+                if start_tick >= section_end_tick {
+                    continue;
+                }
+                notes.push(GeneratedNote {
+                    start_tick,
+                    duration_tick: converted.duration_tick.min(section_end_tick - start_tick),
+                    ..converted
+                });
+            }
+            offset_tick = section_end_tick;
+        }
+
+        notes.sort_by_key(|note| (note.start_tick, note.pitch));
+        notes
+    }
+
+    /// Controller and pitch-bend events of every completed section, laid out like
+    /// [`Self::assembled_notes`].
+    pub fn assembled_control_events(
+        &self,
+        resolution: TickResolution,
+    ) -> Vec<GeneratedControlEvent> {
+        let ticks_per_bar = u32::from(resolution.ticks_per_beat()) * BEATS_PER_BAR;
+        let mut offset_tick = 0u32;
+        let mut events = Vec::new();
+
+        for (section, candidate) in self.sections.iter().zip(&self.completed) {
+            let source_resolution = candidate.tick_resolution;
+            let section_end_tick =
+                offset_tick.saturating_add(u32::from(section.bars) * ticks_per_bar);
+            for event in &candidate.control_events {
+                let tick = offset_tick
+                    .saturating_add(resolution.convert_ticks(event.tick(), source_resolution));
+                if tick < section_end_tick {
+                    events.push(event.with_tick(tick));
+                }
+            }
+            offset_tick = section_end_tick;
+        }
+
+        events.sort_by_key(GeneratedControlEvent::tick);
+        events
+    }
+
+    /// Chord labels of every completed section, renumbered to arrangement bars.
+    pub fn assembled_chords(&self) -> Vec<ChordLabel> {
+        let mut offset_bars = 0u16;
+        let mut chords = Vec::new();
+
+        for (section, section_chords) in self.sections.iter().zip(&self.completed_chords) {
+            chords.extend(
+                section_chords
+                    .iter()
+                    .filter(|chord| (1..=section.bars).contains(&chord.bar))
+                    .map(|chord| ChordLabel {
+                        bar: offset_bars.saturating_add(chord.bar),
+                        symbol: chord.symbol.clone(),
+                    }),
+            );
+            offset_bars = offset_bars.saturating_add(section.bars);
+        }
+
+        chords.sort_by_key(|chord| chord.bar);
+        chords
+    }
+
+    fn completed_bars(&self) -> u16 {
+        self.sections
+            .iter()
+            .take(self.completed.len())
+            .map(|section| section.bars)
+            .fold(0u16, u16::saturating_add)
+    }
+}
+
+fn note_reference_events(notes: &[GeneratedNote]) -> Vec<MidiReferenceEvent> {
+    let mut timed = Vec::with_capacity(notes.len() * 2);
+    for note in notes {
+        timed.push((
+            note.start_tick,
+            1u8,
+            format!(
+                "NoteOn channel={} key={} vel={}",
+                note.channel.saturating_sub(1),
+                note.pitch,
+                note.velocity
+            ),
+        ));
+        timed.push((
+            note.start_tick.saturating_add(note.duration_tick),
+            0u8,
+            format!(
+                "NoteOff channel={} key={} vel=0",
+                note.channel.saturating_sub(1),
+                note.pitch
+            ),
+        ));
+    }
+    timed.sort_by_key(|(tick, order, _)| (*tick, *order));
+
+    let mut previous_tick = 0u32;
+    timed
+        .into_iter()
+        .map(|(absolute_tick, _, event)| {
+            let delta_tick = absolute_tick - previous_tick;
+            previous_tick = absolute_tick;
+            MidiReferenceEvent {
+                track: 0,
+                absolute_tick,
+                delta_tick,
+                event,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArrangementError, ArrangementRun, ArrangementSection};
+    use crate::domain::{
+        ChordLabel, GeneratedNote, GenerationCandidate, ReferenceSlot, ReferenceSource,
+        TickResolution,
+    };
+
+    fn section(name: &str, bars: u16) -> ArrangementSection {
+        ArrangementSection {
+            name: name.to_string(),
+            bars,
+            prompt: format!("{name} idea"),
+        }
+    }
+
+    fn candidate(bars: u16, ticks_per_beat: u32, pitch: u8) -> GenerationCandidate {
+        GenerationCandidate {
+            id: format!("cand-{pitch}"),
+            bars,
+            notes: vec![
+                GeneratedNote {
+                    pitch,
+                    start_tick: 0,
+                    duration_tick: ticks_per_beat,
+                    velocity: 90,
+                    channel: 1,
+                },
+                GeneratedNote {
+                    pitch: pitch + 2,
+                    start_tick: ticks_per_beat * (u32::from(bars) * 4 - 1),
+                    duration_tick: ticks_per_beat,
+                    velocity: 90,
+                    channel: 1,
+                },
+            ],
+            tick_resolution: TickResolution::new(ticks_per_beat as u16)
+                .expect("test PPQ should be valid"),
+            score_hint: None,
+            title: None,
+            control_events: Vec::new(),
+            processing: None,
+        }
+    }
+
+    #[test]
+    fn new_run_rejects_empty_and_invalid_sections() {
+        assert_eq!(
+            ArrangementRun::new(Vec::new()),
+            Err(ArrangementError::NoSections)
+        );
+        assert_eq!(
+            ArrangementRun::new(vec![section("Verse", 0)]),
+            Err(ArrangementError::SectionBarsOutOfRange {
+                name: "Verse".to_string(),
+                bars: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn sections_are_generated_in_order_with_previous_sections_as_seed() {
+        let mut run = ArrangementRun::new(vec![section("Intro", 2), section("Chorus", 4)])
+            .expect("arrangement should be valid");
+
+        assert!(run.continuation_reference().is_none());
+        assert!(
+            run.current_prompt("lofi keys")
+                .expect("first section should have a prompt")
+                .contains("section 1 of 2: \"Intro\" (2 bars)")
+        );
+
+        run.record_section_result(candidate(2, 240, 60), Vec::new())
+            .expect("first section should be recorded");
+
+        let seed = run
+            .continuation_reference()
+            .expect("completed section should become a seed");
+        assert_eq!(seed.slot, ReferenceSlot::ContinuationSeed);
+        assert_eq!(seed.source, ReferenceSource::Live);
+        assert_eq!(seed.bars, 2);
+        assert_eq!(seed.note_count, 2);
+        assert!(
+            run.current_prompt("")
+                .expect("second section should have a prompt")
+                .contains("Continue seamlessly")
+        );
+
+        run.record_section_result(candidate(4, 480, 70), Vec::new())
+            .expect("second section should be recorded");
+        assert!(run.is_complete());
+        assert_eq!(
+            run.record_section_result(candidate(1, 480, 72), Vec::new()),
+            Err(ArrangementError::AlreadyComplete)
+        );
+    }
+
+    #[test]
+    fn assembled_notes_are_offset_by_section_length_at_export_resolution() {
+        let mut run = ArrangementRun::new(vec![section("Intro", 2), section("Verse", 1)])
+            .expect("arrangement should be valid");
+        run.record_section_result(candidate(2, 240, 60), Vec::new())
+            .expect("intro should be recorded");
+        run.record_section_result(candidate(1, 960, 64), Vec::new())
+            .expect("verse should be recorded");
+
+        let starts = run
+            .assembled_notes(TickResolution::DEFAULT)
+            .iter()
+            .map(|note| (note.pitch, note.start_tick, note.duration_tick))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            starts,
+            vec![
+                (60, 0, 480),
+                (62, 480 * 7, 480),
+                (64, 480 * 8, 480),
+                (66, 480 * 11, 480),
+            ]
+        );
+    }
+    #[test]
+    fn assembled_chords_are_renumbered_to_arrangement_bars() {
+        let chord = |bar, symbol: &str| ChordLabel {
+            bar,
+            symbol: symbol.to_string(),
+        };
+        let mut run = ArrangementRun::new(vec![section("Intro", 2), section("Verse", 1)])
+            .expect("arrangement should be valid");
+        run.record_section_result(
+            candidate(2, 480, 60),
+            vec![chord(1, "Am"), chord(2, "F"), chord(3, "G")],
+        )
+        .expect("intro should be recorded");
+        run.record_section_result(candidate(1, 480, 64), vec![chord(1, "C")])
+            .expect("verse should be recorded");
+
+        assert_eq!(
+            run.assembled_chords(),
+            vec![chord(1, "Am"), chord(2, "F"), chord(3, "C")]
+        );
+    }
+
+    #[test]
+    fn assembled_notes_follow_the_requested_resolution() {
+        let mut run = ArrangementRun::new(vec![section("Intro", 1), section("Verse", 1)])
+            .expect("arrangement should be valid");
+        run.record_section_result(candidate(1, 480, 60), Vec::new())
+            .expect("intro should be recorded");
+        run.record_section_result(candidate(1, 480, 64), Vec::new())
+            .expect("verse should be recorded");
+
+        let starts = run
+            .assembled_notes(TickResolution::new(96).expect("96 PPQ should be valid"))
+            .iter()
+            .map(|note| note.start_tick)
+            .collect::<Vec<_>>();
+
+        assert_eq!(starts, vec![0, 96 * 3, 96 * 4, 96 * 7]);
+    }
+}
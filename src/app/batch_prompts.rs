@@ -0,0 +1,169 @@
+use std::path::Path;
+
+use thiserror::Error;
+
+use crate::domain::GenerationRequest;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum BatchPromptError {
+    #[error("failed to read prompt list: {message}")]
+    Io { message: String },
+    #[error("prompt list contains no prompts")]
+    Empty,
+}
+
+/// Reads prompts from a text file (one per line) or, for `.csv` files, the first column.
+pub fn load_batch_prompts(path: impl AsRef<Path>) -> Result<Vec<String>, BatchPromptError> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path).map_err(|error| BatchPromptError::Io {
+        message: error.to_string(),
+    })?;
+    let is_csv = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"));
+    let prompts = parse_batch_prompts(&source, is_csv);
+    if prompts.is_empty() {
+        return Err(BatchPromptError::Empty);
+    }
+    Ok(prompts)
+}
+
+/// Blank lines and `#` comments are skipped. In CSV mode the first field of each row is the
+/// prompt (double quotes allow commas inside it) and a leading `prompt` header row is dropped.
+pub fn parse_batch_prompts(source: &str, csv: bool) -> Vec<String> {
+    source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            if csv {
+                first_csv_field(line)
+            } else {
+                line.to_string()
+            }
+        })
+        .enumerate()
+        .filter(|(index, prompt)| !(csv && *index == 0 && prompt.eq_ignore_ascii_case("prompt")))
+        .map(|(_, prompt)| prompt.trim().to_string())
+        .filter(|prompt| !prompt.is_empty())
+        .collect()
+}
+
+/// `base` with `prompt` swapped in and a request id numbered within the batch.
+pub fn batch_request(base: &GenerationRequest, prompt: &str, index: usize) -> GenerationRequest {
+    GenerationRequest {
+        request_id: format!("{}-batch-{:02}", base.request_id, index + 1),
+        prompt: prompt.to_string(),
+        ..base.clone()
+    }
+}
+
+/// Tracks a sequential pass over a prompt list; a failed prompt is recorded and skipped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchRun {
+    prompts: Vec<String>,
+    completed: usize,
+    failures: Vec<String>,
+}
+
+impl BatchRun {
+    pub fn new(prompts: Vec<String>) -> Result<Self, BatchPromptError> {
+        if prompts.is_empty() {
+            return Err(BatchPromptError::Empty);
+        }
+        Ok(Self {
+            prompts,
+            completed: 0,
+            failures: Vec::new(),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.prompts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prompts.is_empty()
+    }
+
+    pub fn completed_count(&self) -> usize {
+        self.completed
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.completed >= self.prompts.len()
+    }
+
+    pub fn current_prompt(&self) -> Option<&str> {
+        self.prompts.get(self.completed).map(String::as_str)
+    }
+
+    /// Failure messages, each prefixed with the prompt's 1-based position.
+    pub fn failures(&self) -> &[String] {
+        &self.failures
+    }
+
+    pub fn record_success(&mut self) {
+        self.completed = (self.completed + 1).min(self.prompts.len());
+    }
+
+    pub fn record_failure(&mut self, message: &str) {
+        if !self.is_complete() {
+            self.failures
+                .push(format!("prompt {}: {message}", self.completed + 1));
+        }
+        self.record_success();
+    }
+}
+
+fn first_csv_field(line: &str) -> String {
+    let Some(quoted) = line.strip_prefix('"') else {
+        return line.split(',').next().unwrap_or_default().to_string();
+    };
+    let mut field = String::new();
+    let mut chars = quoted.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch == '"' {
+            if chars.peek() == Some(&'"') {
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        field.push(ch);
+    }
+    field
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BatchRun, parse_batch_prompts};
+
+    #[test]
+    fn prompts_are_read_from_lines_or_the_first_csv_column() {
+        let text = "dark synth arpeggio\n\n# skipped\n  lazy swing bass  \n";
+        assert_eq!(
+            parse_batch_prompts(text, false),
+            vec!["dark synth arpeggio", "lazy swing bass"]
+        );
+
+        let csv = "prompt,notes\n\"bright, bouncy melody\",keep\nslow \"pad\" chords,x\n,empty\n";
+        assert_eq!(
+            parse_batch_prompts(csv, true),
+            vec!["bright, bouncy melody", "slow \"pad\" chords"]
+        );
+    }
+
+    #[test]
+    fn batch_run_advances_past_failures() {
+        let mut run =
+            BatchRun::new(vec!["one".to_string(), "two".to_string()]).expect("run should start");
+        assert_eq!(run.current_prompt(), Some("one"));
+        run.record_failure("timed out");
+        assert_eq!(run.current_prompt(), Some("two"));
+        run.record_success();
+        assert!(run.is_complete());
+        assert_eq!(run.failures(), ["prompt 1: timed out"]);
+        assert!(BatchRun::new(Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,175 @@
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use crate::app::stem_export::sanitize_file_label;
+use crate::domain::{ChordLabel, GenerationCandidate, GenerationResult, TickResolution};
+use crate::infra::midi::{MidiConductor, encode_notes_as_smf};
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum CandidateAutosaveError {
+    #[error("failed to encode candidate '{candidate_id}': {message}")]
+    Encode {
+        candidate_id: String,
+        message: String,
+    },
+    #[error("failed to write to the auto-save folder: {message}")]
+    Io { message: String },
+}
+
+/// `<file stem>-<candidate number>-<candidate id>.mid`, so files from one generation group
+/// together and, with the default `sonant-<request id>` stem, a later generation never
+/// overwrites them.
+pub fn autosave_file_name(file_stem: &str, index: usize, candidate_id: &str) -> String {
+    format!(
+        "{}-{:02}-{}.mid",
+        sanitize_file_label(file_stem),
+        index + 1,
+        sanitize_file_label(candidate_id)
+    )
+}
+
+/// Writes every candidate of `result` into `dir`, creating the folder when needed. Each file
+/// is named after `file_stem` and starts with `program` when one is given.
+pub fn autosave_candidates(
+    dir: impl AsRef<Path>,
+    file_stem: &str,
+    result: &GenerationResult,
+    resolution: TickResolution,
+    conductor: &MidiConductor,
+    program: Option<u8>,
+) -> Result<Vec<PathBuf>, CandidateAutosaveError> {
+    let dir = dir.as_ref();
+    std::fs::create_dir_all(dir).map_err(io_error)?;
+
+    let mut written = Vec::with_capacity(result.candidates.len());
+    for (index, candidate) in result.candidates.iter().enumerate() {
+        let bytes = encode_candidate(
+            candidate,
+            &result.metadata.chords,
+            resolution,
+            conductor,
+            program,
+        )?;
+        let path = dir.join(autosave_file_name(file_stem, index, &candidate.id));
+        std::fs::write(&path, bytes).map_err(io_error)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/// A candidate as Standard MIDI File bytes at `resolution`.
+pub(super) fn encode_candidate(
+    candidate: &GenerationCandidate,
+    chords: &[ChordLabel],
+    resolution: TickResolution,
+    conductor: &MidiConductor,
+    program: Option<u8>,
+) -> Result<Vec<u8>, CandidateAutosaveError> {
+    let source_resolution = candidate.tick_resolution;
+    let notes = candidate
+        .notes
+        .iter()
+        .map(|note| resolution.convert_note(note, source_resolution))
+        .collect::<Vec<_>>();
+    let control_events = candidate
+        .control_events
+        .iter()
+        .map(|event| event.with_tick(resolution.convert_ticks(event.tick(), source_resolution)))
+        .collect::<Vec<_>>();
+    encode_notes_as_smf(
+        &notes,
+        &control_events,
+        chords,
+        resolution.ticks_per_beat(),
+        conductor,
+        program,
+    )
+    .map_err(|error| CandidateAutosaveError::Encode {
+        candidate_id: candidate.id.clone(),
+        message: error.to_string(),
+    })
+}
+
+fn io_error(error: std::io::Error) -> CandidateAutosaveError {
+    CandidateAutosaveError::Io {
+        message: error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{autosave_candidates, autosave_file_name};
+    use crate::domain::{
+        GENERATION_CONTRACT_VERSION, GeneratedNote, GenerationCandidate, GenerationMetadata,
+        GenerationResult, ModelRef, TickResolution,
+    };
+    use crate::infra::midi::{MidiConductor, parse_midi_reference};
+    use crate::test_support::TestDir;
+
+    fn candidate(id: &str, pitch: u8) -> GenerationCandidate {
+        GenerationCandidate {
+            id: id.to_string(),
+            bars: 1,
+            notes: vec![GeneratedNote {
+                pitch,
+                start_tick: 0,
+                duration_tick: 480,
+                velocity: 100,
+                channel: 1,
+            }],
+            tick_resolution: TickResolution::DEFAULT,
+            score_hint: None,
+            title: None,
+            control_events: Vec::new(),
+            processing: None,
+        }
+    }
+
+    #[test]
+    fn file_names_carry_the_request_id_and_candidate_order() {
+        assert_eq!(
+            autosave_file_name("sonant-req-42", 0, "cand-1"),
+            "sonant-req-42-01-cand-1.mid"
+        );
+        assert_eq!(
+            autosave_file_name("Sonant Req 7/a", 2, ""),
+            "sonant-req-7-a-03-untitled.mid"
+        );
+    }
+
+    #[test]
+    fn every_candidate_is_written_into_a_created_folder() {
+        let root = TestDir::new("autosave");
+        let dir = root.join("watch");
+        let result = GenerationResult {
+            request_id: "req-42".to_string(),
+            model: ModelRef {
+                provider: "anthropic".to_string(),
+                model: "claude-3-5-sonnet".to_string(),
+            },
+            candidates: vec![candidate("cand-1", 60), candidate("cand-2", 64)],
+            metadata: GenerationMetadata::default(),
+            contract_version: GENERATION_CONTRACT_VERSION,
+        };
+
+        let written = autosave_candidates(
+            &dir,
+            "sonant-req-42",
+            &result,
+            TickResolution::DEFAULT,
+            &MidiConductor::new(120),
+            None,
+        )
+        .expect("candidates should be written");
+
+        assert_eq!(written.len(), 2);
+        assert_eq!(
+            written[1].file_name().and_then(|name| name.to_str()),
+            Some("sonant-req-42-02-cand-2.mid")
+        );
+        let second = parse_midi_reference(&std::fs::read(&written[1]).expect("file should exist"))
+            .expect("written file should parse");
+        assert_eq!(second.summary.min_pitch, 64);
+    }
+}
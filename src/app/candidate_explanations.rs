@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::domain::{GenerationCandidate, LlmError};
+
+/// Longer explanations are cut at a word boundary so they fit the candidate panel.
+pub const CANDIDATE_EXPLANATION_MAX_CHARS: usize = 480;
+/// Past this many explanations the least recently used finished one is evicted.
+pub const CANDIDATE_EXPLANATION_CACHE_MAX_ENTRIES: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CandidateExplanation {
+    Pending,
+    Ready(String),
+    Failed(String),
+}
+
+/// Explanations keyed by a candidate's notes, so a candidate re-opened from history or shown
+/// again after a comparison reuses the answer instead of asking the model twice.
+#[derive(Debug, Default)]
+pub struct CandidateExplanationCache {
+    entries: HashMap<u64, CachedExplanation>,
+    // Bumped on every use; an entry's stamp orders it for eviction.
+    clock: u64,
+}
+
+#[derive(Debug)]
+struct CachedExplanation {
+    explanation: CandidateExplanation,
+    last_used: u64,
+}
+
+impl CandidateExplanationCache {
+    pub fn get(&self, candidate: &GenerationCandidate) -> Option<&CandidateExplanation> {
+        self.entries
+            .get(&candidate_fingerprint(candidate))
+            .map(|cached| &cached.explanation)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Marks `candidate` as pending and returns `true` when an explanation should be requested;
+    /// cached and in-flight ones are left alone. Failed ones are retried.
+    pub fn begin(&mut self, candidate: &GenerationCandidate) -> bool {
+        let key = candidate_fingerprint(candidate);
+        self.clock += 1;
+        match self.entries.get_mut(&key) {
+            Some(cached) if !matches!(cached.explanation, CandidateExplanation::Failed(_)) => {
+                cached.last_used = self.clock;
+                false
+            }
+            _ => {
+                self.insert(key, CandidateExplanation::Pending);
+                true
+            }
+        }
+    }
+
+    pub fn finish(&mut self, candidate: &GenerationCandidate, outcome: Result<String, LlmError>) {
+        let explanation = match outcome {
+            Ok(text) => CandidateExplanation::Ready(truncate_explanation(&text)),
+            Err(error) => CandidateExplanation::Failed(error.user_message()),
+        };
+        self.clock += 1;
+        self.insert(candidate_fingerprint(candidate), explanation);
+    }
+
+    fn insert(&mut self, key: u64, explanation: CandidateExplanation) {
+        self.entries.insert(
+            key,
+            CachedExplanation {
+                explanation,
+                last_used: self.clock,
+            },
+        );
+        while self.entries.len() > CANDIDATE_EXPLANATION_CACHE_MAX_ENTRIES {
+            // Pending entries are waiting on a request, so they are never evicted.
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .filter(|(_, cached)| cached.explanation != CandidateExplanation::Pending)
+                .min_by_key(|(_, cached)| cached.last_used)
+                .map(|(key, _)| *key)
+            else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+    }
+}
+
+fn candidate_fingerprint(candidate: &GenerationCandidate) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    candidate.bars.hash(&mut hasher);
+    for note in &candidate.notes {
+        (
+            note.pitch,
+            note.start_tick,
+            note.duration_tick,
+            note.velocity,
+            note.channel,
+        )
+            .hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+fn truncate_explanation(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= CANDIDATE_EXPLANATION_MAX_CHARS {
+        return text;
+    }
+    let cut = text
+        .char_indices()
+        .nth(CANDIDATE_EXPLANATION_MAX_CHARS)
+        .map_or(text.len(), |(index, _)| index);
+    let head = &text[..cut];
+    let head = head.rfind(' ').map_or(head, |space| &head[..space]);
+    format!("{}…", head.trim_end_matches([',', ';', ':']))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CANDIDATE_EXPLANATION_CACHE_MAX_ENTRIES, CANDIDATE_EXPLANATION_MAX_CHARS,
+        CandidateExplanation, CandidateExplanationCache,
+    };
+    use crate::domain::{GeneratedNote, GenerationCandidate, LlmError, TickResolution};
+
+    fn candidate(id: &str, pitch: u8) -> GenerationCandidate {
+        GenerationCandidate {
+            id: id.to_string(),
+            bars: 1,
+            notes: vec![GeneratedNote {
+                pitch,
+                start_tick: 0,
+                duration_tick: 480,
+                velocity: 96,
+                channel: 1,
+            }],
+            tick_resolution: TickResolution::DEFAULT,
+            score_hint: None,
+            title: None,
+            control_events: Vec::new(),
+            processing: None,
+        }
+    }
+
+    #[test]
+    fn explanations_are_shared_by_identical_notes_and_retried_after_failure() {
+        let mut cache = CandidateExplanationCache::default();
+        let first = candidate("cand-1", 60);
+
+        assert!(cache.begin(&first));
+        assert!(!cache.begin(&first));
+        assert_eq!(cache.get(&first), Some(&CandidateExplanation::Pending));
+
+        cache.finish(&first, Err(LlmError::Timeout));
+        assert!(matches!(
+            cache.get(&first),
+            Some(CandidateExplanation::Failed(_))
+        ));
+        assert!(cache.begin(&first));
+
+        cache.finish(&first, Ok("  A single\nheld middle C. ".to_string()));
+        let same_notes = candidate("history-cand", 60);
+        assert_eq!(
+            cache.get(&same_notes),
+            Some(&CandidateExplanation::Ready(
+                "A single held middle C.".to_string()
+            ))
+        );
+        assert!(!cache.begin(&same_notes));
+        assert_eq!(cache.get(&candidate("cand-2", 62)), None);
+    }
+
+    #[test]
+    fn least_recently_used_explanations_are_evicted_past_the_limit() {
+        let mut cache = CandidateExplanationCache::default();
+        let candidates = (0..=CANDIDATE_EXPLANATION_CACHE_MAX_ENTRIES)
+            .map(|index| candidate(&format!("cand-{index}"), index as u8))
+            .collect::<Vec<_>>();
+        let (first, rest) = candidates.split_first().expect("candidates should exist");
+
+        cache.finish(first, Ok("first".to_string()));
+        cache.finish(&rest[0], Ok("second".to_string()));
+        assert!(!cache.begin(first));
+        for candidate in &rest[1..] {
+            cache.finish(candidate, Ok("later".to_string()));
+        }
+
+        assert_eq!(cache.len(), CANDIDATE_EXPLANATION_CACHE_MAX_ENTRIES);
+        assert!(cache.get(first).is_some());
+        assert_eq!(cache.get(&rest[0]), None);
+    }
+
+    #[test]
+    fn long_explanations_are_cut_at_a_word_boundary() {
+        let mut cache = CandidateExplanationCache::default();
+        let candidate = candidate("cand-1", 60);
+        cache.finish(&candidate, Ok("groove ".repeat(100)));
+
+        let Some(CandidateExplanation::Ready(text)) = cache.get(&candidate) else {
+            panic!("explanation should be ready");
+        };
+        assert!(text.chars().count() <= CANDIDATE_EXPLANATION_MAX_CHARS + 1);
+        assert!(text.ends_with("groove…"));
+    }
+}
@@ -0,0 +1,117 @@
+use crate::domain::{GenerationCandidate, GenerationMode, syncopation_level_for_off_beat_ratio};
+use crate::infra::midi::detect_key;
+
+// Below this profile correlation the key is left out of the name.
+const KEY_CONFIDENCE_MIN: f64 = 0.6;
+const SYNCOPATED_LEVEL_MIN: u8 = 4;
+const SPARSE_NOTES_PER_BAR: f64 = 4.0;
+const BUSY_NOTES_PER_BAR: f64 = 10.0;
+
+/// A descriptive name such as "Syncopated C-min riff, 8 bars". A title supplied by the model
+/// wins; otherwise the name is derived from the candidate's rhythm, key, and length.
+pub fn candidate_name(candidate: &GenerationCandidate, mode: Option<GenerationMode>) -> String {
+    if let Some(title) = candidate
+        .title
+        .as_deref()
+        .map(str::trim)
+        .filter(|title| !title.is_empty())
+    {
+        return title.to_string();
+    }
+
+    let notes_per_bar = candidate.notes.len() as f64 / f64::from(candidate.bars.max(1));
+    let feel = if syncopation_level_for_off_beat_ratio(candidate.off_beat_ratio())
+        >= SYNCOPATED_LEVEL_MIN
+    {
+        "Syncopated"
+    } else if notes_per_bar < SPARSE_NOTES_PER_BAR {
+        "Sparse"
+    } else if notes_per_bar > BUSY_NOTES_PER_BAR {
+        "Busy"
+    } else {
+        "Steady"
+    };
+
+    let mut words = vec![feel.to_string()];
+    if mode != Some(GenerationMode::DrumPattern) {
+        let mut pitch_class_counts = [0_u32; 12];
+        for note in &candidate.notes {
+            pitch_class_counts[usize::from(note.pitch % 12)] += 1;
+        }
+        if let Some(key) =
+            detect_key(&pitch_class_counts).filter(|key| key.confidence >= KEY_CONFIDENCE_MIN)
+        {
+            words.push(key.short_name());
+        }
+    }
+    words.push(mode_noun(mode).to_string());
+
+    let bars = candidate.bars;
+    let unit = if bars == 1 { "bar" } else { "bars" };
+    format!("{}, {bars} {unit}", words.join(" "))
+}
+
+fn mode_noun(mode: Option<GenerationMode>) -> &'static str {
+    match mode {
+        Some(GenerationMode::ChordProgression) => "chords",
+        Some(GenerationMode::DrumPattern) => "groove",
+        Some(GenerationMode::Bassline) => "bassline",
+        Some(GenerationMode::CounterMelody) => "counter-melody",
+        Some(GenerationMode::Harmony) => "harmony",
+        Some(GenerationMode::Melody) => "melody",
+        Some(GenerationMode::Continuation) | None => "riff",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::candidate_name;
+    use crate::domain::{GeneratedNote, GenerationCandidate, GenerationMode, TickResolution};
+
+    fn candidate(pitches: &[u8], start_offset: u32) -> GenerationCandidate {
+        GenerationCandidate {
+            id: "cand-1".to_string(),
+            bars: 2,
+            notes: pitches
+                .iter()
+                .enumerate()
+                .map(|(index, pitch)| GeneratedNote {
+                    pitch: *pitch,
+                    start_tick: index as u32 * 240 + start_offset,
+                    duration_tick: 120,
+                    velocity: 96,
+                    channel: 1,
+                })
+                .collect(),
+            tick_resolution: TickResolution::DEFAULT,
+            score_hint: None,
+            title: None,
+            control_events: Vec::new(),
+            processing: None,
+        }
+    }
+
+    #[test]
+    fn names_come_from_the_model_title_or_candidate_content() {
+        // C natural minor, shifted a sixteenth off the grid.
+        let minor_run = [
+            60, 62, 63, 65, 67, 68, 70, 72, 72, 70, 68, 67, 65, 63, 62, 60,
+        ];
+        let mut riff = candidate(&minor_run, 120);
+        assert_eq!(
+            candidate_name(&riff, Some(GenerationMode::Continuation)),
+            "Syncopated C-min riff, 2 bars"
+        );
+        assert_eq!(
+            candidate_name(&riff, Some(GenerationMode::DrumPattern)),
+            "Syncopated groove, 2 bars"
+        );
+        assert_eq!(
+            candidate_name(&candidate(&[36, 38], 0), None),
+            "Sparse riff, 2 bars"
+        );
+
+        riff.title = Some(" Night Drive Hook ".to_string());
+        assert_eq!(candidate_name(&riff, None), "Night Drive Hook");
+    }
+}
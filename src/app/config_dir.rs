@@ -0,0 +1,77 @@
+use std::path::PathBuf;
+
+pub const SONANT_CONFIG_DIR_ENV: &str = "SONANT_CONFIG_DIR";
+
+pub fn sonant_config_dir() -> Option<PathBuf> {
+    resolve_config_dir(
+        std::env::var(SONANT_CONFIG_DIR_ENV).ok(),
+        std::env::var("HOME").ok(),
+        std::env::var("XDG_CONFIG_HOME").ok(),
+    )
+}
+
+fn resolve_config_dir(
+    override_dir: Option<String>,
+    home_dir: Option<String>,
+    xdg_config_home: Option<String>,
+) -> Option<PathBuf> {
+    if let Some(dir) = non_empty(override_dir) {
+        return Some(PathBuf::from(dir));
+    }
+
+    if cfg!(target_os = "macos") {
+        return non_empty(home_dir).map(|home| {
+            PathBuf::from(home)
+                .join("Library")
+                .join("Application Support")
+                .join("Sonant")
+        });
+    }
+
+    if let Some(dir) = non_empty(xdg_config_home) {
+        return Some(PathBuf::from(dir).join("sonant"));
+    }
+
+    non_empty(home_dir).map(|home| PathBuf::from(home).join(".config").join("sonant"))
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|value| !value.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::resolve_config_dir;
+    use std::path::PathBuf;
+
+    #[test]
+    fn override_directory_takes_precedence() {
+        let resolved = resolve_config_dir(
+            Some("/tmp/sonant-config".to_string()),
+            Some("/home/user".to_string()),
+            Some("/home/user/.xdg".to_string()),
+        );
+
+        assert_eq!(resolved, Some(PathBuf::from("/tmp/sonant-config")));
+    }
+
+    #[test]
+    fn blank_values_are_ignored() {
+        assert_eq!(
+            resolve_config_dir(Some("  ".to_string()), None, Some(String::new())),
+            None
+        );
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn xdg_config_home_is_preferred_over_home_on_non_macos() {
+        let resolved = resolve_config_dir(
+            None,
+            Some("/home/user".to_string()),
+            Some("/home/user/.xdg".to_string()),
+        );
+
+        assert_eq!(resolved, Some(PathBuf::from("/home/user/.xdg/sonant")));
+    }
+}
@@ -0,0 +1,257 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::domain::{GenerationRequest, LlmError};
+
+pub const DEFERRED_REQUESTS_MAX: usize = 20;
+/// How long to wait before resending the oldest deferred request after it failed again.
+pub const DEFERRED_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+/// Resends after which a request that still cannot reach its provider is given up on.
+pub const DEFERRED_MAX_RESENDS: u32 = 10;
+
+/// A request held back because its provider could not be reached.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeferredRequest {
+    pub request: GenerationRequest,
+    /// Why the request was deferred, in user-facing wording.
+    pub reason: String,
+    /// Resends that failed to reach the provider.
+    pub failed_resends: u32,
+}
+
+/// What happened to the deferred request that was last handed out by
+/// [`DeferredRequestQueue::next_due`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeferredOutcome {
+    /// The provider was reached, whatever it answered; the request left the queue.
+    Reached,
+    /// The provider is still unreachable; the request stays at the front.
+    StillOffline,
+}
+
+/// Requests waiting for connectivity, resent oldest first one at a time so the backlog reaches
+/// the provider in the order it was written.
+#[derive(Debug, Clone, Default)]
+pub struct DeferredRequestQueue {
+    entries: VecDeque<DeferredRequest>,
+    in_flight: Option<String>,
+    next_attempt_at: Option<Instant>,
+}
+
+impl DeferredRequestQueue {
+    /// Errors that mean the provider was not reached, rather than that it rejected the request.
+    /// A timeout is not one: the provider may be reachable but slow or hung, and resending
+    /// would only stack up abandoned calls.
+    pub fn is_offline_error(error: &LlmError) -> bool {
+        matches!(error, LlmError::Transport { .. })
+    }
+
+    /// Queues `request` behind any earlier ones; `false` when it is already queued or the queue
+    /// is full.
+    pub fn defer(
+        &mut self,
+        request: GenerationRequest,
+        reason: impl Into<String>,
+        now: Instant,
+    ) -> bool {
+        if self.entries.len() >= DEFERRED_REQUESTS_MAX
+            || self
+                .entries
+                .iter()
+                .any(|entry| entry.request.request_id == request.request_id)
+        {
+            return false;
+        }
+        if self.entries.is_empty() {
+            self.next_attempt_at = Some(now + DEFERRED_RETRY_INTERVAL);
+        }
+        self.entries.push_back(DeferredRequest {
+            request,
+            reason: reason.into(),
+            failed_resends: 0,
+        });
+        true
+    }
+
+    /// The oldest request once its retry time has come, marked in flight until
+    /// [`Self::resolve`] is called for it.
+    pub fn next_due(&mut self, now: Instant) -> Option<GenerationRequest> {
+        if self.in_flight.is_some() || self.next_attempt_at.is_some_and(|at| now < at) {
+            return None;
+        }
+        let entry = self.entries.front()?;
+        self.in_flight = Some(entry.request.request_id.clone());
+        Some(entry.request.clone())
+    }
+
+    pub fn is_in_flight(&self, request_id: &str) -> bool {
+        self.in_flight.as_deref() == Some(request_id)
+    }
+
+    /// Records how the in-flight request went; ignored for any other id. After a send the next
+    /// request is due at once, after another connectivity failure only after the retry interval.
+    /// Returns the request when it failed [`DEFERRED_MAX_RESENDS`] times and was given up on.
+    pub fn resolve(
+        &mut self,
+        request_id: &str,
+        outcome: DeferredOutcome,
+        now: Instant,
+    ) -> Option<DeferredRequest> {
+        if !self.is_in_flight(request_id) {
+            return None;
+        }
+        self.in_flight = None;
+        match outcome {
+            DeferredOutcome::Reached => {
+                self.entries.pop_front();
+                self.next_attempt_at = None;
+                None
+            }
+            DeferredOutcome::StillOffline => {
+                let entry = self.entries.front_mut()?;
+                entry.failed_resends += 1;
+                if entry.failed_resends < DEFERRED_MAX_RESENDS {
+                    self.next_attempt_at = Some(now + DEFERRED_RETRY_INTERVAL);
+                    return None;
+                }
+                self.next_attempt_at = None;
+                self.entries.pop_front()
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &DeferredRequest> {
+        self.entries.iter()
+    }
+
+    /// Drops every queued request except one already resent, whose outcome is still pending.
+    pub fn clear(&mut self) {
+        let in_flight = self.in_flight.as_deref();
+        self.entries
+            .retain(|entry| Some(entry.request.request_id.as_str()) == in_flight);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::{
+        DEFERRED_MAX_RESENDS, DEFERRED_RETRY_INTERVAL, DeferredOutcome, DeferredRequestQueue,
+    };
+    use crate::domain::{
+        GENERATION_CONTRACT_VERSION, GenerationMode, GenerationParams, GenerationRequest, LlmError,
+        ModelRef, TickResolution,
+    };
+
+    fn request(request_id: &str) -> GenerationRequest {
+        GenerationRequest {
+            request_id: request_id.to_string(),
+            model: ModelRef {
+                provider: "anthropic".to_string(),
+                model: "claude-3-5-sonnet".to_string(),
+            },
+            mode: GenerationMode::Melody,
+            prompt: "offline sketch".to_string(),
+            params: GenerationParams {
+                bpm: 120,
+                key: "C".to_string(),
+                scale: "major".to_string(),
+                density: 3,
+                complexity: 3,
+                syncopation: 3,
+                tick_resolution: TickResolution::DEFAULT,
+                temperature: None,
+                top_p: None,
+                max_tokens: None,
+            },
+            references: Vec::new(),
+            variation_count: 1,
+            locked_notes: Vec::new(),
+            contract_version: GENERATION_CONTRACT_VERSION,
+            daw_context: None,
+            constraints: None,
+            validation: None,
+            anonymize_references: false,
+        }
+    }
+
+    #[test]
+    fn deferred_requests_are_resent_in_order_after_the_retry_interval() {
+        assert!(DeferredRequestQueue::is_offline_error(
+            &LlmError::Transport {
+                message: "connection refused".to_string(),
+            }
+        ));
+        assert!(!DeferredRequestQueue::is_offline_error(&LlmError::Timeout));
+        assert!(!DeferredRequestQueue::is_offline_error(&LlmError::Auth));
+
+        let start = Instant::now();
+        let mut queue = DeferredRequestQueue::default();
+        assert!(queue.defer(request("req-1"), "offline", start));
+        assert!(queue.defer(request("req-2"), "offline", start));
+        assert!(!queue.defer(request("req-1"), "offline", start));
+        assert_eq!(queue.next_due(start), None);
+
+        let due = start + DEFERRED_RETRY_INTERVAL;
+        let first = queue.next_due(due).expect("oldest request should be due");
+        assert_eq!(first.request_id, "req-1");
+        assert_eq!(queue.next_due(due), None);
+
+        queue.resolve("req-1", DeferredOutcome::StillOffline, due);
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.next_due(due), None);
+
+        let later = due + DEFERRED_RETRY_INTERVAL;
+        assert_eq!(
+            queue.next_due(later).map(|request| request.request_id),
+            Some("req-1".to_string())
+        );
+        queue.resolve("req-1", DeferredOutcome::Reached, later);
+        assert_eq!(
+            queue.next_due(later).map(|request| request.request_id),
+            Some("req-2".to_string())
+        );
+        queue.clear();
+        assert_eq!(queue.len(), 1);
+        queue.resolve("req-2", DeferredOutcome::Reached, later);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn a_request_that_never_reaches_its_provider_is_given_up_on() {
+        let mut now = Instant::now();
+        let mut queue = DeferredRequestQueue::default();
+        assert!(queue.defer(request("req-1"), "offline", now));
+        assert!(queue.defer(request("req-2"), "offline", now));
+
+        for _ in 1..DEFERRED_MAX_RESENDS {
+            now += DEFERRED_RETRY_INTERVAL;
+            queue.next_due(now).expect("request should be due");
+            assert_eq!(
+                queue.resolve("req-1", DeferredOutcome::StillOffline, now),
+                None
+            );
+        }
+        now += DEFERRED_RETRY_INTERVAL;
+        queue.next_due(now).expect("request should be due");
+        let dropped = queue
+            .resolve("req-1", DeferredOutcome::StillOffline, now)
+            .expect("the last failed resend gives up");
+
+        assert_eq!(dropped.request.request_id, "req-1");
+        assert_eq!(dropped.failed_resends, DEFERRED_MAX_RESENDS);
+        assert_eq!(
+            queue.next_due(now).map(|request| request.request_id),
+            Some("req-2".to_string())
+        );
+    }
+}
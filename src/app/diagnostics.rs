@@ -0,0 +1,429 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::app::generation_history::{GENERATION_HISTORY_FILE_NAME, GenerationHistoryStore};
+use crate::app::input_track_presets::{INPUT_TRACK_PRESETS_FILE_NAME, InputTrackPresetStore};
+use crate::app::recent_files::{RECENT_FILES_FILE_NAME, RecentFilesStore};
+use crate::app::sampling_profiles::{SAMPLING_PROFILES_FILE_NAME, SamplingProfileStore};
+use crate::app::{SONANT_CONFIG_DIR_ENV, sonant_config_dir};
+use crate::infra::llm::{
+    AnthropicProvider, OpenAiCompatibleProvider, RemoteServerProvider, probe_endpoint,
+};
+
+pub const CLAP_BUNDLE_NAME: &str = "Sonant.clap";
+pub const CLAP_PATH_ENV: &str = "CLAP_PATH";
+const CLAP_HELPER_BUNDLE_PATH: &str = "Contents/MacOS/SonantGUIHelper";
+const PROVIDER_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+// sun_path is 104 bytes on macOS and 108 on Linux, including the trailing NUL.
+#[cfg(target_family = "unix")]
+const UNIX_SOCKET_PATH_MAX_BYTES: usize = 103;
+
+struct ProviderKeyEnv {
+    label: &'static str,
+    names: &'static [&'static str],
+}
+
+const PROVIDER_KEY_ENVS: [ProviderKeyEnv; 3] = [
+    ProviderKeyEnv {
+        label: "Anthropic",
+        names: &["SONANT_ANTHROPIC_API_KEY", "ANTHROPIC_API_KEY"],
+    },
+    ProviderKeyEnv {
+        label: "OpenAI-compatible",
+        names: &["SONANT_OPENAI_COMPAT_API_KEY", "OPENAI_API_KEY"],
+    },
+    ProviderKeyEnv {
+        label: "Remote Sonant server",
+        names: &["SONANT_REMOTE_SERVER_TOKEN"],
+    },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticStatus {
+    Ok,
+    Warning,
+    Error,
+}
+
+/// One line of `sonant doctor` output. `hint` says what to change when the check did not pass.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiagnosticCheck {
+    pub name: String,
+    pub status: DiagnosticStatus,
+    pub detail: String,
+    pub hint: Option<String>,
+}
+
+impl DiagnosticCheck {
+    fn ok(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: DiagnosticStatus::Ok,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn warning(
+        name: impl Into<String>,
+        detail: impl Into<String>,
+        hint: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            status: DiagnosticStatus::Warning,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn error(name: impl Into<String>, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: DiagnosticStatus::Error,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+/// Runs every environment check in display order. Provider checks make network requests.
+pub fn run_diagnostics() -> Vec<DiagnosticCheck> {
+    let mut checks = vec![check_api_keys(|name| std::env::var(name).ok())];
+    checks.extend(check_provider_reachability(PROVIDER_PROBE_TIMEOUT));
+    checks.push(check_ipc_sockets());
+    checks.extend(check_config_files(sonant_config_dir().as_deref()));
+    checks.push(check_clap_bundle(&clap_search_dirs()));
+    checks
+}
+
+pub fn check_api_keys(lookup: impl Fn(&str) -> Option<String>) -> DiagnosticCheck {
+    let configured = PROVIDER_KEY_ENVS
+        .iter()
+        .filter_map(|provider| {
+            configured_key_env(provider, &lookup).map(|name| format!("{} ({name})", provider.label))
+        })
+        .collect::<Vec<_>>();
+
+    if configured.is_empty() {
+        DiagnosticCheck::error(
+            "API keys",
+            "no provider API key is set",
+            "export SONANT_ANTHROPIC_API_KEY, SONANT_OPENAI_COMPAT_API_KEY or SONANT_REMOTE_SERVER_TOKEN before starting the DAW",
+        )
+    } else {
+        DiagnosticCheck::ok("API keys", configured.join(", "))
+    }
+}
+
+fn configured_key_env(
+    provider: &ProviderKeyEnv,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Option<&'static str> {
+    provider
+        .names
+        .iter()
+        .copied()
+        .find(|name| lookup(name).is_some_and(|value| !value.trim().is_empty()))
+}
+
+/// Probes the base URL of each provider that has an API key. Providers without a key are
+/// skipped because [`check_api_keys`] already reports them.
+pub fn check_provider_reachability(timeout: Duration) -> Vec<DiagnosticCheck> {
+    let [anthropic, openai_compatible, remote_server] = &PROVIDER_KEY_ENVS;
+    let lookup = |name: &str| std::env::var(name).ok();
+    let mut checks = Vec::new();
+
+    if configured_key_env(anthropic, lookup).is_some() {
+        checks.push(provider_check(
+            anthropic.label,
+            AnthropicProvider::from_env().map(|provider| provider.api_base_url().to_string()),
+            timeout,
+        ));
+    }
+    if configured_key_env(openai_compatible, lookup).is_some() {
+        checks.push(provider_check(
+            openai_compatible.label,
+            OpenAiCompatibleProvider::from_env()
+                .map(|provider| provider.api_base_url().to_string()),
+            timeout,
+        ));
+    }
+    if configured_key_env(remote_server, lookup).is_some() {
+        checks.push(provider_check(
+            remote_server.label,
+            RemoteServerProvider::from_env().map(|provider| provider.api_base_url().to_string()),
+            timeout,
+        ));
+    }
+    checks
+}
+
+fn provider_check(
+    label: &str,
+    base_url: Result<String, crate::domain::LlmError>,
+    timeout: Duration,
+) -> DiagnosticCheck {
+    let name = format!("{label} provider");
+    let base_url = match base_url {
+        Ok(base_url) => base_url,
+        Err(error) => {
+            return DiagnosticCheck::error(
+                name,
+                error.to_string(),
+                "fix the provider environment variables named above",
+            );
+        }
+    };
+
+    match probe_endpoint(&base_url, timeout) {
+        Ok(elapsed) => DiagnosticCheck::ok(
+            name,
+            format!("{base_url} answered in {} ms", elapsed.as_millis()),
+        ),
+        Err(error) => DiagnosticCheck::error(
+            name,
+            error.to_string(),
+            "check the network connection, proxy settings and the provider base URL",
+        ),
+    }
+}
+
+/// The plugin creates its sockets in the temp directory, so a throwaway socket is bound there.
+#[cfg(target_family = "unix")]
+pub fn check_ipc_sockets() -> DiagnosticCheck {
+    use std::os::unix::net::UnixDatagram;
+
+    use crate::app::{HELPER_CONTROL_IPC_SOCKET_ENV, LIVE_INPUT_IPC_SOCKET_ENV};
+
+    const NAME: &str = "IPC sockets";
+    for env_name in [LIVE_INPUT_IPC_SOCKET_ENV, HELPER_CONTROL_IPC_SOCKET_ENV] {
+        if let Ok(path) = std::env::var(env_name)
+            && !Path::new(&path).exists()
+        {
+            return DiagnosticCheck::warning(
+                NAME,
+                format!("{env_name} points at missing socket {path}"),
+                format!("unset {env_name} unless the plugin launched this process"),
+            );
+        }
+    }
+
+    let path = std::env::temp_dir().join(format!("snt-doctor-{}.sock", std::process::id()));
+    let path_bytes = path.as_os_str().len();
+    if path_bytes > UNIX_SOCKET_PATH_MAX_BYTES {
+        return DiagnosticCheck::error(
+            NAME,
+            format!("socket path {} is {path_bytes} bytes long", path.display()),
+            "set TMPDIR to a shorter directory, such as /tmp",
+        );
+    }
+
+    let _ = std::fs::remove_file(&path);
+    let result = UnixDatagram::bind(&path);
+    let _ = std::fs::remove_file(&path);
+    match result {
+        Ok(_) => DiagnosticCheck::ok(
+            NAME,
+            format!("can bind sockets in {}", std::env::temp_dir().display()),
+        ),
+        Err(error) => DiagnosticCheck::error(
+            NAME,
+            format!("cannot bind {}: {error}", path.display()),
+            "make TMPDIR writable or point it at a writable directory",
+        ),
+    }
+}
+
+#[cfg(not(target_family = "unix"))]
+pub fn check_ipc_sockets() -> DiagnosticCheck {
+    DiagnosticCheck::warning(
+        "IPC sockets",
+        "live MIDI input and helper control need Unix domain sockets",
+        "live input tracks are unavailable on this platform",
+    )
+}
+
+/// Opens each settings file the helper reads from `config_dir`. Missing files are fine.
+pub fn check_config_files(config_dir: Option<&Path>) -> Vec<DiagnosticCheck> {
+    let Some(dir) = config_dir else {
+        return vec![DiagnosticCheck::warning(
+            "Config directory",
+            "no config directory could be resolved; settings will not be saved",
+            format!("set HOME or {SONANT_CONFIG_DIR_ENV}"),
+        )];
+    };
+
+    let files: [(&str, fn(&Path) -> Result<(), String>); 4] = [
+        (INPUT_TRACK_PRESETS_FILE_NAME, |path| {
+            InputTrackPresetStore::open(path)
+                .map(drop)
+                .map_err(|error| error.to_string())
+        }),
+        (SAMPLING_PROFILES_FILE_NAME, |path| {
+            SamplingProfileStore::open(path)
+                .map(drop)
+                .map_err(|error| error.to_string())
+        }),
+        (GENERATION_HISTORY_FILE_NAME, |path| {
+            GenerationHistoryStore::open(path)
+                .map(drop)
+                .map_err(|error| error.to_string())
+        }),
+        (RECENT_FILES_FILE_NAME, |path| {
+            RecentFilesStore::open(path)
+                .map(drop)
+                .map_err(|error| error.to_string())
+        }),
+    ];
+
+    files
+        .into_iter()
+        .map(|(file_name, open)| {
+            let path = dir.join(file_name);
+            let name = format!("Config {file_name}");
+            if !path.exists() {
+                return DiagnosticCheck::ok(name, "not created yet; defaults are used");
+            }
+            match open(&path) {
+                Ok(()) => DiagnosticCheck::ok(name, path.display().to_string()),
+                Err(message) => DiagnosticCheck::error(
+                    name,
+                    message,
+                    format!(
+                        "fix or delete {}; Sonant recreates it with defaults",
+                        path.display()
+                    ),
+                ),
+            }
+        })
+        .collect()
+}
+
+/// `CLAP_PATH` entries first, then the platform's standard CLAP folders.
+pub fn clap_search_dirs() -> Vec<PathBuf> {
+    let mut dirs = std::env::var_os(CLAP_PATH_ENV)
+        .map(|value| std::env::split_paths(&value).collect::<Vec<_>>())
+        .unwrap_or_default();
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+
+    if cfg!(target_os = "macos") {
+        if let Some(home) = &home {
+            dirs.push(home.join("Library/Audio/Plug-Ins/CLAP"));
+        }
+        dirs.push(PathBuf::from("/Library/Audio/Plug-Ins/CLAP"));
+    } else if cfg!(target_os = "windows") {
+        if let Some(local) = std::env::var_os("LOCALAPPDATA") {
+            dirs.push(PathBuf::from(local).join("Programs/Common/CLAP"));
+        }
+        if let Some(common) = std::env::var_os("COMMONPROGRAMFILES") {
+            dirs.push(PathBuf::from(common).join("CLAP"));
+        }
+    } else {
+        if let Some(home) = &home {
+            dirs.push(home.join(".clap"));
+        }
+        dirs.push(PathBuf::from("/usr/lib/clap"));
+    }
+    dirs
+}
+
+pub fn check_clap_bundle(search_dirs: &[PathBuf]) -> DiagnosticCheck {
+    const NAME: &str = "CLAP bundle";
+    let Some(bundle) = search_dirs
+        .iter()
+        .map(|dir| dir.join(CLAP_BUNDLE_NAME))
+        .find(|bundle| bundle.exists())
+    else {
+        return DiagnosticCheck::error(
+            NAME,
+            format!("{CLAP_BUNDLE_NAME} was not found in any CLAP folder"),
+            format!(
+                "run scripts/build_clap_bundle.sh and copy dist/{CLAP_BUNDLE_NAME} into {}",
+                search_dirs
+                    .first()
+                    .map(|dir| dir.display().to_string())
+                    .unwrap_or_else(|| format!("a folder listed in {CLAP_PATH_ENV}"))
+            ),
+        );
+    };
+
+    if bundle.is_dir() && !bundle.join(CLAP_HELPER_BUNDLE_PATH).is_file() {
+        return DiagnosticCheck::error(
+            NAME,
+            format!("{} is missing {CLAP_HELPER_BUNDLE_PATH}", bundle.display()),
+            "rebuild the bundle with scripts/build_clap_bundle.sh so the GUI helper is included",
+        );
+    }
+    DiagnosticCheck::ok(NAME, bundle.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CLAP_BUNDLE_NAME, CLAP_HELPER_BUNDLE_PATH, DiagnosticStatus, check_api_keys,
+        check_clap_bundle, check_config_files,
+    };
+    use crate::test_support::TestDir;
+
+    #[test]
+    fn api_key_check_names_configured_providers() {
+        let missing = check_api_keys(|_| None);
+        assert_eq!(missing.status, DiagnosticStatus::Error);
+        assert!(missing.hint.is_some());
+
+        let configured = check_api_keys(|name| {
+            (name == "OPENAI_API_KEY")
+                .then(|| "sk-test".to_string())
+                .or_else(|| (name == "SONANT_ANTHROPIC_API_KEY").then(|| "  ".to_string()))
+        });
+        assert_eq!(configured.status, DiagnosticStatus::Ok);
+        assert_eq!(configured.detail, "OpenAI-compatible (OPENAI_API_KEY)");
+    }
+
+    #[test]
+    fn unreadable_config_files_are_reported_with_their_path() {
+        let dir = TestDir::new("doctor-config");
+        std::fs::write(dir.join("recent_files.json"), b"{not json").expect("file should write");
+        std::fs::write(dir.join("sampling_profiles.json"), b"{}").expect("file should write");
+
+        let checks = check_config_files(Some(dir.path()));
+
+        let failed = checks
+            .iter()
+            .filter(|check| check.status == DiagnosticStatus::Error)
+            .collect::<Vec<_>>();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].name, "Config recent_files.json");
+        assert!(
+            failed[0]
+                .hint
+                .as_deref()
+                .is_some_and(|hint| hint.contains("recent_files.json"))
+        );
+        assert_eq!(
+            check_config_files(None)[0].status,
+            DiagnosticStatus::Warning
+        );
+    }
+
+    #[test]
+    fn clap_bundle_must_include_the_gui_helper() {
+        let empty = TestDir::new("doctor-clap-empty");
+        let installed = TestDir::new("doctor-clap-installed");
+        let bundle = installed.join(CLAP_BUNDLE_NAME);
+        std::fs::create_dir_all(bundle.join("Contents/MacOS")).expect("bundle should be created");
+
+        let search_dirs = [empty.path().to_path_buf(), installed.path().to_path_buf()];
+        assert_eq!(
+            check_clap_bundle(&search_dirs[..1]).status,
+            DiagnosticStatus::Error
+        );
+        let incomplete = check_clap_bundle(&search_dirs);
+        assert_eq!(incomplete.status, DiagnosticStatus::Error);
+        assert!(incomplete.detail.contains("SonantGUIHelper"));
+
+        std::fs::write(bundle.join(CLAP_HELPER_BUNDLE_PATH), b"").expect("helper should write");
+        assert_eq!(check_clap_bundle(&search_dirs).status, DiagnosticStatus::Ok);
+    }
+}
@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+
+/// Keys that cut each other off, like closed, pedal, and open hi-hats on one cymbal.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DrumChokeGroup {
+    pub name: String,
+    pub keys: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DrumMap {
+    pub name: String,
+    pub choke_groups: Vec<DrumChokeGroup>,
+}
+
+impl DrumMap {
+    /// General MIDI percussion: closed (42), pedal (44), and open (46) hi-hats share a cymbal.
+    pub fn general_midi() -> Self {
+        Self {
+            name: "General MIDI".to_string(),
+            choke_groups: vec![DrumChokeGroup {
+                name: "Hi-hat".to_string(),
+                keys: vec![42, 44, 46],
+            }],
+        }
+    }
+
+    pub fn choke_group_for(&self, key: u8) -> Option<&DrumChokeGroup> {
+        self.choke_groups
+            .iter()
+            .find(|group| group.keys.contains(&key))
+    }
+
+    /// Whether a note on `key` should cut off a note still ringing on `other_key`.
+    pub fn chokes(&self, key: u8, other_key: u8) -> bool {
+        key != other_key
+            && self
+                .choke_group_for(key)
+                .is_some_and(|group| group.keys.contains(&other_key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DrumMap;
+
+    #[test]
+    fn general_midi_hi_hats_choke_each_other_only() {
+        let map = DrumMap::general_midi();
+
+        assert!(map.chokes(42, 46));
+        assert!(map.chokes(44, 46));
+        assert!(!map.chokes(46, 46));
+        assert!(!map.chokes(36, 46));
+        assert!(map.choke_group_for(38).is_none());
+    }
+}
@@ -0,0 +1,323 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use thiserror::Error;
+
+use super::reference_sketch::{drawn_reference, ticks_per_step};
+use super::stem_export::{sanitize_file_label, slot_file_label};
+use crate::domain::{GeneratedNote, MidiReferenceSummary, ReferenceSlot, TickResolution};
+use crate::infra::midi::{MidiConductor, MidiWriteError, write_notes_to_midi_file};
+
+/// 16 steps is a bar of sixteenths; 32 is two.
+pub const DRUM_STEP_COUNTS: [u16; 2] = [16, 32];
+const DRUM_STEPS_PER_BAR: u16 = 16;
+const DRUM_HIT_VELOCITY: u8 = 90;
+const DRUM_ACCENT_VELOCITY: u8 = 120;
+/// 1-based, as notes carry it.
+const GM_DRUM_CHANNEL: u8 = 10;
+
+/// One row of the step grid and the General MIDI percussion key it plays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DrumLane {
+    pub name: &'static str,
+    pub key: u8,
+}
+
+/// Rows of the step grid, from the top.
+pub const DRUM_STEP_LANES: [DrumLane; 8] = [
+    DrumLane {
+        name: "Crash",
+        key: 49,
+    },
+    DrumLane {
+        name: "Open Hat",
+        key: 46,
+    },
+    DrumLane {
+        name: "Closed Hat",
+        key: 42,
+    },
+    DrumLane {
+        name: "High Tom",
+        key: 50,
+    },
+    DrumLane {
+        name: "Low Tom",
+        key: 45,
+    },
+    DrumLane {
+        name: "Clap",
+        key: 39,
+    },
+    DrumLane {
+        name: "Snare",
+        key: 38,
+    },
+    DrumLane {
+        name: "Kick",
+        key: 36,
+    },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrumHit {
+    Normal,
+    Accent,
+}
+
+impl DrumHit {
+    fn velocity(self) -> u8 {
+        match self {
+            Self::Normal => DRUM_HIT_VELOCITY,
+            Self::Accent => DRUM_ACCENT_VELOCITY,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DrumStepPatternError {
+    #[error("the drum pattern has no hits to export")]
+    EmptyPattern,
+    #[error(transparent)]
+    Write(#[from] MidiWriteError),
+}
+
+/// A drum groove drawn on a step grid for the DrumPattern slot. Each hit lasts one sixteenth,
+/// and accents carry the groove's weight as velocity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DrumStepPattern {
+    steps: u16,
+    /// Keyed by `(step, key)`.
+    hits: BTreeMap<(u16, u8), DrumHit>,
+}
+
+impl Default for DrumStepPattern {
+    fn default() -> Self {
+        Self {
+            steps: DRUM_STEP_COUNTS[0],
+            hits: BTreeMap::new(),
+        }
+    }
+}
+
+impl DrumStepPattern {
+    pub fn steps(&self) -> u16 {
+        self.steps
+    }
+
+    pub fn bars(&self) -> u16 {
+        self.steps / DRUM_STEPS_PER_BAR
+    }
+
+    pub fn hit_count(&self) -> usize {
+        self.hits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hits.is_empty()
+    }
+
+    pub fn hit(&self, step: u16, key: u8) -> Option<DrumHit> {
+        self.hits.get(&(step, key)).copied()
+    }
+
+    /// Empty, hit, accent, then empty again.
+    pub fn cycle_hit(&mut self, step: u16, key: u8) {
+        if step >= self.steps {
+            return;
+        }
+        match self.hit(step, key) {
+            None => {
+                self.hits.insert((step, key), DrumHit::Normal);
+            }
+            Some(DrumHit::Normal) => {
+                self.hits.insert((step, key), DrumHit::Accent);
+            }
+            Some(DrumHit::Accent) => {
+                self.hits.remove(&(step, key));
+            }
+        }
+    }
+
+    /// Switches between [`DRUM_STEP_COUNTS`]. Growing repeats the first bar into the new one;
+    /// shrinking keeps the first bar.
+    pub fn cycle_steps(&mut self) {
+        let position = DRUM_STEP_COUNTS
+            .iter()
+            .position(|steps| *steps == self.steps)
+            .unwrap_or_default();
+        let next = DRUM_STEP_COUNTS[(position + 1) % DRUM_STEP_COUNTS.len()];
+        if next > self.steps {
+            let repeated = self
+                .hits
+                .iter()
+                .flat_map(|(&(step, key), &hit)| {
+                    (1..next / self.steps).map(move |copy| ((step + copy * self.steps, key), hit))
+                })
+                .collect::<Vec<_>>();
+            self.hits.extend(repeated);
+        } else {
+            self.hits.retain(|(step, _), _| *step < next);
+        }
+        self.steps = next;
+    }
+
+    pub fn clear(&mut self) {
+        self.hits.clear();
+    }
+
+    /// The hits as notes on the GM drum channel at `resolution`.
+    pub fn notes(&self, resolution: TickResolution) -> Vec<GeneratedNote> {
+        let ticks_per_step = ticks_per_step(resolution);
+        let mut notes = self
+            .hits
+            .iter()
+            .map(|(&(step, key), hit)| GeneratedNote {
+                pitch: key,
+                start_tick: u32::from(step) * ticks_per_step,
+                duration_tick: ticks_per_step,
+                velocity: hit.velocity(),
+                channel: GM_DRUM_CHANNEL,
+            })
+            .collect::<Vec<_>>();
+        notes.sort_by_key(|note| (note.start_tick, note.pitch));
+        notes
+    }
+
+    /// The pattern as a DrumPattern reference. `None` while the grid is empty.
+    pub fn to_reference(&self) -> Option<MidiReferenceSummary> {
+        drawn_reference(
+            ReferenceSlot::DrumPattern,
+            self.bars(),
+            &self.notes(TickResolution::DEFAULT),
+            TickResolution::DEFAULT,
+        )
+    }
+}
+
+/// `<file stem>-drum-pattern-steps.mid`; exporting again overwrites the previous file.
+pub fn drum_step_pattern_file_name(file_stem: &str) -> String {
+    format!(
+        "{}-{}-steps.mid",
+        sanitize_file_label(file_stem),
+        slot_file_label(ReferenceSlot::DrumPattern)
+    )
+}
+
+/// Writes the pattern as a Standard MIDI File and returns how many hits it holds.
+pub fn write_drum_step_pattern(
+    path: impl AsRef<Path>,
+    pattern: &DrumStepPattern,
+    resolution: TickResolution,
+    conductor: &MidiConductor,
+) -> Result<usize, DrumStepPatternError> {
+    let notes = pattern.notes(resolution);
+    if notes.is_empty() {
+        return Err(DrumStepPatternError::EmptyPattern);
+    }
+    write_notes_to_midi_file(
+        path,
+        &notes,
+        &[],
+        &[],
+        resolution.ticks_per_beat(),
+        conductor,
+        None,
+    )?;
+    Ok(notes.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        DrumHit, DrumStepPattern, DrumStepPatternError, drum_step_pattern_file_name,
+        write_drum_step_pattern,
+    };
+    use crate::domain::{ReferenceSlot, TickResolution};
+    use crate::infra::midi::{MidiConductor, parse_midi_reference};
+    use crate::test_support::TestDir;
+
+    const KICK: u8 = 36;
+    const SNARE: u8 = 38;
+
+    fn backbeat() -> DrumStepPattern {
+        let mut pattern = DrumStepPattern::default();
+        for step in [0, 8] {
+            pattern.cycle_hit(step, KICK);
+        }
+        for step in [4, 12] {
+            pattern.cycle_hit(step, SNARE);
+            pattern.cycle_hit(step, SNARE);
+        }
+        pattern
+    }
+
+    #[test]
+    fn hits_cycle_through_accent_and_become_a_drum_reference() {
+        let mut pattern = backbeat();
+        assert_eq!(pattern.hit(4, SNARE), Some(DrumHit::Accent));
+        pattern.cycle_hit(0, KICK);
+        pattern.cycle_hit(0, KICK);
+        assert_eq!(pattern.hit(0, KICK), None);
+        pattern.cycle_hit(0, KICK);
+
+        let reference = pattern.to_reference().expect("pattern has hits");
+        assert_eq!(reference.slot, ReferenceSlot::DrumPattern);
+        assert_eq!(reference.bars, 1);
+        assert_eq!(reference.note_count, 4);
+        assert_eq!((reference.min_pitch, reference.max_pitch), (KICK, SNARE));
+        assert!(reference.events.iter().any(|event| event.event
+            == "Midi { channel: u4(9), message: NoteOn { key: u7(38), vel: u7(120) } }"));
+        assert!(DrumStepPattern::default().to_reference().is_none());
+    }
+
+    #[test]
+    fn growing_repeats_the_bar_and_shrinking_keeps_the_first() {
+        let mut pattern = backbeat();
+        pattern.cycle_steps();
+        assert_eq!((pattern.steps(), pattern.bars()), (32, 2));
+        assert_eq!(pattern.hit_count(), 8);
+        assert_eq!(pattern.hit(28, SNARE), Some(DrumHit::Accent));
+
+        pattern.cycle_hit(24, KICK);
+        pattern.cycle_hit(24, KICK);
+        pattern.cycle_hit(24, KICK);
+        pattern.cycle_steps();
+        assert_eq!(pattern, backbeat());
+    }
+
+    #[test]
+    fn patterns_export_as_midi_files_unless_empty() {
+        let dir = TestDir::new("drum-steps");
+        let path = dir.join(drum_step_pattern_file_name("sonant"));
+
+        let written = write_drum_step_pattern(
+            &path,
+            &backbeat(),
+            TickResolution::DEFAULT,
+            &MidiConductor::new(120),
+        )
+        .expect("pattern should be written");
+        let bytes = std::fs::read(&path).expect("pattern file should exist");
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(written, 4);
+        assert_eq!(
+            path.file_name().and_then(|name| name.to_str()),
+            Some("sonant-drum-pattern-steps.mid")
+        );
+        let reference = parse_midi_reference(&bytes).expect("pattern should parse");
+        assert_eq!(reference.summary.note_count, 4);
+
+        assert_eq!(
+            write_drum_step_pattern(
+                &path,
+                &DrumStepPattern::default(),
+                TickResolution::DEFAULT,
+                &MidiConductor::new(120),
+            ),
+            Err(DrumStepPatternError::EmptyPattern)
+        );
+        assert!(!path.exists());
+    }
+}
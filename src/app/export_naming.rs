@@ -0,0 +1,292 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use thiserror::Error;
+
+use super::stem_export::sanitize_file_label;
+use crate::domain::{GenerationMode, GenerationRequest};
+
+/// Keeps the names Sonant used before templates: `sonant-<request id>-...`.
+pub const DEFAULT_EXPORT_NAME_TEMPLATE: &str = "sonant-{request-id}";
+const EXPORT_NAME_PLACEHOLDERS: [&str; 5] = ["request-id", "mode", "key", "bpm", "date"];
+const SECONDS_PER_DAY: u64 = 86_400;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ExportNameTemplateError {
+    #[error("file name template must not be empty")]
+    Empty,
+    #[error("file name template must not contain '/' or '\\'")]
+    PathSeparator,
+    #[error("file name template has an unclosed '{{'")]
+    Unclosed,
+    #[error(
+        "unknown placeholder '{{{name}}}' (expected {{request-id}}, {{mode}}, {{key}}, {{bpm}}, or {{date}})"
+    )]
+    UnknownPlaceholder { name: String },
+}
+
+/// What an export's placeholders are filled with; a missing value is left out of the name.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExportNameContext<'a> {
+    pub request_id: Option<&'a str>,
+    pub mode: Option<GenerationMode>,
+    pub key: Option<&'a str>,
+    pub scale: Option<&'a str>,
+    pub bpm: Option<u16>,
+    pub date_unix_secs: Option<u64>,
+}
+
+impl<'a> ExportNameContext<'a> {
+    /// The request's id and musical params, dated now.
+    pub fn for_request(request: &'a GenerationRequest) -> Self {
+        Self {
+            request_id: Some(&request.request_id),
+            mode: Some(request.mode),
+            key: Some(&request.params.key),
+            scale: Some(&request.params.scale),
+            bpm: Some(request.params.bpm),
+            date_unix_secs: Some(unix_now_secs()),
+        }
+    }
+
+    /// Only the date, for exports that do not come from one request.
+    pub fn dated_now() -> Self {
+        Self {
+            date_unix_secs: Some(unix_now_secs()),
+            ..Self::default()
+        }
+    }
+}
+
+/// File name stem with `{request-id}`, `{mode}`, `{key}`, `{bpm}`, and `{date}` placeholders.
+/// Each export appends its own part, such as the candidate number, to the rendered stem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportNameTemplate {
+    template: String,
+}
+
+impl Default for ExportNameTemplate {
+    fn default() -> Self {
+        Self {
+            template: DEFAULT_EXPORT_NAME_TEMPLATE.to_string(),
+        }
+    }
+}
+
+impl ExportNameTemplate {
+    pub fn parse(template: &str) -> Result<Self, ExportNameTemplateError> {
+        let template = template.trim();
+        if template.is_empty() {
+            return Err(ExportNameTemplateError::Empty);
+        }
+        if template.contains(['/', '\\']) {
+            return Err(ExportNameTemplateError::PathSeparator);
+        }
+        for segment in template_segments(template) {
+            match segment {
+                Segment::Placeholder(name) if !EXPORT_NAME_PLACEHOLDERS.contains(&name) => {
+                    return Err(ExportNameTemplateError::UnknownPlaceholder {
+                        name: name.to_string(),
+                    });
+                }
+                Segment::Unclosed => return Err(ExportNameTemplateError::Unclosed),
+                Segment::Literal(_) | Segment::Placeholder(_) => {}
+            }
+        }
+        Ok(Self {
+            template: template.to_string(),
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.template
+    }
+
+    /// Lower-case, `-` separated, and never empty.
+    pub fn render(&self, context: &ExportNameContext<'_>) -> String {
+        let mut rendered = String::new();
+        for segment in template_segments(&self.template) {
+            match segment {
+                Segment::Literal(text) => rendered.push_str(text),
+                Segment::Placeholder(name) => {
+                    if let Some(value) = placeholder_value(name, context) {
+                        rendered.push_str(&value);
+                    }
+                }
+                Segment::Unclosed => {}
+            }
+        }
+        sanitize_file_label(&rendered)
+    }
+
+    /// The rendered text every name starts with, so files from this template can be told
+    /// apart from others in the same folder. Empty when the template opens with a placeholder.
+    pub fn literal_prefix(&self) -> String {
+        match template_segments(&self.template).next() {
+            Some(Segment::Literal(text)) => {
+                let prefix = sanitize_file_label(text);
+                if prefix == sanitize_file_label("") {
+                    String::new()
+                } else {
+                    prefix
+                }
+            }
+            _ => String::new(),
+        }
+    }
+}
+
+enum Segment<'a> {
+    Literal(&'a str),
+    Placeholder(&'a str),
+    Unclosed,
+}
+
+fn template_segments(template: &str) -> impl Iterator<Item = Segment<'_>> {
+    let mut rest = template;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        match rest.find('{') {
+            Some(0) => match rest.find('}') {
+                Some(end) => {
+                    let name = &rest[1..end];
+                    rest = &rest[end + 1..];
+                    Some(Segment::Placeholder(name.trim()))
+                }
+                None => {
+                    rest = "";
+                    Some(Segment::Unclosed)
+                }
+            },
+            Some(start) => {
+                let literal = &rest[..start];
+                rest = &rest[start..];
+                Some(Segment::Literal(literal))
+            }
+            None => {
+                let literal = rest;
+                rest = "";
+                Some(Segment::Literal(literal))
+            }
+        }
+    })
+}
+
+fn placeholder_value(name: &str, context: &ExportNameContext<'_>) -> Option<String> {
+    match name {
+        "request-id" => context.request_id.map(str::to_string),
+        "mode" => context.mode.map(|mode| mode_file_label(mode).to_string()),
+        "key" => context.key.map(|key| match context.scale {
+            Some(scale) => format!("{key} {scale}"),
+            None => key.to_string(),
+        }),
+        "bpm" => context.bpm.map(|bpm| format!("{bpm}bpm")),
+        "date" => context.date_unix_secs.map(utc_date),
+        _ => None,
+    }
+}
+
+fn mode_file_label(mode: GenerationMode) -> &'static str {
+    match mode {
+        GenerationMode::Melody => "melody",
+        GenerationMode::ChordProgression => "chord-progression",
+        GenerationMode::DrumPattern => "drum-pattern",
+        GenerationMode::Bassline => "bassline",
+        GenerationMode::CounterMelody => "counter-melody",
+        GenerationMode::Harmony => "harmony",
+        GenerationMode::Continuation => "continuation",
+    }
+}
+
+/// `YYYY-MM-DD` in UTC.
+fn utc_date(unix_secs: u64) -> String {
+    // Civil-from-days over the proleptic Gregorian calendar, counted from 0000-03-01.
+    let days = unix_secs / SECONDS_PER_DAY + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+fn unix_now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExportNameContext, ExportNameTemplate, ExportNameTemplateError, utc_date};
+    use crate::domain::GenerationMode;
+
+    fn context() -> ExportNameContext<'static> {
+        ExportNameContext {
+            request_id: Some("req-42"),
+            mode: Some(GenerationMode::ChordProgression),
+            key: Some("F#"),
+            scale: Some("Minor (Aeolian)"),
+            bpm: Some(128),
+            date_unix_secs: Some(1_792_108_800),
+        }
+    }
+
+    #[test]
+    fn default_template_keeps_the_request_id_names() {
+        let template = ExportNameTemplate::default();
+        assert_eq!(template.render(&context()), "sonant-req-42");
+        assert_eq!(template.render(&ExportNameContext::default()), "sonant");
+        assert_eq!(template.literal_prefix(), "sonant");
+    }
+
+    #[test]
+    fn every_placeholder_is_filled_and_sanitized() {
+        let template = ExportNameTemplate::parse(" {date}_{mode} {key} @ {bpm} ({request-id}) ")
+            .expect("template should parse");
+        assert_eq!(
+            template.render(&context()),
+            "2026-10-16-chord-progression-f-minor-aeolian-128bpm-req-42"
+        );
+        assert_eq!(template.literal_prefix(), "");
+    }
+
+    #[test]
+    fn malformed_templates_are_rejected() {
+        assert_eq!(
+            ExportNameTemplate::parse("   "),
+            Err(ExportNameTemplateError::Empty)
+        );
+        assert_eq!(
+            ExportNameTemplate::parse("takes/{date}"),
+            Err(ExportNameTemplateError::PathSeparator)
+        );
+        assert_eq!(
+            ExportNameTemplate::parse("sonant-{date"),
+            Err(ExportNameTemplateError::Unclosed)
+        );
+        assert_eq!(
+            ExportNameTemplate::parse("sonant-{tempo}"),
+            Err(ExportNameTemplateError::UnknownPlaceholder {
+                name: "tempo".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn dates_are_rendered_in_utc() {
+        assert_eq!(utc_date(0), "1970-01-01");
+        assert_eq!(utc_date(951_782_400), "2000-02-29");
+        assert_eq!(utc_date(1_792_108_800 + 86_399), "2026-10-16");
+    }
+}
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+
+use crate::domain::ReferenceSlot;
+
+/// General MIDI programs offered for exported tracks, 0-based as written to the file.
+pub const GM_EXPORT_PROGRAM_CHOICES: [u8; 14] =
+    [0, 4, 16, 24, 32, 33, 38, 48, 52, 56, 73, 80, 81, 89];
+
+/// The General MIDI program exported tracks for `slot` start with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrackProgram {
+    pub slot: ReferenceSlot,
+    pub program: u8,
+}
+
+pub fn default_track_programs() -> Vec<TrackProgram> {
+    vec![
+        TrackProgram {
+            slot: ReferenceSlot::Melody,
+            program: 0,
+        },
+        TrackProgram {
+            slot: ReferenceSlot::ChordProgression,
+            program: 4,
+        },
+        TrackProgram {
+            slot: ReferenceSlot::Bassline,
+            program: 32,
+        },
+        TrackProgram {
+            slot: ReferenceSlot::CounterMelody,
+            program: 73,
+        },
+        TrackProgram {
+            slot: ReferenceSlot::Harmony,
+            program: 48,
+        },
+    ]
+}
+
+pub fn program_for_slot(programs: &[TrackProgram], slot: ReferenceSlot) -> Option<u8> {
+    programs
+        .iter()
+        .find(|assignment| assignment.slot == slot)
+        .map(|assignment| assignment.program)
+}
+
+/// The choice after `current` in [`GM_EXPORT_PROGRAM_CHOICES`], wrapping to `None` (no program
+/// change) after the last one.
+pub fn next_export_program(current: Option<u8>) -> Option<u8> {
+    let Some(current) = current else {
+        return GM_EXPORT_PROGRAM_CHOICES.first().copied();
+    };
+    let position = GM_EXPORT_PROGRAM_CHOICES
+        .iter()
+        .position(|program| *program == current)?;
+    GM_EXPORT_PROGRAM_CHOICES.get(position + 1).copied()
+}
+
+pub fn gm_program_name(program: u8) -> String {
+    let name = match program {
+        0 => "Acoustic Grand Piano",
+        4 => "Electric Piano 1",
+        16 => "Drawbar Organ",
+        24 => "Nylon Guitar",
+        32 => "Acoustic Bass",
+        33 => "Finger Bass",
+        38 => "Synth Bass 1",
+        48 => "String Ensemble 1",
+        52 => "Choir Aahs",
+        56 => "Trumpet",
+        73 => "Flute",
+        80 => "Square Lead",
+        81 => "Saw Lead",
+        89 => "Warm Pad",
+        _ => return format!("Program {}", u16::from(program) + 1),
+    };
+    name.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        GM_EXPORT_PROGRAM_CHOICES, default_track_programs, gm_program_name, next_export_program,
+        program_for_slot,
+    };
+    use crate::domain::ReferenceSlot;
+
+    #[test]
+    fn export_programs_cycle_through_choices() {
+        let programs = default_track_programs();
+        assert_eq!(
+            program_for_slot(&programs, ReferenceSlot::Bassline),
+            Some(32)
+        );
+        assert_eq!(gm_program_name(32), "Acoustic Bass");
+        assert_eq!(
+            program_for_slot(&programs, ReferenceSlot::DrumPattern),
+            None
+        );
+
+        let mut program = None;
+        for _ in 0..GM_EXPORT_PROGRAM_CHOICES.len() {
+            program = next_export_program(program);
+            assert!(program.is_some());
+        }
+        assert_eq!(next_export_program(program), None);
+    }
+}
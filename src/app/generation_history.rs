@@ -0,0 +1,599 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::app::input_track_presets::write_file_atomically;
+use crate::app::sonant_config_dir;
+use crate::domain::{GenerationRequest, GenerationResult};
+
+pub(crate) const GENERATION_HISTORY_FILE_NAME: &str = "generation_history.json";
+pub const GENERATION_HISTORY_MAX_ENTRIES: usize = 50;
+/// The oldest entries are dropped once the history file would grow past this.
+pub const GENERATION_HISTORY_MAX_BYTES: usize = 4 * 1024 * 1024;
+pub const CANDIDATE_ANNOTATION_MAX_CHARS: usize = 200;
+const CSV_HEADER: [&str; 23] = [
+    "request_id",
+    "provider",
+    "model",
+    "mode",
+    "prompt",
+    "bpm",
+    "key",
+    "scale",
+    "density",
+    "complexity",
+    "syncopation",
+    "temperature",
+    "top_p",
+    "max_tokens",
+    "variation_count",
+    "latency_ms",
+    "input_tokens",
+    "output_tokens",
+    "total_tokens",
+    "candidate_id",
+    "note_count",
+    "score_hint",
+    "annotation",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenerationHistoryExportFormat {
+    Json,
+    Csv,
+}
+
+impl GenerationHistoryExportFormat {
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Self::Json => "sonant-history.json",
+            Self::Csv => "sonant-history.csv",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GenerationHistoryEntry {
+    pub request: GenerationRequest,
+    pub result: GenerationResult,
+    /// Free-text notes keyed by candidate id.
+    #[serde(default)]
+    pub annotations: BTreeMap<String, String>,
+}
+
+impl GenerationHistoryEntry {
+    pub fn request_id(&self) -> &str {
+        &self.request.request_id
+    }
+
+    pub fn annotation(&self, candidate_id: &str) -> Option<&str> {
+        self.annotations.get(candidate_id).map(String::as_str)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum GenerationHistoryError {
+    #[error("generation '{request_id}' is not in the history")]
+    EntryNotFound { request_id: String },
+    #[error("candidate '{candidate_id}' is not part of generation '{request_id}'")]
+    CandidateNotFound {
+        request_id: String,
+        candidate_id: String,
+    },
+    #[error("candidate note must be at most {CANDIDATE_ANNOTATION_MAX_CHARS} characters")]
+    AnnotationTooLong,
+    #[error("failed to access generation history file: {message}")]
+    Io { message: String },
+    #[error("failed to parse generation history file: {message}")]
+    Parse { message: String },
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct GenerationHistoryFile {
+    #[serde(default)]
+    entries: Vec<GenerationHistoryEntry>,
+}
+
+/// Most recent successful generations, oldest first, capped at `GENERATION_HISTORY_MAX_ENTRIES`
+/// and `GENERATION_HISTORY_MAX_BYTES`.
+#[derive(Debug, Clone)]
+pub struct GenerationHistoryStore {
+    path: Option<PathBuf>,
+    entries: Vec<GenerationHistoryEntry>,
+    max_bytes: usize,
+    stored_bytes: usize,
+}
+
+impl GenerationHistoryStore {
+    pub fn open_default() -> Result<Self, GenerationHistoryError> {
+        match sonant_config_dir() {
+            Some(dir) => Self::open(dir.join(GENERATION_HISTORY_FILE_NAME)),
+            None => Ok(Self::in_memory()),
+        }
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, GenerationHistoryError> {
+        let path = path.as_ref().to_path_buf();
+        let (entries, stored_bytes) = match std::fs::read(&path) {
+            Ok(bytes) => {
+                let mut file: GenerationHistoryFile =
+                    serde_json::from_slice(&bytes).map_err(|error| {
+                        GenerationHistoryError::Parse {
+                            message: error.to_string(),
+                        }
+                    })?;
+                for entry in &mut file.entries {
+                    entry.request.upgrade_contract();
+                    entry.result.upgrade_contract();
+                }
+                (file.entries, bytes.len())
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => (Vec::new(), 0),
+            Err(error) => {
+                return Err(GenerationHistoryError::Io {
+                    message: error.to_string(),
+                });
+            }
+        };
+
+        Ok(Self {
+            path: Some(path),
+            entries,
+            max_bytes: GENERATION_HISTORY_MAX_BYTES,
+            stored_bytes,
+        })
+    }
+
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            entries: Vec::new(),
+            max_bytes: GENERATION_HISTORY_MAX_BYTES,
+            stored_bytes: 0,
+        }
+    }
+
+    /// Applies from the next recorded generation.
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Size of the history as last written, or as read when nothing has been written yet.
+    pub fn stored_bytes(&self) -> usize {
+        self.stored_bytes
+    }
+
+    pub fn entries(&self) -> &[GenerationHistoryEntry] {
+        &self.entries
+    }
+
+    pub fn entry(&self, request_id: &str) -> Option<&GenerationHistoryEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.request_id() == request_id)
+    }
+
+    pub fn annotation(&self, request_id: &str, candidate_id: &str) -> Option<&str> {
+        self.entry(request_id)?.annotation(candidate_id)
+    }
+
+    pub fn record(
+        &mut self,
+        request: GenerationRequest,
+        result: GenerationResult,
+    ) -> Result<(), GenerationHistoryError> {
+        let mut next = self.entries.clone();
+        next.retain(|entry| entry.request_id() != request.request_id);
+        next.push(GenerationHistoryEntry {
+            request,
+            result,
+            annotations: BTreeMap::new(),
+        });
+        let overflow = next.len().saturating_sub(GENERATION_HISTORY_MAX_ENTRIES);
+        next.drain(..overflow);
+        // The new entry is kept even when it alone is over the limit.
+        let payload = loop {
+            let payload = encode_history(&next)?;
+            if payload.len() <= self.max_bytes || next.len() <= 1 {
+                break payload;
+            }
+            next.remove(0);
+        };
+
+        self.persist(&payload)?;
+        self.entries = next;
+        Ok(())
+    }
+
+    /// Sets the note for one candidate; a blank note removes it.
+    pub fn set_annotation(
+        &mut self,
+        request_id: &str,
+        candidate_id: &str,
+        note: &str,
+    ) -> Result<(), GenerationHistoryError> {
+        let note = note.trim();
+        if note.chars().count() > CANDIDATE_ANNOTATION_MAX_CHARS {
+            return Err(GenerationHistoryError::AnnotationTooLong);
+        }
+
+        let mut next = self.entries.clone();
+        let entry = next
+            .iter_mut()
+            .find(|entry| entry.request_id() == request_id)
+            .ok_or_else(|| GenerationHistoryError::EntryNotFound {
+                request_id: request_id.to_string(),
+            })?;
+        if !entry
+            .result
+            .candidates
+            .iter()
+            .any(|candidate| candidate.id == candidate_id)
+        {
+            return Err(GenerationHistoryError::CandidateNotFound {
+                request_id: request_id.to_string(),
+                candidate_id: candidate_id.to_string(),
+            });
+        }
+        if note.is_empty() {
+            entry.annotations.remove(candidate_id);
+        } else {
+            entry
+                .annotations
+                .insert(candidate_id.to_string(), note.to_string());
+        }
+
+        self.persist(&encode_history(&next)?)?;
+        self.entries = next;
+        Ok(())
+    }
+
+    pub fn export(
+        &self,
+        format: GenerationHistoryExportFormat,
+    ) -> Result<String, GenerationHistoryError> {
+        match format {
+            GenerationHistoryExportFormat::Json => serde_json::to_string_pretty(&self.entries)
+                .map_err(|error| GenerationHistoryError::Parse {
+                    message: error.to_string(),
+                }),
+            GenerationHistoryExportFormat::Csv => Ok(export_csv(&self.entries)),
+        }
+    }
+
+    pub fn export_to_file(
+        &self,
+        path: impl AsRef<Path>,
+        format: GenerationHistoryExportFormat,
+    ) -> Result<(), GenerationHistoryError> {
+        let payload = self.export(format)?;
+        std::fs::write(path, payload).map_err(|error| GenerationHistoryError::Io {
+            message: error.to_string(),
+        })
+    }
+
+    fn persist(&mut self, payload: &[u8]) -> Result<(), GenerationHistoryError> {
+        if let Some(path) = self.path.as_ref() {
+            write_file_atomically(path, payload).map_err(|error| GenerationHistoryError::Io {
+                message: error.to_string(),
+            })?;
+        }
+        self.stored_bytes = payload.len();
+        Ok(())
+    }
+}
+
+/// One row per candidate so ratings and notes can be analysed alongside request settings.
+fn encode_history(entries: &[GenerationHistoryEntry]) -> Result<Vec<u8>, GenerationHistoryError> {
+    serde_json::to_vec_pretty(&GenerationHistoryFile {
+        entries: entries.to_vec(),
+    })
+    .map_err(|error| GenerationHistoryError::Parse {
+        message: error.to_string(),
+    })
+}
+
+fn export_csv(entries: &[GenerationHistoryEntry]) -> String {
+    let mut csv = CSV_HEADER.join(",");
+    csv.push('\n');
+
+    for entry in entries {
+        let request = &entry.request;
+        let params = &request.params;
+        let metadata = &entry.result.metadata;
+        let usage = metadata.usage.clone().unwrap_or_default();
+        let mode = serde_json::to_value(request.mode)
+            .ok()
+            .and_then(|value| value.as_str().map(str::to_string))
+            .unwrap_or_default();
+        let shared = [
+            request.request_id.clone(),
+            entry.result.model.provider.clone(),
+            entry.result.model.model.clone(),
+            mode,
+            request.prompt.clone(),
+            params.bpm.to_string(),
+            params.key.clone(),
+            params.scale.clone(),
+            params.density.to_string(),
+            params.complexity.to_string(),
+            params.syncopation.to_string(),
+            optional_field(params.temperature),
+            optional_field(params.top_p),
+            optional_field(params.max_tokens),
+            request.variation_count.to_string(),
+            optional_field(metadata.latency_ms),
+            optional_field(usage.input_tokens),
+            optional_field(usage.output_tokens),
+            optional_field(usage.total_tokens),
+        ];
+
+        for candidate in &entry.result.candidates {
+            let row = shared
+                .iter()
+                .cloned()
+                .chain([
+                    candidate.id.clone(),
+                    candidate.notes.len().to_string(),
+                    optional_field(candidate.score_hint),
+                    entry
+                        .annotation(&candidate.id)
+                        .unwrap_or_default()
+                        .to_string(),
+                ])
+                .map(|field| escape_csv_field(&field))
+                .collect::<Vec<_>>();
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+    }
+    csv
+}
+
+fn optional_field(value: Option<impl ToString>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        GENERATION_HISTORY_MAX_ENTRIES, GenerationHistoryEntry, GenerationHistoryError,
+        GenerationHistoryExportFormat, GenerationHistoryStore,
+    };
+    use crate::domain::{
+        GENERATION_CONTRACT_VERSION, GeneratedNote, GenerationCandidate, GenerationMetadata,
+        GenerationMode, GenerationParams, GenerationRequest, GenerationResult, GenerationUsage,
+        ModelRef, TickResolution,
+    };
+    use crate::test_support::TestDir;
+
+    fn model() -> ModelRef {
+        ModelRef {
+            provider: "anthropic".to_string(),
+            model: "claude-3-5-sonnet".to_string(),
+        }
+    }
+
+    fn request(request_id: &str) -> GenerationRequest {
+        GenerationRequest {
+            request_id: request_id.to_string(),
+            model: model(),
+            mode: GenerationMode::Melody,
+            prompt: "bright hook".to_string(),
+            params: GenerationParams {
+                bpm: 120,
+                key: "C".to_string(),
+                scale: "major".to_string(),
+                density: 3,
+                complexity: 3,
+                syncopation: 3,
+                tick_resolution: TickResolution::DEFAULT,
+                temperature: None,
+                top_p: None,
+                max_tokens: None,
+            },
+            references: Vec::new(),
+            variation_count: 1,
+            locked_notes: Vec::new(),
+            contract_version: GENERATION_CONTRACT_VERSION,
+            daw_context: None,
+            constraints: None,
+            validation: None,
+            anonymize_references: false,
+        }
+    }
+
+    fn result(request_id: &str) -> GenerationResult {
+        GenerationResult {
+            request_id: request_id.to_string(),
+            model: model(),
+            candidates: vec![GenerationCandidate {
+                id: "cand-1".to_string(),
+                bars: 4,
+                notes: vec![GeneratedNote {
+                    pitch: 60,
+                    start_tick: 0,
+                    duration_tick: 480,
+                    velocity: 100,
+                    channel: 1,
+                }],
+                tick_resolution: TickResolution::DEFAULT,
+                score_hint: None,
+                title: None,
+                control_events: Vec::new(),
+                processing: None,
+            }],
+            metadata: GenerationMetadata::default(),
+            contract_version: GENERATION_CONTRACT_VERSION,
+        }
+    }
+
+    #[test]
+    fn entries_written_before_contract_versioning_are_upgraded_on_open() {
+        let dir = TestDir::new("history");
+        let path = dir.join("generation_history.json");
+        let mut entry = serde_json::to_value(GenerationHistoryEntry {
+            request: request("req-1"),
+            result: result("req-1"),
+            annotations: Default::default(),
+        })
+        .expect("entry should serialize");
+        for part in ["request", "result"] {
+            entry[part]
+                .as_object_mut()
+                .expect("entry part should be an object")
+                .remove("contract_version");
+        }
+        std::fs::write(&path, serde_json::json!({ "entries": [entry] }).to_string())
+            .expect("legacy history should be written");
+
+        let store = GenerationHistoryStore::open(&path).expect("legacy history should open");
+
+        let entry = &store.entries()[0];
+        assert_eq!(entry.request.contract_version, GENERATION_CONTRACT_VERSION);
+        assert_eq!(entry.result.contract_version, GENERATION_CONTRACT_VERSION);
+    }
+
+    #[test]
+    fn annotations_are_persisted_with_history_entries() {
+        let dir = TestDir::new("history");
+        let path = dir.join("generation_history.json");
+        let mut store = GenerationHistoryStore::open(&path).expect("store should open");
+        store
+            .record(request("req-1"), result("req-1"))
+            .expect("result should be recorded");
+
+        store
+            .set_annotation("req-1", "cand-1", "  use for bridge ")
+            .expect("annotation should be saved");
+        let reloaded = GenerationHistoryStore::open(&path).expect("store should reload");
+
+        assert_eq!(
+            reloaded.annotation("req-1", "cand-1"),
+            Some("use for bridge")
+        );
+    }
+
+    #[test]
+    fn blank_annotation_clears_and_unknown_targets_are_rejected() {
+        let mut store = GenerationHistoryStore::in_memory();
+        store
+            .record(request("req-1"), result("req-1"))
+            .expect("result should be recorded");
+        store
+            .set_annotation("req-1", "cand-1", "keeper")
+            .expect("annotation should be saved");
+
+        store
+            .set_annotation("req-1", "cand-1", "   ")
+            .expect("blank annotation should clear");
+
+        assert_eq!(store.annotation("req-1", "cand-1"), None);
+        assert!(matches!(
+            store.set_annotation("req-2", "cand-1", "x"),
+            Err(GenerationHistoryError::EntryNotFound { .. })
+        ));
+        assert!(matches!(
+            store.set_annotation("req-1", "cand-9", "x"),
+            Err(GenerationHistoryError::CandidateNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn history_keeps_only_the_most_recent_entries() {
+        let mut store = GenerationHistoryStore::in_memory();
+
+        for index in 0..GENERATION_HISTORY_MAX_ENTRIES + 2 {
+            let request_id = format!("req-{index}");
+            store
+                .record(request(&request_id), result(&request_id))
+                .expect("result should be recorded");
+        }
+
+        assert_eq!(store.entries().len(), GENERATION_HISTORY_MAX_ENTRIES);
+        assert_eq!(store.entries()[0].request_id(), "req-2");
+    }
+
+    #[test]
+    fn history_drops_the_oldest_entries_past_the_byte_limit() {
+        let mut store = GenerationHistoryStore::in_memory();
+        store
+            .record(request("req-0"), result("req-0"))
+            .expect("result should be recorded");
+        let single_entry_bytes = store.stored_bytes();
+        let mut store = GenerationHistoryStore::in_memory().with_max_bytes(single_entry_bytes * 2);
+
+        for index in 0..4 {
+            let request_id = format!("req-{index}");
+            store
+                .record(request(&request_id), result(&request_id))
+                .expect("result should be recorded");
+        }
+
+        assert!(store.stored_bytes() <= single_entry_bytes * 2);
+        assert!(store.entries().len() < 4);
+        assert_eq!(
+            store.entries().last().map(|entry| entry.request_id()),
+            Some("req-3")
+        );
+        assert_eq!(store.entries()[0].request_id(), "req-2");
+    }
+
+    #[test]
+    fn csv_export_has_one_escaped_row_per_candidate() {
+        let mut store = GenerationHistoryStore::in_memory();
+        let mut request = request("req-1");
+        request.prompt = "warm, \"dusty\" keys".to_string();
+        let mut result = result("req-1");
+        result.metadata.usage = Some(GenerationUsage {
+            input_tokens: Some(120),
+            output_tokens: Some(80),
+            ..GenerationUsage::default()
+        });
+        store
+            .record(request, result)
+            .expect("result should be recorded");
+        store
+            .set_annotation("req-1", "cand-1", "use for bridge")
+            .expect("annotation should be saved");
+
+        let csv = store
+            .export(GenerationHistoryExportFormat::Csv)
+            .expect("csv export should succeed");
+        let lines = csv.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("request_id,provider,model,mode,prompt"));
+        assert_eq!(
+            lines[1],
+            "req-1,anthropic,claude-3-5-sonnet,melody,\"warm, \"\"dusty\"\" keys\",120,C,major,3,3,3,,,,1,,120,80,,cand-1,1,,use for bridge"
+        );
+    }
+
+    #[test]
+    fn json_export_round_trips_entries() {
+        let mut store = GenerationHistoryStore::in_memory();
+        store
+            .record(request("req-1"), result("req-1"))
+            .expect("result should be recorded");
+
+        let json = store
+            .export(GenerationHistoryExportFormat::Json)
+            .expect("json export should succeed");
+        let entries: Vec<GenerationHistoryEntry> =
+            serde_json::from_str(&json).expect("export should parse");
+
+        assert_eq!(entries.as_slice(), store.entries());
+    }
+}
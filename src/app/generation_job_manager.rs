@@ -1,17 +1,29 @@
 use std::collections::VecDeque;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::{Arc, Mutex, mpsc};
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::{Arc, Condvar, Mutex, mpsc};
 use std::thread;
+use std::time::{Duration, Instant};
 
 use crate::domain::{GenerationRequest, GenerationResult, LlmError};
 
-use super::GenerationService;
+use super::{GenerationService, ModelComparison, ScheduledRetry, validate_comparison_requests};
+
+/// How long a job may run without reporting before the watchdog fails it as timed out. Generous
+/// enough for a slow model plus its retries; a provider call that never returns is what it
+/// catches.
+pub const GENERATION_JOB_DEFAULT_DEADLINE: Duration = Duration::from_secs(300);
+
+/// How soon after a request an identical one counts as an accidental double submission.
+pub const GENERATION_JOB_DUPLICATE_WINDOW: Duration = Duration::from_secs(3);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum GenerationJobState {
     #[default]
     Idle,
     Running,
+    /// Waiting out a backoff before the provider is called again.
+    Retrying,
     Succeeded,
     Failed,
     Cancelled,
@@ -24,6 +36,9 @@ pub struct GenerationJobUpdate {
     pub state: GenerationJobState,
     pub result: Option<GenerationResult>,
     pub error: Option<LlmError>,
+    pub retry: Option<ScheduledRetry>,
+    /// Set instead of `result` when a model comparison succeeds.
+    pub comparison: Option<ModelComparison>,
 }
 
 impl GenerationJobUpdate {
@@ -34,6 +49,20 @@ impl GenerationJobUpdate {
             state: GenerationJobState::Running,
             result: None,
             error: None,
+            retry: None,
+            comparison: None,
+        }
+    }
+
+    fn retrying(job_id: u64, request_id: String, retry: ScheduledRetry) -> Self {
+        Self {
+            job_id,
+            request_id,
+            state: GenerationJobState::Retrying,
+            result: None,
+            error: None,
+            retry: Some(retry),
+            comparison: None,
         }
     }
 
@@ -44,6 +73,20 @@ impl GenerationJobUpdate {
             state: GenerationJobState::Succeeded,
             result: Some(result),
             error: None,
+            retry: None,
+            comparison: None,
+        }
+    }
+
+    fn compared(job_id: u64, request_id: String, comparison: ModelComparison) -> Self {
+        Self {
+            job_id,
+            request_id,
+            state: GenerationJobState::Succeeded,
+            result: None,
+            error: None,
+            retry: None,
+            comparison: Some(comparison),
         }
     }
 
@@ -54,6 +97,8 @@ impl GenerationJobUpdate {
             state: GenerationJobState::Failed,
             result: None,
             error: Some(error),
+            retry: None,
+            comparison: None,
         }
     }
 
@@ -64,6 +109,29 @@ impl GenerationJobUpdate {
             state: GenerationJobState::Cancelled,
             result: None,
             error: None,
+            retry: None,
+            comparison: None,
+        }
+    }
+}
+
+/// What [`GenerationJobManager::submit_generate_deduplicated`] did with a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobSubmission {
+    Started {
+        job_id: u64,
+    },
+    /// Matched the still-unfinished job submitted just before, which reports for both.
+    Duplicate {
+        job_id: u64,
+        request_id: String,
+    },
+}
+
+impl JobSubmission {
+    pub fn job_id(&self) -> u64 {
+        match self {
+            Self::Started { job_id } | Self::Duplicate { job_id, .. } => *job_id,
         }
     }
 }
@@ -71,20 +139,40 @@ impl GenerationJobUpdate {
 pub struct GenerationJobManager {
     next_job_id: AtomicU64,
     command_tx: mpsc::Sender<WorkerMessage>,
-    shared: Arc<Mutex<SharedState>>,
+    shared: Arc<SharedUpdates>,
     worker_handle: Mutex<Option<thread::JoinHandle<()>>>,
+    last_submission: Mutex<Option<RecentSubmission>>,
+}
+
+struct RecentSubmission {
+    job_id: u64,
+    request_id: String,
+    /// The request without its id, which differs on every submission.
+    fingerprint: Option<String>,
+    submitted_at: Instant,
 }
 
 impl GenerationJobManager {
     pub fn new(service: GenerationService) -> Result<Self, LlmError> {
-        let shared = Arc::new(Mutex::new(SharedState::default()));
+        Self::with_deadline(service, GENERATION_JOB_DEFAULT_DEADLINE)
+    }
+
+    /// A job that stays Running for `deadline` without starting a retry is failed with
+    /// [`LlmError::Timeout`] and the next job starts; its provider call is abandoned.
+    pub fn with_deadline(service: GenerationService, deadline: Duration) -> Result<Self, LlmError> {
+        if deadline.is_zero() {
+            return Err(LlmError::validation(
+                "generation job deadline must be greater than 0",
+            ));
+        }
+        let shared = Arc::new(SharedUpdates::default());
         let (command_tx, command_rx) = mpsc::channel();
         let worker_tx = command_tx.clone();
         let worker_shared = Arc::clone(&shared);
 
         let handle = thread::Builder::new()
             .name("sonant-generation-job-worker".to_string())
-            .spawn(move || worker_loop(service, command_rx, worker_tx, worker_shared))
+            .spawn(move || worker_loop(service, deadline, command_rx, worker_tx, worker_shared))
             .map_err(|error| {
                 LlmError::internal(format!(
                     "failed to start generation job worker thread: {error}"
@@ -96,10 +184,93 @@ impl GenerationJobManager {
             command_tx,
             shared,
             worker_handle: Mutex::new(Some(handle)),
+            last_submission: Mutex::new(None),
         })
     }
 
+    /// Always starts a job, replacing the running one.
     pub fn submit_generate(&self, request: GenerationRequest) -> Result<u64, LlmError> {
+        let fingerprint = request_fingerprint(&request);
+        let request_id = request.request_id.clone();
+        let job_id = self.submit(JobRequest::Single(request))?;
+        *self.lock_last_submission() = Some(RecentSubmission {
+            job_id,
+            request_id,
+            fingerprint,
+            submitted_at: Instant::now(),
+        });
+        Ok(job_id)
+    }
+
+    /// Like [`Self::submit_generate`], but a request identical apart from its id to one
+    /// submitted within [`GENERATION_JOB_DUPLICATE_WINDOW`] that has not finished yet is
+    /// collapsed into that job instead of restarting it. Call `submit_generate` to override.
+    pub fn submit_generate_deduplicated(
+        &self,
+        request: GenerationRequest,
+    ) -> Result<JobSubmission, LlmError> {
+        if let Some(duplicate) = self.duplicate_of(&request) {
+            return Ok(duplicate);
+        }
+        self.submit_generate(request)
+            .map(|job_id| JobSubmission::Started { job_id })
+    }
+
+    fn duplicate_of(&self, request: &GenerationRequest) -> Option<JobSubmission> {
+        let last_submission = self.lock_last_submission();
+        let recent = last_submission.as_ref()?;
+        if recent.submitted_at.elapsed() > GENERATION_JOB_DUPLICATE_WINDOW
+            || recent.fingerprint.is_none()
+            || recent.fingerprint != request_fingerprint(request)
+            || self.has_finished(recent.job_id)
+        {
+            return None;
+        }
+        Some(JobSubmission::Duplicate {
+            job_id: recent.job_id,
+            request_id: recent.request_id.clone(),
+        })
+    }
+
+    fn has_finished(&self, job_id: u64) -> bool {
+        let shared = self
+            .shared
+            .state
+            .lock()
+            .expect("generation job state lock poisoned");
+        shared.latest.as_ref().is_some_and(|latest| {
+            latest.job_id == job_id
+                && matches!(
+                    latest.state,
+                    GenerationJobState::Succeeded
+                        | GenerationJobState::Failed
+                        | GenerationJobState::Cancelled
+                )
+        })
+    }
+
+    fn lock_last_submission(&self) -> std::sync::MutexGuard<'_, Option<RecentSubmission>> {
+        self.last_submission
+            .lock()
+            .expect("generation job submission lock poisoned")
+    }
+
+    /// Sends `requests`, one per model, concurrently as a single job reported under `request_id`.
+    pub fn submit_comparison(
+        &self,
+        request_id: String,
+        requests: Vec<GenerationRequest>,
+    ) -> Result<u64, LlmError> {
+        validate_comparison_requests(&requests)?;
+        // A comparison replaces the running job, so nothing is left to collapse into.
+        self.lock_last_submission().take();
+        self.submit(JobRequest::Comparison {
+            request_id,
+            requests,
+        })
+    }
+
+    fn submit(&self, request: JobRequest) -> Result<u64, LlmError> {
         let job_id = self.next_job_id.fetch_add(1, Ordering::SeqCst);
         self.command_tx
             .send(WorkerMessage::Start { job_id, request })
@@ -123,6 +294,7 @@ impl GenerationJobManager {
 
     pub fn state(&self) -> GenerationJobState {
         self.shared
+            .state
             .lock()
             .expect("generation job state lock poisoned")
             .state
@@ -130,6 +302,7 @@ impl GenerationJobManager {
 
     pub fn latest_update(&self) -> Option<GenerationJobUpdate> {
         self.shared
+            .state
             .lock()
             .expect("generation job state lock poisoned")
             .latest
@@ -139,16 +312,40 @@ impl GenerationJobManager {
     pub fn drain_updates(&self) -> Vec<GenerationJobUpdate> {
         let mut shared = self
             .shared
+            .state
             .lock()
             .expect("generation job state lock poisoned");
         shared.updates.drain(..).collect()
     }
-}
 
-impl Drop for GenerationJobManager {
-    fn drop(&mut self) {
-        let _ = self.command_tx.send(WorkerMessage::Shutdown);
+    /// Blocks until an update is queued or `timeout` elapses, so callers can sleep instead of
+    /// polling. Returns whether updates are waiting to be drained.
+    pub fn wait_for_updates(&self, timeout: Duration) -> bool {
+        let shared = self
+            .shared
+            .state
+            .lock()
+            .expect("generation job state lock poisoned");
+        let (shared, _) = self
+            .shared
+            .ready
+            .wait_timeout_while(shared, timeout, |shared| shared.updates.is_empty())
+            .expect("generation job state lock poisoned");
+        !shared.updates.is_empty()
+    }
+
+    /// Stops the worker: queued jobs are cancelled and the in-flight one gets up to `grace` to
+    /// finish before it is cancelled and its provider call abandoned. Returns once the worker
+    /// has stopped, with the final updates queued for [`Self::drain_updates`]; later
+    /// submissions fail.
+    pub fn shutdown(&self, grace: Duration) {
+        let _ = self
+            .command_tx
+            .send(WorkerMessage::Shutdown { grace: Some(grace) });
+        self.join_worker();
+    }
 
+    fn join_worker(&self) {
         if let Some(handle) = self
             .worker_handle
             .lock()
@@ -160,6 +357,16 @@ impl Drop for GenerationJobManager {
     }
 }
 
+impl Drop for GenerationJobManager {
+    fn drop(&mut self) {
+        // Without a grace period the in-flight job is cancelled and its thread waited for.
+        let _ = self
+            .command_tx
+            .send(WorkerMessage::Shutdown { grace: None });
+        self.join_worker();
+    }
+}
+
 #[derive(Default)]
 struct SharedState {
     state: GenerationJobState,
@@ -167,19 +374,55 @@ struct SharedState {
     updates: VecDeque<GenerationJobUpdate>,
 }
 
+#[derive(Default)]
+struct SharedUpdates {
+    state: Mutex<SharedState>,
+    /// Signalled whenever an update is queued.
+    ready: Condvar,
+}
+
+enum JobRequest {
+    Single(GenerationRequest),
+    Comparison {
+        request_id: String,
+        requests: Vec<GenerationRequest>,
+    },
+}
+
+impl JobRequest {
+    fn request_id(&self) -> &str {
+        match self {
+            Self::Single(request) => &request.request_id,
+            Self::Comparison { request_id, .. } => request_id,
+        }
+    }
+}
+
+enum JobOutput {
+    Single(GenerationResult),
+    Comparison(ModelComparison),
+}
+
 enum WorkerMessage {
     Start {
         job_id: u64,
-        request: GenerationRequest,
+        request: JobRequest,
     },
     Completion {
         job_id: u64,
         request_id: String,
-        result: Result<GenerationResult, LlmError>,
+        result: Result<JobOutput, LlmError>,
         cancelled: bool,
     },
+    /// The job started a retry, so it is still making progress.
+    Progress {
+        job_id: u64,
+    },
     CancelActive,
-    Shutdown,
+    /// `grace` bounds the wait for the in-flight job; `None` waits for its thread to return.
+    Shutdown {
+        grace: Option<Duration>,
+    },
 }
 
 struct RunningJob {
@@ -188,46 +431,79 @@ struct RunningJob {
     cancel_flag: Arc<AtomicBool>,
     cancelled_reported: bool,
     task_handle: Option<thread::JoinHandle<()>>,
+    last_progress: Instant,
 }
 
 struct PendingJob {
     job_id: u64,
-    request: GenerationRequest,
+    request: JobRequest,
 }
 
 fn worker_loop(
     service: GenerationService,
+    deadline: Duration,
     command_rx: mpsc::Receiver<WorkerMessage>,
     command_tx: mpsc::Sender<WorkerMessage>,
-    shared: Arc<Mutex<SharedState>>,
+    shared: Arc<SharedUpdates>,
 ) {
     let mut in_flight: Option<RunningJob> = None;
     let mut pending_job: Option<PendingJob> = None;
     let mut shutdown_requested = false;
+    // While shutting down, when the in-flight job stops being waited for and is abandoned.
+    let mut drain_until: Option<Instant> = None;
+
+    loop {
+        let received = match in_flight.as_ref() {
+            Some(active) => {
+                let expires_at = active.last_progress + deadline;
+                let wake_at = drain_until.map_or(expires_at, |until| until.min(expires_at));
+                command_rx.recv_timeout(wake_at.saturating_duration_since(Instant::now()))
+            }
+            None => command_rx
+                .recv()
+                .map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        let message = match received {
+            Ok(message) => message,
+            Err(RecvTimeoutError::Disconnected) => break,
+            Err(RecvTimeoutError::Timeout) => {
+                if drain_until.is_some_and(|until| Instant::now() >= until) {
+                    if let Some(mut active) = in_flight.take() {
+                        cancel_running_job(&mut active, &shared);
+                    }
+                    break;
+                }
+                if let Some(stalled) = in_flight.take() {
+                    expire_stalled_job(stalled, &shared);
+                }
+                if shutdown_requested {
+                    break;
+                }
+                if let Some(next) = pending_job.take() {
+                    in_flight = Some(spawn_generation_job(
+                        &service,
+                        &command_tx,
+                        &shared,
+                        next.job_id,
+                        next.request,
+                    ));
+                }
+                continue;
+            }
+        };
 
-    while let Ok(message) = command_rx.recv() {
         match message {
             WorkerMessage::Start { job_id, request } => {
                 if shutdown_requested {
                     push_update(
                         &shared,
-                        GenerationJobUpdate::cancelled(job_id, request.request_id),
+                        GenerationJobUpdate::cancelled(job_id, request.request_id().to_string()),
                     );
                     continue;
                 }
 
                 if let Some(active) = in_flight.as_mut() {
-                    active.cancel_flag.store(true, Ordering::SeqCst);
-                    if !active.cancelled_reported {
-                        active.cancelled_reported = true;
-                        push_update(
-                            &shared,
-                            GenerationJobUpdate::cancelled(
-                                active.job_id,
-                                active.request_id.clone(),
-                            ),
-                        );
-                    }
+                    cancel_running_job(active, &shared);
 
                     if let Some(previous_pending) =
                         pending_job.replace(PendingJob { job_id, request })
@@ -236,7 +512,7 @@ fn worker_loop(
                             &shared,
                             GenerationJobUpdate::cancelled(
                                 previous_pending.job_id,
-                                previous_pending.request.request_id,
+                                previous_pending.request.request_id().to_string(),
                             ),
                         );
                     }
@@ -279,12 +555,18 @@ fn worker_loop(
                     }
                 } else {
                     match result {
-                        Ok(result) => {
+                        Ok(JobOutput::Single(result)) => {
                             push_update(
                                 &shared,
                                 GenerationJobUpdate::succeeded(job_id, request_id, result),
                             );
                         }
+                        Ok(JobOutput::Comparison(comparison)) => {
+                            push_update(
+                                &shared,
+                                GenerationJobUpdate::compared(job_id, request_id, comparison),
+                            );
+                        }
                         Err(error) => {
                             push_update(
                                 &shared,
@@ -313,49 +595,45 @@ fn worker_loop(
                     ));
                 }
             }
+            WorkerMessage::Progress { job_id } => {
+                if let Some(active) = in_flight.as_mut()
+                    && active.job_id == job_id
+                {
+                    active.last_progress = Instant::now();
+                }
+            }
             WorkerMessage::CancelActive => {
                 if let Some(active) = in_flight.as_mut() {
-                    active.cancel_flag.store(true, Ordering::SeqCst);
-                    if !active.cancelled_reported {
-                        active.cancelled_reported = true;
-                        push_update(
-                            &shared,
-                            GenerationJobUpdate::cancelled(
-                                active.job_id,
-                                active.request_id.clone(),
-                            ),
-                        );
-                    }
+                    cancel_running_job(active, &shared);
                 }
 
                 if let Some(next) = pending_job.take() {
                     push_update(
                         &shared,
-                        GenerationJobUpdate::cancelled(next.job_id, next.request.request_id),
+                        GenerationJobUpdate::cancelled(
+                            next.job_id,
+                            next.request.request_id().to_string(),
+                        ),
                     );
                 }
             }
-            WorkerMessage::Shutdown => {
+            WorkerMessage::Shutdown { grace } => {
                 shutdown_requested = true;
 
                 if let Some(active) = in_flight.as_mut() {
-                    active.cancel_flag.store(true, Ordering::SeqCst);
-                    if !active.cancelled_reported {
-                        active.cancelled_reported = true;
-                        push_update(
-                            &shared,
-                            GenerationJobUpdate::cancelled(
-                                active.job_id,
-                                active.request_id.clone(),
-                            ),
-                        );
+                    match grace {
+                        Some(grace) => drain_until = Some(Instant::now() + grace),
+                        None => cancel_running_job(active, &shared),
                     }
                 }
 
                 if let Some(next) = pending_job.take() {
                     push_update(
                         &shared,
-                        GenerationJobUpdate::cancelled(next.job_id, next.request.request_id),
+                        GenerationJobUpdate::cancelled(
+                            next.job_id,
+                            next.request.request_id().to_string(),
+                        ),
                     );
                 }
 
@@ -367,19 +645,31 @@ fn worker_loop(
     }
 }
 
+fn request_fingerprint(request: &GenerationRequest) -> Option<String> {
+    let mut request = request.clone();
+    request.request_id.clear();
+    serde_json::to_string(&request).ok()
+}
+
 fn spawn_generation_job(
     service: &GenerationService,
     command_tx: &mpsc::Sender<WorkerMessage>,
-    shared: &Arc<Mutex<SharedState>>,
+    shared: &Arc<SharedUpdates>,
     job_id: u64,
-    request: GenerationRequest,
+    request: JobRequest,
 ) -> RunningJob {
-    let request_id = request.request_id.clone();
+    let request_id = request.request_id().to_string();
     let cancel_flag = Arc::new(AtomicBool::new(false));
     let cancel_for_thread = Arc::clone(&cancel_flag);
     let tx_for_thread = command_tx.clone();
     let service_for_thread = service.clone();
     let request_id_for_thread = request_id.clone();
+    let shared_for_thread = Arc::clone(shared);
+
+    push_update(
+        shared,
+        GenerationJobUpdate::running(job_id, request_id.clone()),
+    );
 
     let task_handle = thread::spawn(move || {
         if cancel_for_thread.load(Ordering::SeqCst) {
@@ -392,8 +682,30 @@ fn spawn_generation_job(
             return;
         }
 
-        let result = service_for_thread
-            .generate_with_cancel(request, || cancel_for_thread.load(Ordering::SeqCst));
+        let result = match request {
+            JobRequest::Single(request) => service_for_thread
+                .generate_with_retry_observer(
+                    request,
+                    || cancel_for_thread.load(Ordering::SeqCst),
+                    |retry| {
+                        if !cancel_for_thread.load(Ordering::SeqCst) {
+                            let _ = tx_for_thread.send(WorkerMessage::Progress { job_id });
+                            push_update(
+                                &shared_for_thread,
+                                GenerationJobUpdate::retrying(
+                                    job_id,
+                                    request_id_for_thread.clone(),
+                                    retry.clone(),
+                                ),
+                            );
+                        }
+                    },
+                )
+                .map(JobOutput::Single),
+            JobRequest::Comparison { requests, .. } => service_for_thread
+                .compare_models(requests, || cancel_for_thread.load(Ordering::SeqCst))
+                .map(JobOutput::Comparison),
+        };
         let cancelled = cancel_for_thread.load(Ordering::SeqCst);
 
         let _ = tx_for_thread.send(WorkerMessage::Completion {
@@ -404,17 +716,36 @@ fn spawn_generation_job(
         });
     });
 
-    push_update(
-        shared,
-        GenerationJobUpdate::running(job_id, request_id.clone()),
-    );
-
     RunningJob {
         job_id,
         request_id,
         cancel_flag,
         cancelled_reported: false,
         task_handle: Some(task_handle),
+        last_progress: Instant::now(),
+    }
+}
+
+fn cancel_running_job(active: &mut RunningJob, shared: &Arc<SharedUpdates>) {
+    active.cancel_flag.store(true, Ordering::SeqCst);
+    if !active.cancelled_reported {
+        active.cancelled_reported = true;
+        push_update(
+            shared,
+            GenerationJobUpdate::cancelled(active.job_id, active.request_id.clone()),
+        );
+    }
+}
+
+/// Fails a job the watchdog gave up on. Its thread is left to finish on its own, since the
+/// provider call may never return; a late completion no longer matches the in-flight job.
+fn expire_stalled_job(mut stalled: RunningJob, shared: &Arc<SharedUpdates>) {
+    stalled.cancel_flag.store(true, Ordering::SeqCst);
+    if !stalled.cancelled_reported {
+        push_update(
+            shared,
+            GenerationJobUpdate::failed(stalled.job_id, stalled.request_id, LlmError::Timeout),
+        );
     }
 }
 
@@ -424,13 +755,17 @@ fn join_generation_task(job: &mut RunningJob) {
     }
 }
 
-fn push_update(shared: &Arc<Mutex<SharedState>>, update: GenerationJobUpdate) {
-    let mut shared = shared
-        .lock()
-        .expect("generation job state lock poisoned during update");
-    shared.state = update.state;
-    shared.latest = Some(update.clone());
-    shared.updates.push_back(update);
+fn push_update(shared: &Arc<SharedUpdates>, update: GenerationJobUpdate) {
+    {
+        let mut state = shared
+            .state
+            .lock()
+            .expect("generation job state lock poisoned during update");
+        state.state = update.state;
+        state.latest = Some(update.clone());
+        state.updates.push_back(update);
+    }
+    shared.ready.notify_all();
 }
 
 #[cfg(test)]
@@ -442,12 +777,13 @@ mod tests {
     use std::time::{Duration, Instant};
 
     use crate::domain::{
-        GeneratedNote, GenerationCandidate, GenerationMetadata, GenerationMode, GenerationParams,
-        GenerationRequest, GenerationResult, LlmError, ModelRef,
+        GENERATION_CONTRACT_VERSION, GeneratedNote, GenerationCandidate, GenerationMetadata,
+        GenerationMode, GenerationParams, GenerationRequest, GenerationResult, LlmError, ModelRef,
+        TickResolution,
     };
     use crate::infra::llm::{LlmProvider, ProviderRegistry};
 
-    use super::{GenerationJobManager, GenerationJobState, GenerationService};
+    use super::{GenerationJobManager, GenerationJobState, GenerationService, JobSubmission};
 
     struct DelayedProvider {
         delays: Arc<Mutex<VecDeque<Duration>>>,
@@ -598,12 +934,20 @@ mod tests {
                 scale: "major".to_string(),
                 density: 3,
                 complexity: 3,
+                syncopation: 3,
+                tick_resolution: TickResolution::DEFAULT,
                 temperature: Some(0.7),
                 top_p: Some(0.9),
                 max_tokens: Some(256),
             },
             references: Vec::new(),
             variation_count: 1,
+            locked_notes: Vec::new(),
+            contract_version: GENERATION_CONTRACT_VERSION,
+            daw_context: None,
+            constraints: None,
+            validation: None,
+            anonymize_references: false,
         }
     }
 
@@ -624,9 +968,14 @@ mod tests {
                     velocity: 100,
                     channel: 1,
                 }],
+                tick_resolution: TickResolution::DEFAULT,
                 score_hint: Some(0.8),
+                title: None,
+                control_events: Vec::new(),
+                processing: None,
             }],
             metadata: GenerationMetadata::default(),
+            contract_version: GENERATION_CONTRACT_VERSION,
         }
     }
 
@@ -753,6 +1102,59 @@ mod tests {
         }));
     }
 
+    #[test]
+    fn identical_requests_submitted_back_to_back_collapse_into_one_job() {
+        let provider = Arc::new(DelayedProvider {
+            delays: Arc::new(Mutex::new(VecDeque::from([
+                Duration::from_millis(150),
+                Duration::from_millis(10),
+                Duration::from_millis(10),
+            ]))),
+            fail_requests: Arc::new(Mutex::new(Vec::new())),
+        });
+        let manager = manager_with_provider(provider);
+
+        let first = manager
+            .submit_generate_deduplicated(valid_request("req-click-1"))
+            .expect("first submit should succeed");
+        let double_click = manager
+            .submit_generate_deduplicated(valid_request("req-click-2"))
+            .expect("duplicate submit should succeed");
+        let mut different = valid_request("req-other");
+        different.prompt = "darker synth melody".to_string();
+        let other = manager
+            .submit_generate_deduplicated(different.clone())
+            .expect("different request should be submitted");
+
+        assert!(matches!(first, JobSubmission::Started { .. }));
+        assert_eq!(
+            double_click,
+            JobSubmission::Duplicate {
+                job_id: first.job_id(),
+                request_id: "req-click-1".to_string(),
+            }
+        );
+        assert!(matches!(other, JobSubmission::Started { job_id } if job_id > first.job_id()));
+
+        wait_for(
+            &manager,
+            |state| state == GenerationJobState::Succeeded,
+            Duration::from_millis(700),
+        );
+        different.request_id = "req-other-again".to_string();
+        let again = manager
+            .submit_generate_deduplicated(different)
+            .expect("submit after the job finished should succeed");
+        let updates = manager.drain_updates();
+
+        assert!(
+            !updates
+                .iter()
+                .any(|update| update.request_id == "req-click-2")
+        );
+        assert!(matches!(again, JobSubmission::Started { .. }));
+    }
+
     #[test]
     fn completion_of_stale_job_does_not_override_latest_result() {
         let provider = Arc::new(DelayedProvider {
@@ -794,6 +1196,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn wait_for_updates_wakes_when_a_job_reports() {
+        let provider = Arc::new(DelayedProvider {
+            delays: Arc::new(Mutex::new(VecDeque::from([Duration::from_millis(20)]))),
+            fail_requests: Arc::new(Mutex::new(Vec::new())),
+        });
+        let manager = manager_with_provider(provider);
+
+        assert!(!manager.wait_for_updates(Duration::from_millis(10)));
+
+        manager
+            .submit_generate(valid_request("req-wait"))
+            .expect("submit should succeed");
+        assert!(manager.wait_for_updates(Duration::from_secs(2)));
+        assert!(!manager.drain_updates().is_empty());
+    }
+
     #[test]
     fn failed_job_transitions_to_failed_state() {
         let provider = Arc::new(DelayedProvider {
@@ -822,6 +1241,101 @@ mod tests {
         assert!(matches!(latest.error, Some(LlmError::Timeout)));
     }
 
+    #[test]
+    fn watchdog_fails_a_hung_job_and_starts_the_next_one() {
+        let provider = Arc::new(DelayedProvider {
+            delays: Arc::new(Mutex::new(VecDeque::from([Duration::from_secs(2)]))),
+            fail_requests: Arc::new(Mutex::new(Vec::new())),
+        });
+        let mut registry = ProviderRegistry::new();
+        registry
+            .register_shared(provider)
+            .expect("provider registration should succeed");
+        let manager = GenerationJobManager::with_deadline(
+            GenerationService::new(registry),
+            Duration::from_millis(100),
+        )
+        .expect("job manager should start worker");
+
+        let hung_job = manager
+            .submit_generate(valid_request("req-hung"))
+            .expect("submit should succeed");
+        wait_for(
+            &manager,
+            |state| state == GenerationJobState::Failed,
+            Duration::from_millis(1000),
+        );
+        let latest = manager.latest_update().expect("latest update should exist");
+        assert_eq!(latest.job_id, hung_job);
+        assert_eq!(latest.error, Some(LlmError::Timeout));
+
+        let next_job = manager
+            .submit_generate(valid_request("req-next"))
+            .expect("submit should succeed");
+        wait_for(
+            &manager,
+            |state| state == GenerationJobState::Succeeded,
+            Duration::from_millis(1000),
+        );
+        let latest = manager.latest_update().expect("latest update should exist");
+        assert_eq!(latest.job_id, next_job);
+        assert_eq!(latest.request_id, "req-next");
+    }
+
+    #[test]
+    fn shutdown_drains_the_in_flight_job_and_cancels_queued_ones() {
+        let provider = Arc::new(ConcurrencyTrackingProvider::new(Duration::from_millis(80)));
+        let manager = manager_with_provider(provider);
+
+        let running_job = manager
+            .submit_generate(valid_request("req-running"))
+            .expect("submit should succeed");
+        thread::sleep(Duration::from_millis(10));
+        let queued_job = manager
+            .submit_generate(valid_request("req-queued"))
+            .expect("submit should succeed");
+        thread::sleep(Duration::from_millis(10));
+        manager.shutdown(Duration::from_secs(1));
+
+        let updates = manager.drain_updates();
+        assert!(updates.iter().any(|update| {
+            update.job_id == running_job && update.state == GenerationJobState::Succeeded
+        }));
+        assert!(updates.iter().any(|update| {
+            update.job_id == queued_job && update.state == GenerationJobState::Cancelled
+        }));
+        assert!(!updates.iter().any(|update| {
+            update.job_id == queued_job && update.state == GenerationJobState::Running
+        }));
+        assert!(manager.submit_generate(valid_request("req-late")).is_err());
+    }
+
+    #[test]
+    fn shutdown_abandons_a_job_that_outlasts_the_grace_period() {
+        let entered = Arc::new(AtomicBool::new(false));
+        let (_release_tx, release_rx) = mpsc::channel();
+        let manager = manager_with_provider(Arc::new(BlockingProvider {
+            entered: Arc::clone(&entered),
+            release_rx: Arc::new(Mutex::new(release_rx)),
+        }));
+
+        let job_id = manager
+            .submit_generate(valid_request("req-blocked"))
+            .expect("submit should succeed");
+        wait_for(
+            &manager,
+            |state| state == GenerationJobState::Running,
+            Duration::from_millis(200),
+        );
+        let started = Instant::now();
+        manager.shutdown(Duration::from_millis(50));
+
+        assert!(started.elapsed() < Duration::from_secs(1));
+        let latest = manager.latest_update().expect("latest update should exist");
+        assert_eq!(latest.job_id, job_id);
+        assert_eq!(latest.state, GenerationJobState::Cancelled);
+    }
+
     #[test]
     fn cancel_active_marks_running_job_as_cancelled() {
         let entered = Arc::new(AtomicBool::new(false));
@@ -1,12 +1,20 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
 use std::thread;
 use std::time::{Duration, Instant};
 
-use crate::domain::{GenerationRequest, GenerationResult, LlmError};
-use crate::infra::llm::ProviderRegistry;
+use super::ProviderErrorBudget;
+use crate::domain::{
+    GenerationCandidate, GenerationMode, GenerationRequest, GenerationResult, LlmError, ModelRef,
+    PrivacyFilterMode,
+};
+use crate::infra::llm::{PromptBuilder, ProviderRegistry};
 
 const DEFAULT_RETRY_MAX_ATTEMPTS: u8 = 3;
 const DEFAULT_RETRY_INITIAL_BACKOFF_MS: u64 = 200;
 const DEFAULT_RETRY_MAX_BACKOFF_MS: u64 = 2_000;
+const DEFAULT_RETRY_JITTER_PERCENT: u8 = 20;
+const DEFAULT_MAX_RETRY_AFTER_SECS: u64 = 60;
 const BACKOFF_CANCEL_POLL_INTERVAL_MS: u64 = 10;
 const CANCELLATION_ERROR_MESSAGE: &str = "generation cancelled";
 
@@ -15,6 +23,10 @@ pub struct GenerationRetryConfig {
     pub max_attempts: u8,
     pub initial_backoff: Duration,
     pub max_backoff: Duration,
+    /// Share of each delay, in percent, randomized so several clients do not retry in lockstep.
+    pub jitter_percent: u8,
+    /// Longest provider-requested `Retry-After` wait honored; longer waits fail immediately.
+    pub max_retry_after: Duration,
 }
 
 impl Default for GenerationRetryConfig {
@@ -23,6 +35,8 @@ impl Default for GenerationRetryConfig {
             max_attempts: DEFAULT_RETRY_MAX_ATTEMPTS,
             initial_backoff: Duration::from_millis(DEFAULT_RETRY_INITIAL_BACKOFF_MS),
             max_backoff: Duration::from_millis(DEFAULT_RETRY_MAX_BACKOFF_MS),
+            jitter_percent: DEFAULT_RETRY_JITTER_PERCENT,
+            max_retry_after: Duration::from_secs(DEFAULT_MAX_RETRY_AFTER_SECS),
         }
     }
 }
@@ -39,6 +53,11 @@ impl GenerationRetryConfig {
                 "retry initial_backoff must be less than or equal to max_backoff",
             ));
         }
+        if self.jitter_percent > 100 {
+            return Err(LlmError::validation(
+                "retry jitter_percent must be at most 100",
+            ));
+        }
         Ok(())
     }
 
@@ -48,12 +67,45 @@ impl GenerationRetryConfig {
         let backoff = self.initial_backoff.saturating_mul(multiplier);
         backoff.min(self.max_backoff)
     }
+
+    /// Delay before retry `retry_index`, or `None` when the provider asked for a longer wait than
+    /// `max_retry_after`. Jitter only lengthens a provider-requested wait, so the retry never
+    /// arrives early, and only shortens computed backoff. `jitter_sample` is in `0.0..1.0`.
+    fn delay_for_retry(
+        &self,
+        retry_index: u8,
+        error: &LlmError,
+        jitter_sample: f64,
+    ) -> Option<Duration> {
+        let jitter = f64::from(self.jitter_percent) / 100.0 * jitter_sample.clamp(0.0, 1.0);
+        if let LlmError::RateLimited {
+            retry_after: Some(retry_after),
+        } = error
+        {
+            return (*retry_after <= self.max_retry_after)
+                .then(|| retry_after.mul_f64(1.0 + jitter));
+        }
+        Some(self.backoff_for_retry(retry_index).mul_f64(1.0 - jitter))
+    }
+}
+
+/// Reported before the service waits to retry a failed provider call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduledRetry {
+    /// 1-based number of the attempt that runs after `delay`.
+    pub next_attempt: u8,
+    pub max_attempts: u8,
+    pub delay: Duration,
+    pub error: LlmError,
 }
 
 #[derive(Clone)]
 pub struct GenerationService {
     registry: ProviderRegistry,
     retry_config: GenerationRetryConfig,
+    privacy_filter: Option<PrivacyFilterMode>,
+    fallback_models: Vec<ModelRef>,
+    error_budget: ProviderErrorBudget,
 }
 
 impl GenerationService {
@@ -61,6 +113,9 @@ impl GenerationService {
         Self {
             registry,
             retry_config: GenerationRetryConfig::default(),
+            privacy_filter: None,
+            fallback_models: Vec::new(),
+            error_budget: ProviderErrorBudget::default(),
         }
     }
 
@@ -72,20 +127,59 @@ impl GenerationService {
         Ok(Self {
             registry,
             retry_config,
+            privacy_filter: None,
+            fallback_models: Vec::new(),
+            error_budget: ProviderErrorBudget::default(),
         })
     }
 
+    /// Filters identifying text from requests bound for cloud providers; local ones are exempt.
+    pub fn with_privacy_filter(mut self, privacy_filter: Option<PrivacyFilterMode>) -> Self {
+        self.privacy_filter = privacy_filter;
+        self
+    }
+
+    /// Models tried, in order, instead of a requested provider the error budget has demoted.
+    pub fn with_fallback_models(mut self, fallback_models: Vec<ModelRef>) -> Self {
+        self.fallback_models = fallback_models;
+        self
+    }
+
+    pub fn with_error_budget(mut self, error_budget: ProviderErrorBudget) -> Self {
+        self.error_budget = error_budget;
+        self
+    }
+
+    /// Shared handle for reading demotions while the service runs on a worker thread.
+    pub fn error_budget(&self) -> ProviderErrorBudget {
+        self.error_budget.clone()
+    }
+
     pub fn generate(&self, request: GenerationRequest) -> Result<GenerationResult, LlmError> {
         self.generate_with_cancel(request, || false)
     }
 
     pub fn generate_with_cancel<F>(
+        &self,
+        request: GenerationRequest,
+        is_cancelled: F,
+    ) -> Result<GenerationResult, LlmError>
+    where
+        F: Fn() -> bool,
+    {
+        self.generate_with_retry_observer(request, is_cancelled, |_| {})
+    }
+
+    /// Like [`Self::generate_with_cancel`], calling `on_retry` before each backoff wait.
+    pub fn generate_with_retry_observer<F, R>(
         &self,
         mut request: GenerationRequest,
         is_cancelled: F,
+        mut on_retry: R,
     ) -> Result<GenerationResult, LlmError>
     where
         F: Fn() -> bool,
+        R: FnMut(&ScheduledRetry),
     {
         // Canonicalize provider/model IDs so resolution and provider execution use the same values.
         request.model.provider = request.model.provider.trim().to_string();
@@ -93,9 +187,19 @@ impl GenerationService {
 
         request.validate()?;
 
+        request.model = self.route_around_demoted_providers(&request.model);
         let provider = self
             .registry
             .resolve(&request.model.provider, &request.model.model)?;
+        // Only this copy is renamed; the caller's request keeps the paths for history.
+        if request.anonymize_references {
+            request.anonymize_reference_files();
+        }
+        if let Some(privacy_filter) = self.privacy_filter
+            && provider.is_cloud()
+        {
+            request.apply_privacy_filter(privacy_filter)?;
+        }
         let mut attempt = 1_u8;
 
         loop {
@@ -103,9 +207,16 @@ impl GenerationService {
                 return Err(LlmError::internal(CANCELLATION_ERROR_MESSAGE));
             }
 
-            match provider.generate(&request) {
-                Ok(result) => {
+            let outcome = provider.generate(&request);
+            self.error_budget.record(
+                &request.model.provider,
+                outcome.as_ref().map(|_| ()),
+                Instant::now(),
+            );
+            match outcome {
+                Ok(mut result) => {
                     result.validate()?;
+                    result.retain_candidates_with_locked_notes(&request.locked_notes)?;
                     return Ok(result);
                 }
                 Err(error) => {
@@ -117,8 +228,19 @@ impl GenerationService {
                         return Err(LlmError::internal(CANCELLATION_ERROR_MESSAGE));
                     }
 
-                    let backoff = self.retry_config.backoff_for_retry(attempt);
-                    if sleep_with_cancellation(backoff, &is_cancelled) {
+                    let Some(delay) =
+                        self.retry_config
+                            .delay_for_retry(attempt, &error, jitter_sample())
+                    else {
+                        return Err(error);
+                    };
+                    on_retry(&ScheduledRetry {
+                        next_attempt: attempt.saturating_add(1),
+                        max_attempts: self.retry_config.max_attempts,
+                        delay,
+                        error,
+                    });
+                    if sleep_with_cancellation(delay, &is_cancelled) {
                         return Err(LlmError::internal(CANCELLATION_ERROR_MESSAGE));
                     }
                     attempt = attempt.saturating_add(1);
@@ -126,6 +248,47 @@ impl GenerationService {
             }
         }
     }
+
+    /// Asks `model` for a short description of `candidate`, in one attempt and without routing
+    /// around demoted providers, since the answer is informational.
+    pub fn explain_candidate(
+        &self,
+        model: &ModelRef,
+        mode: Option<GenerationMode>,
+        candidate: &GenerationCandidate,
+    ) -> Result<String, LlmError> {
+        let provider = self.registry.resolve(&model.provider, &model.model)?;
+        let prompt = PromptBuilder::build_candidate_explanation(mode, candidate);
+        provider.describe(model.model.trim(), &prompt)
+    }
+
+    fn route_around_demoted_providers(&self, requested: &ModelRef) -> ModelRef {
+        let now = Instant::now();
+        if self.fallback_models.is_empty()
+            || !self.error_budget.is_demoted(&requested.provider, now)
+        {
+            return requested.clone();
+        }
+        let candidates = std::iter::once(requested.clone())
+            .chain(
+                self.fallback_models
+                    .iter()
+                    .filter(|model| model.provider != requested.provider)
+                    .cloned(),
+            )
+            .collect::<Vec<_>>();
+        self.error_budget
+            .fallback_order(&candidates, now)
+            .into_iter()
+            .find(|model| self.registry.resolve(&model.provider, &model.model).is_ok())
+            .unwrap_or_else(|| requested.clone())
+    }
+}
+
+// Uniform-enough value in 0.0..1.0 from the std hasher's per-process random keys.
+fn jitter_sample() -> f64 {
+    let bits = RandomState::new().hash_one(Instant::now());
+    (bits >> 11) as f64 / (1_u64 << 53) as f64
 }
 
 fn sleep_with_cancellation<F>(duration: Duration, is_cancelled: &F) -> bool
@@ -164,9 +327,12 @@ mod tests {
     use std::thread;
 
     use super::{GenerationRetryConfig, GenerationService};
+    use crate::app::{ProviderErrorBudget, ProviderErrorBudgetConfig};
     use crate::domain::{
-        GeneratedNote, GenerationCandidate, GenerationMetadata, GenerationMode, GenerationParams,
-        GenerationRequest, GenerationResult, LlmError, ModelRef,
+        FileReferenceInput, GENERATION_CONTRACT_VERSION, GeneratedNote, GenerationCandidate,
+        GenerationMetadata, GenerationMode, GenerationParams, GenerationRequest, GenerationResult,
+        LlmError, MidiReferenceSummary, ModelRef, PrivacyFilterMode, ReferenceSlot,
+        ReferenceSource, TickResolution,
     };
     use crate::infra::llm::{LlmProvider, ProviderRegistry};
 
@@ -193,6 +359,31 @@ mod tests {
         }
     }
 
+    struct ReferencePathRecordingProvider {
+        sent_paths: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl LlmProvider for ReferencePathRecordingProvider {
+        fn provider_id(&self) -> &str {
+            "anthropic"
+        }
+
+        fn supports_model(&self, model_id: &str) -> bool {
+            model_id == "claude-3-5-sonnet"
+        }
+
+        fn generate(&self, request: &GenerationRequest) -> Result<GenerationResult, LlmError> {
+            *self.sent_paths.lock().expect("mutex poisoned") = request
+                .references
+                .iter()
+                .filter_map(|reference| reference.file.as_ref())
+                .map(|file| file.path.clone())
+                .collect();
+
+            Ok(valid_result(request))
+        }
+    }
+
     struct RoutedCountingProvider {
         provider_id: &'static str,
         model_id: &'static str,
@@ -255,12 +446,20 @@ mod tests {
                 scale: "major".to_string(),
                 density: 3,
                 complexity: 3,
+                syncopation: 3,
+                tick_resolution: TickResolution::DEFAULT,
                 temperature: Some(0.7),
                 top_p: Some(0.9),
                 max_tokens: Some(512),
             },
             references: Vec::new(),
             variation_count: 1,
+            locked_notes: Vec::new(),
+            contract_version: GENERATION_CONTRACT_VERSION,
+            daw_context: None,
+            constraints: None,
+            validation: None,
+            anonymize_references: false,
         }
     }
 
@@ -278,12 +477,94 @@ mod tests {
                     velocity: 100,
                     channel: 1,
                 }],
+                tick_resolution: TickResolution::DEFAULT,
                 score_hint: Some(0.8),
+                title: None,
+                control_events: Vec::new(),
+                processing: None,
             }],
             metadata: GenerationMetadata::default(),
+            contract_version: GENERATION_CONTRACT_VERSION,
         }
     }
 
+    #[test]
+    fn privacy_filter_blocks_or_strips_requests_to_cloud_providers() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let provider = Arc::new(CountingProvider {
+            calls: Arc::clone(&calls),
+            last_ids: Arc::new(Mutex::new(None)),
+        });
+        let mut registry = ProviderRegistry::new();
+        registry
+            .register_shared(provider)
+            .expect("provider registration should succeed");
+        let mut request = valid_request();
+        request.prompt = "melody for ~/Sessions/client-demo".to_string();
+
+        let flagging = GenerationService::new(registry.clone())
+            .with_privacy_filter(Some(PrivacyFilterMode::Flag));
+        let error = flagging
+            .generate(request.clone())
+            .expect_err("flag mode should block identifying text");
+        assert!(matches!(error, LlmError::Validation { .. }));
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        let stripping =
+            GenerationService::new(registry).with_privacy_filter(Some(PrivacyFilterMode::Strip));
+        stripping
+            .generate(request)
+            .expect("strip mode should send the cleaned request");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn anonymized_requests_reach_the_provider_without_reference_paths() {
+        let sent_paths = Arc::new(Mutex::new(Vec::new()));
+        let provider = Arc::new(ReferencePathRecordingProvider {
+            sent_paths: Arc::clone(&sent_paths),
+        });
+        let mut registry = ProviderRegistry::new();
+        registry
+            .register_shared(provider)
+            .expect("provider registration should succeed");
+        let mut request = valid_request();
+        request.anonymize_references = true;
+        request.references = vec![MidiReferenceSummary {
+            slot: ReferenceSlot::Melody,
+            source: ReferenceSource::File,
+            file: Some(FileReferenceInput {
+                path: "/Clients/Acme/Hook Lead.mid".to_string(),
+            }),
+            bars: 4,
+            note_count: 8,
+            density_hint: 0.25,
+            min_pitch: 60,
+            max_pitch: 72,
+            events: Vec::new(),
+            transposition: None,
+            tempo: None,
+        }];
+
+        // Renamed references carry no path, so even flag mode lets the request through.
+        GenerationService::new(registry)
+            .with_privacy_filter(Some(PrivacyFilterMode::Flag))
+            .generate(request.clone())
+            .expect("anonymized request should be sent");
+
+        assert_eq!(
+            *sent_paths.lock().expect("mutex poisoned"),
+            vec!["reference-1.mid".to_string()]
+        );
+        assert_eq!(
+            request.references[0]
+                .file
+                .as_ref()
+                .map(|file| file.path.as_str()),
+            Some("/Clients/Acme/Hook Lead.mid")
+        );
+    }
+
     #[test]
     fn generate_routes_request_to_registry_resolved_provider() {
         let calls = Arc::new(AtomicUsize::new(0));
@@ -382,6 +663,50 @@ mod tests {
         assert_eq!(openai_calls.load(Ordering::SeqCst), 1);
     }
 
+    #[test]
+    fn generate_falls_back_while_requested_provider_is_demoted() {
+        let anthropic_calls = Arc::new(AtomicUsize::new(0));
+        let openai_calls = Arc::new(AtomicUsize::new(0));
+
+        let mut registry = ProviderRegistry::new();
+        registry
+            .register_shared(Arc::new(RoutedCountingProvider {
+                provider_id: "anthropic",
+                model_id: "claude-3-5-sonnet",
+                calls: Arc::clone(&anthropic_calls),
+            }))
+            .expect("anthropic provider registration should succeed");
+        registry
+            .register_shared(Arc::new(RoutedCountingProvider {
+                provider_id: "openai_compatible",
+                model_id: "gpt-4.1",
+                calls: Arc::clone(&openai_calls),
+            }))
+            .expect("openai-compatible provider registration should succeed");
+
+        let error_budget = ProviderErrorBudget::new(ProviderErrorBudgetConfig {
+            min_samples: 1,
+            ..ProviderErrorBudgetConfig::default()
+        });
+        error_budget
+            .record("anthropic", Err(&LlmError::Timeout), Instant::now())
+            .expect("a single failed sample should demote the provider");
+        let service = GenerationService::new(registry)
+            .with_fallback_models(vec![ModelRef {
+                provider: "openai_compatible".to_string(),
+                model: "gpt-4.1".to_string(),
+            }])
+            .with_error_budget(error_budget);
+
+        let result = service
+            .generate(valid_request())
+            .expect("generation should fall back to openai-compatible provider");
+
+        assert_eq!(result.model.provider, "openai_compatible");
+        assert_eq!(anthropic_calls.load(Ordering::SeqCst), 0);
+        assert_eq!(openai_calls.load(Ordering::SeqCst), 1);
+    }
+
     #[test]
     fn generate_returns_error_when_provider_is_missing() {
         let service = GenerationService::new(ProviderRegistry::new());
@@ -446,6 +771,7 @@ mod tests {
                 },
                 candidates: Vec::new(),
                 metadata: GenerationMetadata::default(),
+                contract_version: GENERATION_CONTRACT_VERSION,
             })
         }
     }
@@ -468,12 +794,46 @@ mod tests {
         assert!(matches!(error, LlmError::Validation { .. }));
     }
 
+    #[test]
+    fn generate_rejects_results_that_drop_locked_notes() {
+        let provider = Arc::new(CountingProvider {
+            calls: Arc::new(AtomicUsize::new(0)),
+            last_ids: Arc::new(Mutex::new(None)),
+        });
+        let mut registry = ProviderRegistry::new();
+        registry
+            .register_shared(provider)
+            .expect("provider registration should succeed");
+        let service = GenerationService::new(registry);
+
+        let mut kept = valid_request();
+        kept.locked_notes = valid_result(&kept).candidates[0].notes.clone();
+        service
+            .generate(kept)
+            .expect("candidate keeping the locked note should pass");
+
+        let mut dropped = valid_request();
+        dropped.locked_notes = vec![GeneratedNote {
+            pitch: 72,
+            start_tick: 480,
+            duration_tick: 240,
+            velocity: 100,
+            channel: 1,
+        }];
+        let error = service
+            .generate(dropped)
+            .expect_err("candidate missing the locked note should be rejected");
+
+        assert!(matches!(error, LlmError::InvalidResponse { .. }));
+    }
+
     #[test]
     fn retry_config_backoff_grows_exponentially_and_caps() {
         let config = GenerationRetryConfig {
             max_attempts: 4,
             initial_backoff: Duration::from_millis(10),
             max_backoff: Duration::from_millis(25),
+            ..GenerationRetryConfig::default()
         };
 
         assert_eq!(config.backoff_for_retry(1), Duration::from_millis(10));
@@ -481,6 +841,43 @@ mod tests {
         assert_eq!(config.backoff_for_retry(3), Duration::from_millis(25));
     }
 
+    #[test]
+    fn retry_delay_honors_retry_after_and_applies_jitter() {
+        let config = GenerationRetryConfig {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(400),
+            jitter_percent: 50,
+            max_retry_after: Duration::from_secs(10),
+        };
+        let rate_limited = LlmError::rate_limited(Some(Duration::from_secs(4)));
+
+        assert_eq!(
+            config.delay_for_retry(1, &rate_limited, 0.0),
+            Some(Duration::from_secs(4))
+        );
+        assert_eq!(
+            config.delay_for_retry(1, &rate_limited, 1.0),
+            Some(Duration::from_secs(6))
+        );
+        assert_eq!(
+            config.delay_for_retry(
+                1,
+                &LlmError::rate_limited(Some(Duration::from_secs(30))),
+                0.0
+            ),
+            None
+        );
+        assert_eq!(
+            config.delay_for_retry(2, &LlmError::Timeout, 1.0),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            config.delay_for_retry(2, &LlmError::rate_limited(None), 0.0),
+            Some(Duration::from_millis(200))
+        );
+    }
+
     #[test]
     fn retry_config_validation_rejects_invalid_ranges() {
         let invalid_attempts = GenerationRetryConfig {
@@ -497,6 +894,7 @@ mod tests {
             max_attempts: 3,
             initial_backoff: Duration::from_millis(30),
             max_backoff: Duration::from_millis(20),
+            ..GenerationRetryConfig::default()
         };
         assert!(matches!(
             invalid_backoff.validate(),
@@ -523,6 +921,8 @@ mod tests {
             max_attempts: 3,
             initial_backoff: Duration::from_millis(20),
             max_backoff: Duration::from_millis(80),
+
+            ..GenerationRetryConfig::default()
         };
         let service = GenerationService::with_retry_config(registry, retry_config)
             .expect("retry config should be valid");
@@ -569,7 +969,7 @@ mod tests {
         let provider = Arc::new(RetryControlledProvider {
             calls: Arc::clone(&calls),
             failures_before_success: usize::MAX,
-            failure_error: LlmError::RateLimited,
+            failure_error: LlmError::rate_limited(None),
         });
 
         let mut registry = ProviderRegistry::new();
@@ -581,6 +981,8 @@ mod tests {
             max_attempts: 3,
             initial_backoff: Duration::from_millis(0),
             max_backoff: Duration::from_millis(0),
+
+            ..GenerationRetryConfig::default()
         };
         let service = GenerationService::with_retry_config(registry, retry_config)
             .expect("retry config should be valid");
@@ -589,7 +991,7 @@ mod tests {
             .generate(valid_request())
             .expect_err("retryable error should bubble up after max attempts");
 
-        assert!(matches!(error, LlmError::RateLimited));
+        assert!(matches!(error, LlmError::RateLimited { .. }));
         assert_eq!(calls.load(Ordering::SeqCst), 3);
     }
 
@@ -638,6 +1040,8 @@ mod tests {
             max_attempts: 5,
             initial_backoff: Duration::from_millis(400),
             max_backoff: Duration::from_millis(400),
+
+            ..GenerationRetryConfig::default()
         };
         let service = GenerationService::with_retry_config(registry, retry_config)
             .expect("retry config should be valid");
@@ -0,0 +1,441 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::app::{AppliedClip, InputTrackLayout};
+use crate::domain::ModelRef;
+
+pub const HELPER_CONTROL_IPC_SOCKET_ENV: &str = "SONANT_HELPER_CONTROL_SOCKET_PATH";
+pub const INPUT_TRACK_LAYOUT_ENV: &str = "SONANT_INPUT_TRACK_LAYOUT";
+/// JSON `ModelRef` the project prefers over the global default model.
+pub const PROJECT_MODEL_ENV: &str = "SONANT_PROJECT_MODEL";
+/// Initial helper window size in logical pixels, formatted as `WIDTHxHEIGHT`.
+pub const HELPER_WINDOW_SIZE_ENV: &str = "SONANT_HELPER_WINDOW_SIZE";
+pub const HELPER_WINDOW_MIN_WIDTH: u32 = 640;
+pub const HELPER_WINDOW_MIN_HEIGHT: u32 = 480;
+
+/// Messages sent from the GUI helper back to the plugin process.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HelperControlMessage {
+    InputTrackLayout {
+        layout: InputTrackLayout,
+    },
+    /// 1-based MIDI channels whose live input is echoed to the plugin output.
+    MidiThruChannels {
+        channels: Vec<u8>,
+    },
+    /// Replaces the clip looped on the plugin output; `None` stops playback.
+    AppliedClip {
+        clip: Option<AppliedClip>,
+    },
+    /// Model saved with the DAW project; `None` falls back to the global default.
+    ProjectModel {
+        model: Option<ModelRef>,
+    },
+    /// The helper window is closing; the plugin stops relaying input to it.
+    HelperClosing,
+}
+
+/// Why a message did not reach the plugin.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum HelperControlSendError {
+    #[error("failed to encode the message for the plugin: {message}")]
+    Encode { message: String },
+    #[error("the message is {size} bytes, more than the plugin accepts")]
+    TooLarge { size: usize },
+    #[error("the plugin is not reading messages ({pending_bytes} bytes are waiting)")]
+    Backlog { pending_bytes: usize },
+    #[error("the plugin connection is closed: {message}")]
+    Disconnected { message: String },
+    #[error("helper control IPC is only supported on unix targets")]
+    Unsupported,
+}
+
+/// Parses a `WIDTHxHEIGHT` logical size, clamped to the minimum helper window size.
+pub fn parse_helper_window_size(value: &str) -> Option<(u32, u32)> {
+    let (width, height) = value.trim().split_once('x')?;
+    let width = width.trim().parse::<u32>().ok()?;
+    let height = height.trim().parse::<u32>().ok()?;
+    Some((
+        width.max(HELPER_WINDOW_MIN_WIDTH),
+        height.max(HELPER_WINDOW_MIN_HEIGHT),
+    ))
+}
+
+#[cfg(target_family = "unix")]
+mod platform {
+    use std::io::{ErrorKind, Read, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::{Path, PathBuf};
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+
+    use super::{HelperControlMessage, HelperControlSendError};
+
+    // Each message is framed as a little-endian u32 length followed by its JSON.
+    const FRAME_HEADER_LEN: usize = 4;
+    const HELPER_CONTROL_IPC_MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+    // Bytes the sender holds while the plugin is not reading before it refuses new messages.
+    const HELPER_CONTROL_IPC_MAX_PENDING: usize = 32 * 1024 * 1024;
+    const READ_CHUNK_SIZE: usize = 16 * 1024;
+    const FINISH_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+    /// Sends over a stream socket rather than datagrams: an applied clip easily outgrows the
+    /// datagram limit, which is about 2 KB on macOS. Bytes the socket does not take at once are
+    /// held and written by later calls to `send` or `flush`, so sending never blocks.
+    pub struct HelperControlIpcSender {
+        stream: UnixStream,
+        pending: Mutex<Vec<u8>>,
+    }
+
+    impl HelperControlIpcSender {
+        pub fn new(target_path: impl AsRef<Path>) -> std::io::Result<Self> {
+            let stream = UnixStream::connect(target_path)?;
+            stream.set_nonblocking(true)?;
+            Ok(Self {
+                stream,
+                pending: Mutex::new(Vec::new()),
+            })
+        }
+
+        pub fn send(&self, message: &HelperControlMessage) -> Result<(), HelperControlSendError> {
+            let payload =
+                serde_json::to_vec(message).map_err(|error| HelperControlSendError::Encode {
+                    message: error.to_string(),
+                })?;
+            if payload.len() > HELPER_CONTROL_IPC_MAX_FRAME_SIZE {
+                return Err(HelperControlSendError::TooLarge {
+                    size: payload.len(),
+                });
+            }
+            let mut pending = self
+                .pending
+                .lock()
+                .expect("helper control pending lock poisoned while sending");
+            if pending.len() + FRAME_HEADER_LEN + payload.len() > HELPER_CONTROL_IPC_MAX_PENDING {
+                return Err(HelperControlSendError::Backlog {
+                    pending_bytes: pending.len(),
+                });
+            }
+            pending.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            pending.extend_from_slice(&payload);
+            write_pending(&self.stream, &mut pending)
+        }
+
+        /// Writes what the socket takes of the held bytes and reports whether none are left.
+        pub fn flush(&self) -> Result<bool, HelperControlSendError> {
+            let mut pending = self
+                .pending
+                .lock()
+                .expect("helper control pending lock poisoned while flushing");
+            write_pending(&self.stream, &mut pending)?;
+            Ok(pending.is_empty())
+        }
+
+        /// Flushes until every held byte is written, for a caller about to exit.
+        pub fn finish(&self, timeout: Duration) -> Result<(), HelperControlSendError> {
+            let deadline = Instant::now() + timeout;
+            while !self.flush()? {
+                if Instant::now() >= deadline {
+                    return Err(HelperControlSendError::Backlog {
+                        pending_bytes: self
+                            .pending
+                            .lock()
+                            .expect("helper control pending lock poisoned while finishing")
+                            .len(),
+                    });
+                }
+                std::thread::sleep(FINISH_POLL_INTERVAL);
+            }
+            Ok(())
+        }
+    }
+
+    /// Writes until the socket would block. Nothing can reach a closed connection, so its
+    /// held bytes are dropped rather than reported again on every flush.
+    fn write_pending(
+        mut stream: &UnixStream,
+        pending: &mut Vec<u8>,
+    ) -> Result<(), HelperControlSendError> {
+        let mut written = 0;
+        while written < pending.len() {
+            match stream.write(&pending[written..]) {
+                Ok(0) => {
+                    pending.clear();
+                    return Err(HelperControlSendError::Disconnected {
+                        message: "the plugin closed the connection".to_string(),
+                    });
+                }
+                Ok(count) => written += count,
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(error) => {
+                    pending.clear();
+                    return Err(HelperControlSendError::Disconnected {
+                        message: error.to_string(),
+                    });
+                }
+            }
+        }
+        pending.drain(..written);
+        Ok(())
+    }
+
+    pub struct HelperControlIpcSource {
+        listener: UnixListener,
+        socket_path: PathBuf,
+        connections: Vec<Connection>,
+    }
+
+    struct Connection {
+        stream: UnixStream,
+        // Bytes read but not yet taken as whole frames.
+        buffer: Vec<u8>,
+        closed: bool,
+    }
+
+    impl HelperControlIpcSource {
+        pub fn bind(socket_path: impl AsRef<Path>) -> std::io::Result<Self> {
+            let socket_path = socket_path.as_ref().to_path_buf();
+            if socket_path.exists() {
+                let _ = std::fs::remove_file(&socket_path);
+            }
+            let listener = UnixListener::bind(&socket_path)?;
+            listener.set_nonblocking(true)?;
+            Ok(Self {
+                listener,
+                socket_path,
+                connections: Vec::new(),
+            })
+        }
+
+        pub fn try_recv(&mut self) -> Option<HelperControlMessage> {
+            while let Ok((stream, _)) = self.listener.accept() {
+                if stream.set_nonblocking(true).is_ok() {
+                    self.connections.push(Connection {
+                        stream,
+                        buffer: Vec::new(),
+                        closed: false,
+                    });
+                }
+            }
+            let mut message = None;
+            for connection in &mut self.connections {
+                connection.read_available();
+                message = message.or_else(|| connection.next_message());
+            }
+            self.connections
+                .retain_mut(|connection| !connection.closed || connection.frame_len().is_some());
+            message
+        }
+    }
+
+    impl Connection {
+        fn read_available(&mut self) {
+            let mut chunk = [0u8; READ_CHUNK_SIZE];
+            while !self.closed {
+                match self.stream.read(&mut chunk) {
+                    Ok(0) => self.closed = true,
+                    Ok(count) => self.buffer.extend_from_slice(&chunk[..count]),
+                    Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                    Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                    Err(_) => self.closed = true,
+                }
+            }
+        }
+
+        fn next_message(&mut self) -> Option<HelperControlMessage> {
+            while let Some(payload_len) = self.frame_len() {
+                let frame = self
+                    .buffer
+                    .drain(..FRAME_HEADER_LEN + payload_len)
+                    .collect::<Vec<_>>();
+                // Skip malformed messages instead of stalling the stream behind them.
+                if let Ok(message) = serde_json::from_slice(&frame[FRAME_HEADER_LEN..]) {
+                    return Some(message);
+                }
+            }
+            None
+        }
+
+        /// Payload length of the first frame once all of it has arrived. A length no sender
+        /// writes means the stream is out of step, so the connection is dropped.
+        fn frame_len(&mut self) -> Option<usize> {
+            let header = self.buffer.get(..FRAME_HEADER_LEN)?;
+            let payload_len =
+                u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+            if payload_len > HELPER_CONTROL_IPC_MAX_FRAME_SIZE {
+                self.buffer.clear();
+                self.closed = true;
+                return None;
+            }
+            (self.buffer.len() >= FRAME_HEADER_LEN + payload_len).then_some(payload_len)
+        }
+    }
+
+    impl Drop for HelperControlIpcSource {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.socket_path);
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{HelperControlIpcSender, HelperControlIpcSource};
+        use crate::app::{
+            AppliedClip, AppliedClipEvent, ChannelMapping, HelperControlMessage, InputTrackLayout,
+        };
+        use crate::domain::ReferenceSlot;
+        use crate::test_support::TestDir;
+        use std::io::Write;
+        use std::os::unix::net::UnixStream;
+
+        fn receive(
+            source: &mut HelperControlIpcSource,
+            sender: &HelperControlIpcSender,
+        ) -> Option<HelperControlMessage> {
+            // The socket may take a large message in several writes.
+            for _ in 0..1_000 {
+                sender.flush().expect("flush should succeed");
+                if let Some(message) = source.try_recv() {
+                    return Some(message);
+                }
+            }
+            None
+        }
+
+        #[test]
+        fn sender_to_source_round_trip_delivers_layout_message() {
+            let dir = TestDir::new("control-ipc");
+            let socket_path = dir.join("control.sock");
+            let mut source =
+                HelperControlIpcSource::bind(&socket_path).expect("bind should succeed");
+            let sender =
+                HelperControlIpcSender::new(&socket_path).expect("sender should initialize");
+            let message = HelperControlMessage::InputTrackLayout {
+                layout: InputTrackLayout {
+                    rows: vec![ReferenceSlot::Melody, ReferenceSlot::Bassline],
+                    slot_sources: Vec::new(),
+                    channel_mappings: vec![ChannelMapping {
+                        slot: ReferenceSlot::Melody,
+                        channel: 5,
+                    }],
+                },
+            };
+
+            sender.send(&message).expect("send should succeed");
+
+            assert_eq!(receive(&mut source, &sender), Some(message));
+            assert_eq!(source.try_recv(), None);
+        }
+
+        #[test]
+        fn clips_larger_than_a_datagram_arrive_whole_and_in_order() {
+            let dir = TestDir::new("control-ipc");
+            let socket_path = dir.join("control.sock");
+            let mut source =
+                HelperControlIpcSource::bind(&socket_path).expect("bind should succeed");
+            let sender =
+                HelperControlIpcSender::new(&socket_path).expect("sender should initialize");
+            let clip = AppliedClip {
+                length_beats: 64.0,
+                events: (0..2_000)
+                    .map(|index| AppliedClipEvent {
+                        beat: f64::from(index) / 32.0,
+                        data: [0x90, 36 + (index % 48) as u8, 100],
+                        expression: None,
+                        choke: false,
+                    })
+                    .collect(),
+                note_expressions: false,
+            };
+            let applied = HelperControlMessage::AppliedClip { clip: Some(clip) };
+            assert!(
+                serde_json::to_vec(&applied)
+                    .expect("clip should encode")
+                    .len()
+                    > 64 * 1024
+            );
+
+            sender.send(&applied).expect("send should succeed");
+            sender
+                .send(&HelperControlMessage::HelperClosing)
+                .expect("send should succeed");
+
+            assert_eq!(receive(&mut source, &sender), Some(applied));
+            assert_eq!(
+                receive(&mut source, &sender),
+                Some(HelperControlMessage::HelperClosing)
+            );
+        }
+
+        #[test]
+        fn source_skips_malformed_frames() {
+            let dir = TestDir::new("control-ipc");
+            let socket_path = dir.join("control.sock");
+            let mut source =
+                HelperControlIpcSource::bind(&socket_path).expect("bind should succeed");
+            let mut raw = UnixStream::connect(&socket_path).expect("socket should connect");
+            let garbage = b"not json";
+            let valid = serde_json::to_vec(&HelperControlMessage::HelperClosing)
+                .expect("message should encode");
+            for payload in [&garbage[..], &valid[..]] {
+                raw.write_all(&(payload.len() as u32).to_le_bytes())
+                    .expect("header should be written");
+                raw.write_all(payload).expect("payload should be written");
+            }
+
+            assert_eq!(source.try_recv(), Some(HelperControlMessage::HelperClosing));
+            assert_eq!(source.try_recv(), None);
+        }
+    }
+}
+
+#[cfg(not(target_family = "unix"))]
+mod platform {
+    use std::io::{Error, ErrorKind};
+    use std::path::Path;
+    use std::time::Duration;
+
+    use super::{HelperControlMessage, HelperControlSendError};
+
+    pub struct HelperControlIpcSender;
+
+    impl HelperControlIpcSender {
+        pub fn new(_target_path: impl AsRef<Path>) -> std::io::Result<Self> {
+            Err(Error::new(
+                ErrorKind::Unsupported,
+                "helper control IPC is only supported on unix targets",
+            ))
+        }
+
+        pub fn send(&self, _message: &HelperControlMessage) -> Result<(), HelperControlSendError> {
+            Err(HelperControlSendError::Unsupported)
+        }
+
+        pub fn flush(&self) -> Result<bool, HelperControlSendError> {
+            Ok(true)
+        }
+
+        pub fn finish(&self, _timeout: Duration) -> Result<(), HelperControlSendError> {
+            Ok(())
+        }
+    }
+
+    pub struct HelperControlIpcSource;
+
+    impl HelperControlIpcSource {
+        pub fn bind(_socket_path: impl AsRef<Path>) -> std::io::Result<Self> {
+            Err(Error::new(
+                ErrorKind::Unsupported,
+                "helper control IPC is only supported on unix targets",
+            ))
+        }
+
+        pub fn try_recv(&mut self) -> Option<HelperControlMessage> {
+            None
+        }
+    }
+}
+
+pub use platform::{HelperControlIpcSender, HelperControlIpcSource};
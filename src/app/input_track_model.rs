@@ -1,5 +1,6 @@
 use std::collections::{HashMap, HashSet};
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::domain::{ReferenceSlot, ReferenceSource};
@@ -7,7 +8,7 @@ use crate::domain::{ReferenceSlot, ReferenceSource};
 pub const MIDI_CHANNEL_MIN: u8 = 1;
 pub const MIDI_CHANNEL_MAX: u8 = 16;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ChannelMapping {
     pub slot: ReferenceSlot,
     pub channel: u8,
@@ -43,6 +44,23 @@ pub enum InputTrackModelError {
     },
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlotSourceAssignment {
+    pub slot: ReferenceSlot,
+    pub source: ReferenceSource,
+}
+
+/// Serializable snapshot of the Input Tracks section, persisted through plugin state.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct InputTrackLayout {
+    #[serde(default)]
+    pub rows: Vec<ReferenceSlot>,
+    #[serde(default)]
+    pub slot_sources: Vec<SlotSourceAssignment>,
+    #[serde(default)]
+    pub channel_mappings: Vec<ChannelMapping>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct InputTrackModel {
     slot_sources: HashMap<ReferenceSlot, ReferenceSource>,
@@ -115,6 +133,45 @@ impl InputTrackModel {
     pub fn validate(&self) -> Result<(), InputTrackModelError> {
         validate_channel_mappings(&self.slot_sources, &self.channel_mappings)
     }
+
+    pub fn from_layout(layout: &InputTrackLayout) -> Result<Self, InputTrackModelError> {
+        let mut slot_sources = HashMap::new();
+        for assignment in &layout.slot_sources {
+            set_slot_source(&mut slot_sources, assignment.slot, assignment.source);
+        }
+
+        let model = Self {
+            slot_sources,
+            channel_mappings: layout.channel_mappings.clone(),
+        };
+        model.validate()?;
+        Ok(model)
+    }
+
+    /// Replaces sources and channel mappings in one step; on error the model is left untouched.
+    pub fn apply_layout(&mut self, layout: &InputTrackLayout) -> Result<(), InputTrackModelError> {
+        *self = Self::from_layout(layout)?;
+        Ok(())
+    }
+
+    pub fn to_layout(&self, rows: &[ReferenceSlot]) -> InputTrackLayout {
+        let mut slot_sources = self
+            .slot_sources
+            .iter()
+            .map(|(slot, source)| SlotSourceAssignment {
+                slot: *slot,
+                source: *source,
+            })
+            .collect::<Vec<_>>();
+        // HashMap iteration order is unstable; keep the persisted payload deterministic.
+        slot_sources.sort_by_key(|assignment| assignment.slot as u8);
+
+        InputTrackLayout {
+            rows: rows.to_vec(),
+            slot_sources,
+            channel_mappings: self.channel_mappings.clone(),
+        }
+    }
 }
 
 impl Default for InputTrackModel {
@@ -147,6 +204,17 @@ pub fn default_live_channel_mappings() -> Vec<ChannelMapping> {
     ]
 }
 
+/// Checks a default mapping table as if every mapped slot were live at once.
+pub fn validate_default_channel_mappings(
+    channel_mappings: &[ChannelMapping],
+) -> Result<(), InputTrackModelError> {
+    let all_live = channel_mappings
+        .iter()
+        .map(|mapping| (mapping.slot, ReferenceSource::Live))
+        .collect::<HashMap<_, _>>();
+    validate_channel_mappings(&all_live, channel_mappings)
+}
+
 fn validate_channel_mappings(
     slot_sources: &HashMap<ReferenceSlot, ReferenceSource>,
     channel_mappings: &[ChannelMapping],
@@ -204,7 +272,8 @@ fn set_slot_source(
 #[cfg(test)]
 mod tests {
     use super::{
-        ChannelMapping, InputTrackModel, InputTrackModelError, default_live_channel_mappings,
+        ChannelMapping, InputTrackLayout, InputTrackModel, InputTrackModelError,
+        SlotSourceAssignment, default_live_channel_mappings, validate_default_channel_mappings,
     };
     use crate::domain::{ReferenceSlot, ReferenceSource};
 
@@ -382,4 +451,126 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn layout_round_trip_restores_sources_and_channel_mappings() {
+        let mut model = InputTrackModel::new();
+        model
+            .set_source_for_slot(ReferenceSlot::DrumPattern, ReferenceSource::Live)
+            .expect("source update should succeed");
+        model
+            .set_channel_mapping(ChannelMapping {
+                slot: ReferenceSlot::DrumPattern,
+                channel: 11,
+            })
+            .expect("channel update should succeed");
+        let rows = vec![
+            ReferenceSlot::Melody,
+            ReferenceSlot::DrumPattern,
+            ReferenceSlot::Melody,
+        ];
+
+        let layout = model.to_layout(&rows);
+        let json = serde_json::to_string(&layout).expect("layout should serialize");
+        let decoded: InputTrackLayout =
+            serde_json::from_str(&json).expect("layout should deserialize");
+        let restored = InputTrackModel::from_layout(&decoded).expect("layout should be valid");
+
+        assert_eq!(decoded.rows, rows);
+        assert_eq!(
+            decoded.slot_sources,
+            vec![SlotSourceAssignment {
+                slot: ReferenceSlot::DrumPattern,
+                source: ReferenceSource::Live,
+            }]
+        );
+        assert_eq!(restored, model);
+    }
+
+    #[test]
+    fn layout_with_conflicting_live_channels_is_rejected() {
+        let layout = InputTrackLayout {
+            rows: vec![ReferenceSlot::Melody, ReferenceSlot::Bassline],
+            slot_sources: vec![
+                SlotSourceAssignment {
+                    slot: ReferenceSlot::Melody,
+                    source: ReferenceSource::Live,
+                },
+                SlotSourceAssignment {
+                    slot: ReferenceSlot::Bassline,
+                    source: ReferenceSource::Live,
+                },
+            ],
+            channel_mappings: vec![
+                ChannelMapping {
+                    slot: ReferenceSlot::Melody,
+                    channel: 4,
+                },
+                ChannelMapping {
+                    slot: ReferenceSlot::Bassline,
+                    channel: 4,
+                },
+            ],
+        };
+
+        let error = InputTrackModel::from_layout(&layout)
+            .expect_err("conflicting live channels should be rejected");
+
+        assert_eq!(
+            error,
+            InputTrackModelError::DuplicateLiveChannel {
+                channel: 4,
+                existing_slot: ReferenceSlot::Melody,
+                conflicting_slot: ReferenceSlot::Bassline,
+            }
+        );
+    }
+
+    #[test]
+    fn apply_layout_keeps_current_state_when_layout_is_invalid() {
+        let mut model = InputTrackModel::new();
+        model
+            .set_source_for_slot(ReferenceSlot::Melody, ReferenceSource::Live)
+            .expect("melody should switch to live");
+        let before = model.clone();
+        let invalid = InputTrackLayout {
+            rows: vec![ReferenceSlot::Melody],
+            slot_sources: Vec::new(),
+            channel_mappings: vec![ChannelMapping {
+                slot: ReferenceSlot::Melody,
+                channel: 17,
+            }],
+        };
+
+        assert!(model.apply_layout(&invalid).is_err());
+        assert_eq!(model, before);
+    }
+
+    #[test]
+    fn default_channel_mappings_reject_shared_channels_regardless_of_source() {
+        assert_eq!(
+            validate_default_channel_mappings(&default_live_channel_mappings()),
+            Ok(())
+        );
+
+        let conflicting = vec![
+            ChannelMapping {
+                slot: ReferenceSlot::Melody,
+                channel: 2,
+            },
+            ChannelMapping {
+                slot: ReferenceSlot::ChordProgression,
+                channel: 2,
+            },
+        ];
+
+        assert_eq!(
+            validate_default_channel_mappings(&conflicting),
+            Err(InputTrackModelError::DuplicateLiveChannel {
+                channel: 2,
+                existing_slot: ReferenceSlot::Melody,
+                conflicting_slot: ReferenceSlot::ChordProgression,
+            })
+        );
+    }
 }
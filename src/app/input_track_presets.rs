@@ -0,0 +1,327 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::app::{
+    ChannelMapping, InputTrackLayout, InputTrackModel, InputTrackModelError, SlotSourceAssignment,
+    default_live_channel_mappings, sonant_config_dir,
+};
+use crate::domain::{ReferenceSlot, ReferenceSource};
+
+pub(crate) const INPUT_TRACK_PRESETS_FILE_NAME: &str = "input_track_presets.json";
+const PRESET_NAME_MAX_CHARS: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputTrackPreset {
+    pub name: String,
+    pub layout: InputTrackLayout,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum InputTrackPresetError {
+    #[error("preset name must not be empty")]
+    EmptyName,
+    #[error("preset name must be at most {PRESET_NAME_MAX_CHARS} characters")]
+    NameTooLong,
+    #[error("preset '{name}' was not found")]
+    NotFound { name: String },
+    #[error("preset '{name}' is invalid: {source}")]
+    InvalidLayout {
+        name: String,
+        source: InputTrackModelError,
+    },
+    #[error("failed to access preset file: {message}")]
+    Io { message: String },
+    #[error("failed to parse preset file: {message}")]
+    Parse { message: String },
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct InputTrackPresetFile {
+    #[serde(default)]
+    presets: Vec<InputTrackPreset>,
+}
+
+#[derive(Debug, Clone)]
+pub struct InputTrackPresetStore {
+    path: Option<PathBuf>,
+    presets: Vec<InputTrackPreset>,
+}
+
+impl InputTrackPresetStore {
+    pub fn open_default() -> Result<Self, InputTrackPresetError> {
+        match sonant_config_dir() {
+            Some(dir) => Self::open(dir.join(INPUT_TRACK_PRESETS_FILE_NAME)),
+            None => Ok(Self::in_memory()),
+        }
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, InputTrackPresetError> {
+        let path = path.as_ref().to_path_buf();
+        let presets = match std::fs::read(&path) {
+            Ok(bytes) => {
+                let file: InputTrackPresetFile =
+                    serde_json::from_slice(&bytes).map_err(|error| {
+                        InputTrackPresetError::Parse {
+                            message: error.to_string(),
+                        }
+                    })?;
+                file.presets
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => builtin_presets(),
+            Err(error) => {
+                return Err(InputTrackPresetError::Io {
+                    message: error.to_string(),
+                });
+            }
+        };
+
+        Ok(Self {
+            path: Some(path),
+            presets,
+        })
+    }
+
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            presets: builtin_presets(),
+        }
+    }
+
+    pub fn presets(&self) -> &[InputTrackPreset] {
+        &self.presets
+    }
+
+    pub fn preset(&self, name: &str) -> Option<&InputTrackPreset> {
+        self.presets.iter().find(|preset| preset.name == name)
+    }
+
+    /// Returns the preset's layout once it has been validated against `InputTrackModel`.
+    pub fn resolve(&self, name: &str) -> Result<InputTrackLayout, InputTrackPresetError> {
+        let preset = self
+            .preset(name)
+            .ok_or_else(|| InputTrackPresetError::NotFound {
+                name: name.to_string(),
+            })?;
+        InputTrackModel::from_layout(&preset.layout).map_err(|source| {
+            InputTrackPresetError::InvalidLayout {
+                name: preset.name.clone(),
+                source,
+            }
+        })?;
+        Ok(preset.layout.clone())
+    }
+
+    pub fn save_preset(
+        &mut self,
+        name: &str,
+        layout: InputTrackLayout,
+    ) -> Result<(), InputTrackPresetError> {
+        let name = normalize_preset_name(name)?;
+        InputTrackModel::from_layout(&layout).map_err(|source| {
+            InputTrackPresetError::InvalidLayout {
+                name: name.clone(),
+                source,
+            }
+        })?;
+
+        let mut next = self.presets.clone();
+        if let Some(existing) = next.iter_mut().find(|preset| preset.name == name) {
+            existing.layout = layout;
+        } else {
+            next.push(InputTrackPreset { name, layout });
+        }
+
+        self.persist(&next)?;
+        self.presets = next;
+        Ok(())
+    }
+
+    pub fn remove_preset(&mut self, name: &str) -> Result<bool, InputTrackPresetError> {
+        let mut next = self.presets.clone();
+        let before = next.len();
+        next.retain(|preset| preset.name != name);
+        if next.len() == before {
+            return Ok(false);
+        }
+
+        self.persist(&next)?;
+        self.presets = next;
+        Ok(true)
+    }
+
+    fn persist(&self, presets: &[InputTrackPreset]) -> Result<(), InputTrackPresetError> {
+        let Some(path) = self.path.as_ref() else {
+            return Ok(());
+        };
+
+        let payload = serde_json::to_vec_pretty(&InputTrackPresetFile {
+            presets: presets.to_vec(),
+        })
+        .map_err(|error| InputTrackPresetError::Parse {
+            message: error.to_string(),
+        })?;
+        write_file_atomically(path, &payload).map_err(|error| InputTrackPresetError::Io {
+            message: error.to_string(),
+        })
+    }
+}
+
+pub fn builtin_presets() -> Vec<InputTrackPreset> {
+    let live = |slot| SlotSourceAssignment {
+        slot,
+        source: ReferenceSource::Live,
+    };
+
+    vec![
+        InputTrackPreset {
+            name: "Band jam".to_string(),
+            layout: InputTrackLayout {
+                rows: vec![
+                    ReferenceSlot::Melody,
+                    ReferenceSlot::ChordProgression,
+                    ReferenceSlot::DrumPattern,
+                    ReferenceSlot::Bassline,
+                ],
+                slot_sources: vec![
+                    live(ReferenceSlot::Melody),
+                    live(ReferenceSlot::ChordProgression),
+                    live(ReferenceSlot::DrumPattern),
+                    live(ReferenceSlot::Bassline),
+                ],
+                channel_mappings: default_live_channel_mappings(),
+            },
+        },
+        InputTrackPreset {
+            name: "Solo keys".to_string(),
+            layout: InputTrackLayout {
+                rows: vec![ReferenceSlot::Melody, ReferenceSlot::ChordProgression],
+                slot_sources: vec![live(ReferenceSlot::Melody)],
+                channel_mappings: vec![ChannelMapping {
+                    slot: ReferenceSlot::Melody,
+                    channel: 1,
+                }],
+            },
+        },
+    ]
+}
+
+fn normalize_preset_name(name: &str) -> Result<String, InputTrackPresetError> {
+    let trimmed = name.trim();
+    if trimmed.is_empty() {
+        return Err(InputTrackPresetError::EmptyName);
+    }
+    if trimmed.chars().count() > PRESET_NAME_MAX_CHARS {
+        return Err(InputTrackPresetError::NameTooLong);
+    }
+    Ok(trimmed.to_string())
+}
+
+pub(crate) fn write_file_atomically(path: &Path, payload: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let temp_path = path.with_extension("tmp");
+    std::fs::write(&temp_path, payload)?;
+    std::fs::rename(&temp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InputTrackPresetError, InputTrackPresetStore, builtin_presets};
+    use crate::app::{
+        ChannelMapping, InputTrackLayout, InputTrackModelError, SlotSourceAssignment,
+    };
+    use crate::domain::{ReferenceSlot, ReferenceSource};
+    use crate::test_support::TestDir;
+
+    #[test]
+    fn builtin_presets_are_valid_layouts() {
+        let store = InputTrackPresetStore::in_memory();
+
+        for preset in builtin_presets() {
+            store
+                .resolve(&preset.name)
+                .expect("built-in preset should resolve to a valid model");
+        }
+        assert!(store.preset("Band jam").is_some());
+        assert!(store.preset("Solo keys").is_some());
+    }
+
+    #[test]
+    fn saved_preset_is_persisted_and_reloaded() {
+        let dir = TestDir::new("presets");
+        let path = dir.join("input_track_presets.json");
+        let mut store = InputTrackPresetStore::open(&path).expect("store should open");
+        let layout = InputTrackLayout {
+            rows: vec![ReferenceSlot::Bassline],
+            slot_sources: vec![SlotSourceAssignment {
+                slot: ReferenceSlot::Bassline,
+                source: ReferenceSource::Live,
+            }],
+            channel_mappings: vec![ChannelMapping {
+                slot: ReferenceSlot::Bassline,
+                channel: 6,
+            }],
+        };
+
+        store
+            .save_preset("  Bass practice ", layout.clone())
+            .expect("preset should be saved");
+        let reloaded = InputTrackPresetStore::open(&path).expect("store should reload");
+
+        assert_eq!(
+            reloaded
+                .preset("Bass practice")
+                .map(|preset| preset.layout.clone()),
+            Some(layout)
+        );
+    }
+
+    #[test]
+    fn invalid_layout_is_rejected_without_changing_presets() {
+        let mut store = InputTrackPresetStore::in_memory();
+        let before = store.presets().to_vec();
+        let layout = InputTrackLayout {
+            rows: vec![ReferenceSlot::Melody],
+            slot_sources: Vec::new(),
+            channel_mappings: vec![ChannelMapping {
+                slot: ReferenceSlot::Melody,
+                channel: 0,
+            }],
+        };
+
+        let error = store
+            .save_preset("Broken", layout)
+            .expect_err("invalid channel should be rejected");
+
+        assert_eq!(
+            error,
+            InputTrackPresetError::InvalidLayout {
+                name: "Broken".to_string(),
+                source: InputTrackModelError::ChannelOutOfRange {
+                    slot: ReferenceSlot::Melody,
+                    channel: 0,
+                },
+            }
+        );
+        assert_eq!(store.presets(), before.as_slice());
+    }
+
+    #[test]
+    fn empty_name_and_unknown_preset_are_reported() {
+        let mut store = InputTrackPresetStore::in_memory();
+
+        assert_eq!(
+            store.save_preset("   ", InputTrackLayout::default()),
+            Err(InputTrackPresetError::EmptyName)
+        );
+        assert!(matches!(
+            store.resolve("Missing"),
+            Err(InputTrackPresetError::NotFound { name }) if name == "Missing"
+        ));
+        assert_eq!(store.remove_preset("Missing"), Ok(false));
+    }
+}
@@ -2,13 +2,37 @@ pub const LIVE_INPUT_IPC_SOCKET_ENV: &str = "SONANT_LIVE_INPUT_SOCKET_PATH";
 
 #[cfg(target_family = "unix")]
 mod platform {
+    use std::collections::VecDeque;
     use std::io::ErrorKind;
     use std::os::unix::net::UnixDatagram;
     use std::path::{Path, PathBuf};
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicBool, Ordering};
 
-    use crate::app::{LiveInputEvent, LiveInputEventSource};
+    use crate::app::{
+        HostTransportContext, LiveInputEvent, LiveInputEventSource, QueueOverflowMetrics,
+    };
 
     const LIVE_INPUT_IPC_PACKET_SIZE: usize = 18;
+    // Overflow counters and host context share the socket; their distinct sizes tell the
+    // packets apart.
+    const OVERFLOW_METRICS_PACKET_SIZE: usize = 24;
+    const HOST_CONTEXT_PACKET_SIZE: usize = 20;
+    // A batch is a 4-byte header (tag, reserved, little-endian count) followed by packed
+    // 18-byte event records, so its size is never 18, 20, or 24.
+    const EVENT_BATCH_TAG: u8 = 0xB5;
+    const EVENT_BATCH_HEADER_SIZE: usize = 4;
+    const EVENT_BATCH_MAX_EVENTS: usize = 64;
+    const EVENT_BATCH_MAX_SIZE: usize =
+        EVENT_BATCH_HEADER_SIZE + EVENT_BATCH_MAX_EVENTS * LIVE_INPUT_IPC_PACKET_SIZE;
+    // A host hit on the Generate trigger: the tag followed by a reserved byte.
+    const GENERATE_TRIGGER_TAG: u8 = 0xC6;
+    const GENERATE_TRIGGER_PACKET_SIZE: usize = 2;
+    // The host's name for the plugin's track or clip: the tag, the name's length, and the
+    // UTF-8 name zero-padded to a fixed size so the packet is told apart by size too.
+    const HOST_TRACK_NAME_TAG: u8 = 0xD7;
+    const HOST_TRACK_NAME_MAX_BYTES: usize = 64;
+    const HOST_TRACK_NAME_PACKET_SIZE: usize = 2 + HOST_TRACK_NAME_MAX_BYTES;
 
     pub struct LiveInputIpcSender {
         socket: UnixDatagram,
@@ -26,20 +50,65 @@ mod platform {
         }
 
         pub fn send_event(&self, event: LiveInputEvent) {
-            let payload = encode_live_input_event(event);
+            let mut payload = [0u8; LIVE_INPUT_IPC_PACKET_SIZE];
+            encode_live_input_event(event, &mut payload);
             let _ = self.socket.send_to(&payload, &self.target_path);
         }
 
+        /// Packs events into as few datagrams as possible, encoding into a stack buffer so the
+        /// audio thread never allocates.
         pub fn send_events(&self, events: &[LiveInputEvent]) {
-            for event in events {
+            if let [event] = events {
                 self.send_event(*event);
+                return;
+            }
+            let mut payload = [0u8; EVENT_BATCH_MAX_SIZE];
+            for chunk in events.chunks(EVENT_BATCH_MAX_EVENTS) {
+                let size = encode_event_batch(chunk, &mut payload);
+                let _ = self.socket.send_to(&payload[..size], &self.target_path);
             }
         }
+
+        /// Fails when the datagram was not sent, e.g. before the helper has bound its socket,
+        /// so the caller can try again.
+        pub fn send_queue_overflow_metrics(
+            &self,
+            metrics: QueueOverflowMetrics,
+        ) -> std::io::Result<()> {
+            let payload = encode_overflow_metrics(metrics);
+            self.socket.send_to(&payload, &self.target_path).map(drop)
+        }
+
+        /// Fails when the datagram was not sent, like [`Self::send_queue_overflow_metrics`].
+        pub fn send_host_transport_context(
+            &self,
+            context: HostTransportContext,
+        ) -> std::io::Result<()> {
+            let payload = encode_host_context(context);
+            self.socket.send_to(&payload, &self.target_path).map(drop)
+        }
+
+        pub fn send_generate_trigger(&self) {
+            let payload = [GENERATE_TRIGGER_TAG, 0];
+            let _ = self.socket.send_to(&payload, &self.target_path);
+        }
+
+        /// `None` clears a name reported earlier. Fails when the datagram was not sent.
+        pub fn send_host_track_name(&self, name: Option<&str>) -> std::io::Result<()> {
+            let payload = encode_host_track_name(name);
+            self.socket.send_to(&payload, &self.target_path).map(drop)
+        }
     }
 
     pub struct LiveInputIpcSource {
         socket: UnixDatagram,
         socket_path: PathBuf,
+        overflow_metrics: Mutex<Option<QueueOverflowMetrics>>,
+        host_context: Mutex<Option<HostTransportContext>>,
+        host_track_name: Mutex<Option<String>>,
+        /// Remaining events from the last batch datagram.
+        batched_events: Mutex<VecDeque<LiveInputEvent>>,
+        generate_triggered: AtomicBool,
     }
 
     impl LiveInputIpcSource {
@@ -53,19 +122,97 @@ mod platform {
             Ok(Self {
                 socket,
                 socket_path,
+                overflow_metrics: Mutex::new(None),
+                host_context: Mutex::new(None),
+                host_track_name: Mutex::new(None),
+                batched_events: Mutex::new(VecDeque::new()),
+                generate_triggered: AtomicBool::new(false),
             })
         }
     }
 
     impl LiveInputEventSource for LiveInputIpcSource {
         fn try_pop_live_input_event(&self) -> Option<LiveInputEvent> {
-            let mut payload = [0u8; LIVE_INPUT_IPC_PACKET_SIZE];
-            let size = match self.socket.recv(&mut payload) {
-                Ok(size) => size,
-                Err(error) if error.kind() == ErrorKind::WouldBlock => return None,
-                Err(_) => return None,
-            };
-            decode_live_input_event(&payload[..size])
+            if let Some(event) = self
+                .batched_events
+                .lock()
+                .ok()
+                .and_then(|mut batched| batched.pop_front())
+            {
+                return Some(event);
+            }
+
+            let mut payload = [0u8; EVENT_BATCH_MAX_SIZE];
+            loop {
+                let size = match self.socket.recv(&mut payload) {
+                    Ok(size) => size,
+                    Err(error) if error.kind() == ErrorKind::WouldBlock => return None,
+                    Err(_) => return None,
+                };
+                if size == OVERFLOW_METRICS_PACKET_SIZE {
+                    if let Ok(mut latest) = self.overflow_metrics.lock() {
+                        *latest = Some(decode_overflow_metrics(&payload[..size]));
+                    }
+                    continue;
+                }
+                if size == HOST_CONTEXT_PACKET_SIZE {
+                    if let Ok(mut latest) = self.host_context.lock() {
+                        *latest = Some(decode_host_context(&payload[..size]));
+                    }
+                    continue;
+                }
+                if size == GENERATE_TRIGGER_PACKET_SIZE {
+                    if payload[0] == GENERATE_TRIGGER_TAG {
+                        self.generate_triggered.store(true, Ordering::Relaxed);
+                    }
+                    continue;
+                }
+                if size == HOST_TRACK_NAME_PACKET_SIZE && payload[0] == HOST_TRACK_NAME_TAG {
+                    if let Ok(mut latest) = self.host_track_name.lock() {
+                        *latest = decode_host_track_name(&payload[..size]);
+                    }
+                    continue;
+                }
+                if payload[0] == EVENT_BATCH_TAG && size > LIVE_INPUT_IPC_PACKET_SIZE {
+                    let mut events = decode_event_batch(&payload[..size]);
+                    let first = events.next();
+                    if let Ok(mut batched) = self.batched_events.lock() {
+                        batched.extend(events);
+                    }
+                    match first {
+                        Some(event) => return Some(event),
+                        None => continue,
+                    }
+                }
+                return decode_live_input_event(&payload[..size]);
+            }
+        }
+
+        fn queue_overflow_metrics(&self) -> Option<QueueOverflowMetrics> {
+            self.overflow_metrics.lock().ok().and_then(|latest| *latest)
+        }
+
+        fn host_transport_context(&self) -> Option<HostTransportContext> {
+            self.host_context.lock().ok().and_then(|latest| *latest)
+        }
+
+        fn take_generate_trigger(&self) -> bool {
+            self.generate_triggered.swap(false, Ordering::Relaxed)
+        }
+
+        fn host_track_name(&self) -> Option<String> {
+            self.host_track_name
+                .lock()
+                .ok()
+                .and_then(|latest| latest.clone())
+        }
+
+        fn shutdown(&self) {
+            // Unlinking the path makes further sends from the plugin fail instead of queueing.
+            let _ = std::fs::remove_file(&self.socket_path);
+            if let Ok(mut batched) = self.batched_events.lock() {
+                batched.clear();
+            }
         }
     }
 
@@ -75,16 +222,111 @@ mod platform {
         }
     }
 
-    fn encode_live_input_event(event: LiveInputEvent) -> [u8; LIVE_INPUT_IPC_PACKET_SIZE] {
-        let mut payload = [0u8; LIVE_INPUT_IPC_PACKET_SIZE];
+    fn encode_live_input_event(event: LiveInputEvent, payload: &mut [u8]) {
         payload[..4].copy_from_slice(&event.time.to_le_bytes());
         payload[4..6].copy_from_slice(&event.port_index.to_le_bytes());
         payload[6..9].copy_from_slice(&event.data);
         payload[9] = u8::from(event.is_transport_playing);
         payload[10..18].copy_from_slice(&event.playhead_ppq.to_le_bytes());
+    }
+
+    /// Writes up to [`EVENT_BATCH_MAX_EVENTS`] events into `payload` and returns the size used.
+    fn encode_event_batch(events: &[LiveInputEvent], payload: &mut [u8]) -> usize {
+        let events = &events[..events.len().min(EVENT_BATCH_MAX_EVENTS)];
+        payload[0] = EVENT_BATCH_TAG;
+        payload[1] = 0;
+        payload[2..4].copy_from_slice(&(events.len() as u16).to_le_bytes());
+        for (event, record) in events
+            .iter()
+            .zip(payload[EVENT_BATCH_HEADER_SIZE..].chunks_exact_mut(LIVE_INPUT_IPC_PACKET_SIZE))
+        {
+            encode_live_input_event(*event, record);
+        }
+        EVENT_BATCH_HEADER_SIZE + events.len() * LIVE_INPUT_IPC_PACKET_SIZE
+    }
+
+    /// Decodes records straight out of the receive buffer; a count that disagrees with the
+    /// datagram size yields no events.
+    fn decode_event_batch(payload: &[u8]) -> impl Iterator<Item = LiveInputEvent> + '_ {
+        let count = usize::from(u16::from_le_bytes([payload[2], payload[3]]));
+        let records = &payload[EVENT_BATCH_HEADER_SIZE..];
+        let valid = records.len() == count * LIVE_INPUT_IPC_PACKET_SIZE;
+        records
+            .chunks_exact(LIVE_INPUT_IPC_PACKET_SIZE)
+            .take(if valid { count } else { 0 })
+            .filter_map(decode_live_input_event)
+    }
+
+    fn encode_overflow_metrics(
+        metrics: QueueOverflowMetrics,
+    ) -> [u8; OVERFLOW_METRICS_PACKET_SIZE] {
+        let mut payload = [0u8; OVERFLOW_METRICS_PACKET_SIZE];
+        payload[..8].copy_from_slice(&metrics.live_input_dropped.to_le_bytes());
+        payload[8..16].copy_from_slice(&metrics.app_input_dropped.to_le_bytes());
+        payload[16..24].copy_from_slice(&metrics.generated_output_dropped.to_le_bytes());
+        payload
+    }
+
+    fn decode_overflow_metrics(payload: &[u8]) -> QueueOverflowMetrics {
+        let read_u64 = |start: usize| {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&payload[start..start + 8]);
+            u64::from_le_bytes(bytes)
+        };
+        QueueOverflowMetrics {
+            live_input_dropped: read_u64(0),
+            app_input_dropped: read_u64(8),
+            generated_output_dropped: read_u64(16),
+        }
+    }
+
+    // Zero stands in for "not reported": hosts never report a zero tempo, meter, or loop.
+    fn encode_host_context(context: HostTransportContext) -> [u8; HOST_CONTEXT_PACKET_SIZE] {
+        let (numerator, denominator) = context.time_signature.unwrap_or((0, 0));
+        let mut payload = [0u8; HOST_CONTEXT_PACKET_SIZE];
+        payload[..8].copy_from_slice(&context.tempo_bpm.unwrap_or(0.0).to_le_bytes());
+        payload[8..10].copy_from_slice(&numerator.to_le_bytes());
+        payload[10..12].copy_from_slice(&denominator.to_le_bytes());
+        payload[12..20].copy_from_slice(&context.loop_length_beats.unwrap_or(0.0).to_le_bytes());
+        payload
+    }
+
+    fn decode_host_context(payload: &[u8]) -> HostTransportContext {
+        let read_positive_f64 = |start: usize| {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&payload[start..start + 8]);
+            Some(f64::from_le_bytes(bytes)).filter(|value| value.is_finite() && *value > 0.0)
+        };
+        let read_u16 = |start: usize| u16::from_le_bytes([payload[start], payload[start + 1]]);
+        let time_signature = (read_u16(8), read_u16(10));
+        HostTransportContext {
+            tempo_bpm: read_positive_f64(0),
+            time_signature: (time_signature.0 > 0 && time_signature.1 > 0)
+                .then_some(time_signature),
+            loop_length_beats: read_positive_f64(12),
+        }
+    }
+
+    /// Longer names are cut at a character boundary.
+    fn encode_host_track_name(name: Option<&str>) -> [u8; HOST_TRACK_NAME_PACKET_SIZE] {
+        let name = name.map(str::trim).unwrap_or_default();
+        let mut end = name.len().min(HOST_TRACK_NAME_MAX_BYTES);
+        while !name.is_char_boundary(end) {
+            end -= 1;
+        }
+        let mut payload = [0u8; HOST_TRACK_NAME_PACKET_SIZE];
+        payload[0] = HOST_TRACK_NAME_TAG;
+        payload[1] = end as u8;
+        payload[2..2 + end].copy_from_slice(&name.as_bytes()[..end]);
         payload
     }
 
+    fn decode_host_track_name(payload: &[u8]) -> Option<String> {
+        let len = usize::from(payload[1]).min(HOST_TRACK_NAME_MAX_BYTES);
+        let name = std::str::from_utf8(&payload[2..2 + len]).ok()?.trim();
+        (!name.is_empty()).then(|| name.to_string())
+    }
+
     fn decode_live_input_event(payload: &[u8]) -> Option<LiveInputEvent> {
         if payload.len() != LIVE_INPUT_IPC_PACKET_SIZE {
             return None;
@@ -111,13 +353,15 @@ mod platform {
     #[cfg(test)]
     mod tests {
         use super::{LiveInputIpcSender, LiveInputIpcSource};
-        use crate::app::{LiveInputEvent, LiveInputEventSource};
-        use std::path::PathBuf;
-        use std::time::{SystemTime, UNIX_EPOCH};
+        use crate::app::{
+            HostTransportContext, LiveInputEvent, LiveInputEventSource, QueueOverflowMetrics,
+        };
+        use crate::test_support::TestDir;
 
         #[test]
         fn sender_to_source_round_trip_delivers_event() {
-            let socket_path = unique_test_socket_path();
+            let dir = TestDir::new("live-ipc");
+            let socket_path = dir.join("live.sock");
             let source = LiveInputIpcSource::bind(&socket_path).expect("bind should succeed");
             let sender = LiveInputIpcSender::new(&socket_path).expect("sender should initialize");
             let event = LiveInputEvent {
@@ -136,21 +380,154 @@ mod platform {
         }
 
         #[test]
-        fn source_ignores_empty_queue_without_blocking() {
-            let socket_path = unique_test_socket_path();
+        fn event_batches_arrive_in_order_across_datagrams() {
+            let dir = TestDir::new("live-ipc");
+            let socket_path = dir.join("live.sock");
+            let source = LiveInputIpcSource::bind(&socket_path).expect("bind should succeed");
+            let sender = LiveInputIpcSender::new(&socket_path).expect("sender should initialize");
+            let events = (0..70u32)
+                .map(|index| LiveInputEvent {
+                    time: index,
+                    port_index: 0,
+                    data: [0x90, 60 + (index % 12) as u8, 100],
+                    is_transport_playing: true,
+                    playhead_ppq: f64::from(index) * 0.25,
+                })
+                .collect::<Vec<_>>();
+
+            sender.send_events(&events);
+            sender
+                .send_host_transport_context(HostTransportContext {
+                    tempo_bpm: Some(120.0),
+                    time_signature: None,
+                    loop_length_beats: None,
+                })
+                .expect("host context should send");
+
+            let received =
+                std::iter::from_fn(|| source.try_pop_live_input_event()).collect::<Vec<_>>();
+            assert_eq!(received, events);
+            assert!(source.host_transport_context().is_some());
+        }
+
+        #[test]
+        fn overflow_metrics_are_recorded_between_events() {
+            let dir = TestDir::new("live-ipc");
+            let socket_path = dir.join("live.sock");
             let source = LiveInputIpcSource::bind(&socket_path).expect("bind should succeed");
+            let sender = LiveInputIpcSender::new(&socket_path).expect("sender should initialize");
+            let metrics = QueueOverflowMetrics {
+                live_input_dropped: 3,
+                app_input_dropped: 0,
+                generated_output_dropped: 7,
+            };
+            let event = LiveInputEvent {
+                time: 1,
+                port_index: 0,
+                data: [0x90, 60, 100],
+                is_transport_playing: false,
+                playhead_ppq: 0.0,
+            };
+
+            sender
+                .send_queue_overflow_metrics(metrics)
+                .expect("overflow metrics should send");
+            sender.send_event(event);
+
+            assert_eq!(source.try_pop_live_input_event(), Some(event));
+            assert_eq!(source.queue_overflow_metrics(), Some(metrics));
+        }
+
+        #[test]
+        fn host_context_is_recorded_and_unreported_fields_stay_empty() {
+            let dir = TestDir::new("live-ipc");
+            let socket_path = dir.join("live.sock");
+            let source = LiveInputIpcSource::bind(&socket_path).expect("bind should succeed");
+            let sender = LiveInputIpcSender::new(&socket_path).expect("sender should initialize");
+            let context = HostTransportContext {
+                tempo_bpm: Some(92.5),
+                time_signature: Some((6, 8)),
+                loop_length_beats: None,
+            };
+
+            sender
+                .send_host_transport_context(context)
+                .expect("host context should send");
+
             assert_eq!(source.try_pop_live_input_event(), None);
+            assert_eq!(source.host_transport_context(), Some(context));
         }
 
-        fn unique_test_socket_path() -> PathBuf {
-            let nonce = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .expect("clock should be after unix epoch")
-                .as_nanos();
-            std::env::temp_dir().join(format!(
-                "sonant-live-input-ipc-test-{}-{nonce:x}.sock",
-                std::process::id()
-            ))
+        #[test]
+        fn generate_trigger_is_reported_once_between_events() {
+            let dir = TestDir::new("live-ipc");
+            let socket_path = dir.join("live.sock");
+            let source = LiveInputIpcSource::bind(&socket_path).expect("bind should succeed");
+            let sender = LiveInputIpcSender::new(&socket_path).expect("sender should initialize");
+            let event = LiveInputEvent {
+                time: 3,
+                port_index: 0,
+                data: [0x90, 62, 90],
+                is_transport_playing: false,
+                playhead_ppq: 0.0,
+            };
+
+            sender.send_generate_trigger();
+            sender.send_event(event);
+
+            assert!(!source.take_generate_trigger());
+            assert_eq!(source.try_pop_live_input_event(), Some(event));
+            assert!(source.take_generate_trigger());
+            assert!(!source.take_generate_trigger());
+        }
+
+        #[test]
+        fn host_track_name_is_recorded_truncated_and_cleared() {
+            let dir = TestDir::new("live-ipc");
+            let socket_path = dir.join("live.sock");
+            let source = LiveInputIpcSource::bind(&socket_path).expect("bind should succeed");
+            let sender = LiveInputIpcSender::new(&socket_path).expect("sender should initialize");
+
+            sender
+                .send_host_track_name(Some(" Chorus 2 Lead "))
+                .expect("track name should send");
+            assert_eq!(source.try_pop_live_input_event(), None);
+            assert_eq!(source.host_track_name().as_deref(), Some("Chorus 2 Lead"));
+
+            sender
+                .send_host_track_name(Some(&"é".repeat(40)))
+                .expect("track name should send");
+            assert_eq!(source.try_pop_live_input_event(), None);
+            assert_eq!(source.host_track_name(), Some("é".repeat(32)));
+
+            sender
+                .send_host_track_name(None)
+                .expect("track name should send");
+            assert_eq!(source.try_pop_live_input_event(), None);
+            assert_eq!(source.host_track_name(), None);
+        }
+
+        #[test]
+        fn shutdown_unlinks_the_socket_so_later_sends_go_nowhere() {
+            let dir = TestDir::new("live-ipc");
+            let socket_path = dir.join("live.sock");
+            let source = LiveInputIpcSource::bind(&socket_path).expect("bind should succeed");
+            let sender = LiveInputIpcSender::new(&socket_path).expect("sender should initialize");
+
+            source.shutdown();
+            sender.send_generate_trigger();
+
+            assert!(!socket_path.exists());
+            assert!(!source.take_generate_trigger());
+            assert_eq!(source.try_pop_live_input_event(), None);
+        }
+
+        #[test]
+        fn source_ignores_empty_queue_without_blocking() {
+            let dir = TestDir::new("live-ipc");
+            let socket_path = dir.join("live.sock");
+            let source = LiveInputIpcSource::bind(&socket_path).expect("bind should succeed");
+            assert_eq!(source.try_pop_live_input_event(), None);
         }
     }
 }
@@ -160,7 +537,9 @@ mod platform {
     use std::io::{Error, ErrorKind};
     use std::path::Path;
 
-    use crate::app::{LiveInputEvent, LiveInputEventSource};
+    use crate::app::{
+        HostTransportContext, LiveInputEvent, LiveInputEventSource, QueueOverflowMetrics,
+    };
 
     pub struct LiveInputIpcSender;
 
@@ -175,6 +554,26 @@ mod platform {
         pub fn send_event(&self, _event: LiveInputEvent) {}
 
         pub fn send_events(&self, _events: &[LiveInputEvent]) {}
+
+        pub fn send_queue_overflow_metrics(
+            &self,
+            _metrics: QueueOverflowMetrics,
+        ) -> std::io::Result<()> {
+            Err(ErrorKind::Unsupported.into())
+        }
+
+        pub fn send_host_transport_context(
+            &self,
+            _context: HostTransportContext,
+        ) -> std::io::Result<()> {
+            Err(ErrorKind::Unsupported.into())
+        }
+
+        pub fn send_generate_trigger(&self) {}
+
+        pub fn send_host_track_name(&self, _name: Option<&str>) -> std::io::Result<()> {
+            Err(ErrorKind::Unsupported.into())
+        }
     }
 
     pub struct LiveInputIpcSource;
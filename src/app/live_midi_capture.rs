@@ -1,10 +1,24 @@
+use std::collections::HashMap;
 use std::num::NonZeroUsize;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
 
 use crossbeam_queue::ArrayQueue;
 use thiserror::Error;
 
+use crate::domain::{TickResolution, VelocityOnset, VelocityProfile};
+
 const DEFAULT_CAPTURE_QUEUE_CAPACITY: usize = 2048;
+const CONTROL_CHANGE_STATUS: u8 = 0xB0;
+// Sustain, portamento, sostenuto, soft, legato and hold 2: on/off pedals where every
+// transition matters, so they are never coalesced.
+const SWITCH_CONTROLLERS: std::ops::RangeInclusive<u8> = 64..=69;
+const SUSTAIN_PEDAL_CONTROLLER: u8 = 64;
+const SUSTAIN_PEDAL_DOWN_MIN_VALUE: u8 = 64;
+/// Status byte 0 is never valid MIDI; the plugin sends this payload when a host loop wraps, and
+/// with the transport stopped when playback stops.
+pub const LOOP_WRAP_MARKER_DATA: [u8; 3] = [0, 0, 0];
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct LiveInputEvent {
@@ -15,8 +29,78 @@ pub struct LiveInputEvent {
     pub playhead_ppq: f64,
 }
 
+impl LiveInputEvent {
+    pub fn is_loop_wrap_marker(&self) -> bool {
+        self.data == LOOP_WRAP_MARKER_DATA
+    }
+
+    pub fn is_note_on(&self) -> bool {
+        (self.data[0] & 0xF0) == 0x90 && self.data[2] > 0
+    }
+}
+
+/// Events drained from the capture queue in one poll.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiveInputBatch {
+    /// When the batch was drained from the queue.
+    pub received_at: Instant,
+    pub events: Vec<LiveInputEvent>,
+    /// Control changes dropped because a later value for the same controller superseded them.
+    pub coalesced_count: usize,
+}
+
+/// Events the plugin dropped because a bounded queue was full, counted since activation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueOverflowMetrics {
+    pub live_input_dropped: u64,
+    pub app_input_dropped: u64,
+    pub generated_output_dropped: u64,
+}
+
+impl QueueOverflowMetrics {
+    pub fn total(&self) -> u64 {
+        self.live_input_dropped
+            .saturating_add(self.app_input_dropped)
+            .saturating_add(self.generated_output_dropped)
+    }
+}
+
+/// Project settings the host reports with its transport; `None` where the host is silent.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct HostTransportContext {
+    pub tempo_bpm: Option<f64>,
+    /// Numerator and denominator, e.g. `(6, 8)`.
+    pub time_signature: Option<(u16, u16)>,
+    /// Length of the host loop region, set only while looping is enabled.
+    pub loop_length_beats: Option<f64>,
+}
+
 pub trait LiveInputEventSource: Send + Sync {
     fn try_pop_live_input_event(&self) -> Option<LiveInputEvent>;
+
+    /// Latest plugin-side overflow counters, if the source relays them.
+    fn queue_overflow_metrics(&self) -> Option<QueueOverflowMetrics> {
+        None
+    }
+
+    /// Latest host tempo, meter, and loop length, if the source relays them.
+    fn host_transport_context(&self) -> Option<HostTransportContext> {
+        None
+    }
+
+    /// Whether the host hit the Generate trigger since the last call, if the source relays it.
+    fn take_generate_trigger(&self) -> bool {
+        false
+    }
+
+    /// The host's name for the plugin's track or clip, if the source relays it.
+    fn host_track_name(&self) -> Option<String> {
+        None
+    }
+
+    /// Stops accepting input so the sender sees the helper as gone. Events not yet popped are
+    /// discarded.
+    fn shutdown(&self) {}
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
@@ -28,6 +112,7 @@ pub enum LiveMidiCaptureConfigError {
 pub struct LiveMidiCapture {
     source: Arc<dyn LiveInputEventSource>,
     queue: ArrayQueue<LiveInputEvent>,
+    dropped_events: AtomicU64,
 }
 
 impl LiveMidiCapture {
@@ -41,6 +126,7 @@ impl LiveMidiCapture {
         Self {
             source,
             queue: ArrayQueue::new(capacity.get()),
+            dropped_events: AtomicU64::new(0),
         }
     }
 
@@ -56,16 +142,61 @@ impl LiveMidiCapture {
     pub fn ingest_available(&self) -> usize {
         let mut ingested = 0;
         while let Some(event) = self.source.try_pop_live_input_event() {
-            let _ = self.queue.force_push(event);
+            if self.queue.force_push(event).is_some() {
+                self.dropped_events.fetch_add(1, Ordering::Relaxed);
+            }
             ingested += 1;
         }
         ingested
     }
 
+    /// Events evicted from the helper-side capture queue before they were polled.
+    pub fn dropped_event_count(&self) -> u64 {
+        self.dropped_events.load(Ordering::Relaxed)
+    }
+
+    pub fn source_overflow_metrics(&self) -> Option<QueueOverflowMetrics> {
+        self.source.queue_overflow_metrics()
+    }
+
+    pub fn host_transport_context(&self) -> Option<HostTransportContext> {
+        self.source.host_transport_context()
+    }
+
+    pub fn take_generate_trigger(&self) -> bool {
+        self.source.take_generate_trigger()
+    }
+
+    pub fn host_track_name(&self) -> Option<String> {
+        self.source.host_track_name()
+    }
+
+    /// Shuts the source down and clears the capture queue.
+    pub fn shutdown(&self) {
+        self.source.shutdown();
+        while self.queue.pop().is_some() {}
+    }
+
     pub fn poll_event(&self) -> Option<LiveInputEvent> {
         self.queue.pop()
     }
 
+    /// Drains up to `max_events` queued events as one batch with redundant control changes
+    /// coalesced, or `None` when the queue is empty.
+    pub fn poll_batch(&self, max_events: usize) -> Option<LiveInputBatch> {
+        let drained = self.poll_events(max_events);
+        if drained.is_empty() {
+            return None;
+        }
+        let drained_count = drained.len();
+        let events = coalesce_control_changes(drained);
+        Some(LiveInputBatch {
+            received_at: Instant::now(),
+            coalesced_count: drained_count - events.len(),
+            events,
+        })
+    }
+
     pub fn poll_events(&self, max_events: usize) -> Vec<LiveInputEvent> {
         if max_events == 0 {
             return Vec::new();
@@ -83,15 +214,162 @@ impl LiveMidiCapture {
     }
 }
 
+/// Keeps only the last of consecutive values for each port, channel, and controller. Any other
+/// channel message on that channel, or a loop-wrap marker, ends the run so values that apply
+/// to a note stay in place. Switch controllers such as the sustain pedal pass through untouched.
+fn coalesce_control_changes(events: Vec<LiveInputEvent>) -> Vec<LiveInputEvent> {
+    let mut kept = events.into_iter().map(Some).collect::<Vec<_>>();
+    // (port, status, controller) -> index of the run's latest value in `kept`
+    let mut open_runs = HashMap::<(u16, u8, u8), usize>::new();
+    for index in 0..kept.len() {
+        let Some(event) = kept[index] else {
+            continue;
+        };
+        let status = event.data[0];
+        if event.is_loop_wrap_marker() {
+            open_runs.clear();
+        } else if status & 0xF0 == CONTROL_CHANGE_STATUS
+            && !SWITCH_CONTROLLERS.contains(&event.data[1])
+        {
+            let key = (event.port_index, status, event.data[1]);
+            if let Some(previous) = open_runs.insert(key, index) {
+                kept[previous] = None;
+            }
+        } else if (0x80..0xF0).contains(&status) {
+            open_runs.retain(|(port, run_status, _), _| {
+                *port != event.port_index || run_status & 0x0F != status & 0x0F
+            });
+        }
+    }
+    kept.into_iter().flatten().collect()
+}
+
+/// Reshapes note-on velocities, placing each note by the host playhead at capture time.
+pub fn apply_velocity_profile_to_live_events(
+    events: &mut [LiveInputEvent],
+    profile: VelocityProfile,
+    seed: u64,
+) {
+    let resolution = TickResolution::DEFAULT;
+    let mut note_ons = events
+        .iter_mut()
+        .filter(|event| event.is_note_on())
+        .collect::<Vec<_>>();
+    let mut onsets = note_ons
+        .iter()
+        .map(|event| VelocityOnset {
+            start_tick: resolution.beats_to_ticks(event.playhead_ppq),
+            velocity: event.data[2],
+        })
+        .collect::<Vec<_>>();
+    profile.apply(&mut onsets, resolution, seed);
+    for (event, onset) in note_ons.iter_mut().zip(onsets) {
+        event.data[2] = onset.velocity;
+    }
+}
+
+/// Moves note-offs played under a held sustain pedal (CC64) to the pedal release, or to a
+/// re-strike of the same key, and drops the pedal events themselves. Dropped event times are
+/// carried forward so absolute ticks downstream stay where they were.
+pub fn apply_sustain_pedal_to_live_events(events: &[LiveInputEvent]) -> Vec<LiveInputEvent> {
+    fn emit(shaped: &mut Vec<LiveInputEvent>, carried_time: &mut u32, event: LiveInputEvent) {
+        shaped.push(LiveInputEvent {
+            time: event.time.saturating_add(std::mem::take(carried_time)),
+            ..event
+        });
+    }
+
+    let mut shaped = Vec::with_capacity(events.len());
+    let mut pedal_down = [false; 16];
+    let mut sustained = Vec::<LiveInputEvent>::new();
+    let mut carried_time = 0_u32;
+
+    for event in events.iter().copied() {
+        let status = event.data[0] & 0xF0;
+        let channel = event.data[0] & 0x0F;
+        if status == CONTROL_CHANGE_STATUS && event.data[1] == SUSTAIN_PEDAL_CONTROLLER {
+            carried_time = carried_time.saturating_add(event.time);
+            let is_down = event.data[2] >= SUSTAIN_PEDAL_DOWN_MIN_VALUE;
+            if pedal_down[usize::from(channel)] && !is_down {
+                let (released, held): (Vec<_>, Vec<_>) = sustained
+                    .into_iter()
+                    .partition(|note_off: &LiveInputEvent| note_off.data[0] & 0x0F == channel);
+                sustained = held;
+                for note_off in released {
+                    emit(
+                        &mut shaped,
+                        &mut carried_time,
+                        LiveInputEvent {
+                            time: 0,
+                            playhead_ppq: event.playhead_ppq,
+                            ..note_off
+                        },
+                    );
+                }
+            }
+            pedal_down[usize::from(channel)] = is_down;
+            continue;
+        }
+
+        let is_note_off = status == 0x80 || (status == 0x90 && event.data[2] == 0);
+        if is_note_off && pedal_down[usize::from(channel)] {
+            carried_time = carried_time.saturating_add(event.time);
+            sustained.push(event);
+            continue;
+        }
+        if event.is_note_on()
+            && let Some(index) = sustained.iter().position(|note_off| {
+                note_off.data[0] & 0x0F == channel && note_off.data[1] == event.data[1]
+            })
+        {
+            let note_off = sustained.remove(index);
+            emit(
+                &mut shaped,
+                &mut carried_time,
+                LiveInputEvent {
+                    time: event.time,
+                    playhead_ppq: event.playhead_ppq,
+                    ..note_off
+                },
+            );
+            emit(
+                &mut shaped,
+                &mut carried_time,
+                LiveInputEvent { time: 0, ..event },
+            );
+            continue;
+        }
+        emit(&mut shaped, &mut carried_time, event);
+    }
+
+    // A pedal still down when the take ended lets its notes ring to the last captured event.
+    let end_ppq = events.last().map_or(0.0, |event| event.playhead_ppq);
+    for note_off in sustained {
+        emit(
+            &mut shaped,
+            &mut carried_time,
+            LiveInputEvent {
+                time: 0,
+                playhead_ppq: end_ppq.max(note_off.playhead_ppq),
+                ..note_off
+            },
+        );
+    }
+    shaped
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
         LiveInputEvent, LiveInputEventSource, LiveMidiCapture, LiveMidiCaptureConfigError,
+        apply_sustain_pedal_to_live_events, apply_velocity_profile_to_live_events,
     };
     use std::collections::VecDeque;
     use std::num::NonZeroUsize;
     use std::sync::{Arc, Mutex};
 
+    use crate::domain::VelocityProfile;
+
     struct StubLiveInputSource {
         events: Mutex<VecDeque<LiveInputEvent>>,
     }
@@ -162,6 +440,7 @@ mod tests {
 
         let ingested = capture.ingest_available();
         assert_eq!(ingested, 3);
+        assert_eq!(capture.dropped_event_count(), 1);
 
         assert_eq!(
             capture.poll_events(8),
@@ -186,6 +465,203 @@ mod tests {
         assert!(capture.poll_events(4).is_empty());
     }
 
+    #[test]
+    fn poll_batch_coalesces_control_changes_between_notes() {
+        let control_change = |time: u32, channel: u8, controller: u8, value: u8| LiveInputEvent {
+            time,
+            port_index: 0,
+            data: [0xB0 | channel, controller, value],
+            is_transport_playing: true,
+            playhead_ppq: 0.0,
+        };
+        let source = Arc::new(StubLiveInputSource::new(vec![
+            control_change(1, 0, 1, 10),
+            control_change(2, 0, 1, 20),
+            control_change(3, 1, 1, 5),
+            control_change(4, 0, 7, 90),
+            control_change(5, 0, 1, 30),
+            sample_event(6, 0, 60),
+            control_change(7, 0, 1, 40),
+            control_change(8, 1, 1, 6),
+        ]));
+        let capture = LiveMidiCapture::with_capacity(
+            source,
+            NonZeroUsize::new(16).expect("test capacity must be non-zero"),
+        );
+
+        assert!(capture.poll_batch(16).is_none());
+        capture.ingest_available();
+        let batch = capture
+            .poll_batch(16)
+            .expect("queued events should form a batch");
+
+        assert_eq!(batch.coalesced_count, 3);
+        assert_eq!(
+            batch.events,
+            vec![
+                control_change(4, 0, 7, 90),
+                control_change(5, 0, 1, 30),
+                sample_event(6, 0, 60),
+                control_change(7, 0, 1, 40),
+                control_change(8, 1, 1, 6),
+            ]
+        );
+    }
+
+    #[test]
+    fn poll_batch_keeps_every_sustain_pedal_transition() {
+        let pedal = |time: u32, value: u8| LiveInputEvent {
+            time,
+            port_index: 0,
+            data: [0xB0, 64, value],
+            is_transport_playing: true,
+            playhead_ppq: 0.0,
+        };
+        // Legato pedaling: the pedal comes up and goes straight back down between notes.
+        let source = Arc::new(StubLiveInputSource::new(vec![
+            pedal(1, 127),
+            pedal(2, 0),
+            pedal(3, 127),
+        ]));
+        let capture = LiveMidiCapture::with_capacity(
+            source,
+            NonZeroUsize::new(16).expect("test capacity must be non-zero"),
+        );
+
+        capture.ingest_available();
+        let batch = capture
+            .poll_batch(16)
+            .expect("queued events should form a batch");
+
+        assert_eq!(batch.coalesced_count, 0);
+        assert_eq!(
+            batch.events,
+            vec![pedal(1, 127), pedal(2, 0), pedal(3, 127)]
+        );
+    }
+
+    #[test]
+    fn live_velocity_profile_reshapes_only_note_on_velocities() {
+        let event = |data: [u8; 3], playhead_ppq: f64| LiveInputEvent {
+            time: 0,
+            port_index: 0,
+            data,
+            is_transport_playing: true,
+            playhead_ppq,
+        };
+        let mut events = vec![
+            event([0x90, 60, 80], 0.0),
+            event([0x80, 60, 64], 0.5),
+            event([0x90, 62, 80], 0.5),
+            event([0x90, 64, 80], 1.0),
+        ];
+
+        apply_velocity_profile_to_live_events(&mut events, VelocityProfile::AccentOnDownbeat, 0);
+
+        let velocities = events.iter().map(|event| event.data[2]).collect::<Vec<_>>();
+        assert_eq!(velocities, vec![98, 64, 72, 86]);
+    }
+
+    #[test]
+    fn live_velocity_profile_is_seeded_and_leaves_other_events_alone() {
+        let event = |data: [u8; 3], playhead_ppq: f64| LiveInputEvent {
+            time: 0,
+            port_index: 0,
+            data,
+            is_transport_playing: true,
+            playhead_ppq,
+        };
+        let events = vec![
+            event([0xB0, 1, 40], 0.0),
+            event([0x90, 60, 80], 0.0),
+            event([0x90, 64, 80], 0.0),
+            event([0x90, 60, 0], 0.75),
+            event([0x90, 67, 80], 1.25),
+        ];
+
+        let mut first = events.clone();
+        let mut second = events.clone();
+        apply_velocity_profile_to_live_events(&mut first, VelocityProfile::RandomWalk, 7);
+        apply_velocity_profile_to_live_events(&mut second, VelocityProfile::RandomWalk, 7);
+
+        assert_eq!(first, second);
+        assert_eq!(first[0], events[0]);
+        assert_eq!(first[3], events[3]);
+        // Chord tones start together and move together.
+        assert_eq!(first[1].data[2], first[2].data[2]);
+    }
+
+    #[test]
+    fn sustain_pedal_holds_note_offs_until_release_or_restrike() {
+        let event = |time: u32, data: [u8; 3], playhead_ppq: f64| LiveInputEvent {
+            time,
+            port_index: 0,
+            data,
+            is_transport_playing: true,
+            playhead_ppq,
+        };
+        let events = [
+            event(0, [0xB0, 64, 127], 0.0),
+            event(10, [0x90, 60, 100], 0.0),
+            event(10, [0x80, 60, 0], 0.5),
+            event(10, [0x90, 64, 100], 1.0),
+            event(10, [0x80, 64, 0], 1.5),
+            event(10, [0x90, 60, 90], 2.0),
+            event(10, [0xB0, 64, 0], 4.0),
+        ];
+
+        let shaped = apply_sustain_pedal_to_live_events(&events);
+
+        assert_eq!(
+            shaped
+                .iter()
+                .map(|event| (event.time, event.data, event.playhead_ppq))
+                .collect::<Vec<_>>(),
+            vec![
+                (10, [0x90, 60, 100], 0.0),
+                (20, [0x90, 64, 100], 1.0),
+                (20, [0x80, 60, 0], 2.0),
+                (0, [0x90, 60, 90], 2.0),
+                (10, [0x80, 64, 0], 4.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn sustain_pedal_only_holds_its_own_channel_and_rings_to_the_end_of_the_take() {
+        let event = |time: u32, data: [u8; 3], playhead_ppq: f64| LiveInputEvent {
+            time,
+            port_index: 0,
+            data,
+            is_transport_playing: true,
+            playhead_ppq,
+        };
+        let events = [
+            event(0, [0xB1, 64, 100], 0.0),
+            event(10, [0x90, 48, 100], 0.0),
+            event(10, [0x91, 60, 100], 0.0),
+            event(10, [0x80, 48, 0], 1.0),
+            event(10, [0x91, 60, 0], 1.0),
+            event(10, [0x90, 50, 100], 3.0),
+        ];
+
+        let shaped = apply_sustain_pedal_to_live_events(&events);
+
+        assert_eq!(
+            shaped
+                .iter()
+                .map(|event| (event.time, event.data, event.playhead_ppq))
+                .collect::<Vec<_>>(),
+            vec![
+                (10, [0x90, 48, 100], 0.0),
+                (10, [0x91, 60, 100], 0.0),
+                (10, [0x80, 48, 0], 1.0),
+                (20, [0x90, 50, 100], 3.0),
+                (0, [0x91, 60, 0], 3.0),
+            ]
+        );
+    }
+
     #[test]
     fn try_with_capacity_rejects_zero() {
         let source = Arc::new(StubLiveInputSource::new(Vec::new()));
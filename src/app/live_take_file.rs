@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use thiserror::Error;
+
+use super::LiveInputEvent;
+use super::stem_export::{sanitize_file_label, slot_file_label};
+use crate::domain::{BEATS_PER_BAR, GeneratedNote, ReferenceSlot, TickResolution};
+use crate::infra::midi::{MidiConductor, MidiWriteError, write_notes_to_midi_file};
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum LiveTakeFileError {
+    #[error("the live take has no notes to save")]
+    EmptyTake,
+    #[error(transparent)]
+    Write(#[from] MidiWriteError),
+}
+
+/// Notes of a captured take placed by the host playhead, with the bar the take starts in at
+/// tick 0. A note still held when the events end lasts until the last event.
+pub fn live_take_notes(
+    events: &[LiveInputEvent],
+    resolution: TickResolution,
+) -> Vec<GeneratedNote> {
+    let bar_beats = f64::from(BEATS_PER_BAR);
+    let first_beat = events
+        .iter()
+        .filter(|event| !event.is_loop_wrap_marker() && event.playhead_ppq.is_finite())
+        .map(|event| event.playhead_ppq)
+        .fold(f64::INFINITY, f64::min);
+    if !first_beat.is_finite() {
+        return Vec::new();
+    }
+    let origin = (first_beat / bar_beats).floor() * bar_beats;
+    let tick_at = |playhead_ppq: f64| resolution.beats_to_ticks((playhead_ppq - origin).max(0.0));
+
+    let mut held = HashMap::<(u8, u8), (u32, u8)>::new();
+    let mut notes = Vec::new();
+    let mut last_tick = 0;
+    let mut close = |channel: u8, pitch: u8, (start_tick, velocity): (u32, u8), end_tick: u32| {
+        notes.push(GeneratedNote {
+            pitch,
+            start_tick,
+            duration_tick: end_tick.saturating_sub(start_tick).max(1),
+            velocity,
+            channel,
+        });
+    };
+
+    for event in events {
+        let [status, pitch, velocity] = event.data;
+        let is_note_on = status & 0xF0 == 0x90 && velocity > 0;
+        let is_note_off = status & 0xF0 == 0x80 || (status & 0xF0 == 0x90 && velocity == 0);
+        if !is_note_on && !is_note_off {
+            continue;
+        }
+        let channel = (status & 0x0F) + 1;
+        let tick = tick_at(event.playhead_ppq);
+        last_tick = last_tick.max(tick);
+        // A retrigger ends the previous note on the same key.
+        if let Some(started) = held.remove(&(channel, pitch)) {
+            close(channel, pitch, started, tick);
+        }
+        if is_note_on {
+            held.insert((channel, pitch), (tick, velocity));
+        }
+    }
+    for ((channel, pitch), started) in held {
+        close(channel, pitch, started, last_tick);
+    }
+
+    notes.sort_by_key(|note| (note.start_tick, note.pitch, note.channel));
+    notes
+}
+
+/// `<file stem>-<slot>-take-<unix seconds>.mid`
+pub fn live_take_file_name(
+    file_stem: &str,
+    slot: ReferenceSlot,
+    saved_at_unix_secs: u64,
+) -> String {
+    format!(
+        "{}-{}-take-{saved_at_unix_secs}.mid",
+        sanitize_file_label(file_stem),
+        slot_file_label(slot)
+    )
+}
+
+/// `<file stem>-<slot>-take.mid`, for the copy staged in the temp folder. Without a timestamp
+/// each stage of the slot overwrites the last instead of adding a file.
+pub fn staged_live_take_file_name(file_stem: &str, slot: ReferenceSlot) -> String {
+    format!(
+        "{}-{}-take.mid",
+        sanitize_file_label(file_stem),
+        slot_file_label(slot)
+    )
+}
+
+/// Writes a captured take as a Standard MIDI File and returns how many notes it holds.
+pub fn write_live_take(
+    path: impl AsRef<Path>,
+    events: &[LiveInputEvent],
+    resolution: TickResolution,
+    conductor: &MidiConductor,
+    program: Option<u8>,
+) -> Result<usize, LiveTakeFileError> {
+    let notes = live_take_notes(events, resolution);
+    if notes.is_empty() {
+        return Err(LiveTakeFileError::EmptyTake);
+    }
+    write_notes_to_midi_file(
+        path,
+        &notes,
+        &[],
+        &[],
+        resolution.ticks_per_beat(),
+        conductor,
+        program,
+    )?;
+    Ok(notes.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LiveTakeFileError, live_take_file_name, live_take_notes, write_live_take};
+    use crate::app::{LOOP_WRAP_MARKER_DATA, LiveInputEvent};
+    use crate::domain::{ReferenceSlot, TickResolution};
+    use crate::infra::midi::{MidiConductor, parse_midi_reference};
+    use crate::test_support::TestDir;
+
+    fn event(data: [u8; 3], playhead_ppq: f64) -> LiveInputEvent {
+        LiveInputEvent {
+            time: 0,
+            port_index: 0,
+            data,
+            is_transport_playing: true,
+            playhead_ppq,
+        }
+    }
+
+    #[test]
+    fn notes_start_at_the_bar_the_take_begins_in() {
+        let events = [
+            event(LOOP_WRAP_MARKER_DATA, 0.0),
+            event([0x90, 60, 100], 9.0),
+            event([0x91, 64, 80], 9.5),
+            event([0x80, 60, 0], 10.0),
+            event([0x90, 60, 90], 11.0),
+            event([0x91, 64, 0], 11.5),
+        ];
+
+        let notes = live_take_notes(&events, TickResolution::DEFAULT);
+        let summary = notes
+            .iter()
+            .map(|note| {
+                (
+                    note.pitch,
+                    note.channel,
+                    note.start_tick,
+                    note.duration_tick,
+                    note.velocity,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                (60, 1, 480, 480, 100),
+                (64, 2, 720, 960, 80),
+                (60, 1, 1440, 240, 90),
+            ]
+        );
+    }
+
+    #[test]
+    fn takes_without_notes_are_not_written() {
+        let dir = TestDir::new("live-take");
+        let path = dir.join("empty.mid");
+        let result = write_live_take(
+            &path,
+            &[event([0xB0, 64, 127], 1.0)],
+            TickResolution::DEFAULT,
+            &MidiConductor::new(120),
+            None,
+        );
+        assert_eq!(result, Err(LiveTakeFileError::EmptyTake));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn written_takes_load_back_as_midi_references() {
+        let dir = TestDir::new("live-take");
+        let path = dir.join(live_take_file_name("sonant", ReferenceSlot::Bassline, 1));
+        let events = [event([0x92, 40, 110], 0.0), event([0x82, 40, 0], 2.0)];
+
+        let written = write_live_take(
+            &path,
+            &events,
+            TickResolution::DEFAULT,
+            &MidiConductor::new(96),
+            Some(33),
+        )
+        .expect("take should be written");
+        let bytes = std::fs::read(&path).expect("take file should exist");
+
+        assert_eq!(written, 1);
+        assert!(
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("sonant-bassline-take-"))
+        );
+        let reference = parse_midi_reference(&bytes).expect("take should parse");
+        assert_eq!(reference.summary.note_count, 1);
+    }
+}
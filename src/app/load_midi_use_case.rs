@@ -4,15 +4,31 @@ use std::sync::{Arc, Mutex};
 use thiserror::Error;
 
 use crate::domain::{
-    FileReferenceInput, MidiReferenceSummary, ReferenceSlot, ReferenceSource,
+    FileReferenceInput, MidiReferenceEvent, MidiReferenceSummary, ReferenceSlot, ReferenceSource,
     calculate_reference_density_hint,
 };
 use crate::infra::midi::{MidiLoadError, MidiReferenceData, load_midi_reference};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum LoadMidiCommand {
-    SetFile { slot: ReferenceSlot, path: String },
-    ClearSlot { slot: ReferenceSlot },
+    SetFile {
+        slot: ReferenceSlot,
+        path: String,
+    },
+    /// Loads clips of one part, e.g. several regions exported separately from the DAW, as a
+    /// single reference. Each clip starts on the bar after the previous one ends.
+    SetFiles {
+        slot: ReferenceSlot,
+        paths: Vec<String>,
+    },
+    /// Re-reads a loaded file in place, keeping its position among the slot's references.
+    ReloadFile {
+        slot: ReferenceSlot,
+        path: String,
+    },
+    ClearSlot {
+        slot: ReferenceSlot,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -28,6 +44,24 @@ pub enum LoadMidiOutcome {
     },
 }
 
+/// A slot's loaded references captured before a destructive action so it can be undone.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlotReferenceSnapshot {
+    pub slot: ReferenceSlot,
+    references: Vec<MidiReferenceSummary>,
+    clip_paths: Vec<Vec<String>>,
+}
+
+impl SlotReferenceSnapshot {
+    pub fn is_empty(&self) -> bool {
+        self.references.is_empty()
+    }
+
+    pub fn reference_count(&self) -> usize {
+        self.references.len()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Error)]
 pub enum LoadMidiError {
     #[error("reference MIDI path must not be empty")]
@@ -106,7 +140,9 @@ impl LoadMidiUseCase {
 
     pub fn execute(&self, command: LoadMidiCommand) -> Result<LoadMidiOutcome, LoadMidiError> {
         match command {
-            LoadMidiCommand::SetFile { slot, path } => self.set_file(slot, path),
+            LoadMidiCommand::SetFile { slot, path } => self.set_files(slot, vec![path]),
+            LoadMidiCommand::SetFiles { slot, paths } => self.set_files(slot, paths),
+            LoadMidiCommand::ReloadFile { slot, path } => self.reload_file(slot, path),
             LoadMidiCommand::ClearSlot { slot } => Ok(self.clear_slot(slot)),
         }
     }
@@ -135,23 +171,46 @@ impl LoadMidiUseCase {
         state.slot_references(slot)
     }
 
-    fn set_file(
+    fn set_files(
+        &self,
+        slot: ReferenceSlot,
+        paths: Vec<String>,
+    ) -> Result<LoadMidiOutcome, LoadMidiError> {
+        let clip_paths = normalize_paths(paths)?;
+        let reference = self.load_clips(slot, &clip_paths)?;
+
+        let mut state = self
+            .state
+            .lock()
+            .expect("load MIDI state lock poisoned while writing slot reference");
+        let slot_reference_count = state.append(reference.clone(), clip_paths);
+
+        Ok(LoadMidiOutcome::Loaded {
+            slot,
+            slot_reference_count,
+            reference,
+        })
+    }
+
+    fn reload_file(
         &self,
         slot: ReferenceSlot,
         path: String,
     ) -> Result<LoadMidiOutcome, LoadMidiError> {
         let normalized_path = normalize_path(path)?;
-        let data = self
-            .loader
-            .load_reference(Path::new(&normalized_path))
-            .map_err(|source| LoadMidiError::LoadFailed { source })?;
-        let reference = build_reference_summary(slot, normalized_path, data)?;
+        let clip_paths = self
+            .state
+            .lock()
+            .expect("load MIDI state lock poisoned while reading clip paths")
+            .clip_paths_containing(slot, &normalized_path)
+            .unwrap_or_else(|| vec![normalized_path]);
+        let reference = self.load_clips(slot, &clip_paths)?;
 
         let mut state = self
             .state
             .lock()
-            .expect("load MIDI state lock poisoned while writing slot reference");
-        let slot_reference_count = state.append(reference.clone());
+            .expect("load MIDI state lock poisoned while reloading slot reference");
+        let slot_reference_count = state.replace_or_append(reference.clone(), clip_paths);
 
         Ok(LoadMidiOutcome::Loaded {
             slot,
@@ -160,6 +219,75 @@ impl LoadMidiUseCase {
         })
     }
 
+    pub fn snapshot_slot(&self, slot: ReferenceSlot) -> SlotReferenceSnapshot {
+        let state = self
+            .state
+            .lock()
+            .expect("load MIDI state lock poisoned while snapshotting slot");
+        let (references, clip_paths) = state
+            .references
+            .iter()
+            .zip(&state.clip_paths)
+            .filter(|(reference, _)| reference.slot == slot)
+            .map(|(reference, clip_paths)| (reference.clone(), clip_paths.clone()))
+            .unzip();
+        SlotReferenceSnapshot {
+            slot,
+            references,
+            clip_paths,
+        }
+    }
+
+    /// Replaces the slot's references with a snapshot taken earlier; returns the restored count.
+    pub fn restore_slot(&self, snapshot: SlotReferenceSnapshot) -> usize {
+        let mut state = self
+            .state
+            .lock()
+            .expect("load MIDI state lock poisoned while restoring slot");
+        state.clear(snapshot.slot);
+        for (reference, clip_paths) in snapshot.references.into_iter().zip(snapshot.clip_paths) {
+            state.append(reference, clip_paths);
+        }
+        state.slot_reference_count(snapshot.slot)
+    }
+
+    /// Makes `reference` the only one in its slot. Derived references have no clip files, so
+    /// file watching and reloads leave them alone.
+    pub fn replace_slot_reference(&self, reference: MidiReferenceSummary) -> usize {
+        let mut state = self
+            .state
+            .lock()
+            .expect("load MIDI state lock poisoned while replacing slot reference");
+        state.clear(reference.slot);
+        state.append(reference, Vec::new())
+    }
+
+    /// The clip paths behind each of the slot's references, in load order.
+    pub fn slot_clip_paths(&self, slot: ReferenceSlot) -> Vec<Vec<String>> {
+        let state = self
+            .state
+            .lock()
+            .expect("load MIDI state lock poisoned while reading clip paths");
+        state.slot_clip_paths(slot)
+    }
+
+    fn load_clips(
+        &self,
+        slot: ReferenceSlot,
+        clip_paths: &[String],
+    ) -> Result<MidiReferenceSummary, LoadMidiError> {
+        let clips = clip_paths
+            .iter()
+            .map(|path| {
+                self.loader
+                    .load_reference(Path::new(path))
+                    .map_err(|source| LoadMidiError::LoadFailed { source })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let data = concatenate_clips(clips)?;
+        build_reference_summary(slot, clip_paths[0].clone(), data)
+    }
+
     fn clear_slot(&self, slot: ReferenceSlot) -> LoadMidiOutcome {
         let mut state = self
             .state
@@ -182,21 +310,72 @@ impl Default for LoadMidiUseCase {
 #[derive(Debug, Default)]
 struct ReferenceSlotState {
     references: Vec<MidiReferenceSummary>,
+    // Parallel to `references`: the files each reference was concatenated from.
+    clip_paths: Vec<Vec<String>>,
 }
 
 impl ReferenceSlotState {
-    fn append(&mut self, reference: MidiReferenceSummary) -> usize {
+    fn append(&mut self, reference: MidiReferenceSummary, clip_paths: Vec<String>) -> usize {
         let slot = reference.slot;
         self.references.push(reference);
+        self.clip_paths.push(clip_paths);
         self.slot_reference_count(slot)
     }
 
+    fn replace_or_append(
+        &mut self,
+        reference: MidiReferenceSummary,
+        clip_paths: Vec<String>,
+    ) -> usize {
+        let existing = self.references.iter().position(|existing| {
+            existing.slot == reference.slot
+                && existing.file.as_ref().map(|file| &file.path)
+                    == reference.file.as_ref().map(|file| &file.path)
+        });
+        match existing {
+            Some(index) => {
+                let slot = reference.slot;
+                self.references[index] = reference;
+                self.clip_paths[index] = clip_paths;
+                self.slot_reference_count(slot)
+            }
+            None => self.append(reference, clip_paths),
+        }
+    }
+
     fn clear(&mut self, slot: ReferenceSlot) -> usize {
         let before_len = self.references.len();
-        self.references.retain(|reference| reference.slot != slot);
+        let mut kept_clip_paths = std::mem::take(&mut self.clip_paths).into_iter();
+        let references = std::mem::take(&mut self.references);
+        for reference in references {
+            let clip_paths = kept_clip_paths.next().unwrap_or_default();
+            if reference.slot != slot {
+                self.references.push(reference);
+                self.clip_paths.push(clip_paths);
+            }
+        }
         before_len.saturating_sub(self.references.len())
     }
 
+    fn clip_paths_containing(&self, slot: ReferenceSlot, path: &str) -> Option<Vec<String>> {
+        self.references
+            .iter()
+            .zip(&self.clip_paths)
+            .find(|(reference, clip_paths)| {
+                reference.slot == slot && clip_paths.iter().any(|clip_path| clip_path == path)
+            })
+            .map(|(_, clip_paths)| clip_paths.clone())
+    }
+
+    fn slot_clip_paths(&self, slot: ReferenceSlot) -> Vec<Vec<String>> {
+        self.references
+            .iter()
+            .zip(&self.clip_paths)
+            .filter(|(reference, _)| reference.slot == slot)
+            .map(|(_, clip_paths)| clip_paths.clone())
+            .collect()
+    }
+
     fn snapshot(&self) -> Vec<MidiReferenceSummary> {
         self.references.clone()
     }
@@ -234,6 +413,73 @@ fn normalize_path(path: String) -> Result<String, LoadMidiError> {
     }
 }
 
+fn normalize_paths(paths: Vec<String>) -> Result<Vec<String>, LoadMidiError> {
+    if paths.is_empty() {
+        return Err(LoadMidiError::EmptyPath);
+    }
+    paths.into_iter().map(normalize_path).collect()
+}
+
+/// Joins clips end to end on bar boundaries, rescaling later clips to the first clip's bar
+/// length so clips exported at different resolutions still line up.
+fn concatenate_clips(clips: Vec<MidiReferenceData>) -> Result<MidiReferenceData, LoadMidiError> {
+    let mut clips = clips.into_iter();
+    let Some(first) = clips.next() else {
+        return Err(LoadMidiError::EmptyPath);
+    };
+    let ticks_per_bar = u64::from(first.summary.ticks_per_bar.max(1));
+    let mut summary = first.summary;
+    let mut events = first.events;
+    let mut offset = u64::from(summary.bars) * ticks_per_bar;
+
+    for clip in clips {
+        let clip_ticks_per_bar = u64::from(clip.summary.ticks_per_bar.max(1));
+        for event in clip.events {
+            let tick = offset + u64::from(event.absolute_tick) * ticks_per_bar / clip_ticks_per_bar;
+            events.push(MidiReferenceEvent {
+                absolute_tick: u32::try_from(tick).map_err(|_| overflow("absolute_tick"))?,
+                ..event
+            });
+        }
+        summary.bars = summary
+            .bars
+            .checked_add(clip.summary.bars)
+            .ok_or_else(|| overflow("bars"))?;
+        // A clip without notes reports a 0..0 range, which is not a pitch it plays.
+        if summary.note_count == 0 {
+            summary.min_pitch = clip.summary.min_pitch;
+            summary.max_pitch = clip.summary.max_pitch;
+        } else if clip.summary.note_count > 0 {
+            summary.min_pitch = summary.min_pitch.min(clip.summary.min_pitch);
+            summary.max_pitch = summary.max_pitch.max(clip.summary.max_pitch);
+        }
+        summary.note_count = summary
+            .note_count
+            .checked_add(clip.summary.note_count)
+            .ok_or_else(|| overflow("note_count"))?;
+        offset += u64::from(clip.summary.bars) * ticks_per_bar;
+    }
+
+    // Keep each track's events contiguous and re-derive deltas across clip boundaries.
+    events.sort_by_key(|event| event.track);
+    let mut previous: Option<(u16, u32)> = None;
+    for event in &mut events {
+        event.delta_tick = match previous {
+            Some((track, tick)) if track == event.track => event.absolute_tick.saturating_sub(tick),
+            _ => event.absolute_tick,
+        };
+        previous = Some((event.track, event.absolute_tick));
+    }
+
+    Ok(MidiReferenceData { summary, events })
+}
+
+fn overflow(field: &'static str) -> LoadMidiError {
+    LoadMidiError::LoadFailed {
+        source: MidiLoadError::Overflow { field },
+    }
+}
+
 fn build_reference_summary(
     slot: ReferenceSlot,
     path: String,
@@ -249,6 +495,8 @@ fn build_reference_summary(
         min_pitch: data.summary.min_pitch,
         max_pitch: data.summary.max_pitch,
         events: data.events,
+        transposition: None,
+        tempo: None,
     };
 
     reference
@@ -267,6 +515,7 @@ mod tests {
     };
     use crate::domain::{MidiReferenceEvent, ReferenceSlot};
     use crate::infra::midi::{MidiLoadError, MidiReferenceData, MidiSummary};
+    use crate::test_support::TestDir;
     use std::collections::VecDeque;
     use std::path::{Path, PathBuf};
     use std::sync::{Arc, Mutex};
@@ -309,8 +558,9 @@ mod tests {
 
     #[test]
     fn load_append_clear_flow_is_supported_for_a_slot() {
-        let first_path = temp_test_path("first.mid");
-        let second_path = temp_test_path("second.mid");
+        let dir = TestDir::new("load-midi");
+        let first_path = dir.join("first.mid");
+        let second_path = dir.join("second.mid");
 
         let loader = Arc::new(StubLoader::new(vec![
             Ok(sample_reference_data(4, 12, 60, 72, "first")),
@@ -383,8 +633,9 @@ mod tests {
 
     #[test]
     fn multiple_slots_can_be_loaded_and_cleared_independently() {
-        let melody_path = temp_test_path("melody.mid");
-        let chord_path = temp_test_path("chords.mid");
+        let dir = TestDir::new("load-midi");
+        let melody_path = dir.join("melody.mid");
+        let chord_path = dir.join("chords.mid");
 
         let loader = Arc::new(StubLoader::new(vec![
             Ok(sample_reference_data(4, 16, 60, 72, "melody")),
@@ -449,8 +700,9 @@ mod tests {
 
     #[test]
     fn load_error_is_propagated_and_existing_slot_is_kept() {
-        let current_path = temp_test_path("current.mid");
-        let broken_path = temp_test_path("broken.mid");
+        let dir = TestDir::new("load-midi");
+        let current_path = dir.join("current.mid");
+        let broken_path = dir.join("broken.mid");
 
         let loader = Arc::new(StubLoader::new(vec![
             Ok(sample_reference_data(4, 8, 60, 67, "ok")),
@@ -490,6 +742,163 @@ mod tests {
         );
     }
 
+    #[test]
+    fn reload_replaces_the_matching_reference_in_place() {
+        let dir = TestDir::new("load-midi");
+        let first_path = dir.join("reload-first.mid");
+        let second_path = dir.join("reload-second.mid");
+
+        let loader = Arc::new(StubLoader::new(vec![
+            Ok(sample_reference_data(4, 8, 60, 67, "first")),
+            Ok(sample_reference_data(4, 8, 48, 55, "second")),
+            Ok(sample_reference_data(8, 16, 62, 74, "first-edited")),
+        ]));
+        let use_case = LoadMidiUseCase::with_loader(loader);
+        for path in [&first_path, &second_path] {
+            use_case
+                .execute(LoadMidiCommand::SetFile {
+                    slot: ReferenceSlot::Melody,
+                    path: path.to_string_lossy().to_string(),
+                })
+                .expect("initial load should succeed");
+        }
+
+        let outcome = use_case
+            .execute(LoadMidiCommand::ReloadFile {
+                slot: ReferenceSlot::Melody,
+                path: first_path.to_string_lossy().to_string(),
+            })
+            .expect("reload should succeed");
+
+        assert!(matches!(
+            outcome,
+            LoadMidiOutcome::Loaded {
+                slot_reference_count: 2,
+                ..
+            }
+        ));
+        let references = use_case.slot_references(ReferenceSlot::Melody);
+        assert_eq!(references.len(), 2);
+        assert_eq!(references[0].bars, 8);
+        assert_eq!(references[0].min_pitch, 62);
+        assert_eq!(references[1].min_pitch, 48);
+    }
+
+    #[test]
+    fn multiple_files_are_concatenated_into_one_reference_on_bar_boundaries() {
+        let dir = TestDir::new("load-midi");
+        let intro_path = dir.join("clip-intro.mid");
+        let verse_path = dir.join("clip-verse.mid");
+        let mut verse = sample_reference_data(1, 4, 48, 60, "verse");
+        verse.summary.ticks_per_bar = 960;
+        verse.events = vec![
+            MidiReferenceEvent {
+                track: 0,
+                absolute_tick: 0,
+                delta_tick: 0,
+                event: "Event(verse-start)".to_string(),
+            },
+            MidiReferenceEvent {
+                track: 0,
+                absolute_tick: 480,
+                delta_tick: 480,
+                event: "Event(verse-half)".to_string(),
+            },
+        ];
+
+        let loader = Arc::new(StubLoader::new(vec![
+            Ok(sample_reference_data(2, 8, 60, 72, "intro")),
+            Ok(verse),
+            Ok(sample_reference_data(2, 8, 62, 74, "intro-edited")),
+            Ok(sample_reference_data(1, 4, 48, 60, "verse-edited")),
+        ]));
+        let use_case = LoadMidiUseCase::with_loader(loader.clone());
+
+        use_case
+            .execute(LoadMidiCommand::SetFiles {
+                slot: ReferenceSlot::Bassline,
+                paths: vec![
+                    intro_path.to_string_lossy().to_string(),
+                    verse_path.to_string_lossy().to_string(),
+                ],
+            })
+            .expect("clips should load");
+
+        let reference = use_case
+            .slot_reference(ReferenceSlot::Bassline)
+            .expect("concatenated reference should be stored");
+        assert_eq!(reference.bars, 3);
+        assert_eq!(reference.note_count, 12);
+        assert_eq!((reference.min_pitch, reference.max_pitch), (48, 72));
+        assert_eq!(
+            reference.file.expect("file metadata must exist").path,
+            intro_path.to_string_lossy()
+        );
+        let ticks = reference
+            .events
+            .iter()
+            .map(|event| (event.absolute_tick, event.delta_tick))
+            .collect::<Vec<_>>();
+        assert_eq!(ticks, vec![(0, 0), (3_840, 3_840), (4_800, 960)]);
+
+        use_case
+            .execute(LoadMidiCommand::ReloadFile {
+                slot: ReferenceSlot::Bassline,
+                path: verse_path.to_string_lossy().to_string(),
+            })
+            .expect("reloading one clip should reload the whole reference");
+        let references = use_case.slot_references(ReferenceSlot::Bassline);
+        assert_eq!(references.len(), 1);
+        assert_eq!(references[0].min_pitch, 48);
+        assert_eq!(references[0].max_pitch, 74);
+        assert_eq!(
+            use_case.slot_clip_paths(ReferenceSlot::Bassline),
+            vec![vec![
+                intro_path.to_string_lossy().to_string(),
+                verse_path.to_string_lossy().to_string(),
+            ]]
+        );
+        assert_eq!(loader.seen_paths().len(), 4);
+    }
+
+    #[test]
+    fn cleared_slot_can_be_restored_from_a_snapshot() {
+        let dir = TestDir::new("load-midi");
+        let first_path = dir.join("undo-first.mid");
+        let second_path = dir.join("undo-second.mid");
+        let loader = Arc::new(StubLoader::new(vec![
+            Ok(sample_reference_data(4, 8, 60, 67, "first")),
+            Ok(sample_reference_data(2, 4, 48, 55, "second")),
+        ]));
+        let use_case = LoadMidiUseCase::with_loader(loader);
+        for path in [&first_path, &second_path] {
+            use_case
+                .execute(LoadMidiCommand::SetFile {
+                    slot: ReferenceSlot::Harmony,
+                    path: path.to_string_lossy().to_string(),
+                })
+                .expect("load should succeed");
+        }
+
+        let snapshot = use_case.snapshot_slot(ReferenceSlot::Harmony);
+        use_case
+            .execute(LoadMidiCommand::ClearSlot {
+                slot: ReferenceSlot::Harmony,
+            })
+            .expect("clear should succeed");
+        assert!(use_case.slot_references(ReferenceSlot::Harmony).is_empty());
+
+        assert_eq!(snapshot.reference_count(), 2);
+        assert_eq!(use_case.restore_slot(snapshot), 2);
+        let restored = use_case.slot_references(ReferenceSlot::Harmony);
+        assert_eq!(restored[0].min_pitch, 60);
+        assert_eq!(restored[1].min_pitch, 48);
+        assert_eq!(
+            use_case.slot_clip_paths(ReferenceSlot::Harmony)[1],
+            vec![second_path.to_string_lossy().to_string()]
+        );
+    }
+
     #[test]
     fn empty_path_is_rejected_without_invoking_loader() {
         let loader = Arc::new(StubLoader::new(Vec::new()));
@@ -547,6 +956,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn clips_without_notes_leave_the_pitch_range_to_the_others() {
+        let dir = TestDir::new("load-midi");
+        let loader = Arc::new(StubLoader::new(vec![
+            Ok(sample_reference_data(1, 0, 0, 0, "count-in")),
+            Ok(sample_reference_data(2, 8, 60, 72, "verse")),
+            Ok(sample_reference_data(1, 0, 0, 0, "rest")),
+        ]));
+        let use_case = LoadMidiUseCase::with_loader(loader);
+
+        use_case
+            .execute(LoadMidiCommand::SetFiles {
+                slot: ReferenceSlot::Melody,
+                paths: vec![
+                    dir.join("clip-count-in.mid").to_string_lossy().to_string(),
+                    dir.join("clip-verse.mid").to_string_lossy().to_string(),
+                    dir.join("clip-rest.mid").to_string_lossy().to_string(),
+                ],
+            })
+            .expect("clips should load");
+
+        let reference = use_case
+            .slot_reference(ReferenceSlot::Melody)
+            .expect("concatenated reference should be stored");
+        assert_eq!(reference.bars, 4);
+        assert_eq!(reference.note_count, 8);
+        assert_eq!((reference.min_pitch, reference.max_pitch), (60, 72));
+    }
+
     fn sample_reference_data(
         bars: u16,
         note_count: u32,
@@ -560,6 +998,7 @@ mod tests {
                 note_count,
                 min_pitch,
                 max_pitch,
+                ticks_per_bar: 1_920,
             },
             events: vec![MidiReferenceEvent {
                 track: 0,
@@ -569,8 +1008,4 @@ mod tests {
             }],
         }
     }
-
-    fn temp_test_path(file_name: &str) -> PathBuf {
-        std::env::temp_dir().join(format!("sonant-load-midi-use-case-{file_name}"))
-    }
 }
@@ -190,7 +190,9 @@ impl MidiInputRouter {
         };
 
         LiveReferenceMetrics {
-            bar_count: slot_buffer.bars.len(),
+            bar_count: slot_buffer
+                .final_bar_count
+                .unwrap_or(slot_buffer.bars.len()),
             event_count: slot_buffer
                 .bars
                 .values()
@@ -232,6 +234,8 @@ impl MidiInputRouterState {
 #[derive(Debug, Default)]
 struct SlotBuffer {
     bars: BTreeMap<u64, VecDeque<LiveInputEvent>>,
+    /// Bars from the first recorded bar through the stop position, set when the take finalizes.
+    final_bar_count: Option<usize>,
 }
 
 fn default_channel_to_slot_map() -> HashMap<u8, ReferenceSlot> {
@@ -318,6 +322,15 @@ fn update_transport_state_locked(
     is_playing: bool,
     playhead_ppq: f64,
 ) {
+    if state.is_playing && !is_playing {
+        // Hosts may jump the playhead back on stop, so the take ends where playback last was.
+        let stop_ppq = normalize_playhead_ppq(playhead_ppq)
+            .map_or(state.playhead_ppq, |ppq| ppq.max(state.playhead_ppq));
+        for slot_buffer in state.slot_buffers.values_mut() {
+            finalize_take(slot_buffer, stop_ppq);
+        }
+    }
+
     let should_reset_active_writes =
         !is_playing || !state.is_playing || transport_rewound(state.playhead_ppq, playhead_ppq);
 
@@ -362,6 +375,7 @@ fn push_live_event_locked(
     }
 
     let slot_buffer = state.slot_buffers.entry(slot).or_default();
+    slot_buffer.final_bar_count = None;
     let bar_events = slot_buffer
         .bars
         .entry(bar_index)
@@ -373,6 +387,53 @@ fn push_live_event_locked(
     bar_events.push_back(event);
 }
 
+/// Closes notes still held at `stop_ppq` and fixes the take length to the stop position.
+fn finalize_take(slot_buffer: &mut SlotBuffer, stop_ppq: f64) {
+    if slot_buffer.final_bar_count.is_some() {
+        return;
+    }
+    let (Some(&first_bar), Some(&last_bar)) = (
+        slot_buffer.bars.keys().next(),
+        slot_buffer.bars.keys().next_back(),
+    ) else {
+        return;
+    };
+
+    let mut held_notes = Vec::<LiveInputEvent>::new();
+    for event in slot_buffer.bars.values().flatten() {
+        let status = event.data[0] & 0xF0;
+        let is_note_on = status == 0x90 && event.data[2] > 0;
+        let is_note_off = status == 0x80 || (status == 0x90 && event.data[2] == 0);
+        if !is_note_on && !is_note_off {
+            continue;
+        }
+        held_notes.retain(|held| {
+            held.data[0] & 0x0F != event.data[0] & 0x0F || held.data[1] != event.data[1]
+        });
+        if is_note_on {
+            held_notes.push(*event);
+        }
+    }
+
+    let stop_ppq = normalize_playhead_ppq(stop_ppq).unwrap_or(0.0);
+    if let Some(last_bar_events) = slot_buffer.bars.get_mut(&last_bar) {
+        last_bar_events.extend(held_notes.into_iter().map(|note_on| LiveInputEvent {
+            time: 0,
+            port_index: note_on.port_index,
+            data: [0x80 | (note_on.data[0] & 0x0F), note_on.data[1], 0],
+            is_transport_playing: false,
+            playhead_ppq: stop_ppq.max(note_on.playhead_ppq),
+        }));
+    }
+
+    let end_bar = ((stop_ppq / PPQ_PER_BAR).ceil() as u64).max(last_bar + 1);
+    slot_buffer.final_bar_count = Some(
+        usize::try_from(end_bar - first_bar)
+            .unwrap_or(usize::MAX)
+            .max(slot_buffer.bars.len()),
+    );
+}
+
 fn trim_old_bars(slot_buffer: &mut SlotBuffer, max_bars_per_slot: usize) {
     while slot_buffer.bars.len() > max_bars_per_slot {
         let Some((&oldest_bar, _)) = slot_buffer.bars.first_key_value() else {
@@ -707,6 +768,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn transport_stop_closes_held_notes_and_counts_bars_to_stop_position() {
+        let router = MidiInputRouter::new();
+        router
+            .set_recording_channel_enabled(1, true)
+            .expect("channel 1 should be valid");
+
+        router.update_transport_state(true, 0.0);
+        router.push_live_event(1, note_on(1, 60));
+        router.update_transport_state(true, 10.5);
+        router.update_transport_state(false, 0.0);
+
+        let snapshot = router.snapshot_reference(ReferenceSlot::Melody);
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot[1].data, [0x80, 60, 0]);
+        assert_eq!(snapshot[1].playhead_ppq, 10.5);
+        assert_eq!(
+            router.reference_metrics(ReferenceSlot::Melody),
+            LiveReferenceMetrics {
+                bar_count: 3,
+                event_count: 2
+            }
+        );
+    }
+
     #[test]
     fn reference_metrics_are_empty_for_unrecorded_slot() {
         let router = MidiInputRouter::new();
@@ -1,23 +1,143 @@
+mod applied_clip;
+mod apply_routing;
+mod arrangement;
+mod batch_prompts;
+mod candidate_autosave;
+mod candidate_explanations;
+mod candidate_naming;
+mod config_dir;
+mod deferred_requests;
+mod diagnostics;
+mod drum_map;
+mod drum_step_pattern;
+mod export_naming;
+mod export_programs;
+mod generation_history;
 mod generation_job_manager;
 mod generation_service;
+mod helper_control_ipc;
 mod input_track_model;
+mod input_track_presets;
 mod live_input_ipc;
 mod live_midi_capture;
+mod live_take_file;
 mod load_midi_use_case;
 mod midi_input_router;
+mod model_comparison;
+mod onboarding;
+mod prompt_suggestions;
+mod provider_benchmark;
+mod provider_error_budget;
+mod recent_files;
+mod reference_file_watcher;
+mod reference_sketch;
+mod request_replay;
+mod sampling_profiles;
+mod stem_export;
+mod storage_quota;
 
-pub use generation_job_manager::{GenerationJobManager, GenerationJobState, GenerationJobUpdate};
-pub use generation_service::{GenerationRetryConfig, GenerationService};
+pub use applied_clip::{AppliedClip, AppliedClipEvent, AppliedNoteExpression};
+pub use apply_routing::{
+    AppliedClipSink, ApplyDestination, ApplyError, ApplyFileTarget, ApplyOutcome,
+    applied_file_name, dispatch_apply,
+};
+pub use arrangement::{
+    ARRANGEMENT_SECTION_MAX_BARS, ArrangementError, ArrangementRun, ArrangementSection,
+};
+pub use batch_prompts::{
+    BatchPromptError, BatchRun, batch_request, load_batch_prompts, parse_batch_prompts,
+};
+pub use candidate_autosave::{CandidateAutosaveError, autosave_candidates, autosave_file_name};
+pub use candidate_explanations::{
+    CANDIDATE_EXPLANATION_CACHE_MAX_ENTRIES, CANDIDATE_EXPLANATION_MAX_CHARS, CandidateExplanation,
+    CandidateExplanationCache,
+};
+pub use candidate_naming::candidate_name;
+pub use config_dir::{SONANT_CONFIG_DIR_ENV, sonant_config_dir};
+pub use deferred_requests::{
+    DEFERRED_REQUESTS_MAX, DEFERRED_RETRY_INTERVAL, DeferredOutcome, DeferredRequest,
+    DeferredRequestQueue,
+};
+pub use diagnostics::{
+    CLAP_BUNDLE_NAME, CLAP_PATH_ENV, DiagnosticCheck, DiagnosticStatus, check_api_keys,
+    check_clap_bundle, check_config_files, check_ipc_sockets, check_provider_reachability,
+    clap_search_dirs, run_diagnostics,
+};
+pub use drum_map::{DrumChokeGroup, DrumMap};
+pub use drum_step_pattern::{
+    DRUM_STEP_COUNTS, DRUM_STEP_LANES, DrumHit, DrumLane, DrumStepPattern, DrumStepPatternError,
+    drum_step_pattern_file_name, write_drum_step_pattern,
+};
+pub use export_naming::{
+    DEFAULT_EXPORT_NAME_TEMPLATE, ExportNameContext, ExportNameTemplate, ExportNameTemplateError,
+};
+pub use export_programs::{
+    GM_EXPORT_PROGRAM_CHOICES, TrackProgram, default_track_programs, gm_program_name,
+    next_export_program, program_for_slot,
+};
+pub use generation_history::{
+    CANDIDATE_ANNOTATION_MAX_CHARS, GENERATION_HISTORY_MAX_BYTES, GENERATION_HISTORY_MAX_ENTRIES,
+    GenerationHistoryEntry, GenerationHistoryError, GenerationHistoryExportFormat,
+    GenerationHistoryStore,
+};
+pub use generation_job_manager::{
+    GENERATION_JOB_DEFAULT_DEADLINE, GENERATION_JOB_DUPLICATE_WINDOW, GenerationJobManager,
+    GenerationJobState, GenerationJobUpdate, JobSubmission,
+};
+pub use generation_service::{GenerationRetryConfig, GenerationService, ScheduledRetry};
+pub use helper_control_ipc::{
+    HELPER_CONTROL_IPC_SOCKET_ENV, HELPER_WINDOW_MIN_HEIGHT, HELPER_WINDOW_MIN_WIDTH,
+    HELPER_WINDOW_SIZE_ENV, HelperControlIpcSender, HelperControlIpcSource, HelperControlMessage,
+    HelperControlSendError, INPUT_TRACK_LAYOUT_ENV, PROJECT_MODEL_ENV, parse_helper_window_size,
+};
 pub use input_track_model::{
-    ChannelMapping, InputTrackModel, InputTrackModelError, MIDI_CHANNEL_MAX, MIDI_CHANNEL_MIN,
-    default_live_channel_mappings,
+    ChannelMapping, InputTrackLayout, InputTrackModel, InputTrackModelError, MIDI_CHANNEL_MAX,
+    MIDI_CHANNEL_MIN, SlotSourceAssignment, default_live_channel_mappings,
+    validate_default_channel_mappings,
+};
+pub use input_track_presets::{
+    InputTrackPreset, InputTrackPresetError, InputTrackPresetStore, builtin_presets,
 };
 pub use live_input_ipc::{LIVE_INPUT_IPC_SOCKET_ENV, LiveInputIpcSender, LiveInputIpcSource};
 pub use live_midi_capture::{
-    LiveInputEvent, LiveInputEventSource, LiveMidiCapture, LiveMidiCaptureConfigError,
+    HostTransportContext, LOOP_WRAP_MARKER_DATA, LiveInputBatch, LiveInputEvent,
+    LiveInputEventSource, LiveMidiCapture, LiveMidiCaptureConfigError, QueueOverflowMetrics,
+    apply_sustain_pedal_to_live_events, apply_velocity_profile_to_live_events,
+};
+pub use live_take_file::{
+    LiveTakeFileError, live_take_file_name, live_take_notes, staged_live_take_file_name,
+    write_live_take,
 };
 pub use load_midi_use_case::{
     FileMidiReferenceLoader, LoadMidiCommand, LoadMidiError, LoadMidiOutcome, LoadMidiUseCase,
-    MidiReferenceLoader,
+    MidiReferenceLoader, SlotReferenceSnapshot,
 };
 pub use midi_input_router::{LiveReferenceMetrics, MidiInputRouter, MidiInputRouterError};
+pub use model_comparison::{
+    MODEL_COMPARISON_MAX_MODELS, MODEL_COMPARISON_MIN_MODELS, ModelComparison,
+    ModelComparisonOutcome, validate_comparison_requests,
+};
+pub use onboarding::{OnboardingError, OnboardingMarker};
+pub use prompt_suggestions::{
+    PROMPT_SUGGESTION_MAX, PromptSuggestion, insert_prompt_snippet, suggest_prompt_snippets,
+};
+pub use provider_benchmark::{
+    BENCHMARK_DEFAULT_RUNS, ProviderBenchmark, benchmark_request, run_provider_benchmark,
+};
+pub use provider_error_budget::{ProviderDemotion, ProviderErrorBudget, ProviderErrorBudgetConfig};
+pub use recent_files::{RECENT_FILES_MAX_ENTRIES, RecentFilesError, RecentFilesStore};
+pub use reference_file_watcher::{ReferenceFileChange, ReferenceFileWatcher};
+pub use reference_sketch::{
+    ReferenceSketch, SKETCH_BAR_CHOICES, SKETCH_PITCH_ROWS, SKETCH_STEPS_PER_BAR,
+};
+pub use request_replay::{RequestReplayError, load_generation_request, parse_generation_request};
+pub use sampling_profiles::{
+    SamplingProfile, SamplingProfileError, SamplingProfileStore, builtin_profiles,
+};
+pub use stem_export::{
+    STEM_MANIFEST_FILE_NAME, StemExportError, StemManifest, StemManifestEntry, StemPart,
+    StemSource, export_stems, stem_file_name,
+};
+pub use storage_quota::{
+    AutoSaveQuota, FolderUsage, QuotaEviction, enforce_folder_quota, folder_usage, format_byte_size,
+};
@@ -0,0 +1,265 @@
+use std::thread;
+
+use crate::domain::{GenerationCandidate, GenerationRequest, GenerationResult, LlmError, ModelRef};
+
+use super::GenerationService;
+
+pub const MODEL_COMPARISON_MIN_MODELS: usize = 2;
+pub const MODEL_COMPARISON_MAX_MODELS: usize = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelComparisonOutcome {
+    pub request_id: String,
+    pub model: ModelRef,
+    pub result: Result<GenerationResult, LlmError>,
+}
+
+/// Results of one prompt sent to several models at once, in the order the models were picked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelComparison {
+    pub outcomes: Vec<ModelComparisonOutcome>,
+}
+
+impl ModelComparison {
+    pub fn succeeded_results(&self) -> impl Iterator<Item = &GenerationResult> {
+        self.outcomes
+            .iter()
+            .filter_map(|outcome| outcome.result.as_ref().ok())
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = (&ModelRef, &LlmError)> {
+        self.outcomes.iter().filter_map(|outcome| {
+            outcome
+                .result
+                .as_ref()
+                .err()
+                .map(|error| (&outcome.model, error))
+        })
+    }
+
+    /// Every candidate grouped by model. Ids are prefixed with the model so candidates from
+    /// different models stay distinguishable in a single list.
+    pub fn candidates_by_model(&self) -> Vec<(ModelRef, GenerationCandidate)> {
+        self.succeeded_results()
+            .flat_map(|result| {
+                result.candidates.iter().map(|candidate| {
+                    let mut candidate = candidate.clone();
+                    candidate.id = format!("{}/{}", result.model.model, candidate.id);
+                    (result.model.clone(), candidate)
+                })
+            })
+            .collect()
+    }
+}
+
+/// Checks that `requests` target between two and three distinct models.
+pub fn validate_comparison_requests(requests: &[GenerationRequest]) -> Result<(), LlmError> {
+    if !(MODEL_COMPARISON_MIN_MODELS..=MODEL_COMPARISON_MAX_MODELS).contains(&requests.len()) {
+        return Err(LlmError::validation(format!(
+            "model comparison needs {MODEL_COMPARISON_MIN_MODELS} to {MODEL_COMPARISON_MAX_MODELS} models, got {}",
+            requests.len()
+        )));
+    }
+    for (index, request) in requests.iter().enumerate() {
+        if requests[..index]
+            .iter()
+            .any(|earlier| earlier.model == request.model)
+        {
+            return Err(LlmError::validation(format!(
+                "model comparison lists {}/{} more than once",
+                request.model.provider, request.model.model
+            )));
+        }
+    }
+    Ok(())
+}
+
+impl GenerationService {
+    /// Runs each request on its own thread and waits for all of them. Fails only when every
+    /// model failed, returning the first model's error.
+    pub fn compare_models<F>(
+        &self,
+        requests: Vec<GenerationRequest>,
+        is_cancelled: F,
+    ) -> Result<ModelComparison, LlmError>
+    where
+        F: Fn() -> bool + Sync,
+    {
+        validate_comparison_requests(&requests)?;
+
+        let outcomes = thread::scope(|scope| {
+            let handles = requests
+                .into_iter()
+                .map(|request| {
+                    let request_id = request.request_id.clone();
+                    let model = request.model.clone();
+                    let is_cancelled = &is_cancelled;
+                    let handle =
+                        scope.spawn(move || self.generate_with_cancel(request, is_cancelled));
+                    (request_id, model, handle)
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .map(|(request_id, model, handle)| ModelComparisonOutcome {
+                    request_id,
+                    model,
+                    result: handle.join().unwrap_or_else(|_| {
+                        Err(LlmError::internal("model comparison worker panicked"))
+                    }),
+                })
+                .collect::<Vec<_>>()
+        });
+
+        if outcomes.iter().all(|outcome| outcome.result.is_err())
+            && let Some(Err(error)) = outcomes.first().map(|outcome| outcome.result.clone())
+        {
+            return Err(error);
+        }
+        Ok(ModelComparison { outcomes })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::validate_comparison_requests;
+    use crate::app::GenerationService;
+    use crate::domain::{
+        GENERATION_CONTRACT_VERSION, GeneratedNote, GenerationCandidate, GenerationMetadata,
+        GenerationMode, GenerationParams, GenerationRequest, GenerationResult, LlmError, ModelRef,
+        TickResolution,
+    };
+    use crate::infra::llm::{LlmProvider, ProviderRegistry};
+
+    struct EchoProvider;
+
+    impl LlmProvider for EchoProvider {
+        fn provider_id(&self) -> &str {
+            "echo"
+        }
+
+        fn supports_model(&self, model_id: &str) -> bool {
+            model_id.starts_with("echo-")
+        }
+
+        fn generate(&self, request: &GenerationRequest) -> Result<GenerationResult, LlmError> {
+            if request.model.model.contains("broken") {
+                return Err(LlmError::invalid_response("broken model"));
+            }
+            Ok(GenerationResult {
+                request_id: request.request_id.clone(),
+                model: request.model.clone(),
+                candidates: vec![GenerationCandidate {
+                    id: "cand-1".to_string(),
+                    bars: 1,
+                    notes: vec![GeneratedNote {
+                        pitch: 60,
+                        start_tick: 0,
+                        duration_tick: 240,
+                        velocity: 100,
+                        channel: 1,
+                    }],
+                    tick_resolution: TickResolution::DEFAULT,
+                    score_hint: None,
+                    title: None,
+                    control_events: Vec::new(),
+                    processing: None,
+                }],
+                metadata: GenerationMetadata::default(),
+                contract_version: GENERATION_CONTRACT_VERSION,
+            })
+        }
+    }
+
+    fn request(request_id: &str, model: &str) -> GenerationRequest {
+        GenerationRequest {
+            request_id: request_id.to_string(),
+            model: ModelRef {
+                provider: "echo".to_string(),
+                model: model.to_string(),
+            },
+            mode: GenerationMode::Melody,
+            prompt: "compare me".to_string(),
+            params: GenerationParams {
+                bpm: 120,
+                key: "C".to_string(),
+                scale: "major".to_string(),
+                density: 2,
+                complexity: 2,
+                syncopation: 1,
+                tick_resolution: TickResolution::DEFAULT,
+                temperature: None,
+                top_p: None,
+                max_tokens: None,
+            },
+            references: Vec::new(),
+            variation_count: 1,
+            locked_notes: Vec::new(),
+            contract_version: GENERATION_CONTRACT_VERSION,
+            daw_context: None,
+            constraints: None,
+            validation: None,
+            anonymize_references: false,
+        }
+    }
+
+    fn service() -> GenerationService {
+        let mut registry = ProviderRegistry::new();
+        registry
+            .register(EchoProvider)
+            .expect("provider registration should succeed");
+        GenerationService::new(registry)
+    }
+
+    #[test]
+    fn comparison_requires_two_to_three_distinct_models() {
+        assert!(validate_comparison_requests(&[request("r1", "echo-a")]).is_err());
+        assert!(
+            validate_comparison_requests(&[request("r1", "echo-a"), request("r2", "echo-a")])
+                .is_err()
+        );
+        assert!(
+            validate_comparison_requests(&[
+                request("r1", "echo-a"),
+                request("r2", "echo-b"),
+                request("r3", "echo-c"),
+                request("r4", "echo-d"),
+            ])
+            .is_err()
+        );
+        assert!(
+            validate_comparison_requests(&[request("r1", "echo-a"), request("r2", "echo-b")])
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn comparison_keeps_model_order_and_partial_failures() {
+        let comparison = service()
+            .compare_models(
+                vec![
+                    request("r1", "echo-a"),
+                    request("r2", "echo-broken"),
+                    request("r3", "echo-c"),
+                ],
+                || false,
+            )
+            .expect("comparison should succeed when any model succeeds");
+
+        let grouped = comparison.candidates_by_model();
+        assert_eq!(grouped.len(), 2);
+        assert_eq!(grouped[0].0.model, "echo-a");
+        assert_eq!(grouped[0].1.id, "echo-a/cand-1");
+        assert_eq!(grouped[1].0.model, "echo-c");
+        let failures = comparison.failures().collect::<Vec<_>>();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0.model, "echo-broken");
+
+        let all_failed = service().compare_models(
+            vec![request("r1", "echo-broken"), request("r2", "echo-broken-2")],
+            || false,
+        );
+        assert_eq!(all_failed, Err(LlmError::invalid_response("broken model")));
+    }
+}
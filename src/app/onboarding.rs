@@ -0,0 +1,76 @@
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use crate::app::input_track_presets::write_file_atomically;
+use crate::app::sonant_config_dir;
+
+pub(crate) const ONBOARDING_MARKER_FILE_NAME: &str = "onboarding_complete";
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum OnboardingError {
+    #[error("failed to record onboarding progress: {message}")]
+    Io { message: String },
+}
+
+/// Remembers that the first-run wizard was finished or skipped so it is only offered once.
+#[derive(Debug, Clone, Default)]
+pub struct OnboardingMarker {
+    path: Option<PathBuf>,
+    complete: bool,
+}
+
+impl OnboardingMarker {
+    pub fn open_default() -> Self {
+        match sonant_config_dir() {
+            Some(dir) => Self::open(dir.join(ONBOARDING_MARKER_FILE_NAME)),
+            None => Self::in_memory(),
+        }
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref().to_path_buf();
+        Self {
+            complete: path.exists(),
+            path: Some(path),
+        }
+    }
+
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    pub fn mark_complete(&mut self) -> Result<(), OnboardingError> {
+        if self.complete {
+            return Ok(());
+        }
+        if let Some(path) = self.path.as_ref() {
+            write_file_atomically(path, b"").map_err(|error| OnboardingError::Io {
+                message: error.to_string(),
+            })?;
+        }
+        self.complete = true;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OnboardingMarker;
+    use crate::test_support::TestDir;
+
+    #[test]
+    fn completed_onboarding_is_remembered_across_opens() {
+        let dir = TestDir::new("onboarding");
+        let path = dir.join("onboarding_complete");
+
+        let mut marker = OnboardingMarker::open(&path);
+        assert!(!marker.is_complete());
+        marker.mark_complete().expect("marker should be written");
+        assert!(OnboardingMarker::open(&path).is_complete());
+    }
+}
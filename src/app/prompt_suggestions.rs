@@ -0,0 +1,257 @@
+use crate::domain::{GenerationMode, MidiReferenceSummary};
+use crate::infra::midi::{ReferenceAnalysis, analyze_reference};
+
+pub const PROMPT_SUGGESTION_MAX: usize = 5;
+// Below this profile correlation the detected key is a guess and is not suggested.
+const KEY_CONFIDENCE_MIN: f64 = 0.6;
+const SPARSE_NOTES_PER_BAR: f64 = 4.0;
+const BUSY_NOTES_PER_BAR: f64 = 10.0;
+const NARROW_RANGE_SEMITONES: u8 = 12;
+const WIDE_RANGE_SEMITONES: u8 = 24;
+
+/// A phrase the user can add to the prompt; `label` is the short text shown on its chip.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptSuggestion {
+    pub label: String,
+    pub snippet: String,
+}
+
+impl PromptSuggestion {
+    fn new(label: impl Into<String>, snippet: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            snippet: snippet.into(),
+        }
+    }
+}
+
+/// Proposes prompt phrasing from the references' key, tempo, density, and range, most specific
+/// first, followed by a phrase for the generation mode. Works entirely offline.
+pub fn suggest_prompt_snippets(
+    mode: GenerationMode,
+    references: &[MidiReferenceSummary],
+) -> Vec<PromptSuggestion> {
+    let analyses = references
+        .iter()
+        .filter(|reference| reference.note_count > 0)
+        .map(analyze_reference)
+        .collect::<Vec<_>>();
+    let mut suggestions = Vec::new();
+
+    if mode != GenerationMode::DrumPattern
+        && let Some(key) = analyses
+            .iter()
+            .filter_map(|analysis| analysis.key)
+            .filter(|key| key.confidence >= KEY_CONFIDENCE_MIN)
+            .max_by(|left, right| left.confidence.total_cmp(&right.confidence))
+    {
+        let name = key.name();
+        suggestions.push(PromptSuggestion::new(
+            name.clone(),
+            format!("in {name} like the reference"),
+        ));
+    }
+
+    if let Some(bpm) = analyses.iter().find_map(|analysis| analysis.tempo_bpm) {
+        let bpm = bpm.round();
+        suggestions.push(PromptSuggestion::new(
+            format!("{bpm:.0} BPM feel"),
+            format!("with the reference's {bpm:.0} BPM pulse"),
+        ));
+    }
+
+    if let Some(notes_per_bar) = mean_notes_per_bar(&analyses) {
+        suggestions.push(if notes_per_bar < SPARSE_NOTES_PER_BAR {
+            PromptSuggestion::new("Sparse", "sparse, leaving space between phrases")
+        } else if notes_per_bar > BUSY_NOTES_PER_BAR {
+            PromptSuggestion::new("Busy", "busy and driving with steady sixteenth notes")
+        } else {
+            PromptSuggestion::new("Moderate", "moderately active rhythm")
+        });
+    }
+
+    if mode != GenerationMode::DrumPattern
+        && let Some((min_pitch, max_pitch)) = pitch_span(&analyses)
+    {
+        let range = max_pitch.saturating_sub(min_pitch);
+        if range <= NARROW_RANGE_SEMITONES {
+            suggestions.push(PromptSuggestion::new(
+                "Stepwise",
+                "mostly stepwise within a narrow range",
+            ));
+        } else if range >= WIDE_RANGE_SEMITONES {
+            suggestions.push(PromptSuggestion::new(
+                "Wide leaps",
+                "using a wide range with expressive leaps",
+            ));
+        }
+    }
+
+    let bars = references.iter().map(|reference| reference.bars).max();
+    suggestions.push(mode_suggestion(mode, bars));
+    suggestions.truncate(PROMPT_SUGGESTION_MAX);
+    suggestions
+}
+
+/// Appends `snippet` to `prompt` with a comma separator, unless the prompt already contains it.
+pub fn insert_prompt_snippet(prompt: &str, snippet: &str) -> String {
+    let trimmed = prompt.trim_end();
+    if trimmed.to_lowercase().contains(&snippet.to_lowercase()) {
+        return prompt.to_string();
+    }
+    if trimmed.is_empty() {
+        return snippet.to_string();
+    }
+    let separator = if trimmed.ends_with([',', '.', ';']) {
+        " "
+    } else {
+        ", "
+    };
+    format!("{trimmed}{separator}{snippet}")
+}
+
+fn mode_suggestion(mode: GenerationMode, bars: Option<u16>) -> PromptSuggestion {
+    match mode {
+        GenerationMode::Melody => {
+            PromptSuggestion::new("Call and response", "singable call-and-response phrases")
+        }
+        GenerationMode::ChordProgression => PromptSuggestion::new(
+            "Supportive chords",
+            "chords that support the reference melody",
+        ),
+        GenerationMode::DrumPattern => {
+            PromptSuggestion::new("Tight groove", "tight groove that locks to the reference")
+        }
+        GenerationMode::Bassline => {
+            PromptSuggestion::new("Root motion", "bassline anchored on the chord roots")
+        }
+        GenerationMode::CounterMelody => PromptSuggestion::new(
+            "Fill the gaps",
+            "counter-melody that moves when the reference rests",
+        ),
+        GenerationMode::Harmony => {
+            PromptSuggestion::new("Thirds and sixths", "harmony in thirds and sixths")
+        }
+        GenerationMode::Continuation => match bars {
+            Some(bars) if bars > 0 => PromptSuggestion::new(
+                format!("Continue {bars} bars"),
+                format!("continue for another {bars} bars in the same style"),
+            ),
+            _ => PromptSuggestion::new("Same style", "continue in the same style"),
+        },
+    }
+}
+
+fn mean_notes_per_bar(analyses: &[ReferenceAnalysis]) -> Option<f64> {
+    let (notes, bars) = analyses
+        .iter()
+        .flat_map(|analysis| analysis.notes_per_bar.iter())
+        .fold((0u64, 0u64), |(notes, bars), count| {
+            (notes + u64::from(*count), bars + 1)
+        });
+    (bars > 0).then(|| notes as f64 / bars as f64)
+}
+
+fn pitch_span(analyses: &[ReferenceAnalysis]) -> Option<(u8, u8)> {
+    let min_pitch = analyses.iter().map(|analysis| analysis.min_pitch).min()?;
+    let max_pitch = analyses.iter().map(|analysis| analysis.max_pitch).max()?;
+    Some((min_pitch, max_pitch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PROMPT_SUGGESTION_MAX, insert_prompt_snippet, suggest_prompt_snippets};
+    use crate::domain::{
+        GenerationMode, MidiReferenceEvent, MidiReferenceSummary, ReferenceSlot, ReferenceSource,
+    };
+
+    fn c_major_reference() -> MidiReferenceSummary {
+        // Two bars of a C major scale run in eighth notes.
+        let pitches = [
+            60u8, 62, 64, 65, 67, 69, 71, 72, 72, 71, 69, 67, 65, 64, 62, 60,
+        ];
+        let events = pitches
+            .iter()
+            .enumerate()
+            .flat_map(|(index, pitch)| {
+                let start = index as u32 * 240;
+                [
+                    MidiReferenceEvent {
+                        track: 0,
+                        absolute_tick: start,
+                        delta_tick: 0,
+                        event: format!(
+                            "Midi {{ channel: u4(0), message: NoteOn {{ key: u7({pitch}), vel: u7(96) }} }}"
+                        ),
+                    },
+                    MidiReferenceEvent {
+                        track: 0,
+                        absolute_tick: start + 220,
+                        delta_tick: 220,
+                        event: format!(
+                            "Midi {{ channel: u4(0), message: NoteOff {{ key: u7({pitch}), vel: u7(0) }} }}"
+                        ),
+                    },
+                ]
+            })
+            .collect();
+        MidiReferenceSummary {
+            slot: ReferenceSlot::Melody,
+            source: ReferenceSource::Live,
+            file: None,
+            bars: 2,
+            note_count: pitches.len() as u32,
+            density_hint: 0.5,
+            min_pitch: 60,
+            max_pitch: 72,
+            events,
+            transposition: None,
+            tempo: None,
+        }
+    }
+
+    #[test]
+    fn suggestions_follow_reference_analysis_and_mode() {
+        let melody = suggest_prompt_snippets(GenerationMode::Melody, &[c_major_reference()]);
+        assert!(melody.len() <= PROMPT_SUGGESTION_MAX);
+        assert_eq!(melody[0].label, "C major");
+        assert!(
+            melody
+                .iter()
+                .any(|suggestion| suggestion.label == "Moderate")
+        );
+        assert!(
+            melody
+                .iter()
+                .any(|suggestion| suggestion.label == "Stepwise")
+        );
+
+        let drums = suggest_prompt_snippets(GenerationMode::DrumPattern, &[c_major_reference()]);
+        assert!(drums.iter().all(|suggestion| suggestion.label != "C major"));
+        assert_eq!(
+            drums.last().map(|suggestion| suggestion.label.as_str()),
+            Some("Tight groove")
+        );
+
+        let continuation = suggest_prompt_snippets(GenerationMode::Continuation, &[]);
+        assert_eq!(continuation.len(), 1);
+        assert_eq!(continuation[0].label, "Same style");
+    }
+
+    #[test]
+    fn snippets_are_appended_once_with_a_separator() {
+        assert_eq!(insert_prompt_snippet("", "in C major"), "in C major");
+        assert_eq!(
+            insert_prompt_snippet("warm pad melody ", "in C major"),
+            "warm pad melody, in C major"
+        );
+        assert_eq!(
+            insert_prompt_snippet("Warm melody.", "in C major"),
+            "Warm melody. in C major"
+        );
+        assert_eq!(
+            insert_prompt_snippet("melody In C Major", "in C major"),
+            "melody In C Major"
+        );
+    }
+}
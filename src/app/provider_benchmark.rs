@@ -0,0 +1,190 @@
+use std::time::{Duration, Instant};
+
+use crate::domain::{
+    GENERATION_CONTRACT_VERSION, GenerationMode, GenerationParams, GenerationRequest, LlmError,
+    ModelRef, TickResolution,
+};
+use crate::infra::llm::LlmProvider;
+
+pub const BENCHMARK_DEFAULT_RUNS: usize = 5;
+const BENCHMARK_PROMPT: &str = "Simple one-bar melody in C major for latency benchmarking.";
+
+/// The same small melody request for every provider so latencies are comparable.
+pub fn benchmark_request(model: ModelRef, run_index: usize) -> GenerationRequest {
+    GenerationRequest {
+        request_id: format!("bench-{}-{:02}", model.provider, run_index + 1),
+        model,
+        mode: GenerationMode::Melody,
+        prompt: BENCHMARK_PROMPT.to_string(),
+        params: GenerationParams {
+            bpm: 120,
+            key: "C".to_string(),
+            scale: "major".to_string(),
+            density: 2,
+            complexity: 1,
+            syncopation: 1,
+            tick_resolution: TickResolution::DEFAULT,
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
+        },
+        references: Vec::new(),
+        variation_count: 1,
+        locked_notes: Vec::new(),
+        contract_version: GENERATION_CONTRACT_VERSION,
+        daw_context: None,
+        constraints: None,
+        validation: None,
+        anonymize_references: false,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProviderBenchmark {
+    pub model: ModelRef,
+    /// Round-trip times of the successful runs, in run order.
+    pub latencies: Vec<Duration>,
+    pub failures: Vec<LlmError>,
+}
+
+impl ProviderBenchmark {
+    pub fn runs(&self) -> usize {
+        self.latencies.len() + self.failures.len()
+    }
+
+    pub fn failure_rate(&self) -> f64 {
+        if self.runs() == 0 {
+            return 0.0;
+        }
+        self.failures.len() as f64 / self.runs() as f64
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        let count = u32::try_from(self.latencies.len())
+            .ok()
+            .filter(|count| *count > 0)?;
+        Some(self.latencies.iter().sum::<Duration>() / count)
+    }
+
+    /// Nearest-rank percentile of the successful runs; `percentile` is clamped to 0..=100.
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        if self.latencies.is_empty() {
+            return None;
+        }
+        let mut sorted = self.latencies.clone();
+        sorted.sort_unstable();
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0 * sorted.len() as f64).ceil() as usize;
+        Some(sorted[rank.saturating_sub(1).min(sorted.len() - 1)])
+    }
+}
+
+/// Sends [`benchmark_request`] to `provider` `runs` times in sequence. `on_run` is called
+/// after each attempt so callers can show progress.
+pub fn run_provider_benchmark(
+    provider: &dyn LlmProvider,
+    model: ModelRef,
+    runs: usize,
+    mut on_run: impl FnMut(usize, &Result<Duration, LlmError>),
+) -> ProviderBenchmark {
+    let mut benchmark = ProviderBenchmark {
+        model,
+        latencies: Vec::with_capacity(runs),
+        failures: Vec::new(),
+    };
+
+    for run_index in 0..runs {
+        let request = benchmark_request(benchmark.model.clone(), run_index);
+        let started = Instant::now();
+        let outcome = provider.generate(&request).map(|_| started.elapsed());
+        on_run(run_index, &outcome);
+        match outcome {
+            Ok(latency) => benchmark.latencies.push(latency),
+            Err(error) => benchmark.failures.push(error),
+        }
+    }
+    benchmark
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ProviderBenchmark, benchmark_request, run_provider_benchmark};
+    use crate::domain::{
+        GENERATION_CONTRACT_VERSION, GenerationMetadata, GenerationRequest, GenerationResult,
+        LlmError, ModelRef,
+    };
+    use crate::infra::llm::LlmProvider;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    struct FlakyProvider {
+        calls: AtomicUsize,
+    }
+
+    impl LlmProvider for FlakyProvider {
+        fn provider_id(&self) -> &str {
+            "flaky"
+        }
+
+        fn supports_model(&self, _model_id: &str) -> bool {
+            true
+        }
+
+        fn generate(&self, request: &GenerationRequest) -> Result<GenerationResult, LlmError> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) % 2 == 1 {
+                return Err(LlmError::Timeout);
+            }
+            Ok(GenerationResult {
+                request_id: request.request_id.clone(),
+                model: request.model.clone(),
+                candidates: Vec::new(),
+                metadata: GenerationMetadata::default(),
+                contract_version: GENERATION_CONTRACT_VERSION,
+            })
+        }
+    }
+
+    fn model() -> ModelRef {
+        ModelRef {
+            provider: "flaky".to_string(),
+            model: "m1".to_string(),
+        }
+    }
+
+    #[test]
+    fn benchmark_request_is_valid_and_numbered_per_run() {
+        let request = benchmark_request(model(), 2);
+
+        assert_eq!(request.request_id, "bench-flaky-03");
+        assert!(request.validate().is_ok());
+    }
+
+    #[test]
+    fn runs_record_latencies_failures_and_percentiles() {
+        let provider = FlakyProvider {
+            calls: AtomicUsize::new(0),
+        };
+        let mut reported = Vec::new();
+
+        let benchmark = run_provider_benchmark(&provider, model(), 4, |run, outcome| {
+            reported.push((run, outcome.is_ok()));
+        });
+
+        assert_eq!(reported, vec![(0, true), (1, false), (2, true), (3, false)]);
+        assert_eq!(benchmark.runs(), 4);
+        assert_eq!(benchmark.failure_rate(), 0.5);
+        assert_eq!(
+            benchmark.failures,
+            vec![LlmError::Timeout, LlmError::Timeout]
+        );
+
+        let stats = ProviderBenchmark {
+            model: model(),
+            latencies: [40, 10, 30, 20, 50].map(Duration::from_millis).to_vec(),
+            failures: Vec::new(),
+        };
+        assert_eq!(stats.percentile(50.0), Some(Duration::from_millis(30)));
+        assert_eq!(stats.percentile(90.0), Some(Duration::from_millis(50)));
+        assert_eq!(stats.percentile(0.0), Some(Duration::from_millis(10)));
+        assert_eq!(stats.mean(), Some(Duration::from_millis(30)));
+    }
+}
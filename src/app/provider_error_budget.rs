@@ -0,0 +1,206 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::domain::{LlmError, ModelRef};
+
+const DEFAULT_ERROR_BUDGET_WINDOW_SECS: u64 = 300;
+const DEFAULT_FAILURE_RATE_THRESHOLD: f64 = 0.5;
+const DEFAULT_MIN_SAMPLES: usize = 4;
+const DEFAULT_COOL_DOWN_SECS: u64 = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProviderErrorBudgetConfig {
+    /// Only provider calls newer than this count toward the failure rate.
+    pub window: Duration,
+    /// Failure share, in `0.0..=1.0`, above which a provider is demoted.
+    pub failure_rate_threshold: f64,
+    /// Calls needed in the window before the failure rate is trusted.
+    pub min_samples: usize,
+    pub cool_down: Duration,
+}
+
+impl Default for ProviderErrorBudgetConfig {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(DEFAULT_ERROR_BUDGET_WINDOW_SECS),
+            failure_rate_threshold: DEFAULT_FAILURE_RATE_THRESHOLD,
+            min_samples: DEFAULT_MIN_SAMPLES,
+            cool_down: Duration::from_secs(DEFAULT_COOL_DOWN_SECS),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProviderDemotion {
+    pub provider: String,
+    pub failure_rate: f64,
+    pub until: Instant,
+}
+
+impl ProviderDemotion {
+    pub fn remaining(&self, now: Instant) -> Duration {
+        self.until.saturating_duration_since(now)
+    }
+}
+
+#[derive(Default)]
+struct ErrorBudgetState {
+    outcomes: HashMap<String, VecDeque<(Instant, bool)>>,
+    demotions: HashMap<String, ProviderDemotion>,
+}
+
+/// Rolling per-provider failure rates, shared between the generation worker and the UI.
+#[derive(Clone, Default)]
+pub struct ProviderErrorBudget {
+    config: ProviderErrorBudgetConfig,
+    state: Arc<Mutex<ErrorBudgetState>>,
+}
+
+impl ProviderErrorBudget {
+    pub fn new(config: ProviderErrorBudgetConfig) -> Self {
+        Self {
+            config,
+            state: Arc::default(),
+        }
+    }
+
+    /// Records one provider call; returns the demotion when this call exhausted the budget.
+    pub fn record(
+        &self,
+        provider: &str,
+        outcome: Result<(), &LlmError>,
+        now: Instant,
+    ) -> Option<ProviderDemotion> {
+        let failed = match outcome {
+            Ok(()) => false,
+            Err(error) if counts_against_budget(error) => true,
+            Err(_) => return None,
+        };
+        let mut state = self.state.lock().ok()?;
+        expire_demotions(&mut state, now);
+        if state.demotions.contains_key(provider) {
+            return None;
+        }
+
+        let window_start = now.checked_sub(self.config.window);
+        let outcomes = state.outcomes.entry(provider.to_string()).or_default();
+        outcomes.push_back((now, failed));
+        while outcomes
+            .front()
+            .is_some_and(|(at, _)| window_start.is_some_and(|start| *at < start))
+        {
+            outcomes.pop_front();
+        }
+
+        let samples = outcomes.len();
+        let failures = outcomes.iter().filter(|(_, failed)| *failed).count();
+        let failure_rate = failures as f64 / samples as f64;
+        if samples < self.config.min_samples.max(1)
+            || failure_rate <= self.config.failure_rate_threshold
+        {
+            return None;
+        }
+
+        // A restored provider starts with a clean window instead of its pre-demotion failures.
+        state.outcomes.remove(provider);
+        let demotion = ProviderDemotion {
+            provider: provider.to_string(),
+            failure_rate,
+            until: now + self.config.cool_down,
+        };
+        state
+            .demotions
+            .insert(provider.to_string(), demotion.clone());
+        Some(demotion)
+    }
+
+    pub fn is_demoted(&self, provider: &str, now: Instant) -> bool {
+        self.state.lock().is_ok_and(|mut state| {
+            expire_demotions(&mut state, now);
+            state.demotions.contains_key(provider)
+        })
+    }
+
+    /// Demotions still in their cool-down, soonest-restored first.
+    pub fn demotions(&self, now: Instant) -> Vec<ProviderDemotion> {
+        let Ok(mut state) = self.state.lock() else {
+            return Vec::new();
+        };
+        expire_demotions(&mut state, now);
+        let mut demotions = state.demotions.values().cloned().collect::<Vec<_>>();
+        demotions.sort_by_key(|demotion| demotion.until);
+        demotions
+    }
+
+    /// Moves models of demoted providers behind the healthy ones, keeping the order otherwise.
+    pub fn fallback_order(&self, models: &[ModelRef], now: Instant) -> Vec<ModelRef> {
+        let (healthy, demoted): (Vec<_>, Vec<_>) = models
+            .iter()
+            .cloned()
+            .partition(|model| !self.is_demoted(&model.provider, now));
+        healthy.into_iter().chain(demoted).collect()
+    }
+}
+
+fn expire_demotions(state: &mut ErrorBudgetState, now: Instant) {
+    state.demotions.retain(|_, demotion| demotion.until > now);
+}
+
+// Rejected requests and cancellations say nothing about the provider's health.
+fn counts_against_budget(error: &LlmError) -> bool {
+    !matches!(
+        error,
+        LlmError::Validation { .. } | LlmError::ContentFiltered { .. } | LlmError::Internal { .. }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{ProviderErrorBudget, ProviderErrorBudgetConfig};
+    use crate::domain::{LlmError, ModelRef};
+
+    fn model(provider: &str) -> ModelRef {
+        ModelRef {
+            provider: provider.to_string(),
+            model: format!("{provider}-model"),
+        }
+    }
+
+    #[test]
+    fn failing_provider_is_demoted_until_the_cool_down_ends() {
+        let budget = ProviderErrorBudget::new(ProviderErrorBudgetConfig {
+            window: Duration::from_secs(60),
+            failure_rate_threshold: 0.5,
+            min_samples: 3,
+            cool_down: Duration::from_secs(30),
+        });
+        let start = Instant::now();
+        let timeout = LlmError::Timeout;
+
+        assert!(budget.record("anthropic", Ok(()), start).is_none());
+        assert!(budget.record("anthropic", Err(&timeout), start).is_none());
+        assert!(
+            budget
+                .record("anthropic", Err(&LlmError::validation("bad")), start)
+                .is_none()
+        );
+        let demotion = budget
+            .record("anthropic", Err(&timeout), start)
+            .expect("two of three calls failed");
+        assert_eq!(demotion.until, start + Duration::from_secs(30));
+
+        let models = [model("anthropic"), model("openai_compatible")];
+        assert_eq!(
+            budget.fallback_order(&models, start),
+            vec![model("openai_compatible"), model("anthropic")]
+        );
+
+        let restored = start + Duration::from_secs(31);
+        assert!(!budget.is_demoted("anthropic", restored));
+        assert!(budget.demotions(restored).is_empty());
+        assert_eq!(budget.fallback_order(&models, restored), models.to_vec());
+    }
+}
@@ -0,0 +1,177 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::app::input_track_presets::write_file_atomically;
+use crate::app::sonant_config_dir;
+
+pub(crate) const RECENT_FILES_FILE_NAME: &str = "recent_files.json";
+pub const RECENT_FILES_MAX_ENTRIES: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum RecentFilesError {
+    #[error("failed to access recent files list: {message}")]
+    Io { message: String },
+    #[error("failed to parse recent files list: {message}")]
+    Parse { message: String },
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RecentFilesFile {
+    #[serde(default)]
+    paths: Vec<String>,
+}
+
+/// Reference MIDI paths loaded into FILE tracks, most recently used first.
+#[derive(Debug, Clone, Default)]
+pub struct RecentFilesStore {
+    path: Option<PathBuf>,
+    paths: Vec<String>,
+}
+
+impl RecentFilesStore {
+    pub fn open_default() -> Result<Self, RecentFilesError> {
+        match sonant_config_dir() {
+            Some(dir) => Self::open(dir.join(RECENT_FILES_FILE_NAME)),
+            None => Ok(Self::in_memory()),
+        }
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, RecentFilesError> {
+        let path = path.as_ref().to_path_buf();
+        let paths = match std::fs::read(&path) {
+            Ok(bytes) => {
+                let file: RecentFilesFile =
+                    serde_json::from_slice(&bytes).map_err(|error| RecentFilesError::Parse {
+                        message: error.to_string(),
+                    })?;
+                file.paths
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(error) => {
+                return Err(RecentFilesError::Io {
+                    message: error.to_string(),
+                });
+            }
+        };
+
+        Ok(Self {
+            path: Some(path),
+            paths,
+        })
+    }
+
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    pub fn paths(&self) -> &[String] {
+        &self.paths
+    }
+
+    /// Moves `path` to the front of the list, dropping the oldest entries past
+    /// [`RECENT_FILES_MAX_ENTRIES`].
+    pub fn record(&mut self, path: &str) -> Result<(), RecentFilesError> {
+        let path = path.trim();
+        if path.is_empty() || self.paths.first().is_some_and(|first| first == path) {
+            return Ok(());
+        }
+
+        let mut next = Vec::with_capacity(RECENT_FILES_MAX_ENTRIES);
+        next.push(path.to_string());
+        next.extend(
+            self.paths
+                .iter()
+                .filter(|existing| existing.as_str() != path)
+                .cloned(),
+        );
+        next.truncate(RECENT_FILES_MAX_ENTRIES);
+
+        self.persist(&next)?;
+        self.paths = next;
+        Ok(())
+    }
+
+    pub fn remove(&mut self, path: &str) -> Result<bool, RecentFilesError> {
+        let mut next = self.paths.clone();
+        let before = next.len();
+        next.retain(|existing| existing != path);
+        if next.len() == before {
+            return Ok(false);
+        }
+
+        self.persist(&next)?;
+        self.paths = next;
+        Ok(true)
+    }
+
+    fn persist(&self, paths: &[String]) -> Result<(), RecentFilesError> {
+        let Some(path) = self.path.as_ref() else {
+            return Ok(());
+        };
+
+        let payload = serde_json::to_vec_pretty(&RecentFilesFile {
+            paths: paths.to_vec(),
+        })
+        .map_err(|error| RecentFilesError::Parse {
+            message: error.to_string(),
+        })?;
+        write_file_atomically(path, &payload).map_err(|error| RecentFilesError::Io {
+            message: error.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RECENT_FILES_MAX_ENTRIES, RecentFilesStore};
+    use crate::test_support::TestDir;
+
+    #[test]
+    fn recorded_paths_are_deduplicated_newest_first_and_capped() {
+        let mut store = RecentFilesStore::in_memory();
+
+        for index in 0..RECENT_FILES_MAX_ENTRIES + 2 {
+            store
+                .record(&format!("/midi/{index}.mid"))
+                .expect("path should be recorded");
+        }
+        store
+            .record("/midi/5.mid")
+            .expect("existing path should move to the front");
+        store.record("   ").expect("blank path should be ignored");
+
+        assert_eq!(store.paths().len(), RECENT_FILES_MAX_ENTRIES);
+        assert_eq!(store.paths()[0], "/midi/5.mid");
+        assert_eq!(store.paths()[1], "/midi/11.mid");
+        assert_eq!(
+            store
+                .paths()
+                .iter()
+                .filter(|path| path.as_str() == "/midi/5.mid")
+                .count(),
+            1
+        );
+        assert!(!store.paths().contains(&"/midi/1.mid".to_string()));
+    }
+
+    #[test]
+    fn recorded_paths_are_persisted() {
+        let dir = TestDir::new("recent-files");
+        let path = dir.join("recent_files.json");
+        let mut store = RecentFilesStore::open(&path).expect("store should open");
+
+        store
+            .record("/midi/a.mid")
+            .expect("path should be recorded");
+        store
+            .record("/midi/b.mid")
+            .expect("path should be recorded");
+        assert_eq!(store.remove("/midi/a.mid"), Ok(true));
+        assert_eq!(store.remove("/midi/missing.mid"), Ok(false));
+        let reloaded = RecentFilesStore::open(&path).expect("store should reload");
+
+        assert_eq!(reloaded.paths(), ["/midi/b.mid".to_string()]);
+    }
+}
@@ -0,0 +1,161 @@
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::domain::{MidiReferenceSummary, ReferenceSlot, ReferenceSource};
+
+/// A loaded reference file that was modified on disk after it was loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReferenceFileChange {
+    pub slot: ReferenceSlot,
+    pub path: String,
+}
+
+#[derive(Debug, Clone)]
+struct WatchedReferenceFile {
+    slot: ReferenceSlot,
+    path: String,
+    modified: Option<SystemTime>,
+}
+
+/// Tracks modification times of loaded reference files so edits made in another application
+/// can be reloaded. Call [`Self::sync`] after the loaded set changes and
+/// [`Self::poll_changes`] periodically.
+#[derive(Debug, Default)]
+pub struct ReferenceFileWatcher {
+    files: Vec<WatchedReferenceFile>,
+}
+
+impl ReferenceFileWatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts watching newly loaded file references and forgets the ones that were cleared.
+    pub fn sync(&mut self, references: &[MidiReferenceSummary]) {
+        let loaded = references
+            .iter()
+            .filter(|reference| reference.source == ReferenceSource::File)
+            .filter_map(|reference| {
+                let file = reference.file.as_ref()?;
+                Some((reference.slot, file.path.as_str()))
+            })
+            .collect::<Vec<_>>();
+
+        self.files
+            .retain(|watched| loaded.contains(&(watched.slot, watched.path.as_str())));
+        for (slot, path) in loaded {
+            if !self
+                .files
+                .iter()
+                .any(|watched| watched.slot == slot && watched.path == path)
+            {
+                self.files.push(WatchedReferenceFile {
+                    slot,
+                    path: path.to_string(),
+                    modified: modified_time(Path::new(path)),
+                });
+            }
+        }
+    }
+
+    /// Files whose modification time moved since the last poll. A file that is missing,
+    /// for example while an editor replaces it, is reported once it reappears.
+    pub fn poll_changes(&mut self) -> Vec<ReferenceFileChange> {
+        let mut changes = Vec::new();
+        for watched in &mut self.files {
+            let modified = modified_time(Path::new(&watched.path));
+            if modified.is_some() && modified != watched.modified {
+                changes.push(ReferenceFileChange {
+                    slot: watched.slot,
+                    path: watched.path.clone(),
+                });
+            }
+            watched.modified = modified;
+        }
+        changes
+    }
+
+    pub fn watched_count(&self) -> usize {
+        self.files.len()
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ReferenceFileChange, ReferenceFileWatcher};
+    use crate::domain::{FileReferenceInput, MidiReferenceSummary, ReferenceSlot, ReferenceSource};
+    use crate::test_support::TestDir;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn file_reference(slot: ReferenceSlot, path: &std::path::Path) -> MidiReferenceSummary {
+        MidiReferenceSummary {
+            slot,
+            source: ReferenceSource::File,
+            file: Some(FileReferenceInput {
+                path: path.to_string_lossy().to_string(),
+            }),
+            bars: 1,
+            note_count: 1,
+            density_hint: 0.1,
+            min_pitch: 60,
+            max_pitch: 60,
+            events: Vec::new(),
+            transposition: None,
+            tempo: None,
+        }
+    }
+
+    fn touch(path: &std::path::Path, seconds: u64) {
+        std::fs::File::options()
+            .write(true)
+            .open(path)
+            .expect("watched file should open")
+            .set_modified(UNIX_EPOCH + Duration::from_secs(seconds))
+            .expect("modification time should be settable");
+    }
+
+    #[test]
+    fn modified_files_are_reported_once_per_change() {
+        let dir = TestDir::new("watcher");
+        let path = dir.join("melody.mid");
+        std::fs::write(&path, b"MThd").expect("fixture should be written");
+        touch(&path, 1_000);
+        let mut watcher = ReferenceFileWatcher::new();
+        watcher.sync(&[file_reference(ReferenceSlot::Melody, &path)]);
+
+        assert!(watcher.poll_changes().is_empty());
+        touch(&path, 2_000);
+        assert_eq!(
+            watcher.poll_changes(),
+            vec![ReferenceFileChange {
+                slot: ReferenceSlot::Melody,
+                path: path.to_string_lossy().to_string(),
+            }]
+        );
+        assert!(watcher.poll_changes().is_empty());
+
+        std::fs::remove_file(&path).expect("fixture should be removed");
+        assert!(watcher.poll_changes().is_empty());
+        std::fs::write(&path, b"MThd").expect("fixture should be rewritten");
+        assert_eq!(watcher.poll_changes().len(), 1);
+    }
+
+    #[test]
+    fn cleared_references_stop_being_watched() {
+        let dir = TestDir::new("watcher");
+        let path = dir.join("bass.mid");
+        let mut watcher = ReferenceFileWatcher::new();
+        watcher.sync(&[file_reference(ReferenceSlot::Bassline, &path)]);
+        assert_eq!(watcher.watched_count(), 1);
+
+        watcher.sync(&[]);
+
+        assert_eq!(watcher.watched_count(), 0);
+    }
+}
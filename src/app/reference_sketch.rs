@@ -0,0 +1,303 @@
+use std::collections::BTreeSet;
+
+use crate::domain::{
+    BEATS_PER_BAR, GeneratedNote, MidiReferenceEvent, MidiReferenceSummary, ReferenceSlot,
+    ReferenceSource, TickResolution, calculate_reference_density_hint,
+};
+
+/// Sixteenth-note steps.
+pub const SKETCH_STEPS_PER_BAR: u16 = 16;
+pub const SKETCH_BAR_CHOICES: [u16; 3] = [1, 2, 4];
+/// An octave and its top note, so a scale can be played through to the octave.
+pub const SKETCH_PITCH_ROWS: u8 = 13;
+const SKETCH_DEFAULT_LOW_PITCH: u8 = 60;
+const SKETCH_LOW_PITCH_MAX: u8 = 127 - (SKETCH_PITCH_ROWS - 1);
+const SKETCH_VELOCITY: u8 = 100;
+/// 1-based, as notes carry it.
+const GM_DRUM_CHANNEL: u8 = 10;
+
+/// A short reference drawn on the helper's keyboard and step grid, for users without a
+/// controller. Every note lasts one step. Notes keep their pitch when the keyboard is moved
+/// by an octave, so a sketch can span more than the visible rows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReferenceSketch {
+    bars: u16,
+    low_pitch: u8,
+    cursor: u16,
+    /// `(step, pitch)` pairs.
+    notes: BTreeSet<(u16, u8)>,
+}
+
+impl Default for ReferenceSketch {
+    fn default() -> Self {
+        Self {
+            bars: SKETCH_BAR_CHOICES[0],
+            low_pitch: SKETCH_DEFAULT_LOW_PITCH,
+            cursor: 0,
+            notes: BTreeSet::new(),
+        }
+    }
+}
+
+impl ReferenceSketch {
+    pub fn bars(&self) -> u16 {
+        self.bars
+    }
+
+    pub fn step_count(&self) -> u16 {
+        self.bars * SKETCH_STEPS_PER_BAR
+    }
+
+    /// The lowest visible row; the keyboard shows [`SKETCH_PITCH_ROWS`] keys from here.
+    pub fn low_pitch(&self) -> u8 {
+        self.low_pitch
+    }
+
+    /// The step the next key press is written to.
+    pub fn cursor(&self) -> u16 {
+        self.cursor
+    }
+
+    pub fn note_count(&self) -> usize {
+        self.notes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty()
+    }
+
+    pub fn has_note(&self, step: u16, pitch: u8) -> bool {
+        self.notes.contains(&(step, pitch))
+    }
+
+    /// The next of [`SKETCH_BAR_CHOICES`]. Notes past a shorter end are dropped.
+    pub fn cycle_bars(&mut self) {
+        let position = SKETCH_BAR_CHOICES
+            .iter()
+            .position(|bars| *bars == self.bars)
+            .unwrap_or_default();
+        self.bars = SKETCH_BAR_CHOICES[(position + 1) % SKETCH_BAR_CHOICES.len()];
+        let step_count = self.step_count();
+        self.notes.retain(|(step, _)| *step < step_count);
+        self.cursor = self.cursor.min(step_count - 1);
+    }
+
+    /// Moves the visible rows by an octave; `false` when that would leave the MIDI range.
+    pub fn shift_octave(&mut self, up: bool) -> bool {
+        let shifted = if up {
+            self.low_pitch
+                .checked_add(12)
+                .filter(|pitch| *pitch <= SKETCH_LOW_PITCH_MAX)
+        } else {
+            self.low_pitch.checked_sub(12)
+        };
+        match shifted {
+            Some(pitch) => {
+                self.low_pitch = pitch;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Adds or removes the note on the grid, and moves the cursor after it.
+    pub fn toggle_step(&mut self, step: u16, pitch: u8) {
+        if step >= self.step_count() || pitch > 127 {
+            return;
+        }
+        if !self.notes.remove(&(step, pitch)) {
+            self.notes.insert((step, pitch));
+        }
+        self.cursor = (step + 1) % self.step_count();
+    }
+
+    /// Writes `pitch` at the cursor and steps forward, wrapping at the end.
+    pub fn play_key(&mut self, pitch: u8) {
+        if pitch > 127 {
+            return;
+        }
+        self.notes.insert((self.cursor, pitch));
+        self.rest();
+    }
+
+    /// Leaves the cursor's step empty and steps forward.
+    pub fn rest(&mut self) {
+        self.cursor = (self.cursor + 1) % self.step_count();
+    }
+
+    pub fn clear(&mut self) {
+        self.notes.clear();
+        self.cursor = 0;
+    }
+
+    /// The sketch as a live reference for `slot`. Drum sketches play on the GM drum channel.
+    /// `None` while the sketch is empty.
+    pub fn to_reference(&self, slot: ReferenceSlot) -> Option<MidiReferenceSummary> {
+        let channel = if slot == ReferenceSlot::DrumPattern {
+            GM_DRUM_CHANNEL
+        } else {
+            1
+        };
+        let ticks_per_step = ticks_per_step(TickResolution::DEFAULT);
+        let notes = self
+            .notes
+            .iter()
+            .map(|(step, pitch)| GeneratedNote {
+                pitch: *pitch,
+                start_tick: u32::from(*step) * ticks_per_step,
+                duration_tick: ticks_per_step,
+                velocity: SKETCH_VELOCITY,
+                channel,
+            })
+            .collect::<Vec<_>>();
+        drawn_reference(slot, self.bars, &notes, TickResolution::DEFAULT)
+    }
+}
+
+pub(super) fn ticks_per_step(resolution: TickResolution) -> u32 {
+    u32::from(resolution.ticks_per_beat()) * BEATS_PER_BAR / u32::from(SKETCH_STEPS_PER_BAR)
+}
+
+/// A live reference for `slot` from notes drawn in the helper, ending on the bar line after
+/// `bars` so its length reads back as drawn. `None` without notes.
+pub(super) fn drawn_reference(
+    slot: ReferenceSlot,
+    bars: u16,
+    notes: &[GeneratedNote],
+    resolution: TickResolution,
+) -> Option<MidiReferenceSummary> {
+    let min_pitch = notes.iter().map(|note| note.pitch).min()?;
+    let max_pitch = notes.iter().map(|note| note.pitch).max()?;
+
+    let mut timed = Vec::with_capacity(notes.len() * 2 + 1);
+    for note in notes {
+        let channel = note.channel.clamp(1, 16) - 1;
+        let pitch = note.pitch;
+        timed.push((
+            note.start_tick.saturating_add(note.duration_tick),
+            0u8,
+            format!(
+                "Midi {{ channel: u4({channel}), message: NoteOff {{ key: u7({pitch}), vel: u7(0) }} }}"
+            ),
+        ));
+        timed.push((
+            note.start_tick,
+            1u8,
+            format!(
+                "Midi {{ channel: u4({channel}), message: NoteOn {{ key: u7({pitch}), vel: u7({}) }} }}",
+                note.velocity
+            ),
+        ));
+    }
+    timed.push((
+        u32::from(bars) * u32::from(resolution.ticks_per_beat()) * BEATS_PER_BAR,
+        2u8,
+        "Meta(EndOfTrack)".to_string(),
+    ));
+    // Offs before ons, so a repeated note is released before it sounds again.
+    timed.sort_by_key(|(tick, order, _)| (*tick, *order));
+
+    let mut previous_tick = 0u32;
+    let events = timed
+        .into_iter()
+        .map(|(absolute_tick, _, event)| {
+            let delta_tick = absolute_tick - previous_tick;
+            previous_tick = absolute_tick;
+            MidiReferenceEvent {
+                track: 0,
+                absolute_tick,
+                delta_tick,
+                event,
+            }
+        })
+        .collect();
+
+    let note_count = u32::try_from(notes.len()).unwrap_or(u32::MAX);
+    let reference = MidiReferenceSummary {
+        slot,
+        source: ReferenceSource::Live,
+        file: None,
+        bars,
+        note_count,
+        density_hint: calculate_reference_density_hint(note_count, bars),
+        min_pitch,
+        max_pitch,
+        events,
+        transposition: None,
+        tempo: None,
+    };
+    reference.validate().ok().map(|_| reference)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ReferenceSketch, SKETCH_STEPS_PER_BAR};
+    use crate::domain::ReferenceSlot;
+
+    #[test]
+    fn keys_are_written_at_the_cursor_and_rests_skip_a_step() {
+        let mut sketch = ReferenceSketch::default();
+        assert!(sketch.to_reference(ReferenceSlot::Melody).is_none());
+
+        sketch.play_key(60);
+        sketch.rest();
+        sketch.play_key(64);
+        sketch.toggle_step(15, 67);
+        assert_eq!(sketch.cursor(), 0, "the cursor wraps after the last step");
+        sketch.toggle_step(2, 64);
+        assert!(!sketch.has_note(2, 64));
+
+        let reference = sketch
+            .to_reference(ReferenceSlot::Melody)
+            .expect("sketch has notes");
+        assert_eq!(reference.note_count, 2);
+        assert_eq!((reference.min_pitch, reference.max_pitch), (60, 67));
+        assert_eq!(reference.bars, 1);
+        assert_eq!(
+            reference.events.first().map(|event| event.event.as_str()),
+            Some("Midi { channel: u4(0), message: NoteOn { key: u7(60), vel: u7(100) } }")
+        );
+        let end = reference
+            .events
+            .last()
+            .expect("sketch ends on its bar line");
+        assert_eq!(end.event, "Meta(EndOfTrack)");
+        assert_eq!(end.absolute_tick, 1920);
+        assert_eq!(
+            reference
+                .events
+                .iter()
+                .map(|event| event.delta_tick)
+                .sum::<u32>(),
+            end.absolute_tick
+        );
+    }
+
+    #[test]
+    fn shorter_sketches_drop_notes_past_their_end() {
+        let mut sketch = ReferenceSketch::default();
+        sketch.cycle_bars();
+        assert_eq!(sketch.step_count(), 2 * SKETCH_STEPS_PER_BAR);
+        sketch.toggle_step(20, 48);
+        sketch.toggle_step(3, 50);
+        sketch.cycle_bars();
+        sketch.cycle_bars();
+
+        assert_eq!(sketch.bars(), 1);
+        assert_eq!(sketch.note_count(), 1);
+        assert!(sketch.has_note(3, 50));
+
+        assert!(sketch.shift_octave(false));
+        assert_eq!(sketch.low_pitch(), 48);
+        for _ in 0..5 {
+            sketch.shift_octave(true);
+        }
+        assert_eq!(sketch.low_pitch(), 108);
+        assert!(!sketch.shift_octave(true));
+
+        let drums = sketch
+            .to_reference(ReferenceSlot::DrumPattern)
+            .expect("sketch has notes");
+        assert!(drums.events[0].event.contains("channel: u4(9)"));
+    }
+}
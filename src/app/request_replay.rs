@@ -0,0 +1,92 @@
+use std::path::Path;
+
+use thiserror::Error;
+
+use crate::domain::GenerationRequest;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum RequestReplayError {
+    #[error("failed to read request file: {message}")]
+    Io { message: String },
+    #[error("failed to parse request file: {message}")]
+    Parse { message: String },
+    #[error("request file is not a valid generation request: {message}")]
+    Invalid { message: String },
+}
+
+pub fn load_generation_request(
+    path: impl AsRef<Path>,
+) -> Result<GenerationRequest, RequestReplayError> {
+    let bytes = std::fs::read(path).map_err(|error| RequestReplayError::Io {
+        message: error.to_string(),
+    })?;
+    parse_generation_request(&bytes)
+}
+
+/// Parses a serialized `GenerationRequest`, upgrades it to the current contract, and runs full
+/// contract validation on it.
+pub fn parse_generation_request(bytes: &[u8]) -> Result<GenerationRequest, RequestReplayError> {
+    let mut request: GenerationRequest =
+        serde_json::from_slice(bytes).map_err(|error| RequestReplayError::Parse {
+            message: error.to_string(),
+        })?;
+    request.upgrade_contract();
+    request
+        .validate()
+        .map_err(|error| RequestReplayError::Invalid {
+            message: error.to_string(),
+        })?;
+    Ok(request)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RequestReplayError, parse_generation_request};
+    use crate::domain::GenerationMode;
+
+    #[test]
+    fn serialized_request_is_parsed_with_defaults() {
+        let json = br#"{
+            "request_id": "bug-report-1",
+            "model": { "provider": "anthropic", "model": "claude-3-5-sonnet" },
+            "mode": "bassline",
+            "prompt": "walking bass",
+            "params": {
+                "bpm": 96,
+                "key": "F",
+                "scale": "major",
+                "density": 2,
+                "complexity": 4
+            }
+        }"#;
+
+        let request = parse_generation_request(json).expect("request should parse");
+
+        assert_eq!(request.request_id, "bug-report-1");
+        assert_eq!(request.mode, GenerationMode::Bassline);
+        assert_eq!(request.params.bpm, 96);
+        assert_eq!(request.params.syncopation, 3);
+        assert_eq!(request.variation_count, 1);
+        assert!(request.locked_notes.is_empty());
+    }
+
+    #[test]
+    fn malformed_and_invalid_requests_are_rejected() {
+        assert!(matches!(
+            parse_generation_request(b"{ not json"),
+            Err(RequestReplayError::Parse { .. })
+        ));
+
+        let invalid = br#"{
+            "request_id": "bug-report-2",
+            "model": { "provider": "anthropic", "model": "claude-3-5-sonnet" },
+            "mode": "melody",
+            "prompt": "hook",
+            "params": { "bpm": 0, "key": "C", "scale": "major", "density": 3, "complexity": 3 }
+        }"#;
+        assert!(matches!(
+            parse_generation_request(invalid),
+            Err(RequestReplayError::Invalid { .. })
+        ));
+    }
+}
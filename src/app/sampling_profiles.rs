@@ -0,0 +1,293 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::app::input_track_presets::write_file_atomically;
+use crate::app::sonant_config_dir;
+
+pub(crate) const SAMPLING_PROFILES_FILE_NAME: &str = "sampling_profiles.json";
+const PROFILE_NAME_MAX_CHARS: usize = 32;
+const BUILTIN_PROFILE_PROVIDERS: [&str; 2] = ["anthropic", "openai_compatible"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SamplingProfile {
+    pub name: String,
+    pub provider: String,
+    pub temperature: f32,
+    pub top_p: f32,
+    pub max_tokens: u16,
+}
+
+impl SamplingProfile {
+    pub fn validate(&self) -> Result<(), SamplingProfileError> {
+        if !(0.0..=2.0).contains(&self.temperature) {
+            return Err(SamplingProfileError::InvalidValue {
+                message: format!(
+                    "temperature must be in 0.0..=2.0 (got {})",
+                    self.temperature
+                ),
+            });
+        }
+        if !(0.0..=1.0).contains(&self.top_p) {
+            return Err(SamplingProfileError::InvalidValue {
+                message: format!("top_p must be in 0.0..=1.0 (got {})", self.top_p),
+            });
+        }
+        if self.max_tokens == 0 {
+            return Err(SamplingProfileError::InvalidValue {
+                message: "max_tokens must be greater than 0".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SamplingProfileError {
+    #[error("profile name must not be empty")]
+    EmptyName,
+    #[error("profile name must be at most {PROFILE_NAME_MAX_CHARS} characters")]
+    NameTooLong,
+    #[error("provider must not be empty")]
+    EmptyProvider,
+    #[error("invalid sampling profile: {message}")]
+    InvalidValue { message: String },
+    #[error("failed to access sampling profile file: {message}")]
+    Io { message: String },
+    #[error("failed to parse sampling profile file: {message}")]
+    Parse { message: String },
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SamplingProfileFile {
+    #[serde(default)]
+    profiles: Vec<SamplingProfile>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SamplingProfileStore {
+    path: Option<PathBuf>,
+    profiles: Vec<SamplingProfile>,
+}
+
+impl SamplingProfileStore {
+    pub fn open_default() -> Result<Self, SamplingProfileError> {
+        match sonant_config_dir() {
+            Some(dir) => Self::open(dir.join(SAMPLING_PROFILES_FILE_NAME)),
+            None => Ok(Self::in_memory()),
+        }
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, SamplingProfileError> {
+        let path = path.as_ref().to_path_buf();
+        let profiles = match std::fs::read(&path) {
+            Ok(bytes) => {
+                let file: SamplingProfileFile =
+                    serde_json::from_slice(&bytes).map_err(|error| {
+                        SamplingProfileError::Parse {
+                            message: error.to_string(),
+                        }
+                    })?;
+                file.profiles
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => builtin_profiles(),
+            Err(error) => {
+                return Err(SamplingProfileError::Io {
+                    message: error.to_string(),
+                });
+            }
+        };
+
+        Ok(Self {
+            path: Some(path),
+            profiles,
+        })
+    }
+
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            profiles: builtin_profiles(),
+        }
+    }
+
+    pub fn profiles_for_provider<'a>(
+        &'a self,
+        provider: &'a str,
+    ) -> impl Iterator<Item = &'a SamplingProfile> + 'a {
+        self.profiles
+            .iter()
+            .filter(move |profile| profile.provider == provider)
+    }
+
+    pub fn profile(&self, provider: &str, name: &str) -> Option<&SamplingProfile> {
+        self.profiles
+            .iter()
+            .find(|profile| profile.provider == provider && profile.name == name)
+    }
+
+    /// Inserts or replaces the profile with the same provider and name.
+    pub fn save_profile(&mut self, profile: SamplingProfile) -> Result<(), SamplingProfileError> {
+        let profile = SamplingProfile {
+            name: normalize_profile_name(&profile.name)?,
+            provider: profile.provider.trim().to_string(),
+            ..profile
+        };
+        if profile.provider.is_empty() {
+            return Err(SamplingProfileError::EmptyProvider);
+        }
+        profile.validate()?;
+
+        let mut next = self.profiles.clone();
+        if let Some(existing) = next
+            .iter_mut()
+            .find(|existing| existing.provider == profile.provider && existing.name == profile.name)
+        {
+            *existing = profile;
+        } else {
+            next.push(profile);
+        }
+
+        self.persist(&next)?;
+        self.profiles = next;
+        Ok(())
+    }
+
+    pub fn remove_profile(
+        &mut self,
+        provider: &str,
+        name: &str,
+    ) -> Result<bool, SamplingProfileError> {
+        let mut next = self.profiles.clone();
+        let before = next.len();
+        next.retain(|profile| !(profile.provider == provider && profile.name == name));
+        if next.len() == before {
+            return Ok(false);
+        }
+
+        self.persist(&next)?;
+        self.profiles = next;
+        Ok(true)
+    }
+
+    fn persist(&self, profiles: &[SamplingProfile]) -> Result<(), SamplingProfileError> {
+        let Some(path) = self.path.as_ref() else {
+            return Ok(());
+        };
+
+        let payload = serde_json::to_vec_pretty(&SamplingProfileFile {
+            profiles: profiles.to_vec(),
+        })
+        .map_err(|error| SamplingProfileError::Parse {
+            message: error.to_string(),
+        })?;
+        write_file_atomically(path, &payload).map_err(|error| SamplingProfileError::Io {
+            message: error.to_string(),
+        })
+    }
+}
+
+pub fn builtin_profiles() -> Vec<SamplingProfile> {
+    BUILTIN_PROFILE_PROVIDERS
+        .into_iter()
+        .flat_map(|provider| {
+            [
+                SamplingProfile {
+                    name: "Safe".to_string(),
+                    provider: provider.to_string(),
+                    temperature: 0.4,
+                    top_p: 0.8,
+                    max_tokens: 512,
+                },
+                SamplingProfile {
+                    name: "Wild".to_string(),
+                    provider: provider.to_string(),
+                    temperature: 1.0,
+                    top_p: 1.0,
+                    max_tokens: 1024,
+                },
+            ]
+        })
+        .collect()
+}
+
+fn normalize_profile_name(name: &str) -> Result<String, SamplingProfileError> {
+    let trimmed = name.trim();
+    if trimmed.is_empty() {
+        return Err(SamplingProfileError::EmptyName);
+    }
+    if trimmed.chars().count() > PROFILE_NAME_MAX_CHARS {
+        return Err(SamplingProfileError::NameTooLong);
+    }
+    Ok(trimmed.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SamplingProfile, SamplingProfileError, SamplingProfileStore};
+    use crate::test_support::TestDir;
+
+    fn profile(name: &str, provider: &str, temperature: f32) -> SamplingProfile {
+        SamplingProfile {
+            name: name.to_string(),
+            provider: provider.to_string(),
+            temperature,
+            top_p: 0.9,
+            max_tokens: 768,
+        }
+    }
+
+    #[test]
+    fn builtin_profiles_are_scoped_per_provider() {
+        let store = SamplingProfileStore::in_memory();
+
+        let names = store
+            .profiles_for_provider("anthropic")
+            .map(|profile| profile.name.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, vec!["Safe", "Wild"]);
+        assert!(store.profile("openai_compatible", "Wild").is_some());
+        assert_eq!(store.profiles_for_provider("unknown").count(), 0);
+    }
+
+    #[test]
+    fn saved_profile_is_persisted_and_replaces_same_name() {
+        let dir = TestDir::new("sampling-profiles");
+        let path = dir.join("sampling_profiles.json");
+        let mut store = SamplingProfileStore::open(&path).expect("store should open");
+
+        store
+            .save_profile(profile(" Loose ", "anthropic", 0.9))
+            .expect("profile should save");
+        store
+            .save_profile(profile("Loose", "anthropic", 0.95))
+            .expect("profile should be replaced");
+        let reloaded = SamplingProfileStore::open(&path).expect("store should reload");
+
+        assert_eq!(
+            reloaded
+                .profile("anthropic", "Loose")
+                .map(|profile| profile.temperature),
+            Some(0.95)
+        );
+        assert_eq!(reloaded.profiles_for_provider("anthropic").count(), 3);
+    }
+
+    #[test]
+    fn invalid_profiles_are_rejected() {
+        let mut store = SamplingProfileStore::in_memory();
+
+        assert_eq!(
+            store.save_profile(profile("  ", "anthropic", 0.5)),
+            Err(SamplingProfileError::EmptyName)
+        );
+        assert!(matches!(
+            store.save_profile(profile("Hot", "anthropic", 2.5)),
+            Err(SamplingProfileError::InvalidValue { .. })
+        ));
+        assert_eq!(store.remove_profile("anthropic", "Missing"), Ok(false));
+        assert_eq!(store.remove_profile("anthropic", "Safe"), Ok(true));
+    }
+}
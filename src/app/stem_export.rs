@@ -0,0 +1,329 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::domain::{
+    GeneratedControlEvent, GeneratedNote, GenerationCandidate, ReferenceSlot, TickResolution,
+};
+use crate::infra::midi::{MidiConductor, encode_notes_as_smf};
+
+pub const STEM_MANIFEST_FILE_NAME: &str = "sonant-stems.json";
+const STEM_FILE_INFIX: &str = "stem";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StemSource {
+    Reference { slot: ReferenceSlot },
+    Candidate { candidate_id: String },
+}
+
+impl StemSource {
+    fn file_label(&self) -> String {
+        match self {
+            Self::Reference { slot } => slot_file_label(*slot).to_string(),
+            Self::Candidate { candidate_id } => {
+                format!("candidate-{}", sanitize_file_label(candidate_id))
+            }
+        }
+    }
+}
+
+/// Notes for one stem, positioned at `resolution` ticks per beat.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StemPart {
+    pub source: StemSource,
+    pub notes: Vec<GeneratedNote>,
+    pub control_events: Vec<GeneratedControlEvent>,
+    pub resolution: TickResolution,
+    /// General MIDI program the stem starts with.
+    pub program: Option<u8>,
+}
+
+impl StemPart {
+    pub fn from_reference(
+        slot: ReferenceSlot,
+        notes: Vec<GeneratedNote>,
+        resolution: TickResolution,
+    ) -> Self {
+        Self {
+            source: StemSource::Reference { slot },
+            notes,
+            control_events: Vec::new(),
+            resolution,
+            program: None,
+        }
+    }
+
+    pub fn from_candidate(candidate: &GenerationCandidate) -> Self {
+        Self {
+            source: StemSource::Candidate {
+                candidate_id: candidate.id.clone(),
+            },
+            notes: candidate.notes.clone(),
+            control_events: candidate.control_events.clone(),
+            resolution: candidate.tick_resolution,
+            program: None,
+        }
+    }
+
+    pub fn with_program(self, program: Option<u8>) -> Self {
+        Self { program, ..self }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StemManifestEntry {
+    pub file_name: String,
+    #[serde(flatten)]
+    pub source: StemSource,
+    pub note_count: usize,
+    pub channels: Vec<u8>,
+}
+
+/// Written next to the stems so a DAW session can be rebuilt with the right tempo and roles.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StemManifest {
+    pub bpm: u16,
+    pub ticks_per_beat: u16,
+    pub stems: Vec<StemManifestEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum StemExportError {
+    #[error("there are no reference or generated notes to export")]
+    NothingToExport,
+    #[error("failed to encode stem '{file_name}': {message}")]
+    Encode { file_name: String, message: String },
+    #[error("failed to write stems: {message}")]
+    Io { message: String },
+}
+
+/// Numbered in export order so files sort the same way in a DAW browser, e.g.
+/// `sonant-stem-01-melody.mid` or `sonant-stem-03-candidate-cand-1.mid` for the `sonant` stem.
+pub fn stem_file_name(file_stem: &str, index: usize, source: &StemSource) -> String {
+    format!(
+        "{}-{STEM_FILE_INFIX}-{:02}-{}.mid",
+        sanitize_file_label(file_stem),
+        index + 1,
+        source.file_label()
+    )
+}
+
+/// Writes one MIDI file per non-empty part into `dir`, named after `file_stem`, followed by
+/// the manifest.
+pub fn export_stems(
+    dir: impl AsRef<Path>,
+    file_stem: &str,
+    parts: &[StemPart],
+    resolution: TickResolution,
+    conductor: &MidiConductor,
+) -> Result<StemManifest, StemExportError> {
+    let dir = dir.as_ref();
+    let mut stems = Vec::new();
+
+    for part in parts.iter().filter(|part| !part.notes.is_empty()) {
+        let file_name = stem_file_name(file_stem, stems.len(), &part.source);
+        let notes = part
+            .notes
+            .iter()
+            .map(|note| resolution.convert_note(note, part.resolution))
+            .collect::<Vec<_>>();
+        let control_events = part
+            .control_events
+            .iter()
+            .map(|event| event.with_tick(resolution.convert_ticks(event.tick(), part.resolution)))
+            .collect::<Vec<_>>();
+        let bytes = encode_notes_as_smf(
+            &notes,
+            &control_events,
+            &[],
+            resolution.ticks_per_beat(),
+            conductor,
+            part.program,
+        )
+        .map_err(|error| StemExportError::Encode {
+            file_name: file_name.clone(),
+            message: error.to_string(),
+        })?;
+        std::fs::write(dir.join(&file_name), bytes).map_err(io_error)?;
+
+        let mut channels = notes.iter().map(|note| note.channel).collect::<Vec<_>>();
+        channels.sort_unstable();
+        channels.dedup();
+        stems.push(StemManifestEntry {
+            file_name,
+            source: part.source.clone(),
+            note_count: notes.len(),
+            channels,
+        });
+    }
+
+    if stems.is_empty() {
+        return Err(StemExportError::NothingToExport);
+    }
+
+    let manifest = StemManifest {
+        bpm,
+        ticks_per_beat: resolution.ticks_per_beat(),
+        stems,
+    };
+    let payload = serde_json::to_vec_pretty(&manifest).map_err(|error| StemExportError::Io {
+        message: error.to_string(),
+    })?;
+    std::fs::write(dir.join(STEM_MANIFEST_FILE_NAME), payload).map_err(io_error)?;
+    Ok(manifest)
+}
+
+fn io_error(error: std::io::Error) -> StemExportError {
+    StemExportError::Io {
+        message: error.to_string(),
+    }
+}
+
+pub(super) fn slot_file_label(slot: ReferenceSlot) -> &'static str {
+    match slot {
+        ReferenceSlot::Melody => "melody",
+        ReferenceSlot::ChordProgression => "chord-progression",
+        ReferenceSlot::DrumPattern => "drum-pattern",
+        ReferenceSlot::Bassline => "bassline",
+        ReferenceSlot::CounterMelody => "counter-melody",
+        ReferenceSlot::Harmony => "harmony",
+        ReferenceSlot::ContinuationSeed => "continuation-seed",
+    }
+}
+
+pub(crate) fn sanitize_file_label(label: &str) -> String {
+    let mut sanitized = String::with_capacity(label.len());
+    for character in label.trim().chars() {
+        if character.is_ascii_alphanumeric() {
+            sanitized.push(character.to_ascii_lowercase());
+        } else if !sanitized.ends_with('-') && !sanitized.is_empty() {
+            sanitized.push('-');
+        }
+    }
+    let sanitized = sanitized.trim_end_matches('-');
+    if sanitized.is_empty() {
+        "untitled".to_string()
+    } else {
+        sanitized.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        STEM_MANIFEST_FILE_NAME, StemExportError, StemManifest, StemPart, StemSource, export_stems,
+        stem_file_name,
+    };
+    use crate::domain::{GeneratedNote, GenerationCandidate, ReferenceSlot, TickResolution};
+    use crate::infra::midi::{MidiConductor, parse_midi_reference};
+    use crate::test_support::TestDir;
+
+    fn note(pitch: u8, start_tick: u32, channel: u8) -> GeneratedNote {
+        GeneratedNote {
+            pitch,
+            start_tick,
+            duration_tick: 240,
+            velocity: 100,
+            channel,
+        }
+    }
+
+    #[test]
+    fn stem_file_names_are_numbered_and_sanitized() {
+        assert_eq!(
+            stem_file_name(
+                "sonant",
+                0,
+                &StemSource::Reference {
+                    slot: ReferenceSlot::CounterMelody
+                }
+            ),
+            "sonant-stem-01-counter-melody.mid"
+        );
+        assert_eq!(
+            stem_file_name(
+                "sonant",
+                11,
+                &StemSource::Candidate {
+                    candidate_id: " Cand #2 / B ".to_string()
+                }
+            ),
+            "sonant-stem-12-candidate-cand-2-b.mid"
+        );
+    }
+
+    #[test]
+    fn stems_and_manifest_are_written_for_non_empty_parts() {
+        let dir = TestDir::new("stems");
+        let parts = vec![
+            StemPart::from_reference(
+                ReferenceSlot::Bassline,
+                vec![note(36, 0, 2), note(38, 120, 2)],
+                TickResolution::new(240).expect("240 PPQ should be valid"),
+            ),
+            StemPart::from_reference(ReferenceSlot::Melody, Vec::new(), TickResolution::DEFAULT),
+            StemPart::from_candidate(&GenerationCandidate {
+                id: "cand-1".to_string(),
+                bars: 1,
+                notes: vec![note(60, 0, 1)],
+                tick_resolution: TickResolution::DEFAULT,
+                score_hint: None,
+                title: None,
+                control_events: Vec::new(),
+                processing: None,
+            }),
+        ];
+
+        let manifest = export_stems(
+            dir.path(),
+            "sonant",
+            &parts,
+            TickResolution::DEFAULT,
+            &MidiConductor::new(120),
+        )
+        .expect("stems should export");
+
+        let file_names = manifest
+            .stems
+            .iter()
+            .map(|stem| stem.file_name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            file_names,
+            vec![
+                "sonant-stem-01-bassline.mid",
+                "sonant-stem-02-candidate-cand-1.mid"
+            ]
+        );
+        assert_eq!(manifest.stems[0].channels, vec![2]);
+        let bass = parse_midi_reference(
+            &std::fs::read(dir.join(file_names[0])).expect("bass stem should exist"),
+        )
+        .expect("bass stem should parse");
+        assert_eq!(bass.summary.note_count, 2);
+
+        let written: StemManifest = serde_json::from_slice(
+            &std::fs::read(dir.join(STEM_MANIFEST_FILE_NAME)).expect("manifest should exist"),
+        )
+        .expect("manifest should parse");
+        assert_eq!(written, manifest);
+    }
+
+    #[test]
+    fn empty_parts_are_rejected() {
+        let dir = TestDir::new("stems");
+
+        assert_eq!(
+            export_stems(
+                dir.path(),
+                "sonant",
+                &[],
+                TickResolution::DEFAULT,
+                &MidiConductor::new(120)
+            ),
+            Err(StemExportError::NothingToExport)
+        );
+    }
+}
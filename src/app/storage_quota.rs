@@ -0,0 +1,247 @@
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Cap on the MIDI files Sonant writes into the auto-save folder.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AutoSaveQuota {
+    Mb64,
+    #[default]
+    Mb256,
+    Gb1,
+    Unlimited,
+}
+
+impl AutoSaveQuota {
+    pub const ALL: [Self; 4] = [Self::Mb64, Self::Mb256, Self::Gb1, Self::Unlimited];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Mb64 => "64 MB",
+            Self::Mb256 => "256 MB",
+            Self::Gb1 => "1 GB",
+            Self::Unlimited => "Unlimited",
+        }
+    }
+
+    pub fn max_bytes(self) -> Option<u64> {
+        const MB: u64 = 1024 * 1024;
+        match self {
+            Self::Mb64 => Some(64 * MB),
+            Self::Mb256 => Some(256 * MB),
+            Self::Gb1 => Some(1024 * MB),
+            Self::Unlimited => None,
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let position = Self::ALL
+            .iter()
+            .position(|quota| *quota == self)
+            .unwrap_or_default();
+        Self::ALL[(position + 1) % Self::ALL.len()]
+    }
+}
+
+/// Sonant's own `.mid` files in a folder, told apart by the export name template's literal
+/// prefix; anything else there is left out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FolderUsage {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QuotaEviction {
+    /// Usage after eviction.
+    pub usage: FolderUsage,
+    pub removed: Vec<PathBuf>,
+}
+
+/// An empty `file_prefix` counts every `.mid` file in `dir`.
+pub fn folder_usage(dir: impl AsRef<Path>, file_prefix: &str) -> std::io::Result<FolderUsage> {
+    let files = sonant_midi_files(dir.as_ref(), file_prefix)?;
+    Ok(FolderUsage {
+        files: files.len(),
+        bytes: files.iter().map(|file| file.bytes).sum(),
+    })
+}
+
+/// Deletes the least recently written Sonant files in `dir` until they fit in `max_bytes`.
+/// The newest file is always kept, and a missing folder counts as empty.
+pub fn enforce_folder_quota(
+    dir: impl AsRef<Path>,
+    file_prefix: &str,
+    max_bytes: Option<u64>,
+) -> std::io::Result<QuotaEviction> {
+    let mut files = sonant_midi_files(dir.as_ref(), file_prefix)?;
+    files.sort_by(|left, right| {
+        left.modified
+            .cmp(&right.modified)
+            .then_with(|| left.path.cmp(&right.path))
+    });
+
+    let mut bytes = files.iter().map(|file| file.bytes).sum::<u64>();
+    let mut removed = Vec::new();
+    if let Some(max_bytes) = max_bytes {
+        let mut remaining = files.len();
+        for file in &files {
+            if bytes <= max_bytes || remaining <= 1 {
+                break;
+            }
+            std::fs::remove_file(&file.path)?;
+            bytes -= file.bytes;
+            remaining -= 1;
+            removed.push(file.path.clone());
+        }
+    }
+
+    Ok(QuotaEviction {
+        usage: FolderUsage {
+            files: files.len() - removed.len(),
+            bytes,
+        },
+        removed,
+    })
+}
+
+/// `812 B`, `4.2 KB`, `17.0 MB`
+pub fn format_byte_size(bytes: u64) -> String {
+    const UNITS: [&str; 3] = ["KB", "MB", "GB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+struct SonantMidiFile {
+    path: PathBuf,
+    bytes: u64,
+    modified: SystemTime,
+}
+
+fn sonant_midi_files(dir: &Path, file_prefix: &str) -> std::io::Result<Vec<SonantMidiFile>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error),
+    };
+
+    let mut files = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+        let is_sonant_midi = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(file_prefix))
+            && path
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("mid"));
+        if !is_sonant_midi {
+            continue;
+        }
+        let metadata = entry.metadata()?;
+        if !metadata.is_file() {
+            continue;
+        }
+        files.push(SonantMidiFile {
+            path,
+            bytes: metadata.len(),
+            modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+        });
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, SystemTime};
+
+    use super::{AutoSaveQuota, FolderUsage, enforce_folder_quota, folder_usage, format_byte_size};
+    use crate::test_support::TestDir;
+
+    fn write_file(dir: &Path, name: &str, bytes: usize, age_secs: u64) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, vec![0u8; bytes]).expect("file should be written");
+        File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(SystemTime::now() - Duration::from_secs(age_secs)))
+            .expect("modified time should be set");
+        path
+    }
+
+    #[test]
+    fn oldest_sonant_files_are_evicted_first() {
+        let test_dir = TestDir::new("storage-quota");
+        let dir = test_dir.path();
+        let oldest = write_file(dir, "sonant-req-1-01-a.mid", 400, 300);
+        let middle = write_file(dir, "sonant-req-2-01-b.mid", 400, 200);
+        let newest = write_file(dir, "sonant-req-3-01-c.mid", 400, 100);
+        let foreign = write_file(dir, "my-song.mid", 4_000, 400);
+
+        let eviction =
+            enforce_folder_quota(dir, "sonant", Some(900)).expect("quota should be enforced");
+        let remaining = (
+            oldest.exists(),
+            middle.exists(),
+            newest.exists(),
+            foreign.exists(),
+        );
+
+        assert_eq!(eviction.removed, vec![oldest]);
+        assert_eq!(
+            eviction.usage,
+            FolderUsage {
+                files: 2,
+                bytes: 800
+            }
+        );
+        assert_eq!(remaining, (false, true, true, true));
+    }
+
+    #[test]
+    fn newest_file_is_kept_and_unlimited_removes_nothing() {
+        let test_dir = TestDir::new("storage-quota");
+        let dir = test_dir.path();
+        write_file(dir, "sonant-req-1-01-a.mid", 600, 200);
+        let newest = write_file(dir, "sonant-req-2-01-b.mid", 600, 100);
+
+        let unlimited = enforce_folder_quota(dir, "sonant", AutoSaveQuota::Unlimited.max_bytes())
+            .expect("quota should be enforced");
+        let tight =
+            enforce_folder_quota(dir, "sonant", Some(10)).expect("quota should be enforced");
+        let usage = folder_usage(dir, "sonant").expect("usage should be read");
+
+        assert!(unlimited.removed.is_empty());
+        assert_eq!(tight.removed.len(), 1);
+        assert_eq!(
+            usage,
+            FolderUsage {
+                files: 1,
+                bytes: 600
+            }
+        );
+        assert!(!tight.removed.contains(&newest));
+        assert_eq!(
+            folder_usage(test_dir.join("missing"), "sonant").expect("missing dir should be empty"),
+            FolderUsage::default()
+        );
+    }
+
+    #[test]
+    fn byte_sizes_use_binary_units() {
+        assert_eq!(format_byte_size(812), "812 B");
+        assert_eq!(format_byte_size(4_300), "4.2 KB");
+        assert_eq!(format_byte_size(17 * 1024 * 1024), "17.0 MB");
+        assert_eq!(AutoSaveQuota::Unlimited.next(), AutoSaveQuota::Mb64);
+    }
+}
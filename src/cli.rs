@@ -0,0 +1,500 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use std::time::Duration;
+
+use sonant::app::{
+    AppliedClip, AppliedClipSink, ApplyDestination, ApplyFileTarget, BENCHMARK_DEFAULT_RUNS,
+    BatchRun, DiagnosticStatus, DrumMap, ExportNameContext, ExportNameTemplate,
+    GenerationHistoryStore, HELPER_CONTROL_IPC_SOCKET_ENV, HelperControlIpcSender,
+    ProviderBenchmark, autosave_candidates, batch_request, dispatch_apply, load_batch_prompts,
+    load_generation_request, run_diagnostics, run_provider_benchmark,
+};
+use sonant::domain::{GenerationMode, TickResolution, ValidationStrictness};
+use sonant::infra::midi::MidiConductor;
+
+use crate::ui::{BenchmarkTarget, build_benchmark_targets, build_generation_service};
+
+const REPLAY_USAGE: &str = "Usage: sonant replay <request.json>";
+const DOCTOR_USAGE: &str = "Usage: sonant doctor";
+const BENCH_USAGE: &str =
+    "Usage: sonant bench [--providers anthropic,openai,remote] [--runs <count>]";
+const BATCH_USAGE: &str = "Usage: sonant batch <request.json> <prompts.txt|prompts.csv> [--out <dir>] [--name <template>] [--validate strict|lenient]";
+const APPLY_USAGE: &str = "Usage: sonant apply <request.json> [--candidate <n>] [--to plugin|file|both] [--out <dir>] [--name <template>]";
+// How long `apply` waits for the plugin to read a clip the socket could not take at once.
+const PLUGIN_SEND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Runs a headless subcommand, or returns `None` when `args` does not name one.
+pub(crate) fn run(args: &[String]) -> Option<ExitCode> {
+    let (command, rest) = args.split_first()?;
+    match command.as_str() {
+        "replay" => Some(run_replay(rest)),
+        "doctor" => Some(run_doctor(rest)),
+        "bench" => Some(run_bench(rest)),
+        "batch" => Some(run_batch(rest)),
+        "apply" => Some(run_apply(rest)),
+        _ => None,
+    }
+}
+
+fn run_replay(args: &[String]) -> ExitCode {
+    let [path] = args else {
+        eprintln!("{REPLAY_USAGE}");
+        return ExitCode::from(2);
+    };
+
+    let request = match load_generation_request(path) {
+        Ok(request) => request,
+        Err(error) => {
+            eprintln!("{error}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let service = match build_generation_service() {
+        Ok(service) => service,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let result = match service.generate(request) {
+        Ok(result) => result,
+        Err(error) => {
+            eprintln!("{}", error.user_message());
+            return ExitCode::FAILURE;
+        }
+    };
+    match serde_json::to_string_pretty(&result) {
+        Ok(json) => {
+            println!("{json}");
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("failed to serialize generation result: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run_doctor(args: &[String]) -> ExitCode {
+    if !args.is_empty() {
+        eprintln!("{DOCTOR_USAGE}");
+        return ExitCode::from(2);
+    }
+
+    let checks = run_diagnostics();
+    for check in &checks {
+        let label = match check.status {
+            DiagnosticStatus::Ok => "[OK]  ",
+            DiagnosticStatus::Warning => "[WARN]",
+            DiagnosticStatus::Error => "[FAIL]",
+        };
+        println!("{label} {}: {}", check.name, check.detail);
+        if let Some(hint) = &check.hint {
+            println!("       -> {hint}");
+        }
+    }
+
+    let failures = checks
+        .iter()
+        .filter(|check| check.status == DiagnosticStatus::Error)
+        .count();
+    if failures == 0 {
+        println!("No problems found.");
+        ExitCode::SUCCESS
+    } else {
+        println!("{failures} check(s) failed.");
+        ExitCode::FAILURE
+    }
+}
+
+struct BatchOptions {
+    request_path: String,
+    prompts_path: String,
+    out_dir: Option<PathBuf>,
+    /// File name template for `--out`, with the same placeholders as the helper's setting.
+    name_template: Option<String>,
+    /// Overrides the saved request's candidate validation.
+    validation: Option<ValidationStrictness>,
+}
+
+fn parse_batch_options(args: &[String]) -> Option<BatchOptions> {
+    let [request_path, prompts_path, flags @ ..] = args else {
+        return None;
+    };
+    let mut options = BatchOptions {
+        request_path: request_path.clone(),
+        prompts_path: prompts_path.clone(),
+        out_dir: None,
+        name_template: None,
+        validation: None,
+    };
+    let mut flags = flags.iter();
+    while let Some(flag) = flags.next() {
+        let value = flags.next()?;
+        match flag.as_str() {
+            "--out" => options.out_dir = Some(PathBuf::from(value)),
+            "--name" => options.name_template = Some(value.clone()),
+            "--validate" => options.validation = Some(ValidationStrictness::parse(value)?),
+            _ => return None,
+        }
+    }
+    Some(options)
+}
+
+/// The `--name` template, or the default one when the flag is absent.
+fn parse_name_template(template: Option<&str>) -> Result<ExportNameTemplate, String> {
+    template.map_or_else(
+        || Ok(ExportNameTemplate::default()),
+        |template| ExportNameTemplate::parse(template).map_err(|error| error.to_string()),
+    )
+}
+
+/// Runs every prompt in turn against the references and params of a saved request, recording
+/// each result in the history and, with `--out`, writing its candidates as MIDI files.
+fn run_batch(args: &[String]) -> ExitCode {
+    let Some(options) = parse_batch_options(args) else {
+        eprintln!("{BATCH_USAGE}");
+        return ExitCode::from(2);
+    };
+    let name_template = match parse_name_template(options.name_template.as_deref()) {
+        Ok(template) => template,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut base = match load_generation_request(&options.request_path) {
+        Ok(request) => request,
+        Err(error) => {
+            eprintln!("{error}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if options.validation.is_some() {
+        base.validation = options.validation;
+    }
+    let mut run = match load_batch_prompts(&options.prompts_path).and_then(BatchRun::new) {
+        Ok(run) => run,
+        Err(error) => {
+            eprintln!("{error}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let service = match build_generation_service() {
+        Ok(service) => service,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut history = match GenerationHistoryStore::open_default() {
+        Ok(history) => history,
+        Err(error) => {
+            eprintln!("{error}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let total = run.len();
+    while let Some(prompt) = run.current_prompt().map(str::to_string) {
+        let position = run.completed_count() + 1;
+        let request = batch_request(&base, &prompt, run.completed_count());
+        println!("[{position}/{total}] {prompt}");
+        let result = match service.generate(request.clone()) {
+            Ok(result) => result,
+            Err(error) => {
+                let message = error.user_message();
+                println!("  failed: {message}");
+                run.record_failure(&message);
+                continue;
+            }
+        };
+        println!("  {} candidate(s)", result.candidates.len());
+        if let Some(dir) = &options.out_dir
+            && let Err(error) = autosave_candidates(
+                dir,
+                &name_template.render(&ExportNameContext::for_request(&request)),
+                &result,
+                TickResolution::DEFAULT,
+                &MidiConductor::for_request(&request),
+                None,
+            )
+        {
+            println!("  {error}");
+        }
+        if let Err(error) = history.record(request, result) {
+            println!("  {error}");
+        }
+        run.record_success();
+    }
+
+    let failures = run.failures().len();
+    println!("{} of {total} prompt(s) succeeded.", total - failures);
+    if failures == total {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+struct ApplyOptions {
+    request_path: String,
+    /// 1-based, as listed in the helper.
+    candidate: usize,
+    destination: ApplyDestination,
+    out_dir: Option<PathBuf>,
+    name_template: Option<String>,
+}
+
+fn parse_apply_options(args: &[String]) -> Option<ApplyOptions> {
+    let (request_path, flags) = args.split_first()?;
+    let mut options = ApplyOptions {
+        request_path: request_path.clone(),
+        candidate: 1,
+        destination: ApplyDestination::default(),
+        out_dir: None,
+        name_template: None,
+    };
+    let mut flags = flags.iter();
+    while let Some(flag) = flags.next() {
+        let value = flags.next()?;
+        match flag.as_str() {
+            "--candidate" => options.candidate = value.parse().ok().filter(|index| *index > 0)?,
+            "--to" => options.destination = ApplyDestination::parse(value)?,
+            "--out" => options.out_dir = Some(PathBuf::from(value)),
+            "--name" => options.name_template = Some(value.clone()),
+            _ => return None,
+        }
+    }
+    Some(options)
+}
+
+/// Generates from a saved request and applies one candidate the way the helper's Apply button
+/// does: to the plugin whose control socket is named in the environment, to a MIDI file in
+/// `--out`, or both.
+fn run_apply(args: &[String]) -> ExitCode {
+    let Some(options) = parse_apply_options(args) else {
+        eprintln!("{APPLY_USAGE}");
+        return ExitCode::from(2);
+    };
+    let name_template = match parse_name_template(options.name_template.as_deref()) {
+        Ok(template) => template,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let request = match load_generation_request(&options.request_path) {
+        Ok(request) => request,
+        Err(error) => {
+            eprintln!("{error}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let service = match build_generation_service() {
+        Ok(service) => service,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let result = match service.generate(request.clone()) {
+        Ok(result) => result,
+        Err(error) => {
+            eprintln!("{}", error.user_message());
+            return ExitCode::FAILURE;
+        }
+    };
+    let Some(candidate) = result.candidates.get(options.candidate - 1) else {
+        eprintln!(
+            "candidate {} was requested but the result has {}",
+            options.candidate,
+            result.candidates.len()
+        );
+        return ExitCode::FAILURE;
+    };
+
+    let mut clip = AppliedClip::from_candidate(candidate);
+    if request.mode == GenerationMode::DrumPattern {
+        clip = clip.with_drum_chokes(&DrumMap::general_midi());
+    }
+    let plugin = std::env::var(HELPER_CONTROL_IPC_SOCKET_ENV)
+        .ok()
+        .and_then(|socket_path| HelperControlIpcSender::new(socket_path).ok());
+    let conductor = MidiConductor::for_request(&request);
+    let file_stem = name_template.render(&ExportNameContext::for_request(&request));
+    let file_target = options.out_dir.as_deref().map(|dir| ApplyFileTarget {
+        dir,
+        file_stem: &file_stem,
+        chords: &result.metadata.chords,
+        resolution: TickResolution::DEFAULT,
+        conductor: &conductor,
+        program: None,
+    });
+
+    match dispatch_apply(
+        options.destination,
+        candidate,
+        clip,
+        plugin.as_ref().map(|sender| sender as &dyn AppliedClipSink),
+        file_target,
+    ) {
+        Ok(outcome) => {
+            if outcome.sent_to_plugin {
+                // The process exits next, so wait for the plugin to take the whole clip.
+                if let Some(Err(error)) = plugin
+                    .as_ref()
+                    .map(|sender| sender.finish(PLUGIN_SEND_TIMEOUT))
+                {
+                    eprintln!("failed to send the clip to the plugin: {error}");
+                    return ExitCode::FAILURE;
+                }
+                println!("Applied {} to the plugin output.", candidate.id);
+            }
+            if let Some(path) = outcome.file {
+                println!("Wrote {}", path.display());
+            }
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+struct BenchOptions {
+    providers: Option<Vec<String>>,
+    runs: usize,
+}
+
+fn parse_bench_options(args: &[String]) -> Option<BenchOptions> {
+    let mut options = BenchOptions {
+        providers: None,
+        runs: BENCHMARK_DEFAULT_RUNS,
+    };
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args.next()?;
+        match flag.as_str() {
+            "--providers" => {
+                let providers = value
+                    .split(',')
+                    .map(|name| name.trim().to_ascii_lowercase())
+                    .filter(|name| !name.is_empty())
+                    .collect::<Vec<_>>();
+                options.providers = (!providers.is_empty()).then_some(providers);
+            }
+            "--runs" => options.runs = value.parse().ok().filter(|runs| *runs > 0)?,
+            _ => return None,
+        }
+    }
+    Some(options)
+}
+
+fn run_bench(args: &[String]) -> ExitCode {
+    let Some(options) = parse_bench_options(args) else {
+        eprintln!("{BENCH_USAGE}");
+        return ExitCode::from(2);
+    };
+
+    let (mut targets, notices) = build_benchmark_targets();
+    for notice in &notices {
+        eprintln!("{notice}");
+    }
+    if let Some(requested) = &options.providers {
+        if let Some(unknown) = requested
+            .iter()
+            .find(|name| !matches!(name.as_str(), "anthropic" | "openai" | "remote"))
+        {
+            eprintln!("unknown provider '{unknown}' (expected anthropic, openai or remote)");
+            return ExitCode::from(2);
+        }
+        targets.retain(|target| requested.iter().any(|name| name == target.name));
+    }
+    if targets.is_empty() {
+        eprintln!("no requested provider is configured; run `sonant doctor` to check API keys");
+        return ExitCode::FAILURE;
+    }
+
+    let benchmarks = targets
+        .iter()
+        .map(|target| bench_target(target, options.runs))
+        .collect::<Vec<_>>();
+
+    println!();
+    println!(
+        "{:<40} {:>5} {:>7} {:>8} {:>8} {:>8} {:>8}",
+        "model", "runs", "failed", "mean", "p50", "p90", "max"
+    );
+    for benchmark in &benchmarks {
+        println!(
+            "{:<40} {:>5} {:>6.0}% {:>8} {:>8} {:>8} {:>8}",
+            format!("{}/{}", benchmark.model.provider, benchmark.model.model),
+            benchmark.runs(),
+            benchmark.failure_rate() * 100.0,
+            format_latency(benchmark.mean()),
+            format_latency(benchmark.percentile(50.0)),
+            format_latency(benchmark.percentile(90.0)),
+            format_latency(benchmark.percentile(100.0)),
+        );
+    }
+
+    // Fewest failures first, then the lowest median latency.
+    let fastest = benchmarks
+        .iter()
+        .filter(|benchmark| !benchmark.latencies.is_empty())
+        .min_by(|left, right| {
+            left.failures
+                .len()
+                .cmp(&right.failures.len())
+                .then(left.percentile(50.0).cmp(&right.percentile(50.0)))
+        });
+    match fastest {
+        Some(benchmark) => {
+            println!(
+                "Suggested default: {}/{}",
+                benchmark.model.provider, benchmark.model.model
+            );
+            ExitCode::SUCCESS
+        }
+        None => {
+            eprintln!("every benchmark request failed");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn bench_target(target: &BenchmarkTarget, runs: usize) -> ProviderBenchmark {
+    println!(
+        "{} ({}/{})",
+        target.name, target.model.provider, target.model.model
+    );
+    run_provider_benchmark(
+        target.provider.as_ref(),
+        target.model.clone(),
+        runs,
+        |run_index, outcome| match outcome {
+            Ok(latency) => println!(
+                "  run {}/{runs}: {}",
+                run_index + 1,
+                format_latency(Some(*latency))
+            ),
+            Err(error) => println!(
+                "  run {}/{runs}: failed: {}",
+                run_index + 1,
+                error.user_message()
+            ),
+        },
+    )
+}
+
+fn format_latency(latency: Option<Duration>) -> String {
+    latency
+        .map(|latency| format!("{} ms", latency.as_millis()))
+        .unwrap_or_else(|| "-".to_string())
+}
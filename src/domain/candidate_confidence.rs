@@ -0,0 +1,234 @@
+use serde::{Deserialize, Serialize};
+
+use super::{
+    BEATS_PER_BAR, CandidateRepairReport, GeneratedNote, GenerationCandidate,
+    GenerationConstraints, GenerationResult,
+};
+
+const MISSING_FIELD_PENALTY: f32 = 0.05;
+const REPAIR_PENALTY: f32 = 0.4;
+const OVERRUN_PENALTY: f32 = 0.3;
+const RANGE_PENALTY: f32 = 0.3;
+const RHYTHM_PENALTY: f32 = 0.2;
+const AVOID_PENALTY: f32 = 0.2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfidenceLevel {
+    High,
+    Medium,
+    Low,
+}
+
+/// Heuristic 0–100 quality score for a parsed candidate, with the reasons it lost points.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CandidateConfidence {
+    pub candidate_id: String,
+    pub score: u8,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub issues: Vec<String>,
+}
+
+impl CandidateConfidence {
+    /// Scores schema completeness, how much the repair pass had to fix, notes past the
+    /// declared length, and, when given, adherence to the request's constraints.
+    pub fn assess(
+        candidate: &GenerationCandidate,
+        repair: Option<&CandidateRepairReport>,
+        constraints: Option<&GenerationConstraints>,
+    ) -> Self {
+        let mut penalty = 0.0;
+        let mut issues = Vec::new();
+        let note_count = candidate.notes.len();
+
+        if candidate.score_hint.is_none() {
+            penalty += MISSING_FIELD_PENALTY;
+            issues.push("no score hint".to_string());
+        }
+        if candidate.title.is_none() {
+            penalty += MISSING_FIELD_PENALTY;
+            issues.push("no title".to_string());
+        }
+
+        if let Some(report) = repair.filter(|report| !report.is_clean()) {
+            let repaired =
+                report.duplicates_removed + report.overlaps_trimmed + report.zero_length_extended;
+            let original_count = note_count + report.duplicates_removed as usize;
+            penalty += REPAIR_PENALTY * fraction(repaired as usize, original_count);
+            issues.push(format!("{repaired} note(s) repaired"));
+        }
+
+        let ticks_per_beat = u32::from(candidate.tick_resolution.ticks_per_beat());
+        let length_ticks = u32::from(candidate.bars) * BEATS_PER_BAR * ticks_per_beat;
+        let overruns = count_notes(&candidate.notes, |note| note.start_tick >= length_ticks);
+        if overruns > 0 {
+            penalty += OVERRUN_PENALTY * fraction(overruns, note_count);
+            issues.push(format!(
+                "{overruns} note(s) start after bar {}",
+                candidate.bars
+            ));
+        }
+
+        if let Some(constraints) = constraints {
+            if let Some(range) = constraints.range {
+                let outside = count_notes(&candidate.notes, |note| {
+                    note.pitch < range.low || note.pitch > range.high
+                });
+                if outside > 0 {
+                    penalty += RANGE_PENALTY * fraction(outside, note_count);
+                    issues.push(format!("{outside} note(s) outside {}", range.label()));
+                }
+            }
+            if let Some(rhythm) = constraints.rhythm {
+                let steps_per_beat = u32::from(rhythm.steps_per_beat());
+                if ticks_per_beat % steps_per_beat == 0 {
+                    let step_ticks = ticks_per_beat / steps_per_beat;
+                    let off_grid =
+                        count_notes(&candidate.notes, |note| note.start_tick % step_ticks != 0);
+                    if off_grid > 0 {
+                        penalty += RHYTHM_PENALTY * fraction(off_grid, note_count);
+                        issues.push(format!(
+                            "{off_grid} note(s) off the {} grid",
+                            rhythm.label()
+                        ));
+                    }
+                }
+            }
+            if !constraints.avoid.is_empty() {
+                let (avoided, steps) = count_avoided_steps(&candidate.notes, constraints);
+                if avoided > 0 {
+                    penalty += AVOID_PENALTY * fraction(avoided, steps);
+                    issues.push(format!("{avoided} avoided interval(s)"));
+                }
+            }
+        }
+
+        Self {
+            candidate_id: candidate.id.clone(),
+            score: ((1.0 - penalty).clamp(0.0, 1.0) * 100.0).round() as u8,
+            issues,
+        }
+    }
+
+    pub fn level(&self) -> ConfidenceLevel {
+        match self.score {
+            80.. => ConfidenceLevel::High,
+            50..80 => ConfidenceLevel::Medium,
+            _ => ConfidenceLevel::Low,
+        }
+    }
+}
+
+impl GenerationResult {
+    /// Scores every candidate; run after [`Self::repair_candidates`] so repairs count.
+    pub fn assess_confidence(&mut self, constraints: Option<&GenerationConstraints>) {
+        self.metadata.confidence = self
+            .candidates
+            .iter()
+            .map(|candidate| {
+                let repair = self
+                    .metadata
+                    .repairs
+                    .iter()
+                    .find(|report| report.candidate_id == candidate.id);
+                CandidateConfidence::assess(candidate, repair, constraints)
+            })
+            .collect();
+    }
+}
+
+fn count_notes(notes: &[GeneratedNote], predicate: impl Fn(&GeneratedNote) -> bool) -> usize {
+    notes.iter().filter(|note| predicate(note)).count()
+}
+
+fn fraction(count: usize, total: usize) -> f32 {
+    if total == 0 {
+        return 0.0;
+    }
+    (count as f32 / total as f32).min(1.0)
+}
+
+/// Melodic steps between successive onsets and how many of them are avoided intervals.
+fn count_avoided_steps(
+    notes: &[GeneratedNote],
+    constraints: &GenerationConstraints,
+) -> (usize, usize) {
+    let mut ordered = notes.iter().collect::<Vec<_>>();
+    ordered.sort_by_key(|note| (note.start_tick, note.pitch));
+    let steps = ordered
+        .windows(2)
+        .filter(|pair| pair[0].start_tick != pair[1].start_tick)
+        .map(|pair| pair[0].pitch.abs_diff(pair[1].pitch))
+        .collect::<Vec<_>>();
+    let avoided = steps
+        .iter()
+        .filter(|semitones| {
+            constraints
+                .avoid
+                .iter()
+                .any(|interval| interval.semitones == **semitones)
+        })
+        .count();
+    (avoided, steps.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CandidateConfidence, ConfidenceLevel};
+    use crate::domain::{
+        CandidateRepairReport, GeneratedNote, GenerationCandidate, GenerationConstraints,
+        TickResolution,
+    };
+
+    fn note(pitch: u8, start_tick: u32) -> GeneratedNote {
+        GeneratedNote {
+            pitch,
+            start_tick,
+            duration_tick: 240,
+            velocity: 96,
+            channel: 1,
+        }
+    }
+
+    #[test]
+    fn confidence_drops_for_repairs_overruns_and_constraint_misses() {
+        let mut candidate = GenerationCandidate {
+            id: "cand-1".to_string(),
+            bars: 1,
+            notes: vec![note(60, 0), note(62, 480), note(64, 960), note(65, 1440)],
+            tick_resolution: TickResolution::DEFAULT,
+            score_hint: Some(0.8),
+            title: Some("Rising line".to_string()),
+            control_events: Vec::new(),
+            processing: None,
+        };
+
+        let clean = CandidateConfidence::assess(&candidate, None, None);
+        assert_eq!(clean.score, 100);
+        assert_eq!(clean.level(), ConfidenceLevel::High);
+        assert!(clean.issues.is_empty());
+
+        candidate.notes.push(note(84, 2000));
+        let constraints = GenerationConstraints::parse("range: C4..C5; rhythm: 8th; avoid: b2")
+            .expect("constraints should parse");
+        let repair = CandidateRepairReport {
+            candidate_id: "cand-1".to_string(),
+            duplicates_removed: 0,
+            overlaps_trimmed: 1,
+            zero_length_extended: 0,
+        };
+        let assessed = CandidateConfidence::assess(&candidate, Some(&repair), Some(&constraints));
+
+        assert_eq!(
+            assessed.issues,
+            vec![
+                "1 note(s) repaired",
+                "1 note(s) start after bar 1",
+                "1 note(s) outside C4..C5",
+                "1 note(s) off the 8th grid",
+                "1 avoided interval(s)",
+            ]
+        );
+        assert_eq!(assessed.score, 71);
+        assert_eq!(assessed.level(), ConfidenceLevel::Medium);
+    }
+}
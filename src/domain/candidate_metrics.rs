@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::GenerationCandidate;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CandidateMetric {
+    NoteDensity,
+    PitchEntropy,
+    RhythmicVariety,
+}
+
+impl CandidateMetric {
+    pub const ALL: [Self; 3] = [Self::NoteDensity, Self::PitchEntropy, Self::RhythmicVariety];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::NoteDensity => "Density",
+            Self::PitchEntropy => "Pitch Entropy",
+            Self::RhythmicVariety => "Rhythmic Variety",
+        }
+    }
+
+    /// Short form of `value` for a candidate row, e.g. "6.5/bar" or "2.8 bits".
+    pub fn format_value(self, value: f64) -> String {
+        match self {
+            Self::NoteDensity => format!("{value:.1}/bar"),
+            Self::PitchEntropy | Self::RhythmicVariety => format!("{value:.1} bits"),
+        }
+    }
+}
+
+/// Objective, model-independent measurements of one candidate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CandidateMetrics {
+    /// Notes per bar.
+    pub note_density: f64,
+    /// Shannon entropy of the pitch-class distribution, in bits (0 to log2(12)).
+    pub pitch_entropy: f64,
+    /// Shannon entropy of the distinct inter-onset intervals, in bits.
+    pub rhythmic_variety: f64,
+}
+
+impl CandidateMetrics {
+    pub fn of(candidate: &GenerationCandidate) -> Self {
+        let mut onsets = candidate
+            .notes
+            .iter()
+            .map(|note| note.start_tick)
+            .collect::<Vec<_>>();
+        onsets.sort_unstable();
+        onsets.dedup();
+
+        Self {
+            note_density: candidate.notes.len() as f64 / f64::from(candidate.bars.max(1)),
+            pitch_entropy: entropy(candidate.notes.iter().map(|note| note.pitch % 12)),
+            rhythmic_variety: entropy(onsets.windows(2).map(|pair| pair[1] - pair[0])),
+        }
+    }
+
+    pub fn value(&self, metric: CandidateMetric) -> f64 {
+        match metric {
+            CandidateMetric::NoteDensity => self.note_density,
+            CandidateMetric::PitchEntropy => self.pitch_entropy,
+            CandidateMetric::RhythmicVariety => self.rhythmic_variety,
+        }
+    }
+}
+
+/// Candidate indices ordered by `metric`, ties kept in generation order. With
+/// `top_half_only`, only the first half of that order (rounded up) is kept.
+pub fn rank_candidates(
+    candidates: &[GenerationCandidate],
+    metric: CandidateMetric,
+    descending: bool,
+    top_half_only: bool,
+) -> Vec<usize> {
+    let values = candidates
+        .iter()
+        .map(|candidate| CandidateMetrics::of(candidate).value(metric))
+        .collect::<Vec<_>>();
+    let mut order = (0..candidates.len()).collect::<Vec<_>>();
+    order.sort_by(|left, right| {
+        let ordering = values[*left].total_cmp(&values[*right]);
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+    if top_half_only {
+        order.truncate(candidates.len().div_ceil(2));
+    }
+    order
+}
+
+fn entropy<T: Eq + Hash>(values: impl Iterator<Item = T>) -> f64 {
+    let mut counts = HashMap::new();
+    let mut total = 0_usize;
+    for value in values {
+        *counts.entry(value).or_insert(0_usize) += 1;
+        total += 1;
+    }
+    counts
+        .values()
+        .map(|count| {
+            let probability = *count as f64 / total as f64;
+            -probability * probability.log2()
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CandidateMetric, CandidateMetrics, rank_candidates};
+    use crate::domain::{GeneratedNote, GenerationCandidate, TickResolution};
+
+    fn candidate(id: &str, notes: &[(u8, u32)]) -> GenerationCandidate {
+        GenerationCandidate {
+            id: id.to_string(),
+            bars: 1,
+            notes: notes
+                .iter()
+                .map(|(pitch, start_tick)| GeneratedNote {
+                    pitch: *pitch,
+                    start_tick: *start_tick,
+                    duration_tick: 120,
+                    velocity: 96,
+                    channel: 1,
+                })
+                .collect(),
+            tick_resolution: TickResolution::DEFAULT,
+            score_hint: None,
+            title: None,
+            control_events: Vec::new(),
+            processing: None,
+        }
+    }
+
+    #[test]
+    fn metrics_measure_density_pitch_spread_and_rhythm_and_rank_candidates() {
+        let repeated = candidate("repeated", &[(60, 0), (72, 480), (60, 960), (48, 1440)]);
+        let varied = candidate(
+            "varied",
+            &[
+                (60, 0),
+                (62, 240),
+                (64, 360),
+                (65, 480),
+                (67, 960),
+                (69, 1200),
+            ],
+        );
+        let sparse = candidate("sparse", &[(60, 0), (64, 960)]);
+
+        let metrics = CandidateMetrics::of(&repeated);
+        assert_eq!(metrics.note_density, 4.0);
+        assert_eq!(metrics.pitch_entropy, 0.0);
+        assert_eq!(metrics.rhythmic_variety, 0.0);
+        let metrics = CandidateMetrics::of(&varied);
+        assert!((metrics.pitch_entropy - 6_f64.log2()).abs() < 1e-9);
+        assert!(metrics.rhythmic_variety > 1.0);
+        assert_eq!(CandidateMetrics::of(&sparse).pitch_entropy, 1.0);
+
+        let candidates = [repeated, varied, sparse];
+        assert_eq!(
+            rank_candidates(&candidates, CandidateMetric::NoteDensity, true, false),
+            vec![1, 0, 2]
+        );
+        assert_eq!(
+            rank_candidates(&candidates, CandidateMetric::PitchEntropy, false, false),
+            vec![0, 2, 1]
+        );
+        assert_eq!(
+            rank_candidates(&candidates, CandidateMetric::RhythmicVariety, true, true),
+            vec![1, 0]
+        );
+    }
+}
@@ -0,0 +1,378 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::LlmError;
+
+const PITCH_CLASS_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// Inclusive pitch bounds, written `C2..C4` with middle C (60) as `C4`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PitchRange {
+    pub low: u8,
+    pub high: u8,
+}
+
+impl PitchRange {
+    pub fn label(self) -> String {
+        format!("{}..{}", pitch_name(self.low), pitch_name(self.high))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RhythmGrid {
+    Quarter,
+    Eighth,
+    EighthTriplet,
+    Sixteenth,
+    SixteenthTriplet,
+    ThirtySecond,
+}
+
+impl RhythmGrid {
+    const ALL: [Self; 6] = [
+        Self::Quarter,
+        Self::Eighth,
+        Self::EighthTriplet,
+        Self::Sixteenth,
+        Self::SixteenthTriplet,
+        Self::ThirtySecond,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Quarter => "4th",
+            Self::Eighth => "8th",
+            Self::EighthTriplet => "8t",
+            Self::Sixteenth => "16th",
+            Self::SixteenthTriplet => "16t",
+            Self::ThirtySecond => "32nd",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Quarter => "quarter notes",
+            Self::Eighth => "eighth notes",
+            Self::EighthTriplet => "eighth-note triplets",
+            Self::Sixteenth => "sixteenth notes",
+            Self::SixteenthTriplet => "sixteenth-note triplets",
+            Self::ThirtySecond => "thirty-second notes",
+        }
+    }
+
+    /// Grid steps per quarter-note beat.
+    pub fn steps_per_beat(self) -> u8 {
+        match self {
+            Self::Quarter => 1,
+            Self::Eighth => 2,
+            Self::EighthTriplet => 3,
+            Self::Sixteenth => 4,
+            Self::SixteenthTriplet => 6,
+            Self::ThirtySecond => 8,
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        let value = value.to_ascii_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|grid| grid.label() == value)
+            .or(match value.as_str() {
+                "quarter" | "1/4" => Some(Self::Quarter),
+                "eighth" | "1/8" => Some(Self::Eighth),
+                "sixteenth" | "1/16" => Some(Self::Sixteenth),
+                "1/32" => Some(Self::ThirtySecond),
+                _ => None,
+            })
+    }
+}
+
+/// An interval to keep out of the line, e.g. `b9` (13 semitones).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConstraintInterval {
+    pub name: &'static str,
+    pub semitones: u8,
+}
+
+impl ConstraintInterval {
+    const KNOWN: [Self; 19] = [
+        Self::new("b2", 1),
+        Self::new("2", 2),
+        Self::new("b3", 3),
+        Self::new("3", 4),
+        Self::new("4", 5),
+        Self::new("#4", 6),
+        Self::new("b5", 6),
+        Self::new("5", 7),
+        Self::new("b6", 8),
+        Self::new("6", 9),
+        Self::new("b7", 10),
+        Self::new("7", 11),
+        Self::new("b9", 13),
+        Self::new("9", 14),
+        Self::new("#9", 15),
+        Self::new("11", 17),
+        Self::new("#11", 18),
+        Self::new("b13", 20),
+        Self::new("13", 21),
+    ];
+
+    const fn new(name: &'static str, semitones: u8) -> Self {
+        Self { name, semitones }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        let value = match value {
+            "m2" => "b2",
+            "m3" => "b3",
+            "tritone" => "#4",
+            "m6" => "b6",
+            "m7" => "b7",
+            other => other,
+        };
+        Self::KNOWN
+            .into_iter()
+            .find(|interval| interval.name == value)
+    }
+}
+
+/// Hard musical constraints written in a small `name: value; ...` language, for example
+/// `range: C2..C4; rhythm: 16th; avoid: b9, #11`.
+///
+/// Serialized as that text so saved requests stay readable and are re-validated on load.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct GenerationConstraints {
+    pub range: Option<PitchRange>,
+    pub rhythm: Option<RhythmGrid>,
+    pub avoid: Vec<ConstraintInterval>,
+}
+
+impl GenerationConstraints {
+    /// Parses statements separated by `;` or newlines. Each name may appear at most once.
+    pub fn parse(source: &str) -> Result<Self, LlmError> {
+        let mut constraints = Self::default();
+        for statement in source
+            .split([';', '\n'])
+            .map(str::trim)
+            .filter(|statement| !statement.is_empty())
+        {
+            let Some((name, value)) = statement.split_once(':') else {
+                return Err(LlmError::validation(format!(
+                    "constraint `{statement}` must look like `name: value`"
+                )));
+            };
+            let name = name.trim().to_ascii_lowercase();
+            let value = value.trim();
+            if value.is_empty() {
+                return Err(LlmError::validation(format!(
+                    "constraint `{name}` needs a value"
+                )));
+            }
+            match name.as_str() {
+                "range" => {
+                    if constraints.range.is_some() {
+                        return Err(duplicate(&name));
+                    }
+                    constraints.range = Some(parse_range(value)?);
+                }
+                "rhythm" => {
+                    if constraints.rhythm.is_some() {
+                        return Err(duplicate(&name));
+                    }
+                    constraints.rhythm = Some(RhythmGrid::parse(value).ok_or_else(|| {
+                        LlmError::validation(format!(
+                            "rhythm `{value}` is not one of 4th, 8th, 8t, 16th, 16t, 32nd"
+                        ))
+                    })?);
+                }
+                "avoid" => {
+                    if !constraints.avoid.is_empty() {
+                        return Err(duplicate(&name));
+                    }
+                    for interval in value.split(',').map(str::trim) {
+                        let interval = ConstraintInterval::parse(interval).ok_or_else(|| {
+                            LlmError::validation(format!(
+                                "avoid `{interval}` is not an interval such as b9, #11, or 3"
+                            ))
+                        })?;
+                        if !constraints.avoid.contains(&interval) {
+                            constraints.avoid.push(interval);
+                        }
+                    }
+                }
+                _ => {
+                    return Err(LlmError::validation(format!(
+                        "unknown constraint `{name}`; expected range, rhythm, or avoid"
+                    )));
+                }
+            }
+        }
+        Ok(constraints)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.range.is_none() && self.rhythm.is_none() && self.avoid.is_empty()
+    }
+
+    pub fn validate(&self) -> Result<(), LlmError> {
+        if let Some(range) = self.range
+            && (range.low > range.high || range.high > 127)
+        {
+            return Err(LlmError::validation(format!(
+                "constraint range must be ascending within 0..=127 (got {}..{})",
+                range.low, range.high
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for GenerationConstraints {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut statements = Vec::new();
+        if let Some(range) = self.range {
+            statements.push(format!("range: {}", range.label()));
+        }
+        if let Some(rhythm) = self.rhythm {
+            statements.push(format!("rhythm: {}", rhythm.label()));
+        }
+        if !self.avoid.is_empty() {
+            let intervals = self
+                .avoid
+                .iter()
+                .map(|interval| interval.name)
+                .collect::<Vec<_>>();
+            statements.push(format!("avoid: {}", intervals.join(", ")));
+        }
+        formatter.write_str(&statements.join("; "))
+    }
+}
+
+impl TryFrom<String> for GenerationConstraints {
+    type Error = LlmError;
+
+    fn try_from(source: String) -> Result<Self, Self::Error> {
+        Self::parse(&source)
+    }
+}
+
+impl From<GenerationConstraints> for String {
+    fn from(constraints: GenerationConstraints) -> Self {
+        constraints.to_string()
+    }
+}
+
+fn pitch_name(pitch: u8) -> String {
+    let octave = i16::from(pitch / 12) - 1;
+    format!("{}{octave}", PITCH_CLASS_NAMES[usize::from(pitch % 12)])
+}
+
+fn duplicate(name: &str) -> LlmError {
+    LlmError::validation(format!("constraint `{name}` is given more than once"))
+}
+
+fn parse_range(value: &str) -> Result<PitchRange, LlmError> {
+    let Some((low, high)) = value.split_once("..") else {
+        return Err(LlmError::validation(format!(
+            "range `{value}` must look like C2..C4"
+        )));
+    };
+    let range = PitchRange {
+        low: parse_pitch(low.trim())?,
+        high: parse_pitch(high.trim())?,
+    };
+    if range.low > range.high {
+        return Err(LlmError::validation(format!(
+            "range `{value}` must go from low to high"
+        )));
+    }
+    Ok(range)
+}
+
+fn parse_pitch(value: &str) -> Result<u8, LlmError> {
+    let invalid = || LlmError::validation(format!("`{value}` is not a note such as C4 or F#2"));
+    let mut chars = value.chars();
+    let pitch_class: i16 = match chars.next().map(|letter| letter.to_ascii_uppercase()) {
+        Some('C') => 0,
+        Some('D') => 2,
+        Some('E') => 4,
+        Some('F') => 5,
+        Some('G') => 7,
+        Some('A') => 9,
+        Some('B') => 11,
+        _ => return Err(invalid()),
+    };
+    let rest = chars.as_str();
+    let (accidental, octave) = if let Some(octave) = rest.strip_prefix('#') {
+        (1, octave)
+    } else if let Some(octave) = rest.strip_prefix('b') {
+        (-1, octave)
+    } else {
+        (0, rest)
+    };
+    let octave: i16 = octave.parse().map_err(|_| invalid())?;
+    let pitch = (octave + 1) * 12 + pitch_class + accidental;
+    u8::try_from(pitch)
+        .ok()
+        .filter(|pitch| *pitch <= 127)
+        .ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GenerationConstraints, PitchRange, RhythmGrid};
+
+    #[test]
+    fn parses_statements_and_round_trips_through_text() {
+        let constraints =
+            GenerationConstraints::parse("range: C2..C4; Rhythm: 16th\navoid: b9, tritone, b9")
+                .expect("constraints should parse");
+        assert_eq!(constraints.range, Some(PitchRange { low: 36, high: 60 }));
+        assert_eq!(constraints.rhythm, Some(RhythmGrid::Sixteenth));
+        assert_eq!(
+            constraints
+                .avoid
+                .iter()
+                .map(|interval| interval.semitones)
+                .collect::<Vec<_>>(),
+            vec![13, 6]
+        );
+        assert_eq!(
+            constraints.to_string(),
+            "range: C2..C4; rhythm: 16th; avoid: b9, #4"
+        );
+        assert_eq!(
+            GenerationConstraints::parse(&constraints.to_string()),
+            Ok(constraints)
+        );
+        assert!(
+            GenerationConstraints::parse("  ")
+                .expect("blank input should parse")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_statements() {
+        for source in [
+            "range C2..C4",
+            "range: C4..C2",
+            "range: H2..C4",
+            "range: C2..C10",
+            "rhythm: 7th",
+            "avoid: b4",
+            "tempo: 120",
+            "rhythm: 8th; rhythm: 16th",
+            "avoid:",
+        ] {
+            assert!(
+                GenerationConstraints::parse(source).is_err(),
+                "`{source}` should be rejected"
+            );
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use thiserror::Error;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -13,8 +15,17 @@ pub enum LlmError {
     Validation { message: String },
     #[error("provider authentication failed")]
     Auth,
+    #[error("provider credentials expired")]
+    AuthExpired,
+    /// `retry_after` comes from the provider's `Retry-After` header when it sent one.
     #[error("provider rate limit reached")]
-    RateLimited,
+    RateLimited { retry_after: Option<Duration> },
+    #[error("provider quota or billing limit exceeded")]
+    QuotaExceeded,
+    #[error("provider does not serve model '{model}'")]
+    ModelNotFound { model: String },
+    #[error("provider filtered the response: {message}")]
+    ContentFiltered { message: String },
     #[error("provider request timed out")]
     Timeout,
     #[error("provider returned an invalid response: {message}")]
@@ -44,10 +55,19 @@ impl LlmError {
         }
     }
 
+    pub fn rate_limited(retry_after: Option<Duration>) -> Self {
+        Self::RateLimited { retry_after }
+    }
+
     pub fn category(&self) -> LlmErrorCategory {
         match self {
-            Self::Validation { .. } | Self::Auth => LlmErrorCategory::UserActionRequired,
-            Self::RateLimited | Self::Timeout | Self::Transport { .. } => {
+            Self::Validation { .. }
+            | Self::Auth
+            | Self::AuthExpired
+            | Self::QuotaExceeded
+            | Self::ModelNotFound { .. }
+            | Self::ContentFiltered { .. } => LlmErrorCategory::UserActionRequired,
+            Self::RateLimited { .. } | Self::Timeout | Self::Transport { .. } => {
                 LlmErrorCategory::TemporaryFailure
             }
             Self::InvalidResponse { .. } | Self::Internal { .. } => {
@@ -59,10 +79,17 @@ impl LlmError {
     pub fn is_retryable(&self) -> bool {
         matches!(
             self,
-            Self::RateLimited | Self::Timeout | Self::Transport { .. }
+            Self::RateLimited { .. } | Self::Timeout | Self::Transport { .. }
         )
     }
 
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::RateLimited { retry_after } => *retry_after,
+            _ => None,
+        }
+    }
+
     pub fn user_message(&self) -> String {
         match self {
             Self::Validation { message } => {
@@ -71,9 +98,29 @@ impl LlmError {
             Self::Auth => {
                 "Authentication failed. Check your provider API key and configuration.".to_string()
             }
-            Self::RateLimited => {
+            Self::AuthExpired => {
+                "Your provider credentials have expired. Renew the API key or sign in again."
+                    .to_string()
+            }
+            Self::RateLimited {
+                retry_after: Some(retry_after),
+            } => format!(
+                "The provider is rate limiting requests. Retry in {} s.",
+                retry_after.as_secs().max(1)
+            ),
+            Self::RateLimited { retry_after: None } => {
                 "The provider is rate limiting requests. Please retry in a moment.".to_string()
             }
+            Self::QuotaExceeded => {
+                "Your provider quota is used up. Check the plan or billing settings for your account."
+                    .to_string()
+            }
+            Self::ModelNotFound { model } => {
+                format!("The provider does not offer model '{model}'. Pick another model.")
+            }
+            Self::ContentFiltered { message } => {
+                format!("The provider blocked this request by its content policy. Rephrase the prompt: {message}")
+            }
             Self::Timeout => "The provider did not respond in time. Please retry.".to_string(),
             Self::InvalidResponse { message } => {
                 format!("The provider returned an invalid response format: {message}")
@@ -90,6 +137,8 @@ impl LlmError {
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use super::{LlmError, LlmErrorCategory};
 
     #[test]
@@ -107,7 +156,7 @@ mod tests {
     #[test]
     fn category_maps_temporary_and_internal_errors() {
         assert_eq!(
-            LlmError::RateLimited.category(),
+            LlmError::rate_limited(None).category(),
             LlmErrorCategory::TemporaryFailure
         );
         assert_eq!(
@@ -129,7 +178,7 @@ mod tests {
 
     #[test]
     fn is_retryable_matches_retry_policy() {
-        assert!(LlmError::RateLimited.is_retryable());
+        assert!(LlmError::rate_limited(None).is_retryable());
         assert!(LlmError::Timeout.is_retryable());
         assert!(
             LlmError::Transport {
@@ -140,6 +189,37 @@ mod tests {
         assert!(!LlmError::Auth.is_retryable());
         assert!(!LlmError::validation("invalid request").is_retryable());
         assert!(!LlmError::invalid_response("bad JSON").is_retryable());
+        assert!(!LlmError::QuotaExceeded.is_retryable());
+        assert!(
+            !LlmError::ModelNotFound {
+                model: "gone".to_string()
+            }
+            .is_retryable()
+        );
+    }
+
+    #[test]
+    fn account_and_policy_errors_require_user_action() {
+        for error in [
+            LlmError::AuthExpired,
+            LlmError::QuotaExceeded,
+            LlmError::ModelNotFound {
+                model: "gone".to_string(),
+            },
+            LlmError::ContentFiltered {
+                message: "flagged".to_string(),
+            },
+        ] {
+            assert_eq!(error.category(), LlmErrorCategory::UserActionRequired);
+        }
+    }
+
+    #[test]
+    fn rate_limit_message_includes_retry_after() {
+        let error = LlmError::rate_limited(Some(Duration::from_secs(12)));
+
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(12)));
+        assert!(error.user_message().contains("12 s"));
     }
 
     #[test]
@@ -150,7 +230,7 @@ mod tests {
                 .contains("Check your provider API key")
         );
         assert!(
-            LlmError::RateLimited
+            LlmError::rate_limited(None)
                 .user_message()
                 .contains("rate limiting")
         );
@@ -1,8 +1,31 @@
 use serde::{Deserialize, Serialize};
 
-use super::{LlmError, has_supported_midi_extension};
+use super::{
+    CandidateConfidence, CandidateProcessing, CandidateRepairReport, GenerationConstraints,
+    LlmError, ModeParamSpec, TickResolution, ValidationPolicy, ValidationStrictness,
+    ValidationWarning, has_supported_midi_extension,
+};
 
 const DENSITY_NOTES_PER_BAR_AT_MAX_HINT: f32 = 32.0;
+pub const BEATS_PER_BAR: u32 = 4;
+const FALLBACK_TICKS_PER_BEAT: f32 = 240.0;
+const ON_BEAT_TOLERANCE_DIVISOR: f32 = 16.0;
+const DEFAULT_SYNCOPATION: u8 = 3;
+const MAX_EXPRESSION_CONTROLLER: u8 = 119;
+pub const PITCH_BEND_MIN: i16 = -8192;
+pub const PITCH_BEND_MAX: i16 = 8191;
+const MAX_CHORD_SYMBOL_CHARS: usize = 24;
+/// Schema version of `GenerationRequest` and `GenerationResult`.
+///
+/// 1. Payloads written before the field existed, whose candidates carry no PPQ.
+/// 2. Adds `contract_version`; candidates record the `tick_resolution` their ticks use.
+/// 3. Requests can ask for `anonymize_references`, which older builds would silently ignore.
+///
+/// Older payloads deserialize with their own version and are brought up to date by
+/// `upgrade_contract` wherever they are loaded.
+pub const GENERATION_CONTRACT_VERSION: u32 = 3;
+/// Version assumed for payloads without a `contract_version`.
+pub const LEGACY_GENERATION_CONTRACT_VERSION: u32 = 1;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ModelRef {
@@ -41,6 +64,11 @@ pub struct GenerationParams {
     pub scale: String,
     pub density: u8,
     pub complexity: u8,
+    #[serde(default = "default_syncopation")]
+    pub syncopation: u8,
+    /// PPQ the candidates' ticks are written at, from the project setting.
+    #[serde(default)]
+    pub tick_resolution: TickResolution,
     #[serde(default)]
     pub temperature: Option<f32>,
     #[serde(default)]
@@ -51,30 +79,45 @@ pub struct GenerationParams {
 
 impl GenerationParams {
     pub fn validate(&self) -> Result<(), LlmError> {
+        self.validate_with_spec(ModeParamSpec::ALL)
+    }
+
+    /// Like [`Self::validate`], but skips fields `mode` ignores according to [`ModeParamSpec`].
+    pub fn validate_for_mode(&self, mode: GenerationMode) -> Result<(), LlmError> {
+        self.validate_with_spec(ModeParamSpec::for_mode(mode))
+    }
+
+    fn validate_with_spec(&self, spec: ModeParamSpec) -> Result<(), LlmError> {
         if !(20..=300).contains(&self.bpm) {
             return Err(LlmError::validation(format!(
                 "bpm must be in 20..=300 (got {})",
                 self.bpm
             )));
         }
-        if self.key.trim().is_empty() {
+        if spec.key && self.key.trim().is_empty() {
             return Err(LlmError::validation("key must not be empty"));
         }
-        if self.scale.trim().is_empty() {
+        if spec.scale && self.scale.trim().is_empty() {
             return Err(LlmError::validation("scale must not be empty"));
         }
-        if !(1..=5).contains(&self.density) {
+        if spec.density && !(1..=5).contains(&self.density) {
             return Err(LlmError::validation(format!(
                 "density must be in 1..=5 (got {})",
                 self.density
             )));
         }
-        if !(1..=5).contains(&self.complexity) {
+        if spec.complexity && !(1..=5).contains(&self.complexity) {
             return Err(LlmError::validation(format!(
                 "complexity must be in 1..=5 (got {})",
                 self.complexity
             )));
         }
+        if spec.syncopation && !(1..=5).contains(&self.syncopation) {
+            return Err(LlmError::validation(format!(
+                "syncopation must be in 1..=5 (got {})",
+                self.syncopation
+            )));
+        }
         if let Some(temperature) = self.temperature
             && !(0.0..=2.0).contains(&temperature)
         {
@@ -171,6 +214,38 @@ pub struct MidiReferenceSummary {
     pub max_pitch: u8,
     #[serde(default)]
     pub events: Vec<MidiReferenceEvent>,
+    /// Set when the events and pitch range were moved into the requested key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transposition: Option<ReferenceTransposition>,
+    /// Set when the file was written at a different tempo than the request's BPM.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tempo: Option<ReferenceTempo>,
+}
+
+/// How a reference was transposed before it was summarized for the prompt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReferenceTransposition {
+    /// Key detected in the material as loaded, e.g. `E minor`.
+    pub from_key: String,
+    pub to_key: String,
+    /// Added to every pitch.
+    pub semitones: i8,
+}
+
+/// The tempo a reference was written at next to the one it is generated against. Bars and
+/// ticks stay in musical time, and the reference's tempo events are kept as written.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ReferenceTempo {
+    /// From the file's first tempo event.
+    pub source_bpm: f32,
+    pub target_bpm: u16,
+}
+
+impl ReferenceTempo {
+    /// Above 1 when the reference plays faster at the target tempo than it was written.
+    pub fn speed_ratio(self) -> f32 {
+        f32::from(self.target_bpm) / self.source_bpm
+    }
 }
 
 impl MidiReferenceSummary {
@@ -236,6 +311,104 @@ pub fn calculate_reference_density_hint(note_count: u32, bars: u16) -> f32 {
     (notes_per_bar / DENSITY_NOTES_PER_BAR_AT_MAX_HINT).clamp(0.0, 1.0)
 }
 
+/// Snaps the average ticks per beat to the nearest common MIDI resolution.
+/// Reference summaries carry no explicit PPQ, so consumers infer it from bars and note extent.
+pub fn estimate_ticks_per_beat(total_beats: usize, max_end_tick: u32) -> f32 {
+    if total_beats == 0 || max_end_tick == 0 {
+        return FALLBACK_TICKS_PER_BEAT;
+    }
+
+    let estimated = max_end_tick as f32 / total_beats as f32;
+    if !estimated.is_finite() || estimated < f32::from(TickResolution::COMMON[0].ticks_per_beat()) {
+        return FALLBACK_TICKS_PER_BEAT;
+    }
+
+    f32::from(TickResolution::nearest_common(estimated).ticks_per_beat())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeSignature {
+    pub numerator: u16,
+    pub denominator: u16,
+}
+
+/// A reference slot in use in the session and the MIDI channel it listens on, if live.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DawTrackRole {
+    pub slot: ReferenceSlot,
+    pub source: ReferenceSource,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<u8>,
+}
+
+/// Project settings from the host and the helper, passed to the model as session context.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DawContext {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host_tempo_bpm: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_signature: Option<TimeSignature>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loop_length_beats: Option<f64>,
+    /// The host's name for the plugin's track or clip, which often names the song section
+    /// being worked on (e.g. "Chorus 2").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host_track_name: Option<String>,
+    #[serde(default)]
+    pub track_roles: Vec<DawTrackRole>,
+}
+
+impl DawContext {
+    pub fn validate(&self) -> Result<(), LlmError> {
+        if self
+            .host_tempo_bpm
+            .is_some_and(|tempo| !tempo.is_finite() || tempo <= 0.0)
+        {
+            return Err(LlmError::validation(
+                "daw_context.host_tempo_bpm must be a positive number",
+            ));
+        }
+        if self
+            .time_signature
+            .is_some_and(|signature| signature.numerator == 0 || signature.denominator == 0)
+        {
+            return Err(LlmError::validation(
+                "daw_context.time_signature must have a non-zero numerator and denominator",
+            ));
+        }
+        if self
+            .loop_length_beats
+            .is_some_and(|length| !length.is_finite() || length <= 0.0)
+        {
+            return Err(LlmError::validation(
+                "daw_context.loop_length_beats must be a positive number",
+            ));
+        }
+        if self
+            .host_track_name
+            .as_deref()
+            .is_some_and(|name| name.trim().is_empty())
+        {
+            return Err(LlmError::validation(
+                "daw_context.host_track_name must not be blank",
+            ));
+        }
+        if self
+            .track_roles
+            .iter()
+            .filter_map(|role| role.channel)
+            .any(|channel| !(1..=16).contains(&channel))
+        {
+            return Err(LlmError::validation(
+                "daw_context.track_roles channel must be in 1..=16",
+            ));
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GenerationRequest {
     pub request_id: String,
@@ -247,10 +420,38 @@ pub struct GenerationRequest {
     pub references: Vec<MidiReferenceSummary>,
     #[serde(default = "default_variation_count")]
     pub variation_count: u8,
+    /// Notes every candidate must keep unchanged (pitch, onset, and channel).
+    #[serde(default)]
+    pub locked_notes: Vec<GeneratedNote>,
+    #[serde(default = "default_contract_version")]
+    pub contract_version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daw_context: Option<DawContext>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub constraints: Option<GenerationConstraints>,
+    /// How candidates breaking the range, length, or scale are handled; unchecked when `None`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validation: Option<ValidationStrictness>,
+    /// Send file references as `reference-<n>.mid`. The request keeps the real paths so history
+    /// and replays still know the files; only the copy handed to the provider is renamed.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub anonymize_references: bool,
 }
 
 impl GenerationRequest {
+    /// Brings a payload written under an older contract up to [`GENERATION_CONTRACT_VERSION`].
+    /// Unknown versions are left for [`Self::validate`] to reject.
+    pub fn upgrade_contract(&mut self) {
+        // Everything versions 2 and 3 added to requests has a serde default.
+        if (LEGACY_GENERATION_CONTRACT_VERSION..GENERATION_CONTRACT_VERSION)
+            .contains(&self.contract_version)
+        {
+            self.contract_version = GENERATION_CONTRACT_VERSION;
+        }
+    }
+
     pub fn validate(&self) -> Result<(), LlmError> {
+        validate_contract_version(self.contract_version)?;
         if self.request_id.trim().is_empty() {
             return Err(LlmError::validation("request_id must not be empty"));
         }
@@ -258,15 +459,27 @@ impl GenerationRequest {
         if self.prompt.trim().is_empty() {
             return Err(LlmError::validation("prompt must not be empty"));
         }
-        self.params.validate()?;
+        self.params.validate_for_mode(self.mode)?;
         if self.variation_count == 0 {
             return Err(LlmError::validation(
                 "variation_count must be greater than 0",
             ));
         }
+        for note in &self.locked_notes {
+            note.validate()?;
+        }
         for reference in &self.references {
             reference.validate()?;
         }
+        if let Some(daw_context) = &self.daw_context {
+            daw_context.validate()?;
+        }
+        if let Some(constraints) = &self.constraints {
+            constraints.validate()?;
+        }
+        if let Some(policy) = ValidationPolicy::for_request(self) {
+            policy.check_request(self)?;
+        }
         self.validate_mode_reference_requirements()?;
         Ok(())
     }
@@ -346,6 +559,86 @@ impl GeneratedNote {
         }
         Ok(())
     }
+
+    pub fn matches_locked_note(&self, locked: &GeneratedNote) -> bool {
+        self.pitch == locked.pitch
+            && self.start_tick == locked.start_tick
+            && self.channel == locked.channel
+    }
+}
+
+/// A controller or pitch-bend message on the candidate timeline; `channel` is 1-based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GeneratedControlEvent {
+    ControlChange {
+        tick: u32,
+        controller: u8,
+        value: u8,
+        #[serde(default = "default_channel")]
+        channel: u8,
+    },
+    /// `value` is centred on 0, in -8192..=8191.
+    PitchBend {
+        tick: u32,
+        value: i16,
+        #[serde(default = "default_channel")]
+        channel: u8,
+    },
+}
+
+impl GeneratedControlEvent {
+    pub fn tick(&self) -> u32 {
+        match *self {
+            Self::ControlChange { tick, .. } | Self::PitchBend { tick, .. } => tick,
+        }
+    }
+
+    pub fn channel(&self) -> u8 {
+        match *self {
+            Self::ControlChange { channel, .. } | Self::PitchBend { channel, .. } => channel,
+        }
+    }
+
+    pub fn with_tick(mut self, new_tick: u32) -> Self {
+        match &mut self {
+            Self::ControlChange { tick, .. } | Self::PitchBend { tick, .. } => *tick = new_tick,
+        }
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), LlmError> {
+        match *self {
+            // 120..=127 are channel mode messages (all notes off, reset), not expression.
+            Self::ControlChange {
+                controller, value, ..
+            } => {
+                if controller > MAX_EXPRESSION_CONTROLLER {
+                    return Err(LlmError::validation(format!(
+                        "control change controller must be in 0..={MAX_EXPRESSION_CONTROLLER}"
+                    )));
+                }
+                if value > 127 {
+                    return Err(LlmError::validation(
+                        "control change value must be in 0..=127",
+                    ));
+                }
+            }
+            Self::PitchBend { value, .. } => {
+                if !(PITCH_BEND_MIN..=PITCH_BEND_MAX).contains(&value) {
+                    return Err(LlmError::validation(format!(
+                        "pitch bend value must be in {PITCH_BEND_MIN}..={PITCH_BEND_MAX}"
+                    )));
+                }
+            }
+        }
+        if !(1..=16).contains(&self.channel()) {
+            return Err(LlmError::validation(
+                "control event channel must be in 1..=16",
+            ));
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -353,8 +646,21 @@ pub struct GenerationCandidate {
     pub id: String,
     pub bars: u16,
     pub notes: Vec<GeneratedNote>,
+    /// PPQ of `notes` and `control_events`, taken from the request rather than the response.
+    /// Contract version 1 payloads lack it; `GenerationResult::upgrade_contract` estimates it.
+    #[serde(default)]
+    pub tick_resolution: TickResolution,
     #[serde(default)]
     pub score_hint: Option<f32>,
+    /// Short descriptive name the model may give the candidate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Expression that goes with the notes, such as sustain, mod wheel, or bends.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub control_events: Vec<GeneratedControlEvent>,
+    /// The other version of the notes when post-processing changed them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub processing: Option<CandidateProcessing>,
 }
 
 impl GenerationCandidate {
@@ -380,8 +686,55 @@ impl GenerationCandidate {
         for note in &self.notes {
             note.validate()?;
         }
+        for event in &self.control_events {
+            event.validate()?;
+        }
         Ok(())
     }
+
+    /// PPQ inferred from the bar count and how far the notes reach, falling back to 240 when
+    /// they give nothing to go on; what the piano roll assumed before candidates recorded it.
+    pub fn estimated_tick_resolution(&self) -> TickResolution {
+        let total_beats = usize::from(self.bars.max(1)) * BEATS_PER_BAR as usize;
+        let max_end_tick = self
+            .notes
+            .iter()
+            .map(|note| note.start_tick.saturating_add(note.duration_tick))
+            .max()
+            .unwrap_or(0);
+        TickResolution::nearest_common(estimate_ticks_per_beat(total_beats, max_end_tick))
+    }
+
+    pub fn contains_locked_notes(&self, locked_notes: &[GeneratedNote]) -> bool {
+        locked_notes.iter().all(|locked| {
+            self.notes
+                .iter()
+                .any(|note| note.matches_locked_note(locked))
+        })
+    }
+
+    /// Fraction of note onsets that fall between beats, in 0.0..=1.0.
+    pub fn off_beat_ratio(&self) -> f32 {
+        if self.notes.is_empty() {
+            return 0.0;
+        }
+        let ticks_per_beat = f32::from(self.tick_resolution.ticks_per_beat());
+        let tolerance = ticks_per_beat / ON_BEAT_TOLERANCE_DIVISOR;
+        let off_beat_count = self
+            .notes
+            .iter()
+            .filter(|note| {
+                let offset = note.start_tick as f32 % ticks_per_beat;
+                offset > tolerance && ticks_per_beat - offset > tolerance
+            })
+            .count();
+        off_beat_count as f32 / self.notes.len() as f32
+    }
+}
+
+/// Maps an off-beat ratio onto the 1..=5 scale used by `GenerationParams::syncopation`.
+pub fn syncopation_level_for_off_beat_ratio(ratio: f32) -> u8 {
+    ((ratio.clamp(0.0, 1.0) * 5.0).floor() as u8 + 1).min(5)
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -414,6 +767,31 @@ impl GenerationUsage {
     }
 }
 
+/// A chord symbol such as `Cmaj7` or `F#m7b5` that holds from the start of `bar` (1-based).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChordLabel {
+    pub bar: u16,
+    pub symbol: String,
+}
+
+impl ChordLabel {
+    pub fn validate(&self) -> Result<(), LlmError> {
+        if self.bar == 0 {
+            return Err(LlmError::validation("chord bar must be 1 or greater"));
+        }
+        let symbol = self.symbol.trim();
+        if symbol.is_empty() {
+            return Err(LlmError::validation("chord symbol must not be empty"));
+        }
+        if symbol.chars().count() > MAX_CHORD_SYMBOL_CHARS {
+            return Err(LlmError::validation(format!(
+                "chord symbol must be at most {MAX_CHORD_SYMBOL_CHARS} characters"
+            )));
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub struct GenerationMetadata {
     #[serde(default)]
@@ -424,6 +802,18 @@ pub struct GenerationMetadata {
     pub stop_reason: Option<String>,
     #[serde(default)]
     pub usage: Option<GenerationUsage>,
+    /// Per-bar chord labels for the candidates, ordered by bar.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chords: Vec<ChordLabel>,
+    /// Candidates whose notes were changed by the post-parse repair pass.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub repairs: Vec<CandidateRepairReport>,
+    /// Quality score for each candidate, computed when the response is parsed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub confidence: Vec<CandidateConfidence>,
+    /// Candidates the request's validation policy dropped or corrected.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub validation_warnings: Vec<ValidationWarning>,
 }
 
 impl GenerationMetadata {
@@ -445,6 +835,9 @@ impl GenerationMetadata {
         if let Some(usage) = &self.usage {
             usage.validate()?;
         }
+        for chord in &self.chords {
+            chord.validate()?;
+        }
         Ok(())
     }
 }
@@ -456,10 +849,28 @@ pub struct GenerationResult {
     pub candidates: Vec<GenerationCandidate>,
     #[serde(default)]
     pub metadata: GenerationMetadata,
+    #[serde(default = "default_contract_version")]
+    pub contract_version: u32,
 }
 
 impl GenerationResult {
+    /// Brings a payload written under an older contract up to [`GENERATION_CONTRACT_VERSION`].
+    /// Unknown versions are left for [`Self::validate`] to reject.
+    pub fn upgrade_contract(&mut self) {
+        if self.contract_version == LEGACY_GENERATION_CONTRACT_VERSION {
+            for candidate in &mut self.candidates {
+                candidate.tick_resolution = candidate.estimated_tick_resolution();
+            }
+        }
+        if (LEGACY_GENERATION_CONTRACT_VERSION..GENERATION_CONTRACT_VERSION)
+            .contains(&self.contract_version)
+        {
+            self.contract_version = GENERATION_CONTRACT_VERSION;
+        }
+    }
+
     pub fn validate(&self) -> Result<(), LlmError> {
+        validate_contract_version(self.contract_version)?;
         if self.request_id.trim().is_empty() {
             return Err(LlmError::validation("request_id must not be empty"));
         }
@@ -473,12 +884,55 @@ impl GenerationResult {
         self.metadata.validate()?;
         Ok(())
     }
+
+    /// Stamps the request's PPQ on every candidate. The prompt states it and the response
+    /// never reports it back, so it has to be set before anything reads the ticks.
+    pub fn set_tick_resolution(&mut self, resolution: TickResolution) {
+        for candidate in &mut self.candidates {
+            candidate.tick_resolution = resolution;
+        }
+    }
+
+    /// Drops candidates that removed a locked note; fails if none keep every lock.
+    pub fn retain_candidates_with_locked_notes(
+        &mut self,
+        locked_notes: &[GeneratedNote],
+    ) -> Result<(), LlmError> {
+        if locked_notes.is_empty() {
+            return Ok(());
+        }
+        self.candidates
+            .retain(|candidate| candidate.contains_locked_notes(locked_notes));
+        if self.candidates.is_empty() {
+            return Err(LlmError::invalid_response(
+                "every candidate removed or moved at least one locked note",
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn validate_contract_version(version: u32) -> Result<(), LlmError> {
+    if version == 0 || version > GENERATION_CONTRACT_VERSION {
+        return Err(LlmError::validation(format!(
+            "contract_version must be between 1 and {GENERATION_CONTRACT_VERSION}, got {version}"
+        )));
+    }
+    Ok(())
+}
+
+fn default_contract_version() -> u32 {
+    LEGACY_GENERATION_CONTRACT_VERSION
 }
 
 fn default_channel() -> u8 {
     1
 }
 
+fn default_syncopation() -> u8 {
+    DEFAULT_SYNCOPATION
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -505,6 +959,8 @@ mod tests {
             min_pitch: 60,
             max_pitch: 72,
             events: vec![sample_event()],
+            transposition: None,
+            tempo: None,
         }
     }
 
@@ -525,6 +981,36 @@ mod tests {
                 event: "LiveMidi channel=2 status=0x91 data1=55 data2=100 port=1 time=120"
                     .to_string(),
             }],
+            transposition: None,
+            tempo: None,
+        }
+    }
+
+    fn sample_result() -> GenerationResult {
+        GenerationResult {
+            request_id: "req-1".to_string(),
+            model: ModelRef {
+                provider: "anthropic".to_string(),
+                model: "claude-3-5-sonnet".to_string(),
+            },
+            candidates: vec![GenerationCandidate {
+                id: "cand-1".to_string(),
+                bars: 4,
+                notes: vec![GeneratedNote {
+                    pitch: 60,
+                    start_tick: 0,
+                    duration_tick: 120,
+                    velocity: 100,
+                    channel: 1,
+                }],
+                tick_resolution: TickResolution::DEFAULT,
+                score_hint: Some(0.8),
+                title: None,
+                control_events: Vec::new(),
+                processing: None,
+            }],
+            metadata: GenerationMetadata::default(),
+            contract_version: GENERATION_CONTRACT_VERSION,
         }
     }
 
@@ -546,12 +1032,20 @@ mod tests {
                 scale: "major".to_string(),
                 density: 3,
                 complexity: 3,
+                syncopation: 3,
+                tick_resolution: TickResolution::DEFAULT,
                 temperature: Some(0.7),
                 top_p: Some(0.9),
                 max_tokens: Some(2048),
             },
             references,
             variation_count: 1,
+            locked_notes: Vec::new(),
+            contract_version: GENERATION_CONTRACT_VERSION,
+            daw_context: None,
+            constraints: None,
+            validation: None,
+            anonymize_references: false,
         }
     }
 
@@ -571,12 +1065,20 @@ mod tests {
                 scale: "major".to_string(),
                 density: 3,
                 complexity: 3,
+                syncopation: 3,
+                tick_resolution: TickResolution::DEFAULT,
                 temperature: Some(0.7),
                 top_p: Some(0.9),
                 max_tokens: Some(2048),
             },
             references: Vec::new(),
             variation_count: 1,
+            locked_notes: Vec::new(),
+            contract_version: GENERATION_CONTRACT_VERSION,
+            daw_context: None,
+            constraints: None,
+            validation: None,
+            anonymize_references: false,
         };
 
         assert!(matches!(
@@ -585,6 +1087,319 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn payloads_without_contract_version_are_upgraded_from_version_one() {
+        let mut request_json =
+            serde_json::to_value(valid_request(GenerationMode::Melody, Vec::new()))
+                .expect("request should serialize");
+        request_json
+            .as_object_mut()
+            .expect("request should be an object")
+            .remove("contract_version");
+        let mut result_json =
+            serde_json::to_value(sample_result()).expect("result should serialize");
+        result_json
+            .as_object_mut()
+            .expect("result should be an object")
+            .remove("contract_version");
+
+        let mut request: GenerationRequest =
+            serde_json::from_value(request_json).expect("legacy request should deserialize");
+        let mut result: GenerationResult =
+            serde_json::from_value(result_json).expect("legacy result should deserialize");
+        assert_eq!(request.contract_version, LEGACY_GENERATION_CONTRACT_VERSION);
+        assert_eq!(result.contract_version, LEGACY_GENERATION_CONTRACT_VERSION);
+
+        request.upgrade_contract();
+        result.upgrade_contract();
+
+        assert_eq!(request.contract_version, GENERATION_CONTRACT_VERSION);
+        assert_eq!(result.contract_version, GENERATION_CONTRACT_VERSION);
+        assert!(request.validate().is_ok());
+        assert!(result.validate().is_ok());
+    }
+
+    #[test]
+    fn legacy_candidates_get_an_estimated_tick_resolution_on_upgrade() {
+        let mut result_json =
+            serde_json::to_value(sample_result()).expect("result should serialize");
+        let result_object = result_json
+            .as_object_mut()
+            .expect("result should be an object");
+        result_object.remove("contract_version");
+        let candidate = result_object["candidates"][0]
+            .as_object_mut()
+            .expect("candidate should be an object");
+        candidate.remove("tick_resolution");
+        let mut spanning = candidate.clone();
+        spanning.insert("id".to_string(), serde_json::json!("cand-2"));
+        spanning.insert(
+            "notes".to_string(),
+            serde_json::json!([{
+                "pitch": 60,
+                "start_tick": 15 * 96,
+                "duration_tick": 96,
+                "velocity": 100,
+            }]),
+        );
+        result_object["candidates"]
+            .as_array_mut()
+            .expect("candidates should be an array")
+            .push(spanning.into());
+
+        let mut result: GenerationResult =
+            serde_json::from_value(result_json).expect("legacy result should deserialize");
+        result.upgrade_contract();
+
+        // One short note says nothing about the PPQ, so the piano roll's old fallback applies.
+        assert_eq!(result.candidates[0].tick_resolution.ticks_per_beat(), 240);
+        assert_eq!(result.candidates[1].tick_resolution.ticks_per_beat(), 96);
+    }
+
+    #[test]
+    fn upgrading_a_current_result_keeps_its_tick_resolution() {
+        let mut result = sample_result();
+        result.upgrade_contract();
+
+        assert_eq!(result, sample_result());
+    }
+
+    #[test]
+    fn newer_contract_versions_are_rejected() {
+        let request = GenerationRequest {
+            contract_version: GENERATION_CONTRACT_VERSION + 1,
+            ..valid_request(GenerationMode::Melody, Vec::new())
+        };
+        let result = GenerationResult {
+            contract_version: 0,
+            ..sample_result()
+        };
+
+        assert!(matches!(
+            request.validate(),
+            Err(LlmError::Validation { .. })
+        ));
+        assert!(matches!(
+            result.validate(),
+            Err(LlmError::Validation { .. })
+        ));
+    }
+
+    #[test]
+    fn constraints_serialize_as_dsl_text_and_reparse_on_load() {
+        let mut request = valid_request(GenerationMode::Bassline, Vec::new());
+        request.constraints = Some(
+            GenerationConstraints::parse("range: E1..E3; rhythm: 8th")
+                .expect("constraints should parse"),
+        );
+        let json = serde_json::to_value(&request).expect("request should serialize");
+        assert_eq!(json["constraints"], "range: E1..E3; rhythm: 8th");
+
+        let restored: GenerationRequest =
+            serde_json::from_value(json.clone()).expect("request should deserialize");
+        assert_eq!(restored.constraints, request.constraints);
+
+        let mut invalid = json;
+        invalid["constraints"] = serde_json::Value::from("range: E3..E1");
+        assert!(serde_json::from_value::<GenerationRequest>(invalid).is_err());
+    }
+
+    #[test]
+    fn daw_context_is_optional_and_validated() {
+        let mut request = valid_request(GenerationMode::Melody, Vec::new());
+        let json = serde_json::to_value(&request).expect("request should serialize");
+        assert!(json.get("daw_context").is_none());
+
+        request.daw_context = Some(DawContext {
+            host_tempo_bpm: Some(124.0),
+            time_signature: Some(TimeSignature {
+                numerator: 7,
+                denominator: 8,
+            }),
+            key: Some("D dorian".to_string()),
+            loop_length_beats: Some(14.0),
+            host_track_name: Some("Chorus 2".to_string()),
+            track_roles: vec![DawTrackRole {
+                slot: ReferenceSlot::Bassline,
+                source: ReferenceSource::Live,
+                channel: Some(2),
+            }],
+        });
+        assert!(request.validate().is_ok());
+
+        request.daw_context = Some(DawContext {
+            track_roles: vec![DawTrackRole {
+                slot: ReferenceSlot::Bassline,
+                source: ReferenceSource::Live,
+                channel: Some(17),
+            }],
+            ..DawContext::default()
+        });
+        assert!(matches!(
+            request.validate(),
+            Err(LlmError::Validation { .. })
+        ));
+
+        request.daw_context = Some(DawContext {
+            host_track_name: Some("  ".to_string()),
+            ..DawContext::default()
+        });
+        assert!(matches!(
+            request.validate(),
+            Err(LlmError::Validation { .. })
+        ));
+    }
+
+    #[test]
+    fn chord_labels_are_optional_and_validated() {
+        let metadata: GenerationMetadata = serde_json::from_str(
+            r#"{"chords":[{"bar":1,"symbol":"Am7"},{"bar":3,"symbol":"D7"}]}"#,
+        )
+        .expect("chords should deserialize");
+        assert_eq!(metadata.chords.len(), 2);
+        assert!(metadata.validate().is_ok());
+
+        let serialized =
+            serde_json::to_value(GenerationMetadata::default()).expect("metadata should serialize");
+        assert!(serialized.get("chords").is_none());
+
+        for invalid in [
+            ChordLabel {
+                bar: 0,
+                symbol: "C".to_string(),
+            },
+            ChordLabel {
+                bar: 1,
+                symbol: "  ".to_string(),
+            },
+            ChordLabel {
+                bar: 1,
+                symbol: "C".repeat(MAX_CHORD_SYMBOL_CHARS + 1),
+            },
+        ] {
+            let metadata = GenerationMetadata {
+                chords: vec![invalid],
+                ..GenerationMetadata::default()
+            };
+            assert!(matches!(
+                metadata.validate(),
+                Err(LlmError::Validation { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn estimate_ticks_per_beat_snaps_to_common_resolutions() {
+        assert_eq!(estimate_ticks_per_beat(16, 16 * 480), 480.0);
+        assert_eq!(estimate_ticks_per_beat(16, 16 * 470), 480.0);
+        assert_eq!(estimate_ticks_per_beat(8, 8 * 120), 120.0);
+        assert_eq!(estimate_ticks_per_beat(0, 960), FALLBACK_TICKS_PER_BEAT);
+        assert_eq!(estimate_ticks_per_beat(16, 32), FALLBACK_TICKS_PER_BEAT);
+    }
+
+    #[test]
+    fn control_events_are_optional_and_validated() {
+        let candidate: GenerationCandidate = serde_json::from_str(
+            r#"{
+              "id": "cand-1",
+              "bars": 1,
+              "notes": [{"pitch": 60, "start_tick": 0, "duration_tick": 480, "velocity": 96}],
+              "control_events": [{"type": "control_change", "tick": 0, "controller": 64, "value": 127}]
+            }"#,
+        )
+        .expect("control events should deserialize");
+        assert_eq!(candidate.control_events[0].channel(), 1);
+        assert!(candidate.validate().is_ok());
+
+        let serialized = serde_json::to_value(&sample_result().candidates[0])
+            .expect("candidate should serialize");
+        assert!(serialized.get("control_events").is_none());
+
+        for invalid in [
+            GeneratedControlEvent::ControlChange {
+                tick: 0,
+                controller: 121,
+                value: 0,
+                channel: 1,
+            },
+            GeneratedControlEvent::PitchBend {
+                tick: 0,
+                value: PITCH_BEND_MIN - 1,
+                channel: 1,
+            },
+            GeneratedControlEvent::PitchBend {
+                tick: 0,
+                value: 0,
+                channel: 17,
+            },
+        ] {
+            let mut candidate = candidate.clone();
+            candidate.control_events = vec![invalid];
+            assert!(matches!(
+                candidate.validate(),
+                Err(LlmError::Validation { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn candidates_missing_locked_notes_are_rejected() {
+        let locked = GeneratedNote {
+            pitch: 67,
+            start_tick: 480,
+            duration_tick: 240,
+            velocity: 90,
+            channel: 1,
+        };
+        let mut result = sample_result();
+        let mut keeps_lock = result.candidates[0].clone();
+        keeps_lock.id = "cand-keeps-lock".to_string();
+        keeps_lock.notes.push(GeneratedNote {
+            velocity: 70,
+            duration_tick: 120,
+            ..locked.clone()
+        });
+        result.candidates.push(keeps_lock);
+
+        result
+            .retain_candidates_with_locked_notes(std::slice::from_ref(&locked))
+            .expect("one candidate keeps the lock");
+        assert_eq!(result.candidates.len(), 1);
+        assert_eq!(result.candidates[0].id, "cand-keeps-lock");
+
+        let mut missing = sample_result();
+        assert!(matches!(
+            missing.retain_candidates_with_locked_notes(&[locked]),
+            Err(LlmError::InvalidResponse { .. })
+        ));
+    }
+
+    #[test]
+    fn off_beat_ratio_counts_onsets_between_beats() {
+        let note = |start_tick| GeneratedNote {
+            pitch: 60,
+            start_tick,
+            duration_tick: 120,
+            velocity: 100,
+            channel: 1,
+        };
+        let candidate = GenerationCandidate {
+            id: "cand-sync".to_string(),
+            bars: 1,
+            notes: vec![note(0), note(480), note(720), note(1440), note(1800)],
+            tick_resolution: TickResolution::DEFAULT,
+            score_hint: None,
+            title: None,
+            control_events: Vec::new(),
+            processing: None,
+        };
+
+        assert_eq!(candidate.off_beat_ratio(), 0.4);
+        assert_eq!(syncopation_level_for_off_beat_ratio(0.0), 1);
+        assert_eq!(syncopation_level_for_off_beat_ratio(0.4), 3);
+        assert_eq!(syncopation_level_for_off_beat_ratio(1.0), 5);
+    }
+
     #[test]
     fn calculate_reference_density_hint_uses_shared_normalization_rule() {
         assert_eq!(calculate_reference_density_hint(16, 4), 0.125);
@@ -715,6 +1530,8 @@ mod tests {
             min_pitch: 60,
             max_pitch: 72,
             events: vec![sample_event()],
+            transposition: None,
+            tempo: None,
         };
 
         assert!(matches!(
@@ -738,6 +1555,8 @@ mod tests {
             min_pitch: 60,
             max_pitch: 72,
             events: vec![sample_event()],
+            transposition: None,
+            tempo: None,
         };
 
         assert!(matches!(
@@ -761,6 +1580,8 @@ mod tests {
             min_pitch: 60,
             max_pitch: 72,
             events: vec![sample_event()],
+            transposition: None,
+            tempo: None,
         };
 
         assert!(matches!(
@@ -784,6 +1605,8 @@ mod tests {
             min_pitch: 60,
             max_pitch: 72,
             events: vec![sample_event()],
+            transposition: None,
+            tempo: None,
         };
 
         assert!(matches!(
@@ -806,6 +1629,8 @@ mod tests {
             min_pitch: 60,
             max_pitch: 72,
             events: vec![sample_event()],
+            transposition: None,
+            tempo: None,
         };
 
         assert!(matches!(
@@ -828,6 +1653,8 @@ mod tests {
             min_pitch: 60,
             max_pitch: 72,
             events: vec![sample_event()],
+            transposition: None,
+            tempo: None,
         };
 
         assert!(reference.validate().is_ok());
@@ -847,6 +1674,8 @@ mod tests {
             min_pitch: 60,
             max_pitch: 72,
             events: vec![sample_event()],
+            transposition: None,
+            tempo: None,
         };
 
         assert!(reference.validate().is_ok());
@@ -866,6 +1695,8 @@ mod tests {
             min_pitch: 60,
             max_pitch: 72,
             events: vec![sample_event()],
+            transposition: None,
+            tempo: None,
         };
 
         assert!(reference.validate().is_ok());
@@ -885,6 +1716,8 @@ mod tests {
             min_pitch: 60,
             max_pitch: 72,
             events: vec![sample_event()],
+            transposition: None,
+            tempo: None,
         };
 
         assert!(reference.validate().is_ok());
@@ -909,6 +1742,8 @@ mod tests {
                 delta_tick: 0,
                 event: "   ".to_string(),
             }],
+            transposition: None,
+            tempo: None,
         };
 
         assert!(matches!(
@@ -932,6 +1767,8 @@ mod tests {
             min_pitch: 60,
             max_pitch: 72,
             events: Vec::new(),
+            transposition: None,
+            tempo: None,
         };
 
         assert!(matches!(
@@ -959,12 +1796,17 @@ mod tests {
                     velocity: 100,
                     channel: 1,
                 }],
+                tick_resolution: TickResolution::DEFAULT,
                 score_hint: Some(0.8),
+                title: None,
+                control_events: Vec::new(),
+                processing: None,
             }],
             metadata: GenerationMetadata {
                 provider_request_id: Some("  ".to_string()),
                 ..GenerationMetadata::default()
             },
+            contract_version: GENERATION_CONTRACT_VERSION,
         };
 
         assert!(matches!(
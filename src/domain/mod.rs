@@ -1,12 +1,37 @@
+mod candidate_confidence;
+mod candidate_metrics;
+mod constraints;
 mod errors;
 mod generation_contract;
 mod midi_path;
+mod mode_params;
+mod note_repair;
+mod privacy_filter;
+mod prompt_lint;
+mod tick_resolution;
+mod validation_policy;
+mod velocity_profile;
 
+pub use candidate_confidence::{CandidateConfidence, ConfidenceLevel};
+pub use candidate_metrics::{CandidateMetric, CandidateMetrics, rank_candidates};
+pub use constraints::{ConstraintInterval, GenerationConstraints, PitchRange, RhythmGrid};
 pub use errors::{LlmError, LlmErrorCategory};
 pub use generation_contract::{
-    FileReferenceInput, GeneratedNote, GenerationCandidate, GenerationMetadata, GenerationMode,
-    GenerationParams, GenerationRequest, GenerationResult, GenerationUsage, MidiReferenceEvent,
-    MidiReferenceSummary, ModelRef, ReferenceSlot, ReferenceSource,
-    calculate_reference_density_hint,
+    BEATS_PER_BAR, ChordLabel, DawContext, DawTrackRole, FileReferenceInput,
+    GENERATION_CONTRACT_VERSION, GeneratedControlEvent, GeneratedNote, GenerationCandidate,
+    GenerationMetadata, GenerationMode, GenerationParams, GenerationRequest, GenerationResult,
+    GenerationUsage, LEGACY_GENERATION_CONTRACT_VERSION, MidiReferenceEvent, MidiReferenceSummary,
+    ModelRef, PITCH_BEND_MAX, PITCH_BEND_MIN, ReferenceSlot, ReferenceSource, ReferenceTempo,
+    ReferenceTransposition, TimeSignature, calculate_reference_density_hint,
+    estimate_ticks_per_beat, syncopation_level_for_off_beat_ratio,
 };
 pub use midi_path::has_supported_midi_extension;
+pub use mode_params::{GenerationParam, ModeParamSpec};
+pub use note_repair::{CandidateProcessing, CandidateRepairReport};
+pub use privacy_filter::{PrivacyFilterMode, PrivacyFinding, PrivacyFindingKind};
+pub use prompt_lint::{PromptLint, PromptLintFix};
+pub use tick_resolution::TickResolution;
+pub use validation_policy::{
+    PolicyViolations, ValidationPolicy, ValidationStrictness, ValidationWarning,
+};
+pub use velocity_profile::{VelocityOnset, VelocityProfile};
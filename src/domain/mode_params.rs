@@ -0,0 +1,128 @@
+use super::GenerationMode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenerationParam {
+    Key,
+    Scale,
+    Density,
+    Complexity,
+    Syncopation,
+}
+
+/// Which [`GenerationParams`](super::GenerationParams) fields a mode actually uses. BPM and
+/// sampling settings apply to every mode and are always validated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModeParamSpec {
+    pub key: bool,
+    pub scale: bool,
+    pub density: bool,
+    pub complexity: bool,
+    pub syncopation: bool,
+}
+
+impl ModeParamSpec {
+    pub(super) const ALL: Self = Self {
+        key: true,
+        scale: true,
+        density: true,
+        complexity: true,
+        syncopation: true,
+    };
+
+    pub const fn for_mode(mode: GenerationMode) -> Self {
+        match mode {
+            // Drum kits are unpitched.
+            GenerationMode::DrumPattern => Self {
+                key: false,
+                scale: false,
+                ..Self::ALL
+            },
+            // Harmony voices follow the melody's rhythm rather than choosing their own.
+            GenerationMode::Harmony => Self {
+                density: false,
+                syncopation: false,
+                ..Self::ALL
+            },
+            GenerationMode::Melody
+            | GenerationMode::ChordProgression
+            | GenerationMode::Bassline
+            | GenerationMode::CounterMelody
+            | GenerationMode::Continuation => Self::ALL,
+        }
+    }
+
+    pub const fn applies(&self, param: GenerationParam) -> bool {
+        match param {
+            GenerationParam::Key => self.key,
+            GenerationParam::Scale => self.scale,
+            GenerationParam::Density => self.density,
+            GenerationParam::Complexity => self.complexity,
+            GenerationParam::Syncopation => self.syncopation,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GenerationParam, ModeParamSpec};
+    use crate::domain::{GenerationMode, GenerationParams, TickResolution};
+
+    fn params() -> GenerationParams {
+        GenerationParams {
+            bpm: 120,
+            key: String::new(),
+            scale: String::new(),
+            density: 3,
+            complexity: 3,
+            syncopation: 0,
+            tick_resolution: TickResolution::DEFAULT,
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
+        }
+    }
+
+    #[test]
+    fn drum_pattern_skips_key_and_scale_only() {
+        let drums = ModeParamSpec::for_mode(GenerationMode::DrumPattern);
+        assert!(!drums.applies(GenerationParam::Key));
+        assert!(!drums.applies(GenerationParam::Scale));
+        assert!(drums.applies(GenerationParam::Syncopation));
+
+        let mut drum_params = params();
+        assert!(
+            drum_params
+                .validate_for_mode(GenerationMode::DrumPattern)
+                .is_err()
+        );
+        drum_params.syncopation = 2;
+        assert!(
+            drum_params
+                .validate_for_mode(GenerationMode::DrumPattern)
+                .is_ok()
+        );
+        assert!(
+            drum_params
+                .validate_for_mode(GenerationMode::Melody)
+                .is_err()
+        );
+        assert!(drum_params.validate().is_err());
+
+        let mut harmony_params = drum_params.clone();
+        harmony_params.key = "C".to_string();
+        harmony_params.scale = "major".to_string();
+        harmony_params.density = 0;
+        assert!(
+            harmony_params
+                .validate_for_mode(GenerationMode::Harmony)
+                .is_ok()
+        );
+
+        drum_params.bpm = 10;
+        assert!(
+            drum_params
+                .validate_for_mode(GenerationMode::DrumPattern)
+                .is_err()
+        );
+    }
+}
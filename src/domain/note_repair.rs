@@ -0,0 +1,285 @@
+use std::cmp::Reverse;
+
+use serde::{Deserialize, Serialize};
+
+use super::{GeneratedNote, GenerationCandidate, GenerationResult};
+
+// Zero-length notes become a sixteenth note, or shorter if the next same-pitch note is closer.
+const ZERO_LENGTH_REPAIR_DIVISOR: u32 = 4;
+
+/// What the repair pass changed in one candidate.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct CandidateRepairReport {
+    pub candidate_id: String,
+    #[serde(default)]
+    pub duplicates_removed: u32,
+    #[serde(default)]
+    pub overlaps_trimmed: u32,
+    #[serde(default)]
+    pub zero_length_extended: u32,
+}
+
+impl CandidateRepairReport {
+    pub fn is_clean(&self) -> bool {
+        self.duplicates_removed == 0 && self.overlaps_trimmed == 0 && self.zero_length_extended == 0
+    }
+
+    /// e.g. "1 duplicate removed, 2 overlaps trimmed".
+    pub fn summary(&self) -> String {
+        [
+            (
+                self.duplicates_removed,
+                "duplicate removed",
+                "duplicates removed",
+            ),
+            (self.overlaps_trimmed, "overlap trimmed", "overlaps trimmed"),
+            (
+                self.zero_length_extended,
+                "zero-length note extended",
+                "zero-length notes extended",
+            ),
+        ]
+        .into_iter()
+        .filter(|(count, _, _)| *count > 0)
+        .map(|(count, singular, plural)| {
+            format!("{count} {}", if count == 1 { singular } else { plural })
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+}
+
+/// Both versions of a candidate's notes once post-processing has changed them. `notes` on the
+/// candidate holds the active version and `other_notes` the one set aside, so edits made to
+/// either version survive switching back and forth.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CandidateProcessing {
+    /// Whether `notes` holds the model's raw output instead of the processed notes.
+    #[serde(default)]
+    pub bypassed: bool,
+    pub other_notes: Vec<GeneratedNote>,
+}
+
+impl GenerationCandidate {
+    /// Whether post-processing changed the notes, so there is a raw version to switch to.
+    pub fn has_raw_notes(&self) -> bool {
+        self.processing.is_some()
+    }
+
+    pub fn is_processing_bypassed(&self) -> bool {
+        self.processing
+            .as_ref()
+            .is_some_and(|processing| processing.bypassed)
+    }
+
+    /// The model's output before post-processing.
+    pub fn raw_notes(&self) -> &[GeneratedNote] {
+        match &self.processing {
+            Some(processing) if !processing.bypassed => &processing.other_notes,
+            _ => &self.notes,
+        }
+    }
+
+    pub fn processed_notes(&self) -> &[GeneratedNote] {
+        match &self.processing {
+            Some(processing) if processing.bypassed => &processing.other_notes,
+            _ => &self.notes,
+        }
+    }
+
+    /// Puts the raw (`true`) or processed (`false`) notes in `notes`. Returns whether anything
+    /// changed; candidates post-processing left alone have only one version.
+    pub fn set_processing_bypassed(&mut self, bypassed: bool) -> bool {
+        let Some(processing) = self.processing.as_mut() else {
+            return false;
+        };
+        if processing.bypassed == bypassed {
+            return false;
+        }
+        std::mem::swap(&mut self.notes, &mut processing.other_notes);
+        processing.bypassed = bypassed;
+        true
+    }
+
+    /// Fixes common model artifacts among notes of the same pitch and channel: exact duplicates
+    /// keep only the longest, overlapping notes are trimmed to legato, and zero-length notes get
+    /// a short playable length.
+    pub fn repair_notes(&mut self) -> CandidateRepairReport {
+        let raw_notes = self.notes.clone();
+        let mut report = CandidateRepairReport {
+            candidate_id: self.id.clone(),
+            ..CandidateRepairReport::default()
+        };
+        let ticks_per_beat = u32::from(self.tick_resolution.ticks_per_beat());
+        let zero_length_fill = (ticks_per_beat / ZERO_LENGTH_REPAIR_DIVISOR).max(1);
+
+        let mut order = (0..self.notes.len()).collect::<Vec<_>>();
+        order.sort_by_key(|index| {
+            let note = &self.notes[*index];
+            (
+                note.channel,
+                note.pitch,
+                note.start_tick,
+                Reverse(note.duration_tick),
+            )
+        });
+
+        let mut removed = vec![false; self.notes.len()];
+        let mut previous: Option<usize> = None;
+        for index in order {
+            let note = &self.notes[index];
+            let Some(kept) = previous.filter(|kept| {
+                let kept = &self.notes[*kept];
+                kept.channel == note.channel && kept.pitch == note.pitch
+            }) else {
+                previous = Some(index);
+                continue;
+            };
+            if self.notes[kept].start_tick == note.start_tick {
+                removed[index] = true;
+                report.duplicates_removed += 1;
+                continue;
+            }
+
+            let gap = note.start_tick - self.notes[kept].start_tick;
+            let kept_note = &mut self.notes[kept];
+            if kept_note.duration_tick == 0 {
+                kept_note.duration_tick = zero_length_fill.min(gap);
+                report.zero_length_extended += 1;
+            } else if kept_note.duration_tick > gap {
+                kept_note.duration_tick = gap;
+                report.overlaps_trimmed += 1;
+            }
+            previous = Some(index);
+        }
+        // The last note of each pitch has no successor to check against.
+        for (note, removed) in self.notes.iter_mut().zip(&removed) {
+            if !removed && note.duration_tick == 0 {
+                note.duration_tick = zero_length_fill;
+                report.zero_length_extended += 1;
+            }
+        }
+
+        let mut removed = removed.into_iter();
+        self.notes.retain(|_| !removed.next().unwrap_or(false));
+        // A repeat pass over processed notes keeps the original raw version.
+        if !report.is_clean() && self.processing.is_none() {
+            self.processing = Some(CandidateProcessing {
+                bypassed: false,
+                other_notes: raw_notes,
+            });
+        }
+        report
+    }
+}
+
+impl GenerationResult {
+    /// Repairs every candidate and records a report for each one that changed.
+    pub fn repair_candidates(&mut self) {
+        self.metadata.repairs = self
+            .candidates
+            .iter_mut()
+            .map(GenerationCandidate::repair_notes)
+            .filter(|report| !report.is_clean())
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::{GeneratedNote, GenerationCandidate, TickResolution};
+
+    fn note(pitch: u8, start_tick: u32, duration_tick: u32) -> GeneratedNote {
+        GeneratedNote {
+            pitch,
+            start_tick,
+            duration_tick,
+            velocity: 96,
+            channel: 1,
+        }
+    }
+
+    #[test]
+    fn repair_removes_duplicates_trims_overlaps_and_extends_zero_length_notes() {
+        let mut candidate = GenerationCandidate {
+            id: "cand-1".to_string(),
+            bars: 1,
+            notes: vec![
+                note(60, 0, 960),
+                note(60, 480, 480),
+                note(60, 480, 240),
+                note(64, 0, 0),
+                note(64, 60, 480),
+                note(67, 1440, 0),
+                note(72, 0, 1920),
+            ],
+            tick_resolution: TickResolution::DEFAULT,
+            score_hint: None,
+            title: None,
+            control_events: Vec::new(),
+            processing: None,
+        };
+
+        let report = candidate.repair_notes();
+
+        assert_eq!(report.duplicates_removed, 1);
+        assert_eq!(report.overlaps_trimmed, 1);
+        assert_eq!(report.zero_length_extended, 2);
+        assert_eq!(
+            report.summary(),
+            "1 duplicate removed, 1 overlap trimmed, 2 zero-length notes extended"
+        );
+        assert_eq!(
+            candidate.notes,
+            vec![
+                note(60, 0, 480),
+                note(60, 480, 480),
+                note(64, 0, 60),
+                note(64, 60, 480),
+                note(67, 1440, 120),
+                note(72, 0, 1920),
+            ]
+        );
+        assert!(candidate.validate().is_ok());
+        assert!(candidate.repair_notes().is_clean());
+    }
+
+    #[test]
+    fn repaired_candidates_keep_the_raw_notes_to_switch_back_to() {
+        let raw = vec![note(60, 0, 960), note(60, 480, 480)];
+        let mut candidate = GenerationCandidate {
+            id: "cand-1".to_string(),
+            bars: 1,
+            notes: raw.clone(),
+            tick_resolution: TickResolution::DEFAULT,
+            score_hint: None,
+            title: None,
+            control_events: Vec::new(),
+            processing: None,
+        };
+
+        candidate.repair_notes();
+        let processed = vec![note(60, 0, 480), note(60, 480, 480)];
+        assert_eq!(candidate.raw_notes(), raw.as_slice());
+        assert_eq!(candidate.processed_notes(), processed.as_slice());
+
+        assert!(candidate.set_processing_bypassed(true));
+        assert!(!candidate.set_processing_bypassed(true));
+        assert!(candidate.is_processing_bypassed());
+        assert_eq!(candidate.notes, raw);
+        candidate.notes[0].velocity = 40;
+
+        assert!(candidate.set_processing_bypassed(false));
+        assert_eq!(candidate.notes, processed);
+        assert_eq!(candidate.raw_notes()[0].velocity, 40);
+
+        let mut clean = GenerationCandidate {
+            processing: None,
+            notes: processed.clone(),
+            ..candidate
+        };
+        clean.repair_notes();
+        assert!(!clean.has_raw_notes());
+        assert!(!clean.set_processing_bypassed(true));
+    }
+}
@@ -0,0 +1,282 @@
+use super::{GenerationRequest, LlmError};
+
+const EMAIL_PLACEHOLDER: &str = "[email]";
+const PATH_PLACEHOLDER: &str = "[path]";
+
+/// What to do with identifying text before a request leaves for a cloud provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrivacyFilterMode {
+    /// Refuse to send a request that contains identifying text.
+    Flag,
+    /// Replace identifying text and send the rest.
+    Strip,
+}
+
+impl PrivacyFilterMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "flag" => Some(Self::Flag),
+            "strip" => Some(Self::Strip),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrivacyFindingKind {
+    Email,
+    FilePath,
+}
+
+impl PrivacyFindingKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Email => "email address",
+            Self::FilePath => "file path",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrivacyFinding {
+    /// Request field the text was found in, e.g. `prompt` or `references[0].file.path`.
+    pub field: String,
+    pub kind: PrivacyFindingKind,
+}
+
+impl GenerationRequest {
+    /// Identifying text in the prompt and in reference file locations.
+    pub fn privacy_findings(&self) -> Vec<PrivacyFinding> {
+        let mut findings = Vec::new();
+        for (kind, _) in text_findings(&self.prompt) {
+            let finding = PrivacyFinding {
+                field: "prompt".to_string(),
+                kind,
+            };
+            if !findings.contains(&finding) {
+                findings.push(finding);
+            }
+        }
+
+        for (index, reference) in self.references.iter().enumerate() {
+            if reference
+                .file
+                .as_ref()
+                .is_some_and(|file| file.path.contains(['/', '\\']))
+            {
+                findings.push(PrivacyFinding {
+                    field: format!("references[{index}].file.path"),
+                    kind: PrivacyFindingKind::FilePath,
+                });
+            }
+        }
+        findings
+    }
+
+    /// Applies `mode`: `Flag` rejects a request with findings, `Strip` replaces emails and paths
+    /// in the prompt with placeholders and reduces reference paths to their file names.
+    pub fn apply_privacy_filter(
+        &mut self,
+        mode: PrivacyFilterMode,
+    ) -> Result<Vec<PrivacyFinding>, LlmError> {
+        let findings = self.privacy_findings();
+        if findings.is_empty() {
+            return Ok(findings);
+        }
+
+        match mode {
+            PrivacyFilterMode::Flag => {
+                let listed = findings
+                    .iter()
+                    .map(|finding| format!("{} in {}", finding.kind.label(), finding.field))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                Err(LlmError::validation(format!(
+                    "safe mode blocked the request: {listed}"
+                )))
+            }
+            PrivacyFilterMode::Strip => {
+                for (kind, token) in text_findings(&self.prompt) {
+                    let placeholder = match kind {
+                        PrivacyFindingKind::Email => EMAIL_PLACEHOLDER,
+                        PrivacyFindingKind::FilePath => PATH_PLACEHOLDER,
+                    };
+                    self.prompt = self.prompt.replace(&token, placeholder);
+                }
+                for file in self
+                    .references
+                    .iter_mut()
+                    .filter_map(|reference| reference.file.as_mut())
+                {
+                    if let Some((_, name)) = file.path.rsplit_once(['/', '\\']) {
+                        file.path = name.to_string();
+                    }
+                }
+                Ok(findings)
+            }
+        }
+    }
+}
+
+impl GenerationRequest {
+    /// Renames every file reference to `reference-<n>.mid` so only its musical summary reaches
+    /// the provider, not the folder or file name it was loaded from. `GenerationService` calls it
+    /// on the copy it sends when the request sets `anonymize_references`.
+    pub fn anonymize_reference_files(&mut self) {
+        for (index, file) in self
+            .references
+            .iter_mut()
+            .filter_map(|reference| reference.file.as_mut())
+            .enumerate()
+        {
+            file.path = format!("reference-{}.mid", index + 1);
+        }
+    }
+}
+
+fn text_findings(text: &str) -> Vec<(PrivacyFindingKind, String)> {
+    text.split_whitespace()
+        .map(|word| word.trim_matches(|character: char| "\"'()[]{}<>,;!?".contains(character)))
+        .map(|word| word.strip_suffix('.').unwrap_or(word))
+        .filter_map(|word| {
+            let kind = if is_email(word) {
+                PrivacyFindingKind::Email
+            } else if is_file_path(word) {
+                PrivacyFindingKind::FilePath
+            } else {
+                return None;
+            };
+            Some((kind, word.to_string()))
+        })
+        .collect()
+}
+
+fn is_email(word: &str) -> bool {
+    let Some((local, domain)) = word.split_once('@') else {
+        return false;
+    };
+    !local.is_empty() && domain.contains('.') && !domain.starts_with('.') && !domain.ends_with('.')
+}
+
+/// Absolute or home-relative paths, and relative paths that name a file. Slash chords such as
+/// `C/E` and roman numerals such as `I/IV/V` are not paths.
+fn is_file_path(word: &str) -> bool {
+    let bytes = word.as_bytes();
+    let rooted = word.starts_with('/') && word.len() > 1
+        || word.starts_with("~/")
+        || word.starts_with("~\\")
+        || word.starts_with("\\\\")
+        || bytes.len() > 2
+            && bytes[0].is_ascii_alphabetic()
+            && bytes[1] == b':'
+            && matches!(bytes[2], b'\\' | b'/');
+    if rooted {
+        return true;
+    }
+    word.rsplit_once(['/', '\\']).is_some_and(|(_, name)| {
+        name.rsplit_once('.').is_some_and(|(stem, extension)| {
+            !stem.is_empty()
+                && !extension.is_empty()
+                && extension.chars().all(char::is_alphanumeric)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PrivacyFilterMode;
+    use crate::domain::{
+        FileReferenceInput, GENERATION_CONTRACT_VERSION, GenerationMode, GenerationParams,
+        GenerationRequest, LlmError, MidiReferenceSummary, ModelRef, ReferenceSlot,
+        ReferenceSource, TickResolution,
+    };
+
+    fn request(prompt: &str, reference_path: &str) -> GenerationRequest {
+        GenerationRequest {
+            request_id: "req-privacy".to_string(),
+            model: ModelRef {
+                provider: "anthropic".to_string(),
+                model: "claude-3-5-sonnet".to_string(),
+            },
+            mode: GenerationMode::Melody,
+            prompt: prompt.to_string(),
+            params: GenerationParams {
+                bpm: 120,
+                key: "C".to_string(),
+                scale: "major".to_string(),
+                density: 3,
+                complexity: 3,
+                syncopation: 3,
+                tick_resolution: TickResolution::DEFAULT,
+                temperature: None,
+                top_p: None,
+                max_tokens: None,
+            },
+            references: vec![MidiReferenceSummary {
+                slot: ReferenceSlot::Melody,
+                source: ReferenceSource::File,
+                file: Some(FileReferenceInput {
+                    path: reference_path.to_string(),
+                }),
+                bars: 4,
+                note_count: 8,
+                density_hint: 0.25,
+                min_pitch: 60,
+                max_pitch: 72,
+                events: Vec::new(),
+                transposition: None,
+                tempo: None,
+            }],
+            variation_count: 1,
+            locked_notes: Vec::new(),
+            contract_version: GENERATION_CONTRACT_VERSION,
+            daw_context: None,
+            constraints: None,
+            validation: None,
+            anonymize_references: false,
+        }
+    }
+
+    #[test]
+    fn privacy_filter_flags_or_strips_emails_and_paths() {
+        let prompt = "Hook like /Users/jo/Clients/Acme/demo.mid over C/E and I/IV/V, \
+                      send notes to jo@studio.example.";
+        let mut flagged = request(prompt, "/Users/jo/Clients/Acme/lead.mid");
+        let error = flagged
+            .apply_privacy_filter(PrivacyFilterMode::Flag)
+            .expect_err("flag mode should refuse identifying text");
+        let LlmError::Validation { message } = error else {
+            panic!("expected a validation error, got {error:?}");
+        };
+        assert_eq!(
+            message,
+            "safe mode blocked the request: file path in prompt, email address in prompt, \
+             file path in references[0].file.path"
+        );
+        assert_eq!(flagged.prompt, prompt);
+
+        let mut stripped = request(prompt, "/Users/jo/Clients/Acme/lead.mid");
+        let findings = stripped
+            .apply_privacy_filter(PrivacyFilterMode::Strip)
+            .expect("strip mode should send the cleaned request");
+        assert_eq!(findings.len(), 3);
+        assert_eq!(
+            stripped.prompt,
+            "Hook like [path] over C/E and I/IV/V, send notes to [email]."
+        );
+        assert_eq!(
+            stripped.references[0]
+                .file
+                .as_ref()
+                .map(|file| file.path.as_str()),
+            Some("lead.mid")
+        );
+        assert!(stripped.privacy_findings().is_empty());
+
+        let mut clean = request("Bright arpeggio over C/E", "lead.mid");
+        assert_eq!(
+            clean.apply_privacy_filter(PrivacyFilterMode::Flag),
+            Ok(Vec::new())
+        );
+    }
+}
@@ -0,0 +1,308 @@
+use super::{GenerationMode, GenerationRequest, ModeParamSpec};
+
+const BPM_MIN: u16 = 20;
+const BPM_MAX: u16 = 300;
+const PITCH_CLASS_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+// Prompt words mapped to the scale values the parameter controls use.
+const SCALE_WORDS: [(&str, &str); 9] = [
+    ("major", "major"),
+    ("ionian", "major"),
+    ("minor", "Minor (Aeolian)"),
+    ("aeolian", "Minor (Aeolian)"),
+    ("dorian", "Dorian"),
+    ("phrygian", "Phrygian"),
+    ("lydian", "Lydian"),
+    ("mixolydian", "Mixolydian"),
+    ("locrian", "Locrian"),
+];
+const DRUM_WORDS: [&str; 14] = [
+    "drum",
+    "drums",
+    "kick",
+    "kicks",
+    "snare",
+    "snares",
+    "hat",
+    "hats",
+    "hihat",
+    "hihats",
+    "cymbal",
+    "cymbals",
+    "toms",
+    "percussion",
+];
+
+/// A one-click change to the parameters that resolves a [`PromptLint`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PromptLintFix {
+    SetKey { key: String, scale: String },
+    SetBpm(u16),
+    SetMode(GenerationMode),
+}
+
+/// Something in the prompt text that disagrees with the parameters or the mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptLint {
+    pub message: String,
+    pub fix: Option<PromptLintFix>,
+}
+
+impl GenerationRequest {
+    /// Pre-flight checks of the prompt against the mode and musical parameters: a key or scale
+    /// named in the text that differs from the selected one, drum parts asked for outside
+    /// drum mode, and a tempo in the text that differs from the BPM or is out of range.
+    pub fn prompt_lints(&self) -> Vec<PromptLint> {
+        let words = prompt_words(&self.prompt);
+        let mut lints = Vec::new();
+
+        let spec = ModeParamSpec::for_mode(self.mode);
+        if spec.key
+            && spec.scale
+            && let Some((tonic, scale)) = find_key(&words)
+            && (Some(tonic) != pitch_class(&self.params.key)
+                || !scale.eq_ignore_ascii_case(self.params.scale.trim()))
+        {
+            let key = PITCH_CLASS_NAMES[usize::from(tonic)];
+            lints.push(PromptLint {
+                message: format!(
+                    "The prompt asks for {key} {scale}, but Key/Scale are set to {} {}.",
+                    self.params.key, self.params.scale
+                ),
+                fix: Some(PromptLintFix::SetKey {
+                    key: key.to_string(),
+                    scale: scale.to_string(),
+                }),
+            });
+        }
+
+        if self.mode != GenerationMode::DrumPattern
+            && let Some(word) = words
+                .iter()
+                .find(|word| DRUM_WORDS.contains(&word.lower.as_str()))
+        {
+            lints.push(PromptLint {
+                message: format!(
+                    "The prompt mentions \"{}\", but this mode generates pitched parts only.",
+                    word.text
+                ),
+                fix: Some(PromptLintFix::SetMode(GenerationMode::DrumPattern)),
+            });
+        }
+
+        if let Some(bpm) = find_bpm(&words) {
+            if !(u32::from(BPM_MIN)..=u32::from(BPM_MAX)).contains(&bpm) {
+                lints.push(PromptLint {
+                    message: format!(
+                        "The prompt asks for {bpm} BPM, outside the supported {BPM_MIN}-{BPM_MAX} range."
+                    ),
+                    fix: None,
+                });
+            } else if bpm != u32::from(self.params.bpm) {
+                lints.push(PromptLint {
+                    message: format!(
+                        "The prompt asks for {bpm} BPM, but BPM is set to {}.",
+                        self.params.bpm
+                    ),
+                    fix: u16::try_from(bpm).ok().map(PromptLintFix::SetBpm),
+                });
+            }
+        }
+
+        lints
+    }
+}
+
+struct PromptWord<'a> {
+    text: &'a str,
+    lower: String,
+}
+
+fn prompt_words(prompt: &str) -> Vec<PromptWord<'_>> {
+    prompt
+        .split(|ch: char| !(ch.is_alphanumeric() || matches!(ch, '#' | '♯' | '♭')))
+        .filter(|text| !text.is_empty())
+        .map(|text| PromptWord {
+            text,
+            lower: text.to_lowercase(),
+        })
+        .collect()
+}
+
+/// The first "<note> [sharp|flat] <scale>" phrase. The note letter must be upper case so the
+/// article in "a minor change" is not read as A minor.
+fn find_key(words: &[PromptWord<'_>]) -> Option<(u8, &'static str)> {
+    words.iter().enumerate().find_map(|(index, word)| {
+        if !word.text.starts_with(|ch: char| ch.is_ascii_uppercase()) {
+            return None;
+        }
+        let mut tonic = pitch_class(word.text)?;
+        let mut next = words.get(index + 1)?;
+        match next.lower.as_str() {
+            "sharp" => tonic = (tonic + 1) % 12,
+            "flat" => tonic = (tonic + 11) % 12,
+            _ => return scale_value(&next.lower).map(|scale| (tonic, scale)),
+        }
+        next = words.get(index + 2)?;
+        scale_value(&next.lower).map(|scale| (tonic, scale))
+    })
+}
+
+pub(super) fn pitch_class(name: &str) -> Option<u8> {
+    let mut chars = name.trim().chars();
+    let base = match chars.next()?.to_ascii_uppercase() {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    match chars.as_str() {
+        "" => Some(base),
+        "#" | "♯" => Some((base + 1) % 12),
+        "b" | "♭" => Some((base + 11) % 12),
+        _ => None,
+    }
+}
+
+fn scale_value(word: &str) -> Option<&'static str> {
+    SCALE_WORDS
+        .iter()
+        .find(|(name, _)| *name == word)
+        .map(|(_, value)| *value)
+}
+
+/// A tempo written as "140 bpm" or "140bpm".
+fn find_bpm(words: &[PromptWord<'_>]) -> Option<u32> {
+    words.iter().enumerate().find_map(|(index, word)| {
+        if let Some(number) = word.lower.strip_suffix("bpm")
+            && !number.is_empty()
+        {
+            return number.parse().ok();
+        }
+        let next = words.get(index + 1)?;
+        if next.lower == "bpm" {
+            word.lower.parse().ok()
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PromptLintFix;
+    use crate::domain::{
+        GENERATION_CONTRACT_VERSION, GenerationMode, GenerationParams, GenerationRequest, ModelRef,
+        TickResolution,
+    };
+
+    fn request(mode: GenerationMode, prompt: &str) -> GenerationRequest {
+        GenerationRequest {
+            request_id: "req-1".to_string(),
+            model: ModelRef {
+                provider: "anthropic".to_string(),
+                model: "claude-3-5-sonnet".to_string(),
+            },
+            mode,
+            prompt: prompt.to_string(),
+            params: GenerationParams {
+                bpm: 120,
+                key: "C".to_string(),
+                scale: "major".to_string(),
+                density: 3,
+                complexity: 3,
+                syncopation: 3,
+                tick_resolution: TickResolution::DEFAULT,
+                temperature: None,
+                top_p: None,
+                max_tokens: None,
+            },
+            references: Vec::new(),
+            variation_count: 1,
+            locked_notes: Vec::new(),
+            contract_version: GENERATION_CONTRACT_VERSION,
+            daw_context: None,
+            constraints: None,
+            validation: None,
+            anonymize_references: false,
+        }
+    }
+
+    fn fixes(request: &GenerationRequest) -> Vec<Option<PromptLintFix>> {
+        request
+            .prompt_lints()
+            .into_iter()
+            .map(|lint| lint.fix)
+            .collect()
+    }
+
+    #[test]
+    fn consistent_prompts_have_no_lints() {
+        let request = request(
+            GenerationMode::Melody,
+            "a bright hook in C major at 120 bpm with a minor lift",
+        );
+        assert!(request.prompt_lints().is_empty());
+    }
+
+    #[test]
+    fn key_named_in_the_prompt_must_match_the_params() {
+        assert_eq!(
+            fixes(&request(GenerationMode::Melody, "moody line in A minor")),
+            vec![Some(PromptLintFix::SetKey {
+                key: "A".to_string(),
+                scale: "Minor (Aeolian)".to_string(),
+            })]
+        );
+        assert_eq!(
+            fixes(&request(
+                GenerationMode::Bassline,
+                "walking bass, B flat dorian"
+            )),
+            vec![Some(PromptLintFix::SetKey {
+                key: "A#".to_string(),
+                scale: "Dorian".to_string(),
+            })]
+        );
+    }
+
+    #[test]
+    fn drums_outside_drum_mode_suggest_switching_modes() {
+        assert_eq!(
+            fixes(&request(
+                GenerationMode::Melody,
+                "catchy lead over a four-on-the-floor kick"
+            )),
+            vec![Some(PromptLintFix::SetMode(GenerationMode::DrumPattern))]
+        );
+        assert!(
+            request(
+                GenerationMode::DrumPattern,
+                "tight kick and snare in E minor"
+            )
+            .prompt_lints()
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn tempo_in_the_prompt_must_match_and_be_in_range() {
+        assert_eq!(
+            fixes(&request(GenerationMode::Melody, "driving arp at 140BPM")),
+            vec![Some(PromptLintFix::SetBpm(140))]
+        );
+        let lints = request(GenerationMode::Melody, "hyper line at 900 bpm").prompt_lints();
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].fix, None);
+        assert!(
+            lints[0]
+                .message
+                .contains("outside the supported 20-300 range")
+        );
+    }
+}
@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+
+use super::{GeneratedNote, LlmError};
+
+const MAX_TICKS_PER_BEAT: u16 = 0x7FFF;
+
+/// MIDI ticks per quarter-note beat (PPQ) used when note positions are turned into beats or
+/// written to files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "u16", into = "u16")]
+pub struct TickResolution(u16);
+
+impl TickResolution {
+    pub const DEFAULT: Self = Self(480);
+    /// Resolutions offered in settings and used to snap inferred values.
+    pub const COMMON: [Self; 5] = [Self(96), Self(120), Self(240), Self(480), Self(960)];
+
+    pub fn new(ticks_per_beat: u16) -> Result<Self, LlmError> {
+        if !(1..=MAX_TICKS_PER_BEAT).contains(&ticks_per_beat) {
+            return Err(LlmError::validation(format!(
+                "ticks per beat must be in 1..={MAX_TICKS_PER_BEAT} (got {ticks_per_beat})"
+            )));
+        }
+        Ok(Self(ticks_per_beat))
+    }
+
+    /// Nearest common resolution to an inferred, possibly fractional, ticks-per-beat value.
+    pub fn nearest_common(ticks_per_beat: f32) -> Self {
+        Self::COMMON
+            .into_iter()
+            .min_by(|left, right| {
+                (f32::from(left.0) - ticks_per_beat)
+                    .abs()
+                    .total_cmp(&(f32::from(right.0) - ticks_per_beat).abs())
+            })
+            .unwrap_or(Self::DEFAULT)
+    }
+
+    pub fn ticks_per_beat(self) -> u16 {
+        self.0
+    }
+
+    pub fn ticks_to_beats(self, ticks: u32) -> f64 {
+        f64::from(ticks) / f64::from(self.0)
+    }
+
+    pub fn beats_to_ticks(self, beats: f64) -> u32 {
+        (beats.max(0.0) * f64::from(self.0)).round() as u32
+    }
+
+    /// Converts a position or length expressed at `from` into this resolution.
+    pub fn convert_ticks(self, ticks: u32, from: Self) -> u32 {
+        if from == self {
+            return ticks;
+        }
+        self.beats_to_ticks(from.ticks_to_beats(ticks))
+    }
+
+    /// Rescales note positions from `from`, keeping every sounding note at least one tick long.
+    pub fn convert_note(self, note: &GeneratedNote, from: Self) -> GeneratedNote {
+        GeneratedNote {
+            start_tick: self.convert_ticks(note.start_tick, from),
+            duration_tick: self.convert_ticks(note.duration_tick, from).max(1),
+            ..note.clone()
+        }
+    }
+}
+
+impl Default for TickResolution {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl TryFrom<u16> for TickResolution {
+    type Error = LlmError;
+
+    fn try_from(ticks_per_beat: u16) -> Result<Self, Self::Error> {
+        Self::new(ticks_per_beat)
+    }
+}
+
+impl From<TickResolution> for u16 {
+    fn from(resolution: TickResolution) -> Self {
+        resolution.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TickResolution;
+    use crate::domain::GeneratedNote;
+
+    #[test]
+    fn ticks_convert_between_resolutions_through_beats() {
+        let source = TickResolution::new(96).expect("96 PPQ should be valid");
+        let note = GeneratedNote {
+            pitch: 60,
+            start_tick: 144,
+            duration_tick: 0,
+            velocity: 100,
+            channel: 1,
+        };
+
+        let converted = TickResolution::DEFAULT.convert_note(&note, source);
+
+        assert_eq!(source.ticks_to_beats(144), 1.5);
+        assert_eq!(converted.start_tick, 720);
+        assert_eq!(converted.duration_tick, 1);
+        assert_eq!(TickResolution::DEFAULT.convert_ticks(720, source), 720);
+    }
+
+    #[test]
+    fn out_of_range_resolutions_are_rejected_and_inferred_values_snap() {
+        assert!(TickResolution::new(0).is_err());
+        assert!(serde_json::from_str::<TickResolution>("40000").is_err());
+        assert_eq!(
+            serde_json::from_str::<TickResolution>("960").expect("960 should parse"),
+            TickResolution::new(960).expect("960 PPQ should be valid")
+        );
+        assert_eq!(
+            TickResolution::nearest_common(470.0),
+            TickResolution::DEFAULT
+        );
+    }
+}
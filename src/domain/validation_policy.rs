@@ -0,0 +1,546 @@
+use serde::{Deserialize, Serialize};
+
+use super::prompt_lint::pitch_class;
+use super::{
+    BEATS_PER_BAR, GeneratedNote, GenerationCandidate, GenerationRequest, GenerationResult,
+    LlmError, ModeParamSpec, PitchRange,
+};
+
+/// How candidates that break the request's range, length, or scale are handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationStrictness {
+    /// Candidates that break a limit are dropped.
+    Strict,
+    /// Offending notes are corrected and the correction is reported as a warning.
+    Lenient,
+}
+
+impl ValidationStrictness {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Strict => "Strict",
+            Self::Lenient => "Lenient",
+        }
+    }
+
+    /// `strict` or `lenient`, as taken on the command line.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "strict" => Some(Self::Strict),
+            "lenient" => Some(Self::Lenient),
+            _ => None,
+        }
+    }
+}
+
+/// A candidate the policy dropped or corrected.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationWarning {
+    pub candidate_id: String,
+    /// Dropped under strict validation rather than corrected.
+    #[serde(default)]
+    pub rejected: bool,
+    pub message: String,
+}
+
+/// Notes of one candidate that break each limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PolicyViolations {
+    pub out_of_range: u32,
+    pub past_end: u32,
+    pub out_of_scale: u32,
+}
+
+impl PolicyViolations {
+    pub fn is_clean(&self) -> bool {
+        self.out_of_range == 0 && self.past_end == 0 && self.out_of_scale == 0
+    }
+}
+
+/// The limits a request's candidates are held to: the constraint range, the candidate's own
+/// length, and the request's scale. Locked notes are the user's and are never changed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationPolicy {
+    strictness: ValidationStrictness,
+    range: Option<PitchRange>,
+    // Pitch classes of the key and scale; `None` when the mode is unpitched or the scale is
+    // not one the policy knows.
+    scale: Option<[bool; 12]>,
+    scale_label: String,
+    // Unpitched modes map each key to an instrument, so folding by octaves would swap it.
+    pitched: bool,
+    locked_notes: Vec<GeneratedNote>,
+}
+
+impl ValidationPolicy {
+    /// `None` when the request did not ask for validation.
+    pub fn for_request(request: &GenerationRequest) -> Option<Self> {
+        let strictness = request.validation?;
+        let spec = ModeParamSpec::for_mode(request.mode);
+        let scale = spec
+            .scale
+            .then(|| scale_pitch_classes(&request.params.key, &request.params.scale))
+            .flatten();
+        Some(Self {
+            strictness,
+            range: request
+                .constraints
+                .as_ref()
+                .and_then(|constraints| constraints.range),
+            scale,
+            scale_label: format!(
+                "{} {}",
+                request.params.key.trim(),
+                request.params.scale.trim()
+            ),
+            pitched: spec.key,
+            locked_notes: request.locked_notes.clone(),
+        })
+    }
+
+    pub fn strictness(&self) -> ValidationStrictness {
+        self.strictness
+    }
+
+    /// Strict validation has to be able to hold every candidate to its limits, so it needs a
+    /// scale it knows and locked notes that already keep to the range and scale.
+    pub fn check_request(&self, request: &GenerationRequest) -> Result<(), LlmError> {
+        if self.strictness != ValidationStrictness::Strict {
+            return Ok(());
+        }
+        if ModeParamSpec::for_mode(request.mode).scale && self.scale.is_none() {
+            return Err(LlmError::validation(format!(
+                "strict validation needs a known scale (got '{}')",
+                request.params.scale.trim()
+            )));
+        }
+        if let Some(note) = self
+            .locked_notes
+            .iter()
+            .find(|note| self.out_of_range(note.pitch) || self.out_of_scale(note.pitch))
+        {
+            return Err(LlmError::validation(format!(
+                "locked note {} breaks the range or scale, so strict validation would reject \
+                 every candidate",
+                note.pitch
+            )));
+        }
+        Ok(())
+    }
+
+    /// Counts the notes that break each limit, leaving the candidate as it is.
+    pub fn inspect(&self, candidate: &GenerationCandidate) -> PolicyViolations {
+        let length_ticks = candidate_length_ticks(candidate);
+        let mut violations = PolicyViolations::default();
+        for note in candidate.notes.iter().filter(|note| !self.is_locked(note)) {
+            violations.out_of_range += u32::from(self.out_of_range(note.pitch));
+            violations.out_of_scale += u32::from(self.out_of_scale(note.pitch));
+            violations.past_end += u32::from(note_ends_after(note, length_ticks));
+        }
+        violations
+    }
+
+    /// Snaps notes into the scale and folds them into the range by octaves, drops notes that
+    /// start after the candidate ends, and trims ones that ring past it. Unpitched notes outside
+    /// the range are dropped instead of folded. Returns what it fixed.
+    pub fn correct(&self, candidate: &mut GenerationCandidate) -> PolicyViolations {
+        let violations = self.inspect(candidate);
+        if violations.is_clean() {
+            return violations;
+        }
+        let length_ticks = candidate_length_ticks(candidate);
+        let locked = candidate
+            .notes
+            .iter()
+            .map(|note| self.is_locked(note))
+            .collect::<Vec<_>>();
+        let mut locked = locked.into_iter();
+        candidate.notes.retain_mut(|note| {
+            if locked.next().unwrap_or(false) {
+                return true;
+            }
+            if note.start_tick >= length_ticks {
+                return false;
+            }
+            note.duration_tick = note.duration_tick.min(length_ticks - note.start_tick);
+            if !self.pitched {
+                return !self.out_of_range(note.pitch);
+            }
+            if self.out_of_scale(note.pitch) {
+                note.pitch = self.nearest_scale_pitch(note.pitch);
+            }
+            if let Some(range) = self.range {
+                note.pitch = fold_into_range(note.pitch, range);
+                // A range under an octave clamps, which can land off the scale again.
+                if self.out_of_scale(note.pitch) {
+                    note.pitch = self.nearest_scale_pitch(note.pitch);
+                }
+            }
+            true
+        });
+        violations
+    }
+
+    /// Drops (strict) or corrects (lenient) every candidate that breaks a limit and records a
+    /// warning for each. Strict validation fails when no candidate is left.
+    pub fn apply(&self, result: &mut GenerationResult) -> Result<(), LlmError> {
+        let mut warnings = Vec::new();
+        match self.strictness {
+            ValidationStrictness::Strict => {
+                result.candidates.retain(|candidate| {
+                    let violations = self.inspect(candidate);
+                    if violations.is_clean() {
+                        return true;
+                    }
+                    warnings.push(ValidationWarning {
+                        candidate_id: candidate.id.clone(),
+                        rejected: true,
+                        message: self.describe(&violations, candidate.bars),
+                    });
+                    false
+                });
+                if result.candidates.is_empty() {
+                    let reasons = warnings
+                        .iter()
+                        .map(|warning| format!("{}: {}", warning.candidate_id, warning.message))
+                        .collect::<Vec<_>>()
+                        .join("; ");
+                    return Err(LlmError::invalid_response(format!(
+                        "strict validation rejected every candidate ({reasons})"
+                    )));
+                }
+            }
+            ValidationStrictness::Lenient => {
+                for candidate in &mut result.candidates {
+                    let violations = self.correct(candidate);
+                    if !violations.is_clean() {
+                        warnings.push(ValidationWarning {
+                            candidate_id: candidate.id.clone(),
+                            rejected: false,
+                            message: self.describe(&violations, candidate.bars),
+                        });
+                    }
+                }
+            }
+        }
+        result.metadata.validation_warnings = warnings;
+        Ok(())
+    }
+
+    /// e.g. "2 notes outside C3..C5, 1 note past bar 4".
+    pub fn describe(&self, violations: &PolicyViolations, bars: u16) -> String {
+        let (range_verb, end_verb, scale_verb) = match self.strictness {
+            ValidationStrictness::Strict => ("outside", "past bar", "outside"),
+            ValidationStrictness::Lenient if !self.pitched => {
+                ("dropped outside", "trimmed at bar", "snapped to")
+            }
+            ValidationStrictness::Lenient => ("moved into", "trimmed at bar", "snapped to"),
+        };
+        let range_label = self.range.map(PitchRange::label).unwrap_or_default();
+        [
+            (violations.out_of_range, range_verb, range_label),
+            (violations.past_end, end_verb, bars.to_string()),
+            (
+                violations.out_of_scale,
+                scale_verb,
+                self.scale_label.clone(),
+            ),
+        ]
+        .into_iter()
+        .filter(|(count, _, _)| *count > 0)
+        .map(|(count, verb, limit)| {
+            format!(
+                "{count} {} {verb} {limit}",
+                if count == 1 { "note" } else { "notes" }
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+
+    fn is_locked(&self, note: &GeneratedNote) -> bool {
+        self.locked_notes
+            .iter()
+            .any(|locked| note.matches_locked_note(locked))
+    }
+
+    fn out_of_range(&self, pitch: u8) -> bool {
+        self.range
+            .is_some_and(|range| pitch < range.low || pitch > range.high)
+    }
+
+    fn out_of_scale(&self, pitch: u8) -> bool {
+        self.scale
+            .is_some_and(|scale| !scale[usize::from(pitch % 12)])
+    }
+
+    /// The closest pitch in the scale, a semitone down before a semitone up. A pitch already in
+    /// the range is only moved within it.
+    fn nearest_scale_pitch(&self, pitch: u8) -> u8 {
+        let keep_in_range = !self.out_of_range(pitch);
+        (1..=6)
+            .flat_map(|step| [i16::from(pitch) - step, i16::from(pitch) + step])
+            .filter_map(|candidate| u8::try_from(candidate).ok())
+            .find(|candidate| {
+                *candidate <= 127
+                    && !self.out_of_scale(*candidate)
+                    && !(keep_in_range && self.out_of_range(*candidate))
+            })
+            .unwrap_or(pitch)
+    }
+}
+
+fn candidate_length_ticks(candidate: &GenerationCandidate) -> u32 {
+    u32::from(candidate.bars)
+        * BEATS_PER_BAR
+        * u32::from(candidate.tick_resolution.ticks_per_beat())
+}
+
+fn note_ends_after(note: &GeneratedNote, length_ticks: u32) -> bool {
+    note.start_tick.saturating_add(note.duration_tick) > length_ticks
+}
+
+/// Moves `pitch` by octaves until it fits, clamping when the range is under an octave wide.
+fn fold_into_range(pitch: u8, range: PitchRange) -> u8 {
+    let mut folded = i16::from(pitch);
+    while folded < i16::from(range.low) {
+        folded += 12;
+    }
+    while folded > i16::from(range.high) {
+        folded -= 12;
+    }
+    u8::try_from(folded)
+        .unwrap_or(pitch)
+        .clamp(range.low, range.high)
+}
+
+fn scale_pitch_classes(key: &str, scale: &str) -> Option<[bool; 12]> {
+    let tonic = pitch_class(key)?;
+    let intervals: &[u8] = match scale.trim().to_ascii_lowercase().as_str() {
+        "major" | "ionian" => &[0, 2, 4, 5, 7, 9, 11],
+        "minor" | "aeolian" | "minor (aeolian)" | "natural minor" => &[0, 2, 3, 5, 7, 8, 10],
+        "harmonic minor" => &[0, 2, 3, 5, 7, 8, 11],
+        "melodic minor" => &[0, 2, 3, 5, 7, 9, 11],
+        "dorian" => &[0, 2, 3, 5, 7, 9, 10],
+        "phrygian" => &[0, 1, 3, 5, 7, 8, 10],
+        "lydian" => &[0, 2, 4, 6, 7, 9, 11],
+        "mixolydian" => &[0, 2, 4, 5, 7, 9, 10],
+        "locrian" => &[0, 1, 3, 5, 6, 8, 10],
+        _ => return None,
+    };
+    let mut pitch_classes = [false; 12];
+    for interval in intervals {
+        pitch_classes[usize::from((tonic + interval) % 12)] = true;
+    }
+    Some(pitch_classes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ValidationPolicy, ValidationStrictness};
+    use crate::domain::{
+        GENERATION_CONTRACT_VERSION, GeneratedNote, GenerationCandidate, GenerationConstraints,
+        GenerationMetadata, GenerationMode, GenerationParams, GenerationRequest, GenerationResult,
+        LlmError, ModelRef, TickResolution,
+    };
+
+    fn note(pitch: u8, start_tick: u32, duration_tick: u32) -> GeneratedNote {
+        GeneratedNote {
+            pitch,
+            start_tick,
+            duration_tick,
+            velocity: 96,
+            channel: 1,
+        }
+    }
+
+    fn request(strictness: ValidationStrictness) -> GenerationRequest {
+        GenerationRequest {
+            request_id: "req-1".to_string(),
+            model: ModelRef {
+                provider: "anthropic".to_string(),
+                model: "claude-3-5-sonnet".to_string(),
+            },
+            mode: GenerationMode::Melody,
+            prompt: "hook".to_string(),
+            params: GenerationParams {
+                bpm: 120,
+                key: "D".to_string(),
+                scale: "major".to_string(),
+                density: 3,
+                complexity: 3,
+                syncopation: 3,
+                tick_resolution: TickResolution::DEFAULT,
+                temperature: None,
+                top_p: None,
+                max_tokens: None,
+            },
+            references: Vec::new(),
+            variation_count: 2,
+            locked_notes: Vec::new(),
+            contract_version: GENERATION_CONTRACT_VERSION,
+            daw_context: None,
+            constraints: Some(
+                GenerationConstraints::parse("range: C4..C5").expect("constraints should parse"),
+            ),
+            validation: Some(strictness),
+            anonymize_references: false,
+        }
+    }
+
+    fn result(candidates: Vec<Vec<GeneratedNote>>) -> GenerationResult {
+        GenerationResult {
+            request_id: "req-1".to_string(),
+            model: ModelRef {
+                provider: "anthropic".to_string(),
+                model: "claude-3-5-sonnet".to_string(),
+            },
+            candidates: candidates
+                .into_iter()
+                .enumerate()
+                .map(|(index, notes)| GenerationCandidate {
+                    id: format!("cand-{}", index + 1),
+                    bars: 1,
+                    notes,
+                    tick_resolution: TickResolution::DEFAULT,
+                    score_hint: None,
+                    title: None,
+                    control_events: Vec::new(),
+                    processing: None,
+                })
+                .collect(),
+            metadata: GenerationMetadata::default(),
+            contract_version: GENERATION_CONTRACT_VERSION,
+        }
+    }
+
+    #[test]
+    fn lenient_validation_corrects_notes_and_warns() {
+        let policy = ValidationPolicy::for_request(&request(ValidationStrictness::Lenient))
+            .expect("validation was requested");
+        // F natural is outside D major, 50 is below C4, and the last note rings past bar 1.
+        let mut result = result(vec![vec![
+            note(62, 0, 480),
+            note(65, 480, 480),
+            note(50, 960, 480),
+            note(66, 1440, 960),
+            note(69, 1920, 480),
+        ]]);
+
+        policy
+            .apply(&mut result)
+            .expect("lenient validation keeps candidates");
+
+        let pitches = result.candidates[0]
+            .notes
+            .iter()
+            .map(|note| (note.pitch, note.duration_tick))
+            .collect::<Vec<_>>();
+        assert_eq!(pitches, vec![(62, 480), (64, 480), (62, 480), (66, 480)]);
+        assert_eq!(result.metadata.validation_warnings.len(), 1);
+        assert!(!result.metadata.validation_warnings[0].rejected);
+        assert_eq!(
+            result.metadata.validation_warnings[0].message,
+            "1 note moved into C4..C5, 2 notes trimmed at bar 1, 1 note snapped to D major"
+        );
+    }
+
+    #[test]
+    fn a_range_under_an_octave_keeps_folded_notes_in_the_scale() {
+        let mut request = request(ValidationStrictness::Lenient);
+        request.constraints =
+            Some(GenerationConstraints::parse("range: C4..F4").expect("constraints should parse"));
+        let policy = ValidationPolicy::for_request(&request).expect("validation was requested");
+        // B4 is in D major, but folding it down lands on B3 and the clamp then on C4.
+        let mut result = result(vec![vec![note(71, 0, 480)]]);
+
+        policy
+            .apply(&mut result)
+            .expect("lenient validation keeps candidates");
+
+        assert_eq!(result.candidates[0].notes[0].pitch, 61);
+    }
+
+    #[test]
+    fn unpitched_notes_outside_the_range_are_dropped_rather_than_folded() {
+        let mut request = request(ValidationStrictness::Lenient);
+        request.mode = GenerationMode::DrumPattern;
+        request.constraints =
+            Some(GenerationConstraints::parse("range: C2..C4").expect("constraints should parse"));
+        let policy = ValidationPolicy::for_request(&request).expect("validation was requested");
+        // A low bongo (61) folded down an octave would play as a crash cymbal (49).
+        let mut result = result(vec![vec![
+            note(36, 0, 240),
+            note(42, 240, 240),
+            note(61, 480, 240),
+        ]]);
+
+        policy
+            .apply(&mut result)
+            .expect("lenient validation keeps candidates");
+
+        let pitches = result.candidates[0]
+            .notes
+            .iter()
+            .map(|note| note.pitch)
+            .collect::<Vec<_>>();
+        assert_eq!(pitches, vec![36, 42]);
+        assert_eq!(
+            result.metadata.validation_warnings[0].message,
+            "1 note dropped outside C2..C4"
+        );
+    }
+
+    #[test]
+    fn strict_validation_drops_offending_candidates_and_fails_when_none_are_left() {
+        let policy = ValidationPolicy::for_request(&request(ValidationStrictness::Strict))
+            .expect("validation was requested");
+        let mut mixed = result(vec![
+            vec![note(62, 0, 480), note(66, 480, 480)],
+            vec![note(62, 0, 480), note(65, 480, 480)],
+        ]);
+
+        policy.apply(&mut mixed).expect("one candidate passes");
+
+        assert_eq!(mixed.candidates.len(), 1);
+        assert_eq!(mixed.candidates[0].id, "cand-1");
+        assert!(mixed.metadata.validation_warnings[0].rejected);
+        assert_eq!(
+            mixed.metadata.validation_warnings[0].message,
+            "1 note outside D major"
+        );
+
+        let mut failing = result(vec![vec![note(48, 0, 480)]]);
+        assert!(matches!(
+            policy.apply(&mut failing),
+            Err(LlmError::InvalidResponse { .. })
+        ));
+    }
+
+    #[test]
+    fn strict_requests_need_a_known_scale_and_locked_notes_within_the_limits() {
+        let mut request = request(ValidationStrictness::Strict);
+        request.validate().expect("request should be valid");
+
+        request.locked_notes = vec![note(65, 0, 480)];
+        assert!(matches!(
+            request.validate(),
+            Err(LlmError::Validation { .. })
+        ));
+
+        request.locked_notes.clear();
+        request.params.scale = "whole tone".to_string();
+        assert!(matches!(
+            request.validate(),
+            Err(LlmError::Validation { .. })
+        ));
+
+        request.validation = Some(ValidationStrictness::Lenient);
+        request
+            .validate()
+            .expect("lenient validation skips unknown scales");
+        request.validation = None;
+        assert_eq!(ValidationPolicy::for_request(&request), None);
+    }
+}
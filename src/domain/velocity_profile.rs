@@ -0,0 +1,175 @@
+use super::{BEATS_PER_BAR, GeneratedNote, TickResolution};
+
+const DOWNBEAT_ACCENT: i16 = 18;
+const BEAT_ACCENT: i16 = 6;
+const OFF_BEAT_CUT: i16 = -8;
+const ON_BEAT_TOLERANCE_DIVISOR: u32 = 16;
+const CRESCENDO_START_SCALE: f64 = 0.6;
+const RANDOM_WALK_STEP: i16 = 6;
+const RANDOM_WALK_MAX_DRIFT: i16 = 24;
+
+/// Reusable velocity shapes applied after generation or capture; pitches and timing are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VelocityProfile {
+    AccentOnDownbeat,
+    Crescendo,
+    RandomWalk,
+}
+
+/// Onset position and velocity of one note, for sources that are not `GeneratedNote`s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VelocityOnset {
+    pub start_tick: u32,
+    pub velocity: u8,
+}
+
+impl VelocityProfile {
+    pub const ALL: [Self; 3] = [Self::AccentOnDownbeat, Self::Crescendo, Self::RandomWalk];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::AccentOnDownbeat => "Accent Downbeats",
+            Self::Crescendo => "Crescendo",
+            Self::RandomWalk => "Random Walk",
+        }
+    }
+
+    pub fn apply_to_notes(
+        self,
+        notes: &mut [GeneratedNote],
+        resolution: TickResolution,
+        seed: u64,
+    ) {
+        let mut onsets = notes
+            .iter()
+            .map(|note| VelocityOnset {
+                start_tick: note.start_tick,
+                velocity: note.velocity,
+            })
+            .collect::<Vec<_>>();
+        self.apply(&mut onsets, resolution, seed);
+        for (note, onset) in notes.iter_mut().zip(onsets) {
+            note.velocity = onset.velocity;
+        }
+    }
+
+    /// Reshapes velocities in place, keeping them in 1..=127. Onsets may be in any order; notes
+    /// that start together (chords) move together. `seed` only affects [`Self::RandomWalk`].
+    pub fn apply(self, onsets: &mut [VelocityOnset], resolution: TickResolution, seed: u64) {
+        match self {
+            Self::AccentOnDownbeat => {
+                let ticks_per_beat = u32::from(resolution.ticks_per_beat());
+                let ticks_per_bar = ticks_per_beat * BEATS_PER_BAR;
+                let tolerance = ticks_per_beat / ON_BEAT_TOLERANCE_DIVISOR;
+                let near = |tick: u32, period: u32| {
+                    let offset = tick % period;
+                    offset <= tolerance || period - offset <= tolerance
+                };
+                for onset in onsets.iter_mut() {
+                    let accent = if near(onset.start_tick, ticks_per_bar) {
+                        DOWNBEAT_ACCENT
+                    } else if near(onset.start_tick, ticks_per_beat) {
+                        BEAT_ACCENT
+                    } else {
+                        OFF_BEAT_CUT
+                    };
+                    onset.velocity = offset_velocity(onset.velocity, accent);
+                }
+            }
+            Self::Crescendo => {
+                let (Some(first), Some(last)) = (
+                    onsets.iter().map(|onset| onset.start_tick).min(),
+                    onsets.iter().map(|onset| onset.start_tick).max(),
+                ) else {
+                    return;
+                };
+                if first == last {
+                    return;
+                }
+                let span = f64::from(last - first);
+                for onset in onsets.iter_mut() {
+                    let progress = f64::from(onset.start_tick - first) / span;
+                    let scale = CRESCENDO_START_SCALE + (1.0 - CRESCENDO_START_SCALE) * progress;
+                    onset.velocity = (f64::from(onset.velocity) * scale)
+                        .round()
+                        .clamp(1.0, 127.0) as u8;
+                }
+            }
+            Self::RandomWalk => {
+                let mut order = (0..onsets.len()).collect::<Vec<_>>();
+                order.sort_by_key(|index| onsets[*index].start_tick);
+                let mut state = seed;
+                let mut drift = 0_i16;
+                let mut previous_tick = None;
+                for index in order {
+                    let onset = &mut onsets[index];
+                    if previous_tick != Some(onset.start_tick) {
+                        let step = (splitmix64(&mut state) % (RANDOM_WALK_STEP as u64 * 2 + 1))
+                            as i16
+                            - RANDOM_WALK_STEP;
+                        drift = (drift + step).clamp(-RANDOM_WALK_MAX_DRIFT, RANDOM_WALK_MAX_DRIFT);
+                        previous_tick = Some(onset.start_tick);
+                    }
+                    onset.velocity = offset_velocity(onset.velocity, drift);
+                }
+            }
+        }
+    }
+}
+
+fn offset_velocity(velocity: u8, offset: i16) -> u8 {
+    (i16::from(velocity) + offset).clamp(1, 127) as u8
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut value = *state;
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    value ^ (value >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RANDOM_WALK_MAX_DRIFT, VelocityOnset, VelocityProfile};
+    use crate::domain::TickResolution;
+
+    fn onsets(ticks: &[u32]) -> Vec<VelocityOnset> {
+        ticks
+            .iter()
+            .map(|start_tick| VelocityOnset {
+                start_tick: *start_tick,
+                velocity: 80,
+            })
+            .collect()
+    }
+
+    fn velocities(onsets: &[VelocityOnset]) -> Vec<u8> {
+        onsets.iter().map(|onset| onset.velocity).collect()
+    }
+
+    #[test]
+    fn profiles_shape_velocities_by_position() {
+        let resolution = TickResolution::DEFAULT;
+
+        let mut accented = onsets(&[0, 240, 480, 1920]);
+        VelocityProfile::AccentOnDownbeat.apply(&mut accented, resolution, 0);
+        assert_eq!(velocities(&accented), vec![98, 72, 86, 98]);
+
+        let mut swelled = onsets(&[1920, 0, 960]);
+        VelocityProfile::Crescendo.apply(&mut swelled, resolution, 0);
+        assert_eq!(velocities(&swelled), vec![80, 48, 64]);
+
+        let mut walked = onsets(&[0, 0, 480, 960, 1440]);
+        VelocityProfile::RandomWalk.apply(&mut walked, resolution, 7);
+        assert_eq!(walked[0].velocity, walked[1].velocity);
+        assert!(
+            walked
+                .iter()
+                .all(|onset| (i16::from(onset.velocity) - 80).abs() <= RANDOM_WALK_MAX_DRIFT)
+        );
+        let mut again = onsets(&[0, 0, 480, 960, 1440]);
+        VelocityProfile::RandomWalk.apply(&mut again, resolution, 7);
+        assert_eq!(walked, again);
+    }
+}
@@ -1,3 +1,4 @@
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use reqwest::StatusCode;
@@ -8,16 +9,18 @@ use crate::domain::{
     GenerationMetadata, GenerationRequest, GenerationResult, GenerationUsage, LlmError,
 };
 
+use super::audit_log::{AuditExchange, AuditLog, DESCRIBE_AUDIT_REQUEST_ID, record_best_effort};
 use super::env::{read_env_var, read_timeout_from_env, resolve_timeout_with_global_fallback};
-use super::response_parsing::{extract_json_payload, truncate_message};
+use super::response_parsing::{extract_json_payload, parse_retry_after, truncate_message};
 use super::schema_validator::LlmResponseSchemaValidator;
-use super::{LlmProvider, PromptBuilder};
+use super::{BuiltPrompt, LlmProvider, PromptBuilder};
 
 const PROVIDER_ID: &str = "anthropic";
 const API_VERSION: &str = "2023-06-01";
 const DEFAULT_BASE_URL: &str = "https://api.anthropic.com";
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(8);
 const DEFAULT_MAX_TOKENS: u16 = 1024;
+const DESCRIBE_MAX_TOKENS: u16 = 256;
 const ENV_API_KEY: &str = "SONANT_ANTHROPIC_API_KEY";
 const ENV_API_KEY_FALLBACK: &str = "ANTHROPIC_API_KEY";
 const ENV_BASE_URL: &str = "SONANT_ANTHROPIC_BASE_URL";
@@ -29,6 +32,7 @@ pub struct AnthropicProvider {
     api_base_url: String,
     client: Client,
     schema_validator: LlmResponseSchemaValidator,
+    audit_log: Option<Arc<AuditLog>>,
 }
 
 impl AnthropicProvider {
@@ -81,9 +85,19 @@ impl AnthropicProvider {
             api_base_url,
             client,
             schema_validator,
+            audit_log: None,
         })
     }
 
+    pub fn with_audit_log(mut self, audit_log: Arc<AuditLog>) -> Self {
+        self.audit_log = Some(audit_log);
+        self
+    }
+
+    pub fn api_base_url(&self) -> &str {
+        &self.api_base_url
+    }
+
     fn endpoint_url(&self) -> String {
         format!("{}/v1/messages", self.api_base_url.trim_end_matches('/'))
     }
@@ -107,6 +121,27 @@ impl AnthropicProvider {
         })
     }
 
+    fn record_exchange(
+        &self,
+        model_id: &str,
+        request_id: &str,
+        payload: &AnthropicMessagesRequest,
+        status: StatusCode,
+        latency_ms: u64,
+        response_body: &str,
+    ) {
+        record_best_effort(self.audit_log.as_deref(), &self.api_key, || {
+            AuditExchange::new(
+                PROVIDER_ID,
+                model_id,
+                request_id,
+                self.endpoint_url(),
+                serde_json::to_value(payload).unwrap_or_default(),
+            )
+            .with_response(status.as_u16(), latency_ms, response_body)
+        });
+    }
+
     fn map_success_response(
         &self,
         request: &GenerationRequest,
@@ -119,6 +154,12 @@ impl AnthropicProvider {
                 LlmError::invalid_response(format!("Anthropic response decode failed: {err}"))
             })?;
 
+        if response.stop_reason.as_deref() == Some("refusal") {
+            return Err(LlmError::ContentFiltered {
+                message: "Anthropic declined to answer the request".to_string(),
+            });
+        }
+
         let joined_text = response
             .content
             .iter()
@@ -134,7 +175,9 @@ impl AnthropicProvider {
         let json_payload = extract_json_payload(&joined_text).ok_or_else(|| {
             LlmError::invalid_response("Anthropic text block did not include a JSON object")
         })?;
-        let mut result = self.schema_validator.validate_response_json(json_payload)?;
+        let mut result = self
+            .schema_validator
+            .validate_response_json_for_request(&json_payload, request)?;
 
         if result.request_id != request.request_id {
             return Err(LlmError::invalid_response(format!(
@@ -174,6 +217,10 @@ impl AnthropicProvider {
             provider_request_id,
             stop_reason,
             usage,
+            chords: std::mem::take(&mut result.metadata.chords),
+            repairs: std::mem::take(&mut result.metadata.repairs),
+            confidence: std::mem::take(&mut result.metadata.confidence),
+            validation_warnings: std::mem::take(&mut result.metadata.validation_warnings),
         };
 
         Ok(result)
@@ -210,16 +257,78 @@ impl LlmProvider for AnthropicProvider {
             .get("request-id")
             .and_then(|value| value.to_str().ok())
             .map(str::to_owned);
+        let retry_after = parse_retry_after(response.headers());
 
         let response_body = response.text().map_err(map_transport_error)?;
+        let elapsed_ms = started.elapsed().as_millis();
+        let latency_ms = u64::try_from(elapsed_ms).unwrap_or(u64::MAX);
+        self.record_exchange(
+            &request.model.model,
+            &request.request_id,
+            &payload,
+            status,
+            latency_ms,
+            &response_body,
+        );
         if !status.is_success() {
-            return Err(map_http_error(status, &response_body));
+            return Err(map_http_error(
+                status,
+                retry_after,
+                &response_body,
+                &request.model.model,
+            ));
         }
 
-        let elapsed_ms = started.elapsed().as_millis();
-        let latency_ms = u64::try_from(elapsed_ms).unwrap_or(u64::MAX);
         self.map_success_response(request, &response_body, latency_ms, header_request_id)
     }
+
+    fn describe(&self, model_id: &str, prompt: &BuiltPrompt) -> Result<String, LlmError> {
+        let payload = AnthropicMessagesRequest {
+            model: model_id.to_string(),
+            max_tokens: DESCRIBE_MAX_TOKENS,
+            temperature: None,
+            top_p: None,
+            system: prompt.system.clone(),
+            messages: vec![AnthropicMessage {
+                role: "user".to_string(),
+                content: prompt.user.clone(),
+            }],
+        };
+        let started = Instant::now();
+
+        let response = self
+            .client
+            .post(self.endpoint_url())
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", API_VERSION)
+            .header("content-type", "application/json")
+            .json(&payload)
+            .send()
+            .map_err(map_transport_error)?;
+
+        let status = response.status();
+        let retry_after = parse_retry_after(response.headers());
+        let response_body = response.text().map_err(map_transport_error)?;
+        let latency_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+        self.record_exchange(
+            model_id,
+            DESCRIBE_AUDIT_REQUEST_ID,
+            &payload,
+            status,
+            latency_ms,
+            &response_body,
+        );
+        if !status.is_success() {
+            return Err(map_http_error(
+                status,
+                retry_after,
+                &response_body,
+                model_id,
+            ));
+        }
+
+        map_describe_response(&response_body)
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -283,6 +392,32 @@ struct AnthropicUsage {
     cache_read_input_tokens: Option<u32>,
 }
 
+fn map_describe_response(response_body: &str) -> Result<String, LlmError> {
+    let response: AnthropicMessagesResponse =
+        serde_json::from_str(response_body).map_err(|err| {
+            LlmError::invalid_response(format!("Anthropic response decode failed: {err}"))
+        })?;
+    if response.stop_reason.as_deref() == Some("refusal") {
+        return Err(LlmError::ContentFiltered {
+            message: "Anthropic declined to answer the request".to_string(),
+        });
+    }
+
+    let text = response
+        .content
+        .iter()
+        .filter_map(AnthropicContentBlock::as_text)
+        .collect::<Vec<_>>()
+        .join("");
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(LlmError::invalid_response(
+            "Anthropic response did not include a text content block",
+        ));
+    }
+    Ok(text.to_string())
+}
+
 fn map_usage(usage: AnthropicUsage) -> Option<GenerationUsage> {
     let total_tokens = match (usage.input_tokens, usage.output_tokens) {
         (Some(input), Some(output)) => input.checked_add(output),
@@ -309,23 +444,42 @@ fn map_usage(usage: AnthropicUsage) -> Option<GenerationUsage> {
     }
 }
 
-fn map_http_error(status: StatusCode, body: &str) -> LlmError {
+fn map_http_error(
+    status: StatusCode,
+    retry_after: Option<Duration>,
+    body: &str,
+    model: &str,
+) -> LlmError {
     let parsed_error = serde_json::from_str::<AnthropicErrorEnvelope>(body).ok();
-    let error_type = parsed_error
+    let error_detail = parsed_error
         .as_ref()
-        .and_then(|envelope| envelope.error.as_ref())
-        .map(|detail| detail.error_type.as_str());
-
+        .and_then(|envelope| envelope.error.as_ref());
+    let error_type = error_detail.map(|detail| detail.error_type.as_str());
+    let error_message = error_detail
+        .map(|detail| detail.message.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    if matches!(error_type, Some("billing_error")) || error_message.contains("credit balance") {
+        return LlmError::QuotaExceeded;
+    }
     if matches!(
         error_type,
         Some("authentication_error" | "invalid_api_key_error")
     ) || status == StatusCode::UNAUTHORIZED
         || status == StatusCode::FORBIDDEN
     {
+        if error_message.contains("expired") {
+            return LlmError::AuthExpired;
+        }
         return LlmError::Auth;
     }
     if matches!(error_type, Some("rate_limit_error")) || status == StatusCode::TOO_MANY_REQUESTS {
-        return LlmError::RateLimited;
+        return LlmError::rate_limited(retry_after);
+    }
+    if matches!(error_type, Some("not_found_error")) && error_message.contains("model") {
+        return LlmError::ModelNotFound {
+            model: model.to_string(),
+        };
     }
     if matches!(error_type, Some("timeout_error"))
         || status == StatusCode::REQUEST_TIMEOUT
@@ -368,10 +522,11 @@ struct AnthropicErrorDetail {
 
 #[cfg(test)]
 mod tests {
-    use super::{AnthropicProvider, map_http_error};
+    use super::{AnthropicProvider, map_describe_response, map_http_error};
     use crate::domain::{
-        FileReferenceInput, GenerationMode, GenerationParams, GenerationRequest, LlmError,
-        MidiReferenceSummary, ModelRef, ReferenceSlot, ReferenceSource,
+        FileReferenceInput, GENERATION_CONTRACT_VERSION, GenerationMode, GenerationParams,
+        GenerationRequest, LlmError, MidiReferenceSummary, ModelRef, ReferenceSlot,
+        ReferenceSource, TickResolution,
     };
     use crate::infra::llm::PromptBuilder;
     use reqwest::StatusCode;
@@ -401,6 +556,8 @@ mod tests {
                 scale: "major".to_string(),
                 density: 3,
                 complexity: 2,
+                syncopation: 3,
+                tick_resolution: TickResolution::DEFAULT,
                 temperature: Some(0.5),
                 top_p: Some(0.9),
                 max_tokens: Some(512),
@@ -422,8 +579,16 @@ mod tests {
                     delta_tick: 0,
                     event: "NoteOn channel=0 key=60 vel=100".to_string(),
                 }],
+                transposition: None,
+                tempo: None,
             }],
             variation_count: 2,
+            locked_notes: Vec::new(),
+            contract_version: GENERATION_CONTRACT_VERSION,
+            daw_context: None,
+            constraints: None,
+            validation: None,
+            anonymize_references: false,
         }
     }
 
@@ -557,6 +722,48 @@ mod tests {
         assert_eq!(result.metadata.latency_ms, Some(25));
     }
 
+    #[test]
+    fn map_success_response_skips_prose_around_the_fenced_payload() {
+        let response = r#"{
+          "id": "msg_02",
+          "content": [
+            {
+              "type": "text",
+              "text": "I kept the motif in [C major] as asked.\n\n```json\n{\"request_id\":\"req-42\",\"model\":{\"provider\":\"anthropic\",\"model\":\"claude-3-5-sonnet\"},\"candidates\":[{\"id\":\"cand-1\",\"bars\":4,\"notes\":[{\"pitch\":60,\"start_tick\":0,\"duration_tick\":240,\"velocity\":96}]}]}\n```\n\nWant a busier variation?"
+            }
+          ]
+        }"#;
+
+        let result = provider()
+            .map_success_response(&request(), response, 18, None)
+            .expect("prose around the fence should be ignored");
+
+        assert_eq!(result.request_id, "req-42");
+        assert_eq!(result.candidates[0].id, "cand-1");
+    }
+
+    #[test]
+    fn map_describe_response_returns_trimmed_prose() {
+        let response = r#"{
+          "id": "msg_03",
+          "content": [
+            {"type": "text", "text": "  A stepwise D minor line "},
+            {"type": "text", "text": "over a steady eighth-note pulse.\n"}
+          ]
+        }"#;
+
+        let text = map_describe_response(response).expect("prose should be accepted");
+
+        assert_eq!(
+            text,
+            "A stepwise D minor line over a steady eighth-note pulse."
+        );
+        assert!(matches!(
+            map_describe_response(r#"{"content": []}"#),
+            Err(LlmError::InvalidResponse { .. })
+        ));
+    }
+
     #[test]
     fn map_success_response_rejects_request_id_mismatch() {
         let response = r#"{
@@ -584,19 +791,61 @@ mod tests {
     fn map_http_error_maps_status_and_error_type() {
         let auth = map_http_error(
             StatusCode::UNAUTHORIZED,
+            None,
             r#"{"error":{"type":"authentication_error","message":"invalid key"}}"#,
+            "claude-3-5-sonnet",
         );
         let rate_limited = map_http_error(
             StatusCode::TOO_MANY_REQUESTS,
+            None,
             r#"{"error":{"type":"rate_limit_error","message":"slow down"}}"#,
+            "claude-3-5-sonnet",
         );
         let timeout = map_http_error(
             StatusCode::GATEWAY_TIMEOUT,
+            None,
             r#"{"error":{"type":"timeout_error","message":"timed out"}}"#,
+            "claude-3-5-sonnet",
         );
 
         assert!(matches!(auth, LlmError::Auth));
-        assert!(matches!(rate_limited, LlmError::RateLimited));
+        assert!(matches!(rate_limited, LlmError::RateLimited { .. }));
         assert!(matches!(timeout, LlmError::Timeout));
     }
+
+    #[test]
+    fn map_http_error_maps_account_and_model_errors() {
+        let rate_limited = map_http_error(
+            StatusCode::TOO_MANY_REQUESTS,
+            Some(Duration::from_secs(20)),
+            r#"{"error":{"type":"rate_limit_error","message":"slow down"}}"#,
+            "claude-3-5-sonnet",
+        );
+        let quota = map_http_error(
+            StatusCode::BAD_REQUEST,
+            None,
+            r#"{"error":{"type":"invalid_request_error","message":"Your credit balance is too low"}}"#,
+            "claude-3-5-sonnet",
+        );
+        let expired = map_http_error(
+            StatusCode::UNAUTHORIZED,
+            None,
+            r#"{"error":{"type":"authentication_error","message":"OAuth token has expired"}}"#,
+            "claude-3-5-sonnet",
+        );
+        let missing_model = map_http_error(
+            StatusCode::NOT_FOUND,
+            None,
+            r#"{"error":{"type":"not_found_error","message":"model: claude-0"}}"#,
+            "claude-0",
+        );
+
+        assert_eq!(rate_limited.retry_after(), Some(Duration::from_secs(20)));
+        assert!(matches!(quota, LlmError::QuotaExceeded));
+        assert!(matches!(expired, LlmError::AuthExpired));
+        assert!(matches!(
+            missing_model,
+            LlmError::ModelNotFound { model } if model == "claude-0"
+        ));
+    }
 }
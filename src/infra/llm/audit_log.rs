@@ -0,0 +1,452 @@
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::domain::LlmError;
+
+use super::env::read_env_var;
+
+pub const AUDIT_LOG_ENV: &str = "SONANT_AUDIT_LOG";
+/// Comma-separated literal terms (names, studio or client details) scrubbed from audit records.
+pub const AUDIT_LOG_REDACT_ENV: &str = "SONANT_AUDIT_LOG_REDACT";
+pub const AUDIT_LOG_DIR_NAME: &str = "audit";
+/// Past this size a session file is rolled over to `<session>.1.jsonl`, replacing the last roll.
+pub const AUDIT_LOG_MAX_SESSION_BYTES: u64 = 16 * 1024 * 1024;
+/// Sessions kept in the audit folder, counting the current one; older ones are deleted when a
+/// session writes its first exchange.
+pub const AUDIT_LOG_MAX_SESSIONS: usize = 10;
+const REDACTED: &str = "[REDACTED]";
+const REDACTED_EMAIL: &str = "[REDACTED_EMAIL]";
+
+/// One provider round trip as sent and received, before any parsing.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuditExchange {
+    pub recorded_at_ms: u64,
+    pub provider: String,
+    pub model: String,
+    pub request_id: String,
+    pub endpoint: String,
+    pub http_status: Option<u16>,
+    pub latency_ms: Option<u64>,
+    /// Provider payload, including the full system and user prompts.
+    pub request: Value,
+    /// Raw response body, or the transport error when none arrived.
+    pub response: String,
+}
+
+impl AuditExchange {
+    pub fn new(
+        provider: impl Into<String>,
+        model: impl Into<String>,
+        request_id: impl Into<String>,
+        endpoint: impl Into<String>,
+        request: Value,
+    ) -> Self {
+        let recorded_at_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX))
+            .unwrap_or_default();
+        Self {
+            recorded_at_ms,
+            provider: provider.into(),
+            model: model.into(),
+            request_id: request_id.into(),
+            endpoint: endpoint.into(),
+            http_status: None,
+            latency_ms: None,
+            request,
+            response: String::new(),
+        }
+    }
+
+    /// Fills in what came back from a completed HTTP round trip.
+    pub fn with_response(
+        mut self,
+        http_status: u16,
+        latency_ms: u64,
+        response: impl Into<String>,
+    ) -> Self {
+        self.http_status = Some(http_status);
+        self.latency_ms = Some(latency_ms);
+        self.response = response.into();
+        self
+    }
+}
+
+/// Records a provider exchange when auditing is on. Auditing must never fail the generation
+/// itself, so write errors are dropped; `exchange` is only built when there is a log to write.
+pub(super) fn record_best_effort(
+    audit_log: Option<&AuditLog>,
+    secret: &str,
+    exchange: impl FnOnce() -> AuditExchange,
+) {
+    if let Some(audit_log) = audit_log {
+        let _ = audit_log.record(&exchange(), &[secret]);
+    }
+}
+
+/// Request id recorded for free-text `describe` calls, which have no generation request.
+pub(super) const DESCRIBE_AUDIT_REQUEST_ID: &str = "describe";
+
+/// Opt-in, per-session log of provider exchanges for debugging bad generations.
+///
+/// Each exchange is appended as a JSON line to `session-<start>-<pid>.jsonl` and the most recent
+/// one is also written pretty-printed next to it. API keys handed to [`AuditLog::record`], the
+/// configured redaction terms, and email addresses are replaced before anything reaches disk.
+///
+/// The folder stays bounded: a session rolls over past [`AUDIT_LOG_MAX_SESSION_BYTES`] and only
+/// the last [`AUDIT_LOG_MAX_SESSIONS`] sessions are kept.
+pub struct AuditLog {
+    session: String,
+    session_path: PathBuf,
+    rolled_session_path: PathBuf,
+    last_exchange_path: PathBuf,
+    max_session_bytes: u64,
+    max_sessions: usize,
+    redact_terms: Vec<String>,
+    file: Mutex<Option<File>>,
+}
+
+impl AuditLog {
+    /// Reads [`AUDIT_LOG_ENV`] and [`AUDIT_LOG_REDACT_ENV`]; `None` unless the log is enabled.
+    pub fn from_env(config_dir: &Path) -> Result<Option<Self>, LlmError> {
+        let enabled = match read_env_var(AUDIT_LOG_ENV)? {
+            Some(value) => parse_enabled(&value).ok_or_else(|| {
+                LlmError::validation(format!("{AUDIT_LOG_ENV} must be true or false"))
+            })?,
+            None => false,
+        };
+        if !enabled {
+            return Ok(None);
+        }
+        let redact_terms = read_env_var(AUDIT_LOG_REDACT_ENV)?
+            .map(|value| parse_redact_terms(&value))
+            .unwrap_or_default();
+        Ok(Some(Self::new(
+            config_dir.join(AUDIT_LOG_DIR_NAME),
+            redact_terms,
+        )))
+    }
+
+    pub fn new(dir: impl Into<PathBuf>, redact_terms: Vec<String>) -> Self {
+        let dir = dir.into();
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        let session = format!("session-{started_at}-{}", std::process::id());
+        Self {
+            session_path: dir.join(format!("{session}.jsonl")),
+            rolled_session_path: dir.join(format!("{session}.1.jsonl")),
+            last_exchange_path: dir.join(format!("{session}-last.json")),
+            session,
+            max_session_bytes: AUDIT_LOG_MAX_SESSION_BYTES,
+            max_sessions: AUDIT_LOG_MAX_SESSIONS,
+            redact_terms: redact_terms
+                .into_iter()
+                .filter(|term| !term.trim().is_empty())
+                .collect(),
+            file: Mutex::new(None),
+        }
+    }
+
+    pub fn session_path(&self) -> &Path {
+        &self.session_path
+    }
+
+    /// Pretty-printed copy of the most recent exchange, once one has been recorded.
+    pub fn last_exchange_path(&self) -> Option<&Path> {
+        self.last_exchange_path
+            .is_file()
+            .then_some(self.last_exchange_path.as_path())
+    }
+
+    /// Redacts `secrets` and the configured terms from `exchange` and appends it to the session.
+    pub fn record(&self, exchange: &AuditExchange, secrets: &[&str]) -> std::io::Result<()> {
+        let mut exchange = exchange.clone();
+        redact_value(&mut exchange.request, &self.redact_terms, secrets);
+        exchange.response = self.redact(&exchange.response, secrets);
+        exchange.endpoint = self.redact(&exchange.endpoint, secrets);
+
+        let line = serde_json::to_string(&exchange).map_err(std::io::Error::other)?;
+        let pretty = serde_json::to_string_pretty(&exchange).map_err(std::io::Error::other)?;
+
+        let mut file = self
+            .file
+            .lock()
+            .map_err(|_| std::io::Error::other("audit log lock poisoned"))?;
+        let full = match file.as_ref() {
+            Some(open) => open.metadata()?.len() >= self.max_session_bytes,
+            None => false,
+        };
+        if full {
+            *file = None;
+            std::fs::rename(&self.session_path, &self.rolled_session_path)?;
+        }
+        if file.is_none() {
+            *file = Some(self.open_session_file()?);
+        }
+        if let Some(file) = file.as_mut() {
+            writeln!(file, "{line}")?;
+            file.flush()?;
+        }
+        std::fs::write(&self.last_exchange_path, pretty)
+    }
+
+    fn open_session_file(&self) -> std::io::Result<File> {
+        if let Some(dir) = self.session_path.parent() {
+            std::fs::create_dir_all(dir)?;
+            prune_old_sessions(dir, &self.session, self.max_sessions)?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.session_path)
+    }
+
+    fn redact(&self, text: &str, secrets: &[&str]) -> String {
+        redact_text(text, &self.redact_terms, secrets)
+    }
+}
+
+/// Deletes the files of all but the newest `max_sessions - 1` sessions other than `current`.
+fn prune_old_sessions(dir: &Path, current: &str, max_sessions: usize) -> std::io::Result<()> {
+    let mut sessions = BTreeMap::<(u64, String), Vec<PathBuf>>::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let Some(session) = file_name.to_str().and_then(session_of_file) else {
+            continue;
+        };
+        if session == current {
+            continue;
+        }
+        let started_at = session
+            .strip_prefix("session-")
+            .and_then(|rest| rest.split('-').next())
+            .and_then(|secs| secs.parse::<u64>().ok())
+            .unwrap_or_default();
+        sessions
+            .entry((started_at, session.to_string()))
+            .or_default()
+            .push(entry.path());
+    }
+
+    let excess = sessions
+        .len()
+        .saturating_sub(max_sessions.saturating_sub(1));
+    for path in sessions.into_values().take(excess).flatten() {
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// `session-<start>-<pid>` for the log, rolled-over and last-exchange files of one session.
+fn session_of_file(file_name: &str) -> Option<&str> {
+    if !file_name.starts_with("session-")
+        || !(file_name.ends_with(".jsonl") || file_name.ends_with(".json"))
+    {
+        return None;
+    }
+    let stem = file_name.split('.').next()?;
+    Some(stem.strip_suffix("-last").unwrap_or(stem))
+}
+
+fn redact_value(value: &mut Value, terms: &[String], secrets: &[&str]) {
+    match value {
+        Value::String(text) => *text = redact_text(text, terms, secrets),
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| redact_value(item, terms, secrets)),
+        Value::Object(fields) => fields
+            .values_mut()
+            .for_each(|field| redact_value(field, terms, secrets)),
+        Value::Null | Value::Bool(_) | Value::Number(_) => {}
+    }
+}
+
+fn redact_text(text: &str, terms: &[String], secrets: &[&str]) -> String {
+    let mut redacted = text.to_string();
+    for secret in secrets.iter().map(|secret| secret.trim()) {
+        if !secret.is_empty() {
+            redacted = redacted.replace(secret, REDACTED);
+        }
+    }
+    for term in terms {
+        redacted = redacted.replace(term.as_str(), REDACTED);
+    }
+    redact_emails(&redacted)
+}
+
+fn redact_emails(text: &str) -> String {
+    let is_local = |character: char| character.is_alphanumeric() || "._%+-".contains(character);
+    let is_domain = |character: char| character.is_alphanumeric() || ".-".contains(character);
+
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find('@') {
+        let local_start = rest[..at]
+            .char_indices()
+            .rev()
+            .take_while(|(_, character)| is_local(*character))
+            .last()
+            .map_or(at, |(index, _)| index);
+        let domain_end = rest[at + 1..]
+            .char_indices()
+            .find(|(_, character)| !is_domain(*character))
+            .map_or(rest.len(), |(index, _)| at + 1 + index);
+        let domain = rest[at + 1..domain_end].trim_end_matches('.');
+        let domain_end = at + 1 + domain.len();
+
+        if local_start < at && domain.contains('.') && !domain.starts_with('.') {
+            output.push_str(&rest[..local_start]);
+            output.push_str(REDACTED_EMAIL);
+            rest = &rest[domain_end..];
+        } else {
+            output.push_str(&rest[..=at]);
+            rest = &rest[at + 1..];
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+fn parse_enabled(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" | "" => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_redact_terms(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|term| !term.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AuditExchange, AuditLog, record_best_effort, redact_emails};
+    use crate::test_support::TestDir;
+    use serde_json::json;
+
+    #[test]
+    fn emails_are_replaced_but_lone_at_signs_are_kept() {
+        assert_eq!(
+            redact_emails("mail jane.doe+mix@studio.example.com. or @handle at 120bpm"),
+            "mail [REDACTED_EMAIL]. or @handle at 120bpm"
+        );
+    }
+
+    #[test]
+    fn record_redacts_secrets_terms_and_emails_before_writing() {
+        let dir = TestDir::new("audit");
+        let log = AuditLog::new(dir.join("audit"), vec!["Blue Room Studio".to_string()]);
+        assert!(log.last_exchange_path().is_none());
+
+        let mut exchange = AuditExchange::new(
+            "anthropic",
+            "claude-test",
+            "req-1",
+            "https://api.example.com/v1/messages",
+            json!({
+                "system": "You write MIDI.",
+                "messages": [{"role": "user", "content": "Bassline for Blue Room Studio, ask ops@blue.example"}]
+            }),
+        );
+        exchange.http_status = Some(401);
+        exchange.response = r#"{"error":"invalid key sk-secret-123"}"#.to_string();
+
+        log.record(&exchange, &["sk-secret-123"])
+            .expect("exchange should be recorded");
+        log.record(&exchange, &["sk-secret-123"])
+            .expect("second exchange should be appended");
+
+        let session = std::fs::read_to_string(log.session_path()).expect("session should exist");
+        assert_eq!(session.lines().count(), 2);
+        assert!(!session.contains("sk-secret-123"));
+        assert!(!session.contains("Blue Room Studio"));
+        assert!(!session.contains("ops@blue.example"));
+        assert!(session.contains("You write MIDI."));
+
+        let last = std::fs::read_to_string(
+            log.last_exchange_path()
+                .expect("last exchange should exist after recording"),
+        )
+        .expect("last exchange should be readable");
+        assert!(last.contains("Bassline for [REDACTED], ask [REDACTED_EMAIL]"));
+    }
+
+    #[test]
+    fn a_full_session_rolls_over_and_old_sessions_are_pruned() {
+        let dir = TestDir::new("audit-rotation");
+        let audit_dir = dir.join("audit");
+        std::fs::create_dir_all(&audit_dir).expect("audit dir should be created");
+        for started_at in [100, 200, 300] {
+            for suffix in [".jsonl", ".1.jsonl", "-last.json"] {
+                std::fs::write(
+                    audit_dir.join(format!("session-{started_at}-7{suffix}")),
+                    "{}",
+                )
+                .expect("old session file should be written");
+            }
+        }
+        std::fs::write(audit_dir.join("notes.txt"), "keep").expect("notes should be written");
+
+        let mut log = AuditLog::new(&audit_dir, Vec::new());
+        log.max_session_bytes = 1;
+        log.max_sessions = 2;
+        let exchange = AuditExchange::new("anthropic", "claude-test", "req-1", "", json!({}))
+            .with_response(200, 12, "ok");
+        log.record(&exchange, &[])
+            .expect("first exchange should be recorded");
+        log.record(&exchange, &[])
+            .expect("second exchange should roll over");
+
+        let mut remaining = std::fs::read_dir(&audit_dir)
+            .expect("audit dir should be readable")
+            .map(|entry| entry.expect("entry should be readable").file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .filter(|name| !name.starts_with(&log.session))
+            .collect::<Vec<_>>();
+        remaining.sort();
+        assert_eq!(
+            remaining,
+            [
+                "notes.txt",
+                "session-300-7-last.json",
+                "session-300-7.1.jsonl",
+                "session-300-7.jsonl",
+            ]
+        );
+        assert!(log.rolled_session_path.is_file());
+        let session = std::fs::read_to_string(log.session_path()).expect("session should exist");
+        assert_eq!(session.lines().count(), 1);
+    }
+
+    #[test]
+    fn best_effort_recording_skips_building_without_a_log() {
+        record_best_effort(None, "sk-secret", || {
+            unreachable!("no exchange is built when auditing is off")
+        });
+
+        let dir = TestDir::new("audit");
+        // A file where the audit folder should be makes every write fail.
+        std::fs::write(dir.join("audit"), "").expect("blocking file should be written");
+        let log = AuditLog::new(dir.join("audit"), Vec::new());
+        record_best_effort(Some(&log), "sk-secret", || {
+            AuditExchange::new("anthropic", "claude-test", "req-1", "", json!({}))
+        });
+        assert!(log.last_exchange_path().is_none());
+    }
+}
@@ -1,6 +1,9 @@
 use std::time::Duration;
 
-use crate::domain::LlmError;
+use crate::domain::{LlmError, PrivacyFilterMode};
+
+pub const SAFE_MODE_ENV: &str = "SONANT_SAFE_MODE";
+pub const JOB_DEADLINE_ENV: &str = "SONANT_JOB_DEADLINE_SECS";
 
 pub(crate) fn read_env_var(name: &str) -> Result<Option<String>, LlmError> {
     match std::env::var(name) {
@@ -31,6 +34,25 @@ pub(crate) fn read_timeout_from_env(name: &str) -> Result<Option<Duration>, LlmE
     Ok(Some(parse_timeout_seconds(name, &value)?))
 }
 
+/// Reads [`SAFE_MODE_ENV`]: `flag` or `strip`, with unset, empty, or `off` disabling the filter.
+pub fn privacy_filter_from_env() -> Result<Option<PrivacyFilterMode>, LlmError> {
+    let Some(value) = read_env_var(SAFE_MODE_ENV)? else {
+        return Ok(None);
+    };
+    if value.trim().is_empty() || value.trim().eq_ignore_ascii_case("off") {
+        return Ok(None);
+    }
+    PrivacyFilterMode::parse(&value)
+        .map(Some)
+        .ok_or_else(|| LlmError::validation(format!("{SAFE_MODE_ENV} must be off, flag, or strip")))
+}
+
+/// Reads [`JOB_DEADLINE_ENV`]: seconds a generation job may run without progress before the
+/// job watchdog fails it.
+pub fn job_deadline_from_env() -> Result<Option<Duration>, LlmError> {
+    read_timeout_from_env(JOB_DEADLINE_ENV)
+}
+
 pub(crate) fn resolve_timeout_with_global_fallback<F>(
     provider_timeout: Option<Duration>,
     read_global_timeout: F,
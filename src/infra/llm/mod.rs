@@ -1,14 +1,26 @@
 mod anthropic;
+mod audit_log;
 mod env;
 mod openai_compatible;
 mod prompt_builder;
 mod provider;
 mod provider_registry;
+mod reachability;
+mod remote_server;
 mod response_parsing;
 pub mod schema_validator;
 
 pub use anthropic::AnthropicProvider;
+pub use audit_log::{
+    AUDIT_LOG_DIR_NAME, AUDIT_LOG_ENV, AUDIT_LOG_MAX_SESSION_BYTES, AUDIT_LOG_MAX_SESSIONS,
+    AUDIT_LOG_REDACT_ENV, AuditExchange, AuditLog,
+};
+pub use env::{JOB_DEADLINE_ENV, SAFE_MODE_ENV, job_deadline_from_env, privacy_filter_from_env};
 pub use openai_compatible::OpenAiCompatibleProvider;
-pub use prompt_builder::{BuiltPrompt, PromptBuilder};
+pub use prompt_builder::{
+    BuiltPrompt, PromptAllocation, PromptBudget, PromptBuilder, PromptSection,
+};
 pub use provider::LlmProvider;
 pub use provider_registry::ProviderRegistry;
+pub use reachability::probe_endpoint;
+pub use remote_server::{REMOTE_SERVER_PROVIDER_ID, RemoteServerProvider};
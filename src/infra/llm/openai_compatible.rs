@@ -1,4 +1,5 @@
 use std::collections::BTreeSet;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use reqwest::StatusCode;
@@ -10,14 +11,17 @@ use crate::domain::{
     GenerationMetadata, GenerationRequest, GenerationResult, GenerationUsage, LlmError,
 };
 
+use super::audit_log::{AuditExchange, AuditLog, DESCRIBE_AUDIT_REQUEST_ID, record_best_effort};
 use super::env::{read_env_var, read_timeout_from_env, resolve_timeout_with_global_fallback};
-use super::response_parsing::{extract_json_payload, truncate_message};
+use super::reachability::is_loopback_url;
+use super::response_parsing::{extract_json_payload, parse_retry_after, truncate_message};
 use super::schema_validator::LlmResponseSchemaValidator;
-use super::{LlmProvider, PromptBuilder};
+use super::{BuiltPrompt, LlmProvider, PromptBuilder};
 
 const DEFAULT_PROVIDER_ID: &str = "openai_compatible";
 const DEFAULT_BASE_URL: &str = "https://api.openai.com";
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(8);
+const DESCRIBE_MAX_TOKENS: u16 = 256;
 
 const ENV_API_KEY: &str = "SONANT_OPENAI_COMPAT_API_KEY";
 const ENV_BASE_URL: &str = "SONANT_OPENAI_COMPAT_BASE_URL";
@@ -36,6 +40,7 @@ pub struct OpenAiCompatibleProvider {
     client: Client,
     schema_validator: LlmResponseSchemaValidator,
     supported_models: BTreeSet<String>,
+    audit_log: Option<Arc<AuditLog>>,
 }
 
 impl OpenAiCompatibleProvider {
@@ -135,9 +140,19 @@ impl OpenAiCompatibleProvider {
             client,
             schema_validator,
             supported_models,
+            audit_log: None,
         })
     }
 
+    pub fn with_audit_log(mut self, audit_log: Arc<AuditLog>) -> Self {
+        self.audit_log = Some(audit_log);
+        self
+    }
+
+    pub fn api_base_url(&self) -> &str {
+        &self.api_base_url
+    }
+
     pub fn refresh_models(&mut self) -> Result<(), LlmError> {
         self.supported_models = self.fetch_supported_models()?;
         Ok(())
@@ -165,9 +180,10 @@ impl OpenAiCompatibleProvider {
             .map_err(map_transport_error)?;
 
         let status = response.status();
+        let retry_after = parse_retry_after(response.headers());
         let response_body = response.text().map_err(map_transport_error)?;
         if !status.is_success() {
-            return Err(map_http_error(status, &response_body));
+            return Err(map_http_error(status, retry_after, &response_body, None));
         }
 
         let decoded: OpenAiModelsResponse =
@@ -210,6 +226,27 @@ impl OpenAiCompatibleProvider {
         })
     }
 
+    fn record_exchange(
+        &self,
+        model_id: &str,
+        request_id: &str,
+        payload: &OpenAiChatCompletionsRequest,
+        status: StatusCode,
+        latency_ms: u64,
+        response_body: &str,
+    ) {
+        record_best_effort(self.audit_log.as_deref(), &self.api_key, || {
+            AuditExchange::new(
+                &self.provider_id,
+                model_id,
+                request_id,
+                self.endpoint_url(),
+                serde_json::to_value(payload).unwrap_or_default(),
+            )
+            .with_response(status.as_u16(), latency_ms, response_body)
+        });
+    }
+
     fn map_success_response(
         &self,
         request: &GenerationRequest,
@@ -234,6 +271,16 @@ impl OpenAiCompatibleProvider {
             }
         }
 
+        if response
+            .choices
+            .iter()
+            .any(|choice| choice.finish_reason.as_deref() == Some("content_filter"))
+        {
+            return Err(LlmError::ContentFiltered {
+                message: "the completion was stopped by the provider's content filter".to_string(),
+            });
+        }
+
         let response_text = response_text.ok_or_else(|| {
             LlmError::invalid_response("OpenAI-compatible response did not include text content")
         })?;
@@ -244,7 +291,9 @@ impl OpenAiCompatibleProvider {
             )
         })?;
 
-        let mut result = self.schema_validator.validate_response_json(json_payload)?;
+        let mut result = self
+            .schema_validator
+            .validate_response_json_for_request(&json_payload, request)?;
 
         if result.request_id != request.request_id {
             return Err(LlmError::invalid_response(format!(
@@ -274,6 +323,10 @@ impl OpenAiCompatibleProvider {
             provider_request_id,
             stop_reason,
             usage,
+            chords: std::mem::take(&mut result.metadata.chords),
+            repairs: std::mem::take(&mut result.metadata.repairs),
+            confidence: std::mem::take(&mut result.metadata.confidence),
+            validation_warnings: std::mem::take(&mut result.metadata.validation_warnings),
         };
 
         Ok(result)
@@ -290,6 +343,10 @@ impl LlmProvider for OpenAiCompatibleProvider {
         !model_id.is_empty() && self.supported_models.contains(model_id)
     }
 
+    fn is_cloud(&self) -> bool {
+        !is_loopback_url(&self.api_base_url)
+    }
+
     fn generate(&self, request: &GenerationRequest) -> Result<GenerationResult, LlmError> {
         let payload = self.build_request_payload(request)?;
         let started = Instant::now();
@@ -310,16 +367,82 @@ impl LlmProvider for OpenAiCompatibleProvider {
             .or_else(|| response.headers().get("request-id"))
             .and_then(|value| value.to_str().ok())
             .map(str::to_owned);
+        let retry_after = parse_retry_after(response.headers());
 
         let response_body = response.text().map_err(map_transport_error)?;
+        let elapsed_ms = started.elapsed().as_millis();
+        let latency_ms = u64::try_from(elapsed_ms).unwrap_or(u64::MAX);
+        self.record_exchange(
+            &request.model.model,
+            &request.request_id,
+            &payload,
+            status,
+            latency_ms,
+            &response_body,
+        );
         if !status.is_success() {
-            return Err(map_http_error(status, &response_body));
+            return Err(map_http_error(
+                status,
+                retry_after,
+                &response_body,
+                Some(&request.model.model),
+            ));
         }
 
-        let elapsed_ms = started.elapsed().as_millis();
-        let latency_ms = u64::try_from(elapsed_ms).unwrap_or(u64::MAX);
         self.map_success_response(request, &response_body, latency_ms, header_request_id)
     }
+
+    fn describe(&self, model_id: &str, prompt: &BuiltPrompt) -> Result<String, LlmError> {
+        let payload = OpenAiChatCompletionsRequest {
+            model: model_id.to_string(),
+            messages: vec![
+                OpenAiChatMessageRequest {
+                    role: "system".to_string(),
+                    content: prompt.system.clone(),
+                },
+                OpenAiChatMessageRequest {
+                    role: "user".to_string(),
+                    content: prompt.user.clone(),
+                },
+            ],
+            temperature: None,
+            top_p: None,
+            max_tokens: Some(DESCRIBE_MAX_TOKENS),
+        };
+        let started = Instant::now();
+
+        let response = self
+            .client
+            .post(self.endpoint_url())
+            .bearer_auth(&self.api_key)
+            .header("content-type", "application/json")
+            .json(&payload)
+            .send()
+            .map_err(map_transport_error)?;
+
+        let status = response.status();
+        let retry_after = parse_retry_after(response.headers());
+        let response_body = response.text().map_err(map_transport_error)?;
+        let latency_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+        self.record_exchange(
+            model_id,
+            DESCRIBE_AUDIT_REQUEST_ID,
+            &payload,
+            status,
+            latency_ms,
+            &response_body,
+        );
+        if !status.is_success() {
+            return Err(map_http_error(
+                status,
+                retry_after,
+                &response_body,
+                Some(model_id),
+            ));
+        }
+
+        map_describe_response(&response_body)
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -442,6 +565,30 @@ fn map_usage(usage: OpenAiUsage) -> Option<GenerationUsage> {
     }
 }
 
+fn map_describe_response(response_body: &str) -> Result<String, LlmError> {
+    let response: OpenAiChatCompletionsResponse =
+        serde_json::from_str(response_body).map_err(|err| {
+            LlmError::invalid_response(format!("OpenAI-compatible response decode failed: {err}"))
+        })?;
+    if response
+        .choices
+        .iter()
+        .any(|choice| choice.finish_reason.as_deref() == Some("content_filter"))
+    {
+        return Err(LlmError::ContentFiltered {
+            message: "the completion was stopped by the provider's content filter".to_string(),
+        });
+    }
+
+    response
+        .choices
+        .iter()
+        .find_map(OpenAiChoice::extract_text)
+        .ok_or_else(|| {
+            LlmError::invalid_response("OpenAI-compatible response did not include text content")
+        })
+}
+
 fn extract_message_content(content: &Value) -> Option<String> {
     match content {
         Value::String(text) => non_empty_owned(text),
@@ -467,7 +614,13 @@ fn extract_content_part_text(part: &Value) -> Option<String> {
     }
 }
 
-fn map_http_error(status: StatusCode, body: &str) -> LlmError {
+/// `model` is the requested model; `None` for catalog calls, where a 404 is not about a model.
+fn map_http_error(
+    status: StatusCode,
+    retry_after: Option<Duration>,
+    body: &str,
+    model: Option<&str>,
+) -> LlmError {
     let parsed_error = serde_json::from_str::<OpenAiErrorEnvelope>(body).ok();
     let error_type = parsed_error
         .as_ref()
@@ -478,6 +631,19 @@ fn map_http_error(status: StatusCode, body: &str) -> LlmError {
         .and_then(|envelope| envelope.error.as_ref())
         .and_then(|detail| detail.code.as_deref());
 
+    if matches!(error_type, Some("insufficient_quota"))
+        || matches!(
+            error_code,
+            Some("insufficient_quota" | "billing_hard_limit_reached")
+        )
+    {
+        return LlmError::QuotaExceeded;
+    }
+
+    if matches!(error_code, Some("token_expired" | "expired_api_key")) {
+        return LlmError::AuthExpired;
+    }
+
     if status == StatusCode::UNAUTHORIZED
         || status == StatusCode::FORBIDDEN
         || matches!(error_type, Some("authentication_error"))
@@ -490,13 +656,31 @@ fn map_http_error(status: StatusCode, body: &str) -> LlmError {
     }
 
     if status == StatusCode::TOO_MANY_REQUESTS
-        || matches!(error_type, Some("rate_limit_error" | "insufficient_quota"))
-        || matches!(
-            error_code,
-            Some("rate_limit_exceeded" | "insufficient_quota")
-        )
+        || matches!(error_type, Some("rate_limit_error"))
+        || matches!(error_code, Some("rate_limit_exceeded"))
     {
-        return LlmError::RateLimited;
+        return LlmError::rate_limited(retry_after);
+    }
+
+    if let Some(model) = model
+        && (matches!(error_code, Some("model_not_found")) || status == StatusCode::NOT_FOUND)
+    {
+        return LlmError::ModelNotFound {
+            model: model.to_string(),
+        };
+    }
+
+    if matches!(
+        error_code,
+        Some("content_filter" | "content_policy_violation")
+    ) {
+        return LlmError::ContentFiltered {
+            message: parsed_error
+                .as_ref()
+                .and_then(|envelope| envelope.error.as_ref())
+                .map(|detail| detail.message.clone())
+                .unwrap_or_default(),
+        };
     }
 
     if status == StatusCode::REQUEST_TIMEOUT
@@ -625,10 +809,13 @@ fn build_v1_url(api_base_url: &str, endpoint_path: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::{OpenAiCompatibleProvider, build_v1_url, map_http_error, parse_bool};
+    use super::{
+        OpenAiCompatibleProvider, build_v1_url, map_describe_response, map_http_error, parse_bool,
+    };
     use crate::domain::{
-        FileReferenceInput, GenerationMode, GenerationParams, GenerationRequest, LlmError,
-        MidiReferenceSummary, ModelRef, ReferenceSlot, ReferenceSource,
+        FileReferenceInput, GENERATION_CONTRACT_VERSION, GenerationMode, GenerationParams,
+        GenerationRequest, LlmError, MidiReferenceSummary, ModelRef, ReferenceSlot,
+        ReferenceSource, TickResolution,
     };
     use crate::infra::llm::{LlmProvider, PromptBuilder};
     use reqwest::StatusCode;
@@ -660,6 +847,8 @@ mod tests {
                 scale: "major".to_string(),
                 density: 3,
                 complexity: 2,
+                syncopation: 3,
+                tick_resolution: TickResolution::DEFAULT,
                 temperature: Some(0.5),
                 top_p: Some(0.9),
                 max_tokens: Some(512),
@@ -681,8 +870,16 @@ mod tests {
                     delta_tick: 0,
                     event: "NoteOn channel=0 key=60 vel=100".to_string(),
                 }],
+                transposition: None,
+                tempo: None,
             }],
             variation_count: 2,
+            locked_notes: Vec::new(),
+            contract_version: GENERATION_CONTRACT_VERSION,
+            daw_context: None,
+            constraints: None,
+            validation: None,
+            anonymize_references: false,
         }
     }
 
@@ -828,6 +1025,47 @@ mod tests {
         assert_eq!(result.metadata.latency_ms, Some(33));
     }
 
+    #[test]
+    fn map_success_response_tolerates_prose_and_trailing_commas() {
+        let response = r#"{
+          "id": "chatcmpl_02",
+          "choices": [
+            {
+              "finish_reason": "stop",
+              "message": {
+                "content": "Here you go:\n{\"request_id\":\"req-42\",\"model\":{\"provider\":\"openai_compatible\",\"model\":\"gpt-5.2\"},\"candidates\":[{\"id\":\"cand-1\",\"bars\":4,\"notes\":[{\"pitch\":60,\"start_tick\":0,\"duration_tick\":240,\"velocity\":96},]},]}\nEnjoy!"
+              }
+            }
+          ]
+        }"#;
+
+        let result = provider()
+            .map_success_response(&request(), response, 12, None)
+            .expect("lenient JSON should still parse");
+
+        assert_eq!(result.candidates.len(), 1);
+        assert_eq!(result.candidates[0].notes.len(), 1);
+    }
+
+    #[test]
+    fn map_describe_response_returns_first_choice_text() {
+        let response = r#"{
+          "id": "chatcmpl-3",
+          "choices": [
+            {"finish_reason": "stop", "message": {"content": " Root-fifth bass locking to the kick. "}}
+          ]
+        }"#;
+
+        assert_eq!(
+            map_describe_response(response).expect("prose should be accepted"),
+            "Root-fifth bass locking to the kick."
+        );
+        assert!(matches!(
+            map_describe_response(r#"{"choices": [{"finish_reason": "content_filter"}]}"#),
+            Err(LlmError::ContentFiltered { .. })
+        ));
+    }
+
     #[test]
     fn map_success_response_rejects_request_id_mismatch() {
         let response = r#"{
@@ -856,22 +1094,62 @@ mod tests {
     fn map_http_error_maps_status_and_error_type() {
         let auth = map_http_error(
             StatusCode::UNAUTHORIZED,
+            None,
             r#"{"error":{"type":"authentication_error","code":"invalid_api_key","message":"invalid key"}}"#,
+            Some("gpt-5.2"),
         );
         let rate_limited = map_http_error(
             StatusCode::TOO_MANY_REQUESTS,
+            None,
             r#"{"error":{"type":"rate_limit_error","code":"rate_limit_exceeded","message":"slow down"}}"#,
+            Some("gpt-5.2"),
         );
         let timeout = map_http_error(
             StatusCode::GATEWAY_TIMEOUT,
+            None,
             r#"{"error":{"type":"server_timeout","code":"request_timeout","message":"timed out"}}"#,
+            Some("gpt-5.2"),
         );
 
         assert!(matches!(auth, LlmError::Auth));
-        assert!(matches!(rate_limited, LlmError::RateLimited));
+        assert!(matches!(rate_limited, LlmError::RateLimited { .. }));
         assert!(matches!(timeout, LlmError::Timeout));
     }
 
+    #[test]
+    fn map_http_error_maps_account_model_and_policy_errors() {
+        let quota = map_http_error(
+            StatusCode::TOO_MANY_REQUESTS,
+            Some(Duration::from_secs(30)),
+            r#"{"error":{"type":"insufficient_quota","code":"insufficient_quota","message":"quota"}}"#,
+            Some("gpt-5.2"),
+        );
+        let missing_model = map_http_error(
+            StatusCode::NOT_FOUND,
+            None,
+            r#"{"error":{"type":"invalid_request_error","code":"model_not_found","message":"no such model"}}"#,
+            Some("gpt-0"),
+        );
+        let filtered = map_http_error(
+            StatusCode::BAD_REQUEST,
+            None,
+            r#"{"error":{"type":"invalid_request_error","code":"content_policy_violation","message":"flagged"}}"#,
+            Some("gpt-5.2"),
+        );
+        let catalog_missing = map_http_error(StatusCode::NOT_FOUND, None, "not found", None);
+
+        assert!(matches!(quota, LlmError::QuotaExceeded));
+        assert!(matches!(
+            missing_model,
+            LlmError::ModelNotFound { model } if model == "gpt-0"
+        ));
+        assert!(matches!(
+            filtered,
+            LlmError::ContentFiltered { message } if message == "flagged"
+        ));
+        assert!(matches!(catalog_missing, LlmError::Transport { .. }));
+    }
+
     #[test]
     fn supports_model_uses_static_catalog() {
         let provider = provider();
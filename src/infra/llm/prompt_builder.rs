@@ -1,7 +1,11 @@
 use std::fmt::Write;
 
+use serde_json::Value;
+
 use crate::domain::{
+    BEATS_PER_BAR, DawContext, GeneratedNote, GenerationCandidate, GenerationConstraints,
     GenerationMode, GenerationRequest, MidiReferenceSummary, ReferenceSlot, ReferenceSource,
+    TickResolution,
 };
 
 use super::schema_validator::GENERATION_RESULT_JSON_SCHEMA;
@@ -9,22 +13,224 @@ use super::schema_validator::GENERATION_RESULT_JSON_SCHEMA;
 const SYSTEM_PROMPT: &str =
     "You are Sonant's MIDI generation backend. Follow all constraints and output strict JSON only.";
 
+const EXPLANATION_SYSTEM_PROMPT: &str = "You are a music producer describing MIDI clips to a collaborator. Answer in plain prose without markdown or JSON.";
+const EXPLANATION_INSTRUCTION: &str = "Explain this generated MIDI pattern in two or three sentences for a musician: its harmony or key, rhythm and groove, contour or register, and how it could be used.";
+
+/// Written at [`TickResolution::DEFAULT`]; `render_examples` rescales them to the request.
+const FEW_SHOT_EXAMPLES: [(&str, &str); 2] = [
+    (
+        "melody",
+        r#"{"request_id":"example-1","model":{"provider":"example","model":"example"},"candidates":[{"id":"cand-1","bars":1,"title":"Stepwise lift","score_hint":0.8,"notes":[{"pitch":62,"start_tick":0,"duration_tick":480,"velocity":96},{"pitch":65,"start_tick":480,"duration_tick":480,"velocity":90},{"pitch":69,"start_tick":960,"duration_tick":960,"velocity":100}]}]}"#,
+    ),
+    (
+        "drum_pattern",
+        r#"{"request_id":"example-2","model":{"provider":"example","model":"example"},"candidates":[{"id":"cand-1","bars":1,"title":"Straight backbeat","score_hint":0.7,"notes":[{"pitch":36,"start_tick":0,"duration_tick":120,"velocity":110,"channel":10},{"pitch":42,"start_tick":240,"duration_tick":60,"velocity":70,"channel":10},{"pitch":38,"start_tick":480,"duration_tick":120,"velocity":105,"channel":10}]}]}"#,
+    ),
+];
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BuiltPrompt {
     pub system: String,
     pub user: String,
+    /// How the context window was split and which sections had to be trimmed to fit it.
+    pub allocations: Vec<PromptAllocation>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptSection {
+    System,
+    References,
+    Examples,
+    User,
+}
+
+impl PromptSection {
+    /// Sections that may be shortened, lowest priority first. The system and user sections
+    /// carry hard constraints and are never trimmed.
+    const TRIM_ORDER: [Self; 2] = [Self::Examples, Self::References];
+}
+
+/// Splits a model's context window between prompt sections. Ratios are relative weights and
+/// need not sum to one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PromptBudget {
+    pub context_window_tokens: u32,
+    pub system_ratio: f32,
+    pub references_ratio: f32,
+    pub examples_ratio: f32,
+    pub user_ratio: f32,
+}
+
+impl Default for PromptBudget {
+    fn default() -> Self {
+        Self {
+            context_window_tokens: 8192,
+            system_ratio: 0.05,
+            references_ratio: 0.4,
+            examples_ratio: 0.15,
+            user_ratio: 0.4,
+        }
+    }
+}
+
+impl PromptBudget {
+    pub fn with_context_window(context_window_tokens: u32) -> Self {
+        Self {
+            context_window_tokens,
+            ..Self::default()
+        }
+    }
+
+    pub fn tokens_for(&self, section: PromptSection) -> u32 {
+        let ratio = match section {
+            PromptSection::System => self.system_ratio,
+            PromptSection::References => self.references_ratio,
+            PromptSection::Examples => self.examples_ratio,
+            PromptSection::User => self.user_ratio,
+        };
+        let total =
+            self.system_ratio + self.references_ratio + self.examples_ratio + self.user_ratio;
+        if total <= 0.0 || ratio <= 0.0 {
+            return 0;
+        }
+        (self.context_window_tokens as f32 * ratio / total).floor() as u32
+    }
+}
+
+/// One section's share of the context window against its estimated size after trimming.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PromptAllocation {
+    pub section: PromptSection,
+    pub budget_tokens: u32,
+    pub used_tokens: u32,
+    pub trimmed: bool,
 }
 
 pub struct PromptBuilder;
 
 impl PromptBuilder {
     pub fn build(request: &GenerationRequest) -> BuiltPrompt {
-        let mode = mode_name(request.mode);
-        let mode_template = mode_template(request.mode);
-        let references = render_references(&request.references);
-        let user_prompt = request.prompt.trim();
+        Self::build_with_budget(request, &PromptBudget::default())
+    }
+
+    /// Builds the prompt and, while its estimated size exceeds the context window, trims
+    /// few-shot examples and then reference event rows. Each trimmable section is first cut
+    /// back to its own share; only if that is not enough is it cut further.
+    pub fn build_with_budget(request: &GenerationRequest, budget: &PromptBudget) -> BuiltPrompt {
+        let system_tokens = estimate_tokens(SYSTEM_PROMPT);
+        let user_tokens = estimate_tokens(&render_user_prompt(request, "", ""));
+        let max_reference_events = request
+            .references
+            .iter()
+            .map(|reference| reference.events.len())
+            .max()
+            .unwrap_or(0);
+
+        let mut example_count = FEW_SHOT_EXAMPLES.len();
+        let tick_resolution = request.params.tick_resolution;
+        let mut examples = render_examples(example_count, tick_resolution);
+        let mut event_limit = max_reference_events;
+        let mut references = render_references(&request.references, event_limit);
+        let over_window = |references: &str, examples: &str| {
+            system_tokens + user_tokens + estimate_tokens(references) + estimate_tokens(examples)
+                > budget.context_window_tokens
+        };
+
+        for keep_share in [true, false] {
+            for section in PromptSection::TRIM_ORDER {
+                let share = budget.tokens_for(section);
+                match section {
+                    PromptSection::Examples => {
+                        while example_count > 0
+                            && over_window(&references, &examples)
+                            && (!keep_share || estimate_tokens(&examples) > share)
+                        {
+                            example_count -= 1;
+                            examples = render_examples(example_count, tick_resolution);
+                        }
+                    }
+                    PromptSection::References => {
+                        while event_limit > 0
+                            && over_window(&references, &examples)
+                            && (!keep_share || estimate_tokens(&references) > share)
+                        {
+                            event_limit /= 2;
+                            references = render_references(&request.references, event_limit);
+                        }
+                    }
+                    PromptSection::System | PromptSection::User => {}
+                }
+            }
+        }
+
+        let allocation = |section, used_tokens, trimmed| PromptAllocation {
+            section,
+            budget_tokens: budget.tokens_for(section),
+            used_tokens,
+            trimmed,
+        };
+        let allocations = vec![
+            allocation(PromptSection::System, system_tokens, false),
+            allocation(
+                PromptSection::References,
+                estimate_tokens(&references),
+                event_limit < max_reference_events,
+            ),
+            allocation(
+                PromptSection::Examples,
+                estimate_tokens(&examples),
+                example_count < FEW_SHOT_EXAMPLES.len(),
+            ),
+            allocation(PromptSection::User, user_tokens, false),
+        ];
+
+        BuiltPrompt {
+            system: SYSTEM_PROMPT.to_string(),
+            user: render_user_prompt(request, &references, &examples),
+            allocations,
+        }
+    }
 
+    /// Asks for a short plain-language description of a generated candidate's musical content.
+    pub fn build_candidate_explanation(
+        mode: Option<GenerationMode>,
+        candidate: &GenerationCandidate,
+    ) -> BuiltPrompt {
         let user = format!(
+            "{EXPLANATION_INSTRUCTION}
+
+Generation mode: {mode}
+Title: {title}
+Bars: {bars}
+Ticks per beat: {ticks_per_beat}
+Notes:
+{notes}",
+            mode = mode.map_or("unspecified", mode_name),
+            title = candidate.title.as_deref().unwrap_or("untitled"),
+            bars = candidate.bars,
+            ticks_per_beat = candidate.tick_resolution.ticks_per_beat(),
+            notes = render_note_rows(&candidate.notes),
+        );
+
+        BuiltPrompt {
+            system: EXPLANATION_SYSTEM_PROMPT.to_string(),
+            user,
+            allocations: Vec::new(),
+        }
+    }
+}
+
+/// Rough token count for budgeting; about four characters per token for English and JSON.
+fn estimate_tokens(text: &str) -> u32 {
+    u32::try_from(text.len().div_ceil(4)).unwrap_or(u32::MAX)
+}
+
+fn render_user_prompt(request: &GenerationRequest, references: &str, examples: &str) -> String {
+    let mode = mode_name(request.mode);
+    let mode_template = mode_template(request.mode);
+    let user_prompt = request.prompt.trim();
+
+    format!(
             "Compose a MIDI generation response for Sonant.
 
 Generation mode: {mode}
@@ -40,13 +246,27 @@ Music parameters:
 - scale: {scale}
 - density: {density}
 - complexity: {complexity}
+- syncopation: {syncopation} (1 = on the beat, 5 = heavily off-beat)
+- ticks per beat (PPQ): {ticks_per_beat} (write every start_tick and duration_tick at this resolution; one 4/4 bar is {ticks_per_bar} ticks)
+
+Project context from the DAW session (fit the existing arrangement):
+{project_context}
 
 Reference MIDI summaries and event sequences:
 {references}
 
+Locked notes (hard constraints: every candidate must contain each note with the same pitch, start_tick, and channel):
+{locked_notes}
+
+Advanced constraints (hard constraints: every candidate must satisfy each rule):
+{constraints}
+
 JSON output contract (must follow exactly):
 {json_contract}
 
+Example responses (shape only; never copy their ids, model fields, or notes):
+{examples}
+
 Required fixed fields in your JSON output:
 - request_id must equal \"{request_id}\"
 - model.provider must equal \"{provider}\"
@@ -60,19 +280,68 @@ GenerationResult JSON schema:
             scale = request.params.scale,
             density = request.params.density,
             complexity = request.params.complexity,
+            syncopation = request.params.syncopation,
+            ticks_per_beat = request.params.tick_resolution.ticks_per_beat(),
+            ticks_per_bar =
+                u32::from(request.params.tick_resolution.ticks_per_beat()) * BEATS_PER_BAR,
+            project_context = render_daw_context(request.daw_context.as_ref()),
+            locked_notes = render_note_rows(&request.locked_notes),
+            constraints = render_constraints(request.constraints.as_ref()),
             json_contract = json_output_contract(),
             request_id = request.request_id,
             provider = request.model.provider,
             model = request.model.model,
             variation_count = request.variation_count,
             schema = GENERATION_RESULT_JSON_SCHEMA,
-        );
+        )
+}
 
-        BuiltPrompt {
-            system: SYSTEM_PROMPT.to_string(),
-            user,
+fn render_examples(count: usize, tick_resolution: TickResolution) -> String {
+    if count == 0 {
+        return "- none".to_string();
+    }
+
+    FEW_SHOT_EXAMPLES
+        .iter()
+        .take(count)
+        .map(|(mode, example)| {
+            format!(
+                "- {mode}: {}",
+                rescale_example_ticks(example, tick_resolution)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Moves an example's note ticks to `tick_resolution` so the examples never contradict the
+/// PPQ the prompt asks for.
+fn rescale_example_ticks(example: &str, tick_resolution: TickResolution) -> String {
+    if tick_resolution == TickResolution::DEFAULT {
+        return example.to_string();
+    }
+    let Ok(mut value) = serde_json::from_str::<Value>(example) else {
+        return example.to_string();
+    };
+    let notes = value["candidates"]
+        .as_array_mut()
+        .into_iter()
+        .flatten()
+        .filter_map(|candidate| candidate["notes"].as_array_mut())
+        .flatten();
+    for note in notes {
+        for field in ["start_tick", "duration_tick"] {
+            if let Some(ticks) = note[field]
+                .as_u64()
+                .and_then(|ticks| u32::try_from(ticks).ok())
+            {
+                note[field] = tick_resolution
+                    .convert_ticks(ticks, TickResolution::DEFAULT)
+                    .into();
+            }
         }
     }
+    value.to_string()
 }
 
 fn mode_name(mode: GenerationMode) -> &'static str {
@@ -117,7 +386,106 @@ fn json_output_contract() -> &'static str {
     "Return exactly one JSON object and nothing else. Do not output markdown fences, prose, comments, or trailing text."
 }
 
-fn render_references(references: &[MidiReferenceSummary]) -> String {
+fn render_note_rows(notes: &[GeneratedNote]) -> String {
+    if notes.is_empty() {
+        return "- none".to_string();
+    }
+
+    notes
+        .iter()
+        .map(|note| {
+            format!(
+                "- pitch={} start_tick={} duration_tick={} velocity={} channel={}",
+                note.pitch, note.start_tick, note.duration_tick, note.velocity, note.channel
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn render_constraints(constraints: Option<&GenerationConstraints>) -> String {
+    let Some(constraints) = constraints.filter(|constraints| !constraints.is_empty()) else {
+        return "- none".to_string();
+    };
+
+    let mut lines = Vec::new();
+    if let Some(range) = constraints.range {
+        lines.push(format!(
+            "- pitch range: {} (MIDI {} to {} inclusive)",
+            range.label(),
+            range.low,
+            range.high
+        ));
+    }
+    if let Some(rhythm) = constraints.rhythm {
+        lines.push(format!(
+            "- rhythm grid: {} ({} steps per beat); start every note on this grid",
+            rhythm.description(),
+            rhythm.steps_per_beat()
+        ));
+    }
+    if !constraints.avoid.is_empty() {
+        let intervals = constraints
+            .avoid
+            .iter()
+            .map(|interval| format!("{} ({} semitones)", interval.name, interval.semitones))
+            .collect::<Vec<_>>();
+        lines.push(format!(
+            "- avoid intervals between sounding notes and against the references: {}",
+            intervals.join(", ")
+        ));
+    }
+    lines.join("\n")
+}
+
+fn render_daw_context(context: Option<&DawContext>) -> String {
+    let Some(context) = context else {
+        return "- none".to_string();
+    };
+
+    let mut lines = Vec::new();
+    if let Some(tempo) = context.host_tempo_bpm {
+        lines.push(format!("- host tempo: {tempo} bpm"));
+    }
+    if let Some(signature) = context.time_signature {
+        lines.push(format!(
+            "- time signature: {}/{}",
+            signature.numerator, signature.denominator
+        ));
+    }
+    if let Some(key) = context.key.as_deref() {
+        lines.push(format!("- key: {key}"));
+    }
+    if let Some(loop_length) = context.loop_length_beats {
+        lines.push(format!("- loop length: {loop_length} beats"));
+    }
+    if let Some(name) = context.host_track_name.as_deref() {
+        lines.push(format!(
+            "- host track/clip: \"{name}\" (may name the current song section)"
+        ));
+    }
+    if !context.track_roles.is_empty() {
+        lines.push("- existing tracks:".to_string());
+        for role in &context.track_roles {
+            let source = match (role.source, role.channel) {
+                (ReferenceSource::Live, Some(channel)) => {
+                    format!("live input on channel {channel}")
+                }
+                (ReferenceSource::Live, None) => "live input".to_string(),
+                (ReferenceSource::File, _) => "MIDI file".to_string(),
+            };
+            lines.push(format!("  - {}: {source}", reference_slot_name(role.slot)));
+        }
+    }
+
+    if lines.is_empty() {
+        return "- none".to_string();
+    }
+    lines.join("\n")
+}
+
+/// Renders up to `event_limit` event rows per reference, noting how many were left out.
+fn render_references(references: &[MidiReferenceSummary], event_limit: usize) -> String {
     if references.is_empty() {
         return "- none".to_string();
     }
@@ -159,13 +527,34 @@ fn render_references(references: &[MidiReferenceSummary]) -> String {
             reference.min_pitch, reference.max_pitch
         )
         .expect("failed to write reference pitch_range to String");
+        if let Some(transposition) = &reference.transposition {
+            writeln!(
+                rendered,
+                "  transposed: {:+} semitones from {} to {}; the events and pitch_range are \
+                 already in the new key",
+                transposition.semitones, transposition.from_key, transposition.to_key
+            )
+            .expect("failed to write reference transposition to String");
+        }
+        if let Some(tempo) = reference.tempo {
+            writeln!(
+                rendered,
+                "  tempo: written at {:.1} BPM, read at {} BPM ({:.2}x speed); bars and ticks are \
+                 musical time, so the bar count holds at the target tempo, and the tempo \
+                 events show the tempo as written",
+                tempo.source_bpm,
+                tempo.target_bpm,
+                tempo.speed_ratio()
+            )
+            .expect("failed to write reference tempo to String");
+        }
 
         if reference.events.is_empty() {
             writeln!(rendered, "  events: []")
                 .expect("failed to write empty events list to String");
         } else {
             writeln!(rendered, "  events:").expect("failed to write events header to String");
-            for event in &reference.events {
+            for event in reference.events.iter().take(event_limit) {
                 writeln!(
                     rendered,
                     "    - track={} abs_tick={} delta_tick={} event={}",
@@ -173,6 +562,14 @@ fn render_references(references: &[MidiReferenceSummary]) -> String {
                 )
                 .expect("failed to write reference event to String");
             }
+            let omitted = reference.events.len().saturating_sub(event_limit);
+            if omitted > 0 {
+                writeln!(
+                    rendered,
+                    "    - ({omitted} more event(s) omitted to fit the prompt budget)"
+                )
+                .expect("failed to write omitted event count to String");
+            }
         }
     }
 
@@ -200,10 +597,12 @@ fn reference_source_name(source: ReferenceSource) -> &'static str {
 
 #[cfg(test)]
 mod tests {
-    use super::PromptBuilder;
+    use super::{BuiltPrompt, PromptBudget, PromptBuilder, PromptSection};
     use crate::domain::{
-        FileReferenceInput, GenerationMode, GenerationParams, GenerationRequest,
-        MidiReferenceEvent, MidiReferenceSummary, ModelRef, ReferenceSlot, ReferenceSource,
+        DawContext, DawTrackRole, FileReferenceInput, GENERATION_CONTRACT_VERSION, GeneratedNote,
+        GenerationCandidate, GenerationConstraints, GenerationMode, GenerationParams,
+        GenerationRequest, MidiReferenceEvent, MidiReferenceSummary, ModelRef, ReferenceSlot,
+        ReferenceSource, ReferenceTempo, ReferenceTransposition, TickResolution, TimeSignature,
     };
     use crate::infra::llm::schema_validator::GENERATION_RESULT_JSON_SCHEMA;
 
@@ -222,12 +621,20 @@ mod tests {
                 scale: "minor".to_string(),
                 density: 4,
                 complexity: 3,
+                syncopation: 3,
+                tick_resolution: TickResolution::DEFAULT,
                 temperature: Some(0.5),
                 top_p: Some(0.9),
                 max_tokens: Some(512),
             },
             references: Vec::new(),
             variation_count: 2,
+            locked_notes: Vec::new(),
+            contract_version: GENERATION_CONTRACT_VERSION,
+            daw_context: None,
+            constraints: None,
+            validation: None,
+            anonymize_references: false,
         }
     }
 
@@ -249,6 +656,8 @@ mod tests {
                 delta_tick: 0,
                 event: "NoteOn channel=0 key=60 vel=96".to_string(),
             }],
+            transposition: None,
+            tempo: None,
         }
     }
 
@@ -269,6 +678,8 @@ mod tests {
                 event: "LiveMidi channel=2 status=0x91 data1=55 data2=100 port=1 time=120"
                     .to_string(),
             }],
+            transposition: None,
+            tempo: None,
         }
     }
 
@@ -315,6 +726,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn prompt_states_the_request_ppq_and_rescales_the_examples_to_it() {
+        let mut request = request_with_mode(GenerationMode::Melody);
+        request.params.tick_resolution = TickResolution::new(96).expect("96 PPQ should be valid");
+
+        let prompt = PromptBuilder::build(&request);
+
+        assert!(prompt.user.contains(
+            "- ticks per beat (PPQ): 96 (write every start_tick and duration_tick at this resolution; one 4/4 bar is 384 ticks)"
+        ));
+        assert!(prompt.user.contains(r#""start_tick":192"#));
+        assert!(!prompt.user.contains(r#""start_tick":960"#));
+        assert!(!prompt.user.contains(r#""duration_tick":480"#));
+    }
+
     #[test]
     fn prompt_includes_params_and_json_output_constraints() {
         let prompt = PromptBuilder::build(&request_with_mode(GenerationMode::Melody));
@@ -328,6 +754,8 @@ mod tests {
         assert!(prompt.user.contains("- scale: minor"));
         assert!(prompt.user.contains("- density: 4"));
         assert!(prompt.user.contains("- complexity: 3"));
+        assert!(prompt.user.contains("- syncopation: 3"));
+        assert!(prompt.user.contains("- ticks per beat (PPQ): 480"));
         assert!(prompt.user.contains("request_id must equal \"req-42\""));
         assert!(
             prompt
@@ -352,6 +780,126 @@ mod tests {
         assert!(prompt.user.contains(GENERATION_RESULT_JSON_SCHEMA.trim()));
     }
 
+    #[test]
+    fn prompt_renders_locked_notes_as_hard_constraints() {
+        let mut request = request_with_mode(GenerationMode::Melody);
+        assert!(PromptBuilder::build(&request).user.contains(
+            "Locked notes (hard constraints: every candidate must contain each note with the same pitch, start_tick, and channel):\n- none"
+        ));
+
+        request.locked_notes = vec![GeneratedNote {
+            pitch: 64,
+            start_tick: 960,
+            duration_tick: 240,
+            velocity: 100,
+            channel: 1,
+        }];
+        let prompt = PromptBuilder::build(&request);
+
+        assert!(
+            prompt
+                .user
+                .contains("- pitch=64 start_tick=960 duration_tick=240 velocity=100 channel=1")
+        );
+    }
+
+    #[test]
+    fn prompt_notes_when_a_reference_was_transposed() {
+        let mut request = request_with_mode(GenerationMode::Melody);
+        request.references = vec![file_reference()];
+        assert!(!PromptBuilder::build(&request).user.contains("transposed:"));
+
+        request.references[0].transposition = Some(ReferenceTransposition {
+            from_key: "E minor".to_string(),
+            to_key: "D minor".to_string(),
+            semitones: -2,
+        });
+        let prompt = PromptBuilder::build(&request);
+
+        assert!(prompt.user.contains(
+            "  transposed: -2 semitones from E minor to D minor; the events and pitch_range are already in the new key"
+        ));
+    }
+
+    #[test]
+    fn prompt_notes_when_a_reference_was_written_at_another_tempo() {
+        let mut request = request_with_mode(GenerationMode::Melody);
+        request.references = vec![file_reference()];
+        assert!(!PromptBuilder::build(&request).user.contains("  tempo:"));
+
+        request.references[0].tempo = Some(ReferenceTempo {
+            source_bpm: 90.0,
+            target_bpm: 120,
+        });
+        let prompt = PromptBuilder::build(&request);
+
+        assert!(prompt.user.contains(
+            "  tempo: written at 90.0 BPM, read at 120 BPM (1.33x speed); bars and ticks are musical time, so the bar count holds at the target tempo"
+        ));
+    }
+
+    #[test]
+    fn prompt_renders_advanced_constraints_block() {
+        let mut request = request_with_mode(GenerationMode::Bassline);
+        assert!(PromptBuilder::build(&request).user.contains(
+            "Advanced constraints (hard constraints: every candidate must satisfy each rule):\n- none"
+        ));
+
+        request.constraints = Some(
+            GenerationConstraints::parse("range: C2..C4; rhythm: 16th; avoid: b9")
+                .expect("constraints should parse"),
+        );
+        let prompt = PromptBuilder::build(&request);
+
+        assert!(
+            prompt
+                .user
+                .contains("- pitch range: C2..C4 (MIDI 36 to 60 inclusive)")
+        );
+        assert!(
+            prompt
+                .user
+                .contains("- rhythm grid: sixteenth notes (4 steps per beat)")
+        );
+        assert!(prompt.user.contains("b9 (13 semitones)"));
+    }
+
+    #[test]
+    fn prompt_renders_daw_project_context() {
+        let mut request = request_with_mode(GenerationMode::Bassline);
+        assert!(PromptBuilder::build(&request).user.contains(
+            "Project context from the DAW session (fit the existing arrangement):\n- none"
+        ));
+
+        request.daw_context = Some(DawContext {
+            host_tempo_bpm: Some(92.5),
+            time_signature: Some(TimeSignature {
+                numerator: 6,
+                denominator: 8,
+            }),
+            key: Some("A minor".to_string()),
+            loop_length_beats: Some(12.0),
+            host_track_name: Some("Chorus 2".to_string()),
+            track_roles: vec![
+                DawTrackRole {
+                    slot: ReferenceSlot::DrumPattern,
+                    source: ReferenceSource::Live,
+                    channel: Some(10),
+                },
+                DawTrackRole {
+                    slot: ReferenceSlot::Melody,
+                    source: ReferenceSource::File,
+                    channel: None,
+                },
+            ],
+        });
+        let prompt = PromptBuilder::build(&request);
+
+        assert!(prompt.user.contains(
+            "- host tempo: 92.5 bpm\n- time signature: 6/8\n- key: A minor\n- loop length: 12 beats\n- host track/clip: \"Chorus 2\" (may name the current song section)\n- existing tracks:\n  - drum_pattern: live input on channel 10\n  - melody: MIDI file"
+        ));
+    }
+
     #[test]
     fn prompt_includes_reference_summary_and_event_rows() {
         let mut request = request_with_mode(GenerationMode::CounterMelody);
@@ -410,6 +958,57 @@ mod tests {
         assert!(prompt.user.contains("file_path: n/a"));
     }
 
+    #[test]
+    fn budget_trims_examples_before_reference_events() {
+        let mut request = request_with_mode(GenerationMode::Melody);
+        let mut reference = file_reference();
+        reference.events = reference.events.repeat(40);
+        request.references = vec![reference];
+        let tokens = |prompt: &BuiltPrompt, section: PromptSection| {
+            prompt
+                .allocations
+                .iter()
+                .find(|allocation| allocation.section == section)
+                .copied()
+                .expect("every section should be reported")
+        };
+
+        let roomy =
+            PromptBuilder::build_with_budget(&request, &PromptBudget::with_context_window(1 << 20));
+        assert!(
+            roomy
+                .allocations
+                .iter()
+                .all(|allocation| !allocation.trimmed)
+        );
+        assert!(roomy.user.contains("- drum_pattern: {"));
+        let fixed_tokens = tokens(&roomy, PromptSection::System).used_tokens
+            + tokens(&roomy, PromptSection::User).used_tokens;
+        let reference_tokens = tokens(&roomy, PromptSection::References).used_tokens;
+        let example_tokens = tokens(&roomy, PromptSection::Examples).used_tokens;
+
+        let budget =
+            PromptBudget::with_context_window(fixed_tokens + reference_tokens + example_tokens / 2);
+        let examples_only = PromptBuilder::build_with_budget(&request, &budget);
+        assert!(tokens(&examples_only, PromptSection::Examples).trimmed);
+        assert!(!tokens(&examples_only, PromptSection::References).trimmed);
+        assert!(!examples_only.user.contains("- drum_pattern: {"));
+
+        let budget = PromptBudget::with_context_window(fixed_tokens + 150);
+        let tight = PromptBuilder::build_with_budget(&request, &budget);
+        let references = tokens(&tight, PromptSection::References);
+        assert!(references.trimmed);
+        assert!(references.used_tokens < reference_tokens);
+        assert!(tight.user.contains("omitted to fit the prompt budget"));
+        assert!(tight.user.contains("note_count: 24"));
+        let total = tight
+            .allocations
+            .iter()
+            .map(|allocation| allocation.used_tokens)
+            .sum::<u32>();
+        assert!(total <= budget.context_window_tokens);
+    }
+
     #[test]
     fn prompt_marks_missing_references_explicitly() {
         let prompt = PromptBuilder::build(&request_with_mode(GenerationMode::Melody));
@@ -419,4 +1018,37 @@ mod tests {
                 .contains("Reference MIDI summaries and event sequences:\n- none")
         );
     }
+
+    #[test]
+    fn candidate_explanation_prompt_lists_notes_without_json_contract() {
+        let candidate = GenerationCandidate {
+            id: "cand-1".to_string(),
+            bars: 1,
+            notes: vec![GeneratedNote {
+                pitch: 62,
+                start_tick: 480,
+                duration_tick: 240,
+                velocity: 90,
+                channel: 1,
+            }],
+            tick_resolution: TickResolution::DEFAULT,
+            score_hint: None,
+            title: Some("Stepwise lift".to_string()),
+            control_events: Vec::new(),
+            processing: None,
+        };
+
+        let prompt =
+            PromptBuilder::build_candidate_explanation(Some(GenerationMode::Bassline), &candidate);
+
+        assert!(prompt.system.contains("without markdown or JSON"));
+        assert!(prompt.user.contains("Generation mode: bassline"));
+        assert!(prompt.user.contains("Title: Stepwise lift"));
+        assert!(
+            prompt
+                .user
+                .contains("- pitch=62 start_tick=480 duration_tick=240 velocity=90 channel=1")
+        );
+        assert!(!prompt.user.contains(GENERATION_RESULT_JSON_SCHEMA));
+    }
 }
@@ -1,9 +1,24 @@
 use crate::domain::{GenerationRequest, GenerationResult, LlmError};
 
+use super::BuiltPrompt;
+
 pub trait LlmProvider: Send + Sync {
     fn provider_id(&self) -> &str;
 
     fn supports_model(&self, model_id: &str) -> bool;
 
     fn generate(&self, request: &GenerationRequest) -> Result<GenerationResult, LlmError>;
+
+    /// Free-text completion for `prompt`, used for explanations rather than generation.
+    fn describe(&self, _model_id: &str, _prompt: &BuiltPrompt) -> Result<String, LlmError> {
+        Err(LlmError::validation(format!(
+            "provider '{}' cannot describe candidates",
+            self.provider_id()
+        )))
+    }
+
+    /// Whether requests leave this machine; safe mode only filters requests that do.
+    fn is_cloud(&self) -> bool {
+        true
+    }
 }
@@ -78,8 +78,9 @@ impl ProviderRegistry {
 mod tests {
     use super::ProviderRegistry;
     use crate::domain::{
-        GeneratedNote, GenerationCandidate, GenerationMetadata, GenerationMode, GenerationParams,
-        GenerationRequest, GenerationResult, LlmError, ModelRef,
+        GENERATION_CONTRACT_VERSION, GeneratedNote, GenerationCandidate, GenerationMetadata,
+        GenerationMode, GenerationParams, GenerationRequest, GenerationResult, LlmError, ModelRef,
+        TickResolution,
     };
     use crate::infra::llm::LlmProvider;
 
@@ -111,9 +112,14 @@ mod tests {
                         velocity: 100,
                         channel: 1,
                     }],
+                    tick_resolution: TickResolution::DEFAULT,
                     score_hint: Some(0.9),
+                    title: None,
+                    control_events: Vec::new(),
+                    processing: None,
                 }],
                 metadata: GenerationMetadata::default(),
+                contract_version: GENERATION_CONTRACT_VERSION,
             })
         }
     }
@@ -133,12 +139,20 @@ mod tests {
                 scale: "major".to_string(),
                 density: 3,
                 complexity: 3,
+                syncopation: 3,
+                tick_resolution: TickResolution::DEFAULT,
                 temperature: Some(0.7),
                 top_p: Some(0.9),
                 max_tokens: Some(512),
             },
             references: Vec::new(),
             variation_count: 1,
+            locked_notes: Vec::new(),
+            contract_version: GENERATION_CONTRACT_VERSION,
+            daw_context: None,
+            constraints: None,
+            validation: None,
+            anonymize_references: false,
         }
     }
 
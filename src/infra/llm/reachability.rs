@@ -0,0 +1,93 @@
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use reqwest::Url;
+use reqwest::blocking::Client;
+
+use crate::domain::LlmError;
+
+/// Sends an unauthenticated `GET` to `base_url` and returns the round-trip time. Any HTTP
+/// response counts as reachable; only connection, TLS and timeout failures are errors.
+pub fn probe_endpoint(base_url: &str, timeout: Duration) -> Result<Duration, LlmError> {
+    let base_url = base_url.trim();
+    if base_url.is_empty() {
+        return Err(LlmError::validation("API base URL must not be empty"));
+    }
+    let client = Client::builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|error| LlmError::internal(format!("failed to create HTTP client: {error}")))?;
+
+    let started = Instant::now();
+    client.get(base_url).send().map_err(|error| {
+        if error.is_timeout() {
+            LlmError::Timeout
+        } else {
+            LlmError::Transport {
+                message: format!("{base_url} is unreachable: {error}"),
+            }
+        }
+    })?;
+    Ok(started.elapsed())
+}
+
+/// Whether `base_url` points at this machine, so requests sent to it never leave the host.
+pub(crate) fn is_loopback_url(base_url: &str) -> bool {
+    let Some(host) = Url::parse(base_url.trim())
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+    else {
+        return false;
+    };
+    if host.eq_ignore_ascii_case("localhost") {
+        return true;
+    }
+    host.trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+        .is_ok_and(|address| address.is_loopback())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_loopback_url, probe_endpoint};
+    use crate::domain::LlmError;
+    use std::time::Duration;
+
+    #[test]
+    fn any_http_response_counts_as_reachable() {
+        let mut server = mockito::Server::new();
+        let mock = server.mock("GET", "/").with_status(404).create();
+
+        assert!(probe_endpoint(&server.url(), Duration::from_secs(5)).is_ok());
+        mock.assert();
+    }
+
+    #[test]
+    fn loopback_urls_are_recognized() {
+        assert!(is_loopback_url("http://localhost:11434/v1"));
+        assert!(is_loopback_url("http://127.0.0.1:8080"));
+        assert!(is_loopback_url("http://[::1]:8080/v1"));
+        assert!(!is_loopback_url("https://api.openai.com/v1"));
+        assert!(!is_loopback_url("http://192.168.1.20:8080"));
+        assert!(!is_loopback_url("not a url"));
+    }
+
+    #[test]
+    fn refused_connections_and_blank_urls_are_errors() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("port should bind");
+        let address = listener
+            .local_addr()
+            .expect("listener should have an address");
+        drop(listener);
+
+        assert!(matches!(
+            probe_endpoint(&format!("http://{address}"), Duration::from_secs(5)),
+            Err(LlmError::Transport { .. })
+        ));
+        assert!(matches!(
+            probe_endpoint("  ", Duration::from_secs(5)),
+            Err(LlmError::Validation { .. })
+        ));
+    }
+}
@@ -0,0 +1,292 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use reqwest::StatusCode;
+use reqwest::blocking::{Client, Response};
+use serde::Deserialize;
+
+use crate::domain::{GenerationRequest, GenerationResult, LlmError};
+
+use super::LlmProvider;
+use super::audit_log::{AuditExchange, AuditLog, record_best_effort};
+use super::env::{read_env_var, read_timeout_from_env};
+use super::reachability::is_loopback_url;
+use super::response_parsing::{parse_retry_after, truncate_message};
+
+pub const REMOTE_SERVER_PROVIDER_ID: &str = "sonant_server";
+const HTTP_TIMEOUT: Duration = Duration::from_secs(8);
+// Jobs queue behind other workstations on the shared server, so the deadline is generous.
+const DEFAULT_JOB_TIMEOUT: Duration = Duration::from_secs(120);
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+const ENV_URL: &str = "SONANT_REMOTE_SERVER_URL";
+const ENV_TOKEN: &str = "SONANT_REMOTE_SERVER_TOKEN";
+const ENV_TIMEOUT_SECS: &str = "SONANT_REMOTE_SERVER_TIMEOUT_SECS";
+const ENV_MODEL: &str = "SONANT_REMOTE_SERVER_MODEL";
+const DEFAULT_MODEL: &str = "default";
+
+/// Client for a shared Sonant inference server.
+///
+/// Protocol, all requests authenticated with `Authorization: Bearer <token>`:
+/// - `POST {url}/v1/generations` with a [`GenerationRequest`] body answers `{"job_id": "..."}`.
+/// - `GET {url}/v1/generations/{job_id}` answers `{"status": "queued" | "running"}`,
+///   `{"status": "completed", "result": <GenerationResult>}`, or
+///   `{"status": "failed", "error": "..."}`.
+pub struct RemoteServerProvider {
+    server_url: String,
+    token: String,
+    client: Client,
+    job_timeout: Duration,
+    poll_interval: Duration,
+    default_model: String,
+    audit_log: Option<Arc<AuditLog>>,
+}
+
+impl RemoteServerProvider {
+    pub fn from_env() -> Result<Self, LlmError> {
+        let server_url = read_env_var(ENV_URL)?.ok_or_else(|| {
+            LlmError::validation(
+                "Remote Sonant server URL is missing (set SONANT_REMOTE_SERVER_URL)",
+            )
+        })?;
+        let token = read_env_var(ENV_TOKEN)?.ok_or_else(|| {
+            LlmError::validation(
+                "Remote Sonant server token is missing (set SONANT_REMOTE_SERVER_TOKEN)",
+            )
+        })?;
+        let job_timeout = read_timeout_from_env(ENV_TIMEOUT_SECS)?.unwrap_or(DEFAULT_JOB_TIMEOUT);
+        let provider = Self::with_config(server_url, token, job_timeout)?;
+        Ok(match read_env_var(ENV_MODEL)? {
+            Some(model) if !model.trim().is_empty() => provider.with_default_model(model.trim()),
+            _ => provider,
+        })
+    }
+
+    pub fn with_config(
+        server_url: impl Into<String>,
+        token: impl Into<String>,
+        job_timeout: Duration,
+    ) -> Result<Self, LlmError> {
+        let server_url = server_url.into();
+        if server_url.trim().is_empty() {
+            return Err(LlmError::validation(
+                "Remote Sonant server URL must not be empty",
+            ));
+        }
+        let token = token.into();
+        if token.trim().is_empty() {
+            return Err(LlmError::validation(
+                "Remote Sonant server token must not be empty",
+            ));
+        }
+        let client = Client::builder()
+            .timeout(HTTP_TIMEOUT.min(job_timeout))
+            .build()
+            .map_err(|error| {
+                LlmError::internal(format!(
+                    "failed to create remote server HTTP client: {error}"
+                ))
+            })?;
+
+        Ok(Self {
+            server_url: server_url.trim().trim_end_matches('/').to_string(),
+            token: token.trim().to_string(),
+            client,
+            job_timeout,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            default_model: DEFAULT_MODEL.to_string(),
+            audit_log: None,
+        })
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    pub fn with_default_model(mut self, model: impl Into<String>) -> Self {
+        self.default_model = model.into();
+        self
+    }
+
+    pub fn with_audit_log(mut self, audit_log: Arc<AuditLog>) -> Self {
+        self.audit_log = Some(audit_log);
+        self
+    }
+
+    /// Model requested when the helper starts with this provider selected.
+    pub fn default_model(&self) -> &str {
+        &self.default_model
+    }
+
+    pub fn api_base_url(&self) -> &str {
+        &self.server_url
+    }
+
+    fn generations_url(&self) -> String {
+        format!("{}/v1/generations", self.server_url)
+    }
+
+    fn submit(&self, request: &GenerationRequest) -> Result<String, LlmError> {
+        let response = self
+            .client
+            .post(self.generations_url())
+            .bearer_auth(&self.token)
+            .json(request)
+            .send()
+            .map_err(map_transport_error)?;
+        let (submitted, _): (SubmitResponse, _) = read_json(response, &request.model.model)?;
+        if submitted.job_id.trim().is_empty() {
+            return Err(LlmError::invalid_response(
+                "remote server returned an empty job_id",
+            ));
+        }
+        Ok(submitted.job_id)
+    }
+
+    /// The job status together with the raw body it was decoded from.
+    fn poll(&self, job_id: &str, model: &str) -> Result<(JobStatus, String), LlmError> {
+        let response = self
+            .client
+            .get(format!("{}/{job_id}", self.generations_url()))
+            .bearer_auth(&self.token)
+            .send()
+            .map_err(map_transport_error)?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(LlmError::invalid_response(format!(
+                "remote server no longer knows job '{job_id}'"
+            )));
+        }
+        read_json(response, model)
+    }
+
+    // Records the submitted request against the job's final status body.
+    fn record_exchange(
+        &self,
+        request: &GenerationRequest,
+        job_id: &str,
+        started: Instant,
+        body: &str,
+    ) {
+        let latency_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+        record_best_effort(self.audit_log.as_deref(), &self.token, || {
+            AuditExchange::new(
+                REMOTE_SERVER_PROVIDER_ID,
+                &request.model.model,
+                &request.request_id,
+                format!("{}/{job_id}", self.generations_url()),
+                serde_json::to_value(request).unwrap_or_default(),
+            )
+            .with_response(StatusCode::OK.as_u16(), latency_ms, body)
+        });
+    }
+}
+
+impl LlmProvider for RemoteServerProvider {
+    fn provider_id(&self) -> &str {
+        REMOTE_SERVER_PROVIDER_ID
+    }
+
+    // The server decides which of its models it serves and answers unknown ones with 404.
+    fn supports_model(&self, model_id: &str) -> bool {
+        !model_id.trim().is_empty()
+    }
+
+    fn is_cloud(&self) -> bool {
+        !is_loopback_url(&self.server_url)
+    }
+
+    fn generate(&self, request: &GenerationRequest) -> Result<GenerationResult, LlmError> {
+        let started = Instant::now();
+        let deadline = started + self.job_timeout;
+        let job_id = self.submit(request)?;
+
+        loop {
+            let (status, body) = self.poll(&job_id, &request.model.model)?;
+            if matches!(
+                status,
+                JobStatus::Completed { .. } | JobStatus::Failed { .. }
+            ) {
+                self.record_exchange(request, &job_id, started, &body);
+            }
+            match status {
+                JobStatus::Queued | JobStatus::Running => {}
+                JobStatus::Completed { mut result } => {
+                    result.upgrade_contract();
+                    result.set_tick_resolution(request.params.tick_resolution);
+                    result.validate()?;
+                    return Ok(result);
+                }
+                JobStatus::Failed { error } => {
+                    return Err(LlmError::Transport {
+                        message: format!("remote server job failed: {}", truncate_message(&error)),
+                    });
+                }
+            }
+            if Instant::now() + self.poll_interval >= deadline {
+                return Err(LlmError::Timeout);
+            }
+            std::thread::sleep(self.poll_interval);
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SubmitResponse {
+    job_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum JobStatus {
+    Queued,
+    Running,
+    Completed { result: GenerationResult },
+    Failed { error: String },
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(
+    response: Response,
+    model: &str,
+) -> Result<(T, String), LlmError> {
+    let status = response.status();
+    let retry_after = parse_retry_after(response.headers());
+    let body = response.text().map_err(map_transport_error)?;
+    if !status.is_success() {
+        return Err(map_http_error(status, retry_after, &body, model));
+    }
+    let decoded = serde_json::from_str(&body).map_err(|error| {
+        LlmError::invalid_response(format!("remote server response is not valid: {error}"))
+    })?;
+    Ok((decoded, body))
+}
+
+fn map_http_error(
+    status: StatusCode,
+    retry_after: Option<Duration>,
+    body: &str,
+    model: &str,
+) -> LlmError {
+    match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => LlmError::Auth,
+        StatusCode::TOO_MANY_REQUESTS => LlmError::rate_limited(retry_after),
+        StatusCode::NOT_FOUND => LlmError::ModelNotFound {
+            model: model.to_string(),
+        },
+        StatusCode::REQUEST_TIMEOUT | StatusCode::GATEWAY_TIMEOUT => LlmError::Timeout,
+        _ => LlmError::Transport {
+            message: format!(
+                "remote server returned HTTP {status}: {}",
+                truncate_message(body)
+            ),
+        },
+    }
+}
+
+fn map_transport_error(error: reqwest::Error) -> LlmError {
+    if error.is_timeout() {
+        return LlmError::Timeout;
+    }
+    LlmError::Transport {
+        message: format!("remote server transport error: {error}"),
+    }
+}
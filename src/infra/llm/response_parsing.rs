@@ -1,16 +1,187 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+
 const MAX_ERROR_MESSAGE_LEN: usize = 256;
 
+const RETRY_AFTER_MS: &str = "retry-after-ms";
+const RATE_LIMIT_RESET_HEADERS: [&str; 2] =
+    ["x-ratelimit-reset-requests", "x-ratelimit-reset-tokens"];
+
+/// Reads how long the provider asks clients to wait: `retry-after-ms`, then `Retry-After` in
+/// (possibly fractional) seconds, then the longest OpenAI-style `x-ratelimit-reset-*` duration
+/// such as `6m0s`. HTTP-date values are ignored; the retry policy's own backoff applies then.
+pub(crate) fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    if let Some(millis) =
+        header_text(headers, RETRY_AFTER_MS).and_then(|value| value.parse::<f64>().ok())
+        && millis.is_finite()
+        && millis >= 0.0
+    {
+        return Some(Duration::from_secs_f64(millis / 1_000.0));
+    }
+    if let Some(seconds) = header_text(headers, RETRY_AFTER.as_str()) {
+        return seconds
+            .parse::<f64>()
+            .ok()
+            .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+            .map(Duration::from_secs_f64);
+    }
+    RATE_LIMIT_RESET_HEADERS
+        .into_iter()
+        .filter_map(|name| header_text(headers, name).and_then(parse_duration_text))
+        .max()
+}
+
+fn header_text<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name)?.to_str().ok().map(str::trim)
+}
+
+/// Parses Go-style durations like `1s`, `250ms`, `1.5s` or `1h2m3s`.
+fn parse_duration_text(text: &str) -> Option<Duration> {
+    let mut total = 0.0;
+    let mut rest = text.trim();
+    if rest.is_empty() {
+        return None;
+    }
+    while !rest.is_empty() {
+        let number_end = rest
+            .find(|character: char| !(character.is_ascii_digit() || character == '.'))
+            .unwrap_or(rest.len());
+        let value = rest[..number_end].parse::<f64>().ok()?;
+        rest = &rest[number_end..];
+        let unit_end = rest
+            .find(|character: char| !character.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        let seconds_per_unit = match &rest[..unit_end] {
+            "h" => 3_600.0,
+            "m" => 60.0,
+            "s" => 1.0,
+            "ms" => 0.001,
+            _ => return None,
+        };
+        total += value * seconds_per_unit;
+        rest = &rest[unit_end..];
+    }
+    total.is_finite().then(|| Duration::from_secs_f64(total))
+}
+
 pub(crate) fn truncate_message(body: &str) -> String {
     let compact = body.trim().replace('\n', " ");
     compact.chars().take(MAX_ERROR_MESSAGE_LEN).collect()
 }
 
-pub(crate) fn extract_json_payload(text: &str) -> Option<&str> {
+/// Finds the JSON the model meant to return in free-form text. Fenced blocks, prose around the
+/// payload, and trailing commas are tolerated; objects win over arrays so a bracketed aside in
+/// leading prose is not mistaken for the payload. When nothing parses, the best-effort slice is
+/// returned so the schema validator can report why.
+pub(crate) fn extract_json_payload(text: &str) -> Option<Cow<'_, str>> {
     let trimmed = text.trim();
     if trimmed.is_empty() {
         return None;
     }
 
+    find_first_valid_json(trimmed, b'{')
+        .or_else(|| find_first_valid_json(trimmed, b'['))
+        .or_else(|| extract_best_effort_slice(trimmed).map(Cow::Borrowed))
+}
+
+/// Scans the top-level `opener` candidates left to right; brackets nested inside a candidate are
+/// never tried on their own, so a truncated response cannot yield one of its inner note objects.
+/// A candidate that never closes means the output was cut off, and its raw remainder is returned
+/// for the schema validator to reject.
+fn find_first_valid_json(text: &str, opener: u8) -> Option<Cow<'_, str>> {
+    let mut offset = 0;
+    while let Some(position) = text[offset..].bytes().position(|byte| byte == opener) {
+        let start = offset + position;
+        match balanced_json_slice(&text[start..]) {
+            Balance::Closed(candidate) => {
+                if is_valid_json(candidate) {
+                    return Some(Cow::Borrowed(candidate));
+                }
+                let repaired = strip_trailing_commas(candidate);
+                if is_valid_json(&repaired) {
+                    return Some(Cow::Owned(repaired));
+                }
+                offset = start + candidate.len();
+            }
+            Balance::Mismatched(index) => offset = start + index + 1,
+            Balance::Unterminated => return Some(Cow::Borrowed(&text[start..])),
+        }
+    }
+    None
+}
+
+fn is_valid_json(text: &str) -> bool {
+    serde_json::from_str::<serde::de::IgnoredAny>(text).is_ok()
+}
+
+enum Balance<'a> {
+    /// The prefix up to and including the closing bracket.
+    Closed(&'a str),
+    /// Index of a closing bracket that does not match the innermost open one.
+    Mismatched(usize),
+    /// The text ended with brackets still open.
+    Unterminated,
+}
+
+/// Finds the bracket that closes the first character of `text`, skipping brackets inside string
+/// literals.
+fn balanced_json_slice(text: &str) -> Balance<'_> {
+    let mut closers = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    for (index, byte) in text.bytes().enumerate() {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            b'"' => in_string = true,
+            b'{' => closers.push(b'}'),
+            b'[' => closers.push(b']'),
+            b'}' | b']' => {
+                if closers.pop() != Some(byte) {
+                    return Balance::Mismatched(index);
+                }
+                if closers.is_empty() {
+                    return Balance::Closed(&text[..=index]);
+                }
+            }
+            _ => {}
+        }
+    }
+    Balance::Unterminated
+}
+
+fn strip_trailing_commas(json: &str) -> String {
+    let mut repaired = String::with_capacity(json.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for (index, ch) in json.char_indices() {
+        if in_string {
+            match ch {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if ch == '"' {
+            in_string = true;
+        } else if ch == ',' && json[index + 1..].trim_start().starts_with(['}', ']']) {
+            continue;
+        }
+        repaired.push(ch);
+    }
+    repaired
+}
+
+fn extract_best_effort_slice(trimmed: &str) -> Option<&str> {
     if let Some(fenced) = extract_markdown_fenced_block(trimmed) {
         let fenced = fenced.trim();
         if let Some(json) = extract_braced_json_slice(fenced) {
@@ -25,7 +196,7 @@ pub(crate) fn extract_json_payload(text: &str) -> Option<&str> {
 }
 
 fn extract_markdown_fenced_block(text: &str) -> Option<&str> {
-    let stripped = text.strip_prefix("```")?;
+    let stripped = &text[text.find("```")? + 3..];
     let end = stripped.rfind("```")?;
     let content = stripped[..end].trim();
     if content.is_empty() {
@@ -71,7 +242,53 @@ fn extract_braced_json_slice(text: &str) -> Option<&str> {
 
 #[cfg(test)]
 mod tests {
-    use super::{extract_json_payload, truncate_message};
+    use std::borrow::Cow;
+    use std::time::Duration;
+
+    use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+
+    use super::{extract_json_payload, parse_retry_after, truncate_message};
+
+    #[test]
+    fn retry_after_accepts_delay_seconds_only() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(parse_retry_after(&headers), None);
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("1.5"));
+        assert_eq!(
+            parse_retry_after(&headers),
+            Some(Duration::from_millis(1_500))
+        );
+
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(parse_retry_after(&headers), None);
+    }
+
+    #[test]
+    fn retry_after_reads_millisecond_and_rate_limit_reset_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-ratelimit-reset-requests",
+            HeaderValue::from_static("1m30s"),
+        );
+        headers.insert(
+            "x-ratelimit-reset-tokens",
+            HeaderValue::from_static("250ms"),
+        );
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(90)));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("2"));
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(2)));
+
+        headers.insert("retry-after-ms", HeaderValue::from_static("750"));
+        assert_eq!(
+            parse_retry_after(&headers),
+            Some(Duration::from_millis(750))
+        );
+    }
 
     #[test]
     fn extract_json_payload_parses_markdown_fenced_json() {
@@ -105,6 +322,62 @@ mod tests {
         assert_eq!(payload, "{\"request_id\":\"req-1\"}");
     }
 
+    #[test]
+    fn extract_json_payload_skips_prose_and_fences_from_chatty_responses() {
+        // Anthropic models sometimes introduce the fence with a sentence.
+        let anthropic = "Here is the generated pattern:\n\n```json\n{\"request_id\":\"req-1\",\"candidates\":[]}\n```\n\nLet me know if you want changes.";
+        // Local OpenAI-compatible models often echo an aside before the object.
+        let local = "Sure! I used [4] bars as requested. {\"request_id\":\"req-1\",\"note\":\"a } in a string\"} Hope this helps {not json}";
+
+        assert_eq!(
+            extract_json_payload(anthropic).expect("payload should be found"),
+            "{\"request_id\":\"req-1\",\"candidates\":[]}"
+        );
+        assert_eq!(
+            extract_json_payload(local).expect("payload should be found"),
+            "{\"request_id\":\"req-1\",\"note\":\"a } in a string\"}"
+        );
+    }
+
+    #[test]
+    fn extract_json_payload_repairs_trailing_commas() {
+        // OpenAI-compatible servers without JSON mode emit trailing commas in long arrays.
+        let content = "```\n{\"request_id\":\"req-1\",\"candidates\":[{\"id\":\"c1\",\"text\":\"a, ]\",},],}\n```";
+
+        let payload = extract_json_payload(content).expect("payload should be repaired");
+
+        assert!(matches!(payload, Cow::Owned(_)));
+        assert_eq!(
+            payload,
+            "{\"request_id\":\"req-1\",\"candidates\":[{\"id\":\"c1\",\"text\":\"a, ]\"}]}"
+        );
+    }
+
+    #[test]
+    fn extract_json_payload_falls_back_to_arrays_then_raw_slices() {
+        assert_eq!(
+            extract_json_payload("Candidates: [1, 2, 3,] done").expect("array should be found"),
+            "[1, 2, 3]"
+        );
+        // Truncated output cannot be repaired; the raw slice is handed to the validator.
+        assert_eq!(
+            extract_json_payload("{\"request_id\": \"req-1\", \"candidates\": [} ")
+                .expect("slice should be returned"),
+            "{\"request_id\": \"req-1\", \"candidates\": [}"
+        );
+    }
+
+    #[test]
+    fn extract_json_payload_does_not_pick_a_nested_object_from_truncated_output() {
+        // The response hit the token limit inside the second note.
+        let content = "```json\n{\"request_id\":\"req-1\",\"candidates\":[{\"notes\":[{\"pitch\":60,\"velocity\":90},{\"pitch\":6";
+
+        assert_eq!(
+            extract_json_payload(content).expect("slice should be returned"),
+            "{\"request_id\":\"req-1\",\"candidates\":[{\"notes\":[{\"pitch\":60,\"velocity\":90},{\"pitch\":6"
+        );
+    }
+
     #[test]
     fn truncate_message_compacts_newlines_and_limits_length() {
         let input = "line-1\nline-2";
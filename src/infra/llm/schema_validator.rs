@@ -1,7 +1,10 @@
 use jsonschema::JSONSchema;
 use serde_json::Value;
 
-use crate::domain::{GenerationResult, LlmError};
+use crate::domain::{
+    GenerationConstraints, GenerationRequest, GenerationResult, LlmError, TickResolution,
+    ValidationPolicy,
+};
 
 pub const GENERATION_RESULT_JSON_SCHEMA: &str = r#"
 {
@@ -14,6 +17,10 @@ pub const GENERATION_RESULT_JSON_SCHEMA: &str = r#"
       "type": "string",
       "minLength": 1
     },
+    "contract_version": {
+      "type": "integer",
+      "minimum": 1
+    },
     "model": {
       "type": "object",
       "additionalProperties": false,
@@ -50,6 +57,10 @@ pub const GENERATION_RESULT_JSON_SCHEMA: &str = r#"
             "minimum": 0.0,
             "maximum": 1.0
           },
+          "title": {
+            "type": ["string", "null"],
+            "maxLength": 60
+          },
           "notes": {
             "type": "array",
             "minItems": 1,
@@ -69,7 +80,7 @@ pub const GENERATION_RESULT_JSON_SCHEMA: &str = r#"
                 },
                 "duration_tick": {
                   "type": "integer",
-                  "minimum": 1
+                  "minimum": 0
                 },
                 "velocity": {
                   "type": "integer",
@@ -83,6 +94,66 @@ pub const GENERATION_RESULT_JSON_SCHEMA: &str = r#"
                 }
               }
             }
+          },
+          "control_events": {
+            "type": "array",
+            "items": {
+              "oneOf": [
+                {
+                  "type": "object",
+                  "additionalProperties": false,
+                  "required": ["type", "tick", "controller", "value"],
+                  "properties": {
+                    "type": {
+                      "const": "control_change"
+                    },
+                    "tick": {
+                      "type": "integer",
+                      "minimum": 0
+                    },
+                    "controller": {
+                      "type": "integer",
+                      "minimum": 0,
+                      "maximum": 119
+                    },
+                    "value": {
+                      "type": "integer",
+                      "minimum": 0,
+                      "maximum": 127
+                    },
+                    "channel": {
+                      "type": "integer",
+                      "minimum": 1,
+                      "maximum": 16
+                    }
+                  }
+                },
+                {
+                  "type": "object",
+                  "additionalProperties": false,
+                  "required": ["type", "tick", "value"],
+                  "properties": {
+                    "type": {
+                      "const": "pitch_bend"
+                    },
+                    "tick": {
+                      "type": "integer",
+                      "minimum": 0
+                    },
+                    "value": {
+                      "type": "integer",
+                      "minimum": -8192,
+                      "maximum": 8191
+                    },
+                    "channel": {
+                      "type": "integer",
+                      "minimum": 1,
+                      "maximum": 16
+                    }
+                  }
+                }
+              ]
+            }
           }
         }
       }
@@ -128,6 +199,25 @@ pub const GENERATION_RESULT_JSON_SCHEMA: &str = r#"
               "minimum": 0
             }
           }
+        },
+        "chords": {
+          "type": "array",
+          "items": {
+            "type": "object",
+            "additionalProperties": false,
+            "required": ["bar", "symbol"],
+            "properties": {
+              "bar": {
+                "type": "integer",
+                "minimum": 1
+              },
+              "symbol": {
+                "type": "string",
+                "minLength": 1,
+                "maxLength": 24
+              }
+            }
+          }
         }
       }
     }
@@ -154,33 +244,77 @@ impl LlmResponseSchemaValidator {
         &self,
         response_json: &str,
     ) -> Result<GenerationResult, LlmError> {
-        let json_value: Value = serde_json::from_str(response_json).map_err(|err| {
-            LlmError::invalid_response(format!("response JSON decode failed: {err}"))
-        })?;
-        self.validate_response_value(json_value)
+        let json_value = decode_response_json(response_json)?;
+        self.validate_response_value_with_policy(json_value, None, None)
+    }
+
+    /// Like [`Self::validate_response_json`], with the request's validation policy applied
+    /// and candidate confidence also scored against its `constraints`.
+    pub fn validate_response_json_for_request(
+        &self,
+        response_json: &str,
+        request: &GenerationRequest,
+    ) -> Result<GenerationResult, LlmError> {
+        let json_value = decode_response_json(response_json)?;
+        self.validate_response_value_with_policy(
+            json_value,
+            Some(request.params.tick_resolution),
+            request.constraints.as_ref(),
+            ValidationPolicy::for_request(request).as_ref(),
+        )
     }
 
     pub fn validate_response_value(&self, response: Value) -> Result<GenerationResult, LlmError> {
+        self.validate_response_value_with_policy(response, None, None, None)
+    }
+
+    fn validate_response_value_with_policy(
+        &self,
+        response: Value,
+        tick_resolution: Option<TickResolution>,
+        constraints: Option<&GenerationConstraints>,
+        policy: Option<&ValidationPolicy>,
+    ) -> Result<GenerationResult, LlmError> {
         self.compiled_schema
             .validate(&response)
             .map_err(schema_validation_error)?;
 
-        let result: GenerationResult = serde_json::from_value(response).map_err(|err| {
+        let mut result: GenerationResult = serde_json::from_value(response).map_err(|err| {
             LlmError::invalid_response(format!(
                 "response JSON did not match GenerationResult contract: {err}"
             ))
         })?;
 
+        // The policy and repair passes below measure beats and bars in ticks.
+        if let Some(tick_resolution) = tick_resolution {
+            result.set_tick_resolution(tick_resolution);
+        }
+
+        // Runs before the repair pass, which tidies any collisions its corrections cause.
+        if let Some(policy) = policy {
+            policy.apply(&mut result)?;
+        }
+
+        // Zero-length and overlapping notes are common model artifacts; fix them before the
+        // domain gate rejects them.
+        result.repair_candidates();
+
         // Keep domain-level rules as a second gate so validation behavior is centralized.
         result.validate().map_err(|err| match err {
             LlmError::Validation { message } => LlmError::invalid_response(message),
             other => other,
         })?;
+        result.assess_confidence(constraints);
 
         Ok(result)
     }
 }
 
+fn decode_response_json(response_json: &str) -> Result<Value, LlmError> {
+    serde_json::from_str(response_json)
+        .map_err(|err| LlmError::invalid_response(format!("response JSON decode failed: {err}")))
+}
+
 fn schema_validation_error<'a, I>(errors: I) -> LlmError
 where
     I: IntoIterator<Item = jsonschema::ValidationError<'a>>,
@@ -196,7 +330,10 @@ where
 #[cfg(test)]
 mod tests {
     use super::LlmResponseSchemaValidator;
-    use crate::domain::LlmError;
+    use crate::domain::{
+        GENERATION_CONTRACT_VERSION, GeneratedControlEvent, GenerationMode, GenerationParams,
+        GenerationRequest, LlmError, ModelRef, TickResolution,
+    };
 
     fn validator() -> LlmResponseSchemaValidator {
         LlmResponseSchemaValidator::new().expect("schema validator must compile")
@@ -244,6 +381,7 @@ mod tests {
 
         assert_eq!(result.request_id, "req-42");
         assert_eq!(result.candidates.len(), 1);
+        assert_eq!(result.metadata.confidence.len(), 1);
         assert_eq!(result.metadata.latency_ms, Some(321));
         assert_eq!(
             result.metadata.provider_request_id.as_deref(),
@@ -259,6 +397,140 @@ mod tests {
         );
     }
 
+    fn request_at(tick_resolution: TickResolution) -> GenerationRequest {
+        GenerationRequest {
+            request_id: "req-42".to_string(),
+            model: ModelRef {
+                provider: "anthropic".to_string(),
+                model: "claude-3-5-sonnet".to_string(),
+            },
+            mode: GenerationMode::Melody,
+            prompt: "generate MIDI".to_string(),
+            params: GenerationParams {
+                bpm: 120,
+                key: "C".to_string(),
+                scale: "major".to_string(),
+                density: 3,
+                complexity: 3,
+                syncopation: 3,
+                tick_resolution,
+                temperature: None,
+                top_p: None,
+                max_tokens: None,
+            },
+            references: Vec::new(),
+            variation_count: 1,
+            locked_notes: Vec::new(),
+            contract_version: GENERATION_CONTRACT_VERSION,
+            daw_context: None,
+            constraints: None,
+            validation: None,
+            anonymize_references: false,
+        }
+    }
+
+    #[test]
+    fn candidates_take_the_request_tick_resolution_rather_than_their_extent() {
+        // Four bars at 480 PPQ with every note in the first bar: the extent alone reads as
+        // 120 PPQ.
+        let json = r#"{
+          "request_id": "req-42",
+          "model": { "provider": "anthropic", "model": "claude-3-5-sonnet" },
+          "candidates": [
+            {
+              "id": "cand-1",
+              "bars": 4,
+              "notes": [
+                { "pitch": 60, "start_tick": 0, "duration_tick": 480, "velocity": 96 },
+                { "pitch": 64, "start_tick": 960, "duration_tick": 960, "velocity": 90 }
+              ]
+            }
+          ]
+        }"#;
+
+        for ticks_per_beat in [480, 960] {
+            let tick_resolution =
+                TickResolution::new(ticks_per_beat).expect("test PPQ should be valid");
+            let result = validator()
+                .validate_response_json_for_request(json, &request_at(tick_resolution))
+                .expect("valid response should pass");
+
+            assert_eq!(result.candidates[0].tick_resolution, tick_resolution);
+            assert_eq!(result.candidates[0].notes[1].start_tick, 960);
+        }
+    }
+
+    fn response_with_control_events(control_events: &str) -> String {
+        format!(
+            r#"{{
+              "request_id": "req-42",
+              "model": {{
+                "provider": "anthropic",
+                "model": "claude-3-5-sonnet"
+              }},
+              "candidates": [
+                {{
+                  "id": "cand-1",
+                  "bars": 1,
+                  "notes": [
+                    {{
+                      "pitch": 60,
+                      "start_tick": 0,
+                      "duration_tick": 480,
+                      "velocity": 96
+                    }}
+                  ],
+                  "control_events": {control_events}
+                }}
+              ]
+            }}"#
+        )
+    }
+
+    #[test]
+    fn validate_response_json_accepts_control_and_pitch_bend_events() {
+        let result = validator()
+            .validate_response_json(&response_with_control_events(
+                r#"[
+                  { "type": "control_change", "tick": 0, "controller": 1, "value": 64 },
+                  { "type": "pitch_bend", "tick": 240, "value": -4096, "channel": 2 }
+                ]"#,
+            ))
+            .expect("control events should validate");
+
+        assert_eq!(
+            result.candidates[0].control_events,
+            vec![
+                GeneratedControlEvent::ControlChange {
+                    tick: 0,
+                    controller: 1,
+                    value: 64,
+                    channel: 1,
+                },
+                GeneratedControlEvent::PitchBend {
+                    tick: 240,
+                    value: -4096,
+                    channel: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_response_json_rejects_channel_mode_controllers_and_out_of_range_bends() {
+        for control_events in [
+            r#"[{ "type": "control_change", "tick": 0, "controller": 123, "value": 0 }]"#,
+            r#"[{ "type": "pitch_bend", "tick": 0, "value": 8192 }]"#,
+            r#"[{ "type": "aftertouch", "tick": 0, "value": 10 }]"#,
+        ] {
+            let error = validator()
+                .validate_response_json(&response_with_control_events(control_events))
+                .expect_err("invalid control event must fail");
+
+            assert!(matches!(error, LlmError::InvalidResponse { .. }));
+        }
+    }
+
     #[test]
     fn validate_response_json_rejects_invalid_json() {
         let json = "{ this is not valid json";
@@ -358,4 +630,92 @@ mod tests {
             if message == "usage must include at least one token counter"
         ));
     }
+
+    #[test]
+    fn validate_response_json_accepts_chord_labels_in_metadata() {
+        let json = r#"{
+          "request_id": "req-42",
+          "model": {
+            "provider": "anthropic",
+            "model": "claude-3-5-sonnet"
+          },
+          "candidates": [
+            {
+              "id": "cand-1",
+              "bars": 2,
+              "notes": [
+                {
+                  "pitch": 57,
+                  "start_tick": 0,
+                  "duration_tick": 480,
+                  "velocity": 96
+                }
+              ]
+            }
+          ],
+          "metadata": {
+            "chords": [
+              { "bar": 1, "symbol": "Am7" },
+              { "bar": 2, "symbol": "D9" }
+            ]
+          }
+        }"#;
+
+        let result = validator()
+            .validate_response_json(json)
+            .expect("chord labels should validate");
+
+        assert_eq!(
+            result
+                .metadata
+                .chords
+                .iter()
+                .map(|chord| (chord.bar, chord.symbol.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(1, "Am7"), (2, "D9")]
+        );
+        assert!(
+            validator()
+                .validate_response_json(&json.replace("\"bar\": 2", "\"bar\": 0"))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn validate_response_json_repairs_zero_length_and_overlapping_notes() {
+        let json = r#"{
+          "request_id": "req-42",
+          "model": {
+            "provider": "anthropic",
+            "model": "claude-3-5-sonnet"
+          },
+          "candidates": [
+            {
+              "id": "cand-1",
+              "bars": 1,
+              "notes": [
+                { "pitch": 60, "start_tick": 0, "duration_tick": 960, "velocity": 96 },
+                { "pitch": 60, "start_tick": 480, "duration_tick": 0, "velocity": 96 },
+                { "pitch": 67, "start_tick": 0, "duration_tick": 1920, "velocity": 96 }
+              ]
+            }
+          ]
+        }"#;
+
+        let result = validator()
+            .validate_response_json(json)
+            .expect("repairable notes should validate");
+
+        let durations = result.candidates[0]
+            .notes
+            .iter()
+            .map(|note| note.duration_tick)
+            .collect::<Vec<_>>();
+        assert_eq!(durations, vec![480, 120, 1920]);
+        assert_eq!(result.metadata.repairs.len(), 1);
+        assert_eq!(
+            result.metadata.repairs[0].summary(),
+            "1 overlap trimmed, 1 zero-length note extended"
+        );
+    }
 }
@@ -0,0 +1,286 @@
+use crate::domain::{BEATS_PER_BAR, MidiReferenceSummary, estimate_ticks_per_beat};
+
+const PITCH_CLASS_NAMES: [&str; 12] = [
+    "C", "C#", "D", "Eb", "E", "F", "F#", "G", "Ab", "A", "Bb", "B",
+];
+// Krumhansl-Kessler key profiles, indexed from the tonic.
+const MAJOR_KEY_PROFILE: [f64; 12] = [
+    6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88,
+];
+const MINOR_KEY_PROFILE: [f64; 12] = [
+    6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17,
+];
+const MICROSECONDS_PER_MINUTE: f64 = 60_000_000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyMode {
+    Major,
+    Minor,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DetectedKey {
+    /// Pitch class of the tonic, 0 = C.
+    pub tonic: u8,
+    pub mode: KeyMode,
+    /// Correlation with the winning key profile in -1.0..=1.0; low values mean an ambiguous key.
+    pub confidence: f64,
+}
+
+impl DetectedKey {
+    pub fn name(&self) -> String {
+        let mode = match self.mode {
+            KeyMode::Major => "major",
+            KeyMode::Minor => "minor",
+        };
+        format!("{} {mode}", PITCH_CLASS_NAMES[usize::from(self.tonic % 12)])
+    }
+
+    /// Compact form for labels, e.g. "C-min".
+    pub fn short_name(&self) -> String {
+        let mode = match self.mode {
+            KeyMode::Major => "maj",
+            KeyMode::Minor => "min",
+        };
+        format!("{}-{mode}", PITCH_CLASS_NAMES[usize::from(self.tonic % 12)])
+    }
+}
+
+/// Musical overview of a reference shown before generating from it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReferenceAnalysis {
+    pub key: Option<DetectedKey>,
+    /// From the first tempo meta event; live captures carry none.
+    pub tempo_bpm: Option<f64>,
+    /// Note-ons per bar, one entry per bar of the reference.
+    pub notes_per_bar: Vec<u32>,
+    pub min_pitch: u8,
+    pub max_pitch: u8,
+    pub duration_beats: f64,
+    pub duration_seconds: Option<f64>,
+}
+
+pub fn analyze_reference(reference: &MidiReferenceSummary) -> ReferenceAnalysis {
+    let bars = usize::from(reference.bars.max(1));
+    let beats_per_bar = BEATS_PER_BAR as usize;
+    let max_tick = reference
+        .events
+        .iter()
+        .map(|event| event.absolute_tick)
+        .max()
+        .unwrap_or(0);
+    let ticks_per_bar = f64::from(estimate_ticks_per_beat(bars * beats_per_bar, max_tick))
+        * f64::from(BEATS_PER_BAR);
+
+    let mut pitch_class_counts = [0u32; 12];
+    let mut notes_per_bar = vec![0u32; bars];
+    let mut tempo_bpm = None;
+    for event in &reference.events {
+        if let Some(pitch) = note_on_pitch(&event.event) {
+            pitch_class_counts[usize::from(pitch % 12)] += 1;
+            let bar = (f64::from(event.absolute_tick) / ticks_per_bar) as usize;
+            notes_per_bar[bar.min(bars - 1)] += 1;
+        } else if tempo_bpm.is_none() {
+            tempo_bpm = tempo_bpm_from_event(&event.event);
+        }
+    }
+
+    let duration_beats = (bars * beats_per_bar) as f64;
+    ReferenceAnalysis {
+        key: detect_key(&pitch_class_counts),
+        tempo_bpm,
+        notes_per_bar,
+        min_pitch: reference.min_pitch,
+        max_pitch: reference.max_pitch,
+        duration_beats,
+        duration_seconds: tempo_bpm.map(|bpm| duration_beats * 60.0 / bpm),
+    }
+}
+
+/// Best-correlating major or minor key for a pitch-class histogram.
+pub fn detect_key(pitch_class_counts: &[u32; 12]) -> Option<DetectedKey> {
+    if pitch_class_counts.iter().all(|count| *count == 0) {
+        return None;
+    }
+    let histogram = pitch_class_counts.map(f64::from);
+
+    let mut best: Option<DetectedKey> = None;
+    for tonic in 0..12u8 {
+        for (mode, profile) in [
+            (KeyMode::Major, &MAJOR_KEY_PROFILE),
+            (KeyMode::Minor, &MINOR_KEY_PROFILE),
+        ] {
+            let rotated: [f64; 12] = std::array::from_fn(|pitch_class| {
+                profile[(pitch_class + 12 - usize::from(tonic)) % 12]
+            });
+            let confidence = correlation(&histogram, &rotated);
+            if best.is_none_or(|best| confidence > best.confidence) {
+                best = Some(DetectedKey {
+                    tonic,
+                    mode,
+                    confidence,
+                });
+            }
+        }
+    }
+    best
+}
+
+fn correlation(left: &[f64; 12], right: &[f64; 12]) -> f64 {
+    let left_mean = left.iter().sum::<f64>() / 12.0;
+    let right_mean = right.iter().sum::<f64>() / 12.0;
+    let mut covariance = 0.0;
+    let mut left_variance = 0.0;
+    let mut right_variance = 0.0;
+    for (left, right) in left.iter().zip(right) {
+        covariance += (left - left_mean) * (right - right_mean);
+        left_variance += (left - left_mean).powi(2);
+        right_variance += (right - right_mean).powi(2);
+    }
+    let denominator = (left_variance * right_variance).sqrt();
+    if denominator == 0.0 {
+        0.0
+    } else {
+        covariance / denominator
+    }
+}
+
+fn note_on_pitch(payload: &str) -> Option<u8> {
+    parse_note_event(payload).and_then(|(pitch, is_note_on)| is_note_on.then_some(pitch))
+}
+
+/// Pitch and whether the event starts the note; a zero-velocity note-on ends it. Events are
+/// stored as midly debug strings for files and `LiveMidi ...` lines for live input.
+pub(super) fn parse_note_event(payload: &str) -> Option<(u8, bool)> {
+    if payload.starts_with("LiveMidi ") {
+        let status = hex_after(payload, "status=0x")?;
+        let velocity = decimal_after(payload, "data2=").unwrap_or(0);
+        let is_note_on = match status & 0xF0 {
+            0x90 => velocity > 0,
+            0x80 => false,
+            _ => return None,
+        };
+        let pitch = decimal_after(payload, "data1=").and_then(|pitch| u8::try_from(pitch).ok())?;
+        return Some((pitch, is_note_on));
+    }
+    let is_note_on = if payload.contains("NoteOn") {
+        decimal_after(payload, "vel: u7(").unwrap_or(0) > 0
+    } else if payload.contains("NoteOff") {
+        false
+    } else {
+        return None;
+    };
+    let pitch = decimal_after(payload, "key: u7(").and_then(|pitch| u8::try_from(pitch).ok())?;
+    Some((pitch, is_note_on))
+}
+
+pub(super) fn tempo_bpm_from_event(payload: &str) -> Option<f64> {
+    let microseconds_per_beat = decimal_after(payload, "Tempo(u24(")?;
+    (microseconds_per_beat > 0).then(|| MICROSECONDS_PER_MINUTE / f64::from(microseconds_per_beat))
+}
+
+fn decimal_after(text: &str, marker: &str) -> Option<u32> {
+    let tail = &text[text.find(marker)? + marker.len()..];
+    let end = tail
+        .find(|character: char| !character.is_ascii_digit())
+        .unwrap_or(tail.len());
+    tail[..end].parse().ok()
+}
+
+/// Swaps the decimal right after `marker` for `value`, keeping the rest of the payload.
+pub(super) fn replace_decimal_after(text: &str, marker: &str, value: u32) -> Option<String> {
+    let start = text.find(marker)? + marker.len();
+    let end = text[start..]
+        .find(|character: char| !character.is_ascii_digit())
+        .map_or(text.len(), |offset| start + offset);
+    Some(format!("{}{value}{}", &text[..start], &text[end..]))
+}
+
+fn hex_after(text: &str, marker: &str) -> Option<u8> {
+    let tail = &text[text.find(marker)? + marker.len()..];
+    let end = tail
+        .find(|character: char| !character.is_ascii_hexdigit())
+        .unwrap_or(tail.len());
+    u8::from_str_radix(&tail[..end], 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeyMode, analyze_reference, detect_key};
+    use crate::domain::{MidiReferenceEvent, MidiReferenceSummary, ReferenceSlot, ReferenceSource};
+
+    fn event(absolute_tick: u32, payload: String) -> MidiReferenceEvent {
+        MidiReferenceEvent {
+            track: 0,
+            absolute_tick,
+            delta_tick: 0,
+            event: payload,
+        }
+    }
+
+    fn note_on(absolute_tick: u32, pitch: u8) -> MidiReferenceEvent {
+        event(
+            absolute_tick,
+            format!(
+                "Midi {{ channel: u4(0), message: NoteOn {{ key: u7({pitch}), vel: u7(100) }} }}"
+            ),
+        )
+    }
+
+    #[test]
+    fn key_detection_prefers_the_scale_the_notes_outline() {
+        let mut c_major = [0u32; 12];
+        for pitch_class in [0, 2, 4, 5, 7, 9, 11, 0, 4, 7] {
+            c_major[pitch_class] += 1;
+        }
+        let key = detect_key(&c_major).expect("key should be detected");
+        assert_eq!((key.tonic, key.mode), (0, KeyMode::Major));
+        assert_eq!(key.name(), "C major");
+
+        let mut a_minor = [0u32; 12];
+        for pitch_class in [9, 9, 9, 0, 4, 4, 11, 2, 5, 7, 9, 0] {
+            a_minor[pitch_class] += 1;
+        }
+        assert_eq!(
+            detect_key(&a_minor).map(|key| key.name()),
+            Some("A minor".to_string())
+        );
+        assert!(detect_key(&[0; 12]).is_none());
+    }
+
+    #[test]
+    fn analysis_reports_tempo_density_and_duration() {
+        let reference = MidiReferenceSummary {
+            slot: ReferenceSlot::Melody,
+            source: ReferenceSource::File,
+            file: None,
+            bars: 2,
+            note_count: 3,
+            density_hint: 0.2,
+            min_pitch: 60,
+            max_pitch: 67,
+            events: vec![
+                event(0, "Meta(Tempo(u24(500000)))".to_string()),
+                note_on(0, 60),
+                note_on(480, 64),
+                note_on(1_920, 67),
+                event(
+                    3_840,
+                    "Midi { channel: u4(0), message: NoteOff { key: u7(67), vel: u7(0) } }"
+                        .to_string(),
+                ),
+            ],
+            transposition: None,
+            tempo: None,
+        };
+
+        let analysis = analyze_reference(&reference);
+
+        assert_eq!(analysis.tempo_bpm, Some(120.0));
+        assert_eq!(analysis.notes_per_bar, vec![2, 1]);
+        assert_eq!(analysis.duration_beats, 8.0);
+        assert_eq!(analysis.duration_seconds, Some(4.0));
+        assert_eq!((analysis.min_pitch, analysis.max_pitch), (60, 67));
+        assert!(analysis.key.is_some());
+    }
+}
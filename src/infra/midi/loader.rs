@@ -11,6 +11,7 @@ pub struct MidiSummary {
     pub note_count: u32,
     pub min_pitch: u8,
     pub max_pitch: u8,
+    pub ticks_per_bar: u32,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -132,6 +133,9 @@ pub fn parse_midi_reference(bytes: &[u8]) -> Result<MidiReferenceData, MidiLoadE
     let note_count = u32::try_from(note_count).map_err(|_| MidiLoadError::Overflow {
         field: "note_count",
     })?;
+    let ticks_per_bar = u32::try_from(ticks_per_bar).map_err(|_| MidiLoadError::Overflow {
+        field: "ticks_per_bar",
+    })?;
 
     Ok(MidiReferenceData {
         summary: MidiSummary {
@@ -139,6 +143,7 @@ pub fn parse_midi_reference(bytes: &[u8]) -> Result<MidiReferenceData, MidiLoadE
             note_count,
             min_pitch,
             max_pitch,
+            ticks_per_bar,
         },
         events,
     })
@@ -351,6 +356,7 @@ mod tests {
         assert_eq!(summary.note_count, 2);
         assert_eq!(summary.min_pitch, 60);
         assert_eq!(summary.max_pitch, 64);
+        assert_eq!(summary.ticks_per_bar, 384);
     }
 
     #[test]
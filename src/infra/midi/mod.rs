@@ -1,6 +1,22 @@
+mod analysis;
 mod loader;
+mod monophonic;
+mod similarity;
+mod tempo;
+mod transpose;
+mod voice_split;
+mod writer;
 
+pub use analysis::{DetectedKey, KeyMode, ReferenceAnalysis, analyze_reference, detect_key};
 pub use loader::{
     MidiLoadError, MidiReferenceData, MidiSummary, load_midi_reference, load_midi_summary,
     parse_midi_reference, parse_midi_summary,
 };
+pub use monophonic::force_monophonic;
+pub use similarity::ReferenceSimilarity;
+pub use tempo::normalize_reference_tempo;
+pub use transpose::transpose_references_to_key;
+pub use voice_split::split_melody_and_chords;
+pub use writer::{
+    KeySignature, MidiConductor, MidiWriteError, encode_notes_as_smf, write_notes_to_midi_file,
+};
@@ -0,0 +1,198 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::domain::{
+    BEATS_PER_BAR, MidiReferenceSummary, calculate_reference_density_hint, estimate_ticks_per_beat,
+};
+
+use super::analysis::parse_note_event;
+
+// Onsets closer than this share of a beat count as struck together.
+const ONSET_WINDOW_BEAT_DIVISOR: f32 = 32.0;
+
+pub(super) struct ReferenceNote {
+    pub(super) pitch: u8,
+    pub(super) start_tick: u32,
+    pub(super) on_index: usize,
+    pub(super) off_index: Option<usize>,
+}
+
+/// Leaves one note sounding at a time: of notes struck together only the highest is kept, and a
+/// note still held when the next one starts is cut off there. The note count, pitch range and
+/// density hint are recomputed from what remains.
+pub fn force_monophonic(reference: &mut MidiReferenceSummary) {
+    let notes = reference_notes(reference);
+    if notes.is_empty() {
+        return;
+    }
+    let window = onset_window(reference);
+
+    let mut dropped = HashSet::new();
+    let mut kept = Vec::<ReferenceNote>::new();
+    for note in notes {
+        let lower = match kept.last() {
+            Some(previous) if note.start_tick - previous.start_tick <= window => {
+                if note.pitch > previous.pitch {
+                    let previous = kept.pop().expect("previous note exists");
+                    kept.push(note);
+                    previous
+                } else {
+                    note
+                }
+            }
+            _ => {
+                kept.push(note);
+                continue;
+            }
+        };
+        dropped.insert(lower.on_index);
+        dropped.extend(lower.off_index);
+    }
+
+    for pair in kept.windows(2) {
+        if let Some(off_index) = pair[0].off_index {
+            let off = &mut reference.events[off_index];
+            off.absolute_tick = off.absolute_tick.min(pair[1].start_tick);
+        }
+    }
+
+    retain_events(reference, &dropped);
+    refresh_note_stats(reference);
+}
+
+/// Notes paired with their ending events, in onset order.
+pub(super) fn reference_notes(reference: &MidiReferenceSummary) -> Vec<ReferenceNote> {
+    let mut notes = Vec::<ReferenceNote>::new();
+    let mut open_notes = HashMap::<u8, usize>::new();
+    for (index, event) in reference.events.iter().enumerate() {
+        match parse_note_event(&event.event) {
+            Some((pitch, true)) => {
+                open_notes.insert(pitch, notes.len());
+                notes.push(ReferenceNote {
+                    pitch,
+                    start_tick: event.absolute_tick,
+                    on_index: index,
+                    off_index: None,
+                });
+            }
+            Some((pitch, false)) => {
+                if let Some(note) = open_notes.remove(&pitch) {
+                    notes[note].off_index = Some(index);
+                }
+            }
+            None => {}
+        }
+    }
+    notes.sort_by_key(|note| note.start_tick);
+    notes
+}
+
+/// Largest onset gap, in ticks, still heard as notes struck together.
+pub(super) fn onset_window(reference: &MidiReferenceSummary) -> u32 {
+    let max_tick = reference
+        .events
+        .iter()
+        .map(|event| event.absolute_tick)
+        .max()
+        .unwrap_or(0);
+    let total_beats = usize::from(reference.bars.max(1)) * BEATS_PER_BAR as usize;
+    (estimate_ticks_per_beat(total_beats, max_tick) / ONSET_WINDOW_BEAT_DIVISOR) as u32
+}
+
+/// Removes the events at `dropped` indices, re-sorts by tick and recomputes per-track deltas.
+pub(super) fn retain_events(reference: &mut MidiReferenceSummary, dropped: &HashSet<usize>) {
+    let mut events = std::mem::take(&mut reference.events)
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| !dropped.contains(index))
+        .map(|(_, event)| event)
+        .collect::<Vec<_>>();
+    // Ends sort before starts on the same tick so a cut-off note never overlaps its successor.
+    events.sort_by_key(|event| {
+        let is_note_on = parse_note_event(&event.event).is_some_and(|(_, is_note_on)| is_note_on);
+        (event.absolute_tick, is_note_on)
+    });
+    let mut previous_tick_by_track = HashMap::new();
+    for event in &mut events {
+        let previous_tick = previous_tick_by_track.insert(event.track, event.absolute_tick);
+        event.delta_tick = event.absolute_tick - previous_tick.unwrap_or(0);
+    }
+    reference.events = events;
+}
+
+pub(super) fn refresh_note_stats(reference: &mut MidiReferenceSummary) {
+    let pitches = reference
+        .events
+        .iter()
+        .filter_map(|event| parse_note_event(&event.event))
+        .filter_map(|(pitch, is_note_on)| is_note_on.then_some(pitch))
+        .collect::<Vec<_>>();
+    reference.note_count = u32::try_from(pitches.len()).unwrap_or(u32::MAX);
+    reference.min_pitch = pitches.iter().copied().min().unwrap_or(reference.min_pitch);
+    reference.max_pitch = pitches.iter().copied().max().unwrap_or(reference.max_pitch);
+    reference.density_hint = calculate_reference_density_hint(reference.note_count, reference.bars);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::force_monophonic;
+    use crate::domain::{MidiReferenceEvent, MidiReferenceSummary, ReferenceSlot, ReferenceSource};
+
+    fn note(absolute_tick: u32, pitch: u8, velocity: u8) -> MidiReferenceEvent {
+        MidiReferenceEvent {
+            track: 0,
+            absolute_tick,
+            delta_tick: 0,
+            event: format!(
+                "Midi {{ channel: u4(0), message: NoteOn {{ key: u7({pitch}), vel: u7({velocity}) }} }}"
+            ),
+        }
+    }
+
+    #[test]
+    fn drops_lower_dyad_notes_and_truncates_overlaps() {
+        let mut reference = MidiReferenceSummary {
+            slot: ReferenceSlot::Melody,
+            source: ReferenceSource::File,
+            file: None,
+            bars: 1,
+            note_count: 3,
+            density_hint: 0.0,
+            min_pitch: 55,
+            max_pitch: 64,
+            events: vec![
+                note(0, 55, 90),
+                note(2, 60, 100),
+                note(480, 55, 0),
+                note(720, 60, 0),
+                note(480, 64, 100),
+                note(960, 64, 0),
+            ],
+            transposition: None,
+            tempo: None,
+        };
+        reference.events.sort_by_key(|event| event.absolute_tick);
+
+        force_monophonic(&mut reference);
+
+        assert_eq!(
+            reference
+                .events
+                .iter()
+                .map(|event| (event.absolute_tick, event.delta_tick))
+                .collect::<Vec<_>>(),
+            vec![(2, 2), (480, 478), (480, 0), (960, 480)]
+        );
+        assert!(
+            reference.events[1]
+                .event
+                .contains("key: u7(60), vel: u7(0)")
+        );
+        assert!(
+            reference.events[2]
+                .event
+                .contains("key: u7(64), vel: u7(100)")
+        );
+        assert_eq!(reference.note_count, 2);
+        assert_eq!((reference.min_pitch, reference.max_pitch), (60, 64));
+    }
+}
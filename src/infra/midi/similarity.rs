@@ -0,0 +1,189 @@
+use crate::domain::{
+    BEATS_PER_BAR, GenerationCandidate, MidiReferenceSummary, estimate_ticks_per_beat,
+};
+
+use super::analysis::parse_note_event;
+
+const RHYTHM_STEPS_PER_BEAT: u32 = 4;
+const RHYTHM_STEPS_PER_BAR: usize = (RHYTHM_STEPS_PER_BEAT * BEATS_PER_BAR) as usize;
+
+/// How closely a candidate follows the reference it extends, each part in `0.0..=1.0`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReferenceSimilarity {
+    /// Shared share of the two pitch-class distributions (histogram intersection).
+    pub pitch_class_overlap: f64,
+    /// Correlation of onsets on a sixteenth-note grid within the bar; unrelated or opposing
+    /// rhythms both score 0.
+    pub rhythm_correlation: f64,
+}
+
+impl ReferenceSimilarity {
+    pub fn between(candidate: &GenerationCandidate, reference: &MidiReferenceSummary) -> Self {
+        let candidate_ticks_per_beat = u32::from(candidate.tick_resolution.ticks_per_beat());
+        let candidate_profile = NoteProfile::from_notes(
+            candidate
+                .notes
+                .iter()
+                .map(|note| (note.pitch, note.start_tick)),
+            candidate_ticks_per_beat,
+        );
+
+        let max_tick = reference
+            .events
+            .iter()
+            .map(|event| event.absolute_tick)
+            .max()
+            .unwrap_or(0);
+        let total_beats = usize::from(reference.bars.max(1)) * BEATS_PER_BAR as usize;
+        let reference_ticks_per_beat = estimate_ticks_per_beat(total_beats, max_tick) as u32;
+        let reference_profile = NoteProfile::from_notes(
+            reference.events.iter().filter_map(|event| {
+                parse_note_event(&event.event).and_then(|(pitch, is_note_on)| {
+                    is_note_on.then_some((pitch, event.absolute_tick))
+                })
+            }),
+            reference_ticks_per_beat,
+        );
+
+        Self {
+            pitch_class_overlap: histogram_intersection(
+                &candidate_profile.pitch_classes,
+                &reference_profile.pitch_classes,
+            ),
+            rhythm_correlation: correlation(&candidate_profile.onsets, &reference_profile.onsets)
+                .max(0.0),
+        }
+    }
+
+    /// Both parts weighted equally, as a 0–100 percentage.
+    pub fn score(&self) -> u8 {
+        ((self.pitch_class_overlap + self.rhythm_correlation) * 50.0).round() as u8
+    }
+}
+
+struct NoteProfile {
+    pitch_classes: [f64; 12],
+    onsets: [f64; RHYTHM_STEPS_PER_BAR],
+}
+
+impl NoteProfile {
+    fn from_notes(notes: impl Iterator<Item = (u8, u32)>, ticks_per_beat: u32) -> Self {
+        let ticks_per_step = (ticks_per_beat / RHYTHM_STEPS_PER_BEAT).max(1);
+        let mut profile = Self {
+            pitch_classes: [0.0; 12],
+            onsets: [0.0; RHYTHM_STEPS_PER_BAR],
+        };
+        for (pitch, start_tick) in notes {
+            profile.pitch_classes[usize::from(pitch % 12)] += 1.0;
+            let step = (start_tick + ticks_per_step / 2) / ticks_per_step;
+            profile.onsets[step as usize % RHYTHM_STEPS_PER_BAR] += 1.0;
+        }
+        profile
+    }
+}
+
+fn histogram_intersection(left: &[f64; 12], right: &[f64; 12]) -> f64 {
+    let left_total = left.iter().sum::<f64>();
+    let right_total = right.iter().sum::<f64>();
+    if left_total == 0.0 || right_total == 0.0 {
+        return 0.0;
+    }
+    left.iter()
+        .zip(right)
+        .map(|(left, right)| (left / left_total).min(right / right_total))
+        .sum()
+}
+
+fn correlation(left: &[f64], right: &[f64]) -> f64 {
+    let count = left.len() as f64;
+    let left_mean = left.iter().sum::<f64>() / count;
+    let right_mean = right.iter().sum::<f64>() / count;
+    let mut covariance = 0.0;
+    let mut left_variance = 0.0;
+    let mut right_variance = 0.0;
+    for (left, right) in left.iter().zip(right) {
+        covariance += (left - left_mean) * (right - right_mean);
+        left_variance += (left - left_mean).powi(2);
+        right_variance += (right - right_mean).powi(2);
+    }
+    let denominator = (left_variance * right_variance).sqrt();
+    if denominator == 0.0 {
+        0.0
+    } else {
+        covariance / denominator
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReferenceSimilarity;
+    use crate::domain::{
+        GeneratedNote, GenerationCandidate, MidiReferenceEvent, MidiReferenceSummary,
+        ReferenceSlot, ReferenceSource, TickResolution,
+    };
+
+    fn candidate(notes: &[(u8, u32)]) -> GenerationCandidate {
+        GenerationCandidate {
+            id: "c1".to_string(),
+            bars: 1,
+            notes: notes
+                .iter()
+                .map(|(pitch, start_tick)| GeneratedNote {
+                    pitch: *pitch,
+                    start_tick: *start_tick,
+                    duration_tick: 120,
+                    velocity: 96,
+                    channel: 1,
+                })
+                .collect(),
+            tick_resolution: TickResolution::DEFAULT,
+            score_hint: None,
+            title: None,
+            control_events: Vec::new(),
+            processing: None,
+        }
+    }
+
+    fn reference(notes: &[(u8, u32)]) -> MidiReferenceSummary {
+        MidiReferenceSummary {
+            slot: ReferenceSlot::ContinuationSeed,
+            source: ReferenceSource::File,
+            file: None,
+            bars: 1,
+            note_count: notes.len() as u32,
+            density_hint: 0.5,
+            min_pitch: 60,
+            max_pitch: 67,
+            events: notes
+                .iter()
+                .flat_map(|(pitch, tick)| [(*pitch, *tick, 100), (*pitch, tick + 480, 0)])
+                .map(|(pitch, tick, velocity)| MidiReferenceEvent {
+                    track: 0,
+                    absolute_tick: tick,
+                    delta_tick: 0,
+                    event: format!(
+                        "Midi {{ channel: u4(0), message: NoteOn {{ key: u7({pitch}), vel: u7({velocity}) }} }}"
+                    ),
+                })
+                .collect(),
+            transposition: None,
+            tempo: None,
+        }
+    }
+
+    #[test]
+    fn matching_material_scores_higher_than_unrelated_material() {
+        let seed = reference(&[(60, 0), (64, 480), (67, 960), (64, 1440)]);
+        let coherent = candidate(&[(72, 0), (64, 480), (67, 960), (64, 1440)]);
+        let unrelated = candidate(&[(61, 240), (66, 720), (70, 1200), (63, 1680)]);
+
+        let coherent = ReferenceSimilarity::between(&coherent, &seed);
+        let unrelated = ReferenceSimilarity::between(&unrelated, &seed);
+
+        assert!((coherent.pitch_class_overlap - 1.0).abs() < 1e-9);
+        assert!(coherent.rhythm_correlation > 0.99);
+        assert_eq!(coherent.score(), 100);
+        assert_eq!(unrelated.pitch_class_overlap, 0.0);
+        assert_eq!(unrelated.score(), 0);
+    }
+}
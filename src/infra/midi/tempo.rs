@@ -0,0 +1,122 @@
+use crate::domain::{MidiReferenceSummary, ReferenceTempo};
+
+use super::analysis::tempo_bpm_from_event;
+
+// Rounding in the file's tempo event alone shifts it by a fraction of a BPM.
+const TEMPO_MATCH_TOLERANCE_BPM: f32 = 0.5;
+
+/// Records the written tempo on each reference whose file was written at a tempo other than
+/// `target_bpm`, so the prompt can state the speed ratio. Events are left as written: bars and
+/// ticks are musical time, and the tempo events keep the file's tempo map. Live captures carry
+/// no tempo events, and references annotated before are left alone. Returns how many were
+/// annotated.
+pub fn normalize_reference_tempo(
+    references: &mut [MidiReferenceSummary],
+    target_bpm: u16,
+) -> usize {
+    if target_bpm == 0 {
+        return 0;
+    }
+    let mut normalized = 0;
+    for reference in references
+        .iter_mut()
+        .filter(|reference| reference.tempo.is_none())
+    {
+        let Some(source_bpm) = reference
+            .events
+            .iter()
+            .find_map(|event| tempo_bpm_from_event(&event.event))
+            .map(|bpm| bpm as f32)
+        else {
+            continue;
+        };
+        if (source_bpm - f32::from(target_bpm)).abs() < TEMPO_MATCH_TOLERANCE_BPM {
+            continue;
+        }
+        reference.tempo = Some(ReferenceTempo {
+            source_bpm,
+            target_bpm,
+        });
+        normalized += 1;
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::normalize_reference_tempo;
+    use crate::domain::{MidiReferenceEvent, MidiReferenceSummary, ReferenceSlot, ReferenceSource};
+
+    fn reference(source: ReferenceSource, events: &[&str]) -> MidiReferenceSummary {
+        MidiReferenceSummary {
+            slot: ReferenceSlot::Melody,
+            source,
+            file: None,
+            bars: 2,
+            note_count: 1,
+            density_hint: 0.5,
+            min_pitch: 60,
+            max_pitch: 60,
+            events: events
+                .iter()
+                .enumerate()
+                .map(|(index, event)| MidiReferenceEvent {
+                    track: 0,
+                    absolute_tick: index as u32 * 960,
+                    delta_tick: 960,
+                    event: event.to_string(),
+                })
+                .collect(),
+            transposition: None,
+            tempo: None,
+        }
+    }
+
+    const NOTE_ON: &str = "Midi { channel: u4(0), message: NoteOn { key: u7(60), vel: u7(100) } }";
+
+    #[test]
+    fn slower_file_is_annotated_with_its_tempo_map_kept() {
+        let mut references = vec![reference(
+            ReferenceSource::File,
+            &[
+                "Meta(Tempo(u24(666666)))",
+                NOTE_ON,
+                "Meta(Tempo(u24(600000)))",
+            ],
+        )];
+        let events = references[0].events.clone();
+
+        assert_eq!(normalize_reference_tempo(&mut references, 120), 1);
+
+        let tempo = references[0].tempo.expect("tempo should be recorded");
+        assert!((tempo.source_bpm - 90.0).abs() < 0.01);
+        assert_eq!(tempo.target_bpm, 120);
+        assert!((tempo.speed_ratio() - 4.0 / 3.0).abs() < 0.01);
+        assert_eq!(references[0].bars, 2);
+        assert_eq!(references[0].events, events);
+
+        assert_eq!(
+            normalize_reference_tempo(&mut references, 90),
+            0,
+            "a normalized reference keeps its first annotation"
+        );
+    }
+
+    #[test]
+    fn matching_tempos_and_live_captures_are_left_alone() {
+        let mut references = vec![
+            reference(
+                ReferenceSource::File,
+                &["Meta(Tempo(u24(500001)))", NOTE_ON],
+            ),
+            reference(
+                ReferenceSource::Live,
+                &["LiveMidi channel=0 status=0x90 data1=60 data2=100"],
+            ),
+        ];
+
+        assert_eq!(normalize_reference_tempo(&mut references, 120), 0);
+        assert!(references.iter().all(|reference| reference.tempo.is_none()));
+        assert_eq!(references[0].events[0].event, "Meta(Tempo(u24(500001)))");
+    }
+}
@@ -0,0 +1,208 @@
+use crate::domain::{MidiReferenceSummary, ReferenceSlot, ReferenceTransposition};
+
+use super::analysis::{KeyMode, detect_key, parse_note_event, replace_decimal_after};
+use super::writer::parent_major_pitch_class;
+
+// Below this the detected key is a guess, and transposing from it would do more harm than good.
+const TRANSPOSE_KEY_CONFIDENCE_MIN: f64 = 0.6;
+
+/// Moves every pitched reference from its detected key into `key`/`scale` and records the
+/// shift on it, so a riff recorded in another key can be reused. Keys are matched by their
+/// parent major scale, which keeps an A minor riff untouched for C major, and the shift is the
+/// nearest one (-6..=5 semitones). Drum references, references without a confident key, and
+/// references already in the key are left alone. Returns how many were transposed.
+pub fn transpose_references_to_key(
+    references: &mut [MidiReferenceSummary],
+    key: &str,
+    scale: &str,
+) -> usize {
+    let Some(target_parent) = parent_major_pitch_class(key, scale) else {
+        return 0;
+    };
+    let mut transposed = 0;
+    for reference in references
+        .iter_mut()
+        .filter(|reference| reference.slot != ReferenceSlot::DrumPattern)
+        .filter(|reference| reference.transposition.is_none())
+    {
+        let mut pitch_class_counts = [0u32; 12];
+        for event in &reference.events {
+            if let Some((pitch, true)) = parse_note_event(&event.event) {
+                pitch_class_counts[usize::from(pitch % 12)] += 1;
+            }
+        }
+        let Some(detected) = detect_key(&pitch_class_counts)
+            .filter(|detected| detected.confidence >= TRANSPOSE_KEY_CONFIDENCE_MIN)
+        else {
+            continue;
+        };
+        let source_parent = match detected.mode {
+            KeyMode::Major => detected.tonic,
+            KeyMode::Minor => (detected.tonic + 3) % 12,
+        };
+        let mut semitones = ((12 + target_parent - source_parent) % 12) as i8;
+        if semitones > 5 {
+            semitones -= 12;
+        }
+        if semitones == 0 {
+            continue;
+        }
+
+        transpose_reference(reference, semitones);
+        reference.transposition = Some(ReferenceTransposition {
+            from_key: detected.name(),
+            to_key: format!("{} {}", key.trim(), scale.trim()),
+            semitones,
+        });
+        transposed += 1;
+    }
+    transposed
+}
+
+/// Shifts every note event and the pitch range by `semitones`. Notes pushed past the MIDI
+/// range move back an octave instead of being clamped onto one key.
+fn transpose_reference(reference: &mut MidiReferenceSummary, semitones: i8) {
+    let mut pitch_range: Option<(u8, u8)> = None;
+    for event in &mut reference.events {
+        let Some((pitch, _)) = parse_note_event(&event.event) else {
+            continue;
+        };
+        let shifted = shift_pitch(pitch, semitones);
+        let marker = if event.event.starts_with("LiveMidi ") {
+            "data1="
+        } else {
+            "key: u7("
+        };
+        if let Some(rewritten) = replace_decimal_after(&event.event, marker, u32::from(shifted)) {
+            event.event = rewritten;
+        }
+        pitch_range = Some(pitch_range.map_or((shifted, shifted), |(min, max)| {
+            (min.min(shifted), max.max(shifted))
+        }));
+    }
+    let (min_pitch, max_pitch) = pitch_range.unwrap_or((
+        shift_pitch(reference.min_pitch, semitones),
+        shift_pitch(reference.max_pitch, semitones),
+    ));
+    reference.min_pitch = min_pitch.min(max_pitch);
+    reference.max_pitch = min_pitch.max(max_pitch);
+}
+
+fn shift_pitch(pitch: u8, semitones: i8) -> u8 {
+    let mut shifted = i16::from(pitch) + i16::from(semitones);
+    while shifted > 127 {
+        shifted -= 12;
+    }
+    while shifted < 0 {
+        shifted += 12;
+    }
+    shifted as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::transpose_references_to_key;
+    use crate::domain::{MidiReferenceEvent, MidiReferenceSummary, ReferenceSlot, ReferenceSource};
+    use crate::infra::midi::analysis::parse_note_event;
+
+    fn reference(slot: ReferenceSlot, events: Vec<String>) -> MidiReferenceSummary {
+        MidiReferenceSummary {
+            slot,
+            source: ReferenceSource::Live,
+            file: None,
+            bars: 1,
+            note_count: events.len() as u32,
+            density_hint: 0.5,
+            min_pitch: 0,
+            max_pitch: 127,
+            events: events
+                .into_iter()
+                .enumerate()
+                .map(|(index, event)| MidiReferenceEvent {
+                    track: 0,
+                    absolute_tick: index as u32 * 240,
+                    delta_tick: 240,
+                    event,
+                })
+                .collect(),
+            transposition: None,
+            tempo: None,
+        }
+    }
+
+    // E natural minor, leaning on the tonic and fifth.
+    fn e_minor_riff() -> Vec<String> {
+        [64, 66, 67, 71, 64, 62, 60, 59, 64, 71]
+            .into_iter()
+            .map(|pitch| {
+                format!(
+                    "Midi {{ channel: u4(0), message: NoteOn {{ key: u7({pitch}), vel: u7(100) }} }}"
+                )
+            })
+            .collect()
+    }
+
+    fn pitches(reference: &MidiReferenceSummary) -> Vec<u8> {
+        reference
+            .events
+            .iter()
+            .filter_map(|event| parse_note_event(&event.event).map(|(pitch, _)| pitch))
+            .collect()
+    }
+
+    #[test]
+    fn riff_is_moved_to_the_nearest_matching_key() {
+        let mut references = vec![reference(ReferenceSlot::Melody, e_minor_riff())];
+
+        let transposed = transpose_references_to_key(&mut references, "A", "Minor (Aeolian)");
+
+        assert_eq!(transposed, 1);
+        assert_eq!(
+            pitches(&references[0]),
+            vec![69, 71, 72, 76, 69, 67, 65, 64, 69, 76]
+        );
+        assert_eq!((references[0].min_pitch, references[0].max_pitch), (64, 76));
+        let transposition = references[0]
+            .transposition
+            .as_ref()
+            .expect("transposition should be recorded");
+        assert_eq!(transposition.from_key, "E minor");
+        assert_eq!(transposition.to_key, "A Minor (Aeolian)");
+        assert_eq!(transposition.semitones, 5);
+    }
+
+    #[test]
+    fn relative_keys_drums_and_live_control_changes_are_left_alone() {
+        let mut references = vec![
+            reference(ReferenceSlot::Melody, e_minor_riff()),
+            reference(ReferenceSlot::DrumPattern, e_minor_riff()),
+        ];
+
+        assert_eq!(
+            transpose_references_to_key(&mut references, "G", "major"),
+            0
+        );
+        assert!(
+            references
+                .iter()
+                .all(|reference| reference.transposition.is_none())
+        );
+
+        let mut live = e_minor_riff();
+        live.push("LiveMidi status=0xB0 data1=64 data2=127".to_string());
+        let mut references = vec![reference(ReferenceSlot::Bassline, live)];
+        transpose_references_to_key(&mut references, "F#", "minor");
+
+        assert_eq!(
+            references[0].transposition.as_ref().map(|t| t.semitones),
+            Some(2)
+        );
+        assert_eq!(
+            references[0]
+                .events
+                .last()
+                .map(|event| event.event.as_str()),
+            Some("LiveMidi status=0xB0 data1=64 data2=127")
+        );
+    }
+}
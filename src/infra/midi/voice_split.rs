@@ -0,0 +1,114 @@
+use std::collections::HashSet;
+
+use crate::domain::{MidiReferenceSummary, ReferenceSlot};
+
+use super::force_monophonic;
+use super::monophonic::{onset_window, reference_notes, refresh_note_stats, retain_events};
+
+/// Splits a keyboard performance into its top line, as a Melody reference, and the voices below
+/// it, as a ChordProgression reference. `None` when the take has no notes under the top line.
+pub fn split_melody_and_chords(
+    reference: &MidiReferenceSummary,
+) -> Option<(MidiReferenceSummary, MidiReferenceSummary)> {
+    let notes = reference_notes(reference);
+    let window = onset_window(reference);
+
+    let mut melody_events = HashSet::new();
+    let mut chord_events = HashSet::new();
+    let mut cluster_start = 0;
+    while cluster_start < notes.len() {
+        let first_onset = notes[cluster_start].start_tick;
+        let cluster_end = notes[cluster_start..]
+            .iter()
+            .position(|note| note.start_tick - first_onset > window)
+            .map_or(notes.len(), |offset| cluster_start + offset);
+        let cluster = &notes[cluster_start..cluster_end];
+        let top = cluster
+            .iter()
+            .max_by_key(|note| note.pitch)
+            .expect("clusters are never empty");
+        for note in cluster {
+            let events = if note.on_index == top.on_index {
+                &mut melody_events
+            } else {
+                &mut chord_events
+            };
+            events.insert(note.on_index);
+            events.extend(note.off_index);
+        }
+        cluster_start = cluster_end;
+    }
+    if chord_events.is_empty() {
+        return None;
+    }
+
+    let mut melody = MidiReferenceSummary {
+        slot: ReferenceSlot::Melody,
+        ..reference.clone()
+    };
+    retain_events(&mut melody, &chord_events);
+    force_monophonic(&mut melody);
+
+    let mut chords = MidiReferenceSummary {
+        slot: ReferenceSlot::ChordProgression,
+        ..reference.clone()
+    };
+    retain_events(&mut chords, &melody_events);
+    refresh_note_stats(&mut chords);
+
+    Some((melody, chords))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::split_melody_and_chords;
+    use crate::domain::{MidiReferenceEvent, MidiReferenceSummary, ReferenceSlot, ReferenceSource};
+
+    fn note(absolute_tick: u32, pitch: u8, velocity: u8) -> MidiReferenceEvent {
+        MidiReferenceEvent {
+            track: 0,
+            absolute_tick,
+            delta_tick: 0,
+            event: format!(
+                "Midi {{ channel: u4(0), message: NoteOn {{ key: u7({pitch}), vel: u7({velocity}) }} }}"
+            ),
+        }
+    }
+
+    #[test]
+    fn top_line_becomes_melody_and_lower_voices_become_chords() {
+        let mut events = Vec::new();
+        for (start, top) in [(0, 72), (960, 74)] {
+            for pitch in [48, 52, 55, top] {
+                events.push(note(start, pitch, 100));
+            }
+            for pitch in [48, 52, 55, top] {
+                events.push(note(start + 960, pitch, 0));
+            }
+        }
+        events.sort_by_key(|event| event.absolute_tick);
+        let take = MidiReferenceSummary {
+            slot: ReferenceSlot::Melody,
+            source: ReferenceSource::Live,
+            file: None,
+            bars: 1,
+            note_count: 8,
+            density_hint: 0.0,
+            min_pitch: 48,
+            max_pitch: 74,
+            events,
+            transposition: None,
+            tempo: None,
+        };
+
+        let (melody, chords) = split_melody_and_chords(&take).expect("take has lower voices");
+
+        assert_eq!(melody.slot, ReferenceSlot::Melody);
+        assert_eq!(melody.note_count, 2);
+        assert_eq!((melody.min_pitch, melody.max_pitch), (72, 74));
+        assert_eq!(chords.slot, ReferenceSlot::ChordProgression);
+        assert_eq!(chords.note_count, 6);
+        assert_eq!((chords.min_pitch, chords.max_pitch), (48, 55));
+        assert!(split_melody_and_chords(&melody).is_none());
+    }
+}
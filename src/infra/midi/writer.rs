@@ -0,0 +1,572 @@
+use std::fs;
+use std::path::Path;
+
+use crate::domain::{
+    BEATS_PER_BAR, ChordLabel, GeneratedControlEvent, GeneratedNote, GenerationMode,
+    GenerationRequest, ModeParamSpec, PITCH_BEND_MAX, PITCH_BEND_MIN, TimeSignature,
+};
+use midly::num::{u4, u7, u14, u15, u24, u28};
+use midly::{
+    Format, Header, MetaMessage, MidiMessage, PitchBend, Smf, Timing, TrackEvent, TrackEventKind,
+};
+use thiserror::Error;
+
+const MICROSECONDS_PER_MINUTE: u32 = 60_000_000;
+const MAX_TICKS_PER_QUARTER: u16 = 0x7FFF;
+const MAX_TEMPO_MICROSECONDS: u32 = 0x00FF_FFFF;
+const MAX_DELTA_TICKS: u32 = 0x0FFF_FFFF;
+const MIDI_CLOCKS_PER_CLICK: u8 = 24;
+const THIRTY_SECONDS_PER_QUARTER: u8 = 8;
+const GM_DRUM_CHANNEL: u8 = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum MidiWriteError {
+    #[error("ticks per quarter note must be in 1..={MAX_TICKS_PER_QUARTER} (got {value})")]
+    InvalidTicksPerQuarter { value: u16 },
+    #[error("bpm must be greater than 0")]
+    InvalidTempo,
+    #[error("failed to write MIDI file: {message}")]
+    Io { message: String },
+}
+
+/// Key signature as written to a MIDI file: sharps when positive, flats when negative.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeySignature {
+    pub sharps: i8,
+    pub minor: bool,
+}
+
+impl KeySignature {
+    /// Parses a tonic such as `F#` or `Bb` with a scale name. Church modes are written with the
+    /// accidentals of their parent major scale; unknown scales yield `None`.
+    pub fn from_key_and_scale(key: &str, scale: &str) -> Option<Self> {
+        let tonic = parse_tonic(key.trim())?;
+        let (major_offset, minor) = scale_offset_from_parent_major(scale)?;
+        let major_pitch_class = (tonic.pitch_class + major_offset) % 12;
+        // Walking the circle of fifths: each fifth up adds a sharp.
+        let mut sharps = ((major_pitch_class * 7) % 12) as i8;
+        if sharps > 6 {
+            sharps -= 12;
+        }
+        // Respect the spelling the user chose for enharmonic keys such as F# and Gb.
+        if tonic.accidental > 0 && sharps < 0 && sharps + 12 <= 7 {
+            sharps += 12;
+        } else if tonic.accidental < 0 && sharps > 0 && sharps - 12 >= -7 {
+            sharps -= 12;
+        }
+        Some(Self { sharps, minor })
+    }
+}
+
+/// Tempo, meter, and key written to the conductor track so DAW imports land on the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MidiConductor {
+    pub bpm: u16,
+    /// Written as 4/4 when unknown or not representable in a MIDI file.
+    pub time_signature: Option<TimeSignature>,
+    pub key_signature: Option<KeySignature>,
+}
+
+impl MidiConductor {
+    pub fn new(bpm: u16) -> Self {
+        Self {
+            bpm,
+            time_signature: None,
+            key_signature: None,
+        }
+    }
+
+    /// The request's tempo and key, with the host meter it was generated against.
+    pub fn for_request(request: &GenerationRequest) -> Self {
+        Self::for_settings(
+            request.mode,
+            request.params.bpm,
+            &request.params.key,
+            &request.params.scale,
+            request
+                .daw_context
+                .as_ref()
+                .and_then(|context| context.time_signature),
+        )
+    }
+
+    /// Modes that ignore key and scale get no key signature.
+    pub fn for_settings(
+        mode: GenerationMode,
+        bpm: u16,
+        key: &str,
+        scale: &str,
+        time_signature: Option<TimeSignature>,
+    ) -> Self {
+        let key_signature = if ModeParamSpec::for_mode(mode).key {
+            KeySignature::from_key_and_scale(key, scale)
+        } else {
+            None
+        };
+        Self {
+            bpm,
+            time_signature,
+            key_signature,
+        }
+    }
+}
+
+pub fn write_notes_to_midi_file(
+    path: impl AsRef<Path>,
+    notes: &[GeneratedNote],
+    control_events: &[GeneratedControlEvent],
+    chords: &[ChordLabel],
+    ticks_per_quarter: u16,
+    conductor: &MidiConductor,
+    program: Option<u8>,
+) -> Result<(), MidiWriteError> {
+    let bytes = encode_notes_as_smf(
+        notes,
+        control_events,
+        chords,
+        ticks_per_quarter,
+        conductor,
+        program,
+    )?;
+    fs::write(path, bytes).map_err(|error| MidiWriteError::Io {
+        message: error.to_string(),
+    })
+}
+
+/// Encodes notes and controller events as a two-track Standard MIDI File. The first track is
+/// the conductor: tempo, time signature, and key signature at tick 0, plus chord labels as
+/// marker meta events at the start of their bar. With a General MIDI `program`, every note
+/// channel except percussion starts with a program change.
+pub fn encode_notes_as_smf(
+    notes: &[GeneratedNote],
+    control_events: &[GeneratedControlEvent],
+    chords: &[ChordLabel],
+    ticks_per_quarter: u16,
+    conductor: &MidiConductor,
+    program: Option<u8>,
+) -> Result<Vec<u8>, MidiWriteError> {
+    let bpm = conductor.bpm;
+    if ticks_per_quarter == 0 || ticks_per_quarter > MAX_TICKS_PER_QUARTER {
+        return Err(MidiWriteError::InvalidTicksPerQuarter {
+            value: ticks_per_quarter,
+        });
+    }
+    if bpm == 0 {
+        return Err(MidiWriteError::InvalidTempo);
+    }
+
+    let ticks_per_bar = u32::from(ticks_per_quarter) * BEATS_PER_BAR;
+    let mut conductor_events = Vec::with_capacity(chords.len() + 3);
+    conductor_events.push((
+        0,
+        0u8,
+        TrackEventKind::Meta(MetaMessage::Tempo(u24::new(
+            (MICROSECONDS_PER_MINUTE / u32::from(bpm)).min(MAX_TEMPO_MICROSECONDS),
+        ))),
+    ));
+    let (numerator, denominator_power) = conductor
+        .time_signature
+        .and_then(midi_time_signature)
+        .unwrap_or((4, 2));
+    conductor_events.push((
+        0,
+        0u8,
+        TrackEventKind::Meta(MetaMessage::TimeSignature(
+            numerator,
+            denominator_power,
+            MIDI_CLOCKS_PER_CLICK,
+            THIRTY_SECONDS_PER_QUARTER,
+        )),
+    ));
+    if let Some(key_signature) = conductor.key_signature {
+        conductor_events.push((
+            0,
+            0u8,
+            TrackEventKind::Meta(MetaMessage::KeySignature(
+                key_signature.sharps,
+                key_signature.minor,
+            )),
+        ));
+    }
+    for chord in chords {
+        conductor_events.push((
+            u32::from(chord.bar.saturating_sub(1)).saturating_mul(ticks_per_bar),
+            1u8,
+            TrackEventKind::Meta(MetaMessage::Marker(chord.symbol.trim().as_bytes())),
+        ));
+    }
+    conductor_events.sort_by_key(|(tick, order, _)| (*tick, *order));
+
+    let mut timed_events = Vec::with_capacity(notes.len() * 2 + control_events.len() + 1);
+    if let Some(program) = program {
+        let mut channels = notes
+            .iter()
+            .map(|note| note.channel.clamp(1, 16))
+            .filter(|channel| *channel != GM_DRUM_CHANNEL)
+            .collect::<Vec<_>>();
+        channels.sort_unstable();
+        channels.dedup();
+        for channel in channels {
+            timed_events.push((
+                0,
+                0u8,
+                TrackEventKind::Midi {
+                    channel: u4::new(channel - 1),
+                    message: MidiMessage::ProgramChange {
+                        program: u7::new(program.min(127)),
+                    },
+                },
+            ));
+        }
+    }
+    for event in control_events {
+        let channel = u4::new(event.channel().clamp(1, 16) - 1);
+        let message = match *event {
+            GeneratedControlEvent::ControlChange {
+                controller, value, ..
+            } => MidiMessage::Controller {
+                controller: u7::new(controller.min(127)),
+                value: u7::new(value.min(127)),
+            },
+            GeneratedControlEvent::PitchBend { value, .. } => MidiMessage::PitchBend {
+                bend: PitchBend(u14::new(
+                    (value.clamp(PITCH_BEND_MIN, PITCH_BEND_MAX) - PITCH_BEND_MIN) as u16,
+                )),
+            },
+        };
+        timed_events.push((event.tick(), 2u8, TrackEventKind::Midi { channel, message }));
+    }
+    for note in notes {
+        let channel = u4::new(note.channel.clamp(1, 16) - 1);
+        let key = u7::new(note.pitch.min(127));
+        timed_events.push((
+            note.start_tick,
+            3u8,
+            TrackEventKind::Midi {
+                channel,
+                message: MidiMessage::NoteOn {
+                    key,
+                    vel: u7::new(note.velocity.clamp(1, 127)),
+                },
+            },
+        ));
+        timed_events.push((
+            note.start_tick.saturating_add(note.duration_tick),
+            1u8,
+            TrackEventKind::Midi {
+                channel,
+                message: MidiMessage::NoteOff {
+                    key,
+                    vel: u7::new(0),
+                },
+            },
+        ));
+    }
+    // Note-offs sort ahead of note-ons on the same tick so repeated pitches retrigger cleanly;
+    // controllers sit between so a new note starts with its expression already applied.
+    timed_events.sort_by_key(|(tick, order, _)| (*tick, *order));
+
+    let mut smf = Smf::new(Header::new(
+        Format::Parallel,
+        Timing::Metrical(u15::new(ticks_per_quarter)),
+    ));
+    smf.tracks.push(delta_encoded_track(conductor_events));
+    smf.tracks.push(delta_encoded_track(timed_events));
+
+    let mut bytes = Vec::new();
+    smf.write_std(&mut bytes)
+        .map_err(|error| MidiWriteError::Io {
+            message: error.to_string(),
+        })?;
+    Ok(bytes)
+}
+
+fn delta_encoded_track<'a>(
+    timed_events: Vec<(u32, u8, TrackEventKind<'a>)>,
+) -> Vec<TrackEvent<'a>> {
+    let mut track = Vec::with_capacity(timed_events.len() + 1);
+    let mut previous_tick = 0u32;
+    for (tick, _, kind) in timed_events {
+        track.push(TrackEvent {
+            delta: u28::new(tick.saturating_sub(previous_tick).min(MAX_DELTA_TICKS)),
+            kind,
+        });
+        previous_tick = tick;
+    }
+    track.push(TrackEvent {
+        delta: u28::new(0),
+        kind: TrackEventKind::Meta(MetaMessage::EndOfTrack),
+    });
+    track
+}
+
+/// Numerator and power-of-two denominator exponent, or `None` when MIDI cannot express it.
+fn midi_time_signature(signature: TimeSignature) -> Option<(u8, u8)> {
+    let numerator = u8::try_from(signature.numerator)
+        .ok()
+        .filter(|numerator| *numerator > 0)?;
+    let denominator = signature.denominator;
+    (denominator.is_power_of_two() && denominator <= 64)
+        .then(|| (numerator, denominator.trailing_zeros() as u8))
+}
+
+struct Tonic {
+    pitch_class: u8,
+    accidental: i8,
+}
+
+/// Pitch class of the major scale that shares the key's notes, e.g. 0 for `A` `minor`.
+pub(super) fn parent_major_pitch_class(key: &str, scale: &str) -> Option<u8> {
+    let tonic = parse_tonic(key.trim())?;
+    let (major_offset, _) = scale_offset_from_parent_major(scale)?;
+    Some((tonic.pitch_class + major_offset) % 12)
+}
+
+/// Semitones from the scale's tonic up to its parent major tonic, and whether it sounds minor.
+fn scale_offset_from_parent_major(scale: &str) -> Option<(u8, bool)> {
+    let offset = match scale.trim().to_ascii_lowercase().as_str() {
+        "major" | "ionian" => (0, false),
+        "minor" | "aeolian" | "minor (aeolian)" | "natural minor" | "harmonic minor"
+        | "melodic minor" => (3, true),
+        "dorian" => (10, true),
+        "phrygian" => (8, true),
+        "lydian" => (7, false),
+        "mixolydian" => (5, false),
+        "locrian" => (1, true),
+        _ => return None,
+    };
+    Some(offset)
+}
+
+fn parse_tonic(key: &str) -> Option<Tonic> {
+    let mut chars = key.chars();
+    let natural = match chars.next()?.to_ascii_uppercase() {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    let accidental = match chars.as_str() {
+        "" => 0,
+        "#" | "♯" => 1,
+        "b" | "♭" => -1,
+        _ => return None,
+    };
+    Some(Tonic {
+        pitch_class: ((natural + 12 + accidental) % 12) as u8,
+        accidental: accidental as i8,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeySignature, MidiConductor, MidiWriteError, encode_notes_as_smf};
+    use crate::domain::{ChordLabel, GeneratedControlEvent, GeneratedNote, TimeSignature};
+    use crate::infra::midi::parse_midi_reference;
+    use midly::num::{u7, u14, u24};
+    use midly::{MetaMessage, MidiMessage, PitchBend, Smf, TrackEventKind};
+
+    fn note(pitch: u8, start_tick: u32, duration_tick: u32) -> GeneratedNote {
+        GeneratedNote {
+            pitch,
+            start_tick,
+            duration_tick,
+            velocity: 96,
+            channel: 1,
+        }
+    }
+
+    #[test]
+    fn encoded_notes_round_trip_through_the_loader() {
+        let notes = vec![note(60, 0, 480), note(64, 480, 480), note(67, 1920, 480)];
+
+        let bytes = encode_notes_as_smf(&notes, &[], &[], 480, &MidiConductor::new(120), None)
+            .expect("notes should encode");
+        let reference = parse_midi_reference(&bytes).expect("encoded file should parse");
+
+        assert_eq!(reference.summary.note_count, 3);
+        assert_eq!(reference.summary.min_pitch, 60);
+        assert_eq!(reference.summary.max_pitch, 67);
+        assert_eq!(reference.summary.bars, 2);
+    }
+
+    #[test]
+    fn control_events_are_written_between_note_offs_and_note_ons() {
+        let notes = vec![note(60, 0, 480), note(62, 480, 480)];
+        let control_events = vec![
+            GeneratedControlEvent::PitchBend {
+                tick: 480,
+                value: -8192,
+                channel: 1,
+            },
+            GeneratedControlEvent::ControlChange {
+                tick: 0,
+                controller: 1,
+                value: 64,
+                channel: 1,
+            },
+        ];
+
+        let bytes = encode_notes_as_smf(
+            &notes,
+            &control_events,
+            &[],
+            480,
+            &MidiConductor::new(120),
+            None,
+        )
+        .expect("events should encode");
+        let smf = Smf::parse(&bytes).expect("encoded file should parse");
+        let messages = smf.tracks[1]
+            .iter()
+            .filter_map(|event| match event.kind {
+                TrackEventKind::Midi { message, .. } => Some(message),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            messages,
+            vec![
+                MidiMessage::Controller {
+                    controller: u7::new(1),
+                    value: u7::new(64),
+                },
+                MidiMessage::NoteOn {
+                    key: u7::new(60),
+                    vel: u7::new(96),
+                },
+                MidiMessage::NoteOff {
+                    key: u7::new(60),
+                    vel: u7::new(0),
+                },
+                MidiMessage::PitchBend {
+                    bend: PitchBend(u14::new(0)),
+                },
+                MidiMessage::NoteOn {
+                    key: u7::new(62),
+                    vel: u7::new(96),
+                },
+                MidiMessage::NoteOff {
+                    key: u7::new(62),
+                    vel: u7::new(0),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn chord_labels_are_written_as_markers_at_bar_starts() {
+        let notes = vec![note(57, 0, 1920), note(62, 1920, 1920)];
+        let chords = vec![
+            ChordLabel {
+                bar: 2,
+                symbol: "D7".to_string(),
+            },
+            ChordLabel {
+                bar: 1,
+                symbol: " Am7 ".to_string(),
+            },
+        ];
+
+        let bytes = encode_notes_as_smf(&notes, &[], &chords, 480, &MidiConductor::new(120), None)
+            .expect("should encode");
+        let smf = Smf::parse(&bytes).expect("encoded file should parse");
+        let mut tick = 0u32;
+        let mut markers = Vec::new();
+        for event in &smf.tracks[0] {
+            tick += event.delta.as_int();
+            if let TrackEventKind::Meta(MetaMessage::Marker(text)) = event.kind {
+                markers.push((tick, String::from_utf8_lossy(text).into_owned()));
+            }
+        }
+
+        assert_eq!(
+            markers,
+            vec![(0, "Am7".to_string()), (1920, "D7".to_string())]
+        );
+    }
+
+    #[test]
+    fn conductor_track_carries_tempo_meter_and_key() {
+        let conductor = MidiConductor {
+            bpm: 90,
+            time_signature: Some(TimeSignature {
+                numerator: 6,
+                denominator: 8,
+            }),
+            key_signature: KeySignature::from_key_and_scale("Bb", "minor"),
+        };
+
+        let bytes = encode_notes_as_smf(&[note(58, 0, 480)], &[], &[], 480, &conductor, None)
+            .expect("encode");
+        let smf = Smf::parse(&bytes).expect("encoded file should parse");
+        let conductor_meta = smf.tracks[0]
+            .iter()
+            .filter_map(|event| match event.kind {
+                TrackEventKind::Meta(message) => Some(message),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(smf.tracks.len(), 2);
+        assert_eq!(
+            conductor_meta,
+            vec![
+                MetaMessage::Tempo(u24::new(666_666)),
+                MetaMessage::TimeSignature(6, 3, 24, 8),
+                MetaMessage::KeySignature(-5, true),
+                MetaMessage::EndOfTrack,
+            ]
+        );
+        assert_eq!(
+            KeySignature::from_key_and_scale("F#", "major"),
+            Some(KeySignature {
+                sharps: 6,
+                minor: false
+            })
+        );
+        assert_eq!(KeySignature::from_key_and_scale("C", "blues"), None);
+    }
+
+    #[test]
+    fn program_changes_open_each_melodic_channel() {
+        let mut drum = note(36, 0, 120);
+        drum.channel = 10;
+        let mut bass = note(40, 480, 240);
+        bass.channel = 3;
+        let notes = vec![note(60, 0, 480), drum, bass];
+
+        let bytes = encode_notes_as_smf(&notes, &[], &[], 480, &MidiConductor::new(120), Some(32))
+            .expect("notes should encode");
+        let smf = Smf::parse(&bytes).expect("encoded file should parse");
+        let program_changes = smf.tracks[1]
+            .iter()
+            .filter_map(|event| match event.kind {
+                TrackEventKind::Midi {
+                    channel,
+                    message: MidiMessage::ProgramChange { program },
+                } => Some((event.delta.as_int(), channel.as_int(), program.as_int())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(program_changes, vec![(0, 0, 32), (0, 2, 32)]);
+    }
+
+    #[test]
+    fn invalid_resolution_and_tempo_are_rejected() {
+        assert_eq!(
+            encode_notes_as_smf(&[], &[], &[], 0, &MidiConductor::new(120), None),
+            Err(MidiWriteError::InvalidTicksPerQuarter { value: 0 })
+        );
+        assert_eq!(
+            encode_notes_as_smf(&[], &[], &[], 480, &MidiConductor::new(0), None),
+            Err(MidiWriteError::InvalidTempo)
+        );
+    }
+}
@@ -1,4 +1,7 @@
 pub mod app;
 pub mod domain;
 pub mod infra;
+#[cfg(feature = "clap")]
 pub mod plugin;
+#[cfg(test)]
+mod test_support;
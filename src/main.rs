@@ -1,12 +1,31 @@
+use std::process::ExitCode;
+
+mod cli;
 mod ui;
 
-fn main() {
-    let is_helper = std::env::args().any(|arg| arg == "--gpui-helper");
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let is_helper = args.iter().any(|arg| arg == "--gpui-helper");
 
     if is_helper {
-        ui::run_gpui_helper();
-        return;
+        #[cfg(feature = "gui")]
+        {
+            ui::run_gpui_helper();
+            return ExitCode::SUCCESS;
+        }
+        #[cfg(not(feature = "gui"))]
+        {
+            eprintln!("This sonant binary was built without the `gui` feature.");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    if let Some(code) = cli::run(&args) {
+        return code;
     }
 
-    eprintln!("Sonant helper binary. Run with --gpui-helper.");
+    eprintln!(
+        "Sonant helper binary. Run with --gpui-helper, `sonant replay <request.json>`, `sonant doctor`, `sonant bench`, `sonant batch`, or `sonant apply`."
+    );
+    ExitCode::SUCCESS
 }
@@ -0,0 +1,288 @@
+use crate::app::{AppliedClip, AppliedNoteExpression};
+
+use super::TransportSnapshot;
+
+// Playhead drift below this is treated as continuous playback rather than a seek.
+const PLAYHEAD_JUMP_TOLERANCE_BEATS: f64 = 1.0e-3;
+
+/// One output event from the clip; `channel` is 0-based.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum ClipOutput {
+    Midi([u8; 3]),
+    NoteOn {
+        channel: u8,
+        key: u8,
+        velocity: u8,
+        expression: Option<AppliedNoteExpression>,
+    },
+    NoteOff {
+        channel: u8,
+        key: u8,
+    },
+    /// Cuts the voice immediately, e.g. an open hi-hat silenced by a closed one.
+    NoteChoke {
+        channel: u8,
+        key: u8,
+    },
+}
+
+/// Loops the applied clip on the plugin output in sync with the host transport.
+pub(super) struct AppliedClipPlayer {
+    clip: Option<Box<AppliedClip>>,
+    // Bit K of entry N set => key K is sounding on MIDI channel N+1.
+    sounding_notes: [u128; 16],
+    next_block_beat: Option<f64>,
+}
+
+impl AppliedClipPlayer {
+    pub(super) fn new() -> Self {
+        Self {
+            clip: None,
+            sounding_notes: [0; 16],
+            next_block_beat: None,
+        }
+    }
+
+    /// Swaps in a new clip and releases notes held by the old one. The old clip is handed back
+    /// so it can be dropped off the audio thread.
+    pub(super) fn replace_clip(
+        &mut self,
+        clip: Option<Box<AppliedClip>>,
+        emit: impl FnMut(u32, ClipOutput),
+    ) -> Option<Box<AppliedClip>> {
+        self.release_sounding_notes(emit);
+        self.next_block_beat = None;
+        std::mem::replace(&mut self.clip, clip)
+    }
+
+    pub(super) fn process(
+        &mut self,
+        transport: TransportSnapshot,
+        frames_count: u32,
+        mut emit: impl FnMut(u32, ClipOutput),
+    ) {
+        if self.clip.is_none() {
+            return;
+        }
+        let beats_per_sample = transport
+            .tempo_bpm
+            .filter(|_| transport.is_playing && transport.sample_rate_hz > 0.0)
+            .map(|tempo_bpm| tempo_bpm / 60.0 / transport.sample_rate_hz);
+        let Some(beats_per_sample) = beats_per_sample else {
+            self.release_sounding_notes(&mut emit);
+            self.next_block_beat = None;
+            return;
+        };
+
+        let start_beat = transport.playhead_ppq_at_block_start;
+        let end_beat = start_beat + f64::from(frames_count) * beats_per_sample;
+        if self
+            .next_block_beat
+            .is_some_and(|expected| (start_beat - expected).abs() > PLAYHEAD_JUMP_TOLERANCE_BEATS)
+        {
+            self.release_sounding_notes(&mut emit);
+        }
+        self.next_block_beat = Some(end_beat);
+
+        let Self {
+            clip,
+            sounding_notes,
+            ..
+        } = self;
+        let Some(clip) = clip.as_deref() else {
+            return;
+        };
+        let last_frame = frames_count.saturating_sub(1);
+        clip.for_each_event_between(start_beat, end_beat, |beat, event| {
+            let time = (((beat - start_beat) / beats_per_sample) as u32).min(last_frame);
+            let [status, key, velocity] = event.data;
+            // Controllers and pitch bends pass through as raw MIDI in either output mode.
+            if !event.is_note_event() {
+                emit(time, ClipOutput::Midi(event.data));
+                return;
+            }
+            let channel = status & 0x0F;
+            let key_bit = 1u128 << (key & 0x7F);
+            let is_note_on = event.is_note_on();
+            if is_note_on {
+                sounding_notes[usize::from(channel)] |= key_bit;
+            } else {
+                sounding_notes[usize::from(channel)] &= !key_bit;
+            }
+
+            let output = match (clip.note_expressions, is_note_on) {
+                (false, _) => ClipOutput::Midi(event.data),
+                (true, true) => ClipOutput::NoteOn {
+                    channel,
+                    key,
+                    velocity,
+                    expression: event.expression,
+                },
+                (true, false) if event.choke => ClipOutput::NoteChoke { channel, key },
+                (true, false) => ClipOutput::NoteOff { channel, key },
+            };
+            emit(time, output);
+        });
+    }
+
+    fn release_sounding_notes(&mut self, mut emit: impl FnMut(u32, ClipOutput)) {
+        let note_expressions = self
+            .clip
+            .as_deref()
+            .is_some_and(|clip| clip.note_expressions);
+        for (channel, keys) in self.sounding_notes.iter_mut().enumerate() {
+            while *keys != 0 {
+                let key = keys.trailing_zeros() as u8;
+                *keys &= !(1u128 << key);
+                let channel = channel as u8;
+                emit(
+                    0,
+                    if note_expressions {
+                        ClipOutput::NoteOff { channel, key }
+                    } else {
+                        ClipOutput::Midi([0x80 | channel, key, 0])
+                    },
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AppliedClipPlayer, ClipOutput};
+    use crate::app::{AppliedClip, AppliedClipEvent, AppliedNoteExpression};
+    use crate::plugin::clap_adapter::TransportSnapshot;
+
+    const EXPRESSION: AppliedNoteExpression = AppliedNoteExpression {
+        volume: 0.9,
+        brightness: 0.6,
+    };
+
+    fn clip(note_expressions: bool) -> Box<AppliedClip> {
+        Box::new(AppliedClip {
+            length_beats: 4.0,
+            events: vec![
+                AppliedClipEvent {
+                    beat: 0.0,
+                    data: [0x90, 60, 100],
+                    expression: Some(EXPRESSION),
+                    choke: false,
+                },
+                AppliedClipEvent {
+                    beat: 2.0,
+                    data: [0x80, 60, 0],
+                    expression: None,
+                    choke: false,
+                },
+            ],
+            note_expressions,
+        })
+    }
+
+    // 120 BPM at 48 kHz: one beat every 24_000 samples.
+    fn playing_at(playhead_ppq: f64) -> TransportSnapshot {
+        TransportSnapshot {
+            is_playing: true,
+            is_loop_active: false,
+            playhead_ppq_at_block_start: playhead_ppq,
+            tempo_bpm: Some(120.0),
+            sample_rate_hz: 48_000.0,
+        }
+    }
+
+    #[test]
+    fn clip_events_are_scheduled_at_sample_offsets_within_the_block() {
+        let mut player = AppliedClipPlayer::new();
+        player.replace_clip(Some(clip(false)), |_, _| {});
+        let mut emitted = Vec::new();
+
+        player.process(playing_at(3.5), 24_000, |time, output| {
+            emitted.push((time, output))
+        });
+
+        assert_eq!(emitted, vec![(12_000, ClipOutput::Midi([0x90, 60, 100]))]);
+    }
+
+    #[test]
+    fn sounding_notes_are_released_when_transport_stops() {
+        let mut player = AppliedClipPlayer::new();
+        player.replace_clip(Some(clip(false)), |_, _| {});
+        player.process(playing_at(0.0), 512, |_, _| {});
+        let mut emitted = Vec::new();
+
+        player.process(
+            TransportSnapshot {
+                is_playing: false,
+                ..playing_at(0.1)
+            },
+            512,
+            |time, output| emitted.push((time, output)),
+        );
+
+        assert_eq!(emitted, vec![(0, ClipOutput::Midi([0x80, 60, 0]))]);
+    }
+
+    #[test]
+    fn controller_events_do_not_hold_notes_and_pass_through_as_midi() {
+        let mut player = AppliedClipPlayer::new();
+        let mut clip = clip(true);
+        clip.events.insert(
+            0,
+            AppliedClipEvent {
+                beat: 0.0,
+                data: [0xB0, 64, 127],
+                expression: None,
+                choke: false,
+            },
+        );
+        player.replace_clip(Some(clip), |_, _| {});
+        let mut emitted = Vec::new();
+
+        player.process(playing_at(0.0), 24_000, |_, output| emitted.push(output));
+        player.replace_clip(None, |_, output| emitted.push(output));
+
+        assert_eq!(
+            emitted,
+            vec![
+                ClipOutput::Midi([0xB0, 64, 127]),
+                ClipOutput::NoteOn {
+                    channel: 0,
+                    key: 60,
+                    velocity: 100,
+                    expression: Some(EXPRESSION),
+                },
+                ClipOutput::NoteOff {
+                    channel: 0,
+                    key: 60
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn expression_clips_emit_note_events_with_expression() {
+        let mut player = AppliedClipPlayer::new();
+        player.replace_clip(Some(clip(true)), |_, _| {});
+        let mut emitted = Vec::new();
+
+        player.process(playing_at(0.0), 48_000, |_, output| emitted.push(output));
+        player.replace_clip(None, |_, output| emitted.push(output));
+
+        assert_eq!(
+            emitted,
+            vec![
+                ClipOutput::NoteOn {
+                    channel: 0,
+                    key: 60,
+                    velocity: 100,
+                    expression: Some(EXPRESSION),
+                },
+                ClipOutput::NoteOff {
+                    channel: 0,
+                    key: 60
+                },
+            ]
+        );
+    }
+}
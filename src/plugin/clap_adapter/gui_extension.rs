@@ -6,15 +6,99 @@ use std::path::PathBuf;
 use std::process::{Child, Command, Stdio};
 use std::time::{Duration, Instant};
 
-use crate::app::LiveInputEvent;
 #[cfg(target_family = "unix")]
-use crate::app::{LIVE_INPUT_IPC_SOCKET_ENV, LiveInputIpcSender};
-
-use super::SonantPluginMainThread;
+use crate::app::{
+    HELPER_CONTROL_IPC_SOCKET_ENV, HelperControlIpcSource, INPUT_TRACK_LAYOUT_ENV,
+    LIVE_INPUT_IPC_SOCKET_ENV, LiveInputIpcSender, PROJECT_MODEL_ENV,
+};
+use crate::app::{
+    HELPER_WINDOW_MIN_HEIGHT, HELPER_WINDOW_MIN_WIDTH, HELPER_WINDOW_SIZE_ENV,
+    HelperControlMessage, HostTransportContext, InputTrackLayout, LiveInputEvent,
+    QueueOverflowMetrics,
+};
+use crate::domain::ModelRef;
+
+use super::{SonantPluginMainThread, audit_blocking_call};
+
+const DEFAULT_GUI_LOGICAL_WIDTH: u32 = 800;
+const DEFAULT_GUI_LOGICAL_HEIGHT: u32 = 640;
 
 #[derive(Default)]
 pub(super) struct SonantGuiController {
     state: HelperState,
+    scaling: GuiScaling,
+}
+
+/// Tracks the GUI size in logical pixels and converts it for hosts that talk in physical pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct GuiScaling {
+    scale: f64,
+    logical_width: u32,
+    logical_height: u32,
+    // Win32 and X11 report sizes in physical pixels; Cocoa uses logical points.
+    uses_physical_pixels: bool,
+}
+
+impl Default for GuiScaling {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            logical_width: DEFAULT_GUI_LOGICAL_WIDTH,
+            logical_height: DEFAULT_GUI_LOGICAL_HEIGHT,
+            uses_physical_pixels: false,
+        }
+    }
+}
+
+impl GuiScaling {
+    fn set_api(&mut self, api_type: GuiApiType) {
+        self.uses_physical_pixels = api_type != GuiApiType::COCOA;
+    }
+
+    fn set_scale(&mut self, scale: f64) -> bool {
+        // Logical-pixel APIs resolve the backing scale from the OS per display instead.
+        if !self.uses_physical_pixels || !scale.is_finite() || scale <= 0.0 {
+            return false;
+        }
+        self.scale = scale;
+        true
+    }
+
+    fn host_size(&self) -> GuiSize {
+        GuiSize {
+            width: self.to_host_pixels(self.logical_width),
+            height: self.to_host_pixels(self.logical_height),
+        }
+    }
+
+    fn set_host_size(&mut self, size: GuiSize) {
+        self.logical_width = self
+            .to_logical_pixels(size.width)
+            .max(HELPER_WINDOW_MIN_WIDTH);
+        self.logical_height = self
+            .to_logical_pixels(size.height)
+            .max(HELPER_WINDOW_MIN_HEIGHT);
+    }
+
+    fn to_host_pixels(&self, logical: u32) -> u32 {
+        if self.uses_physical_pixels {
+            (f64::from(logical) * self.scale).round() as u32
+        } else {
+            logical
+        }
+    }
+
+    fn to_logical_pixels(&self, host: u32) -> u32 {
+        if self.uses_physical_pixels {
+            (f64::from(host) / self.scale).round() as u32
+        } else {
+            host
+        }
+    }
+
+    fn helper_window_size_env_value(&self) -> String {
+        format!("{}x{}", self.logical_width, self.logical_height)
+    }
 }
 
 #[derive(Default)]
@@ -22,9 +106,21 @@ struct HelperState {
     child: Option<Child>,
     #[cfg(target_family = "unix")]
     live_input_sender: Option<LiveInputIpcSender>,
+    #[cfg(target_family = "unix")]
+    control_source: Option<HelperControlIpcSource>,
+    relayed: RelayedHostState,
     launched_at: Option<Instant>,
 }
 
+/// Host state last delivered to this helper instance; `None` until a send succeeds, so a value
+/// that could not be sent yet (e.g. before the helper bound its socket) is retried next call.
+#[derive(Default)]
+struct RelayedHostState {
+    overflow_metrics: Option<QueueOverflowMetrics>,
+    host_context: Option<HostTransportContext>,
+    host_track_name: Option<Option<String>>,
+}
+
 impl PluginGuiImpl for SonantPluginMainThread<'_> {
     fn is_api_supported(&mut self, configuration: GuiConfiguration) -> bool {
         let Some(api_type) = GuiApiType::default_for_current_platform() else {
@@ -44,6 +140,7 @@ impl PluginGuiImpl for SonantPluginMainThread<'_> {
 
     fn create(&mut self, configuration: GuiConfiguration) -> Result<(), PluginError> {
         if self.is_api_supported(configuration) {
+            self.gui.scaling.set_api(configuration.api_type);
             Ok(())
         } else {
             Err(PluginError::Message("Only floating GUI is supported"))
@@ -54,18 +151,21 @@ impl PluginGuiImpl for SonantPluginMainThread<'_> {
         self.gui.destroy();
     }
 
-    fn set_scale(&mut self, _scale: f64) -> Result<(), PluginError> {
-        Ok(())
+    fn set_scale(&mut self, scale: f64) -> Result<(), PluginError> {
+        if self.gui.scaling.set_scale(scale) {
+            Ok(())
+        } else {
+            Err(PluginError::Message("GUI scale is resolved from the OS"))
+        }
     }
 
     fn get_size(&mut self) -> Option<GuiSize> {
-        Some(GuiSize {
-            width: 800,
-            height: 640,
-        })
+        Some(self.gui.scaling.host_size())
     }
 
-    fn set_size(&mut self, _size: GuiSize) -> Result<(), PluginError> {
+    /// The new size is applied the next time the helper window is launched.
+    fn set_size(&mut self, size: GuiSize) -> Result<(), PluginError> {
+        self.gui.scaling.set_host_size(size);
         Ok(())
     }
 
@@ -78,7 +178,12 @@ impl PluginGuiImpl for SonantPluginMainThread<'_> {
     }
 
     fn show(&mut self) -> Result<(), PluginError> {
-        self.gui.show()
+        self.gui.show(
+            self.input_track_layout.as_ref(),
+            self.project_model.as_ref(),
+        )?;
+        self.refresh_host_track_name();
+        Ok(())
     }
 
     fn hide(&mut self) -> Result<(), PluginError> {
@@ -88,7 +193,11 @@ impl PluginGuiImpl for SonantPluginMainThread<'_> {
 }
 
 impl SonantGuiController {
-    fn show(&mut self) -> Result<(), PluginError> {
+    fn show(
+        &mut self,
+        input_track_layout: Option<&InputTrackLayout>,
+        project_model: Option<&ModelRef>,
+    ) -> Result<(), PluginError> {
         reap_finished_helper(&mut self.state);
 
         if self.state.child.is_some() {
@@ -101,6 +210,10 @@ impl SonantGuiController {
         let mut command = Command::new(helper_path);
         command
             .arg("--gpui-helper")
+            .env(
+                HELPER_WINDOW_SIZE_ENV,
+                self.scaling.helper_window_size_env_value(),
+            )
             .stdin(Stdio::null())
             .stdout(Stdio::null())
             .stderr(Stdio::inherit());
@@ -115,6 +228,28 @@ impl SonantGuiController {
             sender
         };
 
+        #[cfg(target_family = "unix")]
+        let control_source = {
+            let control_socket_path = helper_control_socket_path();
+            let source = HelperControlIpcSource::bind(&control_socket_path)
+                .map_err(|_| PluginError::Message("Failed to initialize helper control socket"))?;
+            command.env(HELPER_CONTROL_IPC_SOCKET_ENV, &control_socket_path);
+            if let Some(layout) = input_track_layout
+                && let Ok(encoded) = serde_json::to_string(layout)
+            {
+                command.env(INPUT_TRACK_LAYOUT_ENV, encoded);
+            }
+            if let Some(model) = project_model
+                && let Ok(encoded) = serde_json::to_string(model)
+            {
+                command.env(PROJECT_MODEL_ENV, encoded);
+            }
+            source
+        };
+        #[cfg(not(target_family = "unix"))]
+        let _ = (input_track_layout, project_model);
+
+        audit_blocking_call();
         let child = command
             .spawn()
             .map_err(|_| PluginError::Message("Failed to launch SonantGUIHelper"))?;
@@ -123,11 +258,23 @@ impl SonantGuiController {
         #[cfg(target_family = "unix")]
         {
             self.state.live_input_sender = Some(live_input_sender);
+            self.state.control_source = Some(control_source);
         }
         self.state.launched_at = Some(Instant::now());
         Ok(())
     }
 
+    pub(super) fn poll_control_messages(&mut self) -> Vec<HelperControlMessage> {
+        #[cfg(target_family = "unix")]
+        {
+            if let Some(source) = self.state.control_source.as_mut() {
+                audit_blocking_call();
+                return std::iter::from_fn(|| source.try_recv()).collect();
+            }
+        }
+        Vec::new()
+    }
+
     pub(super) fn send_live_input_events(&mut self, events: &[LiveInputEvent]) {
         #[cfg(not(target_family = "unix"))]
         {
@@ -139,11 +286,91 @@ impl SonantGuiController {
                 return;
             }
             if let Some(sender) = self.state.live_input_sender.as_ref() {
+                audit_blocking_call();
                 sender.send_events(events);
             }
         }
     }
 
+    /// Relays drop counters only when they change, so idle callbacks stay quiet.
+    pub(super) fn send_queue_overflow_metrics(&mut self, metrics: QueueOverflowMetrics) {
+        if self.state.relayed.overflow_metrics == Some(metrics) {
+            return;
+        }
+        #[cfg(target_family = "unix")]
+        {
+            let Some(sender) = self.state.live_input_sender.as_ref() else {
+                return;
+            };
+            audit_blocking_call();
+            if sender.send_queue_overflow_metrics(metrics).is_ok() {
+                self.state.relayed.overflow_metrics = Some(metrics);
+            }
+        }
+    }
+
+    /// Relays tempo, meter and loop length only when they change.
+    pub(super) fn send_host_transport_context(&mut self, context: HostTransportContext) {
+        if self.state.relayed.host_context == Some(context) {
+            return;
+        }
+        #[cfg(target_family = "unix")]
+        {
+            let Some(sender) = self.state.live_input_sender.as_ref() else {
+                return;
+            };
+            audit_blocking_call();
+            if sender.send_host_transport_context(context).is_ok() {
+                self.state.relayed.host_context = Some(context);
+            }
+        }
+    }
+
+    /// Relays the host track name only when it changes.
+    pub(super) fn send_host_track_name(&mut self, name: Option<&str>) {
+        if self
+            .state
+            .relayed
+            .host_track_name
+            .as_ref()
+            .map(Option::as_deref)
+            == Some(name)
+        {
+            return;
+        }
+        #[cfg(target_family = "unix")]
+        {
+            let Some(sender) = self.state.live_input_sender.as_ref() else {
+                return;
+            };
+            audit_blocking_call();
+            if sender.send_host_track_name(name).is_ok() {
+                self.state.relayed.host_track_name = Some(name.map(str::to_string));
+            }
+        }
+    }
+
+    /// The helper is closing its window: stop relaying input to it and let `reap_finished_helper`
+    /// collect the process once it exits.
+    pub(super) fn helper_closing(&mut self) {
+        #[cfg(target_family = "unix")]
+        {
+            self.state.live_input_sender = None;
+        }
+        self.state.relayed = RelayedHostState::default();
+    }
+
+    /// Asks the helper to generate from its current prompt; dropped while no helper is running.
+    pub(super) fn send_generate_trigger(&mut self) {
+        #[cfg(target_family = "unix")]
+        {
+            if let Some(sender) = self.state.live_input_sender.as_ref() {
+                audit_blocking_call();
+                sender.send_generate_trigger();
+            }
+        }
+    }
+
     fn hide(&mut self) {
         reap_finished_helper(&mut self.state);
 
@@ -196,25 +423,40 @@ fn reap_finished_helper(state: &mut HelperState) {
         #[cfg(target_family = "unix")]
         {
             state.live_input_sender = None;
+            state.control_source = None;
         }
+        state.relayed = RelayedHostState::default();
         state.launched_at = None;
     }
 }
 
 fn stop_helper(state: &mut HelperState) {
     if let Some(mut child) = state.child.take() {
+        audit_blocking_call();
         let _ = child.kill();
         let _ = child.wait();
     }
     #[cfg(target_family = "unix")]
     {
         state.live_input_sender = None;
+        state.control_source = None;
     }
+    state.relayed = RelayedHostState::default();
     state.launched_at = None;
 }
 
 #[cfg(target_family = "unix")]
 fn helper_live_input_socket_path() -> PathBuf {
+    helper_socket_path("snt-live-in")
+}
+
+#[cfg(target_family = "unix")]
+fn helper_control_socket_path() -> PathBuf {
+    helper_socket_path("snt-ctl")
+}
+
+#[cfg(target_family = "unix")]
+fn helper_socket_path(prefix: &str) -> PathBuf {
     use std::env::temp_dir;
     use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -222,7 +464,7 @@ fn helper_live_input_socket_path() -> PathBuf {
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default()
         .as_nanos();
-    temp_dir().join(format!("snt-live-in-{}-{nonce:x}.sock", std::process::id()))
+    temp_dir().join(format!("{prefix}-{}-{nonce:x}.sock", std::process::id()))
 }
 
 fn resolve_helper_binary_path() -> Option<PathBuf> {
@@ -265,7 +507,53 @@ fn current_library_path() -> Option<PathBuf> {
 
 #[cfg(all(test, target_family = "unix"))]
 mod tests {
-    use super::helper_live_input_socket_path;
+    use super::{
+        GuiScaling, SonantGuiController, helper_control_socket_path, helper_live_input_socket_path,
+    };
+    use crate::app::{HostTransportContext, LiveInputIpcSender};
+    use crate::test_support::TestDir;
+    use clack_extensions::gui::{GuiApiType, GuiSize};
+    use std::os::unix::net::UnixDatagram;
+
+    fn pending_datagrams(socket: &UnixDatagram) -> usize {
+        let mut buffer = [0_u8; 1024];
+        std::iter::from_fn(|| socket.recv(&mut buffer).ok()).count()
+    }
+
+    #[test]
+    fn host_context_is_relayed_once_per_change_and_retried_until_delivered() {
+        let dir = TestDir::new("gui-relay");
+        let socket_path = dir.join("live.sock");
+        let mut controller = SonantGuiController::default();
+        controller.state.live_input_sender =
+            Some(LiveInputIpcSender::new(&socket_path).expect("sender should initialize"));
+        let context = HostTransportContext {
+            tempo_bpm: Some(120.0),
+            time_signature: Some((4, 4)),
+            loop_length_beats: None,
+        };
+
+        // Nothing is listening yet, so the send fails and is not remembered.
+        controller.send_host_transport_context(context);
+        controller.send_host_track_name(Some("Bass"));
+
+        let helper = UnixDatagram::bind(&socket_path).expect("bind should succeed");
+        helper
+            .set_nonblocking(true)
+            .expect("nonblocking should be set");
+        for _ in 0..3 {
+            controller.send_host_transport_context(context);
+            controller.send_host_track_name(Some("Bass"));
+        }
+        assert_eq!(pending_datagrams(&helper), 2);
+
+        controller.send_host_transport_context(HostTransportContext {
+            tempo_bpm: Some(96.0),
+            ..context
+        });
+        controller.send_host_track_name(Some("Bass"));
+        assert_eq!(pending_datagrams(&helper), 1);
+    }
 
     #[test]
     fn helper_live_input_socket_path_uses_temp_dir_and_fits_unix_socket_limit() {
@@ -279,4 +567,49 @@ mod tests {
             path.display()
         );
     }
+
+    #[test]
+    fn helper_control_socket_path_differs_from_live_input_socket_path() {
+        let live_input_path = helper_live_input_socket_path();
+        let control_path = helper_control_socket_path();
+        assert_ne!(live_input_path, control_path);
+        assert!(control_path.to_string_lossy().len() <= 103);
+    }
+
+    #[test]
+    fn physical_pixel_apis_scale_reported_sizes() {
+        let mut scaling = GuiScaling::default();
+        scaling.set_api(GuiApiType::X11);
+
+        assert!(scaling.set_scale(2.0));
+        assert_eq!(
+            scaling.host_size(),
+            GuiSize {
+                width: 1600,
+                height: 1280,
+            }
+        );
+
+        scaling.set_host_size(GuiSize {
+            width: 2000,
+            height: 1500,
+        });
+        assert_eq!(scaling.helper_window_size_env_value(), "1000x750");
+    }
+
+    #[test]
+    fn logical_pixel_apis_ignore_host_scale() {
+        let mut scaling = GuiScaling::default();
+        scaling.set_api(GuiApiType::COCOA);
+
+        assert!(!scaling.set_scale(2.0));
+        assert!(!scaling.set_scale(f64::NAN));
+        assert_eq!(
+            scaling.host_size(),
+            GuiSize {
+                width: 800,
+                height: 640,
+            }
+        );
+    }
 }
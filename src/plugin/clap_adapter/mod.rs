@@ -1,22 +1,43 @@
 use clack_extensions::audio_ports::PluginAudioPorts;
 use clack_extensions::gui::PluginGui;
 use clack_extensions::note_ports::PluginNotePorts;
+use clack_extensions::params::PluginParams;
 use clack_extensions::state::PluginState;
+use clack_extensions::track_info::PluginTrackInfo;
 use clack_plugin::events::Match;
-use clack_plugin::events::event_types::{MidiEvent, TransportFlags};
+use clack_plugin::events::event_types::{
+    MidiEvent, NoteChokeEvent, NoteExpressionEvent, NoteExpressionType, NoteOffEvent, NoteOnEvent,
+    TransportEvent, TransportFlags,
+};
+use clack_plugin::events::io::{InputEvents, OutputEvents};
 use clack_plugin::events::spaces::CoreEventSpace;
 use clack_plugin::prelude::*;
 use crossbeam_queue::ArrayQueue;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicU16, AtomicU32, AtomicU64, Ordering};
 
 mod audio_ports_extension;
+mod clip_player;
 mod gui_extension;
 mod note_ports_extension;
+mod output_buffer;
+mod params_extension;
+#[cfg(feature = "rt-audit")]
+mod rt_audit;
 mod state_extension;
+mod track_info_extension;
 
+use clip_player::{AppliedClipPlayer, ClipOutput};
 use gui_extension::SonantGuiController;
+use output_buffer::{BufferedOutput, OutputEventBuffer};
+use params_extension::GenerateTriggerNote;
 
 const MIDI_EVENT_QUEUE_CAPACITY: usize = 2048;
+const RETIRED_CLIP_QUEUE_CAPACITY: usize = 4;
+// Not a MIDI key, so it marks the Generate trigger note as off.
+const NO_TRIGGER_KEY: u8 = u8::MAX;
+// Room for a full generated queue plus the block's thru echoes and clip events.
+const OUTPUT_EVENT_BUFFER_CAPACITY: usize = MIDI_EVENT_QUEUE_CAPACITY * 2;
 
 pub struct SonantPlugin;
 
@@ -30,7 +51,9 @@ impl Plugin for SonantPlugin {
             .register::<PluginGui>()
             .register::<PluginAudioPorts>()
             .register::<PluginNotePorts>()
-            .register::<PluginState>();
+            .register::<PluginParams>()
+            .register::<PluginState>()
+            .register::<PluginTrackInfo>();
     }
 }
 
@@ -51,12 +74,17 @@ impl DefaultPluginFactory for SonantPlugin {
     }
 
     fn new_main_thread<'a>(
-        _host: HostMainThreadHandle<'a>,
+        host: HostMainThreadHandle<'a>,
         shared: &'a Self::Shared<'a>,
     ) -> Result<Self::MainThread<'a>, PluginError> {
         Ok(SonantPluginMainThread {
+            host,
             shared,
             gui: SonantGuiController::default(),
+            input_track_layout: None,
+            project_model: None,
+            applied_clip: None,
+            host_track_name: None,
         })
     }
 }
@@ -96,26 +124,29 @@ impl Default for RtTransportState {
 #[derive(Debug, Copy, Clone, PartialEq)]
 struct TransportSnapshot {
     is_playing: bool,
+    is_loop_active: bool,
     playhead_ppq_at_block_start: f64,
     tempo_bpm: Option<f64>,
     sample_rate_hz: f64,
 }
 
 impl TransportSnapshot {
-    fn from_process(process: Process<'_>, sample_rate_hz: f64) -> Self {
+    fn from_transport(transport: Option<&TransportEvent>, sample_rate_hz: f64) -> Self {
         let mut snapshot = Self {
             is_playing: false,
+            is_loop_active: false,
             playhead_ppq_at_block_start: 0.0,
             tempo_bpm: None,
             sample_rate_hz,
         };
 
-        let Some(transport) = process.transport else {
+        let Some(transport) = transport else {
             return snapshot;
         };
 
         let flags = transport.flags;
         snapshot.is_playing = flags.contains(TransportFlags::IS_PLAYING);
+        snapshot.is_loop_active = flags.contains(TransportFlags::IS_LOOP_ACTIVE);
         if flags.contains(TransportFlags::HAS_BEATS_TIMELINE) {
             let ppq = transport.song_pos_beats.to_float();
             if ppq.is_finite() && ppq >= 0.0 {
@@ -132,6 +163,42 @@ impl TransportSnapshot {
         snapshot
     }
 
+    /// Tempo, meter, and loop length for the helper's prompt context. Loop bounds are only
+    /// reported while the host loop is enabled.
+    fn host_context(transport: Option<&TransportEvent>) -> crate::app::HostTransportContext {
+        let mut context = crate::app::HostTransportContext::default();
+        let Some(transport) = transport else {
+            return context;
+        };
+
+        let flags = transport.flags;
+        if flags.contains(TransportFlags::HAS_TEMPO)
+            && transport.tempo.is_finite()
+            && transport.tempo > 0.0
+        {
+            context.tempo_bpm = Some(transport.tempo);
+        }
+        if flags.contains(TransportFlags::HAS_TIME_SIGNATURE)
+            && transport.time_signature_numerator > 0
+            && transport.time_signature_denominator > 0
+        {
+            context.time_signature = Some((
+                transport.time_signature_numerator,
+                transport.time_signature_denominator,
+            ));
+        }
+        if flags.contains(TransportFlags::HAS_BEATS_TIMELINE)
+            && flags.contains(TransportFlags::IS_LOOP_ACTIVE)
+        {
+            let loop_length =
+                transport.loop_end_beats.to_float() - transport.loop_start_beats.to_float();
+            if loop_length.is_finite() && loop_length > 0.0 {
+                context.loop_length_beats = Some(loop_length);
+            }
+        }
+        context
+    }
+
     fn event_transport(self, sample_offset: u32) -> RtTransportState {
         let mut playhead_ppq = self.playhead_ppq_at_block_start;
         if let Some(tempo_bpm) = self.tempo_bpm
@@ -217,6 +284,50 @@ fn map_input_event(
     }
 }
 
+/// Emits a transport-only marker when a looping host jumps back to the loop start, so the
+/// helper sees the wrap even if no notes are played across the boundary.
+fn loop_wrap_marker(
+    previous_playhead_ppq: Option<f64>,
+    transport_snapshot: TransportSnapshot,
+) -> Option<RtMidiEvent> {
+    let previous_playhead_ppq = previous_playhead_ppq?;
+    if !transport_snapshot.is_playing
+        || !transport_snapshot.is_loop_active
+        || transport_snapshot.playhead_ppq_at_block_start >= previous_playhead_ppq
+    {
+        return None;
+    }
+
+    Some(RtMidiEvent {
+        time: 0,
+        port_index: 0,
+        data: crate::app::LOOP_WRAP_MARKER_DATA,
+        transport: transport_snapshot.event_transport(0),
+    })
+}
+
+/// Emits a transport-only marker on the first stopped block so the helper finalizes live takes
+/// without waiting for the next note. It carries the last playing position, not the stop jump.
+fn transport_stop_marker(
+    previous_playhead_ppq: Option<f64>,
+    transport_snapshot: TransportSnapshot,
+) -> Option<RtMidiEvent> {
+    let playhead_ppq = previous_playhead_ppq?;
+    if transport_snapshot.is_playing {
+        return None;
+    }
+
+    Some(RtMidiEvent {
+        time: 0,
+        port_index: 0,
+        data: crate::app::LOOP_WRAP_MARKER_DATA,
+        transport: RtTransportState {
+            is_playing: false,
+            playhead_ppq,
+        },
+    })
+}
+
 fn should_accept_note_events<'a>(mut events: impl Iterator<Item = &'a UnknownEvent>) -> bool {
     !events.any(|event| matches!(event.as_core_event(), Some(CoreEventSpace::Midi(_))))
 }
@@ -248,6 +359,53 @@ fn note_event_to_midi(
     })
 }
 
+/// Clip playback is regenerated every block, so a saturated host buffer just drops events.
+fn push_clip_output(output: &mut OutputEvents, time: u32, clip_output: ClipOutput) {
+    match clip_output {
+        ClipOutput::Midi(data) => {
+            let _ = output.try_push(MidiEvent::new(time, 0, data));
+        }
+        ClipOutput::NoteOn {
+            channel,
+            key,
+            velocity,
+            expression,
+        } => {
+            let target = Pckn::new(0u16, u16::from(channel), u16::from(key), Match::All);
+            let _ = output.try_push(NoteOnEvent::new(time, target, f64::from(velocity) / 127.0));
+            if let Some(expression) = expression {
+                let _ = output.try_push(NoteExpressionEvent::new(
+                    time,
+                    target,
+                    NoteExpressionType::Volume,
+                    expression.volume,
+                ));
+                let _ = output.try_push(NoteExpressionEvent::new(
+                    time,
+                    target,
+                    NoteExpressionType::Brightness,
+                    expression.brightness,
+                ));
+            }
+        }
+        ClipOutput::NoteOff { channel, key } => {
+            let target = Pckn::new(0u16, u16::from(channel), u16::from(key), Match::All);
+            let _ = output.try_push(NoteOffEvent::new(time, target, 0.0));
+        }
+        ClipOutput::NoteChoke { channel, key } => {
+            let target = Pckn::new(0u16, u16::from(channel), u16::from(key), Match::All);
+            let _ = output.try_push(NoteChokeEvent::new(time, target));
+        }
+    }
+}
+
+/// Reports a call that can block, such as a syscall or waiting on a process, to the `rt-audit`
+/// detector, which fails the block if it happens inside `process`. A no-op without the feature.
+fn audit_blocking_call() {
+    #[cfg(feature = "rt-audit")]
+    rt_audit::record_blocking_call();
+}
+
 fn velocity_to_midi_byte(velocity: f64) -> u8 {
     (velocity.clamp(0.0, 1.0) * 127.0).round() as u8
 }
@@ -256,6 +414,23 @@ struct MidiBridge {
     live_input_queue: ArrayQueue<RtMidiEvent>,
     app_input_queue: ArrayQueue<RtMidiEvent>,
     generated_output_queue: ArrayQueue<RtMidiEvent>,
+    applied_clip_queue: ArrayQueue<Option<Box<crate::app::AppliedClip>>>,
+    // Replaced clips travel back here so their memory is freed on the main thread.
+    retired_clip_queue: ArrayQueue<Box<crate::app::AppliedClip>>,
+    // Bit N set => live input on MIDI channel N+1 is echoed straight to the output.
+    thru_channel_mask: AtomicU16,
+    live_input_dropped: AtomicU64,
+    app_input_dropped: AtomicU64,
+    generated_output_dropped: AtomicU64,
+    // Host context as raw bits; zero means the host did not report the value.
+    host_tempo_bits: AtomicU64,
+    host_time_signature: AtomicU32,
+    host_loop_length_bits: AtomicU64,
+    generate_triggered: AtomicBool,
+    // Trigger note as raw bytes: `NO_TRIGGER_KEY` turns it off, channel 0 matches any channel.
+    generate_trigger_key: AtomicU8,
+    generate_trigger_channel: AtomicU8,
+    generate_button_held: AtomicBool,
 }
 
 impl MidiBridge {
@@ -264,11 +439,44 @@ impl MidiBridge {
             live_input_queue: ArrayQueue::new(capacity),
             app_input_queue: ArrayQueue::new(capacity),
             generated_output_queue: ArrayQueue::new(capacity),
+            applied_clip_queue: ArrayQueue::new(1),
+            retired_clip_queue: ArrayQueue::new(RETIRED_CLIP_QUEUE_CAPACITY),
+            thru_channel_mask: AtomicU16::new(0),
+            live_input_dropped: AtomicU64::new(0),
+            app_input_dropped: AtomicU64::new(0),
+            generated_output_dropped: AtomicU64::new(0),
+            host_tempo_bits: AtomicU64::new(0),
+            host_time_signature: AtomicU32::new(0),
+            host_loop_length_bits: AtomicU64::new(0),
+            generate_triggered: AtomicBool::new(false),
+            generate_trigger_key: AtomicU8::new(NO_TRIGGER_KEY),
+            generate_trigger_channel: AtomicU8::new(0),
+            generate_button_held: AtomicBool::new(false),
         }
     }
 
+    fn set_thru_channels(&self, channels: &[u8]) {
+        let mask = channels
+            .iter()
+            .filter(|channel| (1..=16).contains(*channel))
+            .fold(0u16, |mask, channel| mask | (1 << (channel - 1)));
+        self.thru_channel_mask.store(mask, Ordering::Relaxed);
+    }
+
+    fn is_thru_enabled_for(&self, event: &RtMidiEvent) -> bool {
+        let status = event.data[0];
+        // Only channel voice messages carry a channel to match against.
+        if !(0x80..0xF0).contains(&status) {
+            return false;
+        }
+        let mask = self.thru_channel_mask.load(Ordering::Relaxed);
+        mask & (1 << (status & 0x0F)) != 0
+    }
+
     fn push_live_input(&self, event: RtMidiEvent) {
-        let _ = self.live_input_queue.force_push(event);
+        if self.live_input_queue.force_push(event).is_some() {
+            self.live_input_dropped.fetch_add(1, Ordering::Relaxed);
+        }
     }
 
     fn pop_live_input(&self) -> Option<RtMidiEvent> {
@@ -276,7 +484,9 @@ impl MidiBridge {
     }
 
     fn push_app_input(&self, event: RtMidiEvent) {
-        let _ = self.app_input_queue.force_push(event);
+        if self.app_input_queue.force_push(event).is_some() {
+            self.app_input_dropped.fetch_add(1, Ordering::Relaxed);
+        }
     }
 
     fn pop_app_input(&self) -> Option<RtMidiEvent> {
@@ -284,20 +494,135 @@ impl MidiBridge {
     }
 
     fn push_generated_output(&self, event: RtMidiEvent) {
-        let _ = self.generated_output_queue.force_push(event);
+        if self.generated_output_queue.force_push(event).is_some() {
+            self.generated_output_dropped
+                .fetch_add(1, Ordering::Relaxed);
+        }
     }
 
     fn pop_generated_output(&self) -> Option<RtMidiEvent> {
         self.generated_output_queue.pop()
     }
 
+    fn record_generated_dropped(&self) {
+        self.generated_output_dropped
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
     fn pop_latest_generated_or(&self, mut fallback: Option<RtMidiEvent>) -> Option<RtMidiEvent> {
         while let Some(latest_event) = self.generated_output_queue.pop() {
+            if fallback.is_some() {
+                self.generated_output_dropped
+                    .fetch_add(1, Ordering::Relaxed);
+            }
             fallback = Some(latest_event);
         }
         fallback
     }
 
+    fn overflow_metrics(&self) -> crate::app::QueueOverflowMetrics {
+        crate::app::QueueOverflowMetrics {
+            live_input_dropped: self.live_input_dropped.load(Ordering::Relaxed),
+            app_input_dropped: self.app_input_dropped.load(Ordering::Relaxed),
+            generated_output_dropped: self.generated_output_dropped.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Records the context seen by the audio thread and reports whether it changed.
+    fn store_host_context(&self, context: crate::app::HostTransportContext) -> bool {
+        let tempo_bits = context.tempo_bpm.map_or(0, f64::to_bits);
+        let time_signature = context
+            .time_signature
+            .map_or(0, |(numerator, denominator)| {
+                (u32::from(numerator) << 16) | u32::from(denominator)
+            });
+        let loop_length_bits = context.loop_length_beats.map_or(0, f64::to_bits);
+        let tempo_changed = self.host_tempo_bits.swap(tempo_bits, Ordering::Relaxed) != tempo_bits;
+        let meter_changed = self
+            .host_time_signature
+            .swap(time_signature, Ordering::Relaxed)
+            != time_signature;
+        let loop_changed = self
+            .host_loop_length_bits
+            .swap(loop_length_bits, Ordering::Relaxed)
+            != loop_length_bits;
+        tempo_changed || meter_changed || loop_changed
+    }
+
+    fn host_context(&self) -> crate::app::HostTransportContext {
+        let tempo_bits = self.host_tempo_bits.load(Ordering::Relaxed);
+        let time_signature = self.host_time_signature.load(Ordering::Relaxed);
+        let loop_length_bits = self.host_loop_length_bits.load(Ordering::Relaxed);
+        crate::app::HostTransportContext {
+            tempo_bpm: (tempo_bits != 0).then(|| f64::from_bits(tempo_bits)),
+            time_signature: (time_signature != 0)
+                .then_some(((time_signature >> 16) as u16, time_signature as u16)),
+            loop_length_beats: (loop_length_bits != 0).then(|| f64::from_bits(loop_length_bits)),
+        }
+    }
+
+    fn trigger_generate(&self) {
+        self.generate_triggered.store(true, Ordering::Relaxed);
+    }
+
+    /// Hits between two main-thread callbacks collapse into one Generate.
+    fn take_generate_trigger(&self) -> bool {
+        self.generate_triggered.swap(false, Ordering::Relaxed)
+    }
+
+    fn set_generate_trigger_note(&self, trigger: GenerateTriggerNote) {
+        self.generate_trigger_key
+            .store(trigger.key.unwrap_or(NO_TRIGGER_KEY), Ordering::Relaxed);
+        self.generate_trigger_channel
+            .store(trigger.channel.unwrap_or(0), Ordering::Relaxed);
+    }
+
+    fn generate_trigger_note(&self) -> GenerateTriggerNote {
+        let key = self.generate_trigger_key.load(Ordering::Relaxed);
+        let channel = self.generate_trigger_channel.load(Ordering::Relaxed);
+        GenerateTriggerNote {
+            key: (key != NO_TRIGGER_KEY).then_some(key),
+            channel: (channel != 0).then_some(channel),
+        }
+    }
+
+    /// Fires Generate when the button goes down; holding it or letting go does nothing.
+    fn set_generate_button(&self, held: bool) -> bool {
+        let was_held = self.generate_button_held.swap(held, Ordering::Relaxed);
+        let fires = held && !was_held;
+        if fires {
+            self.trigger_generate();
+        }
+        fires
+    }
+
+    fn is_generate_button_held(&self) -> bool {
+        self.generate_button_held.load(Ordering::Relaxed)
+    }
+
+    /// Only the newest pending clip matters; a clip the audio thread has not picked up yet is
+    /// dropped here on the main thread.
+    fn push_applied_clip(&self, clip: Option<crate::app::AppliedClip>) {
+        let _ = self.applied_clip_queue.force_push(clip.map(Box::new));
+    }
+
+    fn pop_applied_clip(&self) -> Option<Option<Box<crate::app::AppliedClip>>> {
+        self.applied_clip_queue.pop()
+    }
+
+    /// Hands the clip back when the queue is full so the caller can hold it rather than free
+    /// it on the audio thread.
+    fn retire_applied_clip(
+        &self,
+        clip: Box<crate::app::AppliedClip>,
+    ) -> Result<(), Box<crate::app::AppliedClip>> {
+        self.retired_clip_queue.push(clip)
+    }
+
+    fn drain_retired_clips(&self) {
+        while self.retired_clip_queue.pop().is_some() {}
+    }
+
     fn reset(&self) {
         while self.live_input_queue.pop().is_some() {}
         while self.app_input_queue.pop().is_some() {}
@@ -373,22 +698,210 @@ impl crate::app::LiveInputEventSource for SonantShared {
 impl PluginShared<'_> for SonantShared {}
 
 pub struct SonantPluginMainThread<'a> {
+    host: HostMainThreadHandle<'a>,
     shared: &'a SonantShared,
     gui: SonantGuiController,
+    input_track_layout: Option<crate::app::InputTrackLayout>,
+    project_model: Option<crate::domain::ModelRef>,
+    // Kept so a reactivated audio processor resumes the clip the helper last applied.
+    applied_clip: Option<crate::app::AppliedClip>,
+    // Last name the host's track-info extension reported for this track, relayed as prompt context.
+    host_track_name: Option<String>,
+}
+
+impl SonantPluginMainThread<'_> {
+    fn apply_helper_control_messages(&mut self) {
+        for message in self.gui.poll_control_messages() {
+            match message {
+                crate::app::HelperControlMessage::InputTrackLayout { layout } => {
+                    self.input_track_layout = Some(layout);
+                }
+                crate::app::HelperControlMessage::MidiThruChannels { channels } => {
+                    self.shared.midi_bridge.set_thru_channels(&channels);
+                }
+                crate::app::HelperControlMessage::AppliedClip { clip } => {
+                    self.shared.midi_bridge.push_applied_clip(clip.clone());
+                    self.applied_clip = clip;
+                }
+                crate::app::HelperControlMessage::ProjectModel { model } => {
+                    self.project_model = model;
+                }
+                crate::app::HelperControlMessage::HelperClosing => {
+                    self.gui.helper_closing();
+                }
+            }
+        }
+    }
 }
 
 impl<'a> PluginMainThread<'a, SonantShared> for SonantPluginMainThread<'a> {
     fn on_main_thread(&mut self) {
+        self.apply_helper_control_messages();
+        self.shared.midi_bridge.drain_retired_clips();
         let live_input_events = self.shared.flush_live_input_to_app();
         self.gui.send_live_input_events(&live_input_events);
+        self.gui
+            .send_queue_overflow_metrics(self.shared.midi_bridge.overflow_metrics());
+        self.gui
+            .send_host_transport_context(self.shared.midi_bridge.host_context());
+        self.gui
+            .send_host_track_name(self.host_track_name.as_deref());
+        if self.shared.midi_bridge.take_generate_trigger() {
+            self.gui.send_generate_trigger();
+        }
     }
 }
 
 pub struct SonantAudioProcessor<'a> {
     host: HostAudioProcessorHandle<'a>,
+    block: AudioBlockProcessor,
+}
+
+/// The audio-thread state behind [`SonantAudioProcessor`]. It never touches the host, so a
+/// block can be driven without one.
+struct AudioBlockProcessor {
     midi_bridge: Arc<MidiBridge>,
     pending_output_event: Option<RtMidiEvent>,
     sample_rate_hz: f64,
+    last_playhead_ppq: Option<f64>,
+    clip_player: AppliedClipPlayer,
+    output_buffer: OutputEventBuffer,
+    // A replaced clip the retire queue had no room for. No new clip is taken until it is
+    // handed off, so the audio thread never frees one.
+    unretired_clip: Option<Box<crate::app::AppliedClip>>,
+}
+
+impl AudioBlockProcessor {
+    fn new(midi_bridge: Arc<MidiBridge>, sample_rate_hz: f64) -> Self {
+        Self {
+            midi_bridge,
+            pending_output_event: None,
+            sample_rate_hz,
+            last_playhead_ppq: None,
+            clip_player: AppliedClipPlayer::new(),
+            output_buffer: OutputEventBuffer::with_capacity(OUTPUT_EVENT_BUFFER_CAPACITY),
+            unretired_clip: None,
+        }
+    }
+
+    /// Runs one block and reports whether the main thread needs a callback.
+    fn process_block(
+        &mut self,
+        transport: Option<&TransportEvent>,
+        frames_count: u32,
+        input: &InputEvents,
+        output: &mut OutputEvents,
+    ) -> bool {
+        #[cfg(feature = "rt-audit")]
+        let _rt_audit_scope = rt_audit::AudioThreadScope::enter();
+
+        // Some hosts can emit both MIDI and Note events for the same performance data.
+        // Prefer raw MIDI when present to avoid double-counting live notes.
+        let allow_note_events = should_accept_note_events(input.iter());
+        let mut needs_callback = self
+            .midi_bridge
+            .store_host_context(TransportSnapshot::host_context(transport));
+        let transport_snapshot = TransportSnapshot::from_transport(transport, self.sample_rate_hz);
+
+        if let Some(marker) = loop_wrap_marker(self.last_playhead_ppq, transport_snapshot)
+            .or_else(|| transport_stop_marker(self.last_playhead_ppq, transport_snapshot))
+        {
+            self.midi_bridge.push_live_input(marker);
+            needs_callback = true;
+        }
+        self.last_playhead_ppq = transport_snapshot
+            .is_playing
+            .then_some(transport_snapshot.playhead_ppq_at_block_start);
+
+        let generate_trigger = self.midi_bridge.generate_trigger_note();
+        for event in input.iter() {
+            if params_extension::apply_param_event(&self.midi_bridge, event) {
+                needs_callback = true;
+            }
+            if let Some(midi_event) = map_input_event(event, allow_note_events, transport_snapshot)
+            {
+                if let Some(fires) = generate_trigger.kind(midi_event.data) {
+                    if fires {
+                        self.midi_bridge.trigger_generate();
+                        needs_callback = true;
+                    }
+                    continue;
+                }
+                // Thru is best-effort monitoring: drop the echo rather than delay live input.
+                if self.midi_bridge.is_thru_enabled_for(&midi_event) {
+                    let _ = self
+                        .output_buffer
+                        .push(midi_event.time, BufferedOutput::Thru(midi_event));
+                }
+                self.midi_bridge.push_live_input(midi_event);
+                needs_callback = true;
+            }
+        }
+
+        if let Some(clip) = self.unretired_clip.take() {
+            self.unretired_clip = self.midi_bridge.retire_applied_clip(clip).err();
+            needs_callback = true;
+        }
+        let output_buffer = &mut self.output_buffer;
+        let mut push_clip_event = |time: u32, clip_output: ClipOutput| {
+            let _ = output_buffer.push(time, BufferedOutput::Clip(clip_output));
+        };
+        if self.unretired_clip.is_none()
+            && let Some(clip) = self.midi_bridge.pop_applied_clip()
+            && let Some(retired) = self.clip_player.replace_clip(clip, &mut push_clip_event)
+        {
+            self.unretired_clip = self.midi_bridge.retire_applied_clip(retired).err();
+            needs_callback = true;
+        }
+        self.clip_player
+            .process(transport_snapshot, frames_count, push_clip_event);
+
+        let generated = self
+            .pending_output_event
+            .take()
+            .into_iter()
+            .chain(std::iter::from_fn(|| {
+                self.midi_bridge.pop_generated_output()
+            }));
+        for event in generated {
+            if let Err(BufferedOutput::Generated(event)) = self
+                .output_buffer
+                .push(event.time, BufferedOutput::Generated(event))
+            {
+                // No room this block. Keep the newest generated event and drop stale ones.
+                self.pending_output_event = self.midi_bridge.pop_latest_generated_or(Some(event));
+                break;
+            }
+        }
+
+        for (time, buffered) in self.output_buffer.drain_sorted() {
+            match buffered {
+                BufferedOutput::Thru(event) => {
+                    let _ = output.try_push(event.to_clap());
+                }
+                BufferedOutput::Clip(clip_output) => {
+                    push_clip_output(output, time, clip_output);
+                }
+                BufferedOutput::Generated(event) => {
+                    if output.try_push(event.to_clap()).is_err() {
+                        // Host output is saturated. Keep only the latest generated event.
+                        if self.pending_output_event.replace(event).is_some() {
+                            self.midi_bridge.record_generated_dropped();
+                        }
+                    }
+                }
+            }
+        }
+
+        needs_callback
+    }
+
+    fn reset(&mut self) {
+        self.pending_output_event = None;
+        self.last_playhead_ppq = None;
+        self.output_buffer.clear();
+        self.midi_bridge.reset();
+    }
 }
 
 impl<'a> PluginAudioProcessor<'a, SonantShared, SonantPluginMainThread<'a>>
@@ -396,11 +909,14 @@ impl<'a> PluginAudioProcessor<'a, SonantShared, SonantPluginMainThread<'a>>
 {
     fn activate(
         host: HostAudioProcessorHandle<'a>,
-        _main_thread: &mut SonantPluginMainThread<'a>,
+        main_thread: &mut SonantPluginMainThread<'a>,
         shared: &'a SonantShared,
         audio_config: PluginAudioConfiguration,
     ) -> Result<Self, PluginError> {
         shared.reset_queues();
+        shared
+            .midi_bridge
+            .push_applied_clip(main_thread.applied_clip.clone());
         let sample_rate_hz =
             if audio_config.sample_rate.is_finite() && audio_config.sample_rate > 0.0 {
                 audio_config.sample_rate
@@ -409,69 +925,39 @@ impl<'a> PluginAudioProcessor<'a, SonantShared, SonantPluginMainThread<'a>>
             };
         Ok(Self {
             host,
-            midi_bridge: Arc::clone(&shared.midi_bridge),
-            pending_output_event: None,
-            sample_rate_hz,
+            block: AudioBlockProcessor::new(Arc::clone(&shared.midi_bridge), sample_rate_hz),
         })
     }
 
     fn process(
         &mut self,
         process: Process,
-        _audio: Audio,
+        audio: Audio,
         events: Events,
     ) -> Result<ProcessStatus, PluginError> {
-        // Some hosts can emit both MIDI and Note events for the same performance data.
-        // Prefer raw MIDI when present to avoid double-counting live notes.
-        let allow_note_events = should_accept_note_events(events.input.iter());
-        let transport_snapshot = TransportSnapshot::from_process(process, self.sample_rate_hz);
-
-        let mut received_live_input = false;
-        for event in events.input.iter() {
-            if let Some(midi_event) = map_input_event(event, allow_note_events, transport_snapshot)
-            {
-                self.midi_bridge.push_live_input(midi_event);
-                received_live_input = true;
-            }
-        }
-
-        if received_live_input {
+        if self.block.process_block(
+            process.transport,
+            audio.frames_count(),
+            events.input,
+            events.output,
+        ) {
             self.host.request_callback();
         }
-
-        if let Some(event) = self.pending_output_event.take()
-            && events.output.try_push(event.to_clap()).is_err()
-        {
-            // Host output is still saturated. Keep only the latest generated event.
-            self.pending_output_event = self.midi_bridge.pop_latest_generated_or(Some(event));
-            return Ok(ProcessStatus::Continue);
-        }
-
-        while let Some(event) = self.midi_bridge.pop_generated_output() {
-            if events.output.try_push(event.to_clap()).is_err() {
-                // Host output buffer is saturated. Keep the newest event and drop stale ones.
-                self.pending_output_event = self.midi_bridge.pop_latest_generated_or(Some(event));
-                break;
-            }
-        }
-
         Ok(ProcessStatus::Continue)
     }
 
     fn deactivate(self, _main_thread: &mut SonantPluginMainThread<'a>) {
-        self.midi_bridge.reset();
+        self.block.midi_bridge.reset();
     }
 
     fn reset(&mut self) {
-        self.pending_output_event = None;
-        self.midi_bridge.reset();
+        self.block.reset();
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use clack_plugin::events::event_types::{NoteOffEvent, NoteOnEvent};
     use std::num::NonZeroUsize;
     use std::sync::Arc;
 
@@ -482,6 +968,7 @@ mod tests {
     fn default_transport_snapshot() -> TransportSnapshot {
         TransportSnapshot {
             is_playing: false,
+            is_loop_active: false,
             playhead_ppq_at_block_start: 0.0,
             tempo_bpm: None,
             sample_rate_hz: 44_100.0,
@@ -555,6 +1042,7 @@ mod tests {
         let note_on = NoteOnEvent::new(24_000, Pckn::new(0u16, 0u16, 64u16, 0u32), 0.5);
         let snapshot = TransportSnapshot {
             is_playing: true,
+            is_loop_active: false,
             playhead_ppq_at_block_start: 8.0,
             tempo_bpm: Some(120.0),
             sample_rate_hz: 48_000.0,
@@ -572,6 +1060,82 @@ mod tests {
         );
     }
 
+    #[test]
+    fn transport_stop_marker_is_emitted_once_with_last_playing_position() {
+        let stopped = TransportSnapshot {
+            is_playing: false,
+            is_loop_active: false,
+            playhead_ppq_at_block_start: 0.0,
+            tempo_bpm: Some(120.0),
+            sample_rate_hz: 48_000.0,
+        };
+
+        let marker = transport_stop_marker(Some(10.5), stopped).expect("stop should emit a marker");
+        assert_eq!(marker.data, crate::app::LOOP_WRAP_MARKER_DATA);
+        assert_eq!(
+            marker.transport,
+            RtTransportState {
+                is_playing: false,
+                playhead_ppq: 10.5,
+            }
+        );
+
+        assert!(transport_stop_marker(None, stopped).is_none());
+        assert!(
+            transport_stop_marker(
+                Some(10.5),
+                TransportSnapshot {
+                    is_playing: true,
+                    ..stopped
+                }
+            )
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn loop_wrap_marker_is_emitted_only_when_looping_playhead_jumps_back() {
+        let looping = TransportSnapshot {
+            is_playing: true,
+            is_loop_active: true,
+            playhead_ppq_at_block_start: 0.0,
+            tempo_bpm: Some(120.0),
+            sample_rate_hz: 48_000.0,
+        };
+
+        let marker = loop_wrap_marker(Some(15.9), looping).expect("wrap should emit a marker");
+        assert_eq!(marker.data, crate::app::LOOP_WRAP_MARKER_DATA);
+        assert_eq!(
+            marker.transport,
+            RtTransportState {
+                is_playing: true,
+                playhead_ppq: 0.0,
+            }
+        );
+
+        assert!(loop_wrap_marker(None, looping).is_none());
+        assert!(
+            loop_wrap_marker(
+                Some(15.9),
+                TransportSnapshot {
+                    playhead_ppq_at_block_start: 16.0,
+                    ..looping
+                }
+            )
+            .is_none()
+        );
+        assert!(
+            loop_wrap_marker(
+                Some(15.9),
+                TransportSnapshot {
+                    is_loop_active: false,
+                    ..looping
+                }
+            )
+            .is_none()
+        );
+    }
+
     #[test]
     fn should_accept_note_events_is_false_when_midi_exists() {
         let midi_event = MidiEvent::new(0, 0, [0x90, 64, 100]);
@@ -619,6 +1183,47 @@ mod tests {
         assert_eq!(bridge.pop_generated_output(), None);
     }
 
+    #[test]
+    fn midi_bridge_counts_evictions_per_queue() {
+        let bridge = MidiBridge::new(1);
+        let event = RtMidiEvent {
+            time: 0,
+            port_index: 0,
+            data: [0x90, 60, 100],
+            transport: default_transport(),
+        };
+
+        bridge.push_live_input(event);
+        bridge.push_live_input(event);
+        bridge.push_live_input(event);
+        bridge.push_generated_output(event);
+        let _ = bridge.pop_latest_generated_or(Some(event));
+
+        assert_eq!(
+            bridge.overflow_metrics(),
+            crate::app::QueueOverflowMetrics {
+                live_input_dropped: 2,
+                app_input_dropped: 0,
+                generated_output_dropped: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn midi_bridge_reports_host_context_changes_once() {
+        let bridge = MidiBridge::new(1);
+        let context = crate::app::HostTransportContext {
+            tempo_bpm: Some(128.0),
+            time_signature: Some((7, 8)),
+            loop_length_beats: Some(16.0),
+        };
+
+        assert!(!bridge.store_host_context(crate::app::HostTransportContext::default()));
+        assert!(bridge.store_host_context(context));
+        assert!(!bridge.store_host_context(context));
+        assert_eq!(bridge.host_context(), context);
+    }
+
     #[test]
     fn midi_bridge_reset_clears_both_queues() {
         let bridge = MidiBridge::new(2);
@@ -670,6 +1275,61 @@ mod tests {
         assert_eq!(shared.pop_live_input_event(), None);
     }
 
+    #[test]
+    fn midi_bridge_thru_mask_matches_channel_voice_messages_only() {
+        let bridge = MidiBridge::new(2);
+        let event_on_channel = |status: u8| RtMidiEvent {
+            time: 0,
+            port_index: 0,
+            data: [status, 60, 100],
+            transport: default_transport(),
+        };
+
+        assert!(!bridge.is_thru_enabled_for(&event_on_channel(0x90)));
+
+        bridge.set_thru_channels(&[1, 10, 0, 17]);
+
+        assert!(bridge.is_thru_enabled_for(&event_on_channel(0x90)));
+        assert!(bridge.is_thru_enabled_for(&event_on_channel(0x89)));
+        assert!(!bridge.is_thru_enabled_for(&event_on_channel(0x91)));
+        assert!(!bridge.is_thru_enabled_for(&event_on_channel(0xF8)));
+
+        bridge.set_thru_channels(&[]);
+        assert!(!bridge.is_thru_enabled_for(&event_on_channel(0x90)));
+    }
+
+    #[test]
+    fn midi_bridge_generate_triggers_collapse_until_taken() {
+        let bridge = MidiBridge::new(2);
+        assert!(!bridge.take_generate_trigger());
+
+        bridge.trigger_generate();
+        bridge.trigger_generate();
+
+        assert!(bridge.take_generate_trigger());
+        assert!(!bridge.take_generate_trigger());
+    }
+
+    #[test]
+    fn midi_bridge_hands_back_a_retired_clip_when_its_queue_is_full() {
+        let bridge = MidiBridge::new(2);
+        let clip = || {
+            Box::new(crate::app::AppliedClip {
+                length_beats: 4.0,
+                events: Vec::new(),
+                note_expressions: false,
+            })
+        };
+        for _ in 0..RETIRED_CLIP_QUEUE_CAPACITY {
+            assert!(bridge.retire_applied_clip(clip()).is_ok());
+        }
+
+        assert!(bridge.retire_applied_clip(clip()).is_err());
+
+        bridge.drain_retired_clips();
+        assert!(bridge.retire_applied_clip(clip()).is_ok());
+    }
+
     #[test]
     fn pop_latest_generated_or_returns_newest_queued_event() {
         let bridge = MidiBridge::new(4);
@@ -38,10 +38,17 @@ fn note_port_definition(index: u32, is_input: bool) -> Option<NotePortInfo<'stat
         (NOTE_PORT_ID_OUT, NOTE_PORT_NAME_OUT)
     };
 
+    // The output also speaks the CLAP dialect so applied clips can carry note expressions.
+    let supported_dialects = if is_input {
+        NoteDialects::MIDI
+    } else {
+        NoteDialects::MIDI | NoteDialects::CLAP
+    };
+
     Some(NotePortInfo {
         id: ClapId::new(id),
         name,
-        supported_dialects: NoteDialects::MIDI,
+        supported_dialects,
         preferred_dialect: Some(NoteDialect::Midi),
     })
 }
@@ -73,6 +80,7 @@ mod tests {
         assert_eq!(output.name, NOTE_PORT_NAME_OUT);
         assert_eq!(output.preferred_dialect, Some(NoteDialect::Midi));
         assert!(output.supported_dialects.supports(NoteDialect::Midi));
+        assert!(output.supported_dialects.supports(NoteDialect::Clap));
     }
 
     #[test]
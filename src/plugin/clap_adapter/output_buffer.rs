@@ -0,0 +1,113 @@
+use super::RtMidiEvent;
+use super::clip_player::ClipOutput;
+
+/// Where a buffered output event came from; generated events are retried when the host is full.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum BufferedOutput {
+    Thru(RtMidiEvent),
+    Clip(ClipOutput),
+    Generated(RtMidiEvent),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TimedOutput {
+    time: u32,
+    // Insertion order, so events at the same time keep the order they were produced in.
+    order: u32,
+    output: BufferedOutput,
+}
+
+/// Collects one block's output so it can be handed to the host sorted by time, as CLAP
+/// requires of `out_events`. Storage is reserved up front and never grows on the audio thread.
+pub(super) struct OutputEventBuffer {
+    events: Vec<TimedOutput>,
+}
+
+impl OutputEventBuffer {
+    pub(super) fn with_capacity(capacity: usize) -> Self {
+        Self {
+            events: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Hands the event back when the buffer is full.
+    pub(super) fn push(&mut self, time: u32, output: BufferedOutput) -> Result<(), BufferedOutput> {
+        if self.events.len() == self.events.capacity() {
+            return Err(output);
+        }
+        self.events.push(TimedOutput {
+            time,
+            order: self.events.len() as u32,
+            output,
+        });
+        Ok(())
+    }
+
+    /// Empties the buffer in time order. The (time, insertion index) key is unique, so the
+    /// unstable sort keeps same-time events in order without the allocation a stable sort makes.
+    pub(super) fn drain_sorted(&mut self) -> impl Iterator<Item = (u32, BufferedOutput)> + '_ {
+        self.events
+            .sort_unstable_by_key(|event| (event.time, event.order));
+        self.events
+            .drain(..)
+            .map(|event| (event.time, event.output))
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.events.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BufferedOutput, OutputEventBuffer};
+    use crate::plugin::clap_adapter::clip_player::ClipOutput;
+    use crate::plugin::clap_adapter::{RtMidiEvent, RtTransportState};
+
+    fn generated(time: u32, key: u8) -> BufferedOutput {
+        BufferedOutput::Generated(RtMidiEvent {
+            time,
+            port_index: 0,
+            data: [0x90, key, 100],
+            transport: RtTransportState::default(),
+        })
+    }
+
+    #[test]
+    fn events_drain_in_time_order_keeping_insertion_order_for_ties() {
+        let mut buffer = OutputEventBuffer::with_capacity(8);
+        let thru = BufferedOutput::Thru(RtMidiEvent {
+            time: 40,
+            port_index: 0,
+            data: [0x90, 64, 90],
+            transport: RtTransportState::default(),
+        });
+        let release = BufferedOutput::Clip(ClipOutput::Midi([0x80, 60, 0]));
+        buffer.push(40, thru).expect("buffer has room");
+        buffer.push(0, release).expect("buffer has room");
+        buffer.push(12, generated(12, 67)).expect("buffer has room");
+        buffer.push(0, generated(0, 72)).expect("buffer has room");
+
+        let drained = buffer.drain_sorted().collect::<Vec<_>>();
+
+        assert_eq!(
+            drained,
+            vec![
+                (0, release),
+                (0, generated(0, 72)),
+                (12, generated(12, 67)),
+                (40, thru),
+            ]
+        );
+        assert_eq!(buffer.drain_sorted().count(), 0);
+    }
+
+    #[test]
+    fn a_full_buffer_hands_the_event_back_without_growing() {
+        let mut buffer = OutputEventBuffer::with_capacity(1);
+        buffer.push(0, generated(0, 60)).expect("buffer has room");
+
+        assert_eq!(buffer.push(5, generated(5, 62)), Err(generated(5, 62)));
+        assert_eq!(buffer.events.len(), 1);
+    }
+}
@@ -0,0 +1,322 @@
+use clack_extensions::params::{
+    ParamDisplayWriter, ParamInfo, ParamInfoFlags, ParamInfoWriter, PluginAudioProcessorParams,
+    PluginMainThreadParams,
+};
+use clack_plugin::events::UnknownEvent;
+use clack_plugin::events::io::{InputEvents, OutputEvents};
+use clack_plugin::events::spaces::CoreEventSpace;
+use clack_plugin::utils::ClapId;
+use serde::{Deserialize, Serialize};
+use std::ffi::CStr;
+use std::fmt::Write as _;
+
+use super::{MidiBridge, SonantAudioProcessor, SonantPluginMainThread};
+
+/// Automatable button: each move from below to above 0.5 fires Generate once.
+const GENERATE_PARAM_ID: ClapId = ClapId::new(0);
+/// Note number that fires Generate, or -1 to pass every note through as live input.
+const TRIGGER_NOTE_PARAM_ID: ClapId = ClapId::new(1);
+/// MIDI channel 1-16 the trigger note must arrive on, or 0 for any channel.
+const TRIGGER_CHANNEL_PARAM_ID: ClapId = ClapId::new(2);
+const PARAM_IDS: [ClapId; 3] = [
+    GENERATE_PARAM_ID,
+    TRIGGER_NOTE_PARAM_ID,
+    TRIGGER_CHANNEL_PARAM_ID,
+];
+
+/// The incoming note that fires Generate instead of reaching the helper as live input.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) struct GenerateTriggerNote {
+    /// `None` leaves every note to live input.
+    pub(super) key: Option<u8>,
+    /// MIDI channel 1-16; `None` matches any channel.
+    pub(super) channel: Option<u8>,
+}
+
+impl GenerateTriggerNote {
+    pub(super) fn is_valid(&self) -> bool {
+        self.key.is_none_or(|key| key <= 127)
+            && self
+                .channel
+                .is_none_or(|channel| (1..=16).contains(&channel))
+    }
+
+    /// Note on or off of the trigger key on a matching channel; only a sounding note on fires.
+    pub(super) fn kind(&self, data: [u8; 3]) -> Option<bool> {
+        let [status, key, velocity] = data;
+        let channel = (status & 0x0F) + 1;
+        if self.key != Some(key) || self.channel.is_some_and(|expected| expected != channel) {
+            return None;
+        }
+        match status & 0xF0 {
+            0x90 => Some(velocity > 0),
+            0x80 => Some(false),
+            _ => None,
+        }
+    }
+}
+
+impl PluginMainThreadParams for SonantPluginMainThread<'_> {
+    fn count(&mut self) -> u32 {
+        PARAM_IDS.len() as u32
+    }
+
+    fn get_info(&mut self, param_index: u32, info: &mut ParamInfoWriter) {
+        let Some(&id) = PARAM_IDS.get(param_index as usize) else {
+            return;
+        };
+        let (name, flags, min_value, max_value): (&[u8], _, _, _) = match id {
+            GENERATE_PARAM_ID => (
+                b"Generate",
+                ParamInfoFlags::IS_AUTOMATABLE | ParamInfoFlags::IS_STEPPED,
+                0.0,
+                1.0,
+            ),
+            TRIGGER_NOTE_PARAM_ID => (
+                b"Generate Trigger Note",
+                ParamInfoFlags::IS_STEPPED,
+                -1.0,
+                127.0,
+            ),
+            _ => (
+                b"Generate Trigger Channel",
+                ParamInfoFlags::IS_STEPPED,
+                0.0,
+                16.0,
+            ),
+        };
+        info.set(&ParamInfo {
+            id,
+            flags,
+            cookie: Default::default(),
+            name,
+            module: b"",
+            min_value,
+            max_value,
+            default_value: default_param_value(id),
+        });
+    }
+
+    fn get_value(&mut self, param_id: ClapId) -> Option<f64> {
+        param_value(&self.shared.midi_bridge, param_id)
+    }
+
+    fn value_to_text(
+        &mut self,
+        param_id: ClapId,
+        value: f64,
+        writer: &mut ParamDisplayWriter,
+    ) -> std::fmt::Result {
+        match param_id {
+            GENERATE_PARAM_ID => writer.write_str(if value >= 0.5 { "On" } else { "Off" }),
+            TRIGGER_NOTE_PARAM_ID if value < 0.0 => writer.write_str("Off"),
+            TRIGGER_CHANNEL_PARAM_ID if value < 1.0 => writer.write_str("Any"),
+            TRIGGER_NOTE_PARAM_ID | TRIGGER_CHANNEL_PARAM_ID => {
+                write!(writer, "{}", value.round() as i32)
+            }
+            _ => Err(std::fmt::Error),
+        }
+    }
+
+    fn text_to_value(&mut self, param_id: ClapId, text: &CStr) -> Option<f64> {
+        text_to_param_value(param_id, text.to_str().ok()?)
+    }
+
+    fn flush(
+        &mut self,
+        input_parameter_changes: &InputEvents,
+        _output_parameter_changes: &mut OutputEvents,
+    ) {
+        for event in input_parameter_changes.iter() {
+            apply_param_event(&self.shared.midi_bridge, event);
+        }
+    }
+}
+
+impl PluginAudioProcessorParams for SonantAudioProcessor<'_> {
+    fn flush(
+        &mut self,
+        input_parameter_changes: &InputEvents,
+        _output_parameter_changes: &mut OutputEvents,
+    ) {
+        let mut fired = false;
+        for event in input_parameter_changes.iter() {
+            fired |= apply_param_event(&self.block.midi_bridge, event);
+        }
+        if fired {
+            self.host.request_callback();
+        }
+    }
+}
+
+/// Applies a parameter value event to the bridge and reports whether it fired Generate. Other
+/// events are ignored, so this can run over a block's whole input.
+pub(super) fn apply_param_event(bridge: &MidiBridge, event: &UnknownEvent) -> bool {
+    let Some(CoreEventSpace::ParamValue(event)) = event.as_core_event() else {
+        return false;
+    };
+    let value = event.value();
+    match event.param_id() {
+        Some(GENERATE_PARAM_ID) => bridge.set_generate_button(value >= 0.5),
+        Some(TRIGGER_NOTE_PARAM_ID) => {
+            let trigger = bridge.generate_trigger_note();
+            bridge.set_generate_trigger_note(GenerateTriggerNote {
+                key: (value >= 0.0).then(|| value.round().min(127.0) as u8),
+                ..trigger
+            });
+            false
+        }
+        Some(TRIGGER_CHANNEL_PARAM_ID) => {
+            let trigger = bridge.generate_trigger_note();
+            bridge.set_generate_trigger_note(GenerateTriggerNote {
+                channel: (value >= 1.0).then(|| value.round().min(16.0) as u8),
+                ..trigger
+            });
+            false
+        }
+        _ => false,
+    }
+}
+
+fn param_value(bridge: &MidiBridge, param_id: ClapId) -> Option<f64> {
+    let trigger = bridge.generate_trigger_note();
+    match param_id {
+        GENERATE_PARAM_ID => Some(if bridge.is_generate_button_held() {
+            1.0
+        } else {
+            0.0
+        }),
+        TRIGGER_NOTE_PARAM_ID => Some(trigger.key.map_or(-1.0, f64::from)),
+        TRIGGER_CHANNEL_PARAM_ID => Some(trigger.channel.map_or(0.0, f64::from)),
+        _ => None,
+    }
+}
+
+fn default_param_value(param_id: ClapId) -> f64 {
+    match param_id {
+        TRIGGER_NOTE_PARAM_ID => -1.0,
+        _ => 0.0,
+    }
+}
+
+fn text_to_param_value(param_id: ClapId, text: &str) -> Option<f64> {
+    let text = text.trim();
+    match param_id {
+        GENERATE_PARAM_ID if text.eq_ignore_ascii_case("on") => Some(1.0),
+        GENERATE_PARAM_ID if text.eq_ignore_ascii_case("off") => Some(0.0),
+        TRIGGER_NOTE_PARAM_ID if text.eq_ignore_ascii_case("off") => Some(-1.0),
+        TRIGGER_NOTE_PARAM_ID => text
+            .parse::<u8>()
+            .ok()
+            .filter(|key| *key <= 127)
+            .map(f64::from),
+        TRIGGER_CHANNEL_PARAM_ID if text.eq_ignore_ascii_case("any") => Some(0.0),
+        TRIGGER_CHANNEL_PARAM_ID => text
+            .parse::<u8>()
+            .ok()
+            .filter(|channel| (1..=16).contains(channel))
+            .map(f64::from),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        GENERATE_PARAM_ID, GenerateTriggerNote, TRIGGER_CHANNEL_PARAM_ID, TRIGGER_NOTE_PARAM_ID,
+        apply_param_event, param_value, text_to_param_value,
+    };
+    use crate::plugin::clap_adapter::MidiBridge;
+    use clack_plugin::events::event_types::ParamValueEvent;
+    use clack_plugin::events::{Match, Pckn};
+    use clack_plugin::utils::{ClapId, Cookie};
+
+    fn set(bridge: &MidiBridge, param_id: ClapId, value: f64) -> bool {
+        let event = ParamValueEvent::new(0, param_id, Pckn::match_all(), value, Cookie::empty());
+        apply_param_event(bridge, event.as_ref())
+    }
+
+    #[test]
+    fn trigger_note_is_off_by_default() {
+        let trigger = GenerateTriggerNote::default();
+
+        assert_eq!(trigger.kind([0x90, 0, 100]), None);
+        assert_eq!(trigger.kind([0x90, 36, 100]), None);
+    }
+
+    #[test]
+    fn trigger_note_fires_on_note_on_and_swallows_note_off() {
+        let any_channel = GenerateTriggerNote {
+            key: Some(36),
+            channel: None,
+        };
+        assert_eq!(any_channel.kind([0x90, 36, 100]), Some(true));
+        assert_eq!(any_channel.kind([0x9F, 36, 1]), Some(true));
+        assert_eq!(any_channel.kind([0x90, 36, 0]), Some(false));
+        assert_eq!(any_channel.kind([0x85, 36, 64]), Some(false));
+        assert_eq!(any_channel.kind([0x90, 0, 100]), None);
+        assert_eq!(any_channel.kind([0xB0, 36, 127]), None);
+
+        let channel_ten = GenerateTriggerNote {
+            key: Some(36),
+            channel: Some(10),
+        };
+        assert_eq!(channel_ten.kind([0x99, 36, 100]), Some(true));
+        assert_eq!(channel_ten.kind([0x90, 36, 100]), None);
+    }
+
+    #[test]
+    fn params_configure_the_trigger_note_and_channel() {
+        let bridge = MidiBridge::new(2);
+
+        set(&bridge, TRIGGER_NOTE_PARAM_ID, 36.0);
+        set(&bridge, TRIGGER_CHANNEL_PARAM_ID, 10.0);
+        assert_eq!(
+            bridge.generate_trigger_note(),
+            GenerateTriggerNote {
+                key: Some(36),
+                channel: Some(10),
+            }
+        );
+        assert_eq!(param_value(&bridge, TRIGGER_NOTE_PARAM_ID), Some(36.0));
+        assert_eq!(param_value(&bridge, TRIGGER_CHANNEL_PARAM_ID), Some(10.0));
+
+        set(&bridge, TRIGGER_NOTE_PARAM_ID, -1.0);
+        set(&bridge, TRIGGER_CHANNEL_PARAM_ID, 0.0);
+        assert_eq!(
+            bridge.generate_trigger_note(),
+            GenerateTriggerNote::default()
+        );
+        assert_eq!(param_value(&bridge, TRIGGER_NOTE_PARAM_ID), Some(-1.0));
+    }
+
+    #[test]
+    fn generate_param_fires_once_per_press() {
+        let bridge = MidiBridge::new(2);
+
+        assert!(set(&bridge, GENERATE_PARAM_ID, 1.0));
+        assert!(!set(&bridge, GENERATE_PARAM_ID, 1.0));
+        assert!(bridge.take_generate_trigger());
+        assert!(!set(&bridge, GENERATE_PARAM_ID, 0.0));
+        assert!(set(&bridge, GENERATE_PARAM_ID, 0.8));
+    }
+
+    #[test]
+    fn param_text_parses_values_and_sentinels() {
+        assert_eq!(text_to_param_value(GENERATE_PARAM_ID, "On"), Some(1.0));
+        assert_eq!(
+            text_to_param_value(TRIGGER_NOTE_PARAM_ID, "off"),
+            Some(-1.0)
+        );
+        assert_eq!(
+            text_to_param_value(TRIGGER_NOTE_PARAM_ID, " 36 "),
+            Some(36.0)
+        );
+        assert_eq!(text_to_param_value(TRIGGER_NOTE_PARAM_ID, "128"), None);
+        assert_eq!(
+            text_to_param_value(TRIGGER_CHANNEL_PARAM_ID, "Any"),
+            Some(0.0)
+        );
+        assert_eq!(text_to_param_value(TRIGGER_CHANNEL_PARAM_ID, "17"), None);
+    }
+}
@@ -0,0 +1,181 @@
+//! Debug-only real-time safety audit, enabled by the `rt-audit` feature.
+//!
+//! Installs a global allocator that flags heap traffic on a thread while it is inside an
+//! [`AudioThreadScope`]. Blocking calls that cannot be intercepted from the allocator (locks,
+//! channel receives) must report themselves through [`record_blocking_call`].
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+thread_local! {
+    static IN_AUDIO_SCOPE: Cell<bool> = const { Cell::new(false) };
+    static SCOPE_VIOLATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+struct AuditingAllocator;
+
+#[global_allocator]
+static AUDITING_ALLOCATOR: AuditingAllocator = AuditingAllocator;
+
+unsafe impl GlobalAlloc for AuditingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record_violation();
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        record_violation();
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        record_violation();
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record_violation();
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+fn record_violation() {
+    // `try_with` because the allocator also runs while thread-locals are being torn down.
+    let _ = IN_AUDIO_SCOPE.try_with(|in_scope| {
+        if in_scope.get() {
+            let _ = SCOPE_VIOLATIONS.try_with(|violations| violations.set(violations.get() + 1));
+        }
+    });
+}
+
+/// Call before any operation that may block, such as acquiring a mutex. Production code goes
+/// through `audit_blocking_call`, which compiles away without the feature.
+pub(super) fn record_blocking_call() {
+    record_violation();
+}
+
+/// Marks the current thread as running audio-thread code until dropped. Dropping the scope
+/// panics if anything inside it allocated, freed, or blocked.
+pub(super) struct AudioThreadScope {
+    was_in_scope: bool,
+    violations_before: usize,
+}
+
+impl AudioThreadScope {
+    pub(super) fn enter() -> Self {
+        Self {
+            was_in_scope: IN_AUDIO_SCOPE.with(|in_scope| in_scope.replace(true)),
+            violations_before: SCOPE_VIOLATIONS.with(Cell::get),
+        }
+    }
+}
+
+impl Drop for AudioThreadScope {
+    fn drop(&mut self) {
+        IN_AUDIO_SCOPE.with(|in_scope| in_scope.set(self.was_in_scope));
+        let violations = SCOPE_VIOLATIONS.with(Cell::get) - self.violations_before;
+        if violations > 0 && !std::thread::panicking() {
+            panic!("{violations} heap allocation(s) or blocking call(s) on the audio thread");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AudioThreadScope;
+    use crate::app::{AppliedClip, AppliedClipEvent};
+    use crate::plugin::clap_adapter::{
+        AudioBlockProcessor, MidiBridge, RtMidiEvent, RtTransportState, audit_blocking_call,
+    };
+    use clack_plugin::events::event_types::MidiEvent;
+    use clack_plugin::events::io::{EventBuffer, InputEvents, OutputEvents};
+    use clack_plugin::events::spaces::CoreEventSpace;
+    use std::sync::Arc;
+
+    fn clip() -> AppliedClip {
+        AppliedClip {
+            length_beats: 4.0,
+            events: vec![AppliedClipEvent {
+                beat: 0.0,
+                data: [0x90, 60, 100],
+                expression: None,
+                choke: false,
+            }],
+            note_expressions: false,
+        }
+    }
+
+    fn midi_times(buffer: &EventBuffer) -> Vec<u32> {
+        buffer
+            .iter()
+            .filter_map(|event| match event.as_core_event() {
+                Some(CoreEventSpace::Midi(event)) => Some(event.time()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn process_block_runs_without_heap_traffic_or_blocking() {
+        let bridge = Arc::new(MidiBridge::new(8));
+        bridge.set_thru_channels(&[1]);
+        bridge.push_applied_clip(Some(clip()));
+        bridge.push_generated_output(RtMidiEvent {
+            time: 0,
+            port_index: 0,
+            data: [0x80, 60, 0],
+            transport: RtTransportState::default(),
+        });
+        let mut block = AudioBlockProcessor::new(Arc::clone(&bridge), 48_000.0);
+        // Event buffers are sized up front so the host side of the block does not allocate.
+        let mut input_buffer = EventBuffer::with_capacity(4);
+        input_buffer.push(MidiEvent::new(40, 0, [0x90, 64, 90]).as_ref());
+        let mut output_buffer = EventBuffer::with_capacity(64);
+
+        // `process_block` enters the audit scope itself, so any allocation, free or reported
+        // blocking call on this path panics here.
+        block.process_block(
+            None,
+            512,
+            &InputEvents::from_buffer(&input_buffer),
+            &mut OutputEvents::from_buffer(&mut output_buffer),
+        );
+
+        // The generated event was queued after the thru echo but is written first.
+        assert_eq!(midi_times(&output_buffer), vec![0, 40]);
+        assert!(bridge.pop_live_input().is_some());
+
+        // Replacing the playing clip hands the old one to the main thread instead of freeing it.
+        bridge.push_applied_clip(Some(clip()));
+        let empty_input = EventBuffer::with_capacity(0);
+        let needs_callback = block.process_block(
+            None,
+            512,
+            &InputEvents::from_buffer(&empty_input),
+            &mut OutputEvents::from_buffer(&mut output_buffer),
+        );
+
+        assert!(needs_callback);
+        assert!(bridge.retired_clip_queue.pop().is_some());
+    }
+
+    #[test]
+    fn allocation_inside_scope_is_reported() {
+        let result = std::panic::catch_unwind(|| {
+            let _scope = AudioThreadScope::enter();
+            std::hint::black_box(vec![0u8; 16]);
+        });
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn blocking_call_inside_scope_is_reported() {
+        let result = std::panic::catch_unwind(|| {
+            let _scope = AudioThreadScope::enter();
+            audit_blocking_call();
+        });
+
+        assert!(result.is_err());
+    }
+}
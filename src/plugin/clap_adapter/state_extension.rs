@@ -1,47 +1,325 @@
 use clack_extensions::state::PluginStateImpl;
 use clack_plugin::prelude::PluginError;
 use clack_plugin::stream::{InputStream, OutputStream};
+use serde::Serialize;
+use serde_json::{Map, Value};
 use std::io::{Read, Write};
 
+use crate::app::{InputTrackLayout, InputTrackModel};
+use crate::domain::ModelRef;
+
 use super::SonantPluginMainThread;
+use super::params_extension::GenerateTriggerNote;
 
 const STATE_MAGIC: &[u8; 8] = b"SONANT01";
-const STATE_VERSION: u32 = 1;
+const STATE_HEADER_LEN: usize = STATE_MAGIC.len() + 4;
+// First version whose payload is a JSON document; older payloads are lifted into it on load.
+const STATE_ENVELOPE_VERSION: u32 = 3;
+const STATE_VERSION: u32 = STATE_ENVELOPE_VERSION + STATE_MIGRATIONS.len() as u32;
+
+/// Upgrades a state document by one version, in place.
+type StateMigration = fn(&mut Map<String, Value>);
+
+/// Entry `i` migrates documents from `STATE_ENVELOPE_VERSION + i` to the next version. Append
+/// a migration here whenever the document schema changes; `STATE_VERSION` follows the length.
+const STATE_MIGRATIONS: &[StateMigration] = &[];
+
+#[derive(Serialize)]
+struct StateDocument<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    input_track_layout: Option<&'a InputTrackLayout>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    project_model: Option<&'a ModelRef>,
+    generate_trigger: GenerateTriggerNote,
+}
+
+/// What a saved project restores into the plugin.
+#[derive(Debug, Default, PartialEq)]
+struct RestoredState {
+    input_track_layout: Option<InputTrackLayout>,
+    project_model: Option<ModelRef>,
+    generate_trigger: GenerateTriggerNote,
+}
 
 impl PluginStateImpl for SonantPluginMainThread<'_> {
     fn save(&mut self, output: &mut OutputStream) -> Result<(), PluginError> {
-        output.write_all(STATE_MAGIC)?;
-        output.write_all(&STATE_VERSION.to_le_bytes())?;
+        // Pick up layout edits the helper sent since the last main-thread callback.
+        self.apply_helper_control_messages();
+        let bytes = encode_state(
+            self.input_track_layout.as_ref(),
+            self.project_model.as_ref(),
+            self.shared.midi_bridge.generate_trigger_note(),
+        );
+        output.write_all(&bytes)?;
         Ok(())
     }
 
     fn load(&mut self, input: &mut InputStream) -> Result<(), PluginError> {
         let mut bytes = Vec::new();
         input.read_to_end(&mut bytes)?;
+        let restored = decode_state(&bytes)?;
+        self.input_track_layout = restored.input_track_layout;
+        self.project_model = restored.project_model;
+        self.shared
+            .midi_bridge
+            .set_generate_trigger_note(restored.generate_trigger);
+        Ok(())
+    }
+}
 
-        // Backward compatibility: accept empty state from older plugin builds.
-        if bytes.is_empty() {
-            return Ok(());
-        }
+fn encode_state(
+    input_track_layout: Option<&InputTrackLayout>,
+    project_model: Option<&ModelRef>,
+    generate_trigger: GenerateTriggerNote,
+) -> Vec<u8> {
+    let document_bytes = serde_json::to_vec(&StateDocument {
+        input_track_layout,
+        project_model,
+        generate_trigger,
+    })
+    .unwrap_or_else(|_| b"{}".to_vec());
+
+    let mut bytes = Vec::with_capacity(STATE_HEADER_LEN + 4 + document_bytes.len());
+    bytes.extend_from_slice(STATE_MAGIC);
+    bytes.extend_from_slice(&STATE_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&(document_bytes.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&document_bytes);
+    bytes
+}
+
+fn decode_state(bytes: &[u8]) -> Result<RestoredState, PluginError> {
+    // Backward compatibility: accept empty state from older plugin builds.
+    if bytes.is_empty() {
+        return Ok(RestoredState::default());
+    }
+
+    if bytes.len() < STATE_HEADER_LEN {
+        return Err(PluginError::Message("Invalid state payload"));
+    }
 
-        if bytes.len() < STATE_MAGIC.len() + 4 {
-            return Err(PluginError::Message("Invalid state payload"));
+    if &bytes[..STATE_MAGIC.len()] != STATE_MAGIC {
+        return Err(PluginError::Message("Invalid state magic"));
+    }
+
+    let version = read_u32_le(bytes, STATE_MAGIC.len())
+        .ok_or(PluginError::Message("Invalid state payload"))?;
+
+    if version > STATE_VERSION {
+        return Err(PluginError::Message("Unsupported state version"));
+    }
+
+    let mut document = read_state_document(bytes, version)?;
+    migrate_state_document(
+        &mut document,
+        version.max(STATE_ENVELOPE_VERSION),
+        STATE_MIGRATIONS,
+    );
+
+    // A layout or model that no longer validates is dropped so the project still opens.
+    Ok(RestoredState {
+        input_track_layout: document
+            .remove("input_track_layout")
+            .and_then(|layout| serde_json::from_value::<InputTrackLayout>(layout).ok())
+            .filter(|layout| InputTrackModel::from_layout(layout).is_ok()),
+        project_model: document
+            .remove("project_model")
+            .and_then(|model| serde_json::from_value::<ModelRef>(model).ok())
+            .filter(|model| model.validate().is_ok()),
+        generate_trigger: document
+            .remove("generate_trigger")
+            .and_then(|trigger| serde_json::from_value::<GenerateTriggerNote>(trigger).ok())
+            .filter(GenerateTriggerNote::is_valid)
+            .unwrap_or_default(),
+    })
+}
+
+/// Reads the payload after the header as a document in the `STATE_ENVELOPE_VERSION` schema.
+fn read_state_document(bytes: &[u8], version: u32) -> Result<Map<String, Value>, PluginError> {
+    // Version 1 only carried the header.
+    if version < 2 {
+        return Ok(Map::new());
+    }
+
+    let payload_len = read_u32_le(bytes, STATE_HEADER_LEN)
+        .ok_or(PluginError::Message("Invalid state payload"))? as usize;
+    let payload_start = STATE_HEADER_LEN + 4;
+    let payload = bytes
+        .get(payload_start..payload_start + payload_len)
+        .ok_or(PluginError::Message("Invalid state payload"))?;
+
+    if version == 2 {
+        // Version 2 stored the bare layout JSON, or nothing.
+        let mut document = Map::new();
+        if let Ok(layout) = serde_json::from_slice::<Value>(payload) {
+            document.insert("input_track_layout".to_string(), layout);
         }
+        return Ok(document);
+    }
 
-        if &bytes[..STATE_MAGIC.len()] != STATE_MAGIC {
-            return Err(PluginError::Message("Invalid state magic"));
+    match serde_json::from_slice::<Value>(payload) {
+        Ok(Value::Object(document)) => Ok(document),
+        _ => Err(PluginError::Message("Invalid state document")),
+    }
+}
+
+fn migrate_state_document(
+    document: &mut Map<String, Value>,
+    version: u32,
+    migrations: &[StateMigration],
+) {
+    let first = (version - STATE_ENVELOPE_VERSION) as usize;
+    for migrate in migrations.iter().skip(first) {
+        migrate(document);
+    }
+}
+
+fn read_u32_le(bytes: &[u8], start: usize) -> Option<u32> {
+    let mut value_bytes = [0u8; 4];
+    value_bytes.copy_from_slice(bytes.get(start..start + 4)?);
+    Some(u32::from_le_bytes(value_bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        STATE_ENVELOPE_VERSION, STATE_MAGIC, StateMigration, decode_state, encode_state,
+        migrate_state_document,
+    };
+    use crate::app::{ChannelMapping, InputTrackLayout, SlotSourceAssignment};
+    use crate::domain::{ModelRef, ReferenceSlot, ReferenceSource};
+    use crate::plugin::clap_adapter::params_extension::GenerateTriggerNote;
+
+    fn sample_layout() -> InputTrackLayout {
+        InputTrackLayout {
+            rows: vec![ReferenceSlot::Melody, ReferenceSlot::DrumPattern],
+            slot_sources: vec![SlotSourceAssignment {
+                slot: ReferenceSlot::DrumPattern,
+                source: ReferenceSource::Live,
+            }],
+            channel_mappings: vec![ChannelMapping {
+                slot: ReferenceSlot::DrumPattern,
+                channel: 10,
+            }],
         }
+    }
+
+    #[test]
+    fn state_round_trip_restores_input_track_layout() {
+        let layout = sample_layout();
+        let bytes = encode_state(Some(&layout), None, GenerateTriggerNote::default());
+
+        let decoded = decode_state(&bytes).expect("state should decode");
+
+        assert_eq!(decoded.input_track_layout, Some(layout));
+        assert_eq!(decoded.project_model, None);
+    }
+
+    #[test]
+    fn state_round_trip_restores_project_model() {
+        let model = ModelRef {
+            provider: "openai_compatible".to_string(),
+            model: "gpt-4o-mini".to_string(),
+        };
+        let bytes = encode_state(None, Some(&model), GenerateTriggerNote::default());
+
+        let decoded = decode_state(&bytes).expect("state should decode");
+
+        assert_eq!(decoded.input_track_layout, None);
+        assert_eq!(decoded.project_model, Some(model));
+    }
+
+    #[test]
+    fn state_round_trip_restores_generate_trigger_note() {
+        let trigger = GenerateTriggerNote {
+            key: Some(36),
+            channel: Some(10),
+        };
+        let bytes = encode_state(None, None, trigger);
+
+        let decoded = decode_state(&bytes).expect("state should decode");
 
-        let version_start = STATE_MAGIC.len();
-        let version_end = version_start + 4;
-        let mut version_bytes = [0u8; 4];
-        version_bytes.copy_from_slice(&bytes[version_start..version_end]);
-        let version = u32::from_le_bytes(version_bytes);
+        assert_eq!(decoded.generate_trigger, trigger);
+    }
+
+    #[test]
+    fn state_without_layout_decodes_to_none() {
+        let bytes = encode_state(None, None, GenerateTriggerNote::default());
+
+        assert_eq!(
+            decode_state(&bytes)
+                .expect("state should decode")
+                .input_track_layout,
+            None
+        );
+    }
+
+    #[test]
+    fn version_one_state_is_accepted_without_layout() {
+        let mut bytes = STATE_MAGIC.to_vec();
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+
+        assert_eq!(
+            decode_state(&bytes)
+                .expect("v1 state should decode")
+                .input_track_layout,
+            None
+        );
+        assert_eq!(
+            decode_state(&[])
+                .expect("empty state should decode")
+                .input_track_layout,
+            None
+        );
+    }
+
+    #[test]
+    fn version_two_layout_payload_is_migrated() {
+        let layout = sample_layout();
+        let layout_bytes = serde_json::to_vec(&layout).expect("layout should serialize");
+        let mut bytes = STATE_MAGIC.to_vec();
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+        bytes.extend_from_slice(&(layout_bytes.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&layout_bytes);
 
-        if version > STATE_VERSION {
-            return Err(PluginError::Message("Unsupported state version"));
+        assert_eq!(
+            decode_state(&bytes)
+                .expect("v2 state should decode")
+                .input_track_layout,
+            Some(layout)
+        );
+    }
+
+    #[test]
+    fn migrations_run_in_order_from_the_saved_version() {
+        fn rename_layout(document: &mut serde_json::Map<String, serde_json::Value>) {
+            if let Some(layout) = document.remove("input_track_layout") {
+                document.insert("layout".to_string(), layout);
+            }
         }
+        fn tag_document(document: &mut serde_json::Map<String, serde_json::Value>) {
+            let renamed = document.contains_key("layout");
+            document.insert("renamed".to_string(), renamed.into());
+        }
+        let migrations: &[StateMigration] = &[rename_layout, tag_document];
 
-        Ok(())
+        let mut from_envelope = serde_json::Map::new();
+        from_envelope.insert("input_track_layout".to_string(), 1.into());
+        migrate_state_document(&mut from_envelope, STATE_ENVELOPE_VERSION, migrations);
+        assert_eq!(from_envelope.get("layout"), Some(&1.into()));
+        assert_eq!(from_envelope.get("renamed"), Some(&true.into()));
+
+        let mut from_next = serde_json::Map::new();
+        from_next.insert("input_track_layout".to_string(), 1.into());
+        migrate_state_document(&mut from_next, STATE_ENVELOPE_VERSION + 1, migrations);
+        assert_eq!(from_next.get("input_track_layout"), Some(&1.into()));
+        assert_eq!(from_next.get("renamed"), Some(&false.into()));
+    }
+
+    #[test]
+    fn truncated_layout_payload_is_rejected() {
+        let mut bytes = encode_state(Some(&sample_layout()), None, GenerateTriggerNote::default());
+        bytes.truncate(bytes.len() - 1);
+
+        assert!(decode_state(&bytes).is_err());
     }
 }
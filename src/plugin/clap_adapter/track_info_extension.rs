@@ -0,0 +1,28 @@
+use clack_extensions::track_info::{HostTrackInfo, PluginTrackInfoImpl};
+
+use super::SonantPluginMainThread;
+
+impl PluginTrackInfoImpl for SonantPluginMainThread<'_> {
+    fn changed(&mut self) {
+        self.refresh_host_track_name();
+    }
+}
+
+impl SonantPluginMainThread<'_> {
+    /// Re-reads the host's name for this track, which is all CLAP exposes of the arrangement:
+    /// transport carries no markers, so a track or clip named after its section is the best
+    /// hint of where in the song the part sits.
+    pub(super) fn refresh_host_track_name(&mut self) {
+        let track_info = self.host.shared().get_extension::<HostTrackInfo>();
+        self.host_track_name = track_info
+            .and_then(|track_info| track_info.get(&mut self.host))
+            .and_then(|info| {
+                info.name
+                    .as_deref()
+                    .map(|name| String::from_utf8_lossy(name).trim().to_string())
+            })
+            .filter(|name| !name.is_empty());
+        self.gui
+            .send_host_track_name(self.host_track_name.as_deref());
+    }
+}
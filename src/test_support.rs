@@ -0,0 +1,38 @@
+//! Fixtures shared by the unit tests.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A fresh directory under the system temp dir, removed with everything in it on drop.
+pub(crate) struct TestDir {
+    path: PathBuf,
+}
+
+impl TestDir {
+    /// Creates `sonant-<label>-<pid>-<n>`. Names stay short because tests bind Unix sockets
+    /// inside, and socket paths are limited to about 100 bytes.
+    pub(crate) fn new(label: &str) -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("sonant-{label}-{}-{id}", std::process::id()));
+        // A previous run with the same process id may have been killed before cleaning up.
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).expect("test directory must be creatable");
+        Self { path }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn join(&self, name: impl AsRef<Path>) -> PathBuf {
+        self.path.join(name)
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
@@ -1,36 +1,55 @@
 use std::sync::Arc;
 
+#[cfg(feature = "gui")]
 use sonant::{
-    app::{GenerationJobManager, GenerationService},
-    domain::{GenerationRequest, GenerationResult, LlmError, ModelRef},
-    infra::llm::{AnthropicProvider, LlmProvider, OpenAiCompatibleProvider, ProviderRegistry},
+    app::{GENERATION_JOB_DEFAULT_DEADLINE, GenerationJobManager, ProviderErrorBudget},
+    domain::{GenerationRequest, GenerationResult},
+    infra::llm::job_deadline_from_env,
 };
-
-use super::{
-    DEFAULT_ANTHROPIC_MODEL, DEFAULT_OPENAI_COMPAT_MODEL, STUB_MODEL_ID, STUB_PROVIDER_ID,
-    STUB_PROVIDER_NOTICE,
+use sonant::{
+    app::{GenerationService, sonant_config_dir},
+    domain::{LlmError, ModelRef, PrivacyFilterMode},
+    infra::llm::{
+        AUDIT_LOG_ENV, AnthropicProvider, AuditLog, LlmProvider, OpenAiCompatibleProvider,
+        ProviderRegistry, RemoteServerProvider, privacy_filter_from_env,
+    },
 };
 
+use super::{DEFAULT_ANTHROPIC_MODEL, DEFAULT_OPENAI_COMPAT_MODEL, STUB_PROVIDER_NOTICE};
+#[cfg(feature = "gui")]
+use super::{STUB_MODEL_ID, STUB_PROVIDER_ID};
+
+#[cfg(feature = "gui")]
 pub(super) struct GenerationBackend {
     pub(super) job_manager: Arc<GenerationJobManager>,
     pub(super) default_model: ModelRef,
     pub(super) startup_notice: Option<String>,
+    /// Present when `SONANT_AUDIT_LOG` is enabled and at least one real provider is registered.
+    pub(super) audit_log: Option<Arc<AuditLog>>,
+    pub(super) error_budget: ProviderErrorBudget,
+    /// Same providers as the job manager, for one-off candidate explanations.
+    pub(super) explanation_service: GenerationService,
 }
 
+#[cfg(feature = "gui")]
 pub(super) fn build_generation_backend() -> GenerationBackend {
-    let mut registry = ProviderRegistry::new();
-    let mut default_model = None;
     let mut notices = Vec::new();
-
-    register_anthropic_provider(&mut registry, &mut default_model, &mut notices);
-    register_openai_compatible_provider(&mut registry, &mut default_model, &mut notices);
+    let audit_log = open_audit_log(&mut notices);
+    let (registry, registered_models) =
+        register_configured_providers(audit_log.as_ref(), &mut notices);
 
     if registry.is_empty() {
         return build_stub_backend(notices);
     }
 
-    let service = GenerationService::new(registry);
-    let manager = match GenerationJobManager::new(service) {
+    let default_model = registered_models.first().cloned();
+    let service = GenerationService::new(registry)
+        .with_privacy_filter(read_privacy_filter(&mut notices))
+        .with_fallback_models(registered_models);
+    let error_budget = service.error_budget();
+    let explanation_service = service.clone();
+    let deadline = read_job_deadline(&mut notices);
+    let manager = match GenerationJobManager::with_deadline(service, deadline) {
         Ok(manager) => manager,
         Err(error) => {
             notices.push(format!(
@@ -46,16 +65,158 @@ pub(super) fn build_generation_backend() -> GenerationBackend {
         default_model: default_model
             .expect("default model must be configured when at least one provider exists"),
         startup_notice: (!notices.is_empty()).then(|| notices.join(" ")),
+        audit_log,
+        error_budget,
+        explanation_service,
+    }
+}
+
+/// Generation service for headless commands; fails with the provider notices when none is configured.
+pub(crate) fn build_generation_service() -> Result<GenerationService, String> {
+    let mut notices = Vec::new();
+    let audit_log = open_audit_log(&mut notices);
+    let (registry, _) = register_configured_providers(audit_log.as_ref(), &mut notices);
+    if registry.is_empty() {
+        notices.push(STUB_PROVIDER_NOTICE.to_string());
+        return Err(notices.join(" "));
     }
+    Ok(GenerationService::new(registry).with_privacy_filter(read_privacy_filter(&mut notices)))
+}
+
+/// A configured provider and the model `sonant bench` sends its requests to.
+pub(crate) struct BenchmarkTarget {
+    /// `anthropic`, `openai` or `remote`, as accepted by `--providers`.
+    pub(crate) name: &'static str,
+    pub(crate) provider: Box<dyn LlmProvider>,
+    pub(crate) model: ModelRef,
+}
+
+/// Providers with credentials in the environment; the notices explain misconfigured ones.
+pub(crate) fn build_benchmark_targets() -> (Vec<BenchmarkTarget>, Vec<String>) {
+    let mut targets = Vec::new();
+    let mut notices = Vec::new();
+
+    match AnthropicProvider::from_env() {
+        Ok(provider) => targets.push(BenchmarkTarget {
+            name: "anthropic",
+            model: ModelRef {
+                provider: provider.provider_id().to_string(),
+                model: DEFAULT_ANTHROPIC_MODEL.to_string(),
+            },
+            provider: Box::new(provider),
+        }),
+        Err(error) if !is_missing_credentials_error(&error) => notices.push(format!(
+            "Anthropic provider is unavailable: {}",
+            error.user_message()
+        )),
+        Err(_) => {}
+    }
+    match OpenAiCompatibleProvider::from_env() {
+        Ok(provider) => targets.push(BenchmarkTarget {
+            name: "openai",
+            model: ModelRef {
+                provider: provider.provider_id().to_string(),
+                model: provider
+                    .supported_models()
+                    .into_iter()
+                    .next()
+                    .unwrap_or_else(|| DEFAULT_OPENAI_COMPAT_MODEL.to_string()),
+            },
+            provider: Box::new(provider),
+        }),
+        Err(error) if !is_missing_credentials_error(&error) => notices.push(format!(
+            "OpenAI-compatible provider is unavailable: {}",
+            error.user_message()
+        )),
+        Err(_) => {}
+    }
+    match RemoteServerProvider::from_env() {
+        Ok(provider) => targets.push(BenchmarkTarget {
+            name: "remote",
+            model: ModelRef {
+                provider: provider.provider_id().to_string(),
+                model: provider.default_model().to_string(),
+            },
+            provider: Box::new(provider),
+        }),
+        Err(error) if !is_missing_credentials_error(&error) => notices.push(format!(
+            "Remote Sonant server is unavailable: {}",
+            error.user_message()
+        )),
+        Err(_) => {}
+    }
+
+    (targets, notices)
+}
+
+fn open_audit_log(notices: &mut Vec<String>) -> Option<Arc<AuditLog>> {
+    let Some(config_dir) = sonant_config_dir() else {
+        if std::env::var_os(AUDIT_LOG_ENV).is_some() {
+            notices
+                .push("Audit log is disabled: no config directory could be resolved.".to_string());
+        }
+        return None;
+    };
+    match AuditLog::from_env(&config_dir) {
+        Ok(audit_log) => audit_log.map(Arc::new),
+        Err(error) => {
+            notices.push(format!("Audit log is disabled: {}", error.user_message()));
+            None
+        }
+    }
+}
+
+// An unreadable setting blocks identifying text rather than letting it through.
+fn read_privacy_filter(notices: &mut Vec<String>) -> Option<PrivacyFilterMode> {
+    privacy_filter_from_env().unwrap_or_else(|error| {
+        notices.push(format!(
+            "Safe mode falls back to blocking identifying text: {}",
+            error.user_message()
+        ));
+        Some(PrivacyFilterMode::Flag)
+    })
+}
+
+#[cfg(feature = "gui")]
+fn read_job_deadline(notices: &mut Vec<String>) -> std::time::Duration {
+    job_deadline_from_env()
+        .unwrap_or_else(|error| {
+            notices.push(format!(
+                "Using the default generation deadline: {}",
+                error.user_message()
+            ));
+            None
+        })
+        .unwrap_or(GENERATION_JOB_DEFAULT_DEADLINE)
+}
+
+/// The registry and each registered provider's default model, in fallback order.
+fn register_configured_providers(
+    audit_log: Option<&Arc<AuditLog>>,
+    notices: &mut Vec<String>,
+) -> (ProviderRegistry, Vec<ModelRef>) {
+    let mut registry = ProviderRegistry::new();
+    let mut registered_models = Vec::new();
+
+    register_anthropic_provider(&mut registry, &mut registered_models, audit_log, notices);
+    register_openai_compatible_provider(&mut registry, &mut registered_models, audit_log, notices);
+    register_remote_server_provider(&mut registry, &mut registered_models, audit_log, notices);
+
+    (registry, registered_models)
 }
 
 fn register_anthropic_provider(
     registry: &mut ProviderRegistry,
-    default_model: &mut Option<ModelRef>,
+    registered_models: &mut Vec<ModelRef>,
+    audit_log: Option<&Arc<AuditLog>>,
     notices: &mut Vec<String>,
 ) {
     match AnthropicProvider::from_env() {
         Ok(provider) => {
+            let provider = match audit_log {
+                Some(audit_log) => provider.with_audit_log(Arc::clone(audit_log)),
+                None => provider,
+            };
             if let Err(error) = registry.register(provider) {
                 notices.push(format!(
                     "Anthropic provider could not be registered: {}",
@@ -64,12 +225,10 @@ fn register_anthropic_provider(
                 return;
             }
 
-            if default_model.is_none() {
-                *default_model = Some(ModelRef {
-                    provider: "anthropic".to_string(),
-                    model: DEFAULT_ANTHROPIC_MODEL.to_string(),
-                });
-            }
+            registered_models.push(ModelRef {
+                provider: "anthropic".to_string(),
+                model: DEFAULT_ANTHROPIC_MODEL.to_string(),
+            });
         }
         Err(error) if !is_missing_credentials_error(&error) => {
             notices.push(format!(
@@ -83,11 +242,16 @@ fn register_anthropic_provider(
 
 fn register_openai_compatible_provider(
     registry: &mut ProviderRegistry,
-    default_model: &mut Option<ModelRef>,
+    registered_models: &mut Vec<ModelRef>,
+    audit_log: Option<&Arc<AuditLog>>,
     notices: &mut Vec<String>,
 ) {
     match OpenAiCompatibleProvider::from_env() {
         Ok(provider) => {
+            let provider = match audit_log {
+                Some(audit_log) => provider.with_audit_log(Arc::clone(audit_log)),
+                None => provider,
+            };
             let provider_id = provider.provider_id().to_string();
             let default_model_id = provider
                 .supported_models()
@@ -103,12 +267,10 @@ fn register_openai_compatible_provider(
                 return;
             }
 
-            if default_model.is_none() {
-                *default_model = Some(ModelRef {
-                    provider: provider_id,
-                    model: default_model_id,
-                });
-            }
+            registered_models.push(ModelRef {
+                provider: provider_id,
+                model: default_model_id,
+            });
         }
         Err(error) if !is_missing_credentials_error(&error) => {
             notices.push(format!(
@@ -120,6 +282,43 @@ fn register_openai_compatible_provider(
     }
 }
 
+fn register_remote_server_provider(
+    registry: &mut ProviderRegistry,
+    registered_models: &mut Vec<ModelRef>,
+    audit_log: Option<&Arc<AuditLog>>,
+    notices: &mut Vec<String>,
+) {
+    match RemoteServerProvider::from_env() {
+        Ok(provider) => {
+            let provider = match audit_log {
+                Some(audit_log) => provider.with_audit_log(Arc::clone(audit_log)),
+                None => provider,
+            };
+            let model = ModelRef {
+                provider: provider.provider_id().to_string(),
+                model: provider.default_model().to_string(),
+            };
+            if let Err(error) = registry.register(provider) {
+                notices.push(format!(
+                    "Remote Sonant server could not be registered: {}",
+                    error.user_message()
+                ));
+                return;
+            }
+
+            registered_models.push(model);
+        }
+        Err(error) if !is_missing_credentials_error(&error) => {
+            notices.push(format!(
+                "Remote Sonant server is unavailable: {}",
+                error.user_message()
+            ));
+        }
+        Err(_) => {}
+    }
+}
+
+#[cfg(feature = "gui")]
 fn build_stub_backend(mut notices: Vec<String>) -> GenerationBackend {
     let mut registry = ProviderRegistry::new();
     registry
@@ -127,6 +326,8 @@ fn build_stub_backend(mut notices: Vec<String>) -> GenerationBackend {
         .expect("stub provider registration should succeed");
 
     let service = GenerationService::new(registry);
+    let error_budget = service.error_budget();
+    let explanation_service = service.clone();
     let manager = GenerationJobManager::new(service)
         .expect("stub generation worker should start for helper fallback");
 
@@ -139,18 +340,24 @@ fn build_stub_backend(mut notices: Vec<String>) -> GenerationBackend {
             model: STUB_MODEL_ID.to_string(),
         },
         startup_notice: Some(notices.join(" ")),
+        audit_log: None,
+        error_budget,
+        explanation_service,
     }
 }
 
 fn is_missing_credentials_error(error: &LlmError) -> bool {
     matches!(
         error,
-        LlmError::Validation { message } if message.contains("API key is missing")
+        LlmError::Validation { message }
+            if message.contains("API key is missing") || message.contains("server URL is missing")
     )
 }
 
+#[cfg(feature = "gui")]
 struct HelperUnconfiguredProvider;
 
+#[cfg(feature = "gui")]
 impl LlmProvider for HelperUnconfiguredProvider {
     fn provider_id(&self) -> &str {
         STUB_PROVIDER_ID
@@ -1,7 +1,9 @@
+#[cfg(feature = "gui")]
 use gpui::{App, AppContext, Application, Bounds, WindowBounds, WindowOptions, px, size};
+#[cfg(feature = "gui")]
 use gpui_component::Root;
 
-#[cfg(target_os = "macos")]
+#[cfg(all(feature = "gui", target_os = "macos"))]
 use cocoa::{
     appkit::{
         NSApplication, NSApplicationActivationPolicy::NSApplicationActivationPolicyAccessory,
@@ -9,62 +11,137 @@ use cocoa::{
     base::nil,
 };
 
+// Without `gui` only the provider wiring used by headless commands is built.
 mod backend;
+#[cfg(feature = "gui")]
+mod piano_roll_window;
+#[cfg(feature = "gui")]
 mod request;
+#[cfg(feature = "gui")]
 mod state;
+#[cfg(feature = "gui")]
 mod theme;
+#[cfg(feature = "gui")]
 mod utils;
+#[cfg(feature = "gui")]
 mod window;
 
+pub(crate) use backend::{BenchmarkTarget, build_benchmark_targets, build_generation_service};
+
+#[cfg(feature = "gui")]
 const HELPER_WINDOW_WIDTH: f32 = 800.0;
+#[cfg(feature = "gui")]
 const HELPER_WINDOW_HEIGHT: f32 = 640.0;
+#[cfg(feature = "gui")]
 const PROMPT_EDITOR_ROWS: usize = 5;
-const JOB_UPDATE_POLL_INTERVAL_MS: u64 = 50;
+#[cfg(feature = "gui")]
+const JOB_UPDATE_IDLE_TICK_MS: u64 = 500;
+#[cfg(feature = "gui")]
+const JOB_UPDATE_PLAYBACK_POLL_INTERVAL_MS: u64 = 250;
 
+#[cfg(feature = "gui")]
 const BPM_MIN: u16 = 20;
+#[cfg(feature = "gui")]
 const BPM_MAX: u16 = 300;
+#[cfg(feature = "gui")]
 const DEFAULT_BPM: u16 = 120;
+#[cfg(feature = "gui")]
 const DEFAULT_DENSITY: u8 = 3;
+#[cfg(feature = "gui")]
 const DEFAULT_COMPLEXITY: u8 = 3;
+#[cfg(feature = "gui")]
+const DEFAULT_SYNCOPATION: u8 = 3;
+#[cfg(feature = "gui")]
 const DEFAULT_TEMPERATURE: f32 = 0.7;
+#[cfg(feature = "gui")]
 const DEFAULT_TOP_P: f32 = 0.9;
+#[cfg(feature = "gui")]
 const DEFAULT_MAX_TOKENS: u16 = 512;
+#[cfg(feature = "gui")]
 const DEFAULT_VARIATION_COUNT: u8 = 1;
 
 const DEFAULT_ANTHROPIC_MODEL: &str = "claude-3-5-sonnet";
 const DEFAULT_OPENAI_COMPAT_MODEL: &str = "gpt-5.2";
+#[cfg(feature = "gui")]
 const GPUI_HELPER_REQUEST_ID_PREFIX: &str = "gpui-helper-req";
 
+#[cfg(feature = "gui")]
 const STUB_PROVIDER_ID: &str = "helper_stub";
+#[cfg(feature = "gui")]
 const STUB_MODEL_ID: &str = "helper-unconfigured";
 
+#[cfg(feature = "gui")]
 const PROMPT_PLACEHOLDER: &str =
     "Describe what to generate, for example: Bright pop melody in C major with syncopation.";
+#[cfg(feature = "gui")]
 const PROMPT_VALIDATION_MESSAGE: &str = "Prompt must not be empty.";
-const STUB_PROVIDER_NOTICE: &str = "No LLM provider is configured. Set SONANT_ANTHROPIC_API_KEY or SONANT_OPENAI_COMPAT_API_KEY to enable real generation requests.";
+const STUB_PROVIDER_NOTICE: &str = "No LLM provider is configured. Set SONANT_ANTHROPIC_API_KEY, SONANT_OPENAI_COMPAT_API_KEY, or SONANT_REMOTE_SERVER_URL with SONANT_REMOTE_SERVER_TOKEN to enable real generation requests.";
 
+#[cfg(feature = "gui")]
 const SETTINGS_ANTHROPIC_API_KEY_PLACEHOLDER: &str = "Anthropic API key";
+#[cfg(feature = "gui")]
 const SETTINGS_OPENAI_API_KEY_PLACEHOLDER: &str = "OpenAI-compatible API key";
+#[cfg(feature = "gui")]
 const SETTINGS_CUSTOM_BASE_URL_PLACEHOLDER: &str = "Custom base URL (optional)";
+#[cfg(feature = "gui")]
+const SETTINGS_REMOTE_SERVER_URL_PLACEHOLDER: &str = "https://sonant.studio.lan:8440 (optional)";
+#[cfg(feature = "gui")]
+const SETTINGS_REMOTE_SERVER_TOKEN_PLACEHOLDER: &str = "Remote server token";
+#[cfg(feature = "gui")]
 const SETTINGS_DEFAULT_MODEL_PLACEHOLDER: &str = "Default model ID";
+#[cfg(feature = "gui")]
 const SETTINGS_CONTEXT_WINDOW_PLACEHOLDER: &str = "Context window tokens";
-const MIDI_SLOT_FILE_PICKER_PROMPT: &str = "Select MIDI File (.mid/.midi)";
+#[cfg(feature = "gui")]
+const SETTINGS_TICK_RESOLUTION_PLACEHOLDER: &str = "Ticks per quarter note, e.g. 480";
+#[cfg(feature = "gui")]
+const SETTINGS_AUTO_SAVE_FOLDER_PLACEHOLDER: &str =
+    "Folder for every generated candidate (optional)";
+#[cfg(feature = "gui")]
+const SETTINGS_EXPORT_NAME_TEMPLATE_PLACEHOLDER: &str = "sonant-{request-id}";
+#[cfg(feature = "gui")]
+const INPUT_TRACK_PRESET_NAME_PLACEHOLDER: &str = "Preset name";
+#[cfg(feature = "gui")]
+const SAMPLING_PROFILE_NAME_PLACEHOLDER: &str = "Profile name";
+#[cfg(feature = "gui")]
+const CANDIDATE_ANNOTATION_PLACEHOLDER: &str = "Note for this candidate, e.g. use for bridge";
+#[cfg(feature = "gui")]
+const ARRANGEMENT_SECTION_PROMPT_PLACEHOLDER: &str =
+    "Section idea, e.g. sparse pads building tension";
+#[cfg(feature = "gui")]
+const CONSTRAINTS_PLACEHOLDER: &str = "range: C2..C4; rhythm: 16th; avoid: b9 (optional)";
+#[cfg(feature = "gui")]
+const ARRANGEMENT_EXPORT_PICKER_PROMPT: &str = "Export Arrangement To Folder";
+#[cfg(feature = "gui")]
+const HISTORY_EXPORT_PICKER_PROMPT: &str = "Export History To Folder";
+#[cfg(feature = "gui")]
+const STEM_EXPORT_PICKER_PROMPT: &str = "Export Stems To Folder";
+#[cfg(feature = "gui")]
+const TAKE_SAVE_PICKER_PROMPT: &str = "Save Take To Folder";
+#[cfg(feature = "gui")]
+const REQUEST_IMPORT_PICKER_PROMPT: &str = "Select Generation Request (.json)";
+#[cfg(feature = "gui")]
+const BATCH_PROMPTS_PICKER_PROMPT: &str = "Select Prompt List (.txt, one per line, or .csv)";
+#[cfg(feature = "gui")]
+const MIDI_SLOT_FILE_PICKER_PROMPT: &str =
+    "Select MIDI File(s) (.mid/.midi) — multiple clips are joined in order";
+#[cfg(feature = "gui")]
 const MIDI_SLOT_DROP_ERROR_MESSAGE: &str = "Drop at least one file to set the MIDI reference.";
+#[cfg(feature = "gui")]
 const MIDI_SLOT_UNSUPPORTED_FILE_MESSAGE: &str = "Only .mid or .midi files are supported.";
+#[cfg(feature = "gui")]
 const DEBUG_PROMPT_LOG_ENV: &str = "SONANT_HELPER_DEBUG_PROMPT_LOG";
+#[cfg(feature = "gui")]
 const DEBUG_PROMPT_PREVIEW_CHARS: usize = 120;
 
+#[cfg(feature = "gui")]
 pub(crate) fn run_gpui_helper() {
     Application::new().run(|cx: &mut App| {
         set_plugin_helper_activation_policy();
         gpui_component::init(cx);
         theme::apply_default_theme(cx);
 
-        let bounds = Bounds::centered(
-            None,
-            size(px(HELPER_WINDOW_WIDTH), px(HELPER_WINDOW_HEIGHT)),
-            cx,
-        );
+        let (width, height) = helper_window_size();
+        let bounds = Bounds::centered(None, size(px(width), px(height)), cx);
         let options = WindowOptions {
             window_bounds: Some(WindowBounds::Windowed(bounds)),
             ..Default::default()
@@ -81,19 +158,22 @@ pub(crate) fn run_gpui_helper() {
             return;
         }
 
-        cx.on_window_closed(|cx| {
-            if cx.windows().is_empty() {
-                cx.quit();
-            }
-        })
-        .detach();
-
         cx.activate(true);
         set_plugin_helper_activation_policy();
     });
 }
 
-#[cfg(target_os = "macos")]
+/// GPUI sizes windows in logical pixels and picks up the display scale itself.
+#[cfg(feature = "gui")]
+fn helper_window_size() -> (f32, f32) {
+    std::env::var(sonant::app::HELPER_WINDOW_SIZE_ENV)
+        .ok()
+        .and_then(|value| sonant::app::parse_helper_window_size(&value))
+        .map(|(width, height)| (width as f32, height as f32))
+        .unwrap_or((HELPER_WINDOW_WIDTH, HELPER_WINDOW_HEIGHT))
+}
+
+#[cfg(all(feature = "gui", target_os = "macos"))]
 fn set_plugin_helper_activation_policy() {
     unsafe {
         let app = NSApplication::sharedApplication(nil);
@@ -101,30 +181,38 @@ fn set_plugin_helper_activation_policy() {
     }
 }
 
-#[cfg(not(target_os = "macos"))]
+#[cfg(all(feature = "gui", not(target_os = "macos")))]
 fn set_plugin_helper_activation_policy() {}
 
-#[cfg(test)]
+#[cfg(all(test, feature = "gui"))]
 mod tests {
     use super::request::{
-        PromptSubmissionModel, build_generation_request_with_prompt_validation,
+        PromptSubmissionModel, SamplingParam, alternate_provider_model,
+        build_generation_request_with_prompt_validation, provider_demotion_notice,
         validate_prompt_input,
     };
     use super::state::{
-        MidiSlotErrorState, can_retry_midi_load_error, mode_reference_requirement,
-        mode_reference_requirement_satisfied,
+        MidiSlotErrorState, OverlayMenu, OverlayMenus, TapTempo, can_retry_midi_load_error,
+        mode_reference_requirement, mode_reference_requirement_satisfied,
+    };
+    use super::theme::{
+        DisplayPreference, OsDisplayPreferences, SlotColors, SonantTheme, TrackColor, parse_os_flag,
     };
     use super::utils::{
-        choose_dropped_midi_path, display_file_name_from_path, normalize_api_key_input,
-        parse_truthy_flag, prompt_preview,
+        NumberFormat, choose_dropped_midi_path, display_file_name_from_path,
+        normalize_api_key_input, parse_truthy_flag, pitch_label, prompt_preview,
     };
-    use sonant::app::LoadMidiError;
+    use super::{DEFAULT_MAX_TOKENS, DEFAULT_TEMPERATURE, DEFAULT_TOP_P};
+    use gpui::{point, px};
+    use sonant::app::{LoadMidiError, ProviderDemotion};
     use sonant::domain::{
-        FileReferenceInput, GenerationMode, LlmError, MidiReferenceEvent, MidiReferenceSummary,
-        ModelRef, ReferenceSlot, ReferenceSource, has_supported_midi_extension,
+        FileReferenceInput, GeneratedNote, GenerationMode, LlmError, MidiReferenceEvent,
+        MidiReferenceSummary, ModelRef, ReferenceSlot, ReferenceSource,
+        has_supported_midi_extension,
     };
     use sonant::infra::midi::MidiLoadError;
     use std::path::{Path, PathBuf};
+    use std::time::{Duration, Instant};
 
     fn test_model() -> ModelRef {
         ModelRef {
@@ -151,6 +239,8 @@ mod tests {
                 delta_tick: 0,
                 event: "NoteOn channel=0 key=60 vel=100".to_string(),
             }],
+            transposition: None,
+            tempo: None,
         }
     }
 
@@ -175,6 +265,8 @@ mod tests {
                 event: "LiveMidi channel=2 status=0x91 data1=55 data2=100 port=1 time=120"
                     .to_string(),
             }],
+            transposition: None,
+            tempo: None,
         }
     }
 
@@ -269,11 +361,221 @@ mod tests {
         assert_eq!(request.references, references);
     }
 
+    #[test]
+    fn submission_model_marks_requests_for_reference_anonymization_when_enabled() {
+        let mut model = PromptSubmissionModel::new(test_model());
+        model.set_anonymize_references(true);
+        let references = vec![
+            test_reference_with_slot("/Clients/Acme/Hook Lead.mid", ReferenceSlot::Melody),
+            test_reference_with_slot("/Clients/Acme/Chords.midi", ReferenceSlot::ChordProgression),
+        ];
+
+        let request = model
+            .prepare_request(
+                GenerationMode::Melody,
+                "hook".to_string(),
+                references.clone(),
+            )
+            .expect("request should be prepared");
+
+        // The paths stay for history; the generation service renames them when sending.
+        assert!(request.anonymize_references);
+        assert_eq!(request.references, references);
+    }
+
+    #[test]
+    fn submission_model_transposes_references_only_when_enabled() {
+        let mut reference = test_reference("/tmp/c-major-riff.mid");
+        reference.events = [60, 64, 67, 72, 62, 65, 69, 71, 60, 67]
+            .into_iter()
+            .map(|pitch| MidiReferenceEvent {
+                track: 0,
+                absolute_tick: 0,
+                delta_tick: 0,
+                event: format!(
+                    "Midi {{ channel: u4(0), message: NoteOn {{ key: u7({pitch}), vel: u7(100) }} }}"
+                ),
+            })
+            .collect();
+        let mut model = PromptSubmissionModel::new(test_model());
+        model.set_key("D");
+        model.set_scale("major");
+
+        let untouched = model
+            .prepare_request(
+                GenerationMode::Melody,
+                "riff".to_string(),
+                vec![reference.clone()],
+            )
+            .expect("request should be prepared");
+        model.set_transpose_references(true);
+        let transposed = model
+            .prepare_request(GenerationMode::Melody, "riff".to_string(), vec![reference])
+            .expect("request should be prepared");
+
+        assert_eq!(untouched.references[0].transposition, None);
+        assert_eq!(
+            transposed.references[0]
+                .transposition
+                .as_ref()
+                .map(|transposition| transposition.semitones),
+            Some(2)
+        );
+        assert_eq!(
+            (
+                transposed.references[0].min_pitch,
+                transposed.references[0].max_pitch
+            ),
+            (62, 74)
+        );
+    }
+
+    #[test]
+    fn display_preferences_follow_the_os_unless_forced() {
+        assert_eq!(parse_os_flag("1\n"), Some(true));
+        assert_eq!(parse_os_flag("false\n"), Some(false));
+        assert_eq!(parse_os_flag("No such key"), None);
+
+        let os = OsDisplayPreferences {
+            high_contrast: true,
+            reduced_motion: false,
+        };
+        let automatic = SonantTheme::for_preferences(
+            DisplayPreference::Auto,
+            DisplayPreference::Auto,
+            SlotColors::default(),
+            os,
+        );
+        assert!(!automatic.reduced_motion);
+        assert_eq!(automatic.colors.glow_primary, gpui::transparent_black());
+
+        let forced = SonantTheme::for_preferences(
+            DisplayPreference::Off,
+            DisplayPreference::On,
+            SlotColors::default(),
+            os,
+        );
+        assert!(forced.reduced_motion);
+        assert_eq!(
+            forced.colors.glow_primary,
+            SonantTheme::default().colors.glow_primary
+        );
+    }
+
+    #[test]
+    fn overlay_menus_close_on_an_outside_press_without_reopening_from_their_trigger() {
+        let trigger = point(px(40.0), px(12.0));
+        let elsewhere = point(px(300.0), px(200.0));
+        let mut menus = OverlayMenus::default();
+
+        menus.toggle(OverlayMenu::Channel(1), trigger);
+        assert_eq!(menus.open_menu(), Some(OverlayMenu::Channel(1)));
+        menus.toggle(OverlayMenu::SlotType(1), trigger);
+        assert_eq!(menus.open_menu(), Some(OverlayMenu::SlotType(1)));
+
+        // The press on the open menu's own trigger lands outside the menu first.
+        assert!(menus.dismiss(trigger));
+        menus.toggle(OverlayMenu::SlotType(1), trigger);
+        assert_eq!(menus.open_menu(), None);
+        menus.toggle(OverlayMenu::SlotType(1), trigger);
+        assert_eq!(menus.open_menu(), Some(OverlayMenu::SlotType(1)));
+
+        assert!(menus.dismiss(elsewhere));
+        assert!(!menus.dismiss(elsewhere));
+        menus.toggle(OverlayMenu::SlotType(1), trigger);
+        assert!(menus.is_open(OverlayMenu::SlotType(1)));
+        assert!(menus.close());
+        assert!(!menus.close());
+    }
+
+    #[test]
+    fn row_overlay_menus_follow_their_row_and_close_with_it() {
+        let press = point(px(10.0), px(10.0));
+        let mut menus = OverlayMenus::default();
+        menus.toggle(OverlayMenu::AddTrack(Some(2)), press);
+        menus.remap_rows(|row| Some(row + 1));
+        assert_eq!(menus.open_menu(), Some(OverlayMenu::AddTrack(Some(3))));
+        menus.remap_rows(|row| (row != 3).then_some(row));
+        assert_eq!(menus.open_menu(), None);
+
+        menus.toggle(OverlayMenu::Presets, press);
+        menus.remap_rows(|_| None);
+        assert_eq!(menus.open_menu(), Some(OverlayMenu::Presets));
+    }
+
+    #[test]
+    fn tap_tempo_averages_the_current_run_of_taps() {
+        let start = Instant::now();
+        let mut tap_tempo = TapTempo::default();
+        assert_eq!(tap_tempo.tap(start), None);
+        assert_eq!(tap_tempo.tap(start + Duration::from_millis(500)), Some(120));
+        assert_eq!(
+            tap_tempo.tap(start + Duration::from_millis(1_000)),
+            Some(120)
+        );
+        assert_eq!(
+            tap_tempo.tap(start + Duration::from_millis(1_300)),
+            Some(138)
+        );
+
+        for tap in 1..=10 {
+            tap_tempo.tap(start + Duration::from_millis(1_300 + tap * 400));
+        }
+        assert_eq!(
+            tap_tempo.tap_count(),
+            8,
+            "only the latest taps are averaged"
+        );
+        assert_eq!(
+            tap_tempo.tap(start + Duration::from_millis(5_700)),
+            Some(150)
+        );
+
+        let paused = start + Duration::from_secs(20);
+        assert_eq!(tap_tempo.tap(paused), None, "a long pause starts a new run");
+        assert_eq!(tap_tempo.tap_count(), 1);
+        assert_eq!(tap_tempo.tap(paused + Duration::from_millis(750)), Some(80));
+    }
+
+    #[test]
+    fn custom_slot_colors_replace_the_default_track_colors() {
+        let default_colors = SonantTheme::default().colors;
+        assert_eq!(
+            default_colors.slot_color(ReferenceSlot::Bassline),
+            default_colors.track_red
+        );
+        assert_eq!(TrackColor::Pink.next(), TrackColor::Purple);
+
+        let mut slot_colors = SlotColors::default();
+        slot_colors.set(ReferenceSlot::Bassline, TrackColor::Cyan);
+        let theme = SonantTheme::for_preferences(
+            DisplayPreference::On,
+            DisplayPreference::Auto,
+            slot_colors,
+            OsDisplayPreferences::default(),
+        );
+
+        assert_eq!(
+            theme.colors.slot_color(ReferenceSlot::Bassline),
+            theme.colors.track_cyan
+        );
+        assert_eq!(
+            theme.colors.slot_color(ReferenceSlot::Harmony),
+            theme.colors.track_cyan,
+            "slots may share a color"
+        );
+        assert_eq!(
+            theme.colors.slot_color(ReferenceSlot::Melody),
+            theme.colors.track_purple
+        );
+    }
+
     #[test]
     fn submission_model_applies_updated_parameter_values() {
         let mut model = PromptSubmissionModel::new(test_model());
         model.set_density(5);
         model.set_complexity(4);
+        model.set_syncopation(2);
         model.set_bpm(134);
         model.set_key("D#");
         model.set_scale("Minor (Aeolian)");
@@ -287,6 +589,71 @@ mod tests {
         assert_eq!(request.params.scale, "Minor (Aeolian)");
         assert_eq!(request.params.density, 5);
         assert_eq!(request.params.complexity, 4);
+        assert_eq!(request.params.syncopation, 2);
+    }
+
+    #[test]
+    fn submission_model_toggles_locked_notes_into_requests() {
+        let mut model = PromptSubmissionModel::new(test_model());
+        let note = GeneratedNote {
+            pitch: 67,
+            start_tick: 480,
+            duration_tick: 240,
+            velocity: 100,
+            channel: 1,
+        };
+
+        assert!(model.toggle_locked_note(&note));
+        let request = model
+            .prepare_request(GenerationMode::Melody, "prompt".to_string(), Vec::new())
+            .expect("request should be prepared");
+        assert_eq!(request.locked_notes, vec![note.clone()]);
+
+        let retimed = GeneratedNote {
+            duration_tick: 120,
+            ..note
+        };
+        assert!(!model.toggle_locked_note(&retimed));
+        assert!(model.locked_notes().is_empty());
+    }
+
+    #[test]
+    fn submission_model_applies_imported_request_settings() {
+        let mut model = PromptSubmissionModel::new(test_model());
+        let mut imported = build_generation_request_with_prompt_validation(
+            "bug-report-1".to_string(),
+            ModelRef {
+                provider: "openai_compatible".to_string(),
+                model: "gpt-5.2".to_string(),
+            },
+            GenerationMode::Bassline,
+            "walking bass".to_string(),
+            Vec::new(),
+        )
+        .expect("request should build");
+        imported.params.bpm = 96;
+        imported.params.key = "F".to_string();
+        imported.params.density = 2;
+        imported.params.syncopation = 5;
+        imported.params.temperature = Some(1.4);
+        imported.params.top_p = None;
+
+        model.apply_request(&imported);
+        let request = model
+            .prepare_request(
+                GenerationMode::Bassline,
+                "walking bass".to_string(),
+                Vec::new(),
+            )
+            .expect("request should be prepared");
+
+        assert_eq!(request.model, imported.model);
+        assert_eq!(request.params.bpm, 96);
+        assert_eq!(request.params.key, "F");
+        assert_eq!(request.params.density, 2);
+        assert_eq!(request.params.syncopation, 5);
+        assert_eq!(request.params.temperature, Some(1.4));
+        assert_eq!(request.params.top_p, Some(DEFAULT_TOP_P));
     }
 
     #[test]
@@ -294,9 +661,11 @@ mod tests {
         let mut model = PromptSubmissionModel::new(test_model());
         model.set_density(0);
         model.set_complexity(9);
+        model.set_syncopation(0);
 
         assert_eq!(model.density(), 1);
         assert_eq!(model.complexity(), 5);
+        assert_eq!(model.syncopation(), 1);
     }
 
     #[test]
@@ -309,6 +678,73 @@ mod tests {
         assert_eq!(model.bpm(), 300);
     }
 
+    #[test]
+    fn submission_model_clamps_sampling_params_to_provider_ranges() {
+        let mut model = PromptSubmissionModel::new(test_model());
+        model.step_sampling_param(SamplingParam::Temperature, 20);
+        model.step_sampling_param(SamplingParam::MaxTokens, -100);
+        assert_eq!(model.sampling().temperature, 1.0);
+        assert_eq!(model.sampling().max_tokens, 1);
+
+        model.set_model(ModelRef {
+            provider: "openai_compatible".to_string(),
+            model: "gpt-5.2".to_string(),
+        });
+        model.step_sampling_param(SamplingParam::Temperature, 20);
+        assert_eq!(model.sampling().temperature, 2.0);
+
+        model.set_model(test_model());
+        assert_eq!(model.sampling().temperature, 1.0);
+
+        model.reset_sampling_param(SamplingParam::Temperature);
+        model.reset_sampling_param(SamplingParam::MaxTokens);
+        let request = model
+            .prepare_request(GenerationMode::Melody, "prompt".to_string(), Vec::new())
+            .expect("request should be prepared");
+        assert_eq!(request.params.temperature, Some(DEFAULT_TEMPERATURE));
+        assert_eq!(request.params.top_p, Some(DEFAULT_TOP_P));
+        assert_eq!(request.params.max_tokens, Some(DEFAULT_MAX_TOKENS));
+    }
+
+    #[test]
+    fn retry_uses_fresh_id_and_clamps_sampling_for_alternate_provider() {
+        let mut model = PromptSubmissionModel::new(ModelRef {
+            provider: "openai_compatible".to_string(),
+            model: "gpt-5.2".to_string(),
+        });
+        model.step_sampling_param(SamplingParam::Temperature, 40);
+        let failed = model
+            .prepare_request(GenerationMode::Melody, "prompt".to_string(), Vec::new())
+            .expect("request should be prepared");
+
+        let same = model.prepare_retry(&failed, None);
+        assert_ne!(same.request_id, failed.request_id);
+        assert_eq!(same.model, failed.model);
+        assert_eq!(same.params.temperature, Some(2.0));
+
+        let alternate = alternate_provider_model(&failed.model.provider, true, true)
+            .expect("anthropic should be offered");
+        let switched = model.prepare_retry(&failed, Some(alternate));
+        assert_eq!(switched.model, test_model());
+        assert_eq!(switched.params.temperature, Some(1.0));
+        assert_eq!(alternate_provider_model("anthropic", true, false), None);
+    }
+
+    #[test]
+    fn provider_demotion_notice_names_provider_and_cool_down() {
+        let now = Instant::now();
+        let demotion = ProviderDemotion {
+            provider: "anthropic".to_string(),
+            failure_rate: 0.75,
+            until: now + Duration::from_secs(90),
+        };
+
+        assert_eq!(
+            provider_demotion_notice(&demotion, now),
+            "Anthropic is demoted after 75% of recent calls failed; generations use the next configured provider for 90s."
+        );
+    }
+
     #[test]
     fn submission_model_preserves_multiple_reference_slots_in_request() {
         let mut model = PromptSubmissionModel::new(test_model());
@@ -592,4 +1028,54 @@ mod tests {
         assert_eq!(display_file_name_from_path("melody.mid"), "melody.mid");
         assert_eq!(display_file_name_from_path("/tmp/"), "tmp");
     }
+
+    #[test]
+    fn number_format_follows_the_locale_language() {
+        assert_eq!(
+            NumberFormat::for_locale("de_DE.UTF-8"),
+            NumberFormat::DECIMAL_COMMA
+        );
+        assert_eq!(
+            NumberFormat::for_locale("pt-BR"),
+            NumberFormat::DECIMAL_COMMA
+        );
+        assert_eq!(
+            NumberFormat::for_locale("en_US.UTF-8"),
+            NumberFormat::DECIMAL_POINT
+        );
+        assert_eq!(NumberFormat::for_locale("C"), NumberFormat::DECIMAL_POINT);
+    }
+
+    #[test]
+    fn numbers_parse_with_either_separator_convention() {
+        let comma = NumberFormat::DECIMAL_COMMA;
+        let point = NumberFormat::DECIMAL_POINT;
+
+        assert_eq!(comma.parse_decimal("120,5"), Some(120.5));
+        assert_eq!(comma.parse_integer("8.192"), Some(8192));
+        assert_eq!(comma.parse_integer("1.234.567"), Some(1_234_567));
+        assert_eq!(comma.parse_decimal("1.234,5"), Some(1234.5));
+        assert_eq!(point.parse_decimal("1,234.5"), Some(1234.5));
+        assert_eq!(point.parse_integer("8,192"), Some(8192));
+        assert_eq!(point.parse_decimal("120,5"), Some(120.5));
+        assert_eq!(point.parse_integer("16\u{a0}384"), Some(16384));
+        assert_eq!(point.parse_integer("32'768"), Some(32768));
+        assert_eq!(point.parse_integer("8.192"), None);
+    }
+
+    #[test]
+    fn malformed_numbers_are_rejected() {
+        let format = NumberFormat::DECIMAL_COMMA;
+        for raw in ["", ",", "abc", "-5", "1e3", "1.23.4", ".123", "12,3,4"] {
+            assert_eq!(format.parse_decimal(raw), None, "{raw:?}");
+        }
+    }
+
+    #[test]
+    fn pitch_labels_use_middle_c_as_c4() {
+        assert_eq!(pitch_label(60), "C4");
+        assert_eq!(pitch_label(0), "C-1");
+        assert_eq!(pitch_label(70), "A#4");
+        assert_eq!(pitch_label(127), "G9");
+    }
 }
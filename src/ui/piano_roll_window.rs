@@ -0,0 +1,120 @@
+use gpui::{
+    AnyWindowHandle, Context, Entity, IntoElement, Render, ScrollHandle, Subscription, WeakEntity,
+    Window, div, prelude::*, px,
+};
+use gpui_component::button::{Button, ButtonVariants as _};
+
+use super::theme::SonantTheme;
+use super::window::SonantMainWindow;
+
+/// Piano roll and candidate picker in a window of their own. It keeps no state besides its
+/// scroll position: everything it shows and every click goes through the main window's entity.
+pub(super) struct DetachedPianoRollWindow {
+    main: WeakEntity<SonantMainWindow>,
+    main_window: AnyWindowHandle,
+    vertical_scroll_handle: ScrollHandle,
+    horizontal_scroll_handle: ScrollHandle,
+    _main_subscription: Subscription,
+}
+
+impl DetachedPianoRollWindow {
+    pub(super) fn new(
+        main: Entity<SonantMainWindow>,
+        main_window: AnyWindowHandle,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let main_subscription = cx.observe(&main, |_, _, cx| cx.notify());
+        let weak_main = main.downgrade();
+        window.on_window_should_close(cx, move |_window, cx| {
+            let _ = weak_main.update(cx, |this, cx| this.on_piano_roll_reattached(cx));
+            true
+        });
+
+        Self {
+            main: main.downgrade(),
+            main_window,
+            vertical_scroll_handle: ScrollHandle::new(),
+            horizontal_scroll_handle: ScrollHandle::new(),
+            _main_subscription: main_subscription,
+        }
+    }
+
+    fn on_candidate_selected(&mut self, index: usize, cx: &mut Context<Self>) {
+        let main = self.main.clone();
+        // Selecting also focuses the main window's candidate list, so run it in that window.
+        let _ = self.main_window.update(cx, |_, window, cx| {
+            let _ = main.update(cx, |this, cx| this.on_candidate_selected(index, window, cx));
+        });
+    }
+
+    fn on_reattach_clicked(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let _ = self
+            .main
+            .update(cx, |this, cx| this.on_piano_roll_reattached(cx));
+        window.remove_window();
+    }
+}
+
+impl Render for DetachedPianoRollWindow {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.read_global(|theme: &SonantTheme, _| theme.clone());
+        let colors = theme.colors;
+        let Some(main) = self.main.upgrade() else {
+            return div()
+                .size_full()
+                .bg(colors.surface_background)
+                .into_any_element();
+        };
+
+        let candidate_buttons = main
+            .read(cx)
+            .candidate_picker_entries()
+            .into_iter()
+            .map(|(index, label, selected)| {
+                let button = Button::new(("detached-candidate", index))
+                    .label(label)
+                    .on_click(cx.listener(move |this, _, _window, cx| {
+                        this.on_candidate_selected(index, cx)
+                    }));
+                if selected { button.primary() } else { button }
+            })
+            .collect::<Vec<_>>();
+        let reattach_button = Button::new("detached-piano-roll-reattach")
+            .label("Reattach")
+            .on_click(cx.listener(|this, _, window, cx| this.on_reattach_clicked(window, cx)));
+        let piano_roll = main.read(cx).piano_roll_view(
+            self.main.clone(),
+            &self.vertical_scroll_handle,
+            &self.horizontal_scroll_handle,
+            colors,
+            theme.radius.control,
+        );
+
+        div()
+            .id("detached-piano-roll")
+            .size_full()
+            .flex()
+            .flex_col()
+            .gap(theme.spacing.section_gap)
+            .p(theme.spacing.panel_padding)
+            .bg(colors.surface_background)
+            .child(
+                div()
+                    .flex()
+                    .flex_wrap()
+                    .items_center()
+                    .gap_2()
+                    .child(
+                        div()
+                            .text_size(px(11.0))
+                            .text_color(colors.muted_foreground)
+                            .child("Candidates:"),
+                    )
+                    .children(candidate_buttons)
+                    .child(reattach_button),
+            )
+            .child(div().flex_1().min_h_0().child(piano_roll))
+            .into_any_element()
+    }
+}
@@ -1,16 +1,139 @@
+use std::time::Instant;
+
+use sonant::app::ProviderDemotion;
 use sonant::domain::{
-    GenerationMode, GenerationParams, GenerationRequest, LlmError, MidiReferenceSummary, ModelRef,
+    GENERATION_CONTRACT_VERSION, GeneratedNote, GenerationMode, GenerationParams,
+    GenerationRequest, LlmError, MidiReferenceSummary, ModeParamSpec, ModelRef, TickResolution,
+    ValidationStrictness,
 };
+use sonant::infra::midi::{normalize_reference_tempo, transpose_references_to_key};
 
 use super::{
-    BPM_MAX, BPM_MIN, DEFAULT_BPM, DEFAULT_COMPLEXITY, DEFAULT_DENSITY, DEFAULT_MAX_TOKENS,
-    DEFAULT_TEMPERATURE, DEFAULT_TOP_P, DEFAULT_VARIATION_COUNT, GPUI_HELPER_REQUEST_ID_PREFIX,
+    BPM_MAX, BPM_MIN, DEFAULT_ANTHROPIC_MODEL, DEFAULT_BPM, DEFAULT_COMPLEXITY, DEFAULT_DENSITY,
+    DEFAULT_MAX_TOKENS, DEFAULT_OPENAI_COMPAT_MODEL, DEFAULT_SYNCOPATION, DEFAULT_TEMPERATURE,
+    DEFAULT_TOP_P, DEFAULT_VARIATION_COUNT, GPUI_HELPER_REQUEST_ID_PREFIX,
 };
 
 const PARAM_LEVEL_MIN: u8 = 1;
 const PARAM_LEVEL_MAX: u8 = 5;
 const DEFAULT_KEY: &str = "C";
 const DEFAULT_SCALE: &str = "major";
+const ANTHROPIC_PROVIDER_ID: &str = "anthropic";
+const OPENAI_COMPAT_PROVIDER_ID: &str = "openai_compatible";
+const TEMPERATURE_STEP: f32 = 0.05;
+const TOP_P_STEP: f32 = 0.05;
+const MAX_TOKENS_STEP: u16 = 128;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum SamplingParam {
+    Temperature,
+    TopP,
+    MaxTokens,
+}
+
+impl SamplingParam {
+    pub(super) const ALL: [Self; 3] = [Self::Temperature, Self::TopP, Self::MaxTokens];
+
+    pub(super) fn label(self) -> &'static str {
+        match self {
+            Self::Temperature => "Temperature",
+            Self::TopP => "Top P",
+            Self::MaxTokens => "Max Tokens",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct SamplingParams {
+    pub(super) temperature: f32,
+    pub(super) top_p: f32,
+    pub(super) max_tokens: u16,
+}
+
+impl Default for SamplingParams {
+    fn default() -> Self {
+        Self {
+            temperature: DEFAULT_TEMPERATURE,
+            top_p: DEFAULT_TOP_P,
+            max_tokens: DEFAULT_MAX_TOKENS,
+        }
+    }
+}
+
+impl SamplingParams {
+    pub(super) fn display_value(&self, param: SamplingParam) -> String {
+        match param {
+            SamplingParam::Temperature => format!("{:.2}", self.temperature),
+            SamplingParam::TopP => format!("{:.2}", self.top_p),
+            SamplingParam::MaxTokens => self.max_tokens.to_string(),
+        }
+    }
+
+    fn clamped_to(self, ranges: SamplingRanges) -> Self {
+        Self {
+            temperature: self.temperature.clamp(0.0, ranges.temperature_max),
+            top_p: self.top_p.clamp(0.0, 1.0),
+            max_tokens: self.max_tokens.clamp(1, ranges.max_tokens_max),
+        }
+    }
+}
+
+/// Upper bounds the provider APIs accept; lower bounds are 0.0 (or 1 token) everywhere.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct SamplingRanges {
+    pub(super) temperature_max: f32,
+    pub(super) max_tokens_max: u16,
+}
+
+pub(super) fn sampling_ranges_for_provider(provider: &str) -> SamplingRanges {
+    if provider == ANTHROPIC_PROVIDER_ID {
+        SamplingRanges {
+            temperature_max: 1.0,
+            max_tokens_max: 8192,
+        }
+    } else {
+        SamplingRanges {
+            temperature_max: 2.0,
+            max_tokens_max: 16384,
+        }
+    }
+}
+
+/// Default model of the provider other than `provider`, if that provider has an API key.
+pub(super) fn alternate_provider_model(
+    provider: &str,
+    anthropic_configured: bool,
+    openai_configured: bool,
+) -> Option<ModelRef> {
+    if provider == ANTHROPIC_PROVIDER_ID {
+        openai_configured.then(|| ModelRef {
+            provider: OPENAI_COMPAT_PROVIDER_ID.to_string(),
+            model: DEFAULT_OPENAI_COMPAT_MODEL.to_string(),
+        })
+    } else {
+        anthropic_configured.then(|| ModelRef {
+            provider: ANTHROPIC_PROVIDER_ID.to_string(),
+            model: DEFAULT_ANTHROPIC_MODEL.to_string(),
+        })
+    }
+}
+
+pub(super) fn provider_demotion_notice(demotion: &ProviderDemotion, now: Instant) -> String {
+    format!(
+        "{} is demoted after {:.0}% of recent calls failed; generations use the next configured provider for {}s.",
+        provider_display_name(&demotion.provider),
+        demotion.failure_rate * 100.0,
+        demotion.remaining(now).as_secs().max(1)
+    )
+}
+
+pub(super) fn provider_display_name(provider: &str) -> &'static str {
+    if provider == ANTHROPIC_PROVIDER_ID {
+        "Anthropic"
+    } else {
+        "OpenAI"
+    }
+}
 
 #[derive(Debug, Clone)]
 pub(super) struct PromptSubmissionModel {
@@ -21,6 +144,13 @@ pub(super) struct PromptSubmissionModel {
     scale: String,
     density: u8,
     complexity: u8,
+    syncopation: u8,
+    tick_resolution: TickResolution,
+    sampling: SamplingParams,
+    locked_notes: Vec<GeneratedNote>,
+    anonymize_references: bool,
+    transpose_references: bool,
+    validation: Option<ValidationStrictness>,
 }
 
 impl PromptSubmissionModel {
@@ -33,6 +163,13 @@ impl PromptSubmissionModel {
             scale: DEFAULT_SCALE.to_string(),
             density: clamp_param_level(DEFAULT_DENSITY),
             complexity: clamp_param_level(DEFAULT_COMPLEXITY),
+            syncopation: clamp_param_level(DEFAULT_SYNCOPATION),
+            tick_resolution: TickResolution::DEFAULT,
+            sampling: SamplingParams::default(),
+            locked_notes: Vec::new(),
+            anonymize_references: false,
+            transpose_references: false,
+            validation: None,
         }
     }
 
@@ -42,11 +179,7 @@ impl PromptSubmissionModel {
         prompt: String,
         references: Vec<MidiReferenceSummary>,
     ) -> Result<GenerationRequest, LlmError> {
-        let request_id = format!(
-            "{GPUI_HELPER_REQUEST_ID_PREFIX}-{}",
-            self.next_request_number
-        );
-        self.next_request_number = self.next_request_number.saturating_add(1);
+        let request_id = self.next_request_id();
         let mut request = build_generation_request_with_prompt_validation(
             request_id,
             self.model.clone(),
@@ -59,11 +192,107 @@ impl PromptSubmissionModel {
         request.params.scale = self.scale.clone();
         request.params.density = self.density;
         request.params.complexity = self.complexity;
+        request.params.syncopation = self.syncopation;
+        request.params.tick_resolution = self.tick_resolution;
+        request.params.temperature = Some(self.sampling.temperature);
+        request.params.top_p = Some(self.sampling.top_p);
+        request.params.max_tokens = Some(self.sampling.max_tokens);
+        request.locked_notes = self.locked_notes.clone();
+        request.validation = self.validation;
+        normalize_reference_tempo(&mut request.references, self.bpm);
+        if self.transpose_references && ModeParamSpec::for_mode(mode).key {
+            transpose_references_to_key(&mut request.references, &self.key, &self.scale);
+        }
+        request.anonymize_references = self.anonymize_references;
         Ok(request)
     }
 
+    /// Copies a failed request under a fresh id. Switching `model` also clamps sampling to the
+    /// new provider's ranges.
+    pub(super) fn prepare_retry(
+        &mut self,
+        failed: &GenerationRequest,
+        model: Option<ModelRef>,
+    ) -> GenerationRequest {
+        let mut retry = GenerationRequest {
+            request_id: self.next_request_id(),
+            ..failed.clone()
+        };
+        if let Some(model) = model {
+            let ranges = sampling_ranges_for_provider(&model.provider);
+            let params = &mut retry.params;
+            params.temperature = params
+                .temperature
+                .map(|temperature| temperature.clamp(0.0, ranges.temperature_max));
+            params.max_tokens = params
+                .max_tokens
+                .map(|max_tokens| max_tokens.clamp(1, ranges.max_tokens_max));
+            retry.model = model;
+        }
+        retry
+    }
+
+    fn next_request_id(&mut self) -> String {
+        let request_id = format!(
+            "{GPUI_HELPER_REQUEST_ID_PREFIX}-{}",
+            self.next_request_number
+        );
+        self.next_request_number = self.next_request_number.saturating_add(1);
+        request_id
+    }
+
+    /// Loads model, musical params, sampling, and locks from a previously serialized request.
+    pub(super) fn apply_request(&mut self, request: &GenerationRequest) {
+        let params = &request.params;
+        self.set_model(request.model.clone());
+        self.set_bpm(params.bpm);
+        self.set_key(&params.key);
+        self.set_scale(&params.scale);
+        self.set_density(params.density);
+        self.set_complexity(params.complexity);
+        self.set_syncopation(params.syncopation);
+        let sampling = self.sampling;
+        self.set_sampling(SamplingParams {
+            temperature: params.temperature.unwrap_or(sampling.temperature),
+            top_p: params.top_p.unwrap_or(sampling.top_p),
+            max_tokens: params.max_tokens.unwrap_or(sampling.max_tokens),
+        });
+        self.locked_notes = request.locked_notes.clone();
+    }
+
+    pub(super) fn set_anonymize_references(&mut self, anonymize_references: bool) {
+        self.anonymize_references = anonymize_references;
+    }
+
+    /// The project's PPQ, which the model is asked to write ticks at.
+    pub(super) fn set_tick_resolution(&mut self, tick_resolution: TickResolution) {
+        self.tick_resolution = tick_resolution;
+    }
+
+    pub(super) fn set_validation(&mut self, validation: Option<ValidationStrictness>) {
+        self.validation = validation;
+    }
+
+    /// Moves references into the selected key before they are summarized for the prompt.
+    pub(super) fn set_transpose_references(&mut self, transpose_references: bool) {
+        self.transpose_references = transpose_references;
+    }
+
+    pub(super) fn transpose_references(&self) -> bool {
+        self.transpose_references
+    }
+
     pub(super) fn set_model(&mut self, model: ModelRef) {
         self.model = model;
+        self.sampling = self.sampling.clamped_to(self.sampling_ranges());
+    }
+
+    pub(super) fn model(&self) -> &ModelRef {
+        &self.model
+    }
+
+    pub(super) fn provider(&self) -> &str {
+        self.model.provider.as_str()
     }
 
     pub(super) fn set_bpm(&mut self, bpm: u16) {
@@ -109,6 +338,80 @@ impl PromptSubmissionModel {
     pub(super) fn complexity(&self) -> u8 {
         self.complexity
     }
+
+    pub(super) fn set_syncopation(&mut self, syncopation: u8) {
+        self.syncopation = clamp_param_level(syncopation);
+    }
+
+    pub(super) fn syncopation(&self) -> u8 {
+        self.syncopation
+    }
+
+    pub(super) fn locked_notes(&self) -> &[GeneratedNote] {
+        &self.locked_notes
+    }
+
+    /// Locks `note` or releases the lock already covering it; returns whether it is now locked.
+    pub(super) fn toggle_locked_note(&mut self, note: &GeneratedNote) -> bool {
+        let before = self.locked_notes.len();
+        self.locked_notes
+            .retain(|locked| !note.matches_locked_note(locked));
+        if self.locked_notes.len() != before {
+            return false;
+        }
+        self.locked_notes.push(note.clone());
+        true
+    }
+
+    pub(super) fn clear_locked_notes(&mut self) {
+        self.locked_notes.clear();
+    }
+
+    pub(super) fn sampling(&self) -> SamplingParams {
+        self.sampling
+    }
+
+    pub(super) fn sampling_ranges(&self) -> SamplingRanges {
+        sampling_ranges_for_provider(&self.model.provider)
+    }
+
+    pub(super) fn set_sampling(&mut self, sampling: SamplingParams) {
+        self.sampling = sampling.clamped_to(self.sampling_ranges());
+    }
+
+    /// Moves one sampling parameter by `steps` increments, clamped to the provider's range.
+    pub(super) fn step_sampling_param(&mut self, param: SamplingParam, steps: i32) {
+        let mut sampling = self.sampling;
+        match param {
+            SamplingParam::Temperature => {
+                sampling.temperature = round_to_step(
+                    sampling.temperature + TEMPERATURE_STEP * steps as f32,
+                    TEMPERATURE_STEP,
+                );
+            }
+            SamplingParam::TopP => {
+                sampling.top_p =
+                    round_to_step(sampling.top_p + TOP_P_STEP * steps as f32, TOP_P_STEP);
+            }
+            SamplingParam::MaxTokens => {
+                let max_tokens =
+                    i32::from(sampling.max_tokens) + i32::from(MAX_TOKENS_STEP) * steps;
+                sampling.max_tokens = max_tokens.clamp(1, i32::from(u16::MAX)) as u16;
+            }
+        }
+        self.set_sampling(sampling);
+    }
+
+    pub(super) fn reset_sampling_param(&mut self, param: SamplingParam) {
+        let defaults = SamplingParams::default();
+        let mut sampling = self.sampling;
+        match param {
+            SamplingParam::Temperature => sampling.temperature = defaults.temperature,
+            SamplingParam::TopP => sampling.top_p = defaults.top_p,
+            SamplingParam::MaxTokens => sampling.max_tokens = defaults.max_tokens,
+        }
+        self.set_sampling(sampling);
+    }
 }
 
 /// Builds a request after validating only prompt text.
@@ -133,12 +436,20 @@ pub(super) fn build_generation_request_with_prompt_validation(
             scale: DEFAULT_SCALE.to_string(),
             density: DEFAULT_DENSITY,
             complexity: DEFAULT_COMPLEXITY,
+            syncopation: DEFAULT_SYNCOPATION,
+            tick_resolution: TickResolution::DEFAULT,
             temperature: Some(DEFAULT_TEMPERATURE),
             top_p: Some(DEFAULT_TOP_P),
             max_tokens: Some(DEFAULT_MAX_TOKENS),
         },
         references,
         variation_count: DEFAULT_VARIATION_COUNT,
+        locked_notes: Vec::new(),
+        contract_version: GENERATION_CONTRACT_VERSION,
+        daw_context: None,
+        constraints: None,
+        validation: None,
+        anonymize_references: false,
     })
 }
 
@@ -153,6 +464,10 @@ fn clamp_param_level(level: u8) -> u8 {
     level.clamp(PARAM_LEVEL_MIN, PARAM_LEVEL_MAX)
 }
 
+fn round_to_step(value: f32, step: f32) -> f32 {
+    (value / step).round() * step
+}
+
 fn clamp_bpm(bpm: u16) -> u16 {
     bpm.clamp(BPM_MIN, BPM_MAX)
 }
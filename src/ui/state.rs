@@ -1,6 +1,20 @@
-use super::theme::ThemeColors;
-use sonant::app::LoadMidiError;
-use sonant::domain::{GenerationMode, MidiReferenceSummary, ReferenceSlot};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use super::theme::{DisplayPreference, SlotColors, ThemeColors, TrackColor};
+use super::utils::NumberFormat;
+use gpui::{Pixels, Point};
+use sonant::app::{
+    ApplyDestination, AutoSaveQuota, ChannelMapping, DEFAULT_EXPORT_NAME_TEMPLATE,
+    ExportNameTemplate, ExportNameTemplateError, InputTrackModelError, LoadMidiError, TrackProgram,
+    default_live_channel_mappings, default_track_programs, program_for_slot,
+    validate_default_channel_mappings,
+};
+use sonant::domain::{
+    GenerationMode, LlmError, MidiReferenceSummary, ReferenceSlot, TickResolution,
+    ValidationStrictness,
+};
 use sonant::infra::midi::MidiLoadError;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -12,6 +26,13 @@ pub(super) enum HelperGenerationStatus {
     Running {
         request_id: String,
     },
+    Retrying {
+        request_id: String,
+        next_attempt: u8,
+        max_attempts: u8,
+        retry_at: Instant,
+        reason: String,
+    },
     Succeeded {
         request_id: String,
         candidate_count: usize,
@@ -19,6 +40,12 @@ pub(super) enum HelperGenerationStatus {
     Failed {
         message: String,
     },
+    /// The provider was unreachable; the request waits in the deferred queue.
+    Deferred {
+        request_id: String,
+        backlog: usize,
+        reason: String,
+    },
     Cancelled {
         request_id: String,
     },
@@ -30,6 +57,19 @@ impl HelperGenerationStatus {
             Self::Idle => "Idle".to_string(),
             Self::Submitting { request_id } => format!("Submitting {request_id}..."),
             Self::Running { request_id } => format!("Running {request_id}..."),
+            Self::Retrying {
+                request_id,
+                next_attempt,
+                max_attempts,
+                retry_at,
+                reason,
+            } => {
+                let remaining = retry_at.saturating_duration_since(Instant::now());
+                let seconds = remaining.as_millis().div_ceil(1000);
+                format!(
+                    "Retrying {request_id} (attempt {next_attempt}/{max_attempts}) in {seconds}s: {reason}"
+                )
+            }
             Self::Succeeded {
                 request_id,
                 candidate_count,
@@ -37,6 +77,13 @@ impl HelperGenerationStatus {
                 format!("Succeeded {request_id} ({candidate_count} candidate(s))")
             }
             Self::Failed { message } => format!("Failed: {message}"),
+            Self::Deferred {
+                request_id,
+                backlog,
+                reason,
+            } => format!(
+                "Deferred {request_id} ({backlog} queued, sent when the provider is reachable): {reason}"
+            ),
             Self::Cancelled { request_id } => format!("Cancelled {request_id}"),
         }
     }
@@ -45,6 +92,7 @@ impl HelperGenerationStatus {
         match self {
             Self::Idle => colors.accent_foreground,
             Self::Submitting { .. } | Self::Running { .. } => colors.progress_foreground,
+            Self::Retrying { .. } | Self::Deferred { .. } => colors.warning_foreground,
             Self::Succeeded { .. } => colors.success_foreground,
             Self::Failed { .. } => colors.error_foreground,
             Self::Cancelled { .. } => colors.warning_foreground,
@@ -52,7 +100,10 @@ impl HelperGenerationStatus {
     }
 
     pub(super) fn is_submitting_or_running(&self) -> bool {
-        matches!(self, Self::Submitting { .. } | Self::Running { .. })
+        matches!(
+            self,
+            Self::Submitting { .. } | Self::Running { .. } | Self::Retrying { .. }
+        )
     }
 }
 
@@ -98,9 +149,61 @@ impl MidiSlotErrorState {
     }
 }
 
+/// Follow-up offered next to a failed generation, picked from the error kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum GenerationFailureAction {
+    RetryAt(Instant),
+    OpenSettings,
+    ChooseModel,
+    EditPrompt,
+}
+
+impl GenerationFailureAction {
+    pub(super) fn for_error(error: &LlmError, now: Instant) -> Option<Self> {
+        match error {
+            LlmError::RateLimited {
+                retry_after: Some(retry_after),
+            } => Some(Self::RetryAt(now + *retry_after)),
+            LlmError::Auth | LlmError::AuthExpired | LlmError::QuotaExceeded => {
+                Some(Self::OpenSettings)
+            }
+            LlmError::ModelNotFound { .. } => Some(Self::ChooseModel),
+            LlmError::ContentFiltered { .. } => Some(Self::EditPrompt),
+            _ => None,
+        }
+    }
+
+    /// Whole seconds left before a rate-limited retry is allowed, rounded up.
+    pub(super) fn retry_countdown_secs(self, now: Instant) -> Option<u64> {
+        let Self::RetryAt(retry_at) = self else {
+            return None;
+        };
+        let remaining = retry_at.saturating_duration_since(now);
+        (!remaining.is_zero()).then(|| remaining.as_millis().div_ceil(1000) as u64)
+    }
+
+    pub(super) fn retry_label(self, now: Instant) -> String {
+        match self.retry_countdown_secs(now) {
+            Some(seconds) => format!("Retry in {seconds}s"),
+            None => "Retry".to_string(),
+        }
+    }
+
+    pub(super) fn hint(self) -> &'static str {
+        match self {
+            Self::RetryAt(_) => "The provider asked to wait before the next request.",
+            Self::OpenSettings => "Update the API key or account settings for this provider.",
+            Self::ChooseModel => "Pick another model from the model menu.",
+            Self::EditPrompt => "Reword the prompt and generate again.",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(super) enum ProviderStatus {
     Connected,
+    /// The last request failed in a way that may clear up on its own (rate limit, outage).
+    Degraded,
     InvalidKey,
     NotConfigured,
 }
@@ -109,6 +212,7 @@ impl ProviderStatus {
     pub(super) fn label(self) -> &'static str {
         match self {
             Self::Connected => "API CONNECTED",
+            Self::Degraded => "API DEGRADED",
             Self::InvalidKey => "API INVALID KEY",
             Self::NotConfigured => "API NOT CONFIGURED",
         }
@@ -117,8 +221,8 @@ impl ProviderStatus {
     pub(super) fn color(self, colors: ThemeColors) -> gpui::Hsla {
         match self {
             Self::Connected => colors.success_foreground,
+            Self::Degraded | Self::NotConfigured => colors.warning_foreground,
             Self::InvalidKey => colors.error_foreground,
-            Self::NotConfigured => colors.warning_foreground,
         }
     }
 }
@@ -144,6 +248,51 @@ impl SettingsTab {
 pub(super) enum UiScreen {
     Main,
     Settings,
+    Onboarding(OnboardingStep),
+}
+
+/// Pages of the first-run wizard, in the order they are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum OnboardingStep {
+    ApiKey,
+    Model,
+    TestConnection,
+    FirstReference,
+}
+
+impl OnboardingStep {
+    pub(super) const ALL: [Self; 4] = [
+        Self::ApiKey,
+        Self::Model,
+        Self::TestConnection,
+        Self::FirstReference,
+    ];
+
+    pub(super) fn label(self) -> &'static str {
+        match self {
+            Self::ApiKey => "API Key",
+            Self::Model => "Model",
+            Self::TestConnection => "Test Connection",
+            Self::FirstReference => "First Reference",
+        }
+    }
+
+    pub(super) fn position(self) -> usize {
+        Self::ALL
+            .iter()
+            .position(|step| *step == self)
+            .unwrap_or_default()
+    }
+
+    fn next(self) -> Option<Self> {
+        Self::ALL.get(self.position() + 1).copied()
+    }
+
+    fn previous(self) -> Option<Self> {
+        self.position()
+            .checked_sub(1)
+            .and_then(|index| Self::ALL.get(index).copied())
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -151,8 +300,22 @@ pub(super) enum SettingsField {
     AnthropicApiKey,
     OpenAiApiKey,
     CustomBaseUrl,
+    RemoteServerUrl,
+    RemoteServerToken,
     DefaultModel,
     ContextWindow,
+    TickResolution,
+    AutoSaveFolder,
+    AutoSaveQuota,
+    ExportNameTemplate,
+    ApplyDestination,
+    DefaultChannelMappings,
+    ExportPrograms,
+    AnonymizeReferences,
+    CandidateValidation,
+    HighContrast,
+    ReducedMotion,
+    SlotColors,
 }
 
 impl SettingsField {
@@ -161,8 +324,22 @@ impl SettingsField {
             Self::AnthropicApiKey => "Anthropic API Key",
             Self::OpenAiApiKey => "OpenAI API Key",
             Self::CustomBaseUrl => "Custom Base URL",
+            Self::RemoteServerUrl => "Remote Server URL",
+            Self::RemoteServerToken => "Remote Server Token",
             Self::DefaultModel => "Default Model",
             Self::ContextWindow => "Context Window",
+            Self::TickResolution => "Tick Resolution (PPQ)",
+            Self::AutoSaveFolder => "Auto-Save Folder",
+            Self::AutoSaveQuota => "Auto-Save Folder Limit",
+            Self::ExportNameTemplate => "File Name Template",
+            Self::ApplyDestination => "Apply Destination",
+            Self::DefaultChannelMappings => "Default Channel Mappings",
+            Self::ExportPrograms => "Export Programs",
+            Self::AnonymizeReferences => "Anonymize Reference Files",
+            Self::CandidateValidation => "Candidate Validation",
+            Self::HighContrast => "High Contrast",
+            Self::ReducedMotion => "Reduced Motion",
+            Self::SlotColors => "Slot Colors",
         }
     }
 }
@@ -172,8 +349,23 @@ pub(super) struct SettingsDraftState {
     pub(super) anthropic_api_key: String,
     pub(super) openai_api_key: String,
     pub(super) custom_base_url: String,
+    pub(super) remote_server_url: String,
+    pub(super) remote_server_token: String,
     pub(super) default_model: String,
     pub(super) context_window: String,
+    pub(super) tick_resolution: String,
+    pub(super) auto_save_folder: String,
+    pub(super) auto_save_quota: AutoSaveQuota,
+    pub(super) export_name_template: String,
+    pub(super) apply_destination: ApplyDestination,
+    pub(super) default_channel_mappings: Vec<ChannelMapping>,
+    pub(super) export_programs: Vec<TrackProgram>,
+    pub(super) anonymize_references: bool,
+    /// `None` leaves candidates unchecked.
+    pub(super) candidate_validation: Option<ValidationStrictness>,
+    pub(super) high_contrast: DisplayPreference,
+    pub(super) reduced_motion: DisplayPreference,
+    pub(super) slot_colors: SlotColors,
 }
 
 impl SettingsDraftState {
@@ -191,15 +383,229 @@ impl Default for SettingsDraftState {
             anthropic_api_key: String::new(),
             openai_api_key: String::new(),
             custom_base_url: String::new(),
+            remote_server_url: String::new(),
+            remote_server_token: String::new(),
             default_model: "claude-3-5-sonnet".to_string(),
             context_window: "8192".to_string(),
+            tick_resolution: TickResolution::DEFAULT.ticks_per_beat().to_string(),
+            auto_save_folder: String::new(),
+            auto_save_quota: AutoSaveQuota::default(),
+            export_name_template: DEFAULT_EXPORT_NAME_TEMPLATE.to_string(),
+            apply_destination: ApplyDestination::default(),
+            default_channel_mappings: default_live_channel_mappings(),
+            export_programs: default_track_programs(),
+            anonymize_references: false,
+            candidate_validation: None,
+            high_contrast: DisplayPreference::Auto,
+            reduced_motion: DisplayPreference::Auto,
+            slot_colors: SlotColors::default(),
+        }
+    }
+}
+
+// Older request outcomes no longer say anything about the provider.
+const PROVIDER_HEALTH_MAX_AGE: Duration = Duration::from_secs(10 * 60);
+
+/// Outcome of the most recent request sent to a provider.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct ProviderHealth {
+    pub(super) provider: String,
+    pub(super) status: ProviderStatus,
+    pub(super) checked_at: Instant,
+    pub(super) latency_ms: Option<u64>,
+    pub(super) last_error: Option<String>,
+}
+
+impl ProviderHealth {
+    pub(super) fn succeeded(provider: &str, latency_ms: Option<u64>, now: Instant) -> Self {
+        Self {
+            provider: provider.to_string(),
+            status: ProviderStatus::Connected,
+            checked_at: now,
+            latency_ms,
+            last_error: None,
+        }
+    }
+
+    pub(super) fn failed(provider: &str, error: &LlmError, now: Instant) -> Self {
+        let status = match error {
+            LlmError::Auth | LlmError::AuthExpired => ProviderStatus::InvalidKey,
+            LlmError::RateLimited { .. }
+            | LlmError::QuotaExceeded
+            | LlmError::Timeout
+            | LlmError::Transport { .. } => ProviderStatus::Degraded,
+            // The provider answered; the failure was about this request.
+            _ => ProviderStatus::Connected,
+        };
+        Self {
+            provider: provider.to_string(),
+            status,
+            checked_at: now,
+            latency_ms: None,
+            last_error: Some(error.to_string()),
+        }
+    }
+
+    /// Hover text for the header badge.
+    pub(super) fn details(&self, now: Instant) -> String {
+        let mut details = format!(
+            "{}: last request {}",
+            self.provider,
+            format_age(now.saturating_duration_since(self.checked_at))
+        );
+        if let Some(latency_ms) = self.latency_ms {
+            details.push_str(&format!(", {latency_ms} ms"));
+        }
+        if let Some(error) = &self.last_error {
+            details.push_str(&format!("\nLast error: {error}"));
+        }
+        details
+    }
+}
+
+fn format_age(age: Duration) -> String {
+    match age.as_secs() {
+        0..5 => "just now".to_string(),
+        seconds @ 5..60 => format!("{seconds}s ago"),
+        seconds @ 60..3600 => format!("{} min ago", seconds / 60),
+        seconds => format!("{} h ago", seconds / 3600),
+    }
+}
+
+// A longer pause than this (slower than 20 BPM) starts a new run of taps.
+const TAP_TEMPO_RESET_AFTER: Duration = Duration::from_secs(3);
+// Enough taps to even out a shaky hand while still following a change of tempo.
+const TAP_TEMPO_MAX_TAPS: usize = 8;
+
+/// Tempo read from the spacing of the last few taps, for sessions without a host tempo.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(super) struct TapTempo {
+    taps: VecDeque<Instant>,
+}
+
+impl TapTempo {
+    /// Records a tap at `now`. From the second tap of a run on, returns the average tempo of
+    /// the run in whole BPM.
+    pub(super) fn tap(&mut self, now: Instant) -> Option<u16> {
+        if self
+            .taps
+            .back()
+            .is_some_and(|last| now.saturating_duration_since(*last) > TAP_TEMPO_RESET_AFTER)
+        {
+            self.taps.clear();
+        }
+        self.taps.push_back(now);
+        if self.taps.len() > TAP_TEMPO_MAX_TAPS {
+            self.taps.pop_front();
+        }
+
+        let first = self.taps.front()?;
+        let intervals = u32::try_from(self.taps.len() - 1).ok()?;
+        let average = now
+            .saturating_duration_since(*first)
+            .checked_div(intervals)?;
+        if average.is_zero() {
+            return None;
+        }
+        let bpm = (60.0 / average.as_secs_f64()).round();
+        (bpm <= f64::from(u16::MAX)).then_some(bpm as u16)
+    }
+
+    /// Taps recorded in the current run.
+    pub(super) fn tap_count(&self) -> usize {
+        self.taps.len()
+    }
+}
+
+/// A popover menu of the Input Tracks section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum OverlayMenu {
+    Presets,
+    /// Adds a track after the row, or at the end from the section header.
+    AddTrack(Option<usize>),
+    Channel(usize),
+    SlotType(usize),
+    RecentFiles(usize),
+}
+
+impl OverlayMenu {
+    /// The row the menu opens under; `None` for the section header's menus.
+    pub(super) fn row(self) -> Option<usize> {
+        match self {
+            Self::Presets | Self::AddTrack(None) => None,
+            Self::AddTrack(Some(row))
+            | Self::Channel(row)
+            | Self::SlotType(row)
+            | Self::RecentFiles(row) => Some(row),
         }
     }
+
+    fn with_row(self, row: usize) -> Self {
+        match self {
+            Self::Presets => Self::Presets,
+            Self::AddTrack(_) => Self::AddTrack(Some(row)),
+            Self::Channel(_) => Self::Channel(row),
+            Self::SlotType(_) => Self::SlotType(row),
+            Self::RecentFiles(_) => Self::RecentFiles(row),
+        }
+    }
+}
+
+/// The Input Tracks popover that is open, if any. Opening one closes the others, and a press
+/// outside the open one or Esc closes it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(super) struct OverlayMenus {
+    open: Option<OverlayMenu>,
+    /// The menu the last outside press closed, and where that press landed.
+    dismissed: Option<(OverlayMenu, Point<Pixels>)>,
+}
+
+impl OverlayMenus {
+    pub(super) fn open_menu(&self) -> Option<OverlayMenu> {
+        self.open
+    }
+
+    pub(super) fn is_open(&self, menu: OverlayMenu) -> bool {
+        self.open == Some(menu)
+    }
+
+    /// Opens `menu` for a press on its trigger at `position`, or closes it when it is open.
+    /// The outside-press handler runs first, so a press that has just dismissed `menu` from
+    /// its own trigger leaves it closed instead of reopening it.
+    pub(super) fn toggle(&mut self, menu: OverlayMenu, position: Point<Pixels>) {
+        let dismissed_by_this_press = self.dismissed.take() == Some((menu, position));
+        self.open = (self.open != Some(menu) && !dismissed_by_this_press).then_some(menu);
+    }
+
+    /// Closes the open menu for a press at `position` outside it. Returns whether one was open.
+    pub(super) fn dismiss(&mut self, position: Point<Pixels>) -> bool {
+        let Some(menu) = self.open.take() else {
+            return false;
+        };
+        self.dismissed = Some((menu, position));
+        true
+    }
+
+    /// Closes the open menu after Esc or a pick from it. Returns whether one was open.
+    pub(super) fn close(&mut self) -> bool {
+        self.dismissed = None;
+        self.open.take().is_some()
+    }
+
+    /// Keeps a row's menu under its row as rows move; the menu closes when `remap` drops the
+    /// row.
+    pub(super) fn remap_rows(&mut self, remap: impl Fn(usize) -> Option<usize>) {
+        self.open = self.open.and_then(|menu| match menu.row() {
+            Some(row) => remap(row).map(|row| menu.with_row(row)),
+            None => Some(menu),
+        });
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(super) struct SettingsUiState {
     pub(super) provider_status: ProviderStatus,
+    pub(super) provider_health: Option<ProviderHealth>,
     pub(super) settings_tab: SettingsTab,
     pub(super) settings_dirty: bool,
     pub(super) screen: UiScreen,
@@ -212,6 +618,7 @@ impl SettingsUiState {
         let provider_status = provider_status_from_draft(&saved);
         Self {
             provider_status,
+            provider_health: None,
             settings_tab: SettingsTab::ApiKeys,
             settings_dirty: false,
             screen: UiScreen::Main,
@@ -224,6 +631,21 @@ impl SettingsUiState {
         self.screen = UiScreen::Settings;
     }
 
+    /// Key problems found locally win; otherwise a recent request outcome refines the status.
+    pub(super) fn effective_provider_status(&self, now: Instant) -> ProviderStatus {
+        if self.provider_status != ProviderStatus::Connected {
+            return self.provider_status;
+        }
+        self.recent_provider_health(now)
+            .map_or(self.provider_status, |health| health.status)
+    }
+
+    pub(super) fn recent_provider_health(&self, now: Instant) -> Option<&ProviderHealth> {
+        self.provider_health.as_ref().filter(|health| {
+            now.saturating_duration_since(health.checked_at) <= PROVIDER_HEALTH_MAX_AGE
+        })
+    }
+
     pub(super) fn close_settings(&mut self) {
         self.screen = UiScreen::Main;
     }
@@ -232,6 +654,59 @@ impl SettingsUiState {
         self.screen == UiScreen::Settings
     }
 
+    pub(super) fn start_onboarding(&mut self) {
+        self.screen = UiScreen::Onboarding(OnboardingStep::ApiKey);
+    }
+
+    pub(super) fn onboarding_step(&self) -> Option<OnboardingStep> {
+        match self.screen {
+            UiScreen::Onboarding(step) => Some(step),
+            UiScreen::Main | UiScreen::Settings => None,
+        }
+    }
+
+    /// Why the current wizard page cannot be left yet. Keys set in the environment count, since
+    /// providers are built from them at startup.
+    pub(super) fn onboarding_blocker(&self, env_key_configured: bool) -> Option<&'static str> {
+        match (self.onboarding_step()?, self.draft_provider_status()) {
+            (OnboardingStep::ApiKey, ProviderStatus::InvalidKey) => {
+                Some("API keys must not contain spaces.")
+            }
+            (OnboardingStep::ApiKey, ProviderStatus::NotConfigured) if !env_key_configured => {
+                Some("Enter an API key or a remote server token to continue.")
+            }
+            _ => None,
+        }
+    }
+
+    /// Moves to the next wizard page, saving the draft as the model page is left so the
+    /// connection test and first generation use it. Leaving the last page closes the wizard.
+    pub(super) fn advance_onboarding(&mut self) -> bool {
+        let Some(step) = self.onboarding_step() else {
+            return false;
+        };
+        if step == OnboardingStep::Model {
+            self.commit_draft();
+        }
+        self.screen = step.next().map_or(UiScreen::Main, UiScreen::Onboarding);
+        true
+    }
+
+    pub(super) fn back_onboarding(&mut self) -> bool {
+        match self.onboarding_step().and_then(OnboardingStep::previous) {
+            Some(previous) => {
+                self.screen = UiScreen::Onboarding(previous);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Leaves the wizard, dropping anything typed since the draft was last saved.
+    pub(super) fn skip_onboarding(&mut self) {
+        self.discard_and_close();
+    }
+
     pub(super) fn select_settings_tab(&mut self, tab: SettingsTab) {
         self.settings_tab = tab;
     }
@@ -244,6 +719,47 @@ impl SettingsUiState {
         &self.draft
     }
 
+    /// Saved project resolution for MIDI export; unparseable input falls back to the default.
+    pub(super) fn tick_resolution(&self) -> TickResolution {
+        self.saved
+            .tick_resolution
+            .trim()
+            .parse::<u16>()
+            .ok()
+            .and_then(|ticks_per_beat| TickResolution::new(ticks_per_beat).ok())
+            .unwrap_or_default()
+    }
+
+    /// General MIDI program written at the start of exported tracks for `slot`.
+    pub(super) fn export_program(&self, slot: ReferenceSlot) -> Option<u8> {
+        program_for_slot(&self.saved.export_programs, slot)
+    }
+
+    /// Saved folder that receives every successful generation; `None` when auto-save is off.
+    pub(super) fn auto_save_folder(&self) -> Option<PathBuf> {
+        let folder = self.saved.auto_save_folder.trim();
+        (!folder.is_empty()).then(|| PathBuf::from(folder))
+    }
+
+    pub(super) fn auto_save_quota(&self) -> AutoSaveQuota {
+        self.saved.auto_save_quota
+    }
+
+    /// Saved file name template for every MIDI export; an invalid one falls back to the default.
+    pub(super) fn export_name_template(&self) -> ExportNameTemplate {
+        ExportNameTemplate::parse(&self.saved.export_name_template).unwrap_or_default()
+    }
+
+    /// Why the draft template would be replaced by the default on save.
+    pub(super) fn draft_export_name_template_error(&self) -> Option<ExportNameTemplateError> {
+        ExportNameTemplate::parse(&self.draft.export_name_template).err()
+    }
+
+    /// Where Apply sends the selected candidate; file writes go to the auto-save folder.
+    pub(super) fn apply_destination(&self) -> ApplyDestination {
+        self.saved.apply_destination
+    }
+
     pub(super) fn update_draft(&mut self, draft: SettingsDraftState) {
         self.draft = draft;
         self.settings_dirty = self.saved != self.draft;
@@ -259,8 +775,22 @@ impl SettingsUiState {
             SettingsField::AnthropicApiKey => &mut self.draft.anthropic_api_key,
             SettingsField::OpenAiApiKey => &mut self.draft.openai_api_key,
             SettingsField::CustomBaseUrl => &mut self.draft.custom_base_url,
+            SettingsField::RemoteServerUrl => &mut self.draft.remote_server_url,
+            SettingsField::RemoteServerToken => &mut self.draft.remote_server_token,
             SettingsField::DefaultModel => &mut self.draft.default_model,
             SettingsField::ContextWindow => &mut self.draft.context_window,
+            SettingsField::TickResolution => &mut self.draft.tick_resolution,
+            SettingsField::AutoSaveFolder => &mut self.draft.auto_save_folder,
+            SettingsField::ExportNameTemplate => &mut self.draft.export_name_template,
+            SettingsField::AutoSaveQuota
+            | SettingsField::ApplyDestination
+            | SettingsField::DefaultChannelMappings
+            | SettingsField::ExportPrograms
+            | SettingsField::AnonymizeReferences
+            | SettingsField::CandidateValidation
+            | SettingsField::HighContrast
+            | SettingsField::ReducedMotion
+            | SettingsField::SlotColors => return false,
         };
 
         if *target == value {
@@ -272,17 +802,153 @@ impl SettingsUiState {
         true
     }
 
+    pub(super) fn update_draft_channel_mapping(
+        &mut self,
+        mapping: ChannelMapping,
+    ) -> Result<bool, InputTrackModelError> {
+        let mut next = self.draft.default_channel_mappings.clone();
+        match next
+            .iter_mut()
+            .find(|existing| existing.slot == mapping.slot)
+        {
+            Some(existing) if *existing == mapping => return Ok(false),
+            Some(existing) => *existing = mapping,
+            None => next.push(mapping),
+        }
+        validate_default_channel_mappings(&next)?;
+
+        self.draft.default_channel_mappings = next;
+        self.settings_dirty = self.saved != self.draft;
+        Ok(true)
+    }
+
+    /// `None` removes the slot's program so its exports carry no program change.
+    pub(super) fn update_draft_export_program(
+        &mut self,
+        slot: ReferenceSlot,
+        program: Option<u8>,
+    ) -> bool {
+        if program_for_slot(&self.draft.export_programs, slot) == program {
+            return false;
+        }
+        self.draft
+            .export_programs
+            .retain(|assignment| assignment.slot != slot);
+        if let Some(program) = program {
+            self.draft
+                .export_programs
+                .push(TrackProgram { slot, program });
+        }
+        self.settings_dirty = self.saved != self.draft;
+        true
+    }
+
+    pub(super) fn update_draft_anonymize_references(&mut self, enabled: bool) -> bool {
+        if self.draft.anonymize_references == enabled {
+            return false;
+        }
+        self.draft.anonymize_references = enabled;
+        self.settings_dirty = self.saved != self.draft;
+        true
+    }
+
+    pub(super) fn update_draft_candidate_validation(
+        &mut self,
+        strictness: Option<ValidationStrictness>,
+    ) -> bool {
+        if self.draft.candidate_validation == strictness {
+            return false;
+        }
+        self.draft.candidate_validation = strictness;
+        self.settings_dirty = self.saved != self.draft;
+        true
+    }
+
+    pub(super) fn update_draft_auto_save_quota(&mut self, quota: AutoSaveQuota) -> bool {
+        if self.draft.auto_save_quota == quota {
+            return false;
+        }
+        self.draft.auto_save_quota = quota;
+        self.settings_dirty = self.saved != self.draft;
+        true
+    }
+
+    pub(super) fn update_draft_apply_destination(&mut self, destination: ApplyDestination) -> bool {
+        if self.draft.apply_destination == destination {
+            return false;
+        }
+        self.draft.apply_destination = destination;
+        self.settings_dirty = self.saved != self.draft;
+        true
+    }
+
+    pub(super) fn update_draft_high_contrast(&mut self, preference: DisplayPreference) -> bool {
+        if self.draft.high_contrast == preference {
+            return false;
+        }
+        self.draft.high_contrast = preference;
+        self.settings_dirty = self.saved != self.draft;
+        true
+    }
+
+    pub(super) fn update_draft_reduced_motion(&mut self, preference: DisplayPreference) -> bool {
+        if self.draft.reduced_motion == preference {
+            return false;
+        }
+        self.draft.reduced_motion = preference;
+        self.settings_dirty = self.saved != self.draft;
+        true
+    }
+
+    pub(super) fn update_draft_slot_color(
+        &mut self,
+        slot: ReferenceSlot,
+        color: TrackColor,
+    ) -> bool {
+        if self.draft.slot_colors.get(slot) == color {
+            return false;
+        }
+        self.draft.slot_colors.set(slot, color);
+        self.settings_dirty = self.saved != self.draft;
+        true
+    }
+
+    pub(super) fn reset_draft_channel_mappings(&mut self) -> bool {
+        let defaults = default_live_channel_mappings();
+        if self.draft.default_channel_mappings == defaults {
+            return false;
+        }
+
+        self.draft.default_channel_mappings = defaults;
+        self.settings_dirty = self.saved != self.draft;
+        true
+    }
+
     pub(super) fn draft_provider_status(&self) -> ProviderStatus {
         provider_status_from_draft(&self.draft)
     }
 
     pub(super) fn dirty_fields(&self) -> Vec<SettingsField> {
-        const FIELDS: [SettingsField; 5] = [
+        const FIELDS: [SettingsField; 19] = [
             SettingsField::AnthropicApiKey,
             SettingsField::OpenAiApiKey,
             SettingsField::CustomBaseUrl,
+            SettingsField::RemoteServerUrl,
+            SettingsField::RemoteServerToken,
             SettingsField::DefaultModel,
             SettingsField::ContextWindow,
+            SettingsField::TickResolution,
+            SettingsField::AutoSaveFolder,
+            SettingsField::AutoSaveQuota,
+            SettingsField::ExportNameTemplate,
+            SettingsField::ApplyDestination,
+            SettingsField::DefaultChannelMappings,
+            SettingsField::ExportPrograms,
+            SettingsField::AnonymizeReferences,
+            SettingsField::CandidateValidation,
+            SettingsField::HighContrast,
+            SettingsField::ReducedMotion,
+            SettingsField::SlotColors,
         ];
         FIELDS
             .into_iter()
@@ -299,17 +965,82 @@ impl SettingsUiState {
             SettingsField::CustomBaseUrl => {
                 self.saved.custom_base_url != self.draft.custom_base_url
             }
+            SettingsField::RemoteServerUrl => {
+                self.saved.remote_server_url != self.draft.remote_server_url
+            }
+            SettingsField::RemoteServerToken => {
+                self.saved.remote_server_token != self.draft.remote_server_token
+            }
             SettingsField::DefaultModel => self.saved.default_model != self.draft.default_model,
             SettingsField::ContextWindow => self.saved.context_window != self.draft.context_window,
+            SettingsField::TickResolution => {
+                self.saved.tick_resolution != self.draft.tick_resolution
+            }
+            SettingsField::AutoSaveFolder => {
+                self.saved.auto_save_folder != self.draft.auto_save_folder
+            }
+            SettingsField::AutoSaveQuota => {
+                self.saved.auto_save_quota != self.draft.auto_save_quota
+            }
+            SettingsField::ExportNameTemplate => {
+                self.saved.export_name_template != self.draft.export_name_template
+            }
+            SettingsField::ApplyDestination => {
+                self.saved.apply_destination != self.draft.apply_destination
+            }
+            SettingsField::DefaultChannelMappings => {
+                self.saved.default_channel_mappings != self.draft.default_channel_mappings
+            }
+            SettingsField::ExportPrograms => {
+                self.saved.export_programs != self.draft.export_programs
+            }
+            SettingsField::AnonymizeReferences => {
+                self.saved.anonymize_references != self.draft.anonymize_references
+            }
+            SettingsField::CandidateValidation => {
+                self.saved.candidate_validation != self.draft.candidate_validation
+            }
+            SettingsField::HighContrast => self.saved.high_contrast != self.draft.high_contrast,
+            SettingsField::ReducedMotion => self.saved.reduced_motion != self.draft.reduced_motion,
+            SettingsField::SlotColors => self.saved.slot_colors != self.draft.slot_colors,
+        }
+    }
+
+    /// Rewrites locale-formatted numeric fields ("16.384", "16 384") as plain digits so they
+    /// parse once saved; text that is not a whole number is left for the user to correct.
+    pub(super) fn normalize_numeric_draft_fields(&mut self, format: NumberFormat) {
+        let fields = [
+            (
+                SettingsField::ContextWindow,
+                self.draft.context_window.clone(),
+            ),
+            (
+                SettingsField::TickResolution,
+                self.draft.tick_resolution.clone(),
+            ),
+        ];
+        for (field, raw) in fields {
+            if let Some(value) = format.parse_integer(&raw) {
+                self.update_draft_field(field, value.to_string());
+            }
         }
     }
 
     pub(super) fn save_and_close(&mut self) -> bool {
+        let changed = self.commit_draft();
+        self.close_settings();
+        changed
+    }
+
+    fn commit_draft(&mut self) -> bool {
         let changed = self.settings_dirty;
         self.saved = self.draft.clone();
         self.settings_dirty = false;
         self.provider_status = provider_status_from_draft(&self.saved);
-        self.close_settings();
+        if changed {
+            // New keys or endpoints make the previous outcome meaningless.
+            self.provider_health = None;
+        }
         changed
     }
 
@@ -384,7 +1115,13 @@ pub(super) fn can_retry_midi_load_error(error: &LoadMidiError) -> bool {
 fn provider_status_from_draft(draft: &SettingsDraftState) -> ProviderStatus {
     let anthropic_key = draft.anthropic_api_key.trim();
     let openai_key = draft.openai_api_key.trim();
-    let configured_keys = [anthropic_key, openai_key]
+    // The server token only counts once there is a server URL to send it to.
+    let remote_server_token = if draft.remote_server_url.trim().is_empty() {
+        ""
+    } else {
+        draft.remote_server_token.trim()
+    };
+    let configured_keys = [anthropic_key, openai_key, remote_server_token]
         .into_iter()
         .filter(|key| !key.is_empty())
         .collect::<Vec<_>>();
@@ -405,9 +1142,82 @@ fn provider_status_from_draft(draft: &SettingsDraftState) -> ProviderStatus {
 
 #[cfg(test)]
 mod tests {
+    use std::time::{Duration, Instant};
+
     use super::{
-        ProviderStatus, SettingsDraftState, SettingsField, SettingsTab, SettingsUiState, UiScreen,
+        GenerationFailureAction, HelperGenerationStatus, NumberFormat, OnboardingStep,
+        ProviderHealth, ProviderStatus, SettingsDraftState, SettingsField, SettingsTab,
+        SettingsUiState, UiScreen,
     };
+    use sonant::app::{
+        ChannelMapping, ExportNameTemplate, InputTrackModelError, default_live_channel_mappings,
+    };
+    use sonant::domain::{LlmError, ReferenceSlot, TickResolution};
+
+    #[test]
+    fn retrying_status_shows_attempt_and_countdown() {
+        let status = HelperGenerationStatus::Retrying {
+            request_id: "req-1".to_string(),
+            next_attempt: 2,
+            max_attempts: 3,
+            retry_at: Instant::now() + Duration::from_secs(30),
+            reason: "Rate limited".to_string(),
+        };
+
+        assert_eq!(
+            status.label(),
+            "Retrying req-1 (attempt 2/3) in 30s: Rate limited"
+        );
+        assert!(status.is_submitting_or_running());
+    }
+
+    #[test]
+    fn failure_action_counts_down_rate_limit_retries() {
+        let now = Instant::now();
+        let action = GenerationFailureAction::for_error(
+            &LlmError::rate_limited(Some(Duration::from_millis(2_500))),
+            now,
+        )
+        .expect("rate limit with retry-after should offer a retry");
+
+        assert_eq!(action.retry_countdown_secs(now), Some(3));
+        assert_eq!(action.retry_label(now), "Retry in 3s");
+        let later = now + Duration::from_secs(3);
+        assert_eq!(action.retry_countdown_secs(later), None);
+        assert_eq!(action.retry_label(later), "Retry");
+    }
+
+    #[test]
+    fn failure_action_matches_error_kind() {
+        let now = Instant::now();
+
+        assert_eq!(
+            GenerationFailureAction::for_error(&LlmError::QuotaExceeded, now),
+            Some(GenerationFailureAction::OpenSettings)
+        );
+        assert_eq!(
+            GenerationFailureAction::for_error(
+                &LlmError::ModelNotFound {
+                    model: "gone".to_string()
+                },
+                now
+            ),
+            Some(GenerationFailureAction::ChooseModel)
+        );
+        assert_eq!(
+            GenerationFailureAction::for_error(
+                &LlmError::ContentFiltered {
+                    message: "flagged".to_string()
+                },
+                now
+            ),
+            Some(GenerationFailureAction::EditPrompt)
+        );
+        assert_eq!(
+            GenerationFailureAction::for_error(&LlmError::Timeout, now),
+            None
+        );
+    }
 
     #[test]
     fn open_and_close_settings_updates_screen_state() {
@@ -468,6 +1278,124 @@ mod tests {
         assert!(!unchanged);
     }
 
+    #[test]
+    fn saved_tick_resolution_is_parsed_with_default_fallback() {
+        let mut state = SettingsUiState::new(SettingsDraftState::default());
+        assert_eq!(state.tick_resolution(), TickResolution::DEFAULT);
+
+        state.open_settings();
+        state.update_draft_field(SettingsField::TickResolution, " 960 ");
+        assert_eq!(state.tick_resolution(), TickResolution::DEFAULT);
+        state.save_and_close();
+        assert_eq!(
+            state.tick_resolution(),
+            TickResolution::new(960).expect("960 PPQ should be valid")
+        );
+
+        state.open_settings();
+        state.update_draft_field(SettingsField::TickResolution, "0");
+        state.save_and_close();
+        assert_eq!(state.tick_resolution(), TickResolution::DEFAULT);
+    }
+
+    #[test]
+    fn locale_formatted_numeric_settings_are_saved_as_plain_digits() {
+        let mut state = SettingsUiState::new(SettingsDraftState::default());
+        state.open_settings();
+        state.update_draft_field(SettingsField::ContextWindow, "16.384");
+        state.update_draft_field(SettingsField::TickResolution, "1 920");
+        state.normalize_numeric_draft_fields(NumberFormat::DECIMAL_COMMA);
+        state.save_and_close();
+
+        assert_eq!(state.saved().context_window, "16384");
+        assert_eq!(
+            state.tick_resolution(),
+            TickResolution::new(1920).expect("1920 PPQ should be valid")
+        );
+
+        state.open_settings();
+        state.update_draft_field(SettingsField::ContextWindow, "lots");
+        state.normalize_numeric_draft_fields(NumberFormat::DECIMAL_POINT);
+        assert_eq!(state.draft().context_window, "lots");
+    }
+
+    #[test]
+    fn blank_auto_save_folder_disables_auto_save() {
+        let mut state = SettingsUiState::new(SettingsDraftState::default());
+        assert_eq!(state.auto_save_folder(), None);
+
+        state.open_settings();
+        state.update_draft_field(SettingsField::AutoSaveFolder, " /tmp/sonant ");
+        assert!(state.is_field_dirty(SettingsField::AutoSaveFolder));
+        state.save_and_close();
+        assert_eq!(
+            state.auto_save_folder(),
+            Some(std::path::PathBuf::from("/tmp/sonant"))
+        );
+
+        state.open_settings();
+        state.update_draft_field(SettingsField::AutoSaveFolder, "   ");
+        state.save_and_close();
+        assert_eq!(state.auto_save_folder(), None);
+    }
+
+    #[test]
+    fn invalid_export_name_template_falls_back_to_the_default() {
+        let mut state = SettingsUiState::new(SettingsDraftState::default());
+        assert_eq!(state.export_name_template(), ExportNameTemplate::default());
+
+        state.open_settings();
+        state.update_draft_field(SettingsField::ExportNameTemplate, "{date}-{mode}");
+        assert_eq!(state.draft_export_name_template_error(), None);
+        state.save_and_close();
+        assert_eq!(state.export_name_template().as_str(), "{date}-{mode}");
+
+        state.open_settings();
+        state.update_draft_field(SettingsField::ExportNameTemplate, "{date}-{tempo}");
+        assert!(state.draft_export_name_template_error().is_some());
+        state.save_and_close();
+        assert_eq!(state.export_name_template(), ExportNameTemplate::default());
+    }
+
+    #[test]
+    fn draft_channel_mapping_edits_are_validated_and_resettable() {
+        let mut state = SettingsUiState::new(SettingsDraftState::default());
+
+        let changed = state
+            .update_draft_channel_mapping(ChannelMapping {
+                slot: ReferenceSlot::Bassline,
+                channel: 4,
+            })
+            .expect("free channel should be accepted");
+        assert!(changed);
+        assert_eq!(
+            state.dirty_fields(),
+            vec![SettingsField::DefaultChannelMappings]
+        );
+
+        let error = state
+            .update_draft_channel_mapping(ChannelMapping {
+                slot: ReferenceSlot::Melody,
+                channel: 4,
+            })
+            .expect_err("channel already used by bassline should be rejected");
+        assert_eq!(
+            error,
+            InputTrackModelError::DuplicateLiveChannel {
+                channel: 4,
+                existing_slot: ReferenceSlot::Melody,
+                conflicting_slot: ReferenceSlot::Bassline,
+            }
+        );
+
+        assert!(state.reset_draft_channel_mappings());
+        assert_eq!(
+            state.draft().default_channel_mappings,
+            default_live_channel_mappings()
+        );
+        assert!(!state.settings_dirty);
+    }
+
     #[test]
     fn save_and_close_promotes_draft_and_updates_provider_status() {
         let mut state = SettingsUiState::new(SettingsDraftState::default());
@@ -488,6 +1416,44 @@ mod tests {
         assert_eq!(state.saved(), &draft);
     }
 
+    #[test]
+    fn onboarding_walks_the_steps_and_saves_the_draft_after_the_model_step() {
+        let mut state = SettingsUiState::new(SettingsDraftState::default());
+        state.start_onboarding();
+        assert_eq!(state.onboarding_step(), Some(OnboardingStep::ApiKey));
+        assert!(state.onboarding_blocker(false).is_some());
+        assert_eq!(state.onboarding_blocker(true), None);
+        assert!(!state.back_onboarding());
+
+        let mut draft = state.draft().clone();
+        draft.anthropic_api_key = "sk-ant-valid-key".to_string();
+        state.update_draft(draft.clone());
+        assert_eq!(state.onboarding_blocker(false), None);
+        assert!(state.advance_onboarding());
+        assert_eq!(state.onboarding_step(), Some(OnboardingStep::Model));
+        assert_eq!(state.provider_status, ProviderStatus::NotConfigured);
+
+        assert!(state.advance_onboarding());
+        assert_eq!(
+            state.onboarding_step(),
+            Some(OnboardingStep::TestConnection)
+        );
+        assert_eq!(state.saved(), &draft);
+        assert_eq!(state.provider_status, ProviderStatus::Connected);
+
+        assert!(state.back_onboarding());
+        assert_eq!(state.onboarding_step(), Some(OnboardingStep::Model));
+        state.advance_onboarding();
+        state.advance_onboarding();
+        assert_eq!(
+            state.onboarding_step(),
+            Some(OnboardingStep::FirstReference)
+        );
+        state.advance_onboarding();
+        assert_eq!(state.screen, UiScreen::Main);
+        assert_eq!(state.onboarding_step(), None);
+    }
+
     #[test]
     fn discard_and_close_reverts_draft_to_saved_state() {
         let mut state = SettingsUiState::new(SettingsDraftState::with_default_model("stub-model"));
@@ -507,6 +1473,48 @@ mod tests {
         assert_eq!(state.draft(), state.saved());
     }
 
+    #[test]
+    fn recent_request_outcome_refines_provider_status_until_it_expires() {
+        let mut draft = SettingsDraftState::default();
+        draft.anthropic_api_key = "sk-ant-valid-key".to_string();
+        let mut state = SettingsUiState::new(draft);
+        let now = Instant::now();
+
+        state.provider_health = Some(ProviderHealth::failed(
+            "anthropic",
+            &LlmError::rate_limited(None),
+            now,
+        ));
+        assert_eq!(
+            state.effective_provider_status(now),
+            ProviderStatus::Degraded
+        );
+        assert!(
+            state
+                .recent_provider_health(now)
+                .expect("health should be recent")
+                .details(now)
+                .contains("rate limit")
+        );
+        assert_eq!(
+            state.effective_provider_status(now + Duration::from_secs(11 * 60)),
+            ProviderStatus::Connected
+        );
+
+        state.provider_health = Some(ProviderHealth::succeeded("anthropic", Some(820), now));
+        assert_eq!(
+            state.effective_provider_status(now),
+            ProviderStatus::Connected
+        );
+        assert_eq!(
+            state
+                .recent_provider_health(now)
+                .expect("health should be recent")
+                .details(now + Duration::from_secs(90)),
+            "anthropic: last request 1 min ago, 820 ms"
+        );
+    }
+
     #[test]
     fn provider_status_detects_not_configured_and_invalid_key() {
         let mut state = SettingsUiState::new(SettingsDraftState::default());
@@ -517,4 +1525,20 @@ mod tests {
         state.update_draft(invalid_key_draft);
         assert_eq!(state.draft_provider_status(), ProviderStatus::InvalidKey);
     }
+
+    #[test]
+    fn remote_server_token_counts_only_with_a_server_url() {
+        let mut state = SettingsUiState::new(SettingsDraftState::default());
+
+        let mut draft = state.draft().clone();
+        draft.remote_server_token = "studio-token".to_string();
+        state.update_draft(draft.clone());
+        assert_eq!(state.draft_provider_status(), ProviderStatus::NotConfigured);
+
+        draft.remote_server_url = "http://gpu-box.local:8440".to_string();
+        state.update_draft(draft);
+        assert_eq!(state.draft_provider_status(), ProviderStatus::Connected);
+        assert!(state.is_field_dirty(SettingsField::RemoteServerUrl));
+        assert!(state.is_field_dirty(SettingsField::RemoteServerToken));
+    }
 }
@@ -1,3 +1,5 @@
+use std::process::Command;
+
 use gpui::{App, Global, Hsla, Pixels, SharedString, px, rgb};
 use gpui_component::Theme;
 use sonant::domain::ReferenceSlot;
@@ -46,19 +48,25 @@ pub(super) struct ThemeColors {
     #[allow(dead_code)]
     pub(super) glow_pink: Hsla,
     pub(super) glow_playhead: Hsla,
+    pub(super) slot_colors: SlotColors,
 }
 
 impl ThemeColors {
     #[inline]
     pub(super) fn slot_color(self, slot: ReferenceSlot) -> Hsla {
-        match slot {
-            ReferenceSlot::Melody => self.track_purple,
-            ReferenceSlot::ChordProgression => self.track_blue,
-            ReferenceSlot::DrumPattern => self.track_green,
-            ReferenceSlot::Bassline => self.track_red,
-            ReferenceSlot::CounterMelody => self.track_orange,
-            ReferenceSlot::Harmony => self.track_cyan,
-            ReferenceSlot::ContinuationSeed => self.track_pink,
+        self.track_color(self.slot_colors.get(slot))
+    }
+
+    #[inline]
+    pub(super) fn track_color(self, color: TrackColor) -> Hsla {
+        match color {
+            TrackColor::Purple => self.track_purple,
+            TrackColor::Blue => self.track_blue,
+            TrackColor::Green => self.track_green,
+            TrackColor::Red => self.track_red,
+            TrackColor::Orange => self.track_orange,
+            TrackColor::Cyan => self.track_cyan,
+            TrackColor::Pink => self.track_pink,
         }
     }
 
@@ -81,6 +89,87 @@ impl ThemeColors {
     }
 }
 
+/// One of the theme's track colors, which reference slots are drawn in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum TrackColor {
+    Purple,
+    Blue,
+    Green,
+    Red,
+    Orange,
+    Cyan,
+    Pink,
+}
+
+impl TrackColor {
+    const ALL: [Self; 7] = [
+        Self::Purple,
+        Self::Blue,
+        Self::Green,
+        Self::Red,
+        Self::Orange,
+        Self::Cyan,
+        Self::Pink,
+    ];
+
+    pub(super) fn label(self) -> &'static str {
+        match self {
+            Self::Purple => "Purple",
+            Self::Blue => "Blue",
+            Self::Green => "Green",
+            Self::Red => "Red",
+            Self::Orange => "Orange",
+            Self::Cyan => "Cyan",
+            Self::Pink => "Pink",
+        }
+    }
+
+    pub(super) fn next(self) -> Self {
+        let position = Self::ALL
+            .iter()
+            .position(|color| *color == self)
+            .unwrap_or_default();
+        Self::ALL[(position + 1) % Self::ALL.len()]
+    }
+}
+
+/// The track color each reference slot is drawn in, in track rows and piano roll overlays.
+/// Slots may share a color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct SlotColors {
+    colors: [TrackColor; 7],
+}
+
+impl Default for SlotColors {
+    fn default() -> Self {
+        Self {
+            colors: TrackColor::ALL,
+        }
+    }
+}
+
+impl SlotColors {
+    pub(super) fn get(self, slot: ReferenceSlot) -> TrackColor {
+        self.colors[Self::index(slot)]
+    }
+
+    pub(super) fn set(&mut self, slot: ReferenceSlot, color: TrackColor) {
+        self.colors[Self::index(slot)] = color;
+    }
+
+    fn index(slot: ReferenceSlot) -> usize {
+        match slot {
+            ReferenceSlot::Melody => 0,
+            ReferenceSlot::ChordProgression => 1,
+            ReferenceSlot::DrumPattern => 2,
+            ReferenceSlot::Bassline => 3,
+            ReferenceSlot::CounterMelody => 4,
+            ReferenceSlot::Harmony => 5,
+            ReferenceSlot::ContinuationSeed => 6,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(super) struct ThemeTypography {
     pub(super) font_family: SharedString,
@@ -104,12 +193,154 @@ pub(super) struct ThemeRadius {
     pub(super) panel: Pixels,
 }
 
+/// Whether a display adjustment follows the OS setting or is forced on or off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(super) enum DisplayPreference {
+    #[default]
+    Auto,
+    On,
+    Off,
+}
+
+impl DisplayPreference {
+    pub(super) fn label(self) -> &'static str {
+        match self {
+            Self::Auto => "Auto",
+            Self::On => "On",
+            Self::Off => "Off",
+        }
+    }
+
+    pub(super) fn next(self) -> Self {
+        match self {
+            Self::Auto => Self::On,
+            Self::On => Self::Off,
+            Self::Off => Self::Auto,
+        }
+    }
+
+    pub(super) fn resolve(self, os_enabled: bool) -> bool {
+        match self {
+            Self::Auto => os_enabled,
+            Self::On => true,
+            Self::Off => false,
+        }
+    }
+}
+
+/// Accessibility display settings the OS reports; both stay off where they cannot be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(super) struct OsDisplayPreferences {
+    pub(super) high_contrast: bool,
+    pub(super) reduced_motion: bool,
+}
+
+impl OsDisplayPreferences {
+    #[cfg(target_os = "macos")]
+    pub(super) fn detect() -> Self {
+        let read = |key| {
+            read_os_flag("defaults", &["read", "com.apple.universalaccess", key]).unwrap_or(false)
+        };
+        Self {
+            high_contrast: read("increaseContrast"),
+            reduced_motion: read("reduceMotion"),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(super) fn detect() -> Self {
+        Self {
+            high_contrast: read_os_flag(
+                "gsettings",
+                &["get", "org.gnome.desktop.a11y.interface", "high-contrast"],
+            )
+            .unwrap_or(false),
+            reduced_motion: read_os_flag(
+                "gsettings",
+                &["get", "org.gnome.desktop.interface", "enable-animations"],
+            )
+            .is_some_and(|animations| !animations),
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    pub(super) fn detect() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg_attr(not(any(target_os = "macos", target_os = "linux")), allow(dead_code))]
+fn read_os_flag(program: &str, args: &[&str]) -> Option<bool> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_os_flag(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Reads `defaults` (`1`/`0`) and `gsettings` (`true`/`false`) boolean output.
+pub(super) fn parse_os_flag(output: &str) -> Option<bool> {
+    match output.trim() {
+        "1" | "true" => Some(true),
+        "0" | "false" => Some(false),
+        _ => None,
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(super) struct SonantTheme {
     pub(super) colors: ThemeColors,
     pub(super) typography: ThemeTypography,
     pub(super) spacing: ThemeSpacing,
     pub(super) radius: ThemeRadius,
+    /// Loading spinners are replaced by their static label.
+    pub(super) reduced_motion: bool,
+}
+
+impl SonantTheme {
+    pub(super) fn for_preferences(
+        high_contrast: DisplayPreference,
+        reduced_motion: DisplayPreference,
+        slot_colors: SlotColors,
+        os: OsDisplayPreferences,
+    ) -> Self {
+        let mut theme = Self::default();
+        theme.colors.slot_colors = slot_colors;
+        theme.reduced_motion = reduced_motion.resolve(os.reduced_motion);
+        if high_contrast.resolve(os.high_contrast) {
+            theme.colors.apply_high_contrast();
+        }
+        theme
+    }
+}
+
+impl ThemeColors {
+    /// Black surfaces, light borders and text, and no glow shadows.
+    fn apply_high_contrast(&mut self) {
+        self.surface_background = rgb(0x000000).into();
+        self.surface_foreground = rgb(0xffffff).into();
+        self.panel_background = rgb(0x05070d).into();
+        self.piano_roll_grid_line = rgb(0x475569).into();
+        self.input_background = rgb(0x0f1320).into();
+        self.panel_border = rgb(0xcbd5e1).into();
+        self.panel_active_background = rgb(0x1e293b).into();
+        self.panel_active_border = rgb(0xfacc15).into();
+        self.muted_foreground = rgb(0xe2e8f0).into();
+        self.accent_foreground = rgb(0xbfdbfe).into();
+        for glow in [
+            &mut self.glow_primary,
+            &mut self.glow_purple,
+            &mut self.glow_blue,
+            &mut self.glow_green,
+            &mut self.glow_red,
+            &mut self.glow_orange,
+            &mut self.glow_cyan,
+            &mut self.glow_pink,
+            &mut self.glow_playhead,
+        ] {
+            *glow = gpui::transparent_black();
+        }
+    }
 }
 
 impl Default for SonantTheme {
@@ -151,6 +382,7 @@ impl Default for SonantTheme {
                 glow_cyan: rgb(0x06b6d4).into(),
                 glow_pink: rgb(0xec4899).into(),
                 glow_playhead: rgb(0xeab308).into(),
+                slot_colors: SlotColors::default(),
             },
             typography: ThemeTypography {
                 font_family: ".SystemUIFont".into(),
@@ -174,6 +406,7 @@ impl Default for SonantTheme {
                 control: px(6.0),
                 panel: px(10.0),
             },
+            reduced_motion: false,
         }
     }
 }
@@ -65,6 +65,15 @@ pub(super) fn display_file_name_from_path(path: &str) -> String {
         .to_string()
 }
 
+/// Scientific pitch notation with middle C (60) as `C4`.
+pub(super) fn pitch_label(pitch: u8) -> String {
+    const NAMES: [&str; 12] = [
+        "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+    ];
+    let octave = i16::from(pitch / 12) - 1;
+    format!("{}{octave}", NAMES[usize::from(pitch % 12)])
+}
+
 #[cfg_attr(not(test), allow(dead_code))]
 pub(super) fn normalize_api_key_input(raw: &str) -> Option<String> {
     let trimmed = raw.trim();
@@ -74,3 +83,107 @@ pub(super) fn normalize_api_key_input(raw: &str) -> Option<String> {
         Some(trimmed.to_string())
     }
 }
+
+/// Languages whose locales write `1.234,5`; everything else is read as `1,234.5`.
+const COMMA_DECIMAL_LANGUAGES: [&str; 22] = [
+    "cs", "da", "de", "el", "es", "fi", "fr", "hr", "hu", "id", "it", "nb", "nl", "nn", "pl", "pt",
+    "ro", "ru", "sk", "sl", "sv", "tr",
+];
+
+/// Decimal and digit-grouping separators used to read numbers typed into the inputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct NumberFormat {
+    decimal: char,
+    grouping: char,
+}
+
+impl NumberFormat {
+    pub(super) const DECIMAL_POINT: Self = Self {
+        decimal: '.',
+        grouping: ',',
+    };
+    pub(super) const DECIMAL_COMMA: Self = Self {
+        decimal: ',',
+        grouping: '.',
+    };
+
+    /// Format of the user's numeric locale, from `LC_ALL`, `LC_NUMERIC` or `LANG`.
+    pub(super) fn from_env() -> Self {
+        ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .into_iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.trim().is_empty())
+            .map_or(Self::DECIMAL_POINT, |locale| Self::for_locale(&locale))
+    }
+
+    pub(super) fn for_locale(locale: &str) -> Self {
+        let language = locale
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if COMMA_DECIMAL_LANGUAGES.contains(&language.as_str()) {
+            Self::DECIMAL_COMMA
+        } else {
+            Self::DECIMAL_POINT
+        }
+    }
+
+    /// Reads `raw` as a non-negative number. Spaces and apostrophes always group digits. When
+    /// both `.` and `,` appear the later one is the decimal separator; a lone separator is read
+    /// the way this format uses it, except that this format's grouping separator is still taken
+    /// as a decimal one unless it is followed by exactly three digits ("120,5" is 120.5 BPM).
+    pub(super) fn parse_decimal(self, raw: &str) -> Option<f64> {
+        let cleaned: String = raw
+            .trim()
+            .chars()
+            .filter(|ch| !matches!(ch, ' ' | '\u{a0}' | '\u{202f}' | '\''))
+            .collect();
+        let decimal = match (cleaned.rfind('.'), cleaned.rfind(',')) {
+            (Some(point), Some(comma)) => Some(if point > comma { '.' } else { ',' }),
+            (Some(_), None) => self.lone_separator_role(&cleaned, '.'),
+            (None, Some(_)) => self.lone_separator_role(&cleaned, ','),
+            (None, None) => None,
+        };
+        let (integer, fraction) = match decimal {
+            Some(decimal) => cleaned.rsplit_once(decimal)?,
+            None => (cleaned.as_str(), ""),
+        };
+        let mut groups = integer.split(['.', ',']);
+        let leading = groups.next().unwrap_or_default();
+        if integer.contains(['.', ','])
+            && (leading.is_empty() || !groups.all(|group| group.len() == 3))
+        {
+            return None;
+        }
+        if integer.is_empty() && fraction.is_empty() {
+            return None;
+        }
+        let digits: String = integer
+            .chars()
+            .filter(|ch| !matches!(ch, '.' | ','))
+            .collect();
+        if !digits
+            .chars()
+            .chain(fraction.chars())
+            .all(|ch| ch.is_ascii_digit())
+        {
+            return None;
+        }
+        format!("0{digits}.{fraction}0").parse().ok()
+    }
+
+    /// Like [`Self::parse_decimal`] but rejects values with a fractional part.
+    pub(super) fn parse_integer(self, raw: &str) -> Option<u32> {
+        let value = self.parse_decimal(raw)?;
+        (value.fract() == 0.0 && value <= f64::from(u32::MAX)).then_some(value as u32)
+    }
+
+    /// The decimal separator of `value`, or `None` when its only separator groups digits.
+    fn lone_separator_role(self, value: &str, separator: char) -> Option<char> {
+        let groups: Vec<&str> = value.split(separator).skip(1).collect();
+        let groups_thousands = groups.len() > 1
+            || (separator == self.grouping && groups.first().is_some_and(|group| group.len() == 3));
+        (!groups_thousands).then_some(separator)
+    }
+}
@@ -1,56 +1,127 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use gpui::{
-    App, AppContext, Context, Entity, ExternalPaths, Hsla, IntoElement, PathPromptOptions, Pixels,
-    Render, ScrollHandle, Subscription, Task, Timer, Window, div, prelude::*, px,
+    AnyElement, AnyWindowHandle, App, AppContext, Bounds, Context, Corner, Div, Entity,
+    ExternalPaths, FocusHandle, Hsla, IntoElement, KeyDownEvent, MouseButton, MouseDownEvent,
+    PathPromptOptions, Pixels, Render, ScrollHandle, ScrollStrategy, Stateful, Subscription, Task,
+    Timer, UniformListScrollHandle, WeakEntity, Window, WindowBounds, WindowOptions, anchored,
+    deferred, div, prelude::*, px, size, uniform_list,
 };
 use gpui_component::{
-    Disableable,
+    Disableable, Root,
     button::{Button, ButtonVariants as _},
     input::{Input, InputEvent, InputState},
     label::Label,
     scroll::ScrollableElement,
     select::{Select, SelectEvent, SelectState},
     slider::{Slider, SliderEvent, SliderState, SliderValue},
+    tooltip::Tooltip,
 };
 use sonant::{
     app::{
-        ChannelMapping, GenerationJobManager, GenerationJobState, GenerationJobUpdate,
-        InputTrackModel, LIVE_INPUT_IPC_SOCKET_ENV, LiveInputEvent, LiveInputEventSource,
-        LiveInputIpcSource, LiveMidiCapture, LoadMidiCommand, LoadMidiUseCase, MIDI_CHANNEL_MAX,
-        MIDI_CHANNEL_MIN, MidiInputRouter,
+        ARRANGEMENT_SECTION_MAX_BARS, AppliedClip, AppliedClipSink, ApplyDestination,
+        ApplyFileTarget, ArrangementRun, ArrangementSection, BatchRun,
+        CANDIDATE_EXPLANATION_CACHE_MAX_ENTRIES, CandidateExplanation, CandidateExplanationCache,
+        ChannelMapping, DRUM_STEP_LANES, DeferredOutcome, DeferredRequestQueue, DiagnosticCheck,
+        DiagnosticStatus, DrumHit, DrumMap, DrumStepPattern, ExportNameContext, FolderUsage,
+        GENERATION_HISTORY_MAX_BYTES, GENERATION_HISTORY_MAX_ENTRIES,
+        GenerationHistoryExportFormat, GenerationHistoryStore, GenerationJobManager,
+        GenerationJobState, GenerationJobUpdate, GenerationService, HELPER_CONTROL_IPC_SOCKET_ENV,
+        HelperControlIpcSender, HelperControlMessage, HostTransportContext, INPUT_TRACK_LAYOUT_ENV,
+        InputTrackLayout, InputTrackModel, InputTrackPresetStore, JobSubmission,
+        LIVE_INPUT_IPC_SOCKET_ENV, LiveInputEvent, LiveInputEventSource, LiveInputIpcSource,
+        LiveMidiCapture, LoadMidiCommand, LoadMidiUseCase, MIDI_CHANNEL_MAX, MIDI_CHANNEL_MIN,
+        MODEL_COMPARISON_MAX_MODELS, MODEL_COMPARISON_MIN_MODELS, MidiInputRouter, ModelComparison,
+        OnboardingMarker, PROJECT_MODEL_ENV, PromptSuggestion, ProviderDemotion,
+        ProviderErrorBudget, QueueOverflowMetrics, RecentFilesStore, ReferenceFileWatcher,
+        ReferenceSketch, SKETCH_PITCH_ROWS, SamplingProfile, SamplingProfileStore,
+        SlotReferenceSnapshot, StemPart, StemSource, apply_sustain_pedal_to_live_events,
+        apply_velocity_profile_to_live_events, autosave_candidates, candidate_name, check_api_keys,
+        check_provider_reachability, dispatch_apply, drum_step_pattern_file_name,
+        enforce_folder_quota, export_stems, folder_usage, format_byte_size, gm_program_name,
+        insert_prompt_snippet, live_take_file_name, load_batch_prompts, load_generation_request,
+        next_export_program, program_for_slot, staged_live_take_file_name, suggest_prompt_snippets,
+        write_drum_step_pattern, write_live_take,
     },
     domain::{
-        GeneratedNote, GenerationCandidate, GenerationMode, LlmError, MidiReferenceEvent,
-        MidiReferenceSummary, ModelRef, ReferenceSlot, ReferenceSource,
-        calculate_reference_density_hint, has_supported_midi_extension,
+        CandidateConfidence, CandidateMetric, CandidateMetrics, CandidateRepairReport, ChordLabel,
+        ConfidenceLevel, DawContext, DawTrackRole, GeneratedNote, GenerationCandidate,
+        GenerationConstraints, GenerationMode, GenerationRequest, GenerationResult, LlmError,
+        MidiReferenceEvent, MidiReferenceSummary, ModeParamSpec, ModelRef, PromptLint,
+        PromptLintFix, ReferenceSlot, ReferenceSource, TickResolution, TimeSignature,
+        ValidationStrictness, ValidationWarning, VelocityProfile, calculate_reference_density_hint,
+        estimate_ticks_per_beat, has_supported_midi_extension, rank_candidates,
+        syncopation_level_for_off_beat_ratio,
+    },
+    infra::llm::AuditLog,
+    infra::midi::{
+        MidiConductor, ReferenceAnalysis, ReferenceSimilarity, analyze_reference, force_monophonic,
+        split_melody_and_chords, write_notes_to_midi_file,
     },
 };
 
 use super::backend::build_generation_backend;
-use super::request::PromptSubmissionModel;
+use super::piano_roll_window::DetachedPianoRollWindow;
+use super::request::{
+    PromptSubmissionModel, SamplingParam, SamplingParams, alternate_provider_model,
+    provider_demotion_notice, provider_display_name,
+};
 use super::state::{
-    HelperGenerationStatus, MidiSlotErrorState, SettingsDraftState, SettingsField, SettingsTab,
-    SettingsUiState, mode_reference_requirement, mode_reference_requirement_satisfied,
+    GenerationFailureAction, HelperGenerationStatus, MidiSlotErrorState, OnboardingStep,
+    OverlayMenu, OverlayMenus, ProviderHealth, SettingsDraftState, SettingsField, SettingsTab,
+    SettingsUiState, TapTempo, mode_reference_requirement, mode_reference_requirement_satisfied,
 };
-use super::theme::{SonantTheme, ThemeColors};
+use super::theme::{OsDisplayPreferences, SonantTheme, ThemeColors, apply_theme};
 use super::utils::{
-    choose_dropped_midi_path, display_file_name_from_path, dropped_path_to_load,
-    log_generation_request_submission,
+    NumberFormat, choose_dropped_midi_path, display_file_name_from_path, dropped_path_to_load,
+    log_generation_request_submission, pitch_label, prompt_preview,
 };
 use super::{
-    BPM_MAX, BPM_MIN, DEFAULT_ANTHROPIC_MODEL, DEFAULT_BPM, DEFAULT_COMPLEXITY, DEFAULT_DENSITY,
-    DEFAULT_OPENAI_COMPAT_MODEL, JOB_UPDATE_POLL_INTERVAL_MS, MIDI_SLOT_DROP_ERROR_MESSAGE,
+    ARRANGEMENT_EXPORT_PICKER_PROMPT, ARRANGEMENT_SECTION_PROMPT_PLACEHOLDER,
+    BATCH_PROMPTS_PICKER_PROMPT, BPM_MAX, BPM_MIN, CANDIDATE_ANNOTATION_PLACEHOLDER,
+    CONSTRAINTS_PLACEHOLDER, DEFAULT_ANTHROPIC_MODEL, DEFAULT_BPM, DEFAULT_COMPLEXITY,
+    DEFAULT_DENSITY, DEFAULT_OPENAI_COMPAT_MODEL, DEFAULT_SYNCOPATION,
+    HISTORY_EXPORT_PICKER_PROMPT, INPUT_TRACK_PRESET_NAME_PLACEHOLDER, JOB_UPDATE_IDLE_TICK_MS,
+    JOB_UPDATE_PLAYBACK_POLL_INTERVAL_MS, MIDI_SLOT_DROP_ERROR_MESSAGE,
     MIDI_SLOT_FILE_PICKER_PROMPT, MIDI_SLOT_UNSUPPORTED_FILE_MESSAGE, PROMPT_EDITOR_ROWS,
-    PROMPT_PLACEHOLDER, PROMPT_VALIDATION_MESSAGE, SETTINGS_ANTHROPIC_API_KEY_PLACEHOLDER,
-    SETTINGS_CONTEXT_WINDOW_PLACEHOLDER, SETTINGS_CUSTOM_BASE_URL_PLACEHOLDER,
-    SETTINGS_DEFAULT_MODEL_PLACEHOLDER, SETTINGS_OPENAI_API_KEY_PLACEHOLDER,
+    PROMPT_PLACEHOLDER, PROMPT_VALIDATION_MESSAGE, REQUEST_IMPORT_PICKER_PROMPT,
+    SAMPLING_PROFILE_NAME_PLACEHOLDER, SETTINGS_ANTHROPIC_API_KEY_PLACEHOLDER,
+    SETTINGS_AUTO_SAVE_FOLDER_PLACEHOLDER, SETTINGS_CONTEXT_WINDOW_PLACEHOLDER,
+    SETTINGS_CUSTOM_BASE_URL_PLACEHOLDER, SETTINGS_DEFAULT_MODEL_PLACEHOLDER,
+    SETTINGS_EXPORT_NAME_TEMPLATE_PLACEHOLDER, SETTINGS_OPENAI_API_KEY_PLACEHOLDER,
+    SETTINGS_REMOTE_SERVER_TOKEN_PLACEHOLDER, SETTINGS_REMOTE_SERVER_URL_PLACEHOLDER,
+    SETTINGS_TICK_RESOLUTION_PLACEHOLDER, STEM_EXPORT_PICKER_PROMPT, TAKE_SAVE_PICKER_PROMPT,
 };
 
 const LIVE_CAPTURE_POLL_INTERVAL_MS: u64 = 30;
+const REFERENCE_WATCH_POLL_INTERVAL_MS: u64 = 1_000;
+const TRACK_UNDO_TIMEOUT_MS: u64 = 8_000;
+// Folder under the system temp dir that receives takes revealed for dragging into the DAW. Files
+// are named per slot without a timestamp, so each reveal overwrites the last one.
+const LIVE_TAKE_TEMP_FOLDER: &str = "sonant-takes";
+const ONBOARDING_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+// How long closing the window waits for an in-flight generation before cancelling it.
+const HELPER_CLOSE_JOB_GRACE: Duration = Duration::from_secs(3);
+// Tab order of the keyboard-operable lists and sliders, left column first. GPUI exposes no
+// accessibility tree, so these elements have no accessible names or roles for screen readers
+// yet; keyboard operation is the part of assistive access the helper can offer today.
+const TRACK_LIST_TAB_INDEX: isize = 1;
+const COMPLEXITY_SLIDER_TAB_INDEX: isize = 2;
+const DENSITY_SLIDER_TAB_INDEX: isize = 3;
+const SYNCOPATION_SLIDER_TAB_INDEX: isize = 4;
+const CANDIDATE_LIST_TAB_INDEX: isize = 5;
+const DETACHED_PIANO_ROLL_WIDTH: f32 = 960.0;
+const DETACHED_PIANO_ROLL_HEIGHT: f32 = 520.0;
 const LIVE_CAPTURE_MAX_EVENTS_PER_POLL: usize = 512;
 const PARAM_LEVEL_MIN: u8 = 1;
+const ARRANGEMENT_SECTION_NAMES: [&str; 5] = ["Intro", "Verse", "Chorus", "Bridge", "Outro"];
+const ARRANGEMENT_DEFAULT_SECTION_BARS: u16 = 4;
 const PARAM_LEVEL_MAX: u8 = 5;
 const PARAM_LEVEL_SPAN: u8 = PARAM_LEVEL_MAX - PARAM_LEVEL_MIN;
 const PARAM_KEY_OPTIONS: [&str; 12] = [
@@ -67,6 +138,7 @@ const PARAM_SCALE_OPTIONS: [(&str, &str); 7] = [
 ];
 const PIANO_ROLL_KEY_LABEL_WIDTH: f32 = 48.0;
 const PIANO_ROLL_RULER_HEIGHT: f32 = 22.0;
+const PIANO_ROLL_CHORD_LANE_HEIGHT: f32 = 18.0;
 const PIANO_ROLL_ROW_HEIGHT: f32 = 24.0;
 const PIANO_ROLL_BEAT_WIDTH: f32 = 40.0;
 const PIANO_ROLL_BEATS_PER_BAR: usize = 4;
@@ -80,6 +152,13 @@ const PIANO_ROLL_PLAYHEAD_WIDTH: f32 = 2.0;
 const PIANO_ROLL_FALLBACK_TICKS_PER_BEAT: f32 = 240.0;
 type DropdownState = SelectState<Vec<&'static str>>;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ParamSlider {
+    Complexity,
+    Density,
+    Syncopation,
+}
+
 #[derive(Debug, Clone, Copy)]
 struct PianoRollNoteRect {
     x: f32,
@@ -88,6 +167,9 @@ struct PianoRollNoteRect {
     height: f32,
     is_preview: bool,
     color: Option<Hsla>,
+    /// Index into the selected candidate's notes; only selected-candidate notes are lockable.
+    note_index: Option<usize>,
+    is_locked: bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -103,9 +185,12 @@ struct ParsedReferenceNoteEvent {
     kind: ParsedNoteEventKind,
 }
 
-fn parse_bpm_input_value(raw: &str) -> Option<u16> {
-    let parsed = raw.trim().parse::<u16>().ok()?;
-    (BPM_MIN..=BPM_MAX).contains(&parsed).then_some(parsed)
+/// Fractional tempos typed as "120,5" or "99.6" round to the nearest whole BPM.
+fn parse_bpm_input_value(raw: &str, format: NumberFormat) -> Option<u16> {
+    let parsed = format.parse_decimal(raw)?.round();
+    (f64::from(BPM_MIN)..=f64::from(BPM_MAX))
+        .contains(&parsed)
+        .then_some(parsed as u16)
 }
 
 pub(super) struct SonantMainWindow {
@@ -121,50 +206,189 @@ pub(super) struct SonantMainWindow {
     _scale_dropdown_subscription: Subscription,
     bpm_input: Entity<InputState>,
     _bpm_input_subscription: Subscription,
+    tap_tempo: TapTempo,
     complexity_slider: Entity<SliderState>,
     _complexity_slider_subscription: Subscription,
     density_slider: Entity<SliderState>,
     _density_slider_subscription: Subscription,
+    syncopation_slider: Entity<SliderState>,
+    _syncopation_slider_subscription: Subscription,
+    complexity_slider_focus: FocusHandle,
+    density_slider_focus: FocusHandle,
+    syncopation_slider_focus: FocusHandle,
     settings_anthropic_api_key_input: Entity<InputState>,
     _settings_anthropic_api_key_subscription: Subscription,
     settings_openai_api_key_input: Entity<InputState>,
     _settings_openai_api_key_subscription: Subscription,
     settings_custom_base_url_input: Entity<InputState>,
     _settings_custom_base_url_subscription: Subscription,
+    settings_remote_server_url_input: Entity<InputState>,
+    _settings_remote_server_url_subscription: Subscription,
+    settings_remote_server_token_input: Entity<InputState>,
+    _settings_remote_server_token_subscription: Subscription,
     settings_default_model_input: Entity<InputState>,
     _settings_default_model_subscription: Subscription,
     settings_context_window_input: Entity<InputState>,
+    settings_tick_resolution_input: Entity<InputState>,
+    settings_auto_save_folder_input: Entity<InputState>,
+    settings_export_name_template_input: Entity<InputState>,
+    preset_name_input: Entity<InputState>,
+    arrangement_prompt_input: Entity<InputState>,
+    constraints_input: Entity<InputState>,
+    sampling_profile_name_input: Entity<InputState>,
+    candidate_annotation_input: Entity<InputState>,
     _settings_context_window_subscription: Subscription,
+    _settings_tick_resolution_subscription: Subscription,
+    _settings_auto_save_folder_subscription: Subscription,
+    _settings_export_name_template_subscription: Subscription,
     load_midi_use_case: Arc<LoadMidiUseCase>,
     live_midi_capture: LiveMidiCapture,
     midi_input_router: MidiInputRouter,
     generation_job_manager: Arc<GenerationJobManager>,
+    explanation_service: GenerationService,
+    helper_control_sender: Option<HelperControlIpcSender>,
     submission_model: PromptSubmissionModel,
     settings_ui_state: SettingsUiState,
     is_syncing_settings_inputs: bool,
     input_track_model: InputTrackModel,
+    /// Model pinned to the DAW project; it replaces the settings default while the project is open.
+    project_model: Option<ModelRef>,
+    os_display: OsDisplayPreferences,
+    input_track_presets: InputTrackPresetStore,
+    recent_files: RecentFilesStore,
+    sampling_profiles: SamplingProfileStore,
+    active_sampling_profile: Option<String>,
+    sampling_profile_error: Option<String>,
+    generation_history: GenerationHistoryStore,
+    /// Results finished during playback, written to the history once the transport stops.
+    deferred_history_records: Vec<(GenerationRequest, GenerationResult)>,
+    pending_history_requests: std::collections::HashMap<String, GenerationRequest>,
+    /// Requests whose provider was unreachable, resent once it answers again.
+    deferred_requests: DeferredRequestQueue,
+    candidates_request_id: Option<String>,
+    candidate_annotation_error: Option<String>,
+    history_export_error: Option<String>,
+    stem_export_error: Option<String>,
+    auto_save_error: Option<String>,
+    // Sonant files in the auto-save folder as of the last write or settings visit.
+    auto_save_usage: Option<FolderUsage>,
+    apply_error: Option<String>,
     recording_channel_enabled: [bool; 16],
+    /// Shape applied to live-captured velocities when references are collected, with its seed.
+    live_velocity_profile: Option<(VelocityProfile, u64)>,
+    midi_thru_slots: std::collections::HashSet<ReferenceSlot>,
+    /// Melodic slots whose references are cleaned up to one note at a time before generating.
+    monophonic_slots: std::collections::HashSet<ReferenceSlot>,
     live_capture_transport_playing: bool,
     live_capture_playhead_ppq: f64,
+    auto_generate_on_loop: bool,
+    auto_apply_first_candidate: bool,
+    note_expression_output: bool,
+    // (request_id, candidate_id) of the candidate currently looping on the plugin output.
+    applied_candidate: Option<(String, String)>,
+    // row_index of the track whose reference is auditioning on the plugin output.
+    previewing_reference_row: Option<usize>,
+    candidates_mode: Option<GenerationMode>,
+    /// Model of a single-model result; comparisons use `generation_candidate_models`.
+    candidates_model: Option<ModelRef>,
+    /// Reference the shown candidates extend, for their similarity scores.
+    candidates_reference: Option<MidiReferenceSummary>,
+    generation_failure_action: Option<GenerationFailureAction>,
+    last_failed_request: Option<GenerationRequest>,
+    // Last countdown value rendered, so polling only re-renders when the second changes.
+    shown_retry_countdown_secs: Option<u64>,
     selected_generation_mode: GenerationMode,
     visible_slot_rows: Vec<ReferenceSlot>,
     piano_roll_hidden_rows: std::collections::HashSet<usize>,
     piano_roll_vertical_scroll_handle: ScrollHandle,
     piano_roll_horizontal_scroll_handle: ScrollHandle,
-    add_track_menu_open: bool,
-    channel_menu_open: Option<usize>, // row_index of the row whose channel menu is open
-    slot_type_menu_open: Option<usize>, // row_index of the row whose slot-type menu is open
+    /// Window showing the piano roll instead of the main window, while it is detached.
+    detached_piano_roll: Option<AnyWindowHandle>,
+    piano_roll_detach_error: Option<String>,
+    overlay_menus: OverlayMenus,
+    /// Focused while a popover is open, so Esc reaches it.
+    overlay_menu_focus: FocusHandle,
+    advanced_sampling_open: bool,
+    arrangement_sections: Vec<ArrangementSection>,
+    arrangement_section_name: &'static str,
+    arrangement_section_bars: u16,
+    arrangement_run: Option<ArrangementRun>,
+    arrangement_request_id: Option<String>,
+    arrangement_error: Option<String>,
+    batch_run: Option<BatchRun>,
+    batch_request_id: Option<String>,
+    batch_error: Option<String>,
+    analysis_row_open: Option<usize>, // row_index of the row whose analysis panel is expanded
+    /// Last analysis shown in the open panel, reused while the host is playing.
+    reference_analysis_cache: Option<(ReferenceSlot, Option<ReferenceAnalysis>)>,
+    /// Slot whose keyboard sketch panel is open.
+    sketch_slot_open: Option<ReferenceSlot>,
+    reference_sketch: ReferenceSketch,
+    drum_step_pattern: DrumStepPattern,
     generation_status: HelperGenerationStatus,
     generation_candidates: Vec<GenerationCandidate>,
+    /// Model of each entry in `generation_candidates` when they come from a model comparison.
+    generation_candidate_models: Vec<ModelRef>,
+    comparison_failures: Vec<String>,
+    comparison_models: Vec<ModelRef>,
+    prompt_suggestions: Vec<PromptSuggestion>,
+    /// Pre-flight warnings that held back the last Generate click.
+    prompt_lints: Vec<PromptLint>,
+    // Set by "Generate Anyway" so the next submission skips the pre-flight lint.
+    skip_prompt_lints: bool,
+    /// Request the last Generate click was collapsed into as an accidental double submission.
+    duplicate_submission_of: Option<String>,
+    // Set by "Submit Again" so the next submission starts even if it repeats the running one.
+    allow_duplicate_submission: bool,
+    // Separators of the user's locale, used to read the BPM and numeric settings inputs.
+    number_format: NumberFormat,
+    generation_chords: Vec<ChordLabel>,
+    generation_repairs: Vec<CandidateRepairReport>,
+    generation_validation_warnings: Vec<ValidationWarning>,
+    generation_confidence: Vec<CandidateConfidence>,
+    candidate_explanations: CandidateExplanationCache,
     selected_candidate_index: Option<usize>,
     hidden_candidates: std::collections::HashSet<usize>,
+    candidate_sort: Option<(CandidateMetric, bool)>, // (metric, descending)
+    candidate_top_half_only: bool,
+    candidate_list_focus: FocusHandle,
+    /// Only the rows in view of the candidate list are built, so long sessions stay smooth.
+    candidate_list_scroll_handle: UniformListScrollHandle,
+    track_list_focus: FocusHandle,
+    /// Row the keyboard acts on while the track list has focus.
+    focused_track_row: Option<usize>,
     validation_error: Option<String>,
+    constraints_error: Option<String>,
     input_track_error: Option<String>,
+    settings_channel_mapping_error: Option<String>,
     midi_slot_errors: Vec<MidiSlotErrorState>,
     startup_notice: Option<String>,
+    audit_log: Option<Arc<AuditLog>>,
+    audit_log_notice: Option<String>,
+    provider_error_budget: ProviderErrorBudget,
+    provider_demotions: Vec<ProviderDemotion>,
     _update_poll_task: Task<()>,
     _live_capture_poll_task: Task<()>,
+    _reference_watch_task: Task<()>,
+    reference_file_watcher: ReferenceFileWatcher,
+    reference_reload_notice: Option<String>,
+    track_undo: Option<TrackRowUndo>,
+    _track_undo_expiry_task: Task<()>,
     _midi_file_picker_task: Task<()>,
+    _take_save_task: Task<()>,
+    _arrangement_export_task: Task<()>,
+    _history_export_task: Task<()>,
+    _stem_export_task: Task<()>,
+    _request_import_task: Task<()>,
+    _batch_prompts_task: Task<()>,
+    onboarding_marker: OnboardingMarker,
+    /// A provider key was exported before launch, so generation works without settings keys.
+    env_api_key_configured: bool,
+    /// Results of the wizard's connection test; `None` until it has run.
+    onboarding_checks: Option<Vec<DiagnosticCheck>>,
+    onboarding_check_running: bool,
+    onboarding_error: Option<String>,
+    _onboarding_check_task: Task<()>,
 }
 
 impl SonantMainWindow {
@@ -220,6 +444,18 @@ impl SonantMainWindow {
         });
         let density_slider_subscription =
             cx.subscribe_in(&density_slider, window, Self::on_density_slider_event);
+        let syncopation_slider = cx.new(|_| {
+            SliderState::new()
+                .min(PARAM_LEVEL_MIN as f32)
+                .max(PARAM_LEVEL_MAX as f32)
+                .step(1.0)
+                .default_value(DEFAULT_SYNCOPATION as f32)
+        });
+        let syncopation_slider_subscription = cx.subscribe_in(
+            &syncopation_slider,
+            window,
+            Self::on_syncopation_slider_event,
+        );
         let settings_anthropic_api_key_input = cx.new(|cx| {
             InputState::new(window, cx)
                 .placeholder(SETTINGS_ANTHROPIC_API_KEY_PLACEHOLDER)
@@ -248,6 +484,24 @@ impl SonantMainWindow {
             window,
             Self::on_settings_input_event,
         );
+        let settings_remote_server_url_input = cx.new(|cx| {
+            InputState::new(window, cx).placeholder(SETTINGS_REMOTE_SERVER_URL_PLACEHOLDER)
+        });
+        let settings_remote_server_url_subscription = cx.subscribe_in(
+            &settings_remote_server_url_input,
+            window,
+            Self::on_settings_input_event,
+        );
+        let settings_remote_server_token_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder(SETTINGS_REMOTE_SERVER_TOKEN_PLACEHOLDER)
+                .masked(true)
+        });
+        let settings_remote_server_token_subscription = cx.subscribe_in(
+            &settings_remote_server_token_input,
+            window,
+            Self::on_settings_input_event,
+        );
         let settings_default_model_input = cx
             .new(|cx| InputState::new(window, cx).placeholder(SETTINGS_DEFAULT_MODEL_PLACEHOLDER));
         let settings_default_model_subscription = cx.subscribe_in(
@@ -262,12 +516,82 @@ impl SonantMainWindow {
             window,
             Self::on_settings_input_event,
         );
+        let settings_tick_resolution_input = cx.new(|cx| {
+            InputState::new(window, cx).placeholder(SETTINGS_TICK_RESOLUTION_PLACEHOLDER)
+        });
+        let settings_tick_resolution_subscription = cx.subscribe_in(
+            &settings_tick_resolution_input,
+            window,
+            Self::on_settings_input_event,
+        );
+        let settings_auto_save_folder_input = cx.new(|cx| {
+            InputState::new(window, cx).placeholder(SETTINGS_AUTO_SAVE_FOLDER_PLACEHOLDER)
+        });
+        let settings_auto_save_folder_subscription = cx.subscribe_in(
+            &settings_auto_save_folder_input,
+            window,
+            Self::on_settings_input_event,
+        );
+        let settings_export_name_template_input = cx.new(|cx| {
+            InputState::new(window, cx).placeholder(SETTINGS_EXPORT_NAME_TEMPLATE_PLACEHOLDER)
+        });
+        let settings_export_name_template_subscription = cx.subscribe_in(
+            &settings_export_name_template_input,
+            window,
+            Self::on_settings_input_event,
+        );
+        let preset_name_input = cx
+            .new(|cx| InputState::new(window, cx).placeholder(INPUT_TRACK_PRESET_NAME_PLACEHOLDER));
+        let sampling_profile_name_input =
+            cx.new(|cx| InputState::new(window, cx).placeholder(SAMPLING_PROFILE_NAME_PLACEHOLDER));
+        let candidate_annotation_input =
+            cx.new(|cx| InputState::new(window, cx).placeholder(CANDIDATE_ANNOTATION_PLACEHOLDER));
+        let arrangement_prompt_input = cx.new(|cx| {
+            InputState::new(window, cx).placeholder(ARRANGEMENT_SECTION_PROMPT_PLACEHOLDER)
+        });
+        let constraints_input =
+            cx.new(|cx| InputState::new(window, cx).placeholder(CONSTRAINTS_PLACEHOLDER));
 
         let backend = build_generation_backend();
-        let settings_ui_state = SettingsUiState::new(SettingsDraftState::with_default_model(
+        let mut settings_ui_state = SettingsUiState::new(SettingsDraftState::with_default_model(
             backend.default_model.model.clone(),
         ));
-        let input_track_model = InputTrackModel::new();
+        let (input_track_model, visible_slot_rows, layout_error) = restore_input_track_layout();
+        let project_model = restore_project_model();
+        let onboarding_marker = OnboardingMarker::open_default();
+        let env_api_key_configured =
+            check_api_keys(|name| std::env::var(name).ok()).status == DiagnosticStatus::Ok;
+        if !onboarding_marker.is_complete() && !env_api_key_configured {
+            settings_ui_state.start_onboarding();
+        }
+        let os_display = OsDisplayPreferences::detect();
+        apply_theme(
+            SonantTheme::for_preferences(
+                settings_ui_state.saved().high_contrast,
+                settings_ui_state.saved().reduced_motion,
+                settings_ui_state.saved().slot_colors,
+                os_display,
+            ),
+            cx,
+        );
+        let (input_track_presets, preset_error) = match InputTrackPresetStore::open_default() {
+            Ok(store) => (store, None),
+            Err(error) => (InputTrackPresetStore::in_memory(), Some(error.to_string())),
+        };
+        let (recent_files, recent_files_error) = match RecentFilesStore::open_default() {
+            Ok(store) => (store, None),
+            Err(error) => (RecentFilesStore::in_memory(), Some(error.to_string())),
+        };
+        let (sampling_profiles, sampling_profile_error) = match SamplingProfileStore::open_default()
+        {
+            Ok(store) => (store, None),
+            Err(error) => (SamplingProfileStore::in_memory(), Some(error.to_string())),
+        };
+        let (generation_history, generation_history_error) =
+            match GenerationHistoryStore::open_default() {
+                Ok(store) => (store, None),
+                Err(error) => (GenerationHistoryStore::in_memory(), Some(error.to_string())),
+            };
         let recording_channel_enabled = [false; 16];
         let (live_input_source, live_input_error) = resolve_live_input_source();
         let live_midi_capture = LiveMidiCapture::new(live_input_source);
@@ -286,57 +610,216 @@ impl SonantMainWindow {
             _scale_dropdown_subscription: scale_dropdown_subscription,
             bpm_input,
             _bpm_input_subscription: bpm_input_subscription,
+            tap_tempo: TapTempo::default(),
             complexity_slider,
             _complexity_slider_subscription: complexity_slider_subscription,
             density_slider,
             _density_slider_subscription: density_slider_subscription,
+            syncopation_slider,
+            _syncopation_slider_subscription: syncopation_slider_subscription,
+            complexity_slider_focus: cx
+                .focus_handle()
+                .tab_index(COMPLEXITY_SLIDER_TAB_INDEX)
+                .tab_stop(true),
+            density_slider_focus: cx
+                .focus_handle()
+                .tab_index(DENSITY_SLIDER_TAB_INDEX)
+                .tab_stop(true),
+            syncopation_slider_focus: cx
+                .focus_handle()
+                .tab_index(SYNCOPATION_SLIDER_TAB_INDEX)
+                .tab_stop(true),
             settings_anthropic_api_key_input,
             _settings_anthropic_api_key_subscription: settings_anthropic_api_key_subscription,
             settings_openai_api_key_input,
             _settings_openai_api_key_subscription: settings_openai_api_key_subscription,
             settings_custom_base_url_input,
             _settings_custom_base_url_subscription: settings_custom_base_url_subscription,
+            settings_remote_server_url_input,
+            _settings_remote_server_url_subscription: settings_remote_server_url_subscription,
+            settings_remote_server_token_input,
+            _settings_remote_server_token_subscription: settings_remote_server_token_subscription,
             settings_default_model_input,
             _settings_default_model_subscription: settings_default_model_subscription,
             settings_context_window_input,
             _settings_context_window_subscription: settings_context_window_subscription,
+            settings_tick_resolution_input,
+            _settings_tick_resolution_subscription: settings_tick_resolution_subscription,
+            settings_auto_save_folder_input,
+            _settings_auto_save_folder_subscription: settings_auto_save_folder_subscription,
+            settings_export_name_template_input,
+            _settings_export_name_template_subscription: settings_export_name_template_subscription,
+            preset_name_input,
+            arrangement_prompt_input,
+            constraints_input,
+            sampling_profile_name_input,
+            candidate_annotation_input,
             load_midi_use_case: Arc::new(LoadMidiUseCase::new()),
             live_midi_capture,
             midi_input_router,
             generation_job_manager: Arc::clone(&backend.job_manager),
-            submission_model: PromptSubmissionModel::new(backend.default_model),
+            explanation_service: backend.explanation_service,
+            helper_control_sender: resolve_helper_control_sender(),
+            submission_model: PromptSubmissionModel::new(
+                project_model.clone().unwrap_or(backend.default_model),
+            ),
             settings_ui_state,
             is_syncing_settings_inputs: false,
             input_track_model,
+            project_model,
+            os_display,
+            input_track_presets,
+            recent_files,
+            sampling_profiles,
+            active_sampling_profile: None,
+            sampling_profile_error,
+            generation_history,
+            deferred_history_records: Vec::new(),
+            pending_history_requests: std::collections::HashMap::new(),
+            deferred_requests: DeferredRequestQueue::default(),
+            candidates_request_id: None,
+            candidate_annotation_error: generation_history_error,
+            history_export_error: None,
+            stem_export_error: None,
+            auto_save_error: None,
+            auto_save_usage: None,
+            apply_error: None,
             recording_channel_enabled,
+            live_velocity_profile: None,
+            midi_thru_slots: std::collections::HashSet::new(),
+            monophonic_slots: std::collections::HashSet::new(),
             live_capture_transport_playing: false,
             live_capture_playhead_ppq: 0.0,
+            auto_generate_on_loop: false,
+            auto_apply_first_candidate: false,
+            note_expression_output: false,
+            applied_candidate: None,
+            previewing_reference_row: None,
+            candidates_mode: None,
+            candidates_model: None,
+            candidates_reference: None,
+            generation_failure_action: None,
+            last_failed_request: None,
+            shown_retry_countdown_secs: None,
             selected_generation_mode: GenerationMode::Melody,
-            visible_slot_rows: vec![],
+            visible_slot_rows,
             piano_roll_hidden_rows: std::collections::HashSet::new(),
             piano_roll_vertical_scroll_handle: ScrollHandle::new(),
             piano_roll_horizontal_scroll_handle: ScrollHandle::new(),
-            add_track_menu_open: false,
-            channel_menu_open: None,
-            slot_type_menu_open: None,
+            detached_piano_roll: None,
+            piano_roll_detach_error: None,
+            overlay_menus: OverlayMenus::default(),
+            overlay_menu_focus: cx.focus_handle(),
+            advanced_sampling_open: false,
+            arrangement_sections: Vec::new(),
+            arrangement_section_name: ARRANGEMENT_SECTION_NAMES[0],
+            arrangement_section_bars: ARRANGEMENT_DEFAULT_SECTION_BARS,
+            arrangement_run: None,
+            arrangement_request_id: None,
+            arrangement_error: None,
+            batch_run: None,
+            batch_request_id: None,
+            batch_error: None,
+            analysis_row_open: None,
+            reference_analysis_cache: None,
+            sketch_slot_open: None,
+            reference_sketch: ReferenceSketch::default(),
+            drum_step_pattern: DrumStepPattern::default(),
             generation_status: HelperGenerationStatus::Idle,
             generation_candidates: Vec::new(),
+            generation_candidate_models: Vec::new(),
+            comparison_failures: Vec::new(),
+            comparison_models: Vec::new(),
+            prompt_suggestions: Vec::new(),
+            prompt_lints: Vec::new(),
+            skip_prompt_lints: false,
+            duplicate_submission_of: None,
+            allow_duplicate_submission: false,
+            number_format: NumberFormat::from_env(),
+            generation_chords: Vec::new(),
+            generation_repairs: Vec::new(),
+            generation_validation_warnings: Vec::new(),
+            generation_confidence: Vec::new(),
+            candidate_explanations: CandidateExplanationCache::default(),
             selected_candidate_index: None,
             hidden_candidates: std::collections::HashSet::new(),
+            candidate_sort: None,
+            candidate_top_half_only: false,
+            candidate_list_scroll_handle: UniformListScrollHandle::new(),
+            candidate_list_focus: cx
+                .focus_handle()
+                .tab_index(CANDIDATE_LIST_TAB_INDEX)
+                .tab_stop(true),
+            track_list_focus: cx
+                .focus_handle()
+                .tab_index(TRACK_LIST_TAB_INDEX)
+                .tab_stop(true),
+            focused_track_row: None,
             validation_error: None,
-            input_track_error: live_input_error,
+            constraints_error: None,
+            input_track_error: live_input_error
+                .or(layout_error)
+                .or(preset_error)
+                .or(recent_files_error),
+            settings_channel_mapping_error: None,
             midi_slot_errors: Vec::new(),
             startup_notice: backend.startup_notice,
+            audit_log: backend.audit_log,
+            audit_log_notice: None,
+            provider_error_budget: backend.error_budget,
+            provider_demotions: Vec::new(),
             _update_poll_task: Task::ready(()),
             _live_capture_poll_task: Task::ready(()),
+            _reference_watch_task: Task::ready(()),
+            reference_file_watcher: ReferenceFileWatcher::new(),
+            reference_reload_notice: None,
+            track_undo: None,
+            _track_undo_expiry_task: Task::ready(()),
             _midi_file_picker_task: Task::ready(()),
+            _take_save_task: Task::ready(()),
+            _arrangement_export_task: Task::ready(()),
+            _history_export_task: Task::ready(()),
+            _stem_export_task: Task::ready(()),
+            _request_import_task: Task::ready(()),
+            _batch_prompts_task: Task::ready(()),
+            onboarding_marker,
+            env_api_key_configured,
+            onboarding_checks: None,
+            onboarding_check_running: false,
+            onboarding_error: None,
+            _onboarding_check_task: Task::ready(()),
         };
+        this.submission_model
+            .set_tick_resolution(this.settings_ui_state.tick_resolution());
         if let Err(error) = this.sync_midi_input_router_config() {
             this.input_track_error = Some(error);
         }
+        // The plugin keeps its thru mask across helper restarts; reset it to match this session.
+        this.publish_midi_thru_channels();
         this.sync_dropdowns(window, cx);
         this.sync_settings_inputs_from_draft(window, cx);
         this.start_live_capture_polling(window, cx);
+        this.start_reference_file_watching(window, cx);
+        // The window closes at once; the in-flight job's grace period runs off the UI thread and
+        // the helper quits when it is over.
+        let view = cx.entity().downgrade();
+        window.on_window_should_close(cx, move |_window, cx| {
+            let Some(view) = view.upgrade() else {
+                cx.quit();
+                return true;
+            };
+            let job_manager = view.update(cx, |this, _| this.begin_close());
+            let executor = cx.background_executor().clone();
+            cx.spawn(async move |cx| {
+                executor
+                    .spawn(async move { job_manager.shutdown(HELPER_CLOSE_JOB_GRACE) })
+                    .await;
+                let _ = view.update(cx, |this, _| this.finish_close());
+                let _ = cx.update(|cx| cx.quit());
+            })
+            .detach();
+            true
+        });
         this
     }
 
@@ -347,7 +830,10 @@ impl SonantMainWindow {
         _window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        if matches!(event, InputEvent::Change) && self.validation_error.take().is_some() {
+        if matches!(event, InputEvent::Change)
+            && (self.validation_error.take().is_some() || !self.prompt_lints.is_empty())
+        {
+            self.prompt_lints.clear();
             cx.notify();
         }
     }
@@ -436,7 +922,10 @@ impl SonantMainWindow {
             state.set_selected_value(&mode_label, window, cx);
         });
 
-        let model_id = self.settings_ui_state.saved().default_model.as_str();
+        let model_id = self.project_model.as_ref().map_or(
+            self.settings_ui_state.saved().default_model.as_str(),
+            |model| model.model.as_str(),
+        );
         let model_label = Self::ai_model_dropdown_items()
             .into_iter()
             .find(|item| *item == model_id);
@@ -474,7 +963,7 @@ impl SonantMainWindow {
 
     fn reconcile_bpm_input_with_model(&mut self, window: &mut Window, cx: &mut Context<Self>) {
         let raw = self.bpm_input.read(cx).value().to_string();
-        match parse_bpm_input_value(&raw) {
+        match parse_bpm_input_value(&raw, self.number_format) {
             Some(next_bpm) => {
                 if self.submission_model.bpm() != next_bpm {
                     self.submission_model.set_bpm(next_bpm);
@@ -512,16 +1001,9 @@ impl SonantMainWindow {
         let Some(selected) = selected.as_deref() else {
             return;
         };
-        let provider = if selected == DEFAULT_ANTHROPIC_MODEL {
-            "anthropic"
-        } else {
-            "openai_compatible"
-        };
-        let model_ref = ModelRef {
-            provider: provider.to_string(),
-            model: selected.to_string(),
-        };
-        self.submission_model.set_model(model_ref);
+        self.submission_model
+            .set_model(Self::model_ref_for_dropdown_item(selected));
+        self.active_sampling_profile = None;
         self.settings_ui_state
             .update_draft_field(SettingsField::DefaultModel, selected);
         cx.notify();
@@ -572,7 +1054,7 @@ impl SonantMainWindow {
         cx: &mut Context<Self>,
     ) {
         let raw = self.bpm_input.read(cx).value().to_string();
-        let next_bpm = parse_bpm_input_value(&raw);
+        let next_bpm = parse_bpm_input_value(&raw, self.number_format);
 
         match event {
             InputEvent::Change => {
@@ -596,6 +1078,23 @@ impl SonantMainWindow {
         }
     }
 
+    fn on_tap_tempo_clicked(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(bpm) = self.tap_tempo.tap(Instant::now()) {
+            self.submission_model.set_bpm(bpm);
+            self.sync_bpm_input_from_model(window, cx);
+        }
+        cx.notify();
+    }
+
+    /// Steps from the tempo in the input, so a value still being typed is nudged, not lost.
+    fn on_bpm_nudged(&mut self, delta: i16, window: &mut Window, cx: &mut Context<Self>) {
+        self.reconcile_bpm_input_with_model(window, cx);
+        let bpm = self.submission_model.bpm().saturating_add_signed(delta);
+        self.submission_model.set_bpm(bpm);
+        self.sync_bpm_input_from_model(window, cx);
+        cx.notify();
+    }
+
     fn on_complexity_slider_event(
         &mut self,
         _state: &Entity<SliderState>,
@@ -626,9 +1125,79 @@ impl SonantMainWindow {
         }
     }
 
+    fn on_syncopation_slider_event(
+        &mut self,
+        _state: &Entity<SliderState>,
+        event: &SliderEvent,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let SliderEvent::Change(value) = event;
+        let syncopation = Self::slider_value_to_param_level(*value);
+        if self.submission_model.syncopation() != syncopation {
+            self.submission_model.set_syncopation(syncopation);
+            cx.notify();
+        }
+    }
+
+    /// Left/Down lower the level, Right/Up raise it, and Home/End jump to either end.
+    fn on_param_slider_key_down(
+        &mut self,
+        param: ParamSlider,
+        event: &KeyDownEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let (slider, level) = match param {
+            ParamSlider::Complexity => {
+                (&self.complexity_slider, self.submission_model.complexity())
+            }
+            ParamSlider::Density => (&self.density_slider, self.submission_model.density()),
+            ParamSlider::Syncopation => (
+                &self.syncopation_slider,
+                self.submission_model.syncopation(),
+            ),
+        };
+        let Some(level) = keyboard_param_level(level, event.keystroke.key.as_str()) else {
+            return;
+        };
+        slider.update(cx, |state, cx| state.set_value(level as f32, window, cx));
+        match param {
+            ParamSlider::Complexity => self.submission_model.set_complexity(level),
+            ParamSlider::Density => self.submission_model.set_density(level),
+            ParamSlider::Syncopation => self.submission_model.set_syncopation(level),
+        }
+        cx.stop_propagation();
+        cx.notify();
+    }
+
     fn on_open_settings_clicked(&mut self, window: &mut Window, cx: &mut Context<Self>) {
         self.settings_ui_state.open_settings();
         self.sync_settings_inputs_from_draft(window, cx);
+        self.auto_save_usage = self.settings_ui_state.auto_save_folder().and_then(|dir| {
+            let prefix = self
+                .settings_ui_state
+                .export_name_template()
+                .literal_prefix();
+            folder_usage(dir, &prefix).ok()
+        });
+        cx.notify();
+    }
+
+    fn on_open_last_audit_exchange_clicked(&mut self, cx: &mut Context<Self>) {
+        let Some(audit_log) = self.audit_log.as_ref() else {
+            return;
+        };
+        match audit_log.last_exchange_path() {
+            Some(path) => {
+                cx.open_with_system(path);
+                self.audit_log_notice = None;
+            }
+            None => {
+                self.audit_log_notice =
+                    Some("No provider exchange has been recorded this session yet.".to_string())
+            }
+        }
         cx.notify();
     }
 
@@ -637,774 +1206,2201 @@ impl SonantMainWindow {
         cx.notify();
     }
 
-    fn on_settings_tab_selected(&mut self, tab: SettingsTab, cx: &mut Context<Self>) {
-        if self.settings_ui_state.settings_tab != tab {
-            self.settings_ui_state.select_settings_tab(tab);
+    fn on_start_onboarding_clicked(&mut self, cx: &mut Context<Self>) {
+        self.sync_settings_state_from_inputs(cx);
+        self.settings_ui_state.start_onboarding();
+        self.onboarding_error = None;
+        cx.notify();
+    }
+
+    fn on_onboarding_next_clicked(&mut self, cx: &mut Context<Self>) {
+        self.sync_settings_state_from_inputs(cx);
+        if let Some(blocker) = self
+            .settings_ui_state
+            .onboarding_blocker(self.env_api_key_configured)
+        {
+            self.onboarding_error = Some(blocker.to_string());
             cx.notify();
+            return;
+        }
+        self.onboarding_error = None;
+        self.settings_ui_state.advance_onboarding();
+        if self.settings_ui_state.onboarding_step().is_none() {
+            self.finish_onboarding();
         }
+        cx.notify();
     }
 
-    fn on_discard_settings_clicked(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        self.settings_ui_state.discard_and_close();
+    fn on_onboarding_back_clicked(&mut self, cx: &mut Context<Self>) {
+        self.onboarding_error = None;
+        if self.settings_ui_state.back_onboarding() {
+            cx.notify();
+        }
+    }
+
+    fn on_onboarding_skip_clicked(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.settings_ui_state.skip_onboarding();
         self.sync_settings_inputs_from_draft(window, cx);
+        self.finish_onboarding();
         cx.notify();
     }
 
-    fn on_save_settings_clicked(&mut self, cx: &mut Context<Self>) {
-        self.sync_settings_state_from_inputs(cx);
-        self.settings_ui_state.save_and_close();
-        cx.notify();
+    fn finish_onboarding(&mut self) {
+        self.onboarding_error = None;
+        self.onboarding_check_running = false;
+        self._onboarding_check_task = Task::ready(());
+        if let Err(error) = self.onboarding_marker.mark_complete() {
+            self.startup_notice = Some(error.to_string());
+        }
     }
 
-    fn sync_settings_inputs_from_draft(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        let draft = self.settings_ui_state.draft().clone();
-        self.is_syncing_settings_inputs = true;
-        self.settings_anthropic_api_key_input
-            .update(cx, |input, cx| {
-                input.set_value(draft.anthropic_api_key.clone(), window, cx);
+    /// Checks the keys exported before launch and probes each configured provider, off the UI
+    /// thread.
+    fn on_onboarding_test_connection_clicked(&mut self, cx: &mut Context<Self>) {
+        self.onboarding_check_running = true;
+        self.onboarding_checks = None;
+        let executor = cx.background_executor().clone();
+        self._onboarding_check_task = cx.spawn(async move |view, cx| {
+            let checks = executor
+                .spawn(async move {
+                    let mut checks = vec![check_api_keys(|name| std::env::var(name).ok())];
+                    checks.extend(check_provider_reachability(ONBOARDING_PROBE_TIMEOUT));
+                    checks
+                })
+                .await;
+            let _ = view.update(cx, |view, cx| {
+                view.onboarding_checks = Some(checks);
+                view.onboarding_check_running = false;
+                cx.notify();
             });
-        self.settings_openai_api_key_input.update(cx, |input, cx| {
-            input.set_value(draft.openai_api_key.clone(), window, cx);
-        });
-        self.settings_custom_base_url_input.update(cx, |input, cx| {
-            input.set_value(draft.custom_base_url.clone(), window, cx);
-        });
-        self.settings_default_model_input.update(cx, |input, cx| {
-            input.set_value(draft.default_model.clone(), window, cx);
-        });
-        self.settings_context_window_input.update(cx, |input, cx| {
-            input.set_value(draft.context_window.clone(), window, cx);
         });
-        self.is_syncing_settings_inputs = false;
+        cx.notify();
     }
 
-    fn sync_settings_draft_field_from_input(
+    /// Adds a melody track when there is none and points it at `source`: files open the picker,
+    /// live input arms recording on the track's channel.
+    fn on_onboarding_reference_clicked(
         &mut self,
-        state: &Entity<InputState>,
-        cx: &App,
-    ) -> bool {
-        let field = if state == &self.settings_anthropic_api_key_input {
-            Some(SettingsField::AnthropicApiKey)
-        } else if state == &self.settings_openai_api_key_input {
-            Some(SettingsField::OpenAiApiKey)
-        } else if state == &self.settings_custom_base_url_input {
-            Some(SettingsField::CustomBaseUrl)
-        } else if state == &self.settings_default_model_input {
-            Some(SettingsField::DefaultModel)
-        } else if state == &self.settings_context_window_input {
-            Some(SettingsField::ContextWindow)
-        } else {
-            None
-        };
-
-        let Some(field) = field else {
-            return false;
+        source: ReferenceSource,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let slot = ReferenceSlot::Melody;
+        if !self.visible_slot_rows.contains(&slot) {
+            self.on_add_track_slot_selected(slot, cx);
+        }
+        self.on_reference_source_selected(slot, source, cx);
+        let Some(row_index) = self.visible_slot_rows.iter().position(|row| *row == slot) else {
+            return;
         };
-
-        let value = state.read(cx).value().to_string();
-        self.settings_ui_state.update_draft_field(field, value)
-    }
-
-    fn collect_settings_draft_from_inputs(&self, cx: &App) -> SettingsDraftState {
-        SettingsDraftState {
-            anthropic_api_key: self
-                .settings_anthropic_api_key_input
-                .read(cx)
-                .value()
-                .to_string(),
-            openai_api_key: self
-                .settings_openai_api_key_input
-                .read(cx)
-                .value()
-                .to_string(),
-            custom_base_url: self
-                .settings_custom_base_url_input
-                .read(cx)
-                .value()
-                .to_string(),
-            default_model: self
-                .settings_default_model_input
-                .read(cx)
-                .value()
-                .to_string(),
-            context_window: self
-                .settings_context_window_input
-                .read(cx)
-                .value()
-                .to_string(),
+        match source {
+            ReferenceSource::File => self.on_select_midi_file_clicked(slot, row_index, window, cx),
+            ReferenceSource::Live => {
+                if let Some(channel) = self.channel_mapping_for_slot(slot)
+                    && !self.recording_enabled_for_channel(channel)
+                {
+                    self.on_recording_channel_toggled(channel, cx);
+                }
+            }
         }
     }
 
-    fn sync_settings_state_from_inputs(&mut self, cx: &App) {
-        let draft = self.collect_settings_draft_from_inputs(cx);
-        self.settings_ui_state.update_draft(draft);
-    }
+    fn onboarding_view(
+        &self,
+        step: OnboardingStep,
+        theme: &SonantTheme,
+        cx: &mut Context<Self>,
+    ) -> AnyElement {
+        let colors = theme.colors;
+        let step_index = step.position();
+        let is_last_step = step_index + 1 == OnboardingStep::ALL.len();
 
-    fn on_generate_clicked(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        self.reconcile_bpm_input_with_model(window, cx);
-        self.validation_error = None;
+        let body =
+            match step {
+                OnboardingStep::ApiKey => div()
+                    .flex()
+                    .flex_col()
+                    .gap_2()
+                    .child(Label::new("Anthropic API Key"))
+                    .child(Input::new(&self.settings_anthropic_api_key_input).mask_toggle())
+                    .child(Label::new("OpenAI-Compatible API Key"))
+                    .child(Input::new(&self.settings_openai_api_key_input).mask_toggle())
+                    .child(Label::new("Remote Sonant Server URL"))
+                    .child(Input::new(&self.settings_remote_server_url_input))
+                    .child(Label::new("Remote Server Token"))
+                    .child(Input::new(&self.settings_remote_server_token_input).mask_toggle())
+                    .when(self.env_api_key_configured, |el| {
+                        el.child(
+                            div()
+                                .text_size(px(11.0))
+                                .text_color(colors.muted_foreground)
+                                .child("A provider key is already set in the environment."),
+                        )
+                    }),
+                OnboardingStep::Model => div()
+                    .flex()
+                    .flex_col()
+                    .gap_2()
+                    .child(Label::new("Default Model"))
+                    .child(div().w_full().h(px(36.0)).child(
+                        Select::new(&self.ai_model_dropdown).placeholder("Select AI model"),
+                    )),
+                OnboardingStep::TestConnection => div()
+                    .flex()
+                    .flex_col()
+                    .gap_2()
+                    .child(
+                        div().child(
+                            Button::new("onboarding-test-connection")
+                                .label("Test Connection")
+                                .loading(self.onboarding_check_running && !theme.reduced_motion)
+                                .disabled(self.onboarding_check_running)
+                                .on_click(cx.listener(|this, _, _window, cx| {
+                                    this.on_onboarding_test_connection_clicked(cx)
+                                })),
+                        ),
+                    )
+                    .when(self.onboarding_check_running, |el| {
+                        el.child(
+                            div()
+                                .text_color(colors.muted_foreground)
+                                .child("Contacting providers..."),
+                        )
+                    })
+                    .children(self.onboarding_checks.iter().flatten().map(|check| {
+                        let color = match check.status {
+                            DiagnosticStatus::Ok => colors.success_foreground,
+                            DiagnosticStatus::Warning => colors.warning_foreground,
+                            DiagnosticStatus::Error => colors.error_foreground,
+                        };
+                        div()
+                            .flex()
+                            .flex_col()
+                            .child(
+                                div()
+                                    .text_color(color)
+                                    .child(format!("{}: {}", check.name, check.detail)),
+                            )
+                            .children(check.hint.as_ref().map(|hint| {
+                                div()
+                                    .text_size(px(11.0))
+                                    .text_color(colors.muted_foreground)
+                                    .child(hint.clone())
+                            }))
+                    })),
+                OnboardingStep::FirstReference => {
+                    let reference_count = self.collect_generation_references().len();
+                    div()
+                        .flex()
+                        .flex_col()
+                        .gap_2()
+                        .child(
+                            div()
+                                .flex()
+                                .items_center()
+                                .gap_2()
+                                .child(
+                                    Button::new("onboarding-load-reference")
+                                        .label("Load MIDI File")
+                                        .on_click(cx.listener(|this, _, window, cx| {
+                                            this.on_onboarding_reference_clicked(
+                                                ReferenceSource::File,
+                                                window,
+                                                cx,
+                                            )
+                                        })),
+                                )
+                                .child(
+                                    Button::new("onboarding-record-reference")
+                                        .label("Record Live Input")
+                                        .on_click(cx.listener(|this, _, window, cx| {
+                                            this.on_onboarding_reference_clicked(
+                                                ReferenceSource::Live,
+                                                window,
+                                                cx,
+                                            )
+                                        })),
+                                ),
+                        )
+                        .child(div().text_color(colors.muted_foreground).child(
+                            match reference_count {
+                                0 => "No reference yet. You can also add one later.".to_string(),
+                                1 => "1 reference is ready.".to_string(),
+                                count => format!("{count} references are ready."),
+                            },
+                        ))
+                        .children(self.input_track_error.as_ref().map(|message| {
+                            div()
+                                .text_color(colors.error_foreground)
+                                .child(message.clone())
+                        }))
+                }
+            };
 
-        let references = self.collect_generation_references();
-        if !mode_reference_requirement_satisfied(self.selected_generation_mode, &references) {
-            let message = mode_reference_requirement(self.selected_generation_mode)
-                .unmet_message
-                .unwrap_or("Selected generation mode requires additional MIDI references.")
-                .to_string();
-            self.generation_status = HelperGenerationStatus::Failed { message };
-            cx.notify();
-            return;
-        }
-
-        let prompt = self.prompt_input.read(cx).value().to_string();
-        let request = match self.submission_model.prepare_request(
-            self.selected_generation_mode,
-            prompt,
-            references,
-        ) {
-            Ok(request) => request,
-            Err(LlmError::Validation { .. }) => {
-                self.generation_status = HelperGenerationStatus::Idle;
-                self.validation_error = Some(PROMPT_VALIDATION_MESSAGE.to_string());
-                self.prompt_input
-                    .update(cx, |input, cx| input.focus(window, cx));
-                cx.notify();
-                return;
-            }
-            Err(error) => {
-                self.generation_status = HelperGenerationStatus::Failed {
-                    message: error.user_message(),
-                };
-                cx.notify();
-                return;
-            }
-        };
+        div()
+            .id("onboarding")
+            .flex()
+            .flex_col()
+            .gap(theme.spacing.section_gap)
+            .p(theme.spacing.window_padding)
+            .bg(colors.surface_background)
+            .text_color(colors.surface_foreground)
+            .child(Label::new("Welcome to Sonant"))
+            .child(
+                div().flex().items_center().gap_2().children(
+                    OnboardingStep::ALL
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, candidate)| {
+                            div()
+                                .text_size(px(11.0))
+                                .text_color(if candidate == step {
+                                    colors.accent_foreground
+                                } else {
+                                    colors.muted_foreground
+                                })
+                                .child(format!("{}. {}", index + 1, candidate.label()))
+                        }),
+                ),
+            )
+            .child(
+                div()
+                    .p(theme.spacing.panel_padding)
+                    .rounded(theme.radius.panel)
+                    .border_1()
+                    .border_color(colors.panel_border)
+                    .bg(colors.panel_background)
+                    .child(body),
+            )
+            .children(self.onboarding_error.as_ref().map(|message| {
+                div()
+                    .text_color(colors.error_foreground)
+                    .child(message.clone())
+            }))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .gap_2()
+                    .child(Button::new("onboarding-skip").label("Skip Setup").on_click(
+                        cx.listener(|this, _, window, cx| {
+                            this.on_onboarding_skip_clicked(window, cx)
+                        }),
+                    ))
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .gap_2()
+                            .child(
+                                Button::new("onboarding-back")
+                                    .label("Back")
+                                    .disabled(step_index == 0)
+                                    .on_click(cx.listener(|this, _, _window, cx| {
+                                        this.on_onboarding_back_clicked(cx)
+                                    })),
+                            )
+                            .child(
+                                Button::new("onboarding-next")
+                                    .primary()
+                                    .label(if is_last_step { "Finish" } else { "Next" })
+                                    .on_click(cx.listener(|this, _, _window, cx| {
+                                        this.on_onboarding_next_clicked(cx)
+                                    })),
+                            ),
+                    ),
+            )
+            .into_any_element()
+    }
 
-        // `prepare_request` only validates prompt text; run full contract validation here.
-        if let Err(error) = request.validate() {
-            self.generation_status = HelperGenerationStatus::Failed {
-                message: error.user_message(),
-            };
-            self.upsert_midi_slot_error(MidiSlotErrorState::non_retryable(
-                ReferenceSlot::Melody,
-                0,
-                error.user_message(),
-            ));
+    fn on_settings_tab_selected(&mut self, tab: SettingsTab, cx: &mut Context<Self>) {
+        if self.settings_ui_state.settings_tab != tab {
+            self.settings_ui_state.select_settings_tab(tab);
             cx.notify();
-            return;
-        }
-
-        self.generation_status = HelperGenerationStatus::Submitting {
-            request_id: request.request_id.clone(),
-        };
-
-        log_generation_request_submission(&request);
-
-        if let Err(error) = self.generation_job_manager.submit_generate(request) {
-            self.generation_status = HelperGenerationStatus::Failed {
-                message: error.user_message(),
-            };
-        } else {
-            self.start_update_polling(window, cx);
         }
+    }
 
+    fn on_discard_settings_clicked(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.settings_ui_state.discard_and_close();
+        self.settings_channel_mapping_error = None;
+        self.sync_settings_inputs_from_draft(window, cx);
         cx.notify();
     }
 
-    fn on_generation_mode_selected(&mut self, mode: GenerationMode, cx: &mut Context<Self>) {
-        if self.selected_generation_mode != mode {
-            self.selected_generation_mode = mode;
-            cx.notify();
+    fn on_save_settings_clicked(&mut self, cx: &mut Context<Self>) {
+        self.sync_settings_state_from_inputs(cx);
+        self.settings_ui_state
+            .normalize_numeric_draft_fields(self.number_format);
+        let mappings_changed = self
+            .settings_ui_state
+            .is_field_dirty(SettingsField::DefaultChannelMappings);
+        let auto_save_limit_changed = self
+            .settings_ui_state
+            .is_field_dirty(SettingsField::AutoSaveFolder)
+            || self
+                .settings_ui_state
+                .is_field_dirty(SettingsField::AutoSaveQuota)
+            || self
+                .settings_ui_state
+                .is_field_dirty(SettingsField::ExportNameTemplate);
+        self.settings_ui_state.save_and_close();
+        if auto_save_limit_changed {
+            self.enforce_auto_save_quota();
         }
+        self.settings_channel_mapping_error = None;
+        self.submission_model
+            .set_anonymize_references(self.settings_ui_state.saved().anonymize_references);
+        self.submission_model
+            .set_validation(self.settings_ui_state.saved().candidate_validation);
+        self.submission_model
+            .set_tick_resolution(self.settings_ui_state.tick_resolution());
+        if mappings_changed {
+            self.apply_default_channel_mappings();
+        }
+        let saved = self.settings_ui_state.saved();
+        apply_theme(
+            SonantTheme::for_preferences(
+                saved.high_contrast,
+                saved.reduced_motion,
+                saved.slot_colors,
+                self.os_display,
+            ),
+            cx,
+        );
+        cx.notify();
     }
 
-    fn section_label(text: &str, colors: ThemeColors) -> impl IntoElement {
-        div()
-            .text_size(px(12.0))
-            .font_weight(gpui::FontWeight::BOLD)
-            .text_color(colors.muted_foreground)
-            .child(text.to_uppercase())
-    }
-
-    fn section_label_with_info(text: &str, colors: ThemeColors) -> impl IntoElement {
-        div()
-            .flex()
-            .items_center()
-            .justify_between()
-            .child(Self::section_label(text, colors))
-            .child(
-                div()
-                    .text_size(px(14.0))
-                    .text_color(colors.muted_foreground)
-                    .cursor_pointer()
-                    .hover(|style| style.text_color(colors.primary))
-                    .child("ⓘ"),
-            )
+    fn on_default_channel_mapping_selected(
+        &mut self,
+        slot: ReferenceSlot,
+        channel: u8,
+        cx: &mut Context<Self>,
+    ) {
+        match self
+            .settings_ui_state
+            .update_draft_channel_mapping(ChannelMapping { slot, channel })
+        {
+            Ok(_) => self.settings_channel_mapping_error = None,
+            Err(error) => self.settings_channel_mapping_error = Some(error.to_string()),
+        }
+        cx.notify();
     }
 
-    fn piano_roll_is_black_key(midi_note: i16) -> bool {
-        matches!(midi_note.rem_euclid(12), 1 | 3 | 6 | 8 | 10)
+    fn on_export_program_cycled(&mut self, slot: ReferenceSlot, cx: &mut Context<Self>) {
+        let current = program_for_slot(&self.settings_ui_state.draft().export_programs, slot);
+        if self
+            .settings_ui_state
+            .update_draft_export_program(slot, next_export_program(current))
+        {
+            cx.notify();
+        }
     }
 
-    fn piano_roll_note_label(midi_note: i16) -> Option<String> {
-        let pitch_class = midi_note.rem_euclid(12);
-        if !matches!(pitch_class, 0 | 5) {
-            return None;
+    fn on_auto_save_quota_cycled(&mut self, cx: &mut Context<Self>) {
+        let next = self.settings_ui_state.draft().auto_save_quota.next();
+        if self.settings_ui_state.update_draft_auto_save_quota(next) {
+            cx.notify();
         }
-
-        let octave = (midi_note / 12) - 1;
-        let note_name = if pitch_class == 0 { "C" } else { "F" };
-        Some(format!("{note_name}{octave}"))
     }
 
-    fn generation_mode_output_slot(mode: GenerationMode) -> ReferenceSlot {
-        match mode {
-            GenerationMode::Melody => ReferenceSlot::Melody,
-            GenerationMode::ChordProgression => ReferenceSlot::ChordProgression,
-            GenerationMode::DrumPattern => ReferenceSlot::DrumPattern,
-            GenerationMode::Bassline => ReferenceSlot::Bassline,
-            GenerationMode::CounterMelody => ReferenceSlot::CounterMelody,
-            GenerationMode::Harmony => ReferenceSlot::Harmony,
-            GenerationMode::Continuation => ReferenceSlot::ContinuationSeed,
+    fn on_apply_destination_cycled(&mut self, cx: &mut Context<Self>) {
+        let next = self.settings_ui_state.draft().apply_destination.next();
+        if self.settings_ui_state.update_draft_apply_destination(next) {
+            cx.notify();
         }
     }
 
-    fn slot_glow_color(colors: ThemeColors, slot: ReferenceSlot) -> Hsla {
-        match slot {
-            ReferenceSlot::Melody => colors.glow_purple,
-            ReferenceSlot::ChordProgression => colors.glow_blue,
-            ReferenceSlot::DrumPattern => colors.glow_green,
-            ReferenceSlot::Bassline => colors.glow_red,
-            ReferenceSlot::CounterMelody => colors.glow_orange,
-            ReferenceSlot::Harmony => colors.glow_cyan,
-            ReferenceSlot::ContinuationSeed => colors.glow_pink,
+    fn on_anonymize_references_toggled(&mut self, cx: &mut Context<Self>) {
+        let enabled = !self.settings_ui_state.draft().anonymize_references;
+        if self
+            .settings_ui_state
+            .update_draft_anonymize_references(enabled)
+        {
+            cx.notify();
         }
     }
 
-    fn piano_roll_beat_label(beat_index: usize) -> String {
-        let bar = (beat_index / PIANO_ROLL_BEATS_PER_BAR) + 1;
-        let beat = (beat_index % PIANO_ROLL_BEATS_PER_BAR) + 1;
-        format!("{bar}.{beat}")
+    fn on_candidate_validation_cycled(&mut self, cx: &mut Context<Self>) {
+        let next = match self.settings_ui_state.draft().candidate_validation {
+            None => Some(ValidationStrictness::Lenient),
+            Some(ValidationStrictness::Lenient) => Some(ValidationStrictness::Strict),
+            Some(ValidationStrictness::Strict) => None,
+        };
+        if self
+            .settings_ui_state
+            .update_draft_candidate_validation(next)
+        {
+            cx.notify();
+        }
     }
 
-    fn piano_roll_playhead_x(playhead_ppq: f64) -> f32 {
-        let grid_width = PIANO_ROLL_BEAT_COLUMNS as f32 * PIANO_ROLL_BEAT_WIDTH;
-        let max_x = (grid_width - PIANO_ROLL_PLAYHEAD_WIDTH).max(0.0);
-        (playhead_ppq.max(0.0) as f32 * PIANO_ROLL_BEAT_WIDTH).clamp(0.0, max_x)
+    fn on_display_preference_cycled(&mut self, field: SettingsField, cx: &mut Context<Self>) {
+        let draft = self.settings_ui_state.draft();
+        let changed = match field {
+            SettingsField::HighContrast => {
+                let next = draft.high_contrast.next();
+                self.settings_ui_state.update_draft_high_contrast(next)
+            }
+            SettingsField::ReducedMotion => {
+                let next = draft.reduced_motion.next();
+                self.settings_ui_state.update_draft_reduced_motion(next)
+            }
+            _ => false,
+        };
+        if changed {
+            cx.notify();
+        }
     }
 
-    fn parse_decimal_after_marker(text: &str, marker: &str) -> Option<u32> {
-        let start = text.find(marker)? + marker.len();
-        let tail = &text[start..];
-        let digits: String = tail.chars().take_while(|ch| ch.is_ascii_digit()).collect();
-        if digits.is_empty() {
-            None
-        } else {
-            digits.parse::<u32>().ok()
+    fn on_slot_color_cycled(&mut self, slot: ReferenceSlot, cx: &mut Context<Self>) {
+        let next = self.settings_ui_state.draft().slot_colors.get(slot).next();
+        if self.settings_ui_state.update_draft_slot_color(slot, next) {
+            cx.notify();
         }
     }
 
-    fn parse_hex_after_marker(text: &str, marker: &str) -> Option<u8> {
-        let start = text.find(marker)? + marker.len();
-        let tail = &text[start..];
-        let digits: String = tail
-            .chars()
-            .take_while(|ch| ch.is_ascii_hexdigit())
-            .collect();
-        if digits.is_empty() {
-            None
-        } else {
-            u8::from_str_radix(&digits, 16).ok()
-        }
+    fn on_reset_default_channel_mappings_clicked(&mut self, cx: &mut Context<Self>) {
+        self.settings_ui_state.reset_draft_channel_mappings();
+        self.settings_channel_mapping_error = None;
+        cx.notify();
     }
 
-    fn parse_midi_debug_field(text: &str, field: &str) -> Option<u8> {
-        for marker in [
-            format!("{field}: u4("),
-            format!("{field}: u7("),
-            format!("{field}: "),
-            format!("{field}="),
-        ] {
-            if let Some(value) = Self::parse_decimal_after_marker(text, &marker)
-                && let Ok(value) = u8::try_from(value)
-            {
-                return Some(value);
+    // Slots that are already live keep their channel so a saved settings change never
+    // reroutes a performance in progress; the new defaults apply the next time they go live.
+    fn apply_default_channel_mappings(&mut self) {
+        let defaults = self
+            .settings_ui_state
+            .saved()
+            .default_channel_mappings
+            .clone();
+        for mapping in defaults {
+            if self.source_for_slot(mapping.slot) == ReferenceSource::Live {
+                continue;
+            }
+            if let Err(error) = self.input_track_model.set_channel_mapping(mapping) {
+                self.input_track_error = Some(error.to_string());
             }
         }
-        None
+        self.publish_input_track_layout();
     }
 
-    fn parse_reference_note_event(event: &MidiReferenceEvent) -> Option<ParsedReferenceNoteEvent> {
-        let payload = event.event.as_str();
-        if payload.starts_with("LiveMidi ") {
-            let status = Self::parse_hex_after_marker(payload, "status=0x")?;
-            let pitch = Self::parse_decimal_after_marker(payload, "data1=")
-                .and_then(|value| u8::try_from(value).ok())?;
-            let velocity = Self::parse_decimal_after_marker(payload, "data2=")
-                .and_then(|value| u8::try_from(value).ok())
-                .unwrap_or(0);
-            let channel = Self::parse_decimal_after_marker(payload, "channel=")
-                .and_then(|value| u8::try_from(value).ok())
-                .unwrap_or((status & 0x0F) + 1)
-                .clamp(1, 16);
-
-            let kind = match status & 0xF0 {
-                0x90 if velocity > 0 => ParsedNoteEventKind::NoteOn,
-                0x80 | 0x90 => ParsedNoteEventKind::NoteOff,
-                _ => return None,
-            };
-
-            return Some(ParsedReferenceNoteEvent {
-                channel,
-                pitch,
-                kind,
+    fn sync_settings_inputs_from_draft(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let draft = self.settings_ui_state.draft().clone();
+        self.is_syncing_settings_inputs = true;
+        self.settings_anthropic_api_key_input
+            .update(cx, |input, cx| {
+                input.set_value(draft.anthropic_api_key.clone(), window, cx);
             });
-        }
-
-        if !payload.contains("NoteOn") && !payload.contains("NoteOff") {
-            return None;
-        }
-
-        let pitch = Self::parse_midi_debug_field(payload, "key")?;
-        let raw_channel = Self::parse_midi_debug_field(payload, "channel").unwrap_or(0);
-        let channel = raw_channel.saturating_add(1).clamp(1, 16);
+        self.settings_openai_api_key_input.update(cx, |input, cx| {
+            input.set_value(draft.openai_api_key.clone(), window, cx);
+        });
+        self.settings_custom_base_url_input.update(cx, |input, cx| {
+            input.set_value(draft.custom_base_url.clone(), window, cx);
+        });
+        self.settings_remote_server_url_input
+            .update(cx, |input, cx| {
+                input.set_value(draft.remote_server_url.clone(), window, cx);
+            });
+        self.settings_remote_server_token_input
+            .update(cx, |input, cx| {
+                input.set_value(draft.remote_server_token.clone(), window, cx);
+            });
+        self.settings_default_model_input.update(cx, |input, cx| {
+            input.set_value(draft.default_model.clone(), window, cx);
+        });
+        self.settings_context_window_input.update(cx, |input, cx| {
+            input.set_value(draft.context_window.clone(), window, cx);
+        });
+        self.settings_tick_resolution_input.update(cx, |input, cx| {
+            input.set_value(draft.tick_resolution.clone(), window, cx);
+        });
+        self.settings_auto_save_folder_input
+            .update(cx, |input, cx| {
+                input.set_value(draft.auto_save_folder.clone(), window, cx);
+            });
+        self.settings_export_name_template_input
+            .update(cx, |input, cx| {
+                input.set_value(draft.export_name_template.clone(), window, cx);
+            });
+        self.is_syncing_settings_inputs = false;
+    }
 
-        let kind = if payload.contains("NoteOff") {
-            ParsedNoteEventKind::NoteOff
+    fn sync_settings_draft_field_from_input(
+        &mut self,
+        state: &Entity<InputState>,
+        cx: &App,
+    ) -> bool {
+        let field = if state == &self.settings_anthropic_api_key_input {
+            Some(SettingsField::AnthropicApiKey)
+        } else if state == &self.settings_openai_api_key_input {
+            Some(SettingsField::OpenAiApiKey)
+        } else if state == &self.settings_custom_base_url_input {
+            Some(SettingsField::CustomBaseUrl)
+        } else if state == &self.settings_remote_server_url_input {
+            Some(SettingsField::RemoteServerUrl)
+        } else if state == &self.settings_remote_server_token_input {
+            Some(SettingsField::RemoteServerToken)
+        } else if state == &self.settings_default_model_input {
+            Some(SettingsField::DefaultModel)
+        } else if state == &self.settings_context_window_input {
+            Some(SettingsField::ContextWindow)
+        } else if state == &self.settings_tick_resolution_input {
+            Some(SettingsField::TickResolution)
+        } else if state == &self.settings_auto_save_folder_input {
+            Some(SettingsField::AutoSaveFolder)
+        } else if state == &self.settings_export_name_template_input {
+            Some(SettingsField::ExportNameTemplate)
         } else {
-            let velocity = Self::parse_midi_debug_field(payload, "vel").unwrap_or(0);
-            if velocity > 0 {
-                ParsedNoteEventKind::NoteOn
-            } else {
-                ParsedNoteEventKind::NoteOff
-            }
+            None
         };
 
-        Some(ParsedReferenceNoteEvent {
-            channel,
-            pitch,
-            kind,
-        })
+        let Some(field) = field else {
+            return false;
+        };
+
+        let value = state.read(cx).value().to_string();
+        self.settings_ui_state.update_draft_field(field, value)
     }
 
-    fn collect_reference_generated_notes(reference: &MidiReferenceSummary) -> Vec<GeneratedNote> {
-        let mut notes = Vec::new();
-        let mut active: std::collections::HashMap<(u16, u8, u8), Vec<u32>> =
-            std::collections::HashMap::new();
+    fn collect_settings_draft_from_inputs(&self, cx: &App) -> SettingsDraftState {
+        SettingsDraftState {
+            anthropic_api_key: self
+                .settings_anthropic_api_key_input
+                .read(cx)
+                .value()
+                .to_string(),
+            openai_api_key: self
+                .settings_openai_api_key_input
+                .read(cx)
+                .value()
+                .to_string(),
+            custom_base_url: self
+                .settings_custom_base_url_input
+                .read(cx)
+                .value()
+                .to_string(),
+            remote_server_url: self
+                .settings_remote_server_url_input
+                .read(cx)
+                .value()
+                .to_string(),
+            remote_server_token: self
+                .settings_remote_server_token_input
+                .read(cx)
+                .value()
+                .to_string(),
+            default_model: self
+                .settings_default_model_input
+                .read(cx)
+                .value()
+                .to_string(),
+            context_window: self
+                .settings_context_window_input
+                .read(cx)
+                .value()
+                .to_string(),
+            tick_resolution: self
+                .settings_tick_resolution_input
+                .read(cx)
+                .value()
+                .to_string(),
+            auto_save_folder: self
+                .settings_auto_save_folder_input
+                .read(cx)
+                .value()
+                .to_string(),
+            export_name_template: self
+                .settings_export_name_template_input
+                .read(cx)
+                .value()
+                .to_string(),
+            default_channel_mappings: self
+                .settings_ui_state
+                .draft()
+                .default_channel_mappings
+                .clone(),
+            export_programs: self.settings_ui_state.draft().export_programs.clone(),
+            auto_save_quota: self.settings_ui_state.draft().auto_save_quota,
+            apply_destination: self.settings_ui_state.draft().apply_destination,
+            anonymize_references: self.settings_ui_state.draft().anonymize_references,
+            candidate_validation: self.settings_ui_state.draft().candidate_validation,
+            high_contrast: self.settings_ui_state.draft().high_contrast,
+            reduced_motion: self.settings_ui_state.draft().reduced_motion,
+            slot_colors: self.settings_ui_state.draft().slot_colors,
+        }
+    }
 
-        for event in &reference.events {
-            let Some(parsed) = Self::parse_reference_note_event(event) else {
-                continue;
-            };
-            let key = (event.track, parsed.channel, parsed.pitch);
+    fn sync_settings_state_from_inputs(&mut self, cx: &App) {
+        let draft = self.collect_settings_draft_from_inputs(cx);
+        self.settings_ui_state.update_draft(draft);
+    }
 
-            match parsed.kind {
-                ParsedNoteEventKind::NoteOn => {
-                    active.entry(key).or_default().push(event.absolute_tick);
-                }
-                ParsedNoteEventKind::NoteOff => {
-                    if let Some(starts) = active.get_mut(&key)
-                        && let Some(start_tick) = starts.pop()
-                    {
-                        notes.push(GeneratedNote {
-                            pitch: parsed.pitch,
-                            start_tick,
-                            duration_tick: event.absolute_tick.saturating_sub(start_tick).max(1),
-                            velocity: 100,
-                            channel: parsed.channel,
-                        });
-                        if starts.is_empty() {
-                            active.remove(&key);
-                        }
-                    }
-                }
+    fn on_generate_clicked(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(request) = self.prepare_generation_request(window, cx) else {
+            return;
+        };
+        self.prompt_lints = if std::mem::take(&mut self.skip_prompt_lints) {
+            Vec::new()
+        } else {
+            request.prompt_lints()
+        };
+        if !self.prompt_lints.is_empty() {
+            cx.notify();
+            return;
+        }
+
+        // While earlier requests wait for the provider, queue behind them to keep their order.
+        if !self.deferred_requests.is_empty() {
+            let request_id = request.request_id.clone();
+            let reason = "waiting for earlier deferred requests".to_string();
+            if self
+                .deferred_requests
+                .defer(request, reason.clone(), Instant::now())
+            {
+                self.generation_status = HelperGenerationStatus::Deferred {
+                    request_id,
+                    backlog: self.deferred_requests.len(),
+                    reason,
+                };
+            } else {
+                self.generation_status = HelperGenerationStatus::Failed {
+                    message: "The deferred request queue is full. Reconnect or discard it first."
+                        .to_string(),
+                };
             }
+            self.start_update_polling(window, cx);
+            cx.notify();
+            return;
         }
 
-        for ((_, channel, pitch), starts) in active {
-            for start_tick in starts {
-                notes.push(GeneratedNote {
-                    pitch,
-                    start_tick,
-                    duration_tick: PIANO_ROLL_FALLBACK_TICKS_PER_BEAT as u32,
-                    velocity: 80,
-                    channel,
-                });
+        let previous_status = std::mem::replace(
+            &mut self.generation_status,
+            HelperGenerationStatus::Submitting {
+                request_id: request.request_id.clone(),
+            },
+        );
+
+        log_generation_request_submission(&request);
+
+        let history_request = request.clone();
+        let submission = if std::mem::take(&mut self.allow_duplicate_submission) {
+            self.generation_job_manager
+                .submit_generate(request)
+                .map(|job_id| JobSubmission::Started { job_id })
+        } else {
+            self.generation_job_manager
+                .submit_generate_deduplicated(request)
+        };
+        match submission {
+            Ok(JobSubmission::Started { .. }) => {
+                self.duplicate_submission_of = None;
+                self.pending_history_requests
+                    .insert(history_request.request_id.clone(), history_request);
+                self.start_update_polling(window, cx);
+            }
+            // The running job already answers this click, so its status stays on screen.
+            Ok(JobSubmission::Duplicate { request_id, .. }) => {
+                self.generation_status = previous_status;
+                self.duplicate_submission_of = Some(request_id);
+            }
+            Err(error) => {
+                self.duplicate_submission_of = None;
+                self.generation_status = HelperGenerationStatus::Failed {
+                    message: error.user_message(),
+                };
             }
         }
 
-        notes.sort_by_key(|note| (note.start_tick, note.pitch));
-        notes
+        cx.notify();
     }
 
-    fn estimate_ticks_per_beat(total_beats: usize, max_end_tick: u32) -> f32 {
-        const COMMON_TICKS_PER_BEAT: [f32; 5] = [96.0, 120.0, 240.0, 480.0, 960.0];
+    fn on_submit_duplicate_clicked(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.allow_duplicate_submission = true;
+        self.on_generate_clicked(window, cx);
+    }
 
-        if total_beats == 0 || max_end_tick == 0 {
-            return PIANO_ROLL_FALLBACK_TICKS_PER_BEAT;
-        }
+    /// Sends the current prompt to every model picked for comparison at once.
+    fn on_compare_models_clicked(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(base) = self.prepare_generation_request(window, cx) else {
+            return;
+        };
+        let requests = self
+            .comparison_models
+            .clone()
+            .into_iter()
+            .map(|model| self.submission_model.prepare_retry(&base, Some(model)))
+            .collect::<Vec<_>>();
 
-        let estimated = max_end_tick as f32 / total_beats as f32;
-        if !estimated.is_finite() || estimated < COMMON_TICKS_PER_BEAT[0] {
-            return PIANO_ROLL_FALLBACK_TICKS_PER_BEAT;
-        }
+        self.generation_status = HelperGenerationStatus::Submitting {
+            request_id: base.request_id.clone(),
+        };
+        requests.iter().for_each(log_generation_request_submission);
 
-        let mut nearest = COMMON_TICKS_PER_BEAT[0];
-        for ticks_per_beat in COMMON_TICKS_PER_BEAT {
-            if (estimated - ticks_per_beat).abs() < (estimated - nearest).abs() {
-                nearest = ticks_per_beat;
+        match self
+            .generation_job_manager
+            .submit_comparison(base.request_id, requests.clone())
+        {
+            Ok(_) => {
+                for request in requests {
+                    self.pending_history_requests
+                        .insert(request.request_id.clone(), request);
+                }
+                self.start_update_polling(window, cx);
+            }
+            Err(error) => {
+                self.generation_status = HelperGenerationStatus::Failed {
+                    message: error.user_message(),
+                };
             }
         }
-        nearest
-    }
-
-    fn candidate_ticks_per_beat(candidate: &GenerationCandidate) -> f32 {
-        let total_beats = usize::from(candidate.bars.max(1)) * PIANO_ROLL_BEATS_PER_BAR;
-        let max_end_tick = candidate
-            .notes
-            .iter()
-            .map(|note| note.start_tick.saturating_add(note.duration_tick))
-            .max()
-            .unwrap_or(0);
-        Self::estimate_ticks_per_beat(total_beats, max_end_tick)
+        cx.notify();
     }
 
-    fn reference_ticks_per_beat(reference: &MidiReferenceSummary, notes: &[GeneratedNote]) -> f32 {
-        let total_beats = usize::from(reference.bars.max(1)) * PIANO_ROLL_BEATS_PER_BAR;
-        let max_end_tick = notes
-            .iter()
-            .map(|note| note.start_tick.saturating_add(note.duration_tick))
-            .max()
-            .unwrap_or(0);
-        Self::estimate_ticks_per_beat(total_beats, max_end_tick)
+    fn on_generate_anyway_clicked(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.skip_prompt_lints = true;
+        self.on_generate_clicked(window, cx);
     }
 
-    fn piano_roll_note_rect(
-        note: &GeneratedNote,
-        ticks_per_beat: f32,
-        is_preview: bool,
-    ) -> Option<PianoRollNoteRect> {
-        let pitch = i16::from(note.pitch);
-        if !(PIANO_ROLL_BOTTOM_MIDI_NOTE..=PIANO_ROLL_TOP_MIDI_NOTE).contains(&pitch) {
-            return None;
-        }
-        if !ticks_per_beat.is_finite() || ticks_per_beat <= 0.0 {
-            return None;
+    /// Applies the quick fix of the lint at `index` to the parameter controls and drops it.
+    fn on_prompt_lint_fix_clicked(
+        &mut self,
+        index: usize,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if index >= self.prompt_lints.len() {
+            return;
         }
-
-        let grid_width = PIANO_ROLL_BEAT_COLUMNS as f32 * PIANO_ROLL_BEAT_WIDTH;
-        let start_beat = note.start_tick as f32 / ticks_per_beat;
-        let duration_beats = note.duration_tick as f32 / ticks_per_beat;
-        let x = start_beat * PIANO_ROLL_BEAT_WIDTH;
-        if x >= grid_width {
-            return None;
+        let Some(fix) = self.prompt_lints.remove(index).fix else {
+            return;
+        };
+        match fix {
+            PromptLintFix::SetKey { key, scale } => {
+                self.submission_model.set_key(&key);
+                self.submission_model.set_scale(&scale);
+            }
+            PromptLintFix::SetBpm(bpm) => self.submission_model.set_bpm(bpm),
+            PromptLintFix::SetMode(mode) => self.on_generation_mode_selected(mode, cx),
         }
+        self.sync_dropdowns(window, cx);
+        cx.notify();
+    }
 
-        let unclipped_width =
-            (duration_beats * PIANO_ROLL_BEAT_WIDTH).max(PIANO_ROLL_MIN_NOTE_WIDTH);
-        let width = unclipped_width.min(grid_width - x);
-        if width <= 0.0 {
-            return None;
+    fn prompt_lint_fix_label(fix: &PromptLintFix) -> String {
+        match fix {
+            PromptLintFix::SetKey { key, scale } => {
+                let scale = Self::scale_label_from_value(scale).unwrap_or(scale.as_str());
+                format!("Use {key} {scale}")
+            }
+            PromptLintFix::SetBpm(bpm) => format!("Set {bpm} BPM"),
+            PromptLintFix::SetMode(mode) => {
+                format!("Switch to {}", Self::generation_mode_label(*mode))
+            }
         }
+    }
 
-        let row_index = (PIANO_ROLL_TOP_MIDI_NOTE - pitch) as f32;
-        let y = row_index * PIANO_ROLL_ROW_HEIGHT + PIANO_ROLL_NOTE_VERTICAL_INSET;
-        let height = (PIANO_ROLL_ROW_HEIGHT - (PIANO_ROLL_NOTE_VERTICAL_INSET * 2.0)).max(1.0);
+    fn on_suggest_prompt_clicked(&mut self, cx: &mut Context<Self>) {
+        let references = self.collect_generation_references();
+        self.prompt_suggestions =
+            suggest_prompt_snippets(self.selected_generation_mode, &references);
+        cx.notify();
+    }
 
-        Some(PianoRollNoteRect {
-            x,
-            y,
-            width,
-            height,
-            is_preview,
-            color: None,
-        })
+    fn on_prompt_suggestion_clicked(
+        &mut self,
+        index: usize,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(suggestion) = self.prompt_suggestions.get(index) else {
+            return;
+        };
+        let prompt =
+            insert_prompt_snippet(&self.prompt_input.read(cx).value(), &suggestion.snippet);
+        self.prompt_input
+            .update(cx, |input, cx| input.set_value(prompt, window, cx));
+        cx.notify();
     }
 
-    fn visible_reference_slots(
-        visible_slot_rows: &[ReferenceSlot],
-        piano_roll_hidden_rows: &std::collections::HashSet<usize>,
-    ) -> std::collections::HashSet<ReferenceSlot> {
-        visible_slot_rows
+    fn on_comparison_model_toggled(&mut self, model: ModelRef, cx: &mut Context<Self>) {
+        if let Some(position) = self
+            .comparison_models
             .iter()
-            .copied()
-            .enumerate()
-            .filter_map(|(row_index, slot)| {
-                (!piano_roll_hidden_rows.contains(&row_index)).then_some(slot)
-            })
-            .collect()
+            .position(|picked| *picked == model)
+        {
+            self.comparison_models.remove(position);
+        } else if self.comparison_models.len() < MODEL_COMPARISON_MAX_MODELS {
+            self.comparison_models.push(model);
+        }
+        cx.notify();
     }
 
-    fn piano_roll_reference_note_rects(
-        references: &[MidiReferenceSummary],
-        visible_slot_rows: &[ReferenceSlot],
-        piano_roll_hidden_rows: &std::collections::HashSet<usize>,
-        colors: ThemeColors,
-    ) -> Vec<PianoRollNoteRect> {
-        let visible_slots =
-            Self::visible_reference_slots(visible_slot_rows, piano_roll_hidden_rows);
-        let mut note_rects = Vec::new();
+    /// Builds and validates a request from the current inputs, reporting problems in the UI.
+    fn prepare_generation_request(
+        &mut self,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Option<GenerationRequest> {
+        let prompt = self.prompt_input.read(cx).value().to_string();
+        self.prepare_generation_request_for_prompt(prompt, window, cx)
+    }
 
-        for reference in references {
-            if !visible_slots.contains(&reference.slot) {
-                continue;
+    /// Builds a request from the current references and params with `prompt` in place of the
+    /// prompt editor's text.
+    fn prepare_generation_request_for_prompt(
+        &mut self,
+        prompt: String,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Option<GenerationRequest> {
+        self.reconcile_bpm_input_with_model(window, cx);
+        self.validation_error = None;
+        self.constraints_error = None;
+        self.generation_failure_action = None;
+        self.last_failed_request = None;
+
+        let constraints =
+            match GenerationConstraints::parse(&self.constraints_input.read(cx).value()) {
+                Ok(constraints) => constraints,
+                Err(error) => {
+                    self.generation_status = HelperGenerationStatus::Idle;
+                    self.constraints_error = Some(error.user_message());
+                    self.constraints_input
+                        .update(cx, |input, cx| input.focus(window, cx));
+                    cx.notify();
+                    return None;
+                }
+            };
+
+        let references = self.collect_generation_references();
+        if !mode_reference_requirement_satisfied(self.selected_generation_mode, &references) {
+            let message = mode_reference_requirement(self.selected_generation_mode)
+                .unmet_message
+                .unwrap_or("Selected generation mode requires additional MIDI references.")
+                .to_string();
+            self.generation_status = HelperGenerationStatus::Failed { message };
+            cx.notify();
+            return None;
+        }
+
+        let daw_context = build_daw_context(
+            self.live_midi_capture.host_transport_context(),
+            self.live_midi_capture.host_track_name(),
+            &self.input_track_model,
+            &self.visible_slot_rows,
+            &references,
+        );
+        let mut request = match self.submission_model.prepare_request(
+            self.selected_generation_mode,
+            prompt,
+            references,
+        ) {
+            Ok(request) => request,
+            Err(LlmError::Validation { .. }) => {
+                self.generation_status = HelperGenerationStatus::Idle;
+                self.validation_error = Some(PROMPT_VALIDATION_MESSAGE.to_string());
+                self.prompt_input
+                    .update(cx, |input, cx| input.focus(window, cx));
+                cx.notify();
+                return None;
             }
+            Err(error) => {
+                self.generation_status = HelperGenerationStatus::Failed {
+                    message: error.user_message(),
+                };
+                cx.notify();
+                return None;
+            }
+        };
 
-            let slot_color = colors.slot_color(reference.slot);
-            let notes = Self::collect_reference_generated_notes(reference);
-            let ticks_per_beat = Self::reference_ticks_per_beat(reference, &notes);
-            note_rects.extend(notes.iter().filter_map(|note| {
-                let mut rect = Self::piano_roll_note_rect(note, ticks_per_beat, true)?;
-                rect.color = Some(slot_color);
-                Some(rect)
-            }));
+        request.daw_context = Some(DawContext {
+            key: Some(format!("{} {}", request.params.key, request.params.scale)),
+            ..daw_context
+        });
+        request.constraints = (!constraints.is_empty()).then_some(constraints);
+
+        // `prepare_request` only validates prompt text; run full contract validation here.
+        if let Err(error) = request.validate() {
+            self.generation_status = HelperGenerationStatus::Failed {
+                message: error.user_message(),
+            };
+            self.upsert_midi_slot_error(MidiSlotErrorState::non_retryable(
+                ReferenceSlot::Melody,
+                0,
+                error.user_message(),
+            ));
+            cx.notify();
+            return None;
         }
 
-        note_rects
+        Some(request)
     }
 
-    fn piano_roll_candidate_note_rects(
-        candidates: &[GenerationCandidate],
-        selected_candidate_index: Option<usize>,
-        hidden_candidates: &std::collections::HashSet<usize>,
-    ) -> Vec<PianoRollNoteRect> {
-        let mut note_rects = Vec::new();
-        for is_preview in [true, false] {
-            for (index, candidate) in candidates.iter().enumerate() {
-                if hidden_candidates.contains(&index) {
-                    continue;
+    fn on_import_request_clicked(
+        &mut self,
+        resubmit: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let receiver = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+            prompt: Some(REQUEST_IMPORT_PICKER_PROMPT.into()),
+        });
+
+        self._request_import_task = cx.spawn_in(window, async move |view, window| {
+            let Ok(result) = receiver.await else {
+                return;
+            };
+            let loaded = match result {
+                Ok(Some(paths)) => {
+                    let Some(path) = paths.into_iter().next() else {
+                        return;
+                    };
+                    load_generation_request(&path).map_err(|error| error.to_string())
                 }
-                let candidate_is_preview = selected_candidate_index != Some(index);
-                if candidate_is_preview != is_preview {
-                    continue;
+                Ok(None) => return,
+                Err(error) => Err(format!("Could not open the file dialog: {error}")),
+            };
+            let _ = view.update_in(window, |view, window, cx| match loaded {
+                Ok(request) => view.apply_imported_request(request, resubmit, window, cx),
+                Err(message) => {
+                    view.generation_status = HelperGenerationStatus::Failed { message };
+                    cx.notify();
                 }
+            });
+        });
+    }
+
+    fn apply_imported_request(
+        &mut self,
+        request: GenerationRequest,
+        resubmit: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.selected_generation_mode = request.mode;
+        self.submission_model.apply_request(&request);
+        self.active_sampling_profile = None;
+        self.validation_error = None;
+        self.generation_status = HelperGenerationStatus::Idle;
+
+        let prompt = request.prompt.clone();
+        self.prompt_input
+            .update(cx, |input, cx| input.set_value(prompt, window, cx));
+        let constraints = request
+            .constraints
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default();
+        self.constraints_input
+            .update(cx, |input, cx| input.set_value(constraints, window, cx));
+        self.constraints_error = None;
+        for (slider, level) in [
+            (&self.density_slider, self.submission_model.density()),
+            (&self.complexity_slider, self.submission_model.complexity()),
+            (
+                &self.syncopation_slider,
+                self.submission_model.syncopation(),
+            ),
+        ] {
+            slider.update(cx, |state, cx| state.set_value(level as f32, window, cx));
+        }
+        self.sync_dropdowns(window, cx);
+
+        if resubmit {
+            self.resubmit_imported_request(request, window, cx);
+        }
+        cx.notify();
+    }
 
-                let ticks_per_beat = Self::candidate_ticks_per_beat(candidate);
-                note_rects.extend(candidate.notes.iter().filter_map(|note| {
-                    Self::piano_roll_note_rect(note, ticks_per_beat, candidate_is_preview)
-                }));
+    /// Replays an imported request under a fresh request id, keeping its references and variation count.
+    fn resubmit_imported_request(
+        &mut self,
+        request: GenerationRequest,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let replay = self
+            .submission_model
+            .prepare_request(request.mode, request.prompt, request.references)
+            .map(|replay| GenerationRequest {
+                variation_count: request.variation_count,
+                constraints: request.constraints,
+                ..replay
+            })
+            .and_then(|replay| replay.validate().map(|_| replay));
+        match replay {
+            Ok(replay) => self.submit_replay_request(replay, window, cx),
+            Err(error) => {
+                self.generation_status = HelperGenerationStatus::Failed {
+                    message: error.user_message(),
+                };
             }
         }
+    }
 
-        note_rects
+    fn submit_replay_request(
+        &mut self,
+        replay: GenerationRequest,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        log_generation_request_submission(&replay);
+        let request_id = replay.request_id.clone();
+        match self.generation_job_manager.submit_generate(replay.clone()) {
+            Ok(_) => {
+                self.pending_history_requests
+                    .insert(request_id.clone(), replay);
+                self.generation_status = HelperGenerationStatus::Submitting { request_id };
+                self.start_update_polling(window, cx);
+            }
+            Err(error) => {
+                self.generation_status = HelperGenerationStatus::Failed {
+                    message: error.user_message(),
+                };
+            }
+        }
     }
 
-    fn piano_roll_note_rects(
-        references: &[MidiReferenceSummary],
-        visible_slot_rows: &[ReferenceSlot],
-        piano_roll_hidden_rows: &std::collections::HashSet<usize>,
-        candidates: &[GenerationCandidate],
-        selected_candidate_index: Option<usize>,
-        hidden_candidates: &std::collections::HashSet<usize>,
-        colors: ThemeColors,
-    ) -> Vec<PianoRollNoteRect> {
-        let mut note_rects = Self::piano_roll_reference_note_rects(
-            references,
-            visible_slot_rows,
-            piano_roll_hidden_rows,
-            colors,
-        );
-        note_rects.extend(Self::piano_roll_candidate_note_rects(
-            candidates,
-            selected_candidate_index,
-            hidden_candidates,
-        ));
-        note_rects
+    fn on_generation_mode_selected(&mut self, mode: GenerationMode, cx: &mut Context<Self>) {
+        if self.selected_generation_mode != mode {
+            self.selected_generation_mode = mode;
+            cx.notify();
+        }
     }
 
-    fn piano_roll_grid(
-        colors: ThemeColors,
-        corner_radius: Pixels,
-        vertical_scroll_handle: &ScrollHandle,
-        horizontal_scroll_handle: &ScrollHandle,
-        playhead_ppq: f64,
-        note_color: Hsla,
-        note_glow_color: Hsla,
-        note_rects: Vec<PianoRollNoteRect>,
-    ) -> impl IntoElement {
-        let grid_width = PIANO_ROLL_BEAT_COLUMNS as f32 * PIANO_ROLL_BEAT_WIDTH;
-        let grid_height = (PIANO_ROLL_TOP_MIDI_NOTE - PIANO_ROLL_BOTTOM_MIDI_NOTE + 1) as f32
-            * PIANO_ROLL_ROW_HEIGHT;
-        let playhead_x = Self::piano_roll_playhead_x(playhead_ppq);
-        let playhead_marker_x = (playhead_x - 4.0).max(0.0);
-        let midi_notes: Vec<i16> = (PIANO_ROLL_BOTTOM_MIDI_NOTE..=PIANO_ROLL_TOP_MIDI_NOTE)
-            .rev()
-            .collect();
-        let label_notes = midi_notes.clone();
+    fn section_label(text: &str, colors: ThemeColors) -> impl IntoElement {
+        div()
+            .text_size(px(12.0))
+            .font_weight(gpui::FontWeight::BOLD)
+            .text_color(colors.muted_foreground)
+            .child(text.to_uppercase())
+    }
 
+    fn section_label_with_info(text: &str, colors: ThemeColors) -> impl IntoElement {
         div()
-            .id("piano-roll-grid-frame")
-            .occlude()
-            .flex_1()
-            .rounded(corner_radius)
-            .bg(colors.surface_background)
-            .overflow_hidden()
+            .flex()
+            .items_center()
+            .justify_between()
+            .child(Self::section_label(text, colors))
             .child(
                 div()
-                    .id("piano-roll-grid-layout")
-                    .size_full()
-                    .flex()
-                    .child(
-                        div()
-                            .id("piano-roll-key-label-column")
-                            .w(px(PIANO_ROLL_KEY_LABEL_WIDTH))
-                            .h_full()
-                            .flex_none()
-                            .flex()
-                            .flex_col()
-                            .child(
-                                div()
-                                    .id("piano-roll-ruler-corner")
-                                    .h(px(PIANO_ROLL_RULER_HEIGHT))
-                                    .flex_none()
-                                    .border_b_1()
-                                    .border_color(colors.piano_roll_grid_line)
-                                    .bg(colors.panel_background),
-                            )
-                            .child(
-                                div()
-                                    .id("piano-roll-key-label-viewport")
-                                    .flex_1()
-                                    .track_scroll(vertical_scroll_handle)
-                                    .overflow_y_scroll()
-                                    .map(|mut this| {
-                                        this.style().restrict_scroll_to_axis = Some(true);
-                                        this
-                                    })
-                                    .child(
-                                        div()
-                                            .id("piano-roll-key-label-canvas")
-                                            .w(px(PIANO_ROLL_KEY_LABEL_WIDTH))
-                                            .h(px(grid_height))
-                                            .flex()
-                                            .flex_col()
-                                            .children(label_notes.into_iter().map(|midi_note| {
-                                                let note_label = Self::piano_roll_note_label(
-                                                    midi_note,
-                                                )
-                                                .unwrap_or_default();
-                                                let has_label = !note_label.is_empty();
+                    .text_size(px(14.0))
+                    .text_color(colors.muted_foreground)
+                    .cursor_pointer()
+                    .hover(|style| style.text_color(colors.primary))
+                    .child("ⓘ"),
+            )
+    }
 
-                                                div()
-                                                    .h(px(PIANO_ROLL_ROW_HEIGHT))
-                                                    .flex_none()
-                                                    .flex()
-                                                    .items_center()
-                                                    .border_b_1()
-                                                    .border_color(colors.piano_roll_grid_line)
-                                                    .bg(colors.panel_background)
-                                                    .pr(px(6.0))
-                                                    .justify_end()
-                                                    .text_size(px(10.0))
-                                                    .text_color(if has_label {
-                                                        colors.muted_foreground
-                                                    } else {
-                                                        colors.muted_foreground.opacity(0.35)
-                                                    })
-                                                    .child(note_label)
-                                            })),
-                                    ),
-                            ),
+    /// Floats `menu` over the window from where it sits in the layout, so it opens next to
+    /// its trigger. A press outside it or Esc closes it.
+    fn overlay_menu(&self, menu: Stateful<Div>, corner: Corner, cx: &Context<Self>) -> AnyElement {
+        deferred(
+            anchored().anchor(corner).snap_to_window().child(
+                menu.track_focus(&self.overlay_menu_focus)
+                    .occlude()
+                    .on_mouse_down_out(cx.listener(|this, event: &MouseDownEvent, _window, cx| {
+                        this.on_overlay_menu_dismissed(event, cx);
+                    }))
+                    .on_key_down(cx.listener(|this, event: &KeyDownEvent, _window, cx| {
+                        if event.keystroke.key == "escape" {
+                            this.close_overlay_menu(cx);
+                            cx.stop_propagation();
+                        }
+                    })),
+            ),
+        )
+        .into_any_element()
+    }
+
+    fn preset_menu(&self, theme: &SonantTheme, cx: &Context<Self>) -> Stateful<Div> {
+        let colors = theme.colors;
+        let radius = theme.radius;
+        let preset_names = self
+            .input_track_presets
+            .presets()
+            .iter()
+            .map(|preset| preset.name.clone())
+            .collect::<Vec<_>>();
+        div()
+            .id("input-track-preset-menu")
+            .w(px(260.0))
+            .rounded(radius.control)
+            .border_1()
+            .border_color(colors.panel_active_border)
+            .bg(colors.panel_background)
+            .overflow_hidden()
+            .child(
+                div()
+                    .px_3()
+                    .py(px(6.0))
+                    .border_b_1()
+                    .border_color(colors.panel_border)
+                    .text_size(px(10.0))
+                    .text_color(colors.muted_foreground)
+                    .font_weight(gpui::FontWeight::BOLD)
+                    .child("PRESETS"),
+            )
+            .children(preset_names.into_iter().enumerate().map(|(index, name)| {
+                let label = name.clone();
+                div()
+                    .id(("input-track-preset-option", index))
+                    .flex()
+                    .items_center()
+                    .h(px(32.0))
+                    .px_3()
+                    .bg(colors.panel_background)
+                    .cursor_pointer()
+                    .hover(|s| s.bg(colors.panel_active_background))
+                    .text_size(px(12.0))
+                    .text_color(colors.surface_foreground)
+                    .on_click(cx.listener(move |this, _, _window, cx| {
+                        this.on_input_track_preset_selected(&name, cx);
+                    }))
+                    .child(label)
+            }))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap_2()
+                    .p_2()
+                    .border_t_1()
+                    .border_color(colors.panel_border)
+                    .child(
+                        div()
+                            .flex_1()
+                            .h(px(28.0))
+                            .child(Input::new(&self.preset_name_input)),
+                    )
+                    .child(
+                        Button::new("save-input-track-preset")
+                            .label("Save current")
+                            .on_click(cx.listener(|this, _, _window, cx| {
+                                this.on_save_input_track_preset_clicked(cx);
+                            })),
+                    ),
+            )
+    }
+
+    /// Track types to add after `after_row`, or at the end of the list.
+    fn add_track_menu(
+        &self,
+        after_row: Option<usize>,
+        theme: &SonantTheme,
+        cx: &Context<Self>,
+    ) -> Stateful<Div> {
+        let colors = theme.colors;
+        let radius = theme.radius;
+        div()
+            .id("add-track-menu")
+            .w(px(240.0))
+            .rounded(radius.control)
+            .border_1()
+            .border_color(colors.panel_active_border)
+            .bg(colors.panel_background)
+            .overflow_hidden()
+            .child(
+                div()
+                    .px_3()
+                    .py(px(6.0))
+                    .border_b_1()
+                    .border_color(colors.panel_border)
+                    .text_size(px(10.0))
+                    .text_color(colors.muted_foreground)
+                    .font_weight(gpui::FontWeight::BOLD)
+                    .child("SELECT TYPE"),
+            )
+            .children(Self::reference_slots().iter().copied().map(|slot| {
+                let slot_color = colors.slot_color(slot);
+                let short_label = Self::slot_short_label(slot);
+                div()
+                    .id(("add-slot-option", Self::reference_slot_index(slot)))
+                    .flex()
+                    .items_center()
+                    .gap_2()
+                    .h(px(36.0))
+                    .px_2()
+                    .bg(colors.panel_background)
+                    .cursor_pointer()
+                    .hover(|s| s.bg(colors.panel_active_background))
+                    .on_click(cx.listener(move |this, _, _window, cx| {
+                        this.on_add_track_slot_selected(after_row, slot, cx);
+                    }))
+                    .child(
+                        div()
+                            .w(px(6.0))
+                            .h(px(20.0))
+                            .flex_none()
+                            .rounded(px(2.0))
+                            .bg(slot_color),
                     )
                     .child(
                         div()
-                            .id("piano-roll-main-grid-scroll")
                             .flex_1()
-                            .h_full()
-                            .relative()
-                            .track_scroll(horizontal_scroll_handle)
-                            .overflow_x_scroll()
-                            .map(|mut this| {
-                                this.style().restrict_scroll_to_axis = Some(true);
-                                this
+                            .text_size(px(12.0))
+                            .text_color(colors.surface_foreground)
+                            .child(Self::reference_slot_label(slot)),
+                    )
+                    .child(
+                        div()
+                            .px(px(6.0))
+                            .py(px(2.0))
+                            .rounded(px(4.0))
+                            .text_size(px(9.0))
+                            .text_color(slot_color)
+                            .font_weight(gpui::FontWeight::BOLD)
+                            .border_1()
+                            .border_color(slot_color)
+                            .child(short_label),
+                    )
+            }))
+    }
+
+    fn channel_menu(
+        &self,
+        slot: ReferenceSlot,
+        theme: &SonantTheme,
+        cx: &Context<Self>,
+    ) -> Stateful<Div> {
+        let colors = theme.colors;
+        let radius = theme.radius;
+        let current_channel = self.channel_mapping_for_slot(slot).unwrap_or(1);
+        div()
+            .id("channel-select-menu")
+            .w(px(200.0))
+            .rounded(radius.control)
+            .border_1()
+            .border_color(colors.panel_active_border)
+            .bg(colors.panel_background)
+            .max_h(px(320.0))
+            .overflow_y_scroll()
+            .child(
+                div()
+                    .px_3()
+                    .py(px(6.0))
+                    .border_b_1()
+                    .border_color(colors.panel_border)
+                    .text_size(px(10.0))
+                    .text_color(colors.muted_foreground)
+                    .font_weight(gpui::FontWeight::BOLD)
+                    .child("SELECT MIDI CHANNEL"),
+            )
+            .children((MIDI_CHANNEL_MIN..=MIDI_CHANNEL_MAX).map(|ch| {
+                let is_selected = ch == current_channel;
+                div()
+                    .id(("ch-option", ch as usize))
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .h(px(28.0))
+                    .px_3()
+                    .bg(if is_selected {
+                        colors.panel_active_background
+                    } else {
+                        colors.panel_background
+                    })
+                    .cursor_pointer()
+                    .hover(|s| s.bg(colors.panel_active_background))
+                    .on_click(cx.listener(move |this, _, _window, cx| {
+                        this.on_channel_selected(slot, ch, cx);
+                    }))
+                    .child(
+                        div()
+                            .text_size(px(11.0))
+                            .text_color(if is_selected {
+                                colors.surface_foreground
+                            } else {
+                                colors.muted_foreground
                             })
-                            .scrollbar_width(px(8.0))
-                            .horizontal_scrollbar(horizontal_scroll_handle)
+                            .font_weight(if is_selected {
+                                gpui::FontWeight::BOLD
+                            } else {
+                                gpui::FontWeight::NORMAL
+                            })
+                            .child(format!("Channel {ch}")),
+                    )
+                    .when(is_selected, |el| {
+                        el.child(
+                            div()
+                                .text_size(px(10.0))
+                                .text_color(colors.primary)
+                                .child("✓"),
+                        )
+                    })
+            }))
+    }
+
+    fn slot_type_menu(
+        &self,
+        row_index: usize,
+        slot: ReferenceSlot,
+        theme: &SonantTheme,
+        cx: &Context<Self>,
+    ) -> Stateful<Div> {
+        let colors = theme.colors;
+        let radius = theme.radius;
+        div()
+            .id("slot-type-select-menu")
+            .w(px(220.0))
+            .rounded(radius.control)
+            .border_1()
+            .border_color(colors.panel_active_border)
+            .bg(colors.panel_background)
+            .overflow_hidden()
+            .child(
+                div()
+                    .px_3()
+                    .py(px(6.0))
+                    .border_b_1()
+                    .border_color(colors.panel_border)
+                    .text_size(px(10.0))
+                    .text_color(colors.muted_foreground)
+                    .font_weight(gpui::FontWeight::BOLD)
+                    .child("SELECT REFERENCE TYPE"),
+            )
+            .children(Self::reference_slots().iter().copied().map(|slot_opt| {
+                let is_selected = slot_opt == slot;
+                let slot_color = colors.slot_color(slot_opt);
+                div()
+                    .id(("slot-type-option", slot_opt as usize))
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .h(px(28.0))
+                    .px_3()
+                    .bg(if is_selected {
+                        colors.panel_active_background
+                    } else {
+                        colors.panel_background
+                    })
+                    .cursor_pointer()
+                    .hover(|s| s.bg(colors.panel_active_background))
+                    .on_click(cx.listener(move |this, _, _window, cx| {
+                        this.on_slot_type_selected(row_index, slot_opt, cx);
+                    }))
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .gap_2()
+                            .child(div().w(px(4.0)).h(px(14.0)).rounded(px(2.0)).bg(slot_color))
                             .child(
                                 div()
-                                    .id("piano-roll-main-grid-canvas")
-                                    .w(px(grid_width))
-                                    .h_full()
-                                    .flex()
-                                    .flex_col()
-                                    .child(
-                                        div()
-                                            .id("piano-roll-ruler")
-                                            .h(px(PIANO_ROLL_RULER_HEIGHT))
-                                            .flex_none()
-                                            .flex()
-                                            .border_b_1()
-                                            .border_color(colors.piano_roll_grid_line)
-                                            .bg(colors.panel_background)
-                                            .children((0..PIANO_ROLL_BEAT_COLUMNS).map(
-                                                |beat_index| {
-                                                    let is_bar_start =
-                                                        beat_index % PIANO_ROLL_BEATS_PER_BAR == 0;
-                                                    div()
-                                                        .h_full()
-                                                        .w(px(PIANO_ROLL_BEAT_WIDTH))
-                                                        .flex_none()
-                                                        .border_l_1()
-                                                        .border_color(if is_bar_start {
-                                                            colors.panel_border
-                                                        } else {
-                                                            colors.piano_roll_grid_line
-                                                        })
-                                                        .pl(px(4.0))
-                                                        .pt(px(3.0))
-                                                        .text_size(px(9.0))
-                                                        .text_color(if is_bar_start {
-                                                            colors.accent_foreground
-                                                        } else {
-                                                            colors.muted_foreground.opacity(0.75)
-                                                        })
-                                                        .child(Self::piano_roll_beat_label(
-                                                            beat_index,
-                                                        ))
-                                                },
-                                            ))
-                                            .child(
-                                                div()
-                                                    .id("piano-roll-playhead-marker")
-                                                    .absolute()
-                                                    .top(px(1.0))
-                                                    .left(px(playhead_marker_x))
-                                                    .text_size(px(10.0))
-                                                    .text_color(colors.piano_roll_playhead)
-                                                    .child("▼"),
-                                            ),
-                                    )
-                                    .child(
-                                        div()
-                                            .id("piano-roll-beat-grid-viewport")
-                                            .flex_1()
-                                            .relative()
-                                            .track_scroll(vertical_scroll_handle)
-                                            .overflow_y_scroll()
-                                            .map(|mut this| {
-                                                this.style().restrict_scroll_to_axis = Some(true);
-                                                this
-                                            })
-                                            .scrollbar_width(px(8.0))
-                                            .vertical_scrollbar(vertical_scroll_handle)
-                                            .child(
-                                                div()
-                                                    .id("piano-roll-beat-grid-canvas")
-                                                    .w(px(grid_width))
-                                                    .h(px(grid_height))
-                                                    .children(midi_notes.into_iter().map(
-                                                        |midi_note| {
-                                                            div()
-                                                                .h(px(PIANO_ROLL_ROW_HEIGHT))
-                                                                .flex_none()
-                                                                .flex()
-                                                                .border_b_1()
-                                                                .border_color(
-                                                                    colors.piano_roll_grid_line,
-                                                                )
-                                                                .bg(if Self::piano_roll_is_black_key(
-                                                                    midi_note,
-                                                                ) {
-                                                                    colors
+                                    .text_size(px(11.0))
+                                    .text_color(if is_selected {
+                                        colors.surface_foreground
+                                    } else {
+                                        colors.muted_foreground
+                                    })
+                                    .font_weight(if is_selected {
+                                        gpui::FontWeight::BOLD
+                                    } else {
+                                        gpui::FontWeight::NORMAL
+                                    })
+                                    .child(Self::reference_slot_label(slot_opt)),
+                            ),
+                    )
+                    .when(is_selected, |el| {
+                        el.child(
+                            div()
+                                .text_size(px(10.0))
+                                .text_color(colors.primary)
+                                .child("✓"),
+                        )
+                    })
+            }))
+    }
+
+    fn recent_files_menu(
+        &self,
+        row_index: usize,
+        theme: &SonantTheme,
+        cx: &Context<Self>,
+    ) -> Stateful<Div> {
+        let colors = theme.colors;
+        let radius = theme.radius;
+        let recent_file_paths = self.recent_files.paths().to_vec();
+        div()
+            .id("recent-files-menu")
+            .w(px(320.0))
+            .rounded(radius.control)
+            .border_1()
+            .border_color(colors.panel_active_border)
+            .bg(colors.panel_background)
+            .overflow_hidden()
+            .child(
+                div()
+                    .px_3()
+                    .py(px(6.0))
+                    .border_b_1()
+                    .border_color(colors.panel_border)
+                    .text_size(px(10.0))
+                    .text_color(colors.muted_foreground)
+                    .font_weight(gpui::FontWeight::BOLD)
+                    .child("RECENT FILES"),
+            )
+            .when(recent_file_paths.is_empty(), |el| {
+                el.child(
+                    div()
+                        .px_3()
+                        .py(px(6.0))
+                        .text_size(px(11.0))
+                        .text_color(colors.muted_foreground)
+                        .child("No recently loaded files"),
+                )
+            })
+            .children(recent_file_paths.iter().enumerate().map(|(index, path)| {
+                let selected_path = path.clone();
+                let file_name = std::path::Path::new(path)
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .unwrap_or(path.as_str())
+                    .to_string();
+                div()
+                    .id(("recent-file-option", index))
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .gap_2()
+                    .h(px(28.0))
+                    .px_3()
+                    .bg(colors.panel_background)
+                    .cursor_pointer()
+                    .hover(|s| s.bg(colors.panel_active_background))
+                    .on_click(cx.listener(move |this, _, _window, cx| {
+                        this.on_recent_file_selected(row_index, selected_path.clone(), cx);
+                    }))
+                    .child(
+                        div()
+                            .flex_none()
+                            .text_size(px(11.0))
+                            .text_color(colors.surface_foreground)
+                            .child(file_name),
+                    )
+                    .child(
+                        div()
+                            .min_w(px(0.0))
+                            .overflow_hidden()
+                            .text_size(px(10.0))
+                            .text_color(colors.muted_foreground)
+                            .child(path.clone()),
+                    )
+            }))
+    }
+
+    fn piano_roll_is_black_key(midi_note: i16) -> bool {
+        matches!(midi_note.rem_euclid(12), 1 | 3 | 6 | 8 | 10)
+    }
+
+    fn piano_roll_note_label(midi_note: i16) -> Option<String> {
+        let pitch_class = midi_note.rem_euclid(12);
+        if !matches!(pitch_class, 0 | 5) {
+            return None;
+        }
+
+        let octave = (midi_note / 12) - 1;
+        let note_name = if pitch_class == 0 { "C" } else { "F" };
+        Some(format!("{note_name}{octave}"))
+    }
+
+    fn generation_mode_output_slot(mode: GenerationMode) -> ReferenceSlot {
+        match mode {
+            GenerationMode::Melody => ReferenceSlot::Melody,
+            GenerationMode::ChordProgression => ReferenceSlot::ChordProgression,
+            GenerationMode::DrumPattern => ReferenceSlot::DrumPattern,
+            GenerationMode::Bassline => ReferenceSlot::Bassline,
+            GenerationMode::CounterMelody => ReferenceSlot::CounterMelody,
+            GenerationMode::Harmony => ReferenceSlot::Harmony,
+            GenerationMode::Continuation => ReferenceSlot::ContinuationSeed,
+        }
+    }
+
+    fn slot_glow_color(colors: ThemeColors, slot: ReferenceSlot) -> Hsla {
+        match slot {
+            ReferenceSlot::Melody => colors.glow_purple,
+            ReferenceSlot::ChordProgression => colors.glow_blue,
+            ReferenceSlot::DrumPattern => colors.glow_green,
+            ReferenceSlot::Bassline => colors.glow_red,
+            ReferenceSlot::CounterMelody => colors.glow_orange,
+            ReferenceSlot::Harmony => colors.glow_cyan,
+            ReferenceSlot::ContinuationSeed => colors.glow_pink,
+        }
+    }
+
+    fn piano_roll_beat_label(beat_index: usize) -> String {
+        let bar = (beat_index / PIANO_ROLL_BEATS_PER_BAR) + 1;
+        let beat = (beat_index % PIANO_ROLL_BEATS_PER_BAR) + 1;
+        format!("{bar}.{beat}")
+    }
+
+    /// Left edge of a chord label, or `None` when its bar lies past the end of the grid.
+    fn piano_roll_chord_x(bar: u16) -> Option<f32> {
+        let beat_index = usize::from(bar.checked_sub(1)?) * PIANO_ROLL_BEATS_PER_BAR;
+        (beat_index < PIANO_ROLL_BEAT_COLUMNS).then(|| beat_index as f32 * PIANO_ROLL_BEAT_WIDTH)
+    }
+
+    fn piano_roll_playhead_x(playhead_ppq: f64) -> f32 {
+        let grid_width = PIANO_ROLL_BEAT_COLUMNS as f32 * PIANO_ROLL_BEAT_WIDTH;
+        let max_x = (grid_width - PIANO_ROLL_PLAYHEAD_WIDTH).max(0.0);
+        (playhead_ppq.max(0.0) as f32 * PIANO_ROLL_BEAT_WIDTH).clamp(0.0, max_x)
+    }
+
+    fn parse_decimal_after_marker(text: &str, marker: &str) -> Option<u32> {
+        let start = text.find(marker)? + marker.len();
+        let tail = &text[start..];
+        let digits: String = tail.chars().take_while(|ch| ch.is_ascii_digit()).collect();
+        if digits.is_empty() {
+            None
+        } else {
+            digits.parse::<u32>().ok()
+        }
+    }
+
+    fn parse_hex_after_marker(text: &str, marker: &str) -> Option<u8> {
+        let start = text.find(marker)? + marker.len();
+        let tail = &text[start..];
+        let digits: String = tail
+            .chars()
+            .take_while(|ch| ch.is_ascii_hexdigit())
+            .collect();
+        if digits.is_empty() {
+            None
+        } else {
+            u8::from_str_radix(&digits, 16).ok()
+        }
+    }
+
+    fn parse_midi_debug_field(text: &str, field: &str) -> Option<u8> {
+        for marker in [
+            format!("{field}: u4("),
+            format!("{field}: u7("),
+            format!("{field}: "),
+            format!("{field}="),
+        ] {
+            if let Some(value) = Self::parse_decimal_after_marker(text, &marker)
+                && let Ok(value) = u8::try_from(value)
+            {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    fn parse_reference_note_event(event: &MidiReferenceEvent) -> Option<ParsedReferenceNoteEvent> {
+        let payload = event.event.as_str();
+        if payload.starts_with("LiveMidi ") {
+            let status = Self::parse_hex_after_marker(payload, "status=0x")?;
+            let pitch = Self::parse_decimal_after_marker(payload, "data1=")
+                .and_then(|value| u8::try_from(value).ok())?;
+            let velocity = Self::parse_decimal_after_marker(payload, "data2=")
+                .and_then(|value| u8::try_from(value).ok())
+                .unwrap_or(0);
+            let channel = Self::parse_decimal_after_marker(payload, "channel=")
+                .and_then(|value| u8::try_from(value).ok())
+                .unwrap_or((status & 0x0F) + 1)
+                .clamp(1, 16);
+
+            let kind = match status & 0xF0 {
+                0x90 if velocity > 0 => ParsedNoteEventKind::NoteOn,
+                0x80 | 0x90 => ParsedNoteEventKind::NoteOff,
+                _ => return None,
+            };
+
+            return Some(ParsedReferenceNoteEvent {
+                channel,
+                pitch,
+                kind,
+            });
+        }
+
+        if !payload.contains("NoteOn") && !payload.contains("NoteOff") {
+            return None;
+        }
+
+        let pitch = Self::parse_midi_debug_field(payload, "key")?;
+        let raw_channel = Self::parse_midi_debug_field(payload, "channel").unwrap_or(0);
+        let channel = raw_channel.saturating_add(1).clamp(1, 16);
+
+        let kind = if payload.contains("NoteOff") {
+            ParsedNoteEventKind::NoteOff
+        } else {
+            let velocity = Self::parse_midi_debug_field(payload, "vel").unwrap_or(0);
+            if velocity > 0 {
+                ParsedNoteEventKind::NoteOn
+            } else {
+                ParsedNoteEventKind::NoteOff
+            }
+        };
+
+        Some(ParsedReferenceNoteEvent {
+            channel,
+            pitch,
+            kind,
+        })
+    }
+
+    fn collect_reference_generated_notes(reference: &MidiReferenceSummary) -> Vec<GeneratedNote> {
+        let mut notes = Vec::new();
+        let mut active: std::collections::HashMap<(u16, u8, u8), Vec<u32>> =
+            std::collections::HashMap::new();
+
+        for event in &reference.events {
+            let Some(parsed) = Self::parse_reference_note_event(event) else {
+                continue;
+            };
+            let key = (event.track, parsed.channel, parsed.pitch);
+
+            match parsed.kind {
+                ParsedNoteEventKind::NoteOn => {
+                    active.entry(key).or_default().push(event.absolute_tick);
+                }
+                ParsedNoteEventKind::NoteOff => {
+                    if let Some(starts) = active.get_mut(&key)
+                        && let Some(start_tick) = starts.pop()
+                    {
+                        notes.push(GeneratedNote {
+                            pitch: parsed.pitch,
+                            start_tick,
+                            duration_tick: event.absolute_tick.saturating_sub(start_tick).max(1),
+                            velocity: 100,
+                            channel: parsed.channel,
+                        });
+                        if starts.is_empty() {
+                            active.remove(&key);
+                        }
+                    }
+                }
+            }
+        }
+
+        for ((_, channel, pitch), starts) in active {
+            for start_tick in starts {
+                notes.push(GeneratedNote {
+                    pitch,
+                    start_tick,
+                    duration_tick: PIANO_ROLL_FALLBACK_TICKS_PER_BEAT as u32,
+                    velocity: 80,
+                    channel,
+                });
+            }
+        }
+
+        notes.sort_by_key(|note| (note.start_tick, note.pitch));
+        notes
+    }
+
+    fn reference_ticks_per_beat(reference: &MidiReferenceSummary, notes: &[GeneratedNote]) -> f32 {
+        let total_beats = usize::from(reference.bars.max(1)) * PIANO_ROLL_BEATS_PER_BAR;
+        let max_end_tick = notes
+            .iter()
+            .map(|note| note.start_tick.saturating_add(note.duration_tick))
+            .max()
+            .unwrap_or(0);
+        estimate_ticks_per_beat(total_beats, max_end_tick)
+    }
+
+    fn piano_roll_note_rect(
+        note: &GeneratedNote,
+        ticks_per_beat: f32,
+        is_preview: bool,
+    ) -> Option<PianoRollNoteRect> {
+        let pitch = i16::from(note.pitch);
+        if !(PIANO_ROLL_BOTTOM_MIDI_NOTE..=PIANO_ROLL_TOP_MIDI_NOTE).contains(&pitch) {
+            return None;
+        }
+        if !ticks_per_beat.is_finite() || ticks_per_beat <= 0.0 {
+            return None;
+        }
+
+        let grid_width = PIANO_ROLL_BEAT_COLUMNS as f32 * PIANO_ROLL_BEAT_WIDTH;
+        let start_beat = note.start_tick as f32 / ticks_per_beat;
+        let duration_beats = note.duration_tick as f32 / ticks_per_beat;
+        let x = start_beat * PIANO_ROLL_BEAT_WIDTH;
+        if x >= grid_width {
+            return None;
+        }
+
+        let unclipped_width =
+            (duration_beats * PIANO_ROLL_BEAT_WIDTH).max(PIANO_ROLL_MIN_NOTE_WIDTH);
+        let width = unclipped_width.min(grid_width - x);
+        if width <= 0.0 {
+            return None;
+        }
+
+        let row_index = (PIANO_ROLL_TOP_MIDI_NOTE - pitch) as f32;
+        let y = row_index * PIANO_ROLL_ROW_HEIGHT + PIANO_ROLL_NOTE_VERTICAL_INSET;
+        let height = (PIANO_ROLL_ROW_HEIGHT - (PIANO_ROLL_NOTE_VERTICAL_INSET * 2.0)).max(1.0);
+
+        Some(PianoRollNoteRect {
+            x,
+            y,
+            width,
+            height,
+            is_preview,
+            color: None,
+            note_index: None,
+            is_locked: false,
+        })
+    }
+
+    fn visible_reference_slots(
+        visible_slot_rows: &[ReferenceSlot],
+        piano_roll_hidden_rows: &std::collections::HashSet<usize>,
+    ) -> std::collections::HashSet<ReferenceSlot> {
+        visible_slot_rows
+            .iter()
+            .copied()
+            .enumerate()
+            .filter_map(|(row_index, slot)| {
+                (!piano_roll_hidden_rows.contains(&row_index)).then_some(slot)
+            })
+            .collect()
+    }
+
+    fn piano_roll_reference_note_rects(
+        references: &[MidiReferenceSummary],
+        visible_slot_rows: &[ReferenceSlot],
+        piano_roll_hidden_rows: &std::collections::HashSet<usize>,
+        colors: ThemeColors,
+    ) -> Vec<PianoRollNoteRect> {
+        let visible_slots =
+            Self::visible_reference_slots(visible_slot_rows, piano_roll_hidden_rows);
+        let mut note_rects = Vec::new();
+
+        for reference in references {
+            if !visible_slots.contains(&reference.slot) {
+                continue;
+            }
+
+            let slot_color = colors.slot_color(reference.slot);
+            let notes = Self::collect_reference_generated_notes(reference);
+            let ticks_per_beat = Self::reference_ticks_per_beat(reference, &notes);
+            note_rects.extend(notes.iter().filter_map(|note| {
+                let mut rect = Self::piano_roll_note_rect(note, ticks_per_beat, true)?;
+                rect.color = Some(slot_color);
+                Some(rect)
+            }));
+        }
+
+        note_rects
+    }
+
+    fn piano_roll_candidate_note_rects(
+        candidates: &[GenerationCandidate],
+        selected_candidate_index: Option<usize>,
+        hidden_candidates: &std::collections::HashSet<usize>,
+    ) -> Vec<PianoRollNoteRect> {
+        let mut note_rects = Vec::new();
+        for is_preview in [true, false] {
+            for (index, candidate) in candidates.iter().enumerate() {
+                if hidden_candidates.contains(&index) {
+                    continue;
+                }
+                let candidate_is_preview = selected_candidate_index != Some(index);
+                if candidate_is_preview != is_preview {
+                    continue;
+                }
+
+                let ticks_per_beat = f32::from(candidate.tick_resolution.ticks_per_beat());
+                note_rects.extend(candidate.notes.iter().enumerate().filter_map(
+                    |(note_index, note)| {
+                        let mut rect =
+                            Self::piano_roll_note_rect(note, ticks_per_beat, candidate_is_preview)?;
+                        rect.note_index = (!candidate_is_preview).then_some(note_index);
+                        Some(rect)
+                    },
+                ));
+            }
+        }
+
+        note_rects
+    }
+
+    fn piano_roll_note_rects(
+        references: &[MidiReferenceSummary],
+        visible_slot_rows: &[ReferenceSlot],
+        piano_roll_hidden_rows: &std::collections::HashSet<usize>,
+        candidates: &[GenerationCandidate],
+        selected_candidate_index: Option<usize>,
+        hidden_candidates: &std::collections::HashSet<usize>,
+        colors: ThemeColors,
+    ) -> Vec<PianoRollNoteRect> {
+        let mut note_rects = Self::piano_roll_reference_note_rects(
+            references,
+            visible_slot_rows,
+            piano_roll_hidden_rows,
+            colors,
+        );
+        note_rects.extend(Self::piano_roll_candidate_note_rects(
+            candidates,
+            selected_candidate_index,
+            hidden_candidates,
+        ));
+        note_rects
+    }
+
+    /// Piano roll for the current references and candidates. Each window passes its own scroll
+    /// handles so a detached piano roll scrolls independently of the main window.
+    pub(super) fn piano_roll_view(
+        &self,
+        view: WeakEntity<Self>,
+        vertical_scroll_handle: &ScrollHandle,
+        horizontal_scroll_handle: &ScrollHandle,
+        colors: ThemeColors,
+        corner_radius: Pixels,
+    ) -> impl IntoElement {
+        let generated_slot = Self::generation_mode_output_slot(self.selected_generation_mode);
+        let mut note_rects = Self::piano_roll_note_rects(
+            &self.collect_generation_references(),
+            &self.visible_slot_rows,
+            &self.piano_roll_hidden_rows,
+            &self.generation_candidates,
+            self.selected_candidate_index,
+            &self.hidden_candidates,
+            colors,
+        );
+        if let Some(candidate) = self
+            .selected_candidate_index
+            .and_then(|index| self.generation_candidates.get(index))
+        {
+            mark_locked_note_rects(
+                &mut note_rects,
+                candidate,
+                self.submission_model.locked_notes(),
+            );
+        }
+        let on_note_clicked: Rc<dyn Fn(usize, &mut App)> = Rc::new(move |note_index, cx| {
+            let _ = view.update(cx, |this, cx| {
+                this.on_piano_roll_note_lock_toggled(note_index, cx)
+            });
+        });
+
+        Self::piano_roll_grid(
+            colors,
+            corner_radius,
+            vertical_scroll_handle,
+            horizontal_scroll_handle,
+            self.live_capture_playhead_ppq,
+            colors.slot_color(generated_slot),
+            Self::slot_glow_color(colors, generated_slot),
+            note_rects,
+            &self.generation_chords,
+            on_note_clicked,
+        )
+    }
+
+    /// `(index, label, selected)` for each candidate, in the order the candidate list shows them.
+    pub(super) fn candidate_picker_entries(&self) -> Vec<(usize, String, bool)> {
+        self.candidate_display_order()
+            .into_iter()
+            .map(|index| {
+                let label = self.comparison_candidate_label(index).unwrap_or_else(|| {
+                    candidate_name(&self.generation_candidates[index], self.candidates_mode)
+                });
+                (index, label, self.selected_candidate_index == Some(index))
+            })
+            .collect()
+    }
+
+    fn on_detach_piano_roll_clicked(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(handle) = self.detached_piano_roll {
+            let _ = handle.update(cx, |_, window, _| window.activate_window());
+            return;
+        }
+
+        let main = cx.entity();
+        let main_window = window.window_handle();
+        let bounds = Bounds::centered(
+            None,
+            size(
+                px(DETACHED_PIANO_ROLL_WIDTH),
+                px(DETACHED_PIANO_ROLL_HEIGHT),
+            ),
+            cx,
+        );
+        let options = WindowOptions {
+            window_bounds: Some(WindowBounds::Windowed(bounds)),
+            ..Default::default()
+        };
+        match cx.open_window(options, |window, cx| {
+            let view = cx.new(|cx| DetachedPianoRollWindow::new(main, main_window, window, cx));
+            cx.new(|cx| Root::new(view, window, cx))
+        }) {
+            Ok(handle) => {
+                self.detached_piano_roll = Some(handle.into());
+                self.piano_roll_detach_error = None;
+            }
+            Err(error) => {
+                self.piano_roll_detach_error =
+                    Some(format!("Piano roll window could not be opened: {error}"));
+            }
+        }
+        cx.notify();
+    }
+
+    fn on_reattach_piano_roll_clicked(&mut self, cx: &mut Context<Self>) {
+        if let Some(handle) = self.detached_piano_roll.take() {
+            let _ = handle.update(cx, |_, window, _| window.remove_window());
+        }
+        cx.notify();
+    }
+
+    /// Called by the detached window when it closes, however it was closed.
+    pub(super) fn on_piano_roll_reattached(&mut self, cx: &mut Context<Self>) {
+        self.detached_piano_roll = None;
+        cx.notify();
+    }
+
+    fn piano_roll_grid(
+        colors: ThemeColors,
+        corner_radius: Pixels,
+        vertical_scroll_handle: &ScrollHandle,
+        horizontal_scroll_handle: &ScrollHandle,
+        playhead_ppq: f64,
+        note_color: Hsla,
+        note_glow_color: Hsla,
+        note_rects: Vec<PianoRollNoteRect>,
+        chords: &[ChordLabel],
+        on_note_clicked: Rc<dyn Fn(usize, &mut App)>,
+    ) -> impl IntoElement {
+        let grid_width = PIANO_ROLL_BEAT_COLUMNS as f32 * PIANO_ROLL_BEAT_WIDTH;
+        let grid_height = (PIANO_ROLL_TOP_MIDI_NOTE - PIANO_ROLL_BOTTOM_MIDI_NOTE + 1) as f32
+            * PIANO_ROLL_ROW_HEIGHT;
+        let playhead_x = Self::piano_roll_playhead_x(playhead_ppq);
+        let playhead_marker_x = (playhead_x - 4.0).max(0.0);
+        let midi_notes: Vec<i16> = (PIANO_ROLL_BOTTOM_MIDI_NOTE..=PIANO_ROLL_TOP_MIDI_NOTE)
+            .rev()
+            .collect();
+        let label_notes = midi_notes.clone();
+        let chord_labels = chords
+            .iter()
+            .filter_map(|chord| {
+                Self::piano_roll_chord_x(chord.bar).map(|x| (x, chord.symbol.trim().to_string()))
+            })
+            .collect::<Vec<_>>();
+        let has_chord_lane = !chord_labels.is_empty();
+
+        div()
+            .id("piano-roll-grid-frame")
+            .occlude()
+            .flex_1()
+            .rounded(corner_radius)
+            .bg(colors.surface_background)
+            .overflow_hidden()
+            .child(
+                div()
+                    .id("piano-roll-grid-layout")
+                    .size_full()
+                    .flex()
+                    .child(
+                        div()
+                            .id("piano-roll-key-label-column")
+                            .w(px(PIANO_ROLL_KEY_LABEL_WIDTH))
+                            .h_full()
+                            .flex_none()
+                            .flex()
+                            .flex_col()
+                            .child(
+                                div()
+                                    .id("piano-roll-ruler-corner")
+                                    .h(px(PIANO_ROLL_RULER_HEIGHT))
+                                    .flex_none()
+                                    .border_b_1()
+                                    .border_color(colors.piano_roll_grid_line)
+                                    .bg(colors.panel_background),
+                            )
+                            .when(has_chord_lane, |column| {
+                                column.child(
+                                    div()
+                                        .id("piano-roll-chord-lane-corner")
+                                        .h(px(PIANO_ROLL_CHORD_LANE_HEIGHT))
+                                        .flex_none()
+                                        .border_b_1()
+                                        .border_color(colors.piano_roll_grid_line)
+                                        .bg(colors.panel_background)
+                                        .pr(px(6.0))
+                                        .flex()
+                                        .items_center()
+                                        .justify_end()
+                                        .text_size(px(9.0))
+                                        .text_color(colors.muted_foreground)
+                                        .child("Chords"),
+                                )
+                            })
+                            .child(
+                                div()
+                                    .id("piano-roll-key-label-viewport")
+                                    .flex_1()
+                                    .track_scroll(vertical_scroll_handle)
+                                    .overflow_y_scroll()
+                                    .map(|mut this| {
+                                        this.style().restrict_scroll_to_axis = Some(true);
+                                        this
+                                    })
+                                    .child(
+                                        div()
+                                            .id("piano-roll-key-label-canvas")
+                                            .w(px(PIANO_ROLL_KEY_LABEL_WIDTH))
+                                            .h(px(grid_height))
+                                            .flex()
+                                            .flex_col()
+                                            .children(label_notes.into_iter().map(|midi_note| {
+                                                let note_label = Self::piano_roll_note_label(
+                                                    midi_note,
+                                                )
+                                                .unwrap_or_default();
+                                                let has_label = !note_label.is_empty();
+
+                                                div()
+                                                    .h(px(PIANO_ROLL_ROW_HEIGHT))
+                                                    .flex_none()
+                                                    .flex()
+                                                    .items_center()
+                                                    .border_b_1()
+                                                    .border_color(colors.piano_roll_grid_line)
+                                                    .bg(colors.panel_background)
+                                                    .pr(px(6.0))
+                                                    .justify_end()
+                                                    .text_size(px(10.0))
+                                                    .text_color(if has_label {
+                                                        colors.muted_foreground
+                                                    } else {
+                                                        colors.muted_foreground.opacity(0.35)
+                                                    })
+                                                    .child(note_label)
+                                            })),
+                                    ),
+                            ),
+                    )
+                    .child(
+                        div()
+                            .id("piano-roll-main-grid-scroll")
+                            .flex_1()
+                            .h_full()
+                            .relative()
+                            .track_scroll(horizontal_scroll_handle)
+                            .overflow_x_scroll()
+                            .map(|mut this| {
+                                this.style().restrict_scroll_to_axis = Some(true);
+                                this
+                            })
+                            .scrollbar_width(px(8.0))
+                            .horizontal_scrollbar(horizontal_scroll_handle)
+                            .child(
+                                div()
+                                    .id("piano-roll-main-grid-canvas")
+                                    .w(px(grid_width))
+                                    .h_full()
+                                    .flex()
+                                    .flex_col()
+                                    .child(
+                                        div()
+                                            .id("piano-roll-ruler")
+                                            .h(px(PIANO_ROLL_RULER_HEIGHT))
+                                            .flex_none()
+                                            .flex()
+                                            .border_b_1()
+                                            .border_color(colors.piano_roll_grid_line)
+                                            .bg(colors.panel_background)
+                                            .children((0..PIANO_ROLL_BEAT_COLUMNS).map(
+                                                |beat_index| {
+                                                    let is_bar_start =
+                                                        beat_index % PIANO_ROLL_BEATS_PER_BAR == 0;
+                                                    div()
+                                                        .h_full()
+                                                        .w(px(PIANO_ROLL_BEAT_WIDTH))
+                                                        .flex_none()
+                                                        .border_l_1()
+                                                        .border_color(if is_bar_start {
+                                                            colors.panel_border
+                                                        } else {
+                                                            colors.piano_roll_grid_line
+                                                        })
+                                                        .pl(px(4.0))
+                                                        .pt(px(3.0))
+                                                        .text_size(px(9.0))
+                                                        .text_color(if is_bar_start {
+                                                            colors.accent_foreground
+                                                        } else {
+                                                            colors.muted_foreground.opacity(0.75)
+                                                        })
+                                                        .child(Self::piano_roll_beat_label(
+                                                            beat_index,
+                                                        ))
+                                                },
+                                            ))
+                                            .child(
+                                                div()
+                                                    .id("piano-roll-playhead-marker")
+                                                    .absolute()
+                                                    .top(px(1.0))
+                                                    .left(px(playhead_marker_x))
+                                                    .text_size(px(10.0))
+                                                    .text_color(colors.piano_roll_playhead)
+                                                    .child("▼"),
+                                            ),
+                                    )
+                                    .when(has_chord_lane, |canvas| {
+                                        canvas.child(
+                                            div()
+                                                .id("piano-roll-chord-lane")
+                                                .h(px(PIANO_ROLL_CHORD_LANE_HEIGHT))
+                                                .flex_none()
+                                                .relative()
+                                                .border_b_1()
+                                                .border_color(colors.piano_roll_grid_line)
+                                                .bg(colors.panel_background)
+                                                .children(chord_labels.into_iter().enumerate().map(
+                                                    |(index, (x, symbol))| {
+                                                        div()
+                                                            .id(("piano-roll-chord", index))
+                                                            .absolute()
+                                                            .left(px(x + 4.0))
+                                                            .top(px(2.0))
+                                                            .text_size(px(10.0))
+                                                            .text_color(colors.accent_foreground)
+                                                            .child(symbol)
+                                                    },
+                                                )),
+                                        )
+                                    })
+                                    .child(
+                                        div()
+                                            .id("piano-roll-beat-grid-viewport")
+                                            .flex_1()
+                                            .relative()
+                                            .track_scroll(vertical_scroll_handle)
+                                            .overflow_y_scroll()
+                                            .map(|mut this| {
+                                                this.style().restrict_scroll_to_axis = Some(true);
+                                                this
+                                            })
+                                            .scrollbar_width(px(8.0))
+                                            .vertical_scrollbar(vertical_scroll_handle)
+                                            .child(
+                                                div()
+                                                    .id("piano-roll-beat-grid-canvas")
+                                                    .w(px(grid_width))
+                                                    .h(px(grid_height))
+                                                    .children(midi_notes.into_iter().map(
+                                                        |midi_note| {
+                                                            div()
+                                                                .h(px(PIANO_ROLL_ROW_HEIGHT))
+                                                                .flex_none()
+                                                                .flex()
+                                                                .border_b_1()
+                                                                .border_color(
+                                                                    colors.piano_roll_grid_line,
+                                                                )
+                                                                .bg(if Self::piano_roll_is_black_key(
+                                                                    midi_note,
+                                                                ) {
+                                                                    colors
                                                                         .surface_foreground
                                                                         .opacity(0.05)
                                                                 } else {
@@ -1464,535 +3460,2341 @@ impl SonantMainWindow {
                                                                 resolved_note_color.opacity(0.72)
                                                             };
 
-                                                            let base = div()
-                                                                .id(("piano-roll-note", index))
-                                                                .absolute()
-                                                                .left(px(note.x))
-                                                                .top(px(note.y))
-                                                                .w(px(note.width))
-                                                                .h(px(note.height))
-                                                                .rounded(px(4.0))
-                                                                .border_1()
-                                                                .border_color(note_border)
-                                                                .bg(note_fill);
+                                                            let base = div()
+                                                                .id(("piano-roll-note", index))
+                                                                .absolute()
+                                                                .left(px(note.x))
+                                                                .top(px(note.y))
+                                                                .w(px(note.width))
+                                                                .h(px(note.height))
+                                                                .rounded(px(4.0))
+                                                                .border_1()
+                                                                .border_color(note_border)
+                                                                .bg(note_fill)
+                                                                .when(note.is_locked, |el| {
+                                                                    el.border_2()
+                                                                        .border_color(colors.warning_foreground)
+                                                                });
+                                                            let base = match note.note_index {
+                                                                Some(note_index) => {
+                                                                    let on_note_clicked = on_note_clicked.clone();
+                                                                    base.cursor_pointer().on_click(
+                                                                        move |_, _window, cx| {
+                                                                            on_note_clicked(note_index, cx)
+                                                                        },
+                                                                    )
+                                                                }
+                                                                None => base,
+                                                            };
+
+                                                            if note.is_preview {
+                                                                base.border_dashed()
+                                                            } else {
+                                                                base.shadow(vec![gpui::BoxShadow {
+                                                                    color: note_glow_color.opacity(0.45),
+                                                                    offset: gpui::point(px(0.0), px(0.0)),
+                                                                    blur_radius: px(10.0),
+                                                                    spread_radius: px(0.0),
+                                                                }])
+                                                            }
+                                                        },
+                                                    ))
+                                                    .child(
+                                                        div()
+                                                            .id("piano-roll-playhead-line")
+                                                            .absolute()
+                                                            .top(px(0.0))
+                                                            .left(px(playhead_x))
+                                                            .w(px(PIANO_ROLL_PLAYHEAD_WIDTH))
+                                                            .h(px(grid_height))
+                                                            .bg(colors.piano_roll_playhead)
+                                                            .shadow(vec![gpui::BoxShadow {
+                                                                color: colors
+                                                                    .glow_playhead
+                                                                    .opacity(0.5),
+                                                                offset: gpui::point(px(0.0), px(0.0)),
+                                                                blur_radius: px(10.0),
+                                                                spread_radius: px(0.0),
+                                                            }]),
+                                                    ),
+                                            ),
+                                    ),
+                            ),
+                    ),
+            )
+    }
+
+    fn clamp_param_level(level: u8) -> u8 {
+        level.clamp(PARAM_LEVEL_MIN, PARAM_LEVEL_MAX)
+    }
+
+    fn slider_value_to_param_level(value: SliderValue) -> u8 {
+        let raw = value.end().round() as i16;
+        let clamped = raw.clamp(PARAM_LEVEL_MIN as i16, PARAM_LEVEL_MAX as i16);
+        clamped as u8
+    }
+
+    fn param_level_to_percent(level: u8) -> u8 {
+        let level = Self::clamp_param_level(level);
+        let offset = level.saturating_sub(PARAM_LEVEL_MIN) as u16;
+        ((offset * 100) / PARAM_LEVEL_SPAN as u16) as u8
+    }
+
+    fn parameter_slider_control(
+        id: &'static str,
+        label: &'static str,
+        percent: u8,
+        min_label: &'static str,
+        max_label: &'static str,
+        slider: &Entity<SliderState>,
+        focus_handle: &FocusHandle,
+        on_key_down: impl Fn(&KeyDownEvent, &mut Window, &mut App) + 'static,
+        colors: ThemeColors,
+    ) -> impl IntoElement {
+        div()
+            .id(id)
+            .track_focus(focus_handle)
+            .on_key_down(on_key_down)
+            .flex()
+            .flex_col()
+            .gap_1()
+            .p_1()
+            .rounded(px(4.0))
+            .border_1()
+            .border_color(gpui::transparent_black())
+            .focus(|style| style.border_color(colors.primary))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .child(div().text_size(px(12.0)).child(label))
+                    .child(
+                        div()
+                            .text_size(px(12.0))
+                            .font_weight(gpui::FontWeight::BOLD)
+                            .text_color(colors.accent_foreground)
+                            .child(format!("{percent}%")),
+                    ),
+            )
+            .child(
+                div().py(px(2.0)).child(
+                    Slider::new(slider)
+                        .horizontal()
+                        .h(px(24.0))
+                        .bg(colors.primary)
+                        .text_color(colors.primary),
+                ),
+            )
+            .child(
+                div()
+                    .flex()
+                    .justify_between()
+                    .text_size(px(10.0))
+                    .text_color(colors.muted_foreground)
+                    .child(min_label)
+                    .child(max_label),
+            )
+    }
+
+    fn ai_model_dropdown_items() -> Vec<&'static str> {
+        vec![DEFAULT_ANTHROPIC_MODEL, DEFAULT_OPENAI_COMPAT_MODEL]
+    }
+
+    fn model_ref_for_dropdown_item(item: &str) -> ModelRef {
+        let provider = if item == DEFAULT_ANTHROPIC_MODEL {
+            "anthropic"
+        } else {
+            "openai_compatible"
+        };
+        ModelRef {
+            provider: provider.to_string(),
+            model: item.to_string(),
+        }
+    }
+
+    fn generation_mode_label(mode: GenerationMode) -> &'static str {
+        match mode {
+            GenerationMode::Melody => "Melody",
+            GenerationMode::ChordProgression => "Chord Progression",
+            GenerationMode::DrumPattern => "Drum Pattern",
+            GenerationMode::Bassline => "Bassline",
+            GenerationMode::CounterMelody => "Counter Melody",
+            GenerationMode::Harmony => "Harmony",
+            GenerationMode::Continuation => "Continuation",
+        }
+    }
+
+    fn reference_slots() -> &'static [ReferenceSlot] {
+        const SLOTS: [ReferenceSlot; 7] = [
+            ReferenceSlot::Melody,
+            ReferenceSlot::ChordProgression,
+            ReferenceSlot::DrumPattern,
+            ReferenceSlot::Bassline,
+            ReferenceSlot::CounterMelody,
+            ReferenceSlot::Harmony,
+            ReferenceSlot::ContinuationSeed,
+        ];
+        &SLOTS
+    }
+
+    fn reference_slot_label(slot: ReferenceSlot) -> &'static str {
+        match slot {
+            ReferenceSlot::Melody => "Melody",
+            ReferenceSlot::ChordProgression => "Chord Progression",
+            ReferenceSlot::DrumPattern => "Drum Pattern",
+            ReferenceSlot::Bassline => "Bassline",
+            ReferenceSlot::CounterMelody => "Counter Melody",
+            ReferenceSlot::Harmony => "Harmony",
+            ReferenceSlot::ContinuationSeed => "Continuation Seed",
+        }
+    }
+
+    fn reference_slot_index(slot: ReferenceSlot) -> usize {
+        match slot {
+            ReferenceSlot::Melody => 0,
+            ReferenceSlot::ChordProgression => 1,
+            ReferenceSlot::DrumPattern => 2,
+            ReferenceSlot::Bassline => 3,
+            ReferenceSlot::CounterMelody => 4,
+            ReferenceSlot::Harmony => 5,
+            ReferenceSlot::ContinuationSeed => 6,
+        }
+    }
+
+    fn slot_short_label(slot: ReferenceSlot) -> &'static str {
+        match slot {
+            ReferenceSlot::Melody => "Melody",
+            ReferenceSlot::ChordProgression => "Chords",
+            ReferenceSlot::DrumPattern => "Drums",
+            ReferenceSlot::Bassline => "Bass",
+            ReferenceSlot::CounterMelody => "Counter",
+            ReferenceSlot::Harmony => "Harmony",
+            ReferenceSlot::ContinuationSeed => "Seed",
+        }
+    }
+
+    fn slot_source_display_label(&self, slot: ReferenceSlot) -> String {
+        match self.source_for_slot(slot) {
+            ReferenceSource::Live => {
+                let ch = self.channel_mapping_for_slot(slot).unwrap_or(1);
+                format!("Live: CH {ch}")
+            }
+            ReferenceSource::File => {
+                let file_references = self.load_midi_use_case.snapshot_references();
+                let has_file = file_references.iter().any(|r| r.slot == slot);
+                if has_file {
+                    let label = file_references
+                        .iter()
+                        .find(|r| r.slot == slot)
+                        .and_then(|r| r.file.as_ref())
+                        .map(|f| display_file_name_from_path(&f.path))
+                        .unwrap_or_else(|| "File".to_string());
+                    let extra_clips = self
+                        .load_midi_use_case
+                        .slot_clip_paths(slot)
+                        .first()
+                        .map_or(0, |clip_paths| clip_paths.len().saturating_sub(1));
+                    if extra_clips > 0 {
+                        format!("{label} +{extra_clips}")
+                    } else {
+                        label
+                    }
+                } else {
+                    "Drop MIDI file".to_string()
+                }
+            }
+        }
+    }
+
+    /// Opens or closes a popover from a press on its trigger, and focuses it so Esc closes it.
+    fn on_overlay_menu_toggled(
+        &mut self,
+        menu: OverlayMenu,
+        event: &MouseDownEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.overlay_menus.toggle(menu, event.position);
+        if self.overlay_menus.is_open(menu) {
+            window.focus(&self.overlay_menu_focus);
+        }
+        cx.notify();
+    }
+
+    fn on_overlay_menu_dismissed(&mut self, event: &MouseDownEvent, cx: &mut Context<Self>) {
+        if self.overlay_menus.dismiss(event.position) {
+            cx.notify();
+        }
+    }
+
+    fn close_overlay_menu(&mut self, cx: &mut Context<Self>) {
+        if self.overlay_menus.close() {
+            cx.notify();
+        }
+    }
+
+    fn on_input_track_preset_selected(&mut self, name: &str, cx: &mut Context<Self>) {
+        self.overlay_menus.close();
+        let layout = match self.input_track_presets.resolve(name) {
+            Ok(layout) => layout,
+            Err(error) => {
+                self.input_track_error = Some(error.to_string());
+                cx.notify();
+                return;
+            }
+        };
+        // `apply_layout` validates before mutating, so a bad preset leaves the section as-is.
+        if let Err(error) = self.input_track_model.apply_layout(&layout) {
+            self.input_track_error = Some(error.to_string());
+            cx.notify();
+            return;
+        }
+
+        let removed_slots = self
+            .visible_slot_rows
+            .iter()
+            .copied()
+            .filter(|slot| !layout.rows.contains(slot))
+            .collect::<std::collections::HashSet<_>>();
+        self.visible_slot_rows = layout.rows;
+        self.piano_roll_hidden_rows.clear();
+        self.midi_slot_errors.clear();
+        self.overlay_menus.close();
+        self.analysis_row_open = None;
+        self.sketch_slot_open = None;
+        self.stop_reference_preview();
+        self.input_track_error = None;
+        for slot in removed_slots {
+            self.on_clear_midi_slot_clicked(slot, cx);
+        }
+        if let Err(error) = self.sync_midi_input_router_config() {
+            self.input_track_error = Some(error);
+        }
+        self.publish_input_track_layout();
+        cx.notify();
+    }
+
+    fn on_save_input_track_preset_clicked(&mut self, cx: &mut Context<Self>) {
+        let name = self.preset_name_input.read(cx).value().to_string();
+        let layout = self.input_track_model.to_layout(&self.visible_slot_rows);
+        match self.input_track_presets.save_preset(&name, layout) {
+            Ok(()) => {
+                self.input_track_error = None;
+                self.overlay_menus.close();
+            }
+            Err(error) => self.input_track_error = Some(error.to_string()),
+        }
+        cx.notify();
+    }
+
+    fn on_advanced_sampling_toggled(&mut self, cx: &mut Context<Self>) {
+        self.advanced_sampling_open = !self.advanced_sampling_open;
+        cx.notify();
+    }
+
+    fn on_sampling_param_stepped(
+        &mut self,
+        param: SamplingParam,
+        steps: i32,
+        cx: &mut Context<Self>,
+    ) {
+        self.submission_model.step_sampling_param(param, steps);
+        self.active_sampling_profile = None;
+        cx.notify();
+    }
+
+    fn on_sampling_param_reset(&mut self, param: SamplingParam, cx: &mut Context<Self>) {
+        self.submission_model.reset_sampling_param(param);
+        self.active_sampling_profile = None;
+        cx.notify();
+    }
+
+    fn on_sampling_profile_selected(&mut self, name: &str, cx: &mut Context<Self>) {
+        let Some(profile) = self
+            .sampling_profiles
+            .profile(self.submission_model.provider(), name)
+        else {
+            return;
+        };
+        self.submission_model.set_sampling(SamplingParams {
+            temperature: profile.temperature,
+            top_p: profile.top_p,
+            max_tokens: profile.max_tokens,
+        });
+        self.active_sampling_profile = Some(profile.name.clone());
+        cx.notify();
+    }
+
+    fn on_save_sampling_profile_clicked(&mut self, cx: &mut Context<Self>) {
+        let sampling = self.submission_model.sampling();
+        let profile = SamplingProfile {
+            name: self
+                .sampling_profile_name_input
+                .read(cx)
+                .value()
+                .to_string(),
+            provider: self.submission_model.provider().to_string(),
+            temperature: sampling.temperature,
+            top_p: sampling.top_p,
+            max_tokens: sampling.max_tokens,
+        };
+        let name = profile.name.trim().to_string();
+        match self.sampling_profiles.save_profile(profile) {
+            Ok(()) => {
+                self.sampling_profile_error = None;
+                self.active_sampling_profile = Some(name);
+            }
+            Err(error) => self.sampling_profile_error = Some(error.to_string()),
+        }
+        cx.notify();
+    }
+
+    fn on_remove_sampling_profile_clicked(&mut self, name: &str, cx: &mut Context<Self>) {
+        let provider = self.submission_model.provider().to_string();
+        match self.sampling_profiles.remove_profile(&provider, name) {
+            Ok(_) => {
+                self.sampling_profile_error = None;
+                if self.active_sampling_profile.as_deref() == Some(name) {
+                    self.active_sampling_profile = None;
+                }
+            }
+            Err(error) => self.sampling_profile_error = Some(error.to_string()),
+        }
+        cx.notify();
+    }
+
+    fn on_arrangement_section_name_selected(&mut self, name: &'static str, cx: &mut Context<Self>) {
+        self.arrangement_section_name = name;
+        cx.notify();
+    }
+
+    fn on_arrangement_section_bars_stepped(&mut self, delta: i32, cx: &mut Context<Self>) {
+        let bars = i32::from(self.arrangement_section_bars) + delta;
+        self.arrangement_section_bars =
+            bars.clamp(1, i32::from(ARRANGEMENT_SECTION_MAX_BARS)) as u16;
+        cx.notify();
+    }
+
+    fn on_add_arrangement_section_clicked(&mut self, cx: &mut Context<Self>) {
+        let section = ArrangementSection {
+            name: self.arrangement_section_name.to_string(),
+            bars: self.arrangement_section_bars,
+            prompt: self.arrangement_prompt_input.read(cx).value().to_string(),
+        };
+        match section.validate() {
+            Ok(()) => {
+                self.arrangement_sections.push(section);
+                self.arrangement_error = None;
+            }
+            Err(error) => self.arrangement_error = Some(error.to_string()),
+        }
+        cx.notify();
+    }
+
+    fn on_remove_arrangement_section_clicked(&mut self, index: usize, cx: &mut Context<Self>) {
+        if index < self.arrangement_sections.len() && self.arrangement_request_id.is_none() {
+            self.arrangement_sections.remove(index);
+            cx.notify();
+        }
+    }
+
+    fn on_generate_arrangement_clicked(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.reconcile_bpm_input_with_model(window, cx);
+        self.arrangement_error = None;
+        match ArrangementRun::new(self.arrangement_sections.clone()) {
+            Ok(run) => self.arrangement_run = Some(run),
+            Err(error) => {
+                self.arrangement_error = Some(error.to_string());
+                cx.notify();
+                return;
+            }
+        }
+
+        match self.submit_arrangement_section(cx) {
+            Ok(()) => self.start_update_polling(window, cx),
+            Err(message) => self.abort_arrangement_run(message),
+        }
+        cx.notify();
+    }
+
+    fn submit_arrangement_section(&mut self, cx: &mut Context<Self>) -> Result<(), String> {
+        let Some(run) = self.arrangement_run.as_ref() else {
+            return Err("No arrangement is being generated.".to_string());
+        };
+        let overall_prompt = self.prompt_input.read(cx).value().to_string();
+        let Some(prompt) = run.current_prompt(&overall_prompt) else {
+            return Err("All arrangement sections have already been generated.".to_string());
+        };
+        let mut references = self.collect_generation_references();
+        references.extend(run.continuation_reference());
+
+        let request = self
+            .submission_model
+            .prepare_request(self.selected_generation_mode, prompt, references)
+            .map_err(|error| error.user_message())?;
+        request.validate().map_err(|error| error.user_message())?;
+
+        log_generation_request_submission(&request);
+        let request_id = request.request_id.clone();
+        let history_request = request.clone();
+        self.generation_job_manager
+            .submit_generate(request)
+            .map_err(|error| error.user_message())?;
+        self.pending_history_requests
+            .insert(request_id.clone(), history_request);
+        self.generation_status = HelperGenerationStatus::Submitting {
+            request_id: request_id.clone(),
+        };
+        self.arrangement_request_id = Some(request_id);
+        Ok(())
+    }
+
+    fn advance_arrangement_run(&mut self, cx: &mut Context<Self>) {
+        let Some(pending_request_id) = self.arrangement_request_id.clone() else {
+            return;
+        };
+
+        match &self.generation_status {
+            HelperGenerationStatus::Succeeded { request_id, .. }
+                if *request_id == pending_request_id =>
+            {
+                self.arrangement_request_id = None;
+                let Some(candidate) = self.generation_candidates.first().cloned() else {
+                    self.abort_arrangement_run("The section returned no candidates.".to_string());
+                    return;
+                };
+                let Some(run) = self.arrangement_run.as_mut() else {
+                    return;
+                };
+                if let Err(error) =
+                    run.record_section_result(candidate, self.generation_chords.clone())
+                {
+                    self.abort_arrangement_run(error.to_string());
+                    return;
+                }
+                if !run.is_complete()
+                    && let Err(message) = self.submit_arrangement_section(cx)
+                {
+                    self.abort_arrangement_run(message);
+                }
+            }
+            HelperGenerationStatus::Failed { message } => {
+                let message = message.clone();
+                self.abort_arrangement_run(message);
+            }
+            HelperGenerationStatus::Cancelled { .. } => {
+                self.abort_arrangement_run("Arrangement generation was cancelled.".to_string());
+            }
+            _ => {}
+        }
+    }
+
+    fn abort_arrangement_run(&mut self, message: String) {
+        self.arrangement_request_id = None;
+        self.arrangement_run = None;
+        self.arrangement_error = Some(message);
+    }
+
+    fn on_run_batch_clicked(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let receiver = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+            prompt: Some(BATCH_PROMPTS_PICKER_PROMPT.into()),
+        });
+
+        self._batch_prompts_task = cx.spawn_in(window, async move |view, window| {
+            let Ok(result) = receiver.await else {
+                return;
+            };
+            let loaded = match result {
+                Ok(Some(paths)) => {
+                    let Some(path) = paths.into_iter().next() else {
+                        return;
+                    };
+                    load_batch_prompts(&path)
+                        .and_then(BatchRun::new)
+                        .map_err(|error| error.to_string())
+                }
+                Ok(None) => return,
+                Err(error) => Err(format!("Could not open the file dialog: {error}")),
+            };
+            let _ = view.update_in(window, |view, window, cx| {
+                match loaded {
+                    Ok(run) => {
+                        view.batch_error = None;
+                        view.batch_run = Some(run);
+                        if view.submit_batch_prompt(window, cx) {
+                            view.start_update_polling(window, cx);
+                        }
+                    }
+                    Err(message) => view.batch_error = Some(message),
+                }
+                cx.notify();
+            });
+        });
+    }
+
+    /// Submits the batch's current prompt; on failure the run stops and `false` is returned.
+    fn submit_batch_prompt(&mut self, window: &mut Window, cx: &mut Context<Self>) -> bool {
+        let Some(prompt) = self
+            .batch_run
+            .as_ref()
+            .and_then(BatchRun::current_prompt)
+            .map(str::to_string)
+        else {
+            return false;
+        };
+        let Some(request) = self.prepare_generation_request_for_prompt(prompt, window, cx) else {
+            self.abort_batch_run("Fix the generation settings, then start the batch again.");
+            return false;
+        };
+
+        log_generation_request_submission(&request);
+        let request_id = request.request_id.clone();
+        let history_request = request.clone();
+        if let Err(error) = self.generation_job_manager.submit_generate(request) {
+            self.abort_batch_run(&error.user_message());
+            return false;
+        }
+        self.pending_history_requests
+            .insert(request_id.clone(), history_request);
+        self.generation_status = HelperGenerationStatus::Submitting {
+            request_id: request_id.clone(),
+        };
+        self.batch_request_id = Some(request_id);
+        true
+    }
+
+    /// Moves to the next prompt once the current one finishes; failed prompts are skipped so an
+    /// unattended batch keeps going, while a cancellation stops it.
+    fn advance_batch_run(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(pending_request_id) = self.batch_request_id.clone() else {
+            return;
+        };
+        let Some(run) = self.batch_run.as_mut() else {
+            return;
+        };
+
+        match &self.generation_status {
+            HelperGenerationStatus::Succeeded { request_id, .. }
+                if *request_id == pending_request_id =>
+            {
+                run.record_success();
+            }
+            HelperGenerationStatus::Failed { message } => run.record_failure(message),
+            HelperGenerationStatus::Cancelled { .. } => {
+                self.abort_batch_run("Batch was cancelled.");
+                return;
+            }
+            _ => return,
+        }
+        self.batch_request_id = None;
+        if !run.is_complete() {
+            self.submit_batch_prompt(window, cx);
+        }
+    }
+
+    fn abort_batch_run(&mut self, message: &str) {
+        self.batch_request_id = None;
+        self.batch_error = Some(message.to_string());
+    }
+
+    fn on_export_arrangement_clicked(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let resolution = self.settings_ui_state.tick_resolution();
+        let Some((notes, control_events, chords)) = self
+            .arrangement_run
+            .as_ref()
+            .filter(|run| run.is_complete())
+            .map(|run| {
+                (
+                    run.assembled_notes(resolution),
+                    run.assembled_control_events(resolution),
+                    run.assembled_chords(),
+                )
+            })
+        else {
+            self.arrangement_error = Some("Generate every section before exporting.".to_string());
+            cx.notify();
+            return;
+        };
+        let conductor = self.export_conductor();
+        let program = self.export_program_for_mode(self.selected_generation_mode);
+        let file_stem = self.export_file_stem(None, self.selected_generation_mode);
+
+        let receiver = cx.prompt_for_paths(PathPromptOptions {
+            files: false,
+            directories: true,
+            multiple: false,
+            prompt: Some(ARRANGEMENT_EXPORT_PICKER_PROMPT.into()),
+        });
+
+        self._arrangement_export_task = cx.spawn_in(window, async move |view, window| {
+            let Ok(result) = receiver.await else {
+                return;
+            };
+            let outcome = match result {
+                Ok(Some(paths)) => {
+                    let Some(dir) = paths.into_iter().next() else {
+                        return;
+                    };
+                    write_notes_to_midi_file(
+                        dir.join(format!("{file_stem}-arrangement.mid")),
+                        &notes,
+                        &control_events,
+                        &chords,
+                        resolution.ticks_per_beat(),
+                        &conductor,
+                        program,
+                    )
+                    .map_err(|error| error.to_string())
+                }
+                Ok(None) => return,
+                Err(error) => Err(format!("Could not open the folder dialog: {error}")),
+            };
+            let _ = view.update_in(window, |view, _window, cx| {
+                view.arrangement_error = outcome.err();
+                cx.notify();
+            });
+        });
+    }
+
+    fn on_export_history_clicked(
+        &mut self,
+        format: GenerationHistoryExportFormat,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.generation_history.entries().is_empty() {
+            self.history_export_error = Some("No generations recorded yet.".to_string());
+            cx.notify();
+            return;
+        }
+        let history = self.generation_history.clone();
+
+        let receiver = cx.prompt_for_paths(PathPromptOptions {
+            files: false,
+            directories: true,
+            multiple: false,
+            prompt: Some(HISTORY_EXPORT_PICKER_PROMPT.into()),
+        });
+
+        self._history_export_task = cx.spawn_in(window, async move |view, window| {
+            let Ok(result) = receiver.await else {
+                return;
+            };
+            let outcome = match result {
+                Ok(Some(paths)) => {
+                    let Some(dir) = paths.into_iter().next() else {
+                        return;
+                    };
+                    history
+                        .export_to_file(dir.join(format.file_name()), format)
+                        .map_err(|error| error.to_string())
+                }
+                Ok(None) => return,
+                Err(error) => Err(format!("Could not open the folder dialog: {error}")),
+            };
+            let _ = view.update_in(window, |view, _window, cx| {
+                view.history_export_error = outcome.err();
+                cx.notify();
+            });
+        });
+    }
+
+    /// Visible reference slots first, in row order, then every candidate not hidden in the roll.
+    fn collect_stem_parts(&self) -> Vec<StemPart> {
+        let references = self.collect_generation_references();
+        let mut parts = Vec::new();
+        for (row_index, slot) in self.visible_slot_rows.iter().copied().enumerate() {
+            if self.piano_roll_hidden_rows.contains(&row_index)
+                || parts
+                    .iter()
+                    .any(|part: &StemPart| part.source == StemSource::Reference { slot })
+            {
+                continue;
+            }
+            let Some(reference) = references.iter().find(|reference| reference.slot == slot) else {
+                continue;
+            };
+            let notes = Self::collect_reference_generated_notes(reference);
+            let resolution =
+                TickResolution::nearest_common(Self::reference_ticks_per_beat(reference, &notes));
+            parts.push(
+                StemPart::from_reference(slot, notes, resolution)
+                    .with_program(self.settings_ui_state.export_program(slot)),
+            );
+        }
+        let candidate_program = self
+            .candidates_mode
+            .and_then(|mode| self.export_program_for_mode(mode));
+        parts.extend(
+            self.generation_candidates
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| !self.hidden_candidates.contains(index))
+                .map(|(_, candidate)| {
+                    StemPart::from_candidate(candidate).with_program(candidate_program)
+                }),
+        );
+        parts
+    }
+
+    fn on_export_stems_clicked(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let parts = self.collect_stem_parts();
+        if parts.iter().all(|part| part.notes.is_empty()) {
+            self.stem_export_error =
+                Some("Load a reference or generate a pattern first.".to_string());
+            cx.notify();
+            return;
+        }
+        let resolution = self.settings_ui_state.tick_resolution();
+        let conductor = self.export_conductor();
+        let file_stem = self.export_file_stem(None, self.selected_generation_mode);
+
+        let receiver = cx.prompt_for_paths(PathPromptOptions {
+            files: false,
+            directories: true,
+            multiple: false,
+            prompt: Some(STEM_EXPORT_PICKER_PROMPT.into()),
+        });
+
+        self._stem_export_task = cx.spawn_in(window, async move |view, window| {
+            let Ok(result) = receiver.await else {
+                return;
+            };
+            let outcome = match result {
+                Ok(Some(paths)) => {
+                    let Some(dir) = paths.into_iter().next() else {
+                        return;
+                    };
+                    export_stems(dir, &file_stem, &parts, resolution, &conductor)
+                        .map(|_| ())
+                        .map_err(|error| error.to_string())
+                }
+                Ok(None) => return,
+                Err(error) => Err(format!("Could not open the folder dialog: {error}")),
+            };
+            let _ = view.update_in(window, |view, _window, cx| {
+                view.stem_export_error = outcome.err();
+                cx.notify();
+            });
+        });
+    }
+
+    /// Adds a `slot` row after `after_row`, or at the end of the list.
+    fn on_add_track_slot_selected(
+        &mut self,
+        after_row: Option<usize>,
+        slot: ReferenceSlot,
+        cx: &mut Context<Self>,
+    ) {
+        self.overlay_menus.close();
+        match after_row.filter(|row| *row < self.visible_slot_rows.len()) {
+            Some(row) => self.insert_track_row(row + 1, slot),
+            None => self.visible_slot_rows.push(slot),
+        }
+        self.publish_input_track_layout();
+        cx.notify();
+    }
+
+    /// Inserts a row at `row_index` and moves the state of the rows after it down by one.
+    fn insert_track_row(&mut self, row_index: usize, slot: ReferenceSlot) {
+        self.visible_slot_rows.insert(row_index, slot);
+        let shift = |index: usize| if index >= row_index { index + 1 } else { index };
+        for error in &mut self.midi_slot_errors {
+            error.row_index = shift(error.row_index);
+        }
+        self.piano_roll_hidden_rows = self.piano_roll_hidden_rows.drain().map(shift).collect();
+        self.overlay_menus.remap_rows(|row| Some(shift(row)));
+        for open_row in [
+            &mut self.analysis_row_open,
+            &mut self.previewing_reference_row,
+        ] {
+            *open_row = open_row.map(shift);
+        }
+    }
+
+    fn on_remove_track_row(&mut self, row_index: usize, cx: &mut Context<Self>) {
+        if row_index < self.visible_slot_rows.len() {
+            let slot = self.visible_slot_rows[row_index];
+            let hidden_in_piano_roll = self.piano_roll_hidden_rows.contains(&row_index);
+            self.visible_slot_rows.remove(row_index);
+            let references = (!self.visible_slot_rows.contains(&slot))
+                .then(|| self.load_midi_use_case.snapshot_slot(slot))
+                .filter(|snapshot| !snapshot.is_empty());
+            self.offer_track_undo(
+                TrackRowUndo {
+                    row_index,
+                    slot,
+                    hidden_in_piano_roll,
+                    references,
+                },
+                cx,
+            );
+            self.clear_midi_slot_error_for_row(slot, row_index);
+            // adjust row_index in remaining errors for rows that shifted down
+            for error in &mut self.midi_slot_errors {
+                if error.slot == slot && error.row_index > row_index {
+                    error.row_index -= 1;
+                }
+            }
+            // adjust piano_roll_hidden_rows: remove deleted row, shift down higher indices
+            self.piano_roll_hidden_rows.remove(&row_index);
+            let shifted: std::collections::HashSet<usize> = self
+                .piano_roll_hidden_rows
+                .drain()
+                .map(|i| if i > row_index { i - 1 } else { i })
+                .collect();
+            self.piano_roll_hidden_rows = shifted;
+            self.overlay_menus.remap_rows(|i| match i.cmp(&row_index) {
+                std::cmp::Ordering::Less => Some(i),
+                std::cmp::Ordering::Equal => None,
+                std::cmp::Ordering::Greater => Some(i - 1),
+            });
+            // if no more rows for this slot, clear the underlying file references
+            if !self.visible_slot_rows.contains(&slot) {
+                if self.sketch_slot_open == Some(slot) {
+                    self.sketch_slot_open = None;
+                }
+                self.on_clear_midi_slot_clicked(slot, cx);
+            }
+            self.publish_input_track_layout();
+            cx.notify();
+        }
+    }
+
+    /// Up/Down move between rows, Alt+Up/Down move the row itself, Space shows or hides it in the
+    /// piano roll, and Delete removes it.
+    fn on_track_list_key_down(&mut self, event: &KeyDownEvent, cx: &mut Context<Self>) {
+        let count = self.visible_slot_rows.len();
+        let focused = self.focused_track_row.filter(|row| *row < count);
+        match event.keystroke.key.as_str() {
+            "up" | "down" => {
+                let step = if event.keystroke.key == "up" { -1 } else { 1 };
+                let Some(next) = stepped_list_index(focused, count, step) else {
+                    return;
+                };
+                if event.keystroke.modifiers.alt
+                    && let Some(row) = focused
+                {
+                    self.on_track_row_moved(row, next, cx);
+                }
+                self.focused_track_row = Some(next);
+            }
+            "space" => {
+                if let Some(row) = focused {
+                    self.on_piano_roll_visibility_toggled(row, cx);
+                }
+            }
+            "delete" | "backspace" => {
+                if let Some(row) = focused {
+                    self.on_remove_track_row(row, cx);
+                    self.focused_track_row = (count > 1).then(|| row.min(count - 2));
+                }
+            }
+            _ => return,
+        }
+        cx.stop_propagation();
+        cx.notify();
+    }
+
+    fn on_track_row_moved(&mut self, from: usize, to: usize, cx: &mut Context<Self>) {
+        let row_count = self.visible_slot_rows.len();
+        if from == to || from >= row_count || to >= row_count {
+            return;
+        }
+        let slot = self.visible_slot_rows.remove(from);
+        self.visible_slot_rows.insert(to, slot);
+
+        let remap = |row_index: usize| reordered_row_index(row_index, from, to);
+        for error in &mut self.midi_slot_errors {
+            error.row_index = remap(error.row_index);
+        }
+        self.piano_roll_hidden_rows = self.piano_roll_hidden_rows.drain().map(remap).collect();
+        self.overlay_menus.remap_rows(|row| Some(remap(row)));
+        for open_row in [
+            &mut self.analysis_row_open,
+            &mut self.previewing_reference_row,
+        ] {
+            *open_row = open_row.map(remap);
+        }
+
+        self.publish_input_track_layout();
+        cx.notify();
+    }
+
+    /// Keeps what a removal discarded for a few seconds so an accidental ✕ can be reverted.
+    fn offer_track_undo(&mut self, undo: TrackRowUndo, cx: &mut Context<Self>) {
+        self.track_undo = Some(undo);
+        self._track_undo_expiry_task = cx.spawn(async move |view, cx| {
+            Timer::after(Duration::from_millis(TRACK_UNDO_TIMEOUT_MS)).await;
+            let _ = view.update(cx, |view, cx| {
+                view.track_undo = None;
+                cx.notify();
+            });
+        });
+    }
+
+    fn on_undo_track_removal_clicked(&mut self, cx: &mut Context<Self>) {
+        let Some(undo) = self.track_undo.take() else {
+            return;
+        };
+        self._track_undo_expiry_task = Task::ready(());
+
+        let row_index = undo.row_index.min(self.visible_slot_rows.len());
+        self.insert_track_row(row_index, undo.slot);
+        if undo.hidden_in_piano_roll {
+            self.piano_roll_hidden_rows.insert(row_index);
+        }
+        if let Some(references) = undo.references {
+            self.load_midi_use_case.restore_slot(references);
+        }
+
+        self.publish_input_track_layout();
+        cx.notify();
+    }
+
+    fn on_piano_roll_visibility_toggled(&mut self, row_index: usize, cx: &mut Context<Self>) {
+        if self.piano_roll_hidden_rows.contains(&row_index) {
+            self.piano_roll_hidden_rows.remove(&row_index);
+        } else {
+            self.piano_roll_hidden_rows.insert(row_index);
+        }
+        cx.notify();
+    }
+
+    pub(super) fn on_candidate_selected(
+        &mut self,
+        index: usize,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(candidate) = self.generation_candidates.get(index) else {
+            return;
+        };
+        let annotation = self
+            .candidate_annotation(candidate)
+            .unwrap_or_default()
+            .to_string();
+        self.selected_candidate_index = Some(index);
+        self.candidate_annotation_error = None;
+        self.candidate_annotation_input
+            .update(cx, |input, cx| input.set_value(annotation, window, cx));
+        window.focus(&self.candidate_list_focus);
+        cx.notify();
+    }
+
+    /// Up/Down move the selection, Enter applies it, and Space toggles its visibility.
+    fn on_candidate_list_key_down(
+        &mut self,
+        event: &KeyDownEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let count = self.generation_candidates.len();
+        match event.keystroke.key.as_str() {
+            "up" | "down" => {
+                let step = if event.keystroke.key == "up" { -1 } else { 1 };
+                let order = self.candidate_display_order();
+                let position = self
+                    .selected_candidate_index
+                    .and_then(|selected| order.iter().position(|index| *index == selected));
+                if let Some(position) = stepped_list_index(position, order.len(), step) {
+                    self.on_candidate_selected(order[position], window, cx);
+                    self.candidate_list_scroll_handle
+                        .scroll_to_item(position, ScrollStrategy::Top);
+                }
+            }
+            "enter" => self.on_apply_to_daw_clicked(cx),
+            "space" => {
+                if let Some(index) = self.selected_candidate_index.filter(|index| *index < count) {
+                    self.on_candidate_visibility_toggled(index, cx);
+                }
+            }
+            _ => return,
+        }
+        cx.stop_propagation();
+    }
+
+    fn on_save_candidate_annotation_clicked(&mut self, cx: &mut Context<Self>) {
+        let (Some(request_id), Some(candidate)) = (
+            self.candidates_request_id.clone(),
+            self.selected_candidate_index
+                .and_then(|index| self.generation_candidates.get(index)),
+        ) else {
+            return;
+        };
+        let candidate_id = candidate.id.clone();
+        let note = self.candidate_annotation_input.read(cx).value().to_string();
+        // The annotation needs the history entry, so write any deferred ones now.
+        self.flush_deferred_history();
+        self.candidate_annotation_error = self
+            .generation_history
+            .set_annotation(&request_id, &candidate_id, &note)
+            .err()
+            .map(|error| error.to_string());
+        cx.notify();
+    }
+
+    /// Asks the model that generated the selected candidate to describe it, off the UI thread.
+    /// The answer is cached by the candidate's notes, so repeat clicks do not call the model.
+    fn on_explain_candidate_clicked(&mut self, cx: &mut Context<Self>) {
+        let Some(index) = self.selected_candidate_index else {
+            return;
+        };
+        let Some(candidate) = self.generation_candidates.get(index).cloned() else {
+            return;
+        };
+        if !self.candidate_explanations.begin(&candidate) {
+            return;
+        }
+        let model = self
+            .generation_candidate_models
+            .get(index)
+            .or(self.candidates_model.as_ref())
+            .unwrap_or_else(|| self.submission_model.model())
+            .clone();
+        let mode = self.candidates_mode;
+        let service = self.explanation_service.clone();
+        let executor = cx.background_executor().clone();
+        cx.spawn(async move |view, cx| {
+            let explained = candidate.clone();
+            let outcome = executor
+                .spawn(async move { service.explain_candidate(&model, mode, &explained) })
+                .await;
+            let _ = view.update(cx, |view, cx| {
+                view.candidate_explanations.finish(&candidate, outcome);
+                cx.notify();
+            });
+        })
+        .detach();
+        cx.notify();
+    }
+
+    fn selected_candidate_explanation(&self) -> Option<&CandidateExplanation> {
+        let candidate = self
+            .selected_candidate_index
+            .and_then(|index| self.generation_candidates.get(index))?;
+        self.candidate_explanations.get(candidate)
+    }
+
+    fn on_piano_roll_note_lock_toggled(&mut self, note_index: usize, cx: &mut Context<Self>) {
+        let Some(note) = self
+            .selected_candidate_index
+            .and_then(|index| self.generation_candidates.get(index))
+            .and_then(|candidate| candidate.notes.get(note_index))
+            .cloned()
+        else {
+            return;
+        };
+        self.submission_model.toggle_locked_note(&note);
+        cx.notify();
+    }
+
+    fn on_clear_locked_notes_clicked(&mut self, cx: &mut Context<Self>) {
+        self.submission_model.clear_locked_notes();
+        cx.notify();
+    }
+
+    fn on_candidate_visibility_toggled(&mut self, index: usize, cx: &mut Context<Self>) {
+        if self.hidden_candidates.contains(&index) {
+            self.hidden_candidates.remove(&index);
+        } else {
+            self.hidden_candidates.insert(index);
+        }
+        cx.notify();
+    }
+
+    fn candidate_status_label(index: usize) -> &'static str {
+        match index {
+            0 => "Active",
+            1 => "Var A",
+            2 => "Var B",
+            _ => "",
+        }
+    }
+
+    fn on_slot_source_toggled(&mut self, slot: ReferenceSlot, cx: &mut Context<Self>) {
+        let current = self.source_for_slot(slot);
+        let next = match current {
+            ReferenceSource::File => ReferenceSource::Live,
+            ReferenceSource::Live => ReferenceSource::File,
+        };
+        self.on_reference_source_selected(slot, next, cx);
+    }
+
+    fn settings_tab_button_id(tab: SettingsTab) -> &'static str {
+        match tab {
+            SettingsTab::ApiKeys => "settings-tab-api-keys",
+            SettingsTab::MidiSettings => "settings-tab-midi-settings",
+            SettingsTab::General => "settings-tab-general",
+        }
+    }
+
+    fn source_for_slot(&self, slot: ReferenceSlot) -> ReferenceSource {
+        self.input_track_model.source_for_slot(slot)
+    }
+
+    fn channel_mapping_for_slot(&self, slot: ReferenceSlot) -> Option<u8> {
+        self.input_track_model
+            .channel_mappings()
+            .iter()
+            .find(|mapping| mapping.slot == slot)
+            .map(|mapping| mapping.channel)
+    }
+
+    fn recording_enabled_for_channel(&self, channel: u8) -> bool {
+        recording_enabled_for_channel_array(&self.recording_channel_enabled, channel)
+    }
+
+    fn collect_generation_references(&self) -> Vec<MidiReferenceSummary> {
+        let mut references = self.load_midi_use_case.snapshot_references();
+        references.extend(collect_live_references(
+            &self.input_track_model,
+            &self.recording_channel_enabled,
+            &self.midi_input_router,
+            self.live_velocity_profile,
+        ));
+        for reference in &mut references {
+            if self.monophonic_slots.contains(&reference.slot) {
+                force_monophonic(reference);
+            }
+        }
+        references
+    }
+
+    fn ensure_live_channel_mapping_for_slot(&mut self, slot: ReferenceSlot) -> Result<(), String> {
+        let live_channel_mappings = self.input_track_model.live_channel_mappings();
+        let target_channel = resolve_live_channel_mapping_for_slot(
+            slot,
+            self.channel_mapping_for_slot(slot),
+            &live_channel_mappings,
+        )?;
+
+        self.input_track_model
+            .set_channel_mapping(ChannelMapping {
+                slot,
+                channel: target_channel,
+            })
+            .map_err(|error| error.to_string())
+    }
+
+    fn on_reference_source_selected(
+        &mut self,
+        slot: ReferenceSlot,
+        source: ReferenceSource,
+        cx: &mut Context<Self>,
+    ) {
+        self.input_track_error = None;
+
+        if self.source_for_slot(slot) == source {
+            return;
+        }
+
+        if source == ReferenceSource::Live
+            && let Err(message) = self.ensure_live_channel_mapping_for_slot(slot)
+        {
+            self.input_track_error = Some(message);
+            cx.notify();
+            return;
+        }
+
+        if let Err(error) = self.input_track_model.set_source_for_slot(slot, source) {
+            self.input_track_error = Some(error.to_string());
+        } else if let Err(error) = self.sync_midi_input_router_config() {
+            self.input_track_error = Some(error);
+        }
+
+        self.publish_input_track_layout();
+        cx.notify();
+    }
+
+    fn on_analysis_panel_toggled(&mut self, row_index: usize, cx: &mut Context<Self>) {
+        self.analysis_row_open = if self.analysis_row_open == Some(row_index) {
+            None
+        } else {
+            Some(row_index)
+        };
+        cx.notify();
+    }
+
+    fn slot_reference_analysis(&self, slot: ReferenceSlot) -> Option<ReferenceAnalysis> {
+        self.collect_generation_references()
+            .iter()
+            .find(|reference| reference.slot == slot)
+            .map(analyze_reference)
+    }
+
+    /// Analysis for the open panel, recomputed on each render except while the host is playing.
+    fn open_reference_analysis(&mut self) -> Option<(ReferenceSlot, Option<ReferenceAnalysis>)> {
+        let slot = self
+            .analysis_row_open
+            .and_then(|row_index| self.visible_slot_rows.get(row_index).copied())?;
+        let reuse = self.live_capture_transport_playing
+            && self
+                .reference_analysis_cache
+                .as_ref()
+                .is_some_and(|(cached, _)| *cached == slot);
+        if !reuse {
+            self.reference_analysis_cache = Some((slot, self.slot_reference_analysis(slot)));
+        }
+        self.reference_analysis_cache.clone()
+    }
 
-                                                            if note.is_preview {
-                                                                base.border_dashed()
-                                                            } else {
-                                                                base.shadow(vec![gpui::BoxShadow {
-                                                                    color: note_glow_color.opacity(0.45),
-                                                                    offset: gpui::point(px(0.0), px(0.0)),
-                                                                    blur_radius: px(10.0),
-                                                                    spread_radius: px(0.0),
-                                                                }])
-                                                            }
-                                                        },
-                                                    ))
-                                                    .child(
-                                                        div()
-                                                            .id("piano-roll-playhead-line")
-                                                            .absolute()
-                                                            .top(px(0.0))
-                                                            .left(px(playhead_x))
-                                                            .w(px(PIANO_ROLL_PLAYHEAD_WIDTH))
-                                                            .h(px(grid_height))
-                                                            .bg(colors.piano_roll_playhead)
-                                                            .shadow(vec![gpui::BoxShadow {
-                                                                color: colors
-                                                                    .glow_playhead
-                                                                    .opacity(0.5),
-                                                                offset: gpui::point(px(0.0), px(0.0)),
-                                                                blur_radius: px(10.0),
-                                                                spread_radius: px(0.0),
-                                                            }]),
-                                                    ),
-                                            ),
-                                    ),
-                            ),
-                    ),
+    /// Fills the Melody and ChordProgression slots from the top line and lower voices of one take.
+    fn on_split_piano_take_clicked(&mut self, slot: ReferenceSlot, cx: &mut Context<Self>) {
+        self.input_track_error = None;
+        // Read the take before monophonic cleanup, which would already have dropped the chords.
+        let take = match self.source_for_slot(slot) {
+            ReferenceSource::File => self.load_midi_use_case.slot_reference(slot),
+            ReferenceSource::Live => collect_live_references(
+                &self.input_track_model,
+                &self.recording_channel_enabled,
+                &self.midi_input_router,
+                self.live_velocity_profile,
             )
+            .into_iter()
+            .find(|reference| reference.slot == slot),
+        };
+        let split = take.as_ref().and_then(split_melody_and_chords);
+        let Some((melody, chords)) = split else {
+            self.input_track_error =
+                Some("This take has no voices under its top line to split.".to_string());
+            cx.notify();
+            return;
+        };
+
+        for reference in [melody, chords] {
+            let target = reference.slot;
+            if let Err(error) = self
+                .input_track_model
+                .set_source_for_slot(target, ReferenceSource::File)
+            {
+                self.input_track_error = Some(error.to_string());
+            }
+            self.load_midi_use_case.replace_slot_reference(reference);
+            if !self.visible_slot_rows.contains(&target) {
+                self.visible_slot_rows.push(target);
+            }
+        }
+        if let Err(error) = self.sync_midi_input_router_config() {
+            self.input_track_error = Some(error);
+        }
+        self.reference_analysis_cache = None;
+        self.publish_input_track_layout();
+        cx.notify();
     }
 
-    fn clamp_param_level(level: u8) -> u8 {
-        level.clamp(PARAM_LEVEL_MIN, PARAM_LEVEL_MAX)
+    fn on_sketch_panel_toggled(&mut self, slot: ReferenceSlot, cx: &mut Context<Self>) {
+        self.sketch_slot_open = if self.sketch_slot_open == Some(slot) {
+            None
+        } else {
+            Some(slot)
+        };
+        cx.notify();
     }
 
-    fn slider_value_to_param_level(value: SliderValue) -> u8 {
-        let raw = value.end().round() as i16;
-        let clamped = raw.clamp(PARAM_LEVEL_MIN as i16, PARAM_LEVEL_MAX as i16);
-        clamped as u8
+    fn on_sketch_step_toggled(&mut self, step: u16, pitch: u8, cx: &mut Context<Self>) {
+        self.reference_sketch.toggle_step(step, pitch);
+        cx.notify();
     }
 
-    fn param_level_to_percent(level: u8) -> u8 {
-        let level = Self::clamp_param_level(level);
-        let offset = level.saturating_sub(PARAM_LEVEL_MIN) as u16;
-        ((offset * 100) / PARAM_LEVEL_SPAN as u16) as u8
+    fn on_sketch_key_played(&mut self, pitch: u8, cx: &mut Context<Self>) {
+        self.reference_sketch.play_key(pitch);
+        cx.notify();
     }
 
-    fn parameter_slider_control(
-        id: &'static str,
-        label: &'static str,
-        percent: u8,
-        min_label: &'static str,
-        max_label: &'static str,
-        slider: &Entity<SliderState>,
-        colors: ThemeColors,
-    ) -> impl IntoElement {
-        div()
-            .id(id)
-            .flex()
-            .flex_col()
-            .gap_1()
-            .child(
-                div()
-                    .flex()
-                    .items_center()
-                    .justify_between()
-                    .child(div().text_size(px(12.0)).child(label))
-                    .child(
-                        div()
-                            .text_size(px(12.0))
-                            .font_weight(gpui::FontWeight::BOLD)
-                            .text_color(colors.accent_foreground)
-                            .child(format!("{percent}%")),
-                    ),
-            )
-            .child(
-                div().py(px(2.0)).child(
-                    Slider::new(slider)
-                        .horizontal()
-                        .h(px(24.0))
-                        .bg(colors.primary)
-                        .text_color(colors.primary),
-                ),
-            )
-            .child(
-                div()
-                    .flex()
-                    .justify_between()
-                    .text_size(px(10.0))
-                    .text_color(colors.muted_foreground)
-                    .child(min_label)
-                    .child(max_label),
-            )
+    fn on_sketch_rest_clicked(&mut self, cx: &mut Context<Self>) {
+        self.reference_sketch.rest();
+        cx.notify();
+    }
+
+    fn on_sketch_bars_cycled(&mut self, cx: &mut Context<Self>) {
+        self.reference_sketch.cycle_bars();
+        cx.notify();
+    }
+
+    fn on_sketch_octave_shifted(&mut self, up: bool, cx: &mut Context<Self>) {
+        if self.reference_sketch.shift_octave(up) {
+            cx.notify();
+        }
+    }
+
+    fn on_sketch_cleared(&mut self, cx: &mut Context<Self>) {
+        self.reference_sketch.clear();
+        cx.notify();
+    }
+
+    fn on_sketch_written(&mut self, slot: ReferenceSlot, cx: &mut Context<Self>) {
+        let Some(reference) = self.reference_sketch.to_reference(slot) else {
+            self.input_track_error =
+                Some("Play a key or click the grid to sketch a note first.".to_string());
+            cx.notify();
+            return;
+        };
+        self.write_drawn_reference(slot, reference, cx);
+    }
+
+    fn on_drum_step_cycled(&mut self, step: u16, key: u8, cx: &mut Context<Self>) {
+        self.drum_step_pattern.cycle_hit(step, key);
+        cx.notify();
+    }
+
+    fn on_drum_step_count_cycled(&mut self, cx: &mut Context<Self>) {
+        self.drum_step_pattern.cycle_steps();
+        cx.notify();
+    }
+
+    fn on_drum_pattern_cleared(&mut self, cx: &mut Context<Self>) {
+        self.drum_step_pattern.clear();
+        cx.notify();
+    }
+
+    fn on_drum_pattern_written(&mut self, cx: &mut Context<Self>) {
+        let Some(reference) = self.drum_step_pattern.to_reference() else {
+            self.input_track_error = Some("Click a step to add a drum hit first.".to_string());
+            cx.notify();
+            return;
+        };
+        self.write_drawn_reference(ReferenceSlot::DrumPattern, reference, cx);
+    }
+
+    /// Writes the pattern to the temp folder and shows it, ready to drag into the DAW.
+    fn on_drum_pattern_exported(&mut self, cx: &mut Context<Self>) {
+        let folder = std::env::temp_dir().join(LIVE_TAKE_TEMP_FOLDER);
+        let file_stem = self.export_file_stem(None, self.selected_generation_mode);
+        let path = folder.join(drum_step_pattern_file_name(&file_stem));
+        let written = std::fs::create_dir_all(&folder)
+            .map_err(|error| format!("Could not create {}: {error}", folder.display()))
+            .and_then(|()| {
+                write_drum_step_pattern(
+                    &path,
+                    &self.drum_step_pattern,
+                    self.settings_ui_state.tick_resolution(),
+                    &self.export_conductor(),
+                )
+                .map_err(|error| format!("Could not write the drum pattern: {error}"))
+            });
+        match written {
+            Ok(_) => {
+                self.input_track_error = None;
+                cx.reveal_path(&path);
+            }
+            Err(message) => self.input_track_error = Some(message),
+        }
+        cx.notify();
+    }
+
+    /// Replaces what `slot` holds with a reference drawn in the helper, the way a split take
+    /// fills its slots.
+    fn write_drawn_reference(
+        &mut self,
+        slot: ReferenceSlot,
+        reference: MidiReferenceSummary,
+        cx: &mut Context<Self>,
+    ) {
+        self.input_track_error = None;
+        if let Err(error) = self
+            .input_track_model
+            .set_source_for_slot(slot, ReferenceSource::File)
+        {
+            self.input_track_error = Some(error.to_string());
+        }
+        self.load_midi_use_case.replace_slot_reference(reference);
+        if let Err(error) = self.sync_midi_input_router_config() {
+            self.input_track_error = Some(error);
+        }
+        self.reference_analysis_cache = None;
+        self.publish_input_track_layout();
+        cx.notify();
+    }
+
+    fn on_recent_file_selected(&mut self, row_index: usize, path: String, cx: &mut Context<Self>) {
+        self.overlay_menus.close();
+        let Some(slot) = self.visible_slot_rows.get(row_index).copied() else {
+            cx.notify();
+            return;
+        };
+        self.set_midi_slot_file(slot, row_index, path, cx);
+    }
+
+    fn on_slot_type_selected(
+        &mut self,
+        row_index: usize,
+        new_slot: ReferenceSlot,
+        cx: &mut Context<Self>,
+    ) {
+        if row_index < self.visible_slot_rows.len() {
+            self.visible_slot_rows[row_index] = new_slot;
+            self.publish_input_track_layout();
+        }
+        self.overlay_menus.close();
+        cx.notify();
+    }
+
+    fn on_channel_selected(&mut self, slot: ReferenceSlot, channel: u8, cx: &mut Context<Self>) {
+        self.overlay_menus.close();
+        if let Err(error) = self
+            .input_track_model
+            .set_channel_mapping(ChannelMapping { slot, channel })
+        {
+            self.input_track_error = Some(error.to_string());
+        } else if let Err(error) = self.sync_midi_input_router_config() {
+            self.input_track_error = Some(error);
+        }
+        self.publish_input_track_layout();
+        cx.notify();
+    }
+
+    fn on_recording_channel_toggled(&mut self, channel: u8, cx: &mut Context<Self>) {
+        if !(MIDI_CHANNEL_MIN..=MIDI_CHANNEL_MAX).contains(&channel) {
+            return;
+        }
+
+        let index = usize::from(channel - MIDI_CHANNEL_MIN);
+        self.recording_channel_enabled[index] = !self.recording_channel_enabled[index];
+        if let Err(error) = self.sync_midi_input_router_config() {
+            self.input_track_error = Some(error);
+        }
+        cx.notify();
+    }
+
+    fn on_monophonic_toggled(&mut self, slot: ReferenceSlot, cx: &mut Context<Self>) {
+        if !self.monophonic_slots.remove(&slot) {
+            self.monophonic_slots.insert(slot);
+        }
+        cx.notify();
+    }
+
+    fn on_midi_thru_toggled(&mut self, slot: ReferenceSlot, cx: &mut Context<Self>) {
+        if !self.midi_thru_slots.remove(&slot) {
+            self.midi_thru_slots.insert(slot);
+        }
+        self.publish_midi_thru_channels();
+        cx.notify();
+    }
+
+    fn upsert_midi_slot_error(&mut self, error: MidiSlotErrorState) {
+        if let Some(existing) = self
+            .midi_slot_errors
+            .iter_mut()
+            .find(|e| e.slot == error.slot && e.row_index == error.row_index)
+        {
+            *existing = error;
+        } else {
+            self.midi_slot_errors.push(error);
+        }
+    }
+
+    fn clear_midi_slot_error(&mut self, slot: ReferenceSlot) {
+        self.midi_slot_errors
+            .retain(|existing| existing.slot != slot);
+    }
+
+    fn clear_midi_slot_error_for_row(&mut self, slot: ReferenceSlot, row_index: usize) {
+        self.midi_slot_errors
+            .retain(|e| !(e.slot == slot && e.row_index == row_index));
+    }
+
+    fn midi_slot_error_for_row(
+        &self,
+        slot: ReferenceSlot,
+        row_index: usize,
+    ) -> Option<&MidiSlotErrorState> {
+        self.midi_slot_errors
+            .iter()
+            .find(|e| e.slot == slot && e.row_index == row_index)
     }
 
-    fn ai_model_dropdown_items() -> Vec<&'static str> {
-        vec![DEFAULT_ANTHROPIC_MODEL, DEFAULT_OPENAI_COMPAT_MODEL]
+    fn on_project_model_pin_toggled(&mut self, cx: &mut Context<Self>) {
+        self.project_model = match self.project_model {
+            Some(_) => None,
+            None => Some(self.submission_model.model().clone()),
+        };
+        self.send_to_plugin(&HelperControlMessage::ProjectModel {
+            model: self.project_model.clone(),
+        });
+        cx.notify();
     }
 
-    fn generation_mode_label(mode: GenerationMode) -> &'static str {
-        match mode {
-            GenerationMode::Melody => "Melody",
-            GenerationMode::ChordProgression => "Chord Progression",
-            GenerationMode::DrumPattern => "Drum Pattern",
-            GenerationMode::Bassline => "Bassline",
-            GenerationMode::CounterMelody => "Counter Melody",
-            GenerationMode::Harmony => "Harmony",
-            GenerationMode::Continuation => "Continuation",
-        }
+    fn publish_input_track_layout(&mut self) {
+        self.send_to_plugin(&HelperControlMessage::InputTrackLayout {
+            layout: self.input_track_model.to_layout(&self.visible_slot_rows),
+        });
+        // Source and channel edits change which channels a thru-enabled slot listens on.
+        self.publish_midi_thru_channels();
     }
 
-    fn reference_slots() -> &'static [ReferenceSlot] {
-        const SLOTS: [ReferenceSlot; 7] = [
-            ReferenceSlot::Melody,
-            ReferenceSlot::ChordProgression,
-            ReferenceSlot::DrumPattern,
-            ReferenceSlot::Bassline,
-            ReferenceSlot::CounterMelody,
-            ReferenceSlot::Harmony,
-            ReferenceSlot::ContinuationSeed,
-        ];
-        &SLOTS
+    fn publish_midi_thru_channels(&mut self) {
+        self.send_to_plugin(&HelperControlMessage::MidiThruChannels {
+            channels: midi_thru_channels(
+                &self.input_track_model,
+                &self.visible_slot_rows,
+                &self.midi_thru_slots,
+            ),
+        });
     }
 
-    fn reference_slot_label(slot: ReferenceSlot) -> &'static str {
-        match slot {
-            ReferenceSlot::Melody => "Melody",
-            ReferenceSlot::ChordProgression => "Chord Progression",
-            ReferenceSlot::DrumPattern => "Drum Pattern",
-            ReferenceSlot::Bassline => "Bassline",
-            ReferenceSlot::CounterMelody => "Counter Melody",
-            ReferenceSlot::Harmony => "Harmony",
-            ReferenceSlot::ContinuationSeed => "Continuation Seed",
+    /// Sends `message` to the plugin and shows a failure next to Apply, where the plugin
+    /// connection is reported. Returns whether the message was queued.
+    fn send_to_plugin(&mut self, message: &HelperControlMessage) -> bool {
+        let Some(sender) = self.helper_control_sender.as_ref() else {
+            return false;
+        };
+        match sender.send(message) {
+            Ok(()) => true,
+            Err(error) => {
+                self.apply_error = Some(format!("Plugin connection: {error}"));
+                false
+            }
         }
     }
 
-    fn reference_slot_index(slot: ReferenceSlot) -> usize {
-        match slot {
-            ReferenceSlot::Melody => 0,
-            ReferenceSlot::ChordProgression => 1,
-            ReferenceSlot::DrumPattern => 2,
-            ReferenceSlot::Bassline => 3,
-            ReferenceSlot::CounterMelody => 4,
-            ReferenceSlot::Harmony => 5,
-            ReferenceSlot::ContinuationSeed => 6,
-        }
-    }
+    fn sync_midi_input_router_config(&mut self) -> Result<(), String> {
+        self.midi_input_router
+            .update_channel_mapping(self.input_track_model.live_channel_mappings())
+            .map_err(|error| error.to_string())?;
 
-    fn slot_short_label(slot: ReferenceSlot) -> &'static str {
-        match slot {
-            ReferenceSlot::Melody => "Melody",
-            ReferenceSlot::ChordProgression => "Chords",
-            ReferenceSlot::DrumPattern => "Drums",
-            ReferenceSlot::Bassline => "Bass",
-            ReferenceSlot::CounterMelody => "Counter",
-            ReferenceSlot::Harmony => "Harmony",
-            ReferenceSlot::ContinuationSeed => "Seed",
+        for channel in MIDI_CHANNEL_MIN..=MIDI_CHANNEL_MAX {
+            self.midi_input_router
+                .set_recording_channel_enabled(channel, self.recording_enabled_for_channel(channel))
+                .map_err(|error| error.to_string())?;
         }
+
+        self.midi_input_router.update_transport_state(
+            self.live_capture_transport_playing,
+            self.live_capture_playhead_ppq,
+        );
+        Ok(())
     }
 
-    fn slot_source_display_label(&self, slot: ReferenceSlot) -> String {
-        match self.source_for_slot(slot) {
-            ReferenceSource::Live => {
-                let ch = self.channel_mapping_for_slot(slot).unwrap_or(1);
-                format!("Live: CH {ch}")
-            }
-            ReferenceSource::File => {
-                let file_references = self.load_midi_use_case.snapshot_references();
-                let has_file = file_references.iter().any(|r| r.slot == slot);
-                if has_file {
-                    file_references
-                        .iter()
-                        .find(|r| r.slot == slot)
-                        .and_then(|r| r.file.as_ref())
-                        .map(|f| display_file_name_from_path(&f.path))
-                        .unwrap_or_else(|| "File".to_string())
-                } else {
-                    "Drop MIDI file".to_string()
+    fn start_live_capture_polling(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self._live_capture_poll_task = cx.spawn_in(window, async move |view, window| {
+            loop {
+                Timer::after(Duration::from_millis(LIVE_CAPTURE_POLL_INTERVAL_MS)).await;
+                let keep_polling = match view.update_in(window, |view, window, cx| {
+                    view.poll_live_capture_events(window, cx)
+                }) {
+                    Ok(keep_polling) => keep_polling,
+                    Err(_) => break,
+                };
+
+                if !keep_polling {
+                    break;
                 }
             }
-        }
+        });
     }
 
-    fn on_add_track_clicked(&mut self, cx: &mut Context<Self>) {
-        self.add_track_menu_open = !self.add_track_menu_open;
-        cx.notify();
+    fn start_reference_file_watching(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self._reference_watch_task = cx.spawn_in(window, async move |view, window| {
+            loop {
+                Timer::after(Duration::from_millis(REFERENCE_WATCH_POLL_INTERVAL_MS)).await;
+                if view
+                    .update_in(window, |view, _window, cx| {
+                        view.reload_changed_reference_files(cx)
+                    })
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
     }
 
-    fn on_add_track_slot_selected(&mut self, slot: ReferenceSlot, cx: &mut Context<Self>) {
-        self.visible_slot_rows.push(slot);
-        self.add_track_menu_open = false;
-        cx.notify();
-    }
+    /// Reloads file references edited outside Sonant so slot summaries match the file on disk.
+    /// Skipped while the host is playing; pending edits are picked up once it stops.
+    fn reload_changed_reference_files(&mut self, cx: &mut Context<Self>) {
+        if self.live_capture_transport_playing {
+            return;
+        }
+        self.reference_file_watcher
+            .sync(&self.load_midi_use_case.snapshot_references());
+        let changes = self.reference_file_watcher.poll_changes();
+        if changes.is_empty() {
+            return;
+        }
 
-    fn on_remove_track_row(&mut self, row_index: usize, cx: &mut Context<Self>) {
-        if row_index < self.visible_slot_rows.len() {
-            let slot = self.visible_slot_rows[row_index];
-            self.visible_slot_rows.remove(row_index);
-            self.clear_midi_slot_error_for_row(slot, row_index);
-            // adjust row_index in remaining errors for rows that shifted down
-            for error in &mut self.midi_slot_errors {
-                if error.slot == slot && error.row_index > row_index {
-                    error.row_index -= 1;
+        for change in changes {
+            let row_index = self
+                .visible_slot_rows
+                .iter()
+                .position(|slot| *slot == change.slot)
+                .unwrap_or(0);
+            match self
+                .load_midi_use_case
+                .execute(LoadMidiCommand::ReloadFile {
+                    slot: change.slot,
+                    path: change.path.clone(),
+                }) {
+                Ok(_) => {
+                    self.clear_midi_slot_error_for_row(change.slot, row_index);
+                    self.reference_reload_notice = Some(format!(
+                        "Reloaded {} after it changed on disk.",
+                        display_file_name_from_path(&change.path)
+                    ));
+                }
+                Err(error) => {
+                    self.upsert_midi_slot_error(MidiSlotErrorState::from_load_error(
+                        change.slot,
+                        row_index,
+                        &change.path,
+                        &error,
+                    ));
                 }
             }
-            // adjust piano_roll_hidden_rows: remove deleted row, shift down higher indices
-            self.piano_roll_hidden_rows.remove(&row_index);
-            let shifted: std::collections::HashSet<usize> = self
-                .piano_roll_hidden_rows
-                .drain()
-                .map(|i| if i > row_index { i - 1 } else { i })
-                .collect();
-            self.piano_roll_hidden_rows = shifted;
-            // if no more rows for this slot, clear the underlying file references
-            if !self.visible_slot_rows.contains(&slot) {
-                self.on_clear_midi_slot_clicked(slot, cx);
+        }
+        cx.notify();
+    }
+
+    fn poll_live_capture_events(&mut self, window: &mut Window, cx: &mut Context<Self>) -> bool {
+        let _ = self.live_midi_capture.ingest_available();
+        // Messages the plugin socket could not take at once go out as it drains.
+        if let Some(Err(error)) = self
+            .helper_control_sender
+            .as_ref()
+            .map(HelperControlIpcSender::flush)
+        {
+            self.apply_error = Some(format!("Plugin connection: {error}"));
+            cx.notify();
+        }
+        let mut routed_any = false;
+        let mut loop_wrapped = false;
+
+        // Drain every batch first so heavy input causes one redraw per poll, not one per batch.
+        while let Some(batch) = self
+            .live_midi_capture
+            .poll_batch(LIVE_CAPTURE_MAX_EVENTS_PER_POLL)
+        {
+            let drained_count = batch.events.len() + batch.coalesced_count;
+            loop_wrapped |= self.route_live_events_to_router(batch.events);
+            routed_any = true;
+
+            if drained_count < LIVE_CAPTURE_MAX_EVENTS_PER_POLL {
+                break;
             }
+        }
+
+        // A host trigger generates from whatever the prompt currently says.
+        let host_triggered = self.live_midi_capture.take_generate_trigger()
+            && !self.generation_status.is_submitting_or_running();
+        if host_triggered || (loop_wrapped && self.should_auto_generate_on_loop_wrap()) {
+            self.on_generate_clicked(window, cx);
+        } else if routed_any {
             cx.notify();
         }
+
+        true
     }
 
-    fn on_piano_roll_visibility_toggled(&mut self, row_index: usize, cx: &mut Context<Self>) {
-        if self.piano_roll_hidden_rows.contains(&row_index) {
-            self.piano_roll_hidden_rows.remove(&row_index);
-        } else {
-            self.piano_roll_hidden_rows.insert(row_index);
-        }
+    fn should_auto_generate_on_loop_wrap(&self) -> bool {
+        self.auto_generate_on_loop
+            && !self.generation_status.is_submitting_or_running()
+            && self
+                .recording_channel_enabled
+                .iter()
+                .any(|enabled| *enabled)
+    }
+
+    fn on_auto_generate_on_loop_toggled(&mut self, cx: &mut Context<Self>) {
+        self.auto_generate_on_loop = !self.auto_generate_on_loop;
         cx.notify();
     }
 
-    fn on_candidate_selected(&mut self, index: usize, cx: &mut Context<Self>) {
-        if index < self.generation_candidates.len() {
-            self.selected_candidate_index = Some(index);
-            cx.notify();
-        }
+    fn on_live_velocity_profile_toggled(
+        &mut self,
+        profile: VelocityProfile,
+        cx: &mut Context<Self>,
+    ) {
+        self.live_velocity_profile = match self.live_velocity_profile {
+            Some((active, _)) if active == profile => None,
+            _ => Some((profile, velocity_profile_seed())),
+        };
+        cx.notify();
     }
 
-    fn on_candidate_visibility_toggled(&mut self, index: usize, cx: &mut Context<Self>) {
-        if self.hidden_candidates.contains(&index) {
-            self.hidden_candidates.remove(&index);
-        } else {
-            self.hidden_candidates.insert(index);
-        }
+    /// Cycles the metric's sort from descending to ascending to off.
+    fn on_candidate_sort_clicked(&mut self, metric: CandidateMetric, cx: &mut Context<Self>) {
+        self.candidate_sort = match self.candidate_sort {
+            Some((active, true)) if active == metric => Some((metric, false)),
+            Some((active, false)) if active == metric => None,
+            _ => Some((metric, true)),
+        };
         cx.notify();
     }
 
-    fn candidate_display_name(index: usize) -> String {
-        match index {
-            0 => "Pattern 1".to_string(),
-            1 => "Variation A".to_string(),
-            2 => "Variation B".to_string(),
-            n => format!("Pattern {}", n + 1),
-        }
+    fn on_candidate_top_half_toggled(&mut self, cx: &mut Context<Self>) {
+        self.candidate_top_half_only = !self.candidate_top_half_only;
+        cx.notify();
     }
 
-    fn candidate_status_label(index: usize) -> &'static str {
-        match index {
-            0 => "Active",
-            1 => "Var A",
-            2 => "Var B",
-            _ => "",
+    /// One row of the candidate list, built only while it is scrolled into view.
+    fn candidate_row(
+        &self,
+        index: usize,
+        colors: ThemeColors,
+        cx: &Context<Self>,
+    ) -> Stateful<Div> {
+        let candidate = &self.generation_candidates[index];
+        let is_selected = self.selected_candidate_index == Some(index);
+        let is_visible = !self.hidden_candidates.contains(&index);
+        let display_name = self
+            .comparison_candidate_label(index)
+            .unwrap_or_else(|| candidate_name(candidate, self.candidates_mode));
+        let status_label = Self::candidate_status_label(index);
+        let annotation = self.candidate_annotation(candidate).map(str::to_string);
+        let is_applied = self.is_candidate_applied(candidate);
+        let confidence = self.candidate_confidence(candidate).cloned();
+        let off_beat_ratio = candidate.off_beat_ratio();
+        let mut rhythm_label = format!(
+            "sync {} · {:.0}% off-beat",
+            syncopation_level_for_off_beat_ratio(off_beat_ratio),
+            off_beat_ratio * 100.0
+        );
+        if let Some((metric, _)) = self.candidate_sort {
+            let value = CandidateMetrics::of(candidate).value(metric);
+            rhythm_label.push_str(&format!(" · {}", metric.format_value(value)));
+        }
+        if let Some(reference) = self.candidates_reference.as_ref() {
+            let similarity = ReferenceSimilarity::between(candidate, reference);
+            rhythm_label.push_str(&format!(" · {}% like ref", similarity.score()));
+        }
+
+        div()
+            .id(("candidate-row", index))
+            .flex()
+            .items_center()
+            .h(px(32.0))
+            .bg(if is_selected {
+                colors.success_foreground.opacity(0.08)
+            } else {
+                colors.panel_background
+            })
+            .hover(|s| s.bg(colors.input_background))
+            .cursor_pointer()
+            .on_click(cx.listener(move |this, _, window, cx| {
+                this.on_candidate_selected(index, window, cx);
+            }))
+            .when_some(annotation.clone(), |el, note| {
+                el.tooltip(move |window, cx| Tooltip::new(note.clone()).build(window, cx))
+            })
+            // Green left border (active only)
+            .child(div().w(px(3.0)).h_full().flex_none().bg(if is_selected {
+                colors.success_foreground
+            } else {
+                gpui::transparent_black()
+            }))
+            // Drag handle
+            .child(
+                div()
+                    .w(px(18.0))
+                    .flex()
+                    .items_center()
+                    .justify_center()
+                    .flex_none()
+                    .text_size(px(12.0))
+                    .text_color(colors.muted_foreground)
+                    .child("⠿"),
+            )
+            // Radio indicator
+            .child(
+                div()
+                    .w(px(16.0))
+                    .flex()
+                    .items_center()
+                    .justify_center()
+                    .flex_none()
+                    .text_size(px(12.0))
+                    .text_color(if is_selected {
+                        colors.success_foreground
+                    } else {
+                        colors.muted_foreground
+                    })
+                    .child(if is_selected { "◉" } else { "◌" }),
+            )
+            // Pattern name + status label
+            .child(
+                div()
+                    .flex_1()
+                    .flex()
+                    .items_center()
+                    .gap_2()
+                    .min_w(px(0.0))
+                    .child(
+                        div()
+                            .text_size(px(11.0))
+                            .text_color(if is_selected {
+                                colors.surface_foreground
+                            } else {
+                                colors.muted_foreground
+                            })
+                            .font_weight(if is_selected {
+                                gpui::FontWeight::BOLD
+                            } else {
+                                gpui::FontWeight::NORMAL
+                            })
+                            .overflow_hidden()
+                            .child(display_name),
+                    )
+                    .when(!status_label.is_empty(), |el| {
+                        el.child(
+                            div()
+                                .flex_none()
+                                .px(px(4.0))
+                                .py(px(1.0))
+                                .rounded(px(3.0))
+                                .text_size(px(9.0))
+                                .text_color(if is_selected {
+                                    colors.success_foreground
+                                } else {
+                                    colors.muted_foreground
+                                })
+                                .font_weight(gpui::FontWeight::BOLD)
+                                .border_1()
+                                .border_color(if is_selected {
+                                    colors.success_foreground
+                                } else {
+                                    colors.panel_border
+                                })
+                                .child(status_label),
+                        )
+                    })
+                    .when_some(confidence, |el, confidence| {
+                        let color = match confidence.level() {
+                            ConfidenceLevel::High => colors.success_foreground,
+                            ConfidenceLevel::Medium => colors.warning_foreground,
+                            ConfidenceLevel::Low => colors.error_foreground,
+                        };
+                        let details = if confidence.issues.is_empty() {
+                            "No issues found".to_string()
+                        } else {
+                            confidence.issues.join("\n")
+                        };
+                        el.child(
+                            div()
+                                .id(("candidate-confidence", index))
+                                .flex_none()
+                                .px(px(4.0))
+                                .py(px(1.0))
+                                .rounded(px(3.0))
+                                .text_size(px(9.0))
+                                .font_weight(gpui::FontWeight::BOLD)
+                                .text_color(color)
+                                .border_1()
+                                .border_color(color)
+                                .tooltip(move |window, cx| {
+                                    Tooltip::new(details.clone()).build(window, cx)
+                                })
+                                .child(format!("{}%", confidence.score)),
+                        )
+                    })
+                    .child(
+                        div()
+                            .flex_none()
+                            .text_size(px(9.0))
+                            .text_color(colors.muted_foreground)
+                            .child(rhythm_label),
+                    )
+                    .when(annotation.is_some(), |el| {
+                        el.child(
+                            div()
+                                .flex_none()
+                                .text_size(px(10.0))
+                                .text_color(colors.muted_foreground)
+                                .child("✎"),
+                        )
+                    })
+                    .when(is_applied, |el| {
+                        el.child(
+                            div()
+                                .flex_none()
+                                .text_size(px(9.0))
+                                .font_weight(gpui::FontWeight::BOLD)
+                                .text_color(colors.success_foreground)
+                                .child("IN DAW"),
+                        )
+                    }),
+            )
+            // Action buttons
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap_1()
+                    .pr_2()
+                    .pl_2()
+                    .h(px(24.0))
+                    .border_l_1()
+                    .border_color(colors.panel_border)
+                    // Visibility toggle
+                    .child(
+                        div()
+                            .id(("candidate-visible", index))
+                            .w(px(20.0))
+                            .h(px(20.0))
+                            .flex()
+                            .items_center()
+                            .justify_center()
+                            .rounded(px(999.0))
+                            .text_size(px(11.0))
+                            .text_color(if is_visible {
+                                colors.surface_foreground
+                            } else {
+                                colors.panel_border
+                            })
+                            .cursor_pointer()
+                            .hover(|s| s.text_color(colors.surface_foreground))
+                            .on_click(cx.listener(move |this, _, _window, cx| {
+                                this.on_candidate_visibility_toggled(index, cx);
+                            }))
+                            .child(if is_visible { "◉" } else { "◌" }),
+                    )
+                    // More button
+                    .child(
+                        div()
+                            .id(("candidate-more", index))
+                            .w(px(20.0))
+                            .h(px(20.0))
+                            .flex()
+                            .items_center()
+                            .justify_center()
+                            .rounded(px(999.0))
+                            .text_size(px(14.0))
+                            .text_color(colors.muted_foreground)
+                            .cursor_pointer()
+                            .hover(|s| s.text_color(colors.surface_foreground))
+                            .child("⋮"),
+                    ),
+            )
+    }
+
+    /// Indices into `generation_candidates` in the order the list shows them.
+    fn candidate_display_order(&self) -> Vec<usize> {
+        match self.candidate_sort {
+            Some((metric, descending)) => rank_candidates(
+                &self.generation_candidates,
+                metric,
+                descending,
+                self.candidate_top_half_only,
+            ),
+            None => (0..self.generation_candidates.len()).collect(),
         }
     }
 
-    fn on_slot_source_toggled(&mut self, slot: ReferenceSlot, cx: &mut Context<Self>) {
-        let current = self.source_for_slot(slot);
-        let next = match current {
-            ReferenceSource::File => ReferenceSource::Live,
-            ReferenceSource::Live => ReferenceSource::File,
+    fn on_candidate_velocity_profile_clicked(
+        &mut self,
+        profile: VelocityProfile,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(candidate) = self
+            .selected_candidate_index
+            .and_then(|index| self.generation_candidates.get_mut(index))
+        else {
+            return;
         };
-        self.on_reference_source_selected(slot, next, cx);
+        let resolution = candidate.tick_resolution;
+        profile.apply_to_notes(&mut candidate.notes, resolution, velocity_profile_seed());
+        cx.notify();
     }
 
-    fn settings_tab_button_id(tab: SettingsTab) -> &'static str {
-        match tab {
-            SettingsTab::ApiKeys => "settings-tab-api-keys",
-            SettingsTab::MidiSettings => "settings-tab-midi-settings",
-            SettingsTab::General => "settings-tab-general",
+    /// A/B switch between the selected candidate's post-processed notes and the raw model output.
+    fn on_processing_bypass_toggled(&mut self, cx: &mut Context<Self>) {
+        let Some(candidate) = self
+            .selected_candidate_index
+            .and_then(|index| self.generation_candidates.get_mut(index))
+        else {
+            return;
+        };
+        let bypassed = !candidate.is_processing_bypassed();
+        if candidate.set_processing_bypassed(bypassed) {
+            cx.notify();
         }
     }
 
-    fn source_for_slot(&self, slot: ReferenceSlot) -> ReferenceSource {
-        self.input_track_model.source_for_slot(slot)
+    fn on_transpose_references_toggled(&mut self, cx: &mut Context<Self>) {
+        let enabled = !self.submission_model.transpose_references();
+        self.submission_model.set_transpose_references(enabled);
+        cx.notify();
     }
 
-    fn channel_mapping_for_slot(&self, slot: ReferenceSlot) -> Option<u8> {
-        self.input_track_model
-            .channel_mappings()
-            .iter()
-            .find(|mapping| mapping.slot == slot)
-            .map(|mapping| mapping.channel)
+    fn on_auto_apply_toggled(&mut self, cx: &mut Context<Self>) {
+        self.auto_apply_first_candidate = !self.auto_apply_first_candidate;
+        cx.notify();
     }
 
-    fn recording_enabled_for_channel(&self, channel: u8) -> bool {
-        recording_enabled_for_channel_array(&self.recording_channel_enabled, channel)
+    fn on_note_expression_output_toggled(&mut self, cx: &mut Context<Self>) {
+        self.note_expression_output = !self.note_expression_output;
+        cx.notify();
     }
 
-    fn collect_generation_references(&self) -> Vec<MidiReferenceSummary> {
-        let mut references = self.load_midi_use_case.snapshot_references();
-        references.extend(collect_live_references(
-            &self.input_track_model,
-            &self.recording_channel_enabled,
-            &self.midi_input_router,
-        ));
-        references
+    fn on_apply_to_daw_clicked(&mut self, cx: &mut Context<Self>) {
+        let Some(candidate) = self
+            .selected_candidate_index
+            .and_then(|index| self.generation_candidates.get(index))
+            .cloned()
+        else {
+            return;
+        };
+        self.apply_candidate_to_daw(&candidate);
+        cx.notify();
     }
 
-    fn ensure_live_channel_mapping_for_slot(&mut self, slot: ReferenceSlot) -> Result<(), String> {
-        let live_channel_mappings = self.input_track_model.live_channel_mappings();
-        let target_channel = resolve_live_channel_mapping_for_slot(
-            slot,
-            self.channel_mapping_for_slot(slot),
-            &live_channel_mappings,
-        )?;
-
-        self.input_track_model
-            .set_channel_mapping(ChannelMapping {
-                slot,
-                channel: target_channel,
-            })
-            .map_err(|error| error.to_string())
+    /// Sends the candidate to the Apply destination chosen in Settings.
+    fn apply_candidate_to_daw(&mut self, candidate: &GenerationCandidate) {
+        self.apply_candidate_to(self.settings_ui_state.apply_destination(), candidate);
     }
 
-    fn on_reference_source_selected(
+    fn apply_candidate_to(
         &mut self,
-        slot: ReferenceSlot,
-        source: ReferenceSource,
-        cx: &mut Context<Self>,
+        destination: ApplyDestination,
+        candidate: &GenerationCandidate,
     ) {
-        self.input_track_error = None;
-
-        if self.source_for_slot(slot) == source {
-            return;
+        let mut clip = AppliedClip {
+            note_expressions: self.note_expression_output,
+            ..AppliedClip::from_candidate(candidate)
+        };
+        if self.candidates_mode == Some(GenerationMode::DrumPattern) {
+            clip = clip.with_drum_chokes(&DrumMap::general_midi());
         }
-
-        if source == ReferenceSource::Live
-            && let Err(message) = self.ensure_live_channel_mapping_for_slot(slot)
-        {
-            self.input_track_error = Some(message);
-            cx.notify();
-            return;
+        let dir = self.settings_ui_state.auto_save_folder();
+        let conductor = self.export_conductor();
+        let file_stem = self.export_file_stem(
+            self.candidates_request_id.as_deref(),
+            self.candidates_mode
+                .unwrap_or(self.selected_generation_mode),
+        );
+        let file_target = dir.as_deref().map(|dir| ApplyFileTarget {
+            dir,
+            file_stem: &file_stem,
+            chords: &self.generation_chords,
+            resolution: self.settings_ui_state.tick_resolution(),
+            conductor: &conductor,
+            program: self.export_program_for_mode(
+                self.candidates_mode
+                    .unwrap_or(self.selected_generation_mode),
+            ),
+        });
+        let plugin = self
+            .helper_control_sender
+            .as_ref()
+            .map(|sender| sender as &dyn AppliedClipSink);
+
+        match dispatch_apply(destination, candidate, clip, plugin, file_target) {
+            Ok(outcome) => {
+                self.apply_error = None;
+                if outcome.file.is_some() {
+                    self.enforce_auto_save_quota();
+                }
+                if outcome.sent_to_plugin {
+                    self.previewing_reference_row = None;
+                    self.applied_candidate = self
+                        .candidates_request_id
+                        .clone()
+                        .map(|request_id| (request_id, candidate.id.clone()));
+                }
+            }
+            Err(error) => self.apply_error = Some(error.to_string()),
         }
+    }
 
-        if let Err(error) = self.input_track_model.set_source_for_slot(slot, source) {
-            self.input_track_error = Some(error.to_string());
-        } else if let Err(error) = self.sync_midi_input_router_config() {
-            self.input_track_error = Some(error);
+    /// The slot's captured take with the same velocity and sustain processing its reference
+    /// gets, positioned by the host playhead.
+    fn processed_live_take(&self, slot: ReferenceSlot) -> Vec<LiveInputEvent> {
+        let mut events = self.midi_input_router.snapshot_reference(slot);
+        if let Some((profile, seed)) = self.live_velocity_profile {
+            apply_velocity_profile_to_live_events(&mut events, profile, seed);
         }
-
-        cx.notify();
+        apply_sustain_pedal_to_live_events(&events)
     }
 
-    fn on_channel_menu_toggled(&mut self, row_index: usize, cx: &mut Context<Self>) {
-        self.channel_menu_open = if self.channel_menu_open == Some(row_index) {
-            None
-        } else {
-            Some(row_index)
-        };
-        cx.notify();
+    fn write_live_take_file(&self, slot: ReferenceSlot, path: &Path) -> Result<(), String> {
+        write_live_take(
+            path,
+            &self.processed_live_take(slot),
+            self.settings_ui_state.tick_resolution(),
+            &self.export_conductor(),
+            self.settings_ui_state.export_program(slot),
+        )
+        .map(|_| ())
+        .map_err(|error| format!("Could not write the take: {error}"))
     }
 
-    fn on_slot_type_menu_toggled(&mut self, row_index: usize, cx: &mut Context<Self>) {
-        self.slot_type_menu_open = if self.slot_type_menu_open == Some(row_index) {
-            None
+    /// GPUI cannot start a drag into another application, so the row's MIDI is shown in the
+    /// file manager instead, ready to be dragged into the DAW. A FILE row reveals its loaded
+    /// file; a LIVE row's take is written to the temp folder first.
+    fn on_reveal_track_row_midi_clicked(
+        &mut self,
+        slot: ReferenceSlot,
+        row_index: usize,
+        cx: &mut Context<Self>,
+    ) {
+        let path = if self.input_track_model.source_for_slot(slot) == ReferenceSource::Live {
+            let folder = std::env::temp_dir().join(LIVE_TAKE_TEMP_FOLDER);
+            let file_stem = self.export_file_stem(None, self.selected_generation_mode);
+            let path = folder.join(staged_live_take_file_name(&file_stem, slot));
+            let written = std::fs::create_dir_all(&folder)
+                .map_err(|error| format!("Could not create {}: {error}", folder.display()))
+                .and_then(|()| self.write_live_take_file(slot, &path));
+            if let Err(message) = written {
+                self.upsert_midi_slot_error(MidiSlotErrorState::non_retryable(
+                    slot, row_index, message,
+                ));
+                cx.notify();
+                return;
+            }
+            path
         } else {
-            Some(row_index)
+            let Some(file) = self
+                .load_midi_use_case
+                .slot_reference(slot)
+                .and_then(|reference| reference.file)
+            else {
+                return;
+            };
+            PathBuf::from(file.path)
         };
+        self.clear_midi_slot_error_for_row(slot, row_index);
+        cx.reveal_path(&path);
         cx.notify();
     }
 
-    fn on_slot_type_selected(
+    /// Writes the slot's take to a chosen folder and switches the slot to FILE on that file,
+    /// so the capture survives the session and can be edited like any other reference.
+    fn on_save_take_as_file_clicked(
         &mut self,
+        slot: ReferenceSlot,
         row_index: usize,
-        new_slot: ReferenceSlot,
+        window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        if row_index < self.visible_slot_rows.len() {
-            self.visible_slot_rows[row_index] = new_slot;
-        }
-        self.slot_type_menu_open = None;
-        cx.notify();
-    }
+        let events = self.processed_live_take(slot);
+        let resolution = self.settings_ui_state.tick_resolution();
+        let conductor = self.export_conductor();
+        let program = self.settings_ui_state.export_program(slot);
+        let file_stem = self.export_file_stem(None, self.selected_generation_mode);
+        let saved_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
 
-    fn on_channel_selected(&mut self, slot: ReferenceSlot, channel: u8, cx: &mut Context<Self>) {
-        self.channel_menu_open = None;
-        if let Err(error) = self
-            .input_track_model
-            .set_channel_mapping(ChannelMapping { slot, channel })
-        {
-            self.input_track_error = Some(error.to_string());
-        } else if let Err(error) = self.sync_midi_input_router_config() {
-            self.input_track_error = Some(error);
-        }
-        cx.notify();
+        let receiver = cx.prompt_for_paths(PathPromptOptions {
+            files: false,
+            directories: true,
+            multiple: false,
+            prompt: Some(TAKE_SAVE_PICKER_PROMPT.into()),
+        });
+
+        self._take_save_task = cx.spawn_in(window, async move |view, window| {
+            let Ok(result) = receiver.await else {
+                return;
+            };
+            let outcome = match result {
+                Ok(Some(paths)) => {
+                    let Some(dir) = paths.into_iter().next() else {
+                        return;
+                    };
+                    let path = dir.join(live_take_file_name(&file_stem, slot, saved_at));
+                    write_live_take(&path, &events, resolution, &conductor, program)
+                        .map(|_| path)
+                        .map_err(|error| format!("Could not save the take: {error}"))
+                }
+                Ok(None) => return,
+                Err(error) => Err(format!("Could not open the folder dialog: {error}")),
+            };
+            let _ = view.update_in(window, |view, _window, cx| match outcome {
+                Ok(path) => {
+                    view.on_reference_source_selected(slot, ReferenceSource::File, cx);
+                    if view.source_for_slot(slot) == ReferenceSource::File {
+                        view.set_midi_slot_file(
+                            slot,
+                            row_index,
+                            path.to_string_lossy().to_string(),
+                            cx,
+                        );
+                    }
+                }
+                Err(message) => {
+                    view.upsert_midi_slot_error(MidiSlotErrorState::non_retryable(
+                        slot, row_index, message,
+                    ));
+                    cx.notify();
+                }
+            });
+        });
     }
 
-    fn on_recording_channel_toggled(&mut self, channel: u8, cx: &mut Context<Self>) {
-        if !(MIDI_CHANNEL_MIN..=MIDI_CHANNEL_MAX).contains(&channel) {
+    fn on_reference_preview_clicked(&mut self, row_index: usize, cx: &mut Context<Self>) {
+        if self.previewing_reference_row == Some(row_index) {
+            self.stop_reference_preview();
+            cx.notify();
+            return;
+        }
+        let Some(slot) = self.visible_slot_rows.get(row_index).copied() else {
+            return;
+        };
+        if self.helper_control_sender.is_none() {
+            self.input_track_error =
+                Some("Reference preview needs the plugin connection.".to_string());
+            cx.notify();
             return;
         }
+        let Some(reference) = self
+            .collect_generation_references()
+            .into_iter()
+            .find(|reference| reference.slot == slot)
+        else {
+            return;
+        };
 
-        let index = usize::from(channel - MIDI_CHANNEL_MIN);
-        self.recording_channel_enabled[index] = !self.recording_channel_enabled[index];
-        if let Err(error) = self.sync_midi_input_router_config() {
-            self.input_track_error = Some(error);
+        let notes = Self::collect_reference_generated_notes(&reference);
+        if notes.is_empty() {
+            self.input_track_error = Some(format!(
+                "{} has no notes to preview.",
+                Self::reference_slot_label(slot)
+            ));
+            cx.notify();
+            return;
+        }
+        let resolution =
+            TickResolution::nearest_common(Self::reference_ticks_per_beat(&reference, &notes));
+        let clip = AppliedClip {
+            note_expressions: self.note_expression_output,
+            ..AppliedClip::from_notes(&notes, &[], reference.bars, resolution)
+        };
+        if self.send_to_plugin(&HelperControlMessage::AppliedClip { clip: Some(clip) }) {
+            self.previewing_reference_row = Some(row_index);
         }
         cx.notify();
     }
 
-    fn upsert_midi_slot_error(&mut self, error: MidiSlotErrorState) {
-        if let Some(existing) = self
-            .midi_slot_errors
-            .iter_mut()
-            .find(|e| e.slot == error.slot && e.row_index == error.row_index)
-        {
-            *existing = error;
-        } else {
-            self.midi_slot_errors.push(error);
+    /// Ends an audition and puts the applied candidate, if any, back on the plugin output.
+    fn stop_reference_preview(&mut self) {
+        if self.previewing_reference_row.take().is_none() {
+            return;
         }
-    }
-
-    fn clear_midi_slot_error(&mut self, slot: ReferenceSlot) {
-        self.midi_slot_errors
-            .retain(|existing| existing.slot != slot);
-    }
-
-    fn clear_midi_slot_error_for_row(&mut self, slot: ReferenceSlot, row_index: usize) {
-        self.midi_slot_errors
-            .retain(|e| !(e.slot == slot && e.row_index == row_index));
-    }
-
-    fn midi_slot_error_for_row(
-        &self,
-        slot: ReferenceSlot,
-        row_index: usize,
-    ) -> Option<&MidiSlotErrorState> {
-        self.midi_slot_errors
+        let applied = self
+            .generation_candidates
             .iter()
-            .find(|e| e.slot == slot && e.row_index == row_index)
-    }
-
-    fn sync_midi_input_router_config(&mut self) -> Result<(), String> {
-        self.midi_input_router
-            .update_channel_mapping(self.input_track_model.live_channel_mappings())
-            .map_err(|error| error.to_string())?;
-
-        for channel in MIDI_CHANNEL_MIN..=MIDI_CHANNEL_MAX {
-            self.midi_input_router
-                .set_recording_channel_enabled(channel, self.recording_enabled_for_channel(channel))
-                .map_err(|error| error.to_string())?;
-        }
-
-        self.midi_input_router.update_transport_state(
-            self.live_capture_transport_playing,
-            self.live_capture_playhead_ppq,
-        );
-        Ok(())
-    }
-
-    fn start_live_capture_polling(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        self._live_capture_poll_task = cx.spawn_in(window, async move |view, window| {
-            loop {
-                Timer::after(Duration::from_millis(LIVE_CAPTURE_POLL_INTERVAL_MS)).await;
-                let keep_polling = match view.update_in(window, |view, _window, cx| {
-                    view.poll_live_capture_events(cx)
-                }) {
-                    Ok(keep_polling) => keep_polling,
-                    Err(_) => break,
-                };
-
-                if !keep_polling {
-                    break;
-                }
-            }
-        });
-    }
-
-    fn poll_live_capture_events(&mut self, cx: &mut Context<Self>) -> bool {
-        let _ = self.live_midi_capture.ingest_available();
-        let mut routed_any = false;
-
-        loop {
-            let events = self
-                .live_midi_capture
-                .poll_events(LIVE_CAPTURE_MAX_EVENTS_PER_POLL);
-            let event_count = events.len();
-            if event_count == 0 {
-                break;
-            }
-
-            self.route_live_events_to_router(events);
-            routed_any = true;
-
-            if event_count < LIVE_CAPTURE_MAX_EVENTS_PER_POLL {
-                break;
+            .find(|candidate| self.is_candidate_applied(candidate))
+            .cloned();
+        match applied {
+            Some(candidate) => self.apply_candidate_to(ApplyDestination::Plugin, &candidate),
+            None => {
+                self.send_to_plugin(&HelperControlMessage::AppliedClip { clip: None });
             }
         }
-
-        if routed_any {
-            cx.notify();
-        }
-
-        true
     }
 
-    fn route_live_events_to_router(&mut self, events: Vec<LiveInputEvent>) {
+    /// Routes captured events and reports whether the host loop wrapped during the batch.
+    fn route_live_events_to_router(&mut self, events: Vec<LiveInputEvent>) -> bool {
         let mut routable_events = Vec::with_capacity(events.len());
         let mut last_transport_state = None;
+        let mut loop_wrapped = false;
 
         for event in events {
             last_transport_state = Some((event.is_transport_playing, event.playhead_ppq));
+            loop_wrapped |= event.is_loop_wrap_marker() && event.is_transport_playing;
 
             let Some(channel) = midi_channel_from_status(event.data[0]) else {
                 continue;
@@ -2008,6 +5810,9 @@ impl SonantMainWindow {
             .push_live_events_with_transport(&routable_events);
 
         if let Some((is_transport_playing, playhead_ppq)) = last_transport_state {
+            if self.live_capture_transport_playing && !is_transport_playing {
+                self.flush_deferred_history();
+            }
             self.live_capture_transport_playing = is_transport_playing;
             self.live_capture_playhead_ppq = playhead_ppq;
             if Some((is_transport_playing, playhead_ppq)) != last_routable_transport_state {
@@ -2015,6 +5820,8 @@ impl SonantMainWindow {
                     .update_transport_state(is_transport_playing, playhead_ppq);
             }
         }
+
+        loop_wrapped
     }
 
     #[allow(dead_code)]
@@ -2024,20 +5831,38 @@ impl SonantMainWindow {
         summarize_live_recording(&events, metrics.bar_count)
     }
 
-    fn start_update_polling(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        self._update_poll_task = cx.spawn_in(window, async move |view, window| {
-            loop {
-                Timer::after(Duration::from_millis(JOB_UPDATE_POLL_INTERVAL_MS)).await;
-                let keep_polling = match view
-                    .update_in(window, |view, _window, cx| view.poll_generation_updates(cx))
-                {
-                    Ok(keep_polling) => keep_polling,
-                    Err(_) => break,
-                };
+    /// Renders job updates as soon as the worker reports them. The wait wakes on its own every
+    /// idle tick so countdowns keep moving, and during playback updates are spaced out to keep
+    /// helper CPU use low.
+    fn start_update_polling(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let job_manager = Arc::clone(&self.generation_job_manager);
+        let executor = cx.background_executor().clone();
+        self._update_poll_task = cx.spawn_in(window, async move |view, window| {
+            loop {
+                let job_manager = Arc::clone(&job_manager);
+                executor
+                    .spawn(async move {
+                        job_manager
+                            .wait_for_updates(Duration::from_millis(JOB_UPDATE_IDLE_TICK_MS));
+                    })
+                    .await;
+                let (keep_polling, is_transport_playing) =
+                    match view.update_in(window, |view, window, cx| {
+                        (
+                            view.poll_generation_updates(window, cx),
+                            view.live_capture_transport_playing,
+                        )
+                    }) {
+                        Ok(polled) => polled,
+                        Err(_) => break,
+                    };
 
                 if !keep_polling {
                     break;
                 }
+                if is_transport_playing {
+                    Timer::after(Duration::from_millis(JOB_UPDATE_PLAYBACK_POLL_INTERVAL_MS)).await;
+                }
             }
         });
     }
@@ -2062,7 +5887,7 @@ impl SonantMainWindow {
         let receiver = cx.prompt_for_paths(PathPromptOptions {
             files: true,
             directories: false,
-            multiple: false,
+            multiple: true,
             prompt: Some(MIDI_SLOT_FILE_PICKER_PROMPT.into()),
         });
 
@@ -2073,25 +5898,26 @@ impl SonantMainWindow {
             };
 
             match result {
-                Ok(Some(paths)) => {
-                    if let Some(path) = paths.into_iter().next() {
-                        let _ = view.update_in(window, |view, _window, cx| {
-                            if !has_supported_midi_extension(&path) {
-                                view.upsert_midi_slot_error(MidiSlotErrorState::non_retryable(
-                                    slot,
-                                    row_index,
-                                    MIDI_SLOT_UNSUPPORTED_FILE_MESSAGE,
-                                ));
-                                cx.notify();
-                                return;
-                            }
+                Ok(Some(paths)) if !paths.is_empty() => {
+                    let _ = view.update_in(window, |view, _window, cx| {
+                        if !paths.iter().all(|path| has_supported_midi_extension(path)) {
+                            view.upsert_midi_slot_error(MidiSlotErrorState::non_retryable(
+                                slot,
+                                row_index,
+                                MIDI_SLOT_UNSUPPORTED_FILE_MESSAGE,
+                            ));
+                            cx.notify();
+                            return;
+                        }
 
-                            let path = path.to_string_lossy().to_string();
-                            view.set_midi_slot_file(slot, row_index, path, cx);
-                        });
-                    }
+                        let paths = paths
+                            .iter()
+                            .map(|path| path.to_string_lossy().to_string())
+                            .collect();
+                        view.set_midi_slot_files(slot, row_index, paths, cx);
+                    });
                 }
-                Ok(None) => {}
+                Ok(_) => {}
                 Err(error) => {
                     let message = format!("Could not open the file dialog: {error}");
                     let _ = view.update_in(window, |view, _window, cx| {
@@ -2140,90 +5966,769 @@ impl SonantMainWindow {
         path: String,
         cx: &mut Context<Self>,
     ) {
+        self.set_midi_slot_files(slot, row_index, vec![path], cx);
+    }
+
+    /// Loads one file, or several clips joined into a single reference.
+    fn set_midi_slot_files(
+        &mut self,
+        slot: ReferenceSlot,
+        row_index: usize,
+        paths: Vec<String>,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(first_path) = paths.first().cloned() else {
+            return;
+        };
         self.clear_midi_slot_error_for_row(slot, row_index);
-        match self.load_midi_use_case.execute(LoadMidiCommand::SetFile {
-            slot,
-            path: path.clone(),
-        }) {
-            Ok(_) => cx.notify(),
+        let command = if paths.len() == 1 {
+            LoadMidiCommand::SetFile {
+                slot,
+                path: first_path.clone(),
+            }
+        } else {
+            LoadMidiCommand::SetFiles {
+                slot,
+                paths: paths.clone(),
+            }
+        };
+        match self.load_midi_use_case.execute(command) {
+            Ok(_) => {
+                for path in paths.iter().rev() {
+                    if let Err(error) = self.recent_files.record(path) {
+                        self.input_track_error = Some(error.to_string());
+                    }
+                }
+                cx.notify();
+            }
             Err(error) => {
                 self.upsert_midi_slot_error(MidiSlotErrorState::from_load_error(
-                    slot, row_index, &path, &error,
+                    slot,
+                    row_index,
+                    &first_path,
+                    &error,
                 ));
                 cx.notify();
             }
         }
     }
 
-    fn on_retry_midi_slot_clicked(
-        &mut self,
-        slot: ReferenceSlot,
-        row_index: usize,
-        cx: &mut Context<Self>,
-    ) {
-        let retry_path = self
-            .midi_slot_error_for_row(slot, row_index)
-            .and_then(|error| error.retry_path.clone());
-        if let Some(path) = retry_path {
-            self.set_midi_slot_file(slot, row_index, path, cx);
+    fn on_retry_midi_slot_clicked(
+        &mut self,
+        slot: ReferenceSlot,
+        row_index: usize,
+        cx: &mut Context<Self>,
+    ) {
+        let retry_path = self
+            .midi_slot_error_for_row(slot, row_index)
+            .and_then(|error| error.retry_path.clone());
+        if let Some(path) = retry_path {
+            self.set_midi_slot_file(slot, row_index, path, cx);
+        }
+    }
+
+    fn on_clear_midi_slot_clicked(&mut self, slot: ReferenceSlot, cx: &mut Context<Self>) {
+        self.clear_midi_slot_error(slot);
+        if self
+            .load_midi_use_case
+            .execute(LoadMidiCommand::ClearSlot { slot })
+            .is_ok()
+        {
+            cx.notify();
+        }
+    }
+
+    fn poll_generation_updates(&mut self, window: &mut Window, cx: &mut Context<Self>) -> bool {
+        let updates = self.generation_job_manager.drain_updates();
+        if !updates.is_empty() {
+            for update in updates {
+                self.apply_generation_update(update);
+            }
+            self.advance_arrangement_run(cx);
+            self.advance_batch_run(window, cx);
+
+            cx.notify();
+        }
+        if self.submit_due_deferred_request() {
+            cx.notify();
+        } else if matches!(
+            self.generation_status,
+            HelperGenerationStatus::Retrying { .. }
+        ) {
+            // Keep the retry countdown in the status line ticking.
+            cx.notify();
+        }
+
+        let retry_countdown_secs = self
+            .generation_failure_action
+            .and_then(|action| action.retry_countdown_secs(Instant::now()));
+        if retry_countdown_secs != self.shown_retry_countdown_secs {
+            self.shown_retry_countdown_secs = retry_countdown_secs;
+            cx.notify();
+        }
+        let provider_demotions = self.provider_error_budget.demotions(Instant::now());
+        if !provider_demotions.is_empty() || !self.provider_demotions.is_empty() {
+            // Ticks the cool-down and clears the notice once the provider is restored.
+            self.provider_demotions = provider_demotions;
+            cx.notify();
+        }
+
+        self.generation_status.is_submitting_or_running()
+            || retry_countdown_secs.is_some()
+            || !self.deferred_requests.is_empty()
+    }
+
+    /// Resends the oldest deferred request once it is due and nothing else is in flight.
+    fn submit_due_deferred_request(&mut self) -> bool {
+        if self.generation_status.is_submitting_or_running() {
+            return false;
+        }
+        let now = Instant::now();
+        let Some(request) = self.deferred_requests.next_due(now) else {
+            return false;
+        };
+
+        log_generation_request_submission(&request);
+        let request_id = request.request_id.clone();
+        match self.generation_job_manager.submit_generate(request.clone()) {
+            Ok(_) => {
+                self.pending_history_requests
+                    .insert(request_id.clone(), request);
+                self.generation_status = HelperGenerationStatus::Submitting { request_id };
+            }
+            Err(error) => {
+                if self
+                    .deferred_requests
+                    .resolve(&request_id, DeferredOutcome::StillOffline, now)
+                    .is_some()
+                {
+                    self.generation_status = HelperGenerationStatus::Failed {
+                        message: error.user_message(),
+                    };
+                }
+            }
+        }
+        true
+    }
+
+    /// Moves a request that failed to reach its provider into the deferred queue. Batch and
+    /// arrangement runs keep their own failure handling, and a resend that hit the cap falls
+    /// through to the ordinary failure path.
+    fn defer_unreachable_request(
+        &mut self,
+        update: &GenerationJobUpdate,
+    ) -> Option<HelperGenerationStatus> {
+        let error = update
+            .error
+            .as_ref()
+            .filter(|error| DeferredRequestQueue::is_offline_error(error))?;
+        let now = Instant::now();
+        if self.deferred_requests.is_in_flight(&update.request_id) {
+            if self
+                .deferred_requests
+                .resolve(&update.request_id, DeferredOutcome::StillOffline, now)
+                .is_some()
+            {
+                return None;
+            }
+        } else {
+            if self.batch_run.is_some() || self.arrangement_run.is_some() {
+                return None;
+            }
+            let request = self.pending_history_requests.get(&update.request_id)?;
+            if !self
+                .deferred_requests
+                .defer(request.clone(), error.user_message(), now)
+            {
+                return None;
+            }
+        }
+
+        if let Some(request) = self.pending_history_requests.remove(&update.request_id) {
+            self.settings_ui_state.provider_health =
+                Some(ProviderHealth::failed(&request.model.provider, error, now));
+        }
+        Some(HelperGenerationStatus::Deferred {
+            request_id: update.request_id.clone(),
+            backlog: self.deferred_requests.len(),
+            reason: error.user_message(),
+        })
+    }
+
+    fn on_discard_deferred_requests_clicked(&mut self, cx: &mut Context<Self>) {
+        self.deferred_requests.clear();
+        if matches!(
+            self.generation_status,
+            HelperGenerationStatus::Deferred { .. }
+        ) {
+            self.generation_status = HelperGenerationStatus::Idle;
+        }
+        cx.notify();
+    }
+
+    fn retry_alternate_model(&self) -> Option<ModelRef> {
+        let failed = self.last_failed_request.as_ref()?;
+        let settings = self.settings_ui_state.saved();
+        alternate_provider_model(
+            &failed.model.provider,
+            !settings.anthropic_api_key.trim().is_empty(),
+            !settings.openai_api_key.trim().is_empty(),
+        )
+    }
+
+    fn on_retry_failed_generation_clicked(
+        &mut self,
+        use_alternate_provider: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self
+            .generation_failure_action
+            .and_then(|action| action.retry_countdown_secs(Instant::now()))
+            .is_some()
+        {
+            return;
+        }
+        let model = if use_alternate_provider {
+            let Some(model) = self.retry_alternate_model() else {
+                return;
+            };
+            Some(model)
+        } else {
+            None
+        };
+        let Some(failed) = self.last_failed_request.take() else {
+            return;
+        };
+
+        let retry = self.submission_model.prepare_retry(&failed, model);
+        self.generation_failure_action = None;
+        self.submit_replay_request(retry, window, cx);
+        cx.notify();
+    }
+
+    fn on_edit_prompt_after_failure_clicked(
+        &mut self,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.prompt_input
+            .update(cx, |input, cx| input.focus(window, cx));
+    }
+
+    fn apply_generation_update(&mut self, mut update: GenerationJobUpdate) {
+        if update.state == GenerationJobState::Failed
+            && let Some(status) = self.defer_unreachable_request(&update)
+        {
+            self.generation_status = status;
+            return;
+        }
+        if matches!(
+            update.state,
+            GenerationJobState::Succeeded
+                | GenerationJobState::Failed
+                | GenerationJobState::Cancelled
+        ) {
+            self.deferred_requests.resolve(
+                &update.request_id,
+                DeferredOutcome::Reached,
+                Instant::now(),
+            );
+            if self.duplicate_submission_of.as_ref() == Some(&update.request_id) {
+                self.duplicate_submission_of = None;
+            }
+        }
+
+        if update.state == GenerationJobState::Succeeded
+            && let Some(comparison) = update.comparison.take()
+        {
+            self.generation_status = self.apply_model_comparison(update.request_id, comparison);
+            return;
+        }
+
+        self.generation_status = match update.state {
+            GenerationJobState::Idle => HelperGenerationStatus::Idle,
+            GenerationJobState::Running => HelperGenerationStatus::Running {
+                request_id: update.request_id,
+            },
+            GenerationJobState::Retrying => match update.retry {
+                Some(retry) => HelperGenerationStatus::Retrying {
+                    request_id: update.request_id,
+                    next_attempt: retry.next_attempt,
+                    max_attempts: retry.max_attempts,
+                    retry_at: Instant::now() + retry.delay,
+                    reason: retry.error.user_message(),
+                },
+                None => HelperGenerationStatus::Running {
+                    request_id: update.request_id,
+                },
+            },
+            GenerationJobState::Succeeded => {
+                let request = self.pending_history_requests.get(&update.request_id);
+                self.candidates_mode = request.map(|request| request.mode);
+                self.candidates_reference = request.and_then(extended_reference);
+                self.candidates_model = update.result.as_ref().map(|result| result.model.clone());
+                if let Some(result) = update.result.as_ref() {
+                    self.settings_ui_state.provider_health = Some(ProviderHealth::succeeded(
+                        &result.model.provider,
+                        result.metadata.latency_ms,
+                        Instant::now(),
+                    ));
+                    self.auto_save_candidates(result);
+                    self.record_generation_history(result);
+                }
+                let (candidates, chords, repairs, validation_warnings, confidence) = update
+                    .result
+                    .map(|result| {
+                        (
+                            result.candidates,
+                            result.metadata.chords,
+                            result.metadata.repairs,
+                            result.metadata.validation_warnings,
+                            result.metadata.confidence,
+                        )
+                    })
+                    .unwrap_or_default();
+                self.generation_chords = chords;
+                self.generation_repairs = repairs;
+                self.generation_validation_warnings = validation_warnings;
+                self.generation_confidence = confidence;
+                let candidate_count = candidates.len();
+                self.generation_candidates = candidates;
+                self.generation_candidate_models.clear();
+                self.comparison_failures.clear();
+                self.selected_candidate_index = if candidate_count > 0 { Some(0) } else { None };
+                self.hidden_candidates.clear();
+                self.candidates_request_id = Some(update.request_id.clone());
+                if self.auto_apply_first_candidate
+                    && let Some(candidate) = self.generation_candidates.first().cloned()
+                {
+                    self.apply_candidate_to_daw(&candidate);
+                }
+                HelperGenerationStatus::Succeeded {
+                    request_id: update.request_id,
+                    candidate_count,
+                }
+            }
+            GenerationJobState::Failed => {
+                self.last_failed_request = self.pending_history_requests.remove(&update.request_id);
+                if let (Some(request), Some(error)) =
+                    (self.last_failed_request.as_ref(), update.error.as_ref())
+                {
+                    self.settings_ui_state.provider_health = Some(ProviderHealth::failed(
+                        &request.model.provider,
+                        error,
+                        Instant::now(),
+                    ));
+                }
+                self.generation_failure_action = update
+                    .error
+                    .as_ref()
+                    .and_then(|error| GenerationFailureAction::for_error(error, Instant::now()));
+                let message = update
+                    .error
+                    .map(|error| error.user_message())
+                    .unwrap_or_else(|| "Generation failed for an unknown reason.".to_string());
+                HelperGenerationStatus::Failed { message }
+            }
+            GenerationJobState::Cancelled => {
+                self.pending_history_requests.remove(&update.request_id);
+                HelperGenerationStatus::Cancelled {
+                    request_id: update.request_id,
+                }
+            }
+        };
+    }
+
+    /// Shows every compared model's candidates in one list, grouped in the order models were picked.
+    fn apply_model_comparison(
+        &mut self,
+        request_id: String,
+        comparison: ModelComparison,
+    ) -> HelperGenerationStatus {
+        let request = comparison
+            .outcomes
+            .iter()
+            .find_map(|outcome| self.pending_history_requests.get(&outcome.request_id));
+        self.candidates_mode = request.map(|request| request.mode);
+        self.candidates_reference = request.and_then(extended_reference);
+        self.candidates_model = None;
+        for result in comparison.succeeded_results() {
+            self.auto_save_candidates(result);
+            self.record_generation_history(result);
+        }
+        for outcome in &comparison.outcomes {
+            self.pending_history_requests.remove(&outcome.request_id);
+        }
+        self.comparison_failures = comparison
+            .failures()
+            .map(|(model, error)| format!("{}: {}", model.model, error.user_message()))
+            .collect();
+
+        let (models, candidates): (Vec<_>, Vec<_>) =
+            comparison.candidates_by_model().into_iter().unzip();
+        let candidate_count = candidates.len();
+        // Chord labels differ per model, so none are shown for a comparison.
+        self.generation_chords = Vec::new();
+        // Match the model-prefixed ids from `candidates_by_model`.
+        self.generation_repairs = comparison
+            .succeeded_results()
+            .flat_map(|result| {
+                result
+                    .metadata
+                    .repairs
+                    .iter()
+                    .map(|report| CandidateRepairReport {
+                        candidate_id: format!("{}/{}", result.model.model, report.candidate_id),
+                        ..report.clone()
+                    })
+            })
+            .collect();
+        self.generation_validation_warnings = comparison
+            .succeeded_results()
+            .flat_map(|result| {
+                result
+                    .metadata
+                    .validation_warnings
+                    .iter()
+                    .map(|warning| ValidationWarning {
+                        candidate_id: format!("{}/{}", result.model.model, warning.candidate_id),
+                        ..warning.clone()
+                    })
+            })
+            .collect();
+        self.generation_confidence = comparison
+            .succeeded_results()
+            .flat_map(|result| {
+                result
+                    .metadata
+                    .confidence
+                    .iter()
+                    .map(|confidence| CandidateConfidence {
+                        candidate_id: format!("{}/{}", result.model.model, confidence.candidate_id),
+                        ..confidence.clone()
+                    })
+            })
+            .collect();
+        self.generation_candidates = candidates;
+        self.generation_candidate_models = models;
+        self.selected_candidate_index = (candidate_count > 0).then_some(0);
+        self.hidden_candidates.clear();
+        self.candidates_request_id = Some(request_id.clone());
+        HelperGenerationStatus::Succeeded {
+            request_id,
+            candidate_count,
+        }
+    }
+
+    fn selected_candidate_repair(&self) -> Option<&CandidateRepairReport> {
+        let candidate = self
+            .selected_candidate_index
+            .and_then(|index| self.generation_candidates.get(index))?;
+        self.generation_repairs
+            .iter()
+            .find(|report| report.candidate_id == candidate.id)
+    }
+
+    fn selected_candidate_validation_warning(&self) -> Option<&ValidationWarning> {
+        let candidate = self
+            .selected_candidate_index
+            .and_then(|index| self.generation_candidates.get(index))?;
+        self.generation_validation_warnings
+            .iter()
+            .find(|warning| !warning.rejected && warning.candidate_id == candidate.id)
+    }
+
+    /// e.g. "Strict validation dropped 2 candidates (cand-2: 1 note outside D major; ...)".
+    fn rejected_candidates_summary(&self) -> Option<String> {
+        let rejected = self
+            .generation_validation_warnings
+            .iter()
+            .filter(|warning| warning.rejected)
+            .map(|warning| format!("{}: {}", warning.candidate_id, warning.message))
+            .collect::<Vec<_>>();
+        (!rejected.is_empty()).then(|| {
+            format!(
+                "Strict validation dropped {} candidate{} ({})",
+                rejected.len(),
+                if rejected.len() == 1 { "" } else { "s" },
+                rejected.join("; ")
+            )
+        })
+    }
+
+    fn candidate_confidence(
+        &self,
+        candidate: &GenerationCandidate,
+    ) -> Option<&CandidateConfidence> {
+        self.generation_confidence
+            .iter()
+            .find(|confidence| confidence.candidate_id == candidate.id)
+    }
+
+    /// `model · #n` for candidates of a model comparison, numbered within each model.
+    fn comparison_candidate_label(&self, index: usize) -> Option<String> {
+        let model = self.generation_candidate_models.get(index)?;
+        let position = self.generation_candidate_models[..index]
+            .iter()
+            .filter(|earlier| *earlier == model)
+            .count();
+        Some(format!("{} · #{}", model.model, position + 1))
+    }
+
+    fn auto_save_candidates(&mut self, result: &GenerationResult) {
+        let Some(dir) = self.settings_ui_state.auto_save_folder() else {
+            self.auto_save_error = None;
+            return;
+        };
+        let request = self.pending_history_requests.get(&result.request_id);
+        let conductor = request.map_or_else(|| self.export_conductor(), MidiConductor::for_request);
+        let program = self.export_program_for_mode(
+            request.map_or(self.selected_generation_mode, |request| request.mode),
+        );
+        let file_stem = match request {
+            Some(request) => self
+                .settings_ui_state
+                .export_name_template()
+                .render(&ExportNameContext::for_request(request)),
+            None => self.export_file_stem(Some(&result.request_id), self.selected_generation_mode),
+        };
+        self.auto_save_error = autosave_candidates(
+            dir,
+            &file_stem,
+            result,
+            self.settings_ui_state.tick_resolution(),
+            &conductor,
+            program,
+        )
+        .err()
+        .map(|error| error.to_string());
+        self.enforce_auto_save_quota();
+    }
+
+    /// Trims Sonant's files in the auto-save folder to the configured limit, oldest first.
+    fn enforce_auto_save_quota(&mut self) {
+        let Some(dir) = self.settings_ui_state.auto_save_folder() else {
+            self.auto_save_usage = None;
+            return;
+        };
+        let prefix = self
+            .settings_ui_state
+            .export_name_template()
+            .literal_prefix();
+        match enforce_folder_quota(
+            dir,
+            &prefix,
+            self.settings_ui_state.auto_save_quota().max_bytes(),
+        ) {
+            Ok(eviction) => self.auto_save_usage = Some(eviction.usage),
+            Err(error) => {
+                self.auto_save_error =
+                    Some(format!("failed to trim the auto-save folder: {error}"));
+            }
+        }
+    }
+
+    fn export_program_for_mode(&self, mode: GenerationMode) -> Option<u8> {
+        self.settings_ui_state
+            .export_program(Self::generation_mode_output_slot(mode))
+    }
+
+    /// Export file names from the saved template, filled from the current params.
+    fn export_file_stem(&self, request_id: Option<&str>, mode: GenerationMode) -> String {
+        self.settings_ui_state
+            .export_name_template()
+            .render(&ExportNameContext {
+                request_id,
+                mode: Some(mode),
+                key: Some(self.submission_model.key()),
+                scale: Some(self.submission_model.scale()),
+                bpm: Some(self.submission_model.bpm()),
+                ..ExportNameContext::dated_now()
+            })
+    }
+
+    /// Tempo and key from the current params, with the host meter when it reports one.
+    fn export_conductor(&self) -> MidiConductor {
+        MidiConductor::for_settings(
+            self.selected_generation_mode,
+            self.submission_model.bpm(),
+            self.submission_model.key(),
+            self.submission_model.scale(),
+            self.live_midi_capture
+                .host_transport_context()
+                .and_then(|context| context.time_signature)
+                .map(|(numerator, denominator)| TimeSignature {
+                    numerator,
+                    denominator,
+                }),
+        )
+    }
+
+    fn record_generation_history(&mut self, result: &GenerationResult) {
+        let Some(request) = self.pending_history_requests.remove(&result.request_id) else {
+            return;
+        };
+        self.deferred_history_records
+            .push((request, result.clone()));
+        if !self.live_capture_transport_playing {
+            self.flush_deferred_history();
         }
     }
 
-    fn on_clear_midi_slot_clicked(&mut self, slot: ReferenceSlot, cx: &mut Context<Self>) {
-        self.clear_midi_slot_error(slot);
-        if self
-            .load_midi_use_case
-            .execute(LoadMidiCommand::ClearSlot { slot })
-            .is_ok()
-        {
-            cx.notify();
+    /// Starts winding the session down as the window closes: the plugin stops relaying input and
+    /// results that already arrived are written to history. Returns the job manager for the
+    /// caller to shut down off the UI thread, giving the in-flight job a short grace period.
+    fn begin_close(&mut self) -> Arc<GenerationJobManager> {
+        self.send_to_plugin(&HelperControlMessage::HelperClosing);
+        self.live_midi_capture.shutdown();
+        self.flush_deferred_history();
+        Arc::clone(&self.generation_job_manager)
+    }
+
+    /// Applies the updates the job manager's shutdown left behind and writes the last finished
+    /// results to history.
+    fn finish_close(&mut self) {
+        for update in self.generation_job_manager.drain_updates() {
+            self.apply_generation_update(update);
         }
+        self.flush_deferred_history();
     }
 
-    fn poll_generation_updates(&mut self, cx: &mut Context<Self>) -> bool {
-        let updates = self.generation_job_manager.drain_updates();
-        if !updates.is_empty() {
-            for update in updates {
-                self.apply_generation_update(update);
+    fn flush_deferred_history(&mut self) {
+        for (request, result) in std::mem::take(&mut self.deferred_history_records) {
+            if let Err(error) = self.generation_history.record(request, result) {
+                self.candidate_annotation_error = Some(error.to_string());
             }
-
-            cx.notify();
         }
+    }
 
-        self.generation_status.is_submitting_or_running()
+    fn is_candidate_applied(&self, candidate: &GenerationCandidate) -> bool {
+        self.applied_candidate
+            .as_ref()
+            .is_some_and(|(request_id, candidate_id)| {
+                self.candidates_request_id.as_deref() == Some(request_id.as_str())
+                    && *candidate_id == candidate.id
+            })
     }
 
-    fn apply_generation_update(&mut self, update: GenerationJobUpdate) {
-        self.generation_status = match update.state {
-            GenerationJobState::Idle => HelperGenerationStatus::Idle,
-            GenerationJobState::Running => HelperGenerationStatus::Running {
-                request_id: update.request_id,
-            },
-            GenerationJobState::Succeeded => {
-                let candidates = update
-                    .result
-                    .map(|result| result.candidates)
-                    .unwrap_or_default();
-                let candidate_count = candidates.len();
-                self.generation_candidates = candidates;
-                self.selected_candidate_index = if candidate_count > 0 { Some(0) } else { None };
-                self.hidden_candidates.clear();
-                HelperGenerationStatus::Succeeded {
-                    request_id: update.request_id,
-                    candidate_count,
-                }
-            }
-            GenerationJobState::Failed => {
-                let message = update
-                    .error
-                    .map(|error| error.user_message())
-                    .unwrap_or_else(|| "Generation failed for an unknown reason.".to_string());
-                HelperGenerationStatus::Failed { message }
-            }
-            GenerationJobState::Cancelled => HelperGenerationStatus::Cancelled {
-                request_id: update.request_id,
-            },
+    fn candidate_annotation(&self, candidate: &GenerationCandidate) -> Option<&str> {
+        self.generation_history
+            .annotation(self.candidates_request_id.as_deref()?, &candidate.id)
+    }
+
+    fn storage_usage_lines(&self) -> [String; 3] {
+        let auto_save = match (
+            self.settings_ui_state.auto_save_folder(),
+            self.auto_save_usage,
+        ) {
+            (None, _) => "Auto-save folder: not set".to_string(),
+            (Some(_), None) => "Auto-save folder: nothing saved yet".to_string(),
+            (Some(_), Some(usage)) => format!(
+                "Auto-save folder: {} files, {} of {}",
+                usage.files,
+                format_byte_size(usage.bytes),
+                self.settings_ui_state.auto_save_quota().label()
+            ),
         };
+        [
+            format!(
+                "History: {} of {GENERATION_HISTORY_MAX_ENTRIES} generations, {} of {}",
+                self.generation_history.entries().len(),
+                format_byte_size(self.generation_history.stored_bytes() as u64),
+                format_byte_size(GENERATION_HISTORY_MAX_BYTES as u64)
+            ),
+            format!(
+                "Explanation cache: {} of {CANDIDATE_EXPLANATION_CACHE_MAX_ENTRIES} entries",
+                self.candidate_explanations.len()
+            ),
+            auto_save,
+        ]
+    }
+}
+
+/// A removed track row and, when it was the slot's last row, the references that were cleared.
+struct TrackRowUndo {
+    row_index: usize,
+    slot: ReferenceSlot,
+    hidden_in_piano_roll: bool,
+    references: Option<SlotReferenceSnapshot>,
+}
+
+impl TrackRowUndo {
+    fn message(&self) -> String {
+        let label = SonantMainWindow::reference_slot_label(self.slot);
+        match self
+            .references
+            .as_ref()
+            .map(SlotReferenceSnapshot::reference_count)
+        {
+            Some(1) => format!("Removed {label} track and its loaded reference."),
+            Some(count) => format!("Removed {label} track and its {count} loaded references."),
+            None => format!("Removed {label} track."),
+        }
+    }
+}
+
+/// Payload carried while a track row is dragged by its color stripe.
+#[derive(Clone)]
+struct DraggedTrackRow {
+    row_index: usize,
+    label: String,
+    color: Hsla,
+}
+
+impl Render for DraggedTrackRow {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let colors = cx.read_global(|theme: &SonantTheme, _| theme.colors);
+        div()
+            .flex()
+            .items_center()
+            .gap_2()
+            .px_2()
+            .h(px(28.0))
+            .rounded(px(4.0))
+            .border_1()
+            .border_color(self.color)
+            .bg(colors.panel_active_background)
+            .text_size(px(11.0))
+            .text_color(colors.surface_foreground)
+            .child(div().w(px(4.0)).h(px(14.0)).rounded(px(2.0)).bg(self.color))
+            .child(self.label.clone())
+    }
+}
+
+/// Level after a key press on a parameter slider, or `None` for keys the slider ignores.
+fn keyboard_param_level(level: u8, key: &str) -> Option<u8> {
+    match key {
+        "left" | "down" => Some(level.saturating_sub(1).max(PARAM_LEVEL_MIN)),
+        "right" | "up" => Some(level.saturating_add(1).min(PARAM_LEVEL_MAX)),
+        "home" => Some(PARAM_LEVEL_MIN),
+        "end" => Some(PARAM_LEVEL_MAX),
+        _ => None,
+    }
+}
+
+/// Selection after an arrow key press, clamped to the list; the first press selects an end.
+fn stepped_list_index(current: Option<usize>, count: usize, step: isize) -> Option<usize> {
+    let last = count.checked_sub(1)?;
+    Some(match current {
+        Some(index) => index.min(last).saturating_add_signed(step).min(last),
+        None if step < 0 => last,
+        None => 0,
+    })
+}
+
+/// Where a row ends up after the row at `from` is moved to `to`.
+fn reordered_row_index(row_index: usize, from: usize, to: usize) -> usize {
+    if row_index == from {
+        to
+    } else if from < to && (from + 1..=to).contains(&row_index) {
+        row_index - 1
+    } else if to < from && (to..from).contains(&row_index) {
+        row_index + 1
+    } else {
+        row_index
     }
 }
 
@@ -2250,6 +6755,93 @@ fn resolve_live_input_source() -> (Arc<dyn LiveInputEventSource>, Option<String>
     }
 }
 
+/// Diagnostics line for the Input Tracks section; `None` while nothing has been dropped.
+fn queue_overflow_summary(
+    plugin_metrics: Option<QueueOverflowMetrics>,
+    helper_capture_dropped: u64,
+) -> Option<String> {
+    let plugin_metrics = plugin_metrics.unwrap_or_default();
+    if plugin_metrics.total() == 0 && helper_capture_dropped == 0 {
+        return None;
+    }
+    Some(format!(
+        "Dropped MIDI events: live input {}, plugin relay {}, helper capture {}, output {}",
+        plugin_metrics.live_input_dropped,
+        plugin_metrics.app_input_dropped,
+        helper_capture_dropped,
+        plugin_metrics.generated_output_dropped
+    ))
+}
+
+fn resolve_helper_control_sender() -> Option<HelperControlIpcSender> {
+    let socket_path = std::env::var(HELPER_CONTROL_IPC_SOCKET_ENV).ok()?;
+    HelperControlIpcSender::new(socket_path).ok()
+}
+
+fn mark_locked_note_rects(
+    note_rects: &mut [PianoRollNoteRect],
+    candidate: &GenerationCandidate,
+    locked_notes: &[GeneratedNote],
+) {
+    for rect in note_rects {
+        rect.is_locked = rect
+            .note_index
+            .and_then(|note_index| candidate.notes.get(note_index))
+            .is_some_and(|note| {
+                locked_notes
+                    .iter()
+                    .any(|locked| note.matches_locked_note(locked))
+            });
+    }
+}
+
+fn midi_thru_channels(
+    input_track_model: &InputTrackModel,
+    visible_slot_rows: &[ReferenceSlot],
+    midi_thru_slots: &std::collections::HashSet<ReferenceSlot>,
+) -> Vec<u8> {
+    let mut channels = input_track_model
+        .live_channel_mappings()
+        .into_iter()
+        .filter(|mapping| {
+            midi_thru_slots.contains(&mapping.slot) && visible_slot_rows.contains(&mapping.slot)
+        })
+        .map(|mapping| mapping.channel)
+        .collect::<Vec<_>>();
+    channels.sort_unstable();
+    channels.dedup();
+    channels
+}
+
+fn restore_input_track_layout() -> (InputTrackModel, Vec<ReferenceSlot>, Option<String>) {
+    let Ok(raw) = std::env::var(INPUT_TRACK_LAYOUT_ENV) else {
+        return (InputTrackModel::new(), Vec::new(), None);
+    };
+    match parse_input_track_layout(&raw) {
+        Ok((model, rows)) => (model, rows, None),
+        Err(message) => (InputTrackModel::new(), Vec::new(), Some(message)),
+    }
+}
+
+fn restore_project_model() -> Option<ModelRef> {
+    let raw = std::env::var(PROJECT_MODEL_ENV).ok()?;
+    parse_project_model(&raw)
+}
+
+fn parse_project_model(raw: &str) -> Option<ModelRef> {
+    serde_json::from_str::<ModelRef>(raw)
+        .ok()
+        .filter(|model| model.validate().is_ok())
+}
+
+fn parse_input_track_layout(raw: &str) -> Result<(InputTrackModel, Vec<ReferenceSlot>), String> {
+    let layout: InputTrackLayout = serde_json::from_str(raw)
+        .map_err(|error| format!("Saved input track layout could not be read: {error}"))?;
+    let model = InputTrackModel::from_layout(&layout)
+        .map_err(|error| format!("Saved input track layout is invalid: {error}"))?;
+    Ok((model, layout.rows))
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 struct LiveRecordingSummary {
     bar_count: usize,
@@ -2267,7 +6859,7 @@ fn summarize_live_recording(events: &[LiveInputEvent], bar_count: usize) -> Live
     };
 
     for event in events {
-        if is_note_on_event(*event) {
+        if event.is_note_on() {
             summary.note_count += 1;
             let pitch = event.data[1];
             summary.min_pitch = Some(summary.min_pitch.map_or(pitch, |min| min.min(pitch)));
@@ -2278,10 +6870,6 @@ fn summarize_live_recording(events: &[LiveInputEvent], bar_count: usize) -> Live
     summary
 }
 
-fn is_note_on_event(event: LiveInputEvent) -> bool {
-    (event.data[0] & 0xF0) == 0x90 && event.data[2] > 0
-}
-
 fn midi_channel_from_status(status: u8) -> Option<u8> {
     if (status & 0x80) == 0 {
         return None;
@@ -2318,6 +6906,7 @@ fn collect_live_references(
     input_track_model: &InputTrackModel,
     recording_channel_enabled: &[bool; 16],
     midi_input_router: &MidiInputRouter,
+    velocity_profile: Option<(VelocityProfile, u64)>,
 ) -> Vec<MidiReferenceSummary> {
     let channel_mappings = input_track_model.channel_mappings();
     SonantMainWindow::reference_slots()
@@ -2332,13 +6921,83 @@ fn collect_live_references(
                 return None;
             }
 
-            let events = midi_input_router.snapshot_reference(slot);
+            let mut events = midi_input_router.snapshot_reference(slot);
+            if let Some((profile, seed)) = velocity_profile {
+                apply_velocity_profile_to_live_events(&mut events, profile, seed);
+            }
+            let events = apply_sustain_pedal_to_live_events(&events);
             let metrics = midi_input_router.reference_metrics(slot);
             build_live_reference_summary(slot, &events, metrics.bar_count)
         })
         .collect()
 }
 
+/// The continuation seed when the request has one, otherwise its first reference.
+fn extended_reference(request: &GenerationRequest) -> Option<MidiReferenceSummary> {
+    request
+        .references
+        .iter()
+        .find(|reference| reference.slot == ReferenceSlot::ContinuationSeed)
+        .or_else(|| request.references.first())
+        .cloned()
+}
+
+fn supports_monophonic_cleanup(slot: ReferenceSlot) -> bool {
+    matches!(slot, ReferenceSlot::Melody | ReferenceSlot::Bassline)
+}
+
+fn velocity_profile_seed() -> u64 {
+    RandomState::new().hash_one(Instant::now())
+}
+
+/// Session context a collaborator would see: host transport settings and track name plus the
+/// slot rows that currently carry material. File rows only count once a file is loaded into them.
+fn build_daw_context(
+    host_context: Option<HostTransportContext>,
+    host_track_name: Option<String>,
+    input_track_model: &InputTrackModel,
+    visible_slot_rows: &[ReferenceSlot],
+    references: &[MidiReferenceSummary],
+) -> DawContext {
+    let host_context = host_context.unwrap_or_default();
+    let channel_mappings = input_track_model.channel_mappings();
+    let track_roles = visible_slot_rows
+        .iter()
+        .copied()
+        .filter_map(|slot| match input_track_model.source_for_slot(slot) {
+            ReferenceSource::Live => Some(DawTrackRole {
+                slot,
+                source: ReferenceSource::Live,
+                channel: channel_mapping_for_slot_in_mappings(channel_mappings, slot),
+            }),
+            ReferenceSource::File => references
+                .iter()
+                .any(|reference| {
+                    reference.slot == slot && reference.source == ReferenceSource::File
+                })
+                .then_some(DawTrackRole {
+                    slot,
+                    source: ReferenceSource::File,
+                    channel: None,
+                }),
+        })
+        .collect();
+
+    DawContext {
+        host_tempo_bpm: host_context.tempo_bpm,
+        time_signature: host_context
+            .time_signature
+            .map(|(numerator, denominator)| TimeSignature {
+                numerator,
+                denominator,
+            }),
+        key: None,
+        loop_length_beats: host_context.loop_length_beats,
+        host_track_name,
+        track_roles,
+    }
+}
+
 fn build_live_reference_summary(
     slot: ReferenceSlot,
     events: &[LiveInputEvent],
@@ -2364,6 +7023,8 @@ fn build_live_reference_summary(
         min_pitch,
         max_pitch,
         events: build_live_reference_events(events),
+        transposition: None,
+        tempo: None,
     };
 
     reference.validate().ok().map(|_| reference)
@@ -2471,6 +7132,16 @@ impl Render for SonantMainWindow {
         let colors = theme.colors;
         let spacing = theme.spacing;
         let radius = theme.radius;
+        let open_analysis = self.open_reference_analysis();
+        let session_bpm = self.submission_model.bpm();
+
+        if let Some(step) = self.settings_ui_state.onboarding_step() {
+            return div()
+                .size_full()
+                .overflow_y_scrollbar()
+                .overflow_x_hidden()
+                .child(self.onboarding_view(step, &theme, cx));
+        }
 
         if self.settings_ui_state.is_settings_open() {
             let selected_tab = self.settings_ui_state.settings_tab;
@@ -2512,9 +7183,18 @@ impl Render for SonantMainWindow {
                         .justify_between()
                         .gap_2()
                         .child(Label::new("Settings"))
-                        .child(Button::new("close-settings-button").label("Back").on_click(
-                            cx.listener(|this, _, _window, cx| this.on_close_settings_clicked(cx)),
-                        )),
+                        .child(
+                            div()
+                                .flex()
+                                .items_center()
+                                .gap_2()
+                                .child(Button::new("settings-setup-wizard-button").label("Setup Wizard").on_click(
+                                    cx.listener(|this, _, _window, cx| this.on_start_onboarding_clicked(cx)),
+                                ))
+                                .child(Button::new("close-settings-button").label("Back").on_click(
+                                    cx.listener(|this, _, _window, cx| this.on_close_settings_clicked(cx)),
+                                )),
+                        ),
                 )
                 .child(
                     div()
@@ -2564,7 +7244,11 @@ impl Render for SonantMainWindow {
                         .child(Label::new("OpenAI-Compatible API Key"))
                         .child(Input::new(&self.settings_openai_api_key_input).mask_toggle())
                         .child(Label::new("Custom Base URL"))
-                        .child(Input::new(&self.settings_custom_base_url_input)),
+                        .child(Input::new(&self.settings_custom_base_url_input))
+                        .child(Label::new("Remote Sonant Server URL"))
+                        .child(Input::new(&self.settings_remote_server_url_input))
+                        .child(Label::new("Remote Server Token"))
+                        .child(Input::new(&self.settings_remote_server_token_input).mask_toggle()),
                     SettingsTab::MidiSettings => div()
                         .id("settings-tab-midi-panel")
                         .flex()
@@ -2575,7 +7259,124 @@ impl Render for SonantMainWindow {
                         .border_1()
                         .border_color(colors.panel_border)
                         .bg(colors.panel_background)
-                        .child(Label::new("MIDI Settings")),
+                        .child(
+                            div()
+                                .flex()
+                                .items_center()
+                                .justify_between()
+                                .child(Label::new("Default Channel Mappings"))
+                                .child(
+                                    Button::new("settings-reset-channel-mappings")
+                                        .label("Reset to Defaults")
+                                        .on_click(cx.listener(|this, _, _window, cx| {
+                                            this.on_reset_default_channel_mappings_clicked(cx)
+                                        })),
+                                ),
+                        )
+                        .child(
+                            div()
+                                .text_size(px(11.0))
+                                .text_color(colors.muted_foreground)
+                                .child(
+                                    "Channels a slot listens on when it is switched to Live input.",
+                                ),
+                        )
+                        .children(Self::reference_slots().iter().copied().map(|slot| {
+                            let current_channel = draft_settings
+                                .default_channel_mappings
+                                .iter()
+                                .find(|mapping| mapping.slot == slot)
+                                .map(|mapping| mapping.channel);
+                            let slot_index = Self::reference_slot_index(slot);
+                            div()
+                                .id(("default-channel-row", slot_index))
+                                .flex()
+                                .items_center()
+                                .gap_2()
+                                .child(
+                                    div()
+                                        .w(px(120.0))
+                                        .flex_none()
+                                        .text_size(px(12.0))
+                                        .text_color(colors.slot_color(slot))
+                                        .child(Self::reference_slot_label(slot)),
+                                )
+                                .child(div().flex().flex_wrap().gap_1().children(
+                                    (MIDI_CHANNEL_MIN..=MIDI_CHANNEL_MAX).map(|channel| {
+                                        let is_selected = current_channel == Some(channel);
+                                        div()
+                                            .id((
+                                                "default-channel-option",
+                                                slot_index * 16 + usize::from(channel),
+                                            ))
+                                            .w(px(24.0))
+                                            .h(px(20.0))
+                                            .flex()
+                                            .items_center()
+                                            .justify_center()
+                                            .rounded(radius.control)
+                                            .border_1()
+                                            .border_color(if is_selected {
+                                                colors.primary
+                                            } else {
+                                                colors.panel_border
+                                            })
+                                            .text_size(px(10.0))
+                                            .text_color(if is_selected {
+                                                colors.primary
+                                            } else {
+                                                colors.muted_foreground
+                                            })
+                                            .cursor_pointer()
+                                            .hover(|s| s.bg(colors.panel_active_background))
+                                            .on_click(cx.listener(move |this, _, _window, cx| {
+                                                this.on_default_channel_mapping_selected(
+                                                    slot, channel, cx,
+                                                );
+                                            }))
+                                            .child(channel.to_string())
+                                    }),
+                                ))
+                        }))
+                        .children(self.settings_channel_mapping_error.iter().map(|message| {
+                            div()
+                                .text_size(px(11.0))
+                                .text_color(colors.error_foreground)
+                                .child(message.clone())
+                        }))
+                        .child(Label::new(SettingsField::ExportPrograms.label()))
+                        .child(
+                            div()
+                                .text_size(px(11.0))
+                                .text_color(colors.muted_foreground)
+                                .child("General MIDI instrument each exported track starts with."),
+                        )
+                        .children(Self::reference_slots().iter().copied().map(|slot| {
+                            let program = program_for_slot(&draft_settings.export_programs, slot);
+                            let slot_index = Self::reference_slot_index(slot);
+                            div()
+                                .flex()
+                                .items_center()
+                                .gap_2()
+                                .child(
+                                    div()
+                                        .w(px(120.0))
+                                        .flex_none()
+                                        .text_size(px(12.0))
+                                        .text_color(colors.slot_color(slot))
+                                        .child(Self::reference_slot_label(slot)),
+                                )
+                                .child(
+                                    Button::new(("export-program", slot_index))
+                                        .label(program.map_or_else(
+                                            || "No Program Change".to_string(),
+                                            gm_program_name,
+                                        ))
+                                        .on_click(cx.listener(move |this, _, _window, cx| {
+                                            this.on_export_program_cycled(slot, cx);
+                                        })),
+                                )
+                        })),
                     SettingsTab::General => div()
                         .id("settings-tab-general-panel")
                         .flex()
@@ -2589,7 +7390,275 @@ impl Render for SonantMainWindow {
                         .child(Label::new("Default Model"))
                         .child(Input::new(&self.settings_default_model_input))
                         .child(Label::new("Context Window"))
-                        .child(Input::new(&self.settings_context_window_input)),
+                        .child(Input::new(&self.settings_context_window_input))
+                        .child(Label::new(SettingsField::TickResolution.label()))
+                        .child(Input::new(&self.settings_tick_resolution_input))
+                        .child(Label::new(SettingsField::AutoSaveFolder.label()))
+                        .child(Input::new(&self.settings_auto_save_folder_input))
+                        .child(Label::new(SettingsField::AutoSaveQuota.label()))
+                        .child(
+                            div()
+                                .flex()
+                                .items_center()
+                                .gap_2()
+                                .child(
+                                    Button::new("settings-auto-save-quota")
+                                        .label(draft_settings.auto_save_quota.label())
+                                        .on_click(cx.listener(|this, _, _window, cx| {
+                                            this.on_auto_save_quota_cycled(cx);
+                                        })),
+                                )
+                                .child(
+                                    div()
+                                        .text_size(px(11.0))
+                                        .text_color(colors.muted_foreground)
+                                        .child("The oldest Sonant files are deleted past the limit; other files are left alone."),
+                                ),
+                        )
+                        .child(Label::new(SettingsField::ExportNameTemplate.label()))
+                        .child(Input::new(&self.settings_export_name_template_input))
+                        .child(
+                            div()
+                                .text_size(px(11.0))
+                                .text_color(colors.muted_foreground)
+                                .child(
+                                    "Names auto-saved, applied, stem, arrangement, and take files. \
+                                     Placeholders: {request-id}, {mode}, {key}, {bpm}, {date}.",
+                                ),
+                        )
+                        .children(
+                            self.settings_ui_state
+                                .draft_export_name_template_error()
+                                .map(|error| {
+                                    div()
+                                        .text_size(px(11.0))
+                                        .text_color(colors.error_foreground)
+                                        .child(format!("{error}; the default name will be used."))
+                                }),
+                        )
+                        .child(Label::new("Storage"))
+                        .child(
+                            div()
+                                .flex()
+                                .flex_col()
+                                .gap_1()
+                                .text_size(px(11.0))
+                                .text_color(colors.muted_foreground)
+                                .children(self.storage_usage_lines()),
+                        )
+                        .child(Label::new(SettingsField::ApplyDestination.label()))
+                        .child(
+                            div()
+                                .flex()
+                                .items_center()
+                                .gap_2()
+                                .child(
+                                    Button::new("settings-apply-destination")
+                                        .label(draft_settings.apply_destination.label())
+                                        .on_click(cx.listener(|this, _, _window, cx| {
+                                            this.on_apply_destination_cycled(cx);
+                                        })),
+                                )
+                                .child(
+                                    div()
+                                        .text_size(px(11.0))
+                                        .text_color(colors.muted_foreground)
+                                        .child("Where Apply sends a candidate. Files go to the Auto-Save Folder."),
+                                ),
+                        )
+                        .child(Label::new(SettingsField::AnonymizeReferences.label()))
+                        .child(
+                            div()
+                                .flex()
+                                .items_center()
+                                .gap_2()
+                                .child(
+                                    Button::new("settings-anonymize-references")
+                                        .label(if draft_settings.anonymize_references {
+                                            "On"
+                                        } else {
+                                            "Off"
+                                        })
+                                        .on_click(cx.listener(|this, _, _window, cx| {
+                                            this.on_anonymize_references_toggled(cx);
+                                        })),
+                                )
+                                .child(
+                                    div()
+                                        .text_size(px(11.0))
+                                        .text_color(colors.muted_foreground)
+                                        .child(
+                                            "Send reference files as reference-1.mid, \
+                                             reference-2.mid, ... instead of their paths.",
+                                        ),
+                                ),
+                        )
+                        .child(Label::new(SettingsField::CandidateValidation.label()))
+                        .child(
+                            div()
+                                .flex()
+                                .items_center()
+                                .gap_2()
+                                .child(
+                                    Button::new("settings-candidate-validation")
+                                        .label(
+                                            draft_settings
+                                                .candidate_validation
+                                                .map_or("Off", ValidationStrictness::label),
+                                        )
+                                        .on_click(cx.listener(|this, _, _window, cx| {
+                                            this.on_candidate_validation_cycled(cx);
+                                        })),
+                                )
+                                .child(
+                                    div()
+                                        .text_size(px(11.0))
+                                        .text_color(colors.muted_foreground)
+                                        .child(
+                                            "Hold candidates to the range constraint, their length, \
+                                             and the scale. Strict drops candidates that break one; \
+                                             Lenient corrects the notes and warns.",
+                                        ),
+                                ),
+                        )
+                        .children(
+                            [
+                                (
+                                    SettingsField::HighContrast,
+                                    draft_settings.high_contrast,
+                                    self.os_display.high_contrast,
+                                    "Stronger borders and text, no glow.",
+                                ),
+                                (
+                                    SettingsField::ReducedMotion,
+                                    draft_settings.reduced_motion,
+                                    self.os_display.reduced_motion,
+                                    "No loading spinners.",
+                                ),
+                            ]
+                            .into_iter()
+                            .flat_map(|(field, preference, os_enabled, hint)| {
+                                let system = if os_enabled { "on" } else { "off" };
+                                [
+                                    Label::new(field.label()).into_any_element(),
+                                    div()
+                                        .flex()
+                                        .items_center()
+                                        .gap_2()
+                                        .child(
+                                            Button::new(("settings-display-preference", field as usize))
+                                                .label(preference.label())
+                                                .on_click(cx.listener(move |this, _, _window, cx| {
+                                                    this.on_display_preference_cycled(field, cx);
+                                                })),
+                                        )
+                                        .child(
+                                            div()
+                                                .text_size(px(11.0))
+                                                .text_color(colors.muted_foreground)
+                                                .child(format!("{hint} Auto follows the system (currently {system}).")),
+                                        )
+                                        .into_any_element(),
+                                ]
+                            }),
+                        )
+                        .child(Label::new(SettingsField::SlotColors.label()))
+                        .child(
+                            div()
+                                .text_size(px(11.0))
+                                .text_color(colors.muted_foreground)
+                                .child("Color of each slot's track rows and piano roll notes."),
+                        )
+                        .children(Self::reference_slots().iter().copied().map(|slot| {
+                            let track_color = draft_settings.slot_colors.get(slot);
+                            let slot_index = Self::reference_slot_index(slot);
+                            div()
+                                .flex()
+                                .items_center()
+                                .gap_2()
+                                .child(
+                                    div()
+                                        .w(px(10.0))
+                                        .h(px(10.0))
+                                        .flex_none()
+                                        .rounded(px(999.0))
+                                        .bg(colors.track_color(track_color)),
+                                )
+                                .child(
+                                    div()
+                                        .w(px(120.0))
+                                        .flex_none()
+                                        .text_size(px(12.0))
+                                        .text_color(colors.track_color(track_color))
+                                        .child(Self::reference_slot_label(slot)),
+                                )
+                                .child(
+                                    Button::new(("settings-slot-color", slot_index))
+                                        .label(track_color.label())
+                                        .on_click(cx.listener(move |this, _, _window, cx| {
+                                            this.on_slot_color_cycled(slot, cx);
+                                        })),
+                                )
+                        }))
+                        .child(Label::new(format!(
+                            "Sampling Profiles ({})",
+                            self.submission_model.provider()
+                        )))
+                        .children(
+                            self.sampling_profiles
+                                .profiles_for_provider(self.submission_model.provider())
+                                .enumerate()
+                                .map(|(index, profile)| {
+                                    let name = profile.name.clone();
+                                    div()
+                                        .id(("settings-sampling-profile-row", index))
+                                        .flex()
+                                        .items_center()
+                                        .justify_between()
+                                        .gap_2()
+                                        .child(div().text_size(px(12.0)).child(format!(
+                                            "{}  ·  temp {:.2}  ·  top_p {:.2}  ·  {} tokens",
+                                            profile.name,
+                                            profile.temperature,
+                                            profile.top_p,
+                                            profile.max_tokens
+                                        )))
+                                        .child(
+                                            Button::new((
+                                                "settings-sampling-profile-remove",
+                                                index,
+                                            ))
+                                            .label("Remove")
+                                            .on_click(cx.listener(move |this, _, _window, cx| {
+                                                this.on_remove_sampling_profile_clicked(&name, cx)
+                                            })),
+                                        )
+                                }),
+                        )
+                        .child(
+                            div()
+                                .flex()
+                                .items_center()
+                                .gap_2()
+                                .child(
+                                    div()
+                                        .flex_1()
+                                        .child(Input::new(&self.sampling_profile_name_input)),
+                                )
+                                .child(
+                                    Button::new("settings-sampling-profile-save")
+                                        .label("Save Current Sampling")
+                                        .on_click(cx.listener(|this, _, _window, cx| {
+                                            this.on_save_sampling_profile_clicked(cx)
+                                        })),
+                                ),
+                        )
+                        .children(self.sampling_profile_error.iter().map(|message| {
+                            div()
+                                .text_size(px(11.0))
+                                .text_color(colors.error_foreground)
+                                .child(message.clone())
+                        })),
                 })
                 .child(
                     div()
@@ -2642,10 +7711,22 @@ impl Render for SonantMainWindow {
                 );
         }
 
-        let provider_status_label = self.settings_ui_state.provider_status.label();
-        let provider_status_color = self.settings_ui_state.provider_status.color(colors);
+        let now = Instant::now();
+        let provider_status = self.settings_ui_state.effective_provider_status(now);
+        let provider_status_label = provider_status.label();
+        let provider_status_color = provider_status.color(colors);
+        let provider_health_details = self
+            .settings_ui_state
+            .recent_provider_health(now)
+            .map(|health| health.details(now));
         let status_label = self.generation_status.label();
         let status_color = self.generation_status.color(colors);
+        let generation_failed = matches!(
+            self.generation_status,
+            HelperGenerationStatus::Failed { .. }
+        );
+        let failure_action = self.generation_failure_action.filter(|_| generation_failed);
+        let retry_alternate_model = self.retry_alternate_model();
         let generating = self.generation_status.is_submitting_or_running();
         let generation_references = self.collect_generation_references();
         let mode_requirement = mode_reference_requirement(self.selected_generation_mode);
@@ -2655,18 +7736,11 @@ impl Render for SonantMainWindow {
         );
         let complexity_percent = Self::param_level_to_percent(self.submission_model.complexity());
         let density_percent = Self::param_level_to_percent(self.submission_model.density());
-        let generated_slot = Self::generation_mode_output_slot(self.selected_generation_mode);
-        let piano_roll_note_color = colors.slot_color(generated_slot);
-        let piano_roll_note_glow_color = Self::slot_glow_color(colors, generated_slot);
-        let piano_roll_note_rects = Self::piano_roll_note_rects(
-            &generation_references,
-            &self.visible_slot_rows,
-            &self.piano_roll_hidden_rows,
-            &self.generation_candidates,
-            self.selected_candidate_index,
-            &self.hidden_candidates,
-            colors,
-        );
+        let syncopation_percent = Self::param_level_to_percent(self.submission_model.syncopation());
+        let param_spec = ModeParamSpec::for_mode(self.selected_generation_mode);
+        let locked_note_count = self.submission_model.locked_notes().len();
+        let piano_roll_detached = self.detached_piano_roll.is_some();
+        let view = cx.entity().downgrade();
 
         div()
             .size_full()
@@ -2751,7 +7825,12 @@ impl Render for SonantMainWindow {
                                             .rounded(px(999.0))
                                             .bg(provider_status_color),
                                     )
-                                    .child(provider_status_label),
+                                    .child(provider_status_label)
+                                    .when_some(provider_health_details, |el, details| {
+                                        el.tooltip(move |window, cx| {
+                                            Tooltip::new(details.clone()).build(window, cx)
+                                        })
+                                    }),
                             )
                             .child(
                                 div()
@@ -2807,10 +7886,97 @@ impl Render for SonantMainWindow {
                                             .flex_col()
                                             .child(Input::new(&self.prompt_input).h_full()),
                                     )
+                                    .child(
+                                        div()
+                                            .flex()
+                                            .flex_wrap()
+                                            .items_center()
+                                            .gap_1()
+                                            .child(
+                                                Button::new("suggest-prompt-button")
+                                                    .label("Suggest")
+                                                    .tooltip("Phrasing ideas from the loaded references")
+                                                    .on_click(cx.listener(|this, _, _window, cx| {
+                                                        this.on_suggest_prompt_clicked(cx)
+                                                    })),
+                                            )
+                                            .children(self.prompt_suggestions.iter().enumerate().map(
+                                                |(index, suggestion)| {
+                                                    let snippet = suggestion.snippet.clone();
+                                                    Button::new(("prompt-suggestion", index))
+                                                        .label(suggestion.label.clone())
+                                                        .tooltip(snippet)
+                                                        .on_click(cx.listener(move |this, _, window, cx| {
+                                                            this.on_prompt_suggestion_clicked(index, window, cx)
+                                                        }))
+                                                },
+                                            )),
+                                    )
                                     .children(self.validation_error.iter().map(|message| {
                                         div()
                                             .text_color(colors.error_foreground)
-                                            .child(format!("Validation: {message}"))
+                                            .child(format!("Validation: {message}"))
+                                    }))
+                                    .children(self.prompt_lints.iter().enumerate().map(|(index, lint)| {
+                                        div()
+                                            .flex()
+                                            .items_center()
+                                            .gap_2()
+                                            .child(
+                                                div()
+                                                    .flex_1()
+                                                    .text_size(px(11.0))
+                                                    .text_color(colors.warning_foreground)
+                                                    .child(lint.message.clone()),
+                                            )
+                                            .children(lint.fix.as_ref().map(|fix| {
+                                                Button::new(("prompt-lint-fix", index))
+                                                    .label(Self::prompt_lint_fix_label(fix))
+                                                    .on_click(cx.listener(move |this, _, window, cx| {
+                                                        this.on_prompt_lint_fix_clicked(index, window, cx)
+                                                    }))
+                                            }))
+                                    }))
+                                    .when(!self.prompt_lints.is_empty(), |el| {
+                                        el.child(
+                                            div().flex().justify_end().child(
+                                                Button::new("generate-anyway")
+                                                    .label("Generate Anyway")
+                                                    .on_click(cx.listener(|this, _, window, cx| {
+                                                        this.on_generate_anyway_clicked(window, cx)
+                                                    })),
+                                            ),
+                                        )
+                                    })
+                                    .children(self.duplicate_submission_of.as_ref().map(|request_id| {
+                                        div()
+                                            .flex()
+                                            .items_center()
+                                            .gap_2()
+                                            .child(
+                                                div()
+                                                    .flex_1()
+                                                    .text_size(px(11.0))
+                                                    .text_color(colors.warning_foreground)
+                                                    .child(format!(
+                                                        "Same request as {request_id}, which is still running; the click was ignored."
+                                                    )),
+                                            )
+                                            .child(
+                                                Button::new("generate-submit-duplicate")
+                                                    .label("Submit Again")
+                                                    .on_click(cx.listener(|this, _, window, cx| {
+                                                        this.on_submit_duplicate_clicked(window, cx)
+                                                    })),
+                                            )
+                                    }))
+                                    .child(Self::section_label("Advanced Constraints", colors))
+                                    .child(Input::new(&self.constraints_input))
+                                    .children(self.constraints_error.iter().map(|message| {
+                                        div()
+                                            .text_color(colors.error_foreground)
+                                            .text_size(px(11.0))
+                                            .child(format!("Constraints: {message}"))
                                     })),
                             )
                             .child(
@@ -2865,14 +8031,85 @@ impl Render for SonantMainWindow {
                                             Select::new(&self.ai_model_dropdown)
                                                 .placeholder("Select AI model"),
                                         ),
-                                    ),
+                                    )
+                                    .child(
+                                        div()
+                                            .flex()
+                                            .items_center()
+                                            .gap_2()
+                                            .child(
+                                                Button::new("project-model-pin-button")
+                                                    .label(if self.project_model.is_some() {
+                                                        "Unpin from Project"
+                                                    } else {
+                                                        "Pin to Project"
+                                                    })
+                                                    .on_click(cx.listener(|this, _, _window, cx| {
+                                                        this.on_project_model_pin_toggled(cx)
+                                                    })),
+                                            )
+                                            .child(
+                                                div()
+                                                    .text_size(px(11.0))
+                                                    .text_color(colors.muted_foreground)
+                                                    .child(match self.project_model.as_ref() {
+                                                        Some(model) => format!("Project model: {}", model.model),
+                                                        None => "Using the settings default".to_string(),
+                                                    }),
+                                            ),
+                                    )
+                                    .child({
+                                        let picked_count = self.comparison_models.len();
+                                        div()
+                                            .flex()
+                                            .flex_wrap()
+                                            .items_center()
+                                            .gap_2()
+                                            .child(
+                                                div()
+                                                    .text_size(px(11.0))
+                                                    .text_color(colors.muted_foreground)
+                                                    .child("Compare:"),
+                                            )
+                                            .children(
+                                                Self::ai_model_dropdown_items()
+                                                    .into_iter()
+                                                    .enumerate()
+                                                    .map(|(index, item)| {
+                                                        let model = Self::model_ref_for_dropdown_item(item);
+                                                        let picked = self.comparison_models.contains(&model);
+                                                        let button = Button::new(("compare-model-toggle", index))
+                                                            .label(item)
+                                                            .on_click(cx.listener(move |this, _, _window, cx| {
+                                                                this.on_comparison_model_toggled(model.clone(), cx)
+                                                            }));
+                                                        if picked { button.primary() } else { button }
+                                                    }),
+                                            )
+                                            .child(
+                                                Button::new("compare-models-button")
+                                                    .label("Compare Models")
+                                                    .disabled(
+                                                        generating
+                                                            || !(MODEL_COMPARISON_MIN_MODELS..=MODEL_COMPARISON_MAX_MODELS)
+                                                                .contains(&picked_count),
+                                                    )
+                                                    .on_click(cx.listener(|this, _, window, cx| {
+                                                        this.on_compare_models_clicked(window, cx)
+                                                    })),
+                                            )
+                                    }),
                             )
                             .child(
                                 {
                                 let visible_slot_rows = self.visible_slot_rows.clone();
-                                let add_menu_open = self.add_track_menu_open;
-                                let channel_menu_open = self.channel_menu_open;
-                                let slot_type_menu_open = self.slot_type_menu_open;
+                                let open_overlay_menu = self.overlay_menus.open_menu();
+                                let add_menu_open = open_overlay_menu == Some(OverlayMenu::AddTrack(None));
+                                let preset_menu_open = open_overlay_menu == Some(OverlayMenu::Presets);
+                                let analysis_row_open = self.analysis_row_open;
+                                let sketch_slot_open = self.sketch_slot_open;
+                                let reference_sketch = self.reference_sketch.clone();
+                                let drum_step_pattern = self.drum_step_pattern.clone();
                                 let has_visible = !visible_slot_rows.is_empty();
 
                                 div()
@@ -2891,6 +8128,26 @@ impl Render for SonantMainWindow {
                                             .justify_between()
                                             .child(Self::section_label("Input Tracks", colors))
                                             .child(
+                                                div()
+                                                    .flex()
+                                                    .items_center()
+                                                    .gap_1()
+                                                    .child(
+                                                    div()
+                                                        .id("preset-btn-header")
+                                                        .px_1()
+                                                        .py(px(2.0))
+                                                        .rounded(radius.control)
+                                                        .text_size(px(11.0))
+                                                        .text_color(if preset_menu_open { colors.primary } else { colors.muted_foreground })
+                                                        .cursor_pointer()
+                                                        .hover(|s| s.text_color(colors.primary).bg(colors.input_background))
+                                                        .on_mouse_down(MouseButton::Left, cx.listener(|this, event: &MouseDownEvent, window, cx| {
+                                                            this.on_overlay_menu_toggled(OverlayMenu::Presets, event, window, cx);
+                                                        }))
+                                                        .child("Presets"),
+                                                    )
+                                                    .child(
                                                     div()
                                                         .id("add-track-btn-header")
                                                         .px_1()
@@ -2900,81 +8157,59 @@ impl Render for SonantMainWindow {
                                                         .text_color(if add_menu_open { colors.primary } else { colors.muted_foreground })
                                                         .cursor_pointer()
                                                         .hover(|s| s.text_color(colors.primary).bg(colors.input_background))
-                                                        .on_click(cx.listener(|this, _, _window, cx| {
-                                                            this.on_add_track_clicked(cx);
+                                                        .on_mouse_down(MouseButton::Left, cx.listener(|this, event: &MouseDownEvent, window, cx| {
+                                                            this.on_overlay_menu_toggled(OverlayMenu::AddTrack(None), event, window, cx);
                                                         }))
                                                         .child(if add_menu_open { "- Cancel" } else { "+ Add" }),
+                                                    )
+                                                    // Header popovers, opening down from the buttons' right edge
+                                                    .when(preset_menu_open || add_menu_open, |el| {
+                                                        let menu = if preset_menu_open {
+                                                            self.preset_menu(&theme, cx)
+                                                        } else {
+                                                            self.add_track_menu(None, &theme, cx)
+                                                        };
+                                                        el.child(
+                                                            div()
+                                                                .absolute()
+                                                                .top(gpui::relative(1.0))
+                                                                .right_0()
+                                                                .child(self.overlay_menu(menu, Corner::TopRight, cx)),
+                                                        )
+                                                    }),
                                             ),
                                     )
-                                    // Add Track menu (shown when add_menu_open)
-                                    .when(add_menu_open, |el| {
-                                        el.child(
-                                            div()
-                                                .id("add-track-menu")
-                                                .rounded(radius.control)
-                                                .border_1()
-                                                .border_color(colors.panel_active_border)
-                                                .bg(colors.panel_background)
-                                                .overflow_hidden()
-                                                .child(
-                                                    div()
-                                                        .px_3()
-                                                        .py(px(6.0))
-                                                        .border_b_1()
-                                                        .border_color(colors.panel_border)
-                                                        .text_size(px(10.0))
-                                                        .text_color(colors.muted_foreground)
-                                                        .font_weight(gpui::FontWeight::BOLD)
-                                                        .child("SELECT TYPE"),
-                                                )
-                                                .children(Self::reference_slots().iter().copied().map(|slot| {
-                                                    let slot_color = colors.slot_color(slot);
-                                                    let short_label = Self::slot_short_label(slot);
-                                                    div()
-                                                        .id(("add-slot-option", Self::reference_slot_index(slot)))
-                                                        .flex()
-                                                        .items_center()
-                                                        .gap_2()
-                                                        .h(px(36.0))
-                                                        .px_2()
-                                                        .bg(colors.panel_background)
-                                                        .cursor_pointer()
-                                                        .hover(|s| s.bg(colors.panel_active_background))
+                                    .child(
+                                        div()
+                                            .flex()
+                                            .items_center()
+                                            .gap_1()
+                                            .child(
+                                                div()
+                                                    .text_size(px(11.0))
+                                                    .text_color(colors.muted_foreground)
+                                                    .child("Live velocity:"),
+                                            )
+                                            .children(VelocityProfile::ALL.into_iter().enumerate().map(
+                                                |(index, profile)| {
+                                                    let button = Button::new(("live-velocity-profile", index))
+                                                        .label(profile.label())
                                                         .on_click(cx.listener(move |this, _, _window, cx| {
-                                                            this.on_add_track_slot_selected(slot, cx);
-                                                        }))
-                                                        .child(
-                                                            div()
-                                                                .w(px(6.0))
-                                                                .h(px(20.0))
-                                                                .flex_none()
-                                                                .rounded(px(2.0))
-                                                                .bg(slot_color),
-                                                        )
-                                                        .child(
-                                                            div()
-                                                                .flex_1()
-                                                                .text_size(px(12.0))
-                                                                .text_color(colors.surface_foreground)
-                                                                .child(Self::reference_slot_label(slot)),
-                                                        )
-                                                        .child(
-                                                            div()
-                                                                .px(px(6.0))
-                                                                .py(px(2.0))
-                                                                .rounded(px(4.0))
-                                                                .text_size(px(9.0))
-                                                                .text_color(slot_color)
-                                                                .font_weight(gpui::FontWeight::BOLD)
-                                                                .border_1()
-                                                                .border_color(slot_color)
-                                                                .child(short_label),
-                                                        )
-                                                })),
-                                        )
-                                    })
+                                                            this.on_live_velocity_profile_toggled(profile, cx)
+                                                        }));
+                                                    if self
+                                                        .live_velocity_profile
+                                                        .is_some_and(|(active, _)| active == profile)
+                                                    {
+                                                        button.primary()
+                                                    } else {
+                                                        button
+                                                    }
+                                                },
+                                            )),
+                                    )
                                     // Empty state drop zone (no tracks added yet)
-                                    .when(!has_visible && !add_menu_open, |el| {
+                                    .when(!has_visible, |el| {
                                         el.child(
                                             div()
                                                 .id("input-tracks-empty")
@@ -3038,9 +8273,14 @@ impl Render for SonantMainWindow {
                                         el.child(
                                             div()
                                                 .id("input-tracks-list")
+                                                .track_focus(&self.track_list_focus)
+                                                .on_key_down(cx.listener(|this, event: &KeyDownEvent, _window, cx| {
+                                                    this.on_track_list_key_down(event, cx)
+                                                }))
                                                 .rounded(radius.control)
                                                 .border_1()
                                                 .border_color(colors.panel_border)
+                                                .focus(|style| style.border_color(colors.primary))
                                                 .bg(colors.input_background)
                                                 .overflow_hidden()
                                                 .child(
@@ -3077,17 +8317,29 @@ impl Render for SonantMainWindow {
                                                     let is_live = self.source_for_slot(slot) == ReferenceSource::Live;
                                                     let live_ch = self.channel_mapping_for_slot(slot).unwrap_or(1);
                                                     let monitoring_on = is_live && self.recording_enabled_for_channel(live_ch);
+                                                    let thru_on = is_live && self.midi_thru_slots.contains(&slot);
+                                                    let supports_mono = supports_monophonic_cleanup(slot);
+                                                    let mono_on = supports_mono && self.monophonic_slots.contains(&slot);
                                                     let slot_error = self.midi_slot_error_for_row(slot, row_index).cloned();
                                                     let piano_roll_visible = !self.piano_roll_hidden_rows.contains(&row_index);
+                                                    let is_previewing = self.previewing_reference_row == Some(row_index);
+                                                    let has_reference = is_previewing
+                                                        || if is_live {
+                                                            self.midi_input_router.reference_metrics(slot).event_count > 0
+                                                        } else {
+                                                            self.load_midi_use_case.slot_reference(slot).is_some()
+                                                        };
                                                     // グレーアウト用の色（非表示行は薄く）
                                                     let row_slot_color = if piano_roll_visible { slot_color } else { slot_color.opacity(0.25) };
                                                     let row_fg = if piano_roll_visible { colors.surface_foreground } else { colors.muted_foreground.opacity(0.4) };
+                                                    let is_keyboard_row = self.focused_track_row == Some(row_index);
 
                                                     div()
                                                         .id(("track-row", row_index))
                                                         .flex()
                                                         .items_center()
                                                         .h(px(40.0))
+                                                        .when(is_keyboard_row, |el| el.border_1().border_color(colors.primary))
                                                         .bg(if piano_roll_visible {
                                                             colors.panel_background
                                                         } else {
@@ -3095,11 +8347,20 @@ impl Render for SonantMainWindow {
                                                         })
                                                         .hover(|s| s.bg(colors.input_background))
                                                         .can_drop(move |value, _, _| {
-                                                            !is_live
-                                                                && value
-                                                                    .downcast_ref::<ExternalPaths>()
-                                                                    .is_some_and(|paths| !paths.paths().is_empty())
+                                                            value.downcast_ref::<DraggedTrackRow>().is_some()
+                                                                || (!is_live
+                                                                    && value
+                                                                        .downcast_ref::<ExternalPaths>()
+                                                                        .is_some_and(|paths| !paths.paths().is_empty()))
                                                         })
+                                                        .drag_over::<DraggedTrackRow>(move |style, _, _, _| {
+                                                            style.border_t_2().border_color(colors.primary)
+                                                        })
+                                                        .on_drop(cx.listener(
+                                                            move |this, dragged: &DraggedTrackRow, _window, cx| {
+                                                                this.on_track_row_moved(dragged.row_index, row_index, cx)
+                                                            },
+                                                        ))
                                                         .drag_over::<ExternalPaths>(move |style, paths, _, _| {
                                                             if is_live {
                                                                 style
@@ -3118,13 +8379,27 @@ impl Render for SonantMainWindow {
                                                                 this.on_midi_slot_drop(slot, row_index, paths, cx)
                                                             },
                                                         ))
-                                                        // Color stripe
+                                                        // Color stripe (drag handle for reordering)
                                                         .child(
                                                             div()
+                                                                .id(("slot-drag-handle", row_index))
                                                                 .w(px(6.0))
                                                                 .h_full()
                                                                 .flex_none()
-                                                                .bg(row_slot_color),
+                                                                .bg(row_slot_color)
+                                                                .cursor_grab()
+                                                                .on_drag(
+                                                                    DraggedTrackRow {
+                                                                        row_index,
+                                                                        label: format!(
+                                                                            "{} · {}",
+                                                                            Self::reference_slot_label(slot),
+                                                                            source_label
+                                                                        ),
+                                                                        color: slot_color,
+                                                                    },
+                                                                    |dragged, _offset, _window, cx| cx.new(|_| dragged.clone()),
+                                                                ),
                                                         )
                                                         // Source label + type badge (always clickable)
                                                         .child(
@@ -3145,88 +8420,297 @@ impl Render for SonantMainWindow {
                                                                         .text_color(row_fg)
                                                                         .cursor_pointer()
                                                                         .hover(|s| s.text_color(colors.primary))
-                                                                        .on_click(cx.listener(move |this, _, window, cx| {
-                                                                            if is_live {
-                                                                                this.on_channel_menu_toggled(row_index, cx);
-                                                                            } else {
+                                                                        .when(is_live, |el| {
+                                                                            el.on_mouse_down(MouseButton::Left, cx.listener(move |this, event: &MouseDownEvent, window, cx| {
+                                                                                this.on_overlay_menu_toggled(OverlayMenu::Channel(row_index), event, window, cx);
+                                                                            }))
+                                                                        })
+                                                                        .when(!is_live, |el| {
+                                                                            el.on_click(cx.listener(move |this, _, window, cx| {
                                                                                 this.on_select_midi_file_clicked(slot, row_index, window, cx);
-                                                                            }
-                                                                        }))
+                                                                            }))
+                                                                        })
                                                                         .child(source_label),
                                                                 )
-                                                                // Type badge (clickable → slot type menu)
+                                                                // Type badge (clickable → slot type menu)
+                                                                .child(
+                                                                    div()
+                                                                        .id(("slot-type-badge", row_index))
+                                                                        .flex_none()
+                                                                        .px(px(6.0))
+                                                                        .py(px(2.0))
+                                                                        .rounded(px(4.0))
+                                                                        .text_size(px(9.0))
+                                                                        .text_color(row_slot_color)
+                                                                        .font_weight(gpui::FontWeight::BOLD)
+                                                                        .border_1()
+                                                                        .border_color(row_slot_color)
+                                                                        .cursor_pointer()
+                                                                        .hover(|s| s.bg(colors.input_background))
+                                                                        .on_mouse_down(MouseButton::Left, cx.listener(move |this, event: &MouseDownEvent, window, cx| {
+                                                                            this.on_overlay_menu_toggled(OverlayMenu::SlotType(row_index), event, window, cx);
+                                                                        }))
+                                                                        .child(short_label),
+                                                                ),
+                                                        )
+                                                        // Action buttons (fixed layout: source toggle + monitor + remove)
+                                                        .child(
+                                                            div()
+                                                                .flex()
+                                                                .items_center()
+                                                                .gap_1()
+                                                                .pr_2()
+                                                                .pl_2()
+                                                                .h(px(24.0))
+                                                                .border_l_1()
+                                                                .border_color(colors.panel_border)
+                                                                // Source toggle
+                                                                .child(
+                                                                    div()
+                                                                        .id(("slot-source-toggle", row_index))
+                                                                        .px(px(4.0))
+                                                                        .py(px(2.0))
+                                                                        .rounded(px(3.0))
+                                                                        .text_size(px(9.0))
+                                                                        .text_color(if is_live { colors.surface_foreground } else { colors.muted_foreground })
+                                                                        .font_weight(gpui::FontWeight::BOLD)
+                                                                        .cursor_pointer()
+                                                                        .hover(|s| s.text_color(colors.surface_foreground).bg(colors.input_background))
+                                                                        .on_click(cx.listener(move |this, _, _window, cx| {
+                                                                            this.on_slot_source_toggled(slot, cx);
+                                                                        }))
+                                                                        .child(if is_live { "INPUT" } else { "FILE" }),
+                                                                )
+                                                                // Recent files menu toggle (FILE only)
+                                                                .child(
+                                                                    div()
+                                                                        .id(("slot-recent-files", row_index))
+                                                                        .px(px(4.0))
+                                                                        .py(px(2.0))
+                                                                        .rounded(px(3.0))
+                                                                        .text_size(px(9.0))
+                                                                        .font_weight(gpui::FontWeight::BOLD)
+                                                                        .text_color(if is_live {
+                                                                            colors.panel_border
+                                                                        } else if open_overlay_menu == Some(OverlayMenu::RecentFiles(row_index)) {
+                                                                            colors.primary
+                                                                        } else {
+                                                                            colors.muted_foreground
+                                                                        })
+                                                                        .when(!is_live, |el| {
+                                                                            el.cursor_pointer()
+                                                                                .hover(|s| s.text_color(colors.surface_foreground).bg(colors.input_background))
+                                                                                .on_mouse_down(MouseButton::Left, cx.listener(move |this, event: &MouseDownEvent, window, cx| {
+                                                                                    this.on_overlay_menu_toggled(OverlayMenu::RecentFiles(row_index), event, window, cx);
+                                                                                }))
+                                                                        })
+                                                                        .child("RECENT ▾"),
+                                                                )
+                                                                // Monitoring toggle (LIVE only)
+                                                                .child(
+                                                                    div()
+                                                                        .id(("slot-monitor", row_index))
+                                                                        .w(px(20.0))
+                                                                        .h(px(20.0))
+                                                                        .flex()
+                                                                        .items_center()
+                                                                        .justify_center()
+                                                                        .rounded(px(999.0))
+                                                                        .text_size(px(12.0))
+                                                                        .text_color(if is_live {
+                                                                            if monitoring_on { colors.error_foreground } else { colors.muted_foreground }
+                                                                        } else {
+                                                                            colors.panel_border
+                                                                        })
+                                                                        .when(is_live, |el| {
+                                                                            el.cursor_pointer()
+                                                                                .hover(|s| s.text_color(colors.surface_foreground))
+                                                                                .on_click(cx.listener(move |this, _, _window, cx| {
+                                                                                    this.on_recording_channel_toggled(live_ch, cx);
+                                                                                }))
+                                                                        })
+                                                                        .child("●"),
+                                                                )
+                                                                // MIDI thru toggle (LIVE only)
+                                                                .child(
+                                                                    div()
+                                                                        .id(("slot-thru", row_index))
+                                                                        .px(px(4.0))
+                                                                        .py(px(2.0))
+                                                                        .rounded(px(3.0))
+                                                                        .text_size(px(9.0))
+                                                                        .font_weight(gpui::FontWeight::BOLD)
+                                                                        .text_color(if is_live {
+                                                                            if thru_on { colors.primary } else { colors.muted_foreground }
+                                                                        } else {
+                                                                            colors.panel_border
+                                                                        })
+                                                                        .when(is_live, |el| {
+                                                                            el.cursor_pointer()
+                                                                                .hover(|s| s.text_color(colors.surface_foreground))
+                                                                                .on_click(cx.listener(move |this, _, _window, cx| {
+                                                                                    this.on_midi_thru_toggled(slot, cx);
+                                                                                }))
+                                                                        })
+                                                                        .child("THRU"),
+                                                                )
+                                                                // Monophonic cleanup toggle (Melody/Bassline only)
+                                                                .child(
+                                                                    div()
+                                                                        .id(("slot-mono", row_index))
+                                                                        .px(px(4.0))
+                                                                        .py(px(2.0))
+                                                                        .rounded(px(3.0))
+                                                                        .text_size(px(9.0))
+                                                                        .font_weight(gpui::FontWeight::BOLD)
+                                                                        .text_color(if supports_mono {
+                                                                            if mono_on { colors.primary } else { colors.muted_foreground }
+                                                                        } else {
+                                                                            colors.panel_border
+                                                                        })
+                                                                        .when(supports_mono, |el| {
+                                                                            el.cursor_pointer()
+                                                                                .hover(|s| s.text_color(colors.surface_foreground))
+                                                                                .on_click(cx.listener(move |this, _, _window, cx| {
+                                                                                    this.on_monophonic_toggled(slot, cx);
+                                                                                }))
+                                                                        })
+                                                                        .child("MONO"),
+                                                                )
+                                                                // Reference preview (plays the loaded or captured material)
                                                                 .child(
                                                                     div()
-                                                                        .id(("slot-type-badge", row_index))
-                                                                        .flex_none()
-                                                                        .px(px(6.0))
+                                                                        .id(("slot-preview", row_index))
+                                                                        .w(px(20.0))
+                                                                        .h(px(20.0))
+                                                                        .flex()
+                                                                        .items_center()
+                                                                        .justify_center()
+                                                                        .rounded(px(999.0))
+                                                                        .text_size(px(10.0))
+                                                                        .text_color(if is_previewing {
+                                                                            colors.primary
+                                                                        } else if has_reference {
+                                                                            colors.muted_foreground
+                                                                        } else {
+                                                                            colors.panel_border
+                                                                        })
+                                                                        .when(has_reference, |el| {
+                                                                            el.cursor_pointer()
+                                                                                .hover(|s| s.text_color(colors.surface_foreground))
+                                                                                .on_click(cx.listener(move |this, _, _window, cx| {
+                                                                                    this.on_reference_preview_clicked(row_index, cx);
+                                                                                }))
+                                                                        })
+                                                                        .child(if is_previewing { "■" } else { "▶" }),
+                                                                )
+                                                                // Save the captured take as a file (LIVE only)
+                                                                .child(
+                                                                    div()
+                                                                        .id(("slot-save-take", row_index))
+                                                                        .px(px(4.0))
                                                                         .py(px(2.0))
-                                                                        .rounded(px(4.0))
+                                                                        .rounded(px(3.0))
                                                                         .text_size(px(9.0))
-                                                                        .text_color(row_slot_color)
                                                                         .font_weight(gpui::FontWeight::BOLD)
-                                                                        .border_1()
-                                                                        .border_color(row_slot_color)
-                                                                        .cursor_pointer()
-                                                                        .hover(|s| s.bg(colors.input_background))
-                                                                        .on_click(cx.listener(move |this, _, _window, cx| {
-                                                                            this.on_slot_type_menu_toggled(row_index, cx);
-                                                                        }))
-                                                                        .child(short_label),
-                                                                ),
-                                                        )
-                                                        // Action buttons (fixed layout: source toggle + monitor + remove)
-                                                        .child(
-                                                            div()
-                                                                .flex()
-                                                                .items_center()
-                                                                .gap_1()
-                                                                .pr_2()
-                                                                .pl_2()
-                                                                .h(px(24.0))
-                                                                .border_l_1()
-                                                                .border_color(colors.panel_border)
-                                                                // Source toggle
+                                                                        .text_color(if is_live && has_reference {
+                                                                            colors.muted_foreground
+                                                                        } else {
+                                                                            colors.panel_border
+                                                                        })
+                                                                        .when(is_live && has_reference, |el| {
+                                                                            el.cursor_pointer()
+                                                                                .hover(|s| s.text_color(colors.surface_foreground))
+                                                                                .tooltip(|window, cx| {
+                                                                                    Tooltip::new("Save take as file and switch this row to FILE")
+                                                                                        .build(window, cx)
+                                                                                })
+                                                                                .on_click(cx.listener(move |this, _, window, cx| {
+                                                                                    this.on_save_take_as_file_clicked(slot, row_index, window, cx);
+                                                                                }))
+                                                                        })
+                                                                        .child("SAVE"),
+                                                                )
+                                                                // Reveal the row's MIDI for dragging into the DAW
                                                                 .child(
                                                                     div()
-                                                                        .id(("slot-source-toggle", row_index))
+                                                                        .id(("slot-reveal-midi", row_index))
                                                                         .px(px(4.0))
                                                                         .py(px(2.0))
                                                                         .rounded(px(3.0))
                                                                         .text_size(px(9.0))
-                                                                        .text_color(if is_live { colors.surface_foreground } else { colors.muted_foreground })
                                                                         .font_weight(gpui::FontWeight::BOLD)
+                                                                        .text_color(if has_reference {
+                                                                            colors.muted_foreground
+                                                                        } else {
+                                                                            colors.panel_border
+                                                                        })
+                                                                        .when(has_reference, |el| {
+                                                                            el.cursor_pointer()
+                                                                                .hover(|s| s.text_color(colors.surface_foreground))
+                                                                                .tooltip(|window, cx| {
+                                                                                    Tooltip::new("Show as a .mid file to drag into the DAW")
+                                                                                        .build(window, cx)
+                                                                                })
+                                                                                .on_click(cx.listener(move |this, _, _window, cx| {
+                                                                                    this.on_reveal_track_row_midi_clicked(slot, row_index, cx);
+                                                                                }))
+                                                                        })
+                                                                        .child(".MID"),
+                                                                )
+                                                                // Analysis panel toggle
+                                                                .child(
+                                                                    div()
+                                                                        .id(("slot-analysis", row_index))
+                                                                        .w(px(20.0))
+                                                                        .h(px(20.0))
+                                                                        .flex()
+                                                                        .items_center()
+                                                                        .justify_center()
+                                                                        .rounded(px(999.0))
+                                                                        .text_size(px(11.0))
+                                                                        .text_color(if analysis_row_open == Some(row_index) {
+                                                                            colors.primary
+                                                                        } else {
+                                                                            colors.muted_foreground
+                                                                        })
                                                                         .cursor_pointer()
-                                                                        .hover(|s| s.text_color(colors.surface_foreground).bg(colors.input_background))
+                                                                        .hover(|s| s.text_color(colors.surface_foreground))
                                                                         .on_click(cx.listener(move |this, _, _window, cx| {
-                                                                            this.on_slot_source_toggled(slot, cx);
+                                                                            this.on_analysis_panel_toggled(row_index, cx);
                                                                         }))
-                                                                        .child(if is_live { "INPUT" } else { "FILE" }),
+                                                                        .child("ⓘ"),
                                                                 )
-                                                                // Monitoring toggle (LIVE only)
+                                                                // Keyboard sketch panel toggle
                                                                 .child(
                                                                     div()
-                                                                        .id(("slot-monitor", row_index))
+                                                                        .id(("slot-sketch", row_index))
                                                                         .w(px(20.0))
                                                                         .h(px(20.0))
                                                                         .flex()
                                                                         .items_center()
                                                                         .justify_center()
                                                                         .rounded(px(999.0))
-                                                                        .text_size(px(12.0))
-                                                                        .text_color(if is_live {
-                                                                            if monitoring_on { colors.error_foreground } else { colors.muted_foreground }
+                                                                        .text_size(px(11.0))
+                                                                        .text_color(if sketch_slot_open == Some(slot) {
+                                                                            colors.primary
                                                                         } else {
-                                                                            colors.panel_border
+                                                                            colors.muted_foreground
                                                                         })
-                                                                        .when(is_live, |el| {
-                                                                            el.cursor_pointer()
-                                                                                .hover(|s| s.text_color(colors.surface_foreground))
-                                                                                .on_click(cx.listener(move |this, _, _window, cx| {
-                                                                                    this.on_recording_channel_toggled(live_ch, cx);
-                                                                                }))
+                                                                        .cursor_pointer()
+                                                                        .hover(|s| s.text_color(colors.surface_foreground))
+                                                                        .tooltip(move |window, cx| {
+                                                                            Tooltip::new(if slot == ReferenceSlot::DrumPattern {
+                                                                                "Draw a drum pattern on the step grid"
+                                                                            } else {
+                                                                                "Sketch a reference on the keyboard"
+                                                                            })
+                                                                            .build(window, cx)
                                                                         })
-                                                                        .child("●"),
+                                                                        .on_click(cx.listener(move |this, _, _window, cx| {
+                                                                            this.on_sketch_panel_toggled(slot, cx);
+                                                                        }))
+                                                                        .child("✎"),
                                                                 )
                                                                 // Piano roll visibility toggle
                                                                 .child(
@@ -3248,6 +8732,30 @@ impl Render for SonantMainWindow {
                                                                         .child(if piano_roll_visible { "◉" } else { "◌" }),
                                                                 )
 
+                                                                // Add a track below this one
+                                                                .child(
+                                                                    div()
+                                                                        .id(("slot-insert", row_index))
+                                                                        .w(px(20.0))
+                                                                        .h(px(20.0))
+                                                                        .flex()
+                                                                        .items_center()
+                                                                        .justify_center()
+                                                                        .rounded(px(999.0))
+                                                                        .text_size(px(12.0))
+                                                                        .text_color(if open_overlay_menu == Some(OverlayMenu::AddTrack(Some(row_index))) {
+                                                                            colors.primary
+                                                                        } else {
+                                                                            colors.muted_foreground
+                                                                        })
+                                                                        .cursor_pointer()
+                                                                        .hover(|s| s.text_color(colors.surface_foreground))
+                                                                        .tooltip(|window, cx| Tooltip::new("Add a track below").build(window, cx))
+                                                                        .on_mouse_down(MouseButton::Left, cx.listener(move |this, event: &MouseDownEvent, window, cx| {
+                                                                            this.on_overlay_menu_toggled(OverlayMenu::AddTrack(Some(row_index)), event, window, cx);
+                                                                        }))
+                                                                        .child("+"),
+                                                                )
                                                                 // Remove track button — trash icon
                                                                 .child(
                                                                     div()
@@ -3289,392 +8797,1183 @@ impl Render for SonantMainWindow {
                                                                         }))
                                                                 })
                                                         }))
+                                                        // Row popovers, opening down from the row's color stripe
+                                                        .children(open_overlay_menu.filter(|menu| menu.row() == Some(row_index)).map(|menu| {
+                                                            let menu = match menu {
+                                                                OverlayMenu::Channel(_) => self.channel_menu(slot, &theme, cx),
+                                                                OverlayMenu::SlotType(_) => self.slot_type_menu(row_index, slot, &theme, cx),
+                                                                OverlayMenu::RecentFiles(_) => self.recent_files_menu(row_index, &theme, cx),
+                                                                OverlayMenu::AddTrack(_) | OverlayMenu::Presets => {
+                                                                    self.add_track_menu(Some(row_index), &theme, cx)
+                                                                }
+                                                            };
+                                                            div()
+                                                                .absolute()
+                                                                .top(gpui::relative(1.0))
+                                                                .left(px(6.0))
+                                                                .child(self.overlay_menu(menu, Corner::TopLeft, cx))
+                                                        }))
                                                 }))
                                         )
                                     })
-                                    // Channel selection menu (shown when a LIVE row's label is clicked)
-                                    .when(channel_menu_open.is_some(), |el| {
-                                        let open_row = channel_menu_open.unwrap_or(0);
-                                        let menu_slot = visible_slot_rows.get(open_row).copied().unwrap_or(ReferenceSlot::Melody);
-                                        let current_ch = self.channel_mapping_for_slot(menu_slot).unwrap_or(1);
+                                    // Reference analysis (shown when a row's ⓘ toggle is clicked)
+                                    .children(open_analysis.map(|(slot, analysis)| {
+                                        let stat = |label: &'static str, value: String| {
+                                            div()
+                                                .flex()
+                                                .flex_col()
+                                                .gap(px(2.0))
+                                                .child(
+                                                    div()
+                                                        .text_size(px(9.0))
+                                                        .text_color(colors.muted_foreground)
+                                                        .font_weight(gpui::FontWeight::BOLD)
+                                                        .child(label),
+                                                )
+                                                .child(
+                                                    div()
+                                                        .text_size(px(11.0))
+                                                        .text_color(colors.surface_foreground)
+                                                        .child(value),
+                                                )
+                                        };
+                                        let panel = div()
+                                            .id("reference-analysis-panel")
+                                            .flex()
+                                            .flex_col()
+                                            .gap_2()
+                                            .px_3()
+                                            .py(px(8.0))
+                                            .rounded(radius.control)
+                                            .border_1()
+                                            .border_color(colors.panel_active_border)
+                                            .bg(colors.panel_background)
+                                            .child(
+                                                div()
+                                                    .text_size(px(10.0))
+                                                    .text_color(colors.muted_foreground)
+                                                    .font_weight(gpui::FontWeight::BOLD)
+                                                    .child(format!(
+                                                        "ANALYSIS — {}",
+                                                        Self::reference_slot_label(slot).to_uppercase()
+                                                    )),
+                                            );
+                                        let Some(analysis) = analysis else {
+                                            return panel.child(
+                                                div()
+                                                    .text_size(px(11.0))
+                                                    .text_color(colors.muted_foreground)
+                                                    .child("Load or record a reference to analyze it."),
+                                            );
+                                        };
+                                        let peak = analysis.notes_per_bar.iter().copied().max().unwrap_or(0).max(1);
+                                        let bars = analysis.notes_per_bar.len();
+                                        panel
+                                            .when(slot != ReferenceSlot::DrumPattern, |el| {
+                                                el.child(
+                                                    Button::new("reference-analysis-split-button")
+                                                        .label("Split into Melody + Chords")
+                                                        .on_click(cx.listener(move |this, _, _window, cx| {
+                                                            this.on_split_piano_take_clicked(slot, cx)
+                                                        })),
+                                                )
+                                            })
+                                            .child(
+                                                div()
+                                                    .flex()
+                                                    .flex_wrap()
+                                                    .gap_4()
+                                                    .child(stat(
+                                                        "KEY",
+                                                        analysis.key.map_or_else(
+                                                            || "—".to_string(),
+                                                            |key| format!("{} ({:.0}%)", key.name(), key.confidence.max(0.0) * 100.0),
+                                                        ),
+                                                    ))
+                                                    .child(stat(
+                                                        "TEMPO",
+                                                        analysis
+                                                            .tempo_bpm
+                                                            .map_or_else(
+                                                                || "Not in file".to_string(),
+                                                                |bpm| {
+                                                                    if (bpm - f64::from(session_bpm)).abs() < 0.5 {
+                                                                        format!("{bpm:.1} BPM")
+                                                                    } else {
+                                                                        format!("{bpm:.1} BPM · read at {session_bpm}")
+                                                                    }
+                                                                },
+                                                            ),
+                                                    ))
+                                                    .child(stat(
+                                                        "RANGE",
+                                                        format!("{}–{}", pitch_label(analysis.min_pitch), pitch_label(analysis.max_pitch)),
+                                                    ))
+                                                    .child(stat(
+                                                        "DURATION",
+                                                        match analysis.duration_seconds {
+                                                            Some(seconds) => format!("{bars} bars · {seconds:.1} s"),
+                                                            None => format!("{bars} bars · {:.0} beats", analysis.duration_beats),
+                                                        },
+                                                    )),
+                                            )
+                                            .child(
+                                                div()
+                                                    .text_size(px(9.0))
+                                                    .text_color(colors.muted_foreground)
+                                                    .font_weight(gpui::FontWeight::BOLD)
+                                                    .child("NOTES PER BAR"),
+                                            )
+                                            .child(
+                                                div()
+                                                    .flex()
+                                                    .items_end()
+                                                    .gap(px(2.0))
+                                                    .h(px(32.0))
+                                                    .children(analysis.notes_per_bar.iter().enumerate().map(|(bar, count)| {
+                                                        let height = 32.0 * *count as f32 / peak as f32;
+                                                        div()
+                                                            .id(("analysis-density-bar", bar))
+                                                            .flex_1()
+                                                            .max_w(px(12.0))
+                                                            .h(px(height.max(1.0)))
+                                                            .rounded(px(1.0))
+                                                            .bg(colors.slot_color(slot))
+                                                            .tooltip({
+                                                                let details = format!("Bar {}: {count} notes", bar + 1);
+                                                                move |window, cx| Tooltip::new(details.clone()).build(window, cx)
+                                                            })
+                                                    })),
+                                            )
+                                    }))
+                                    // Keyboard sketch (shown when a row's ✎ toggle is clicked)
+                                    .children(sketch_slot_open.filter(|slot| *slot != ReferenceSlot::DrumPattern).map(|slot| {
+                                        let sketch = &reference_sketch;
+                                        let slot_color = colors.slot_color(slot);
+                                        let low_pitch = sketch.low_pitch();
+                                        let pitches = (0..SKETCH_PITCH_ROWS).rev().map(move |row| low_pitch + row);
+                                        let cursor = sketch.cursor();
+                                        let step_cell = |step: u16, pitch: u8| {
+                                            let set = sketch.has_note(step, pitch);
+                                            div()
+                                                .id(("sketch-step", usize::from(step) * 128 + usize::from(pitch)))
+                                                .w(px(12.0))
+                                                .h(px(10.0))
+                                                .flex_none()
+                                                .rounded(px(2.0))
+                                                .border_1()
+                                                .border_color(if step == cursor {
+                                                    colors.primary
+                                                } else if step % 4 == 0 {
+                                                    colors.panel_active_border
+                                                } else {
+                                                    colors.panel_border
+                                                })
+                                                .bg(if set {
+                                                    slot_color
+                                                } else if Self::piano_roll_is_black_key(i16::from(pitch)) {
+                                                    colors.surface_background
+                                                } else {
+                                                    colors.input_background
+                                                })
+                                                .cursor_pointer()
+                                                .on_click(cx.listener(move |this, _, _window, cx| {
+                                                    this.on_sketch_step_toggled(step, pitch, cx);
+                                                }))
+                                        };
+                                        div()
+                                            .id("reference-sketch-panel")
+                                            .flex()
+                                            .flex_col()
+                                            .gap_2()
+                                            .px_3()
+                                            .py(px(8.0))
+                                            .rounded(radius.control)
+                                            .border_1()
+                                            .border_color(colors.panel_active_border)
+                                            .bg(colors.panel_background)
+                                            .child(
+                                                div()
+                                                    .text_size(px(10.0))
+                                                    .text_color(colors.muted_foreground)
+                                                    .font_weight(gpui::FontWeight::BOLD)
+                                                    .child(format!(
+                                                        "SKETCH — {}",
+                                                        Self::reference_slot_label(slot).to_uppercase()
+                                                    )),
+                                            )
+                                            .child(
+                                                div()
+                                                    .text_size(px(11.0))
+                                                    .text_color(colors.muted_foreground)
+                                                    .child(format!(
+                                                        "Play a key to write it at step {} and move on, or click the grid to toggle a note. Each note lasts a sixteenth.",
+                                                        cursor + 1
+                                                    )),
+                                            )
+                                            .child(
+                                                div()
+                                                    .flex()
+                                                    .flex_wrap()
+                                                    .items_center()
+                                                    .gap_2()
+                                                    .child(
+                                                        Button::new("reference-sketch-octave-down")
+                                                            .label("Octave −")
+                                                            .on_click(cx.listener(|this, _, _window, cx| {
+                                                                this.on_sketch_octave_shifted(false, cx);
+                                                            })),
+                                                    )
+                                                    .child(
+                                                        Button::new("reference-sketch-octave-up")
+                                                            .label("Octave +")
+                                                            .on_click(cx.listener(|this, _, _window, cx| {
+                                                                this.on_sketch_octave_shifted(true, cx);
+                                                            })),
+                                                    )
+                                                    .child(
+                                                        Button::new("reference-sketch-bars")
+                                                            .label(match sketch.bars() {
+                                                                1 => "1 Bar".to_string(),
+                                                                bars => format!("{bars} Bars"),
+                                                            })
+                                                            .on_click(cx.listener(|this, _, _window, cx| {
+                                                                this.on_sketch_bars_cycled(cx);
+                                                            })),
+                                                    )
+                                                    .child(
+                                                        Button::new("reference-sketch-rest")
+                                                            .label("Rest")
+                                                            .on_click(cx.listener(|this, _, _window, cx| {
+                                                                this.on_sketch_rest_clicked(cx);
+                                                            })),
+                                                    )
+                                                    .child(
+                                                        Button::new("reference-sketch-clear")
+                                                            .label("Clear")
+                                                            .on_click(cx.listener(|this, _, _window, cx| {
+                                                                this.on_sketch_cleared(cx);
+                                                            })),
+                                                    )
+                                                    .child({
+                                                        let button = Button::new("reference-sketch-write")
+                                                            .label(format!("Write to {}", Self::reference_slot_label(slot)))
+                                                            .on_click(cx.listener(move |this, _, _window, cx| {
+                                                                this.on_sketch_written(slot, cx);
+                                                            }));
+                                                        if sketch.is_empty() { button } else { button.primary() }
+                                                    }),
+                                            )
+                                            .child(
+                                                div()
+                                                    .id("reference-sketch-grid")
+                                                    .flex()
+                                                    .flex_col()
+                                                    .gap(px(1.0))
+                                                    .overflow_x_scroll()
+                                                    .children(pitches.clone().map(|pitch| {
+                                                        div()
+                                                            .flex()
+                                                            .items_center()
+                                                            .gap(px(1.0))
+                                                            .child(
+                                                                div()
+                                                                    .w(px(32.0))
+                                                                    .flex_none()
+                                                                    .text_size(px(9.0))
+                                                                    .text_color(colors.muted_foreground)
+                                                                    .child(pitch_label(pitch)),
+                                                            )
+                                                            .children((0..sketch.step_count()).map(|step| step_cell(step, pitch)))
+                                                    })),
+                                            )
+                                            .child(
+                                                div()
+                                                    .flex()
+                                                    .items_end()
+                                                    .gap(px(2.0))
+                                                    .children(pitches.rev().map(|pitch| {
+                                                        let black = Self::piano_roll_is_black_key(i16::from(pitch));
+                                                        div()
+                                                            .id(("reference-sketch-key", usize::from(pitch)))
+                                                            .w(px(22.0))
+                                                            .h(px(if black { 32.0 } else { 44.0 }))
+                                                            .flex()
+                                                            .items_end()
+                                                            .justify_center()
+                                                            .pb(px(2.0))
+                                                            .rounded(px(3.0))
+                                                            .border_1()
+                                                            .border_color(colors.panel_border)
+                                                            .bg(if black { colors.surface_background } else { colors.surface_foreground })
+                                                            .text_size(px(8.0))
+                                                            .text_color(if black { colors.muted_foreground } else { colors.surface_background })
+                                                            .cursor_pointer()
+                                                            .hover(|s| s.bg(slot_color))
+                                                            .on_click(cx.listener(move |this, _, _window, cx| {
+                                                                this.on_sketch_key_played(pitch, cx);
+                                                            }))
+                                                            .when(pitch % 12 == 0, |el| el.child(pitch_label(pitch)))
+                                                    })),
+                                            )
+                                    }))
+                                    // Drum step sequencer (the ✎ toggle on a Drum Pattern row)
+                                    .children(sketch_slot_open.filter(|slot| *slot == ReferenceSlot::DrumPattern).map(|slot| {
+                                        let pattern = &drum_step_pattern;
+                                        let slot_color = colors.slot_color(slot);
+                                        div()
+                                            .id("drum-step-panel")
+                                            .flex()
+                                            .flex_col()
+                                            .gap_2()
+                                            .px_3()
+                                            .py(px(8.0))
+                                            .rounded(radius.control)
+                                            .border_1()
+                                            .border_color(colors.panel_active_border)
+                                            .bg(colors.panel_background)
+                                            .child(
+                                                div()
+                                                    .text_size(px(10.0))
+                                                    .text_color(colors.muted_foreground)
+                                                    .font_weight(gpui::FontWeight::BOLD)
+                                                    .child("STEP SEQUENCER — DRUM PATTERN"),
+                                            )
+                                            .child(
+                                                div()
+                                                    .text_size(px(11.0))
+                                                    .text_color(colors.muted_foreground)
+                                                    .child("Click a step for a hit, again for an accent, and once more to clear it."),
+                                            )
+                                            .child(
+                                                div()
+                                                    .flex()
+                                                    .flex_wrap()
+                                                    .items_center()
+                                                    .gap_2()
+                                                    .child(
+                                                        Button::new("drum-step-count")
+                                                            .label(format!("{} Steps", pattern.steps()))
+                                                            .on_click(cx.listener(|this, _, _window, cx| {
+                                                                this.on_drum_step_count_cycled(cx);
+                                                            })),
+                                                    )
+                                                    .child(
+                                                        Button::new("drum-step-clear")
+                                                            .label("Clear")
+                                                            .on_click(cx.listener(|this, _, _window, cx| {
+                                                                this.on_drum_pattern_cleared(cx);
+                                                            })),
+                                                    )
+                                                    .child(
+                                                        Button::new("drum-step-export")
+                                                            .label("Show .MID")
+                                                            .disabled(pattern.is_empty())
+                                                            .tooltip("Write the pattern as a .mid file to drag into the DAW")
+                                                            .on_click(cx.listener(|this, _, _window, cx| {
+                                                                this.on_drum_pattern_exported(cx);
+                                                            })),
+                                                    )
+                                                    .child({
+                                                        let button = Button::new("drum-step-write")
+                                                            .label("Write to Drum Pattern")
+                                                            .on_click(cx.listener(|this, _, _window, cx| {
+                                                                this.on_drum_pattern_written(cx);
+                                                            }));
+                                                        if pattern.is_empty() { button } else { button.primary() }
+                                                    })
+                                                    .child(
+                                                        div()
+                                                            .text_size(px(11.0))
+                                                            .text_color(colors.muted_foreground)
+                                                            .child(match pattern.hit_count() {
+                                                                1 => "1 hit".to_string(),
+                                                                hits => format!("{hits} hits"),
+                                                            }),
+                                                    ),
+                                            )
+                                            .child(
+                                                div()
+                                                    .id("drum-step-grid")
+                                                    .flex()
+                                                    .flex_col()
+                                                    .gap(px(2.0))
+                                                    .overflow_x_scroll()
+                                                    .children(DRUM_STEP_LANES.iter().map(|lane| {
+                                                        let key = lane.key;
+                                                        div()
+                                                            .flex()
+                                                            .items_center()
+                                                            .gap(px(2.0))
+                                                            .child(
+                                                                div()
+                                                                    .w(px(72.0))
+                                                                    .flex_none()
+                                                                    .text_size(px(10.0))
+                                                                    .text_color(colors.muted_foreground)
+                                                                    .child(lane.name),
+                                                            )
+                                                            .children((0..pattern.steps()).map(|step| {
+                                                                let hit = pattern.hit(step, key);
+                                                                div()
+                                                                    .id(("drum-step", usize::from(step) * 128 + usize::from(key)))
+                                                                    .w(px(14.0))
+                                                                    .h(px(14.0))
+                                                                    .flex_none()
+                                                                    .rounded(px(2.0))
+                                                                    .border_1()
+                                                                    .border_color(if step % 4 == 0 {
+                                                                        colors.panel_active_border
+                                                                    } else {
+                                                                        colors.panel_border
+                                                                    })
+                                                                    .bg(match hit {
+                                                                        Some(DrumHit::Accent) => slot_color,
+                                                                        Some(DrumHit::Normal) => slot_color.opacity(0.5),
+                                                                        None if (step / 4) % 2 == 0 => colors.input_background,
+                                                                        None => colors.surface_background,
+                                                                    })
+                                                                    .cursor_pointer()
+                                                                    .hover(|s| s.border_color(slot_color))
+                                                                    .on_click(cx.listener(move |this, _, _window, cx| {
+                                                                        this.on_drum_step_cycled(step, key, cx);
+                                                                    }))
+                                                            }))
+                                                    })),
+                                            )
+                                    }))
+                                    .children(self.input_track_error.iter().map(|message| {
+                                        div()
+                                            .text_color(colors.error_foreground)
+                                            .text_size(px(11.0))
+                                            .child(format!("Input Tracks: {message}"))
+                                    }))
+                                    // Undo toast for the last removed track row
+                                    .children(self.track_undo.as_ref().map(|undo| {
+                                        div()
+                                            .id("track-undo-toast")
+                                            .flex()
+                                            .items_center()
+                                            .justify_between()
+                                            .gap_2()
+                                            .px_3()
+                                            .py(px(6.0))
+                                            .rounded(radius.control)
+                                            .border_1()
+                                            .border_color(colors.panel_active_border)
+                                            .bg(colors.panel_active_background)
+                                            .child(
+                                                div()
+                                                    .text_size(px(11.0))
+                                                    .text_color(colors.surface_foreground)
+                                                    .child(undo.message()),
+                                            )
+                                            .child(
+                                                Button::new("track-undo-button")
+                                                    .label("Undo")
+                                                    .primary()
+                                                    .on_click(cx.listener(|this, _, _window, cx| {
+                                                        this.on_undo_track_removal_clicked(cx)
+                                                    })),
+                                            )
+                                    }))
+                                    .children(self.reference_reload_notice.iter().map(|notice| {
+                                        div()
+                                            .text_color(colors.muted_foreground)
+                                            .text_size(px(10.0))
+                                            .child(notice.clone())
+                                    }))
+                                    .children(
+                                        queue_overflow_summary(
+                                            self.live_midi_capture.source_overflow_metrics(),
+                                            self.live_midi_capture.dropped_event_count(),
+                                        )
+                                        .map(|summary| {
+                                            div()
+                                                .id("queue-overflow-diagnostics")
+                                                .text_color(colors.muted_foreground)
+                                                .text_size(px(10.0))
+                                                .child(summary)
+                                        }),
+                                    )
+                            }
+                            )
+                            .child({
+                                let has_candidates = !self.generation_candidates.is_empty();
+                                let candidate_order = self.candidate_display_order();
+                                div()
+                                    .id("generated-patterns-section")
+                                    .flex()
+                                    .flex_col()
+                                    .gap_2()
+                                    .pt(spacing.panel_padding)
+                                    .border_t_1()
+                                    .border_color(colors.panel_border)
+                                    .child(Self::section_label("Generated Patterns", colors))
+                                    .children(self.comparison_failures.iter().map(|failure| {
+                                        div()
+                                            .text_size(px(11.0))
+                                            .text_color(colors.warning_foreground)
+                                            .child(format!("Comparison failed for {failure}"))
+                                    }))
+                                    .when(locked_note_count > 0, |el| {
+                                        el.child(
+                                            div()
+                                                .flex()
+                                                .items_center()
+                                                .justify_between()
+                                                .text_size(px(11.0))
+                                                .text_color(colors.warning_foreground)
+                                                .child(format!(
+                                                    "{locked_note_count} locked note(s) kept on regenerate"
+                                                ))
+                                                .child(
+                                                    Button::new("clear-locked-notes")
+                                                        .label("Clear Locks")
+                                                        .on_click(cx.listener(|this, _, _window, cx| {
+                                                            this.on_clear_locked_notes_clicked(cx)
+                                                        })),
+                                                ),
+                                        )
+                                    })
+                                    .when(!has_candidates, |el| {
                                         el.child(
                                             div()
-                                                .id("channel-select-menu")
+                                                .flex()
+                                                .items_center()
+                                                .justify_center()
+                                                .h(px(64.0))
                                                 .rounded(radius.control)
                                                 .border_1()
-                                                .border_color(colors.panel_active_border)
-                                                .bg(colors.panel_background)
-                                                .overflow_hidden()
+                                                .border_color(colors.panel_border)
+                                                .bg(colors.input_background)
                                                 .child(
                                                     div()
-                                                        .px_3()
-                                                        .py(px(6.0))
-                                                        .border_b_1()
-                                                        .border_color(colors.panel_border)
-                                                        .text_size(px(10.0))
+                                                        .text_size(px(11.0))
                                                         .text_color(colors.muted_foreground)
-                                                        .font_weight(gpui::FontWeight::BOLD)
-                                                        .child("SELECT MIDI CHANNEL"),
-                                                )
-                                                .children((MIDI_CHANNEL_MIN..=MIDI_CHANNEL_MAX).map(|ch| {
-                                                    let is_selected = ch == current_ch;
+                                                        .child("No patterns generated yet"),
+                                                ),
+                                        )
+                                    })
+                                    .when(has_candidates, |el| {
+                                        el.child(
+                                            div()
+                                                .flex()
+                                                .items_center()
+                                                .gap_1()
+                                                .child(
                                                     div()
-                                                        .id(("ch-option", ch as usize))
-                                                        .flex()
-                                                        .items_center()
-                                                        .justify_between()
-                                                        .h(px(28.0))
-                                                        .px_3()
-                                                        .bg(if is_selected { colors.panel_active_background } else { colors.panel_background })
-                                                        .cursor_pointer()
-                                                        .hover(|s| s.bg(colors.panel_active_background))
-                                                        .on_click(cx.listener(move |this, _, _window, cx| {
-                                                            this.on_channel_selected(menu_slot, ch, cx);
-                                                        }))
-                                                        .child(
-                                                            div()
-                                                                .text_size(px(11.0))
-                                                                .text_color(if is_selected { colors.surface_foreground } else { colors.muted_foreground })
-                                                                .font_weight(if is_selected { gpui::FontWeight::BOLD } else { gpui::FontWeight::NORMAL })
-                                                                .child(format!("Channel {ch}")),
-                                                        )
-                                                        .when(is_selected, |el| {
-                                                            el.child(
+                                                        .text_size(px(11.0))
+                                                        .text_color(colors.muted_foreground)
+                                                        .child("Sort:"),
+                                                )
+                                                .children(CandidateMetric::ALL.into_iter().enumerate().map(
+                                                    |(index, metric)| {
+                                                        let direction = match self.candidate_sort {
+                                                            Some((active, true)) if active == metric => " ↓",
+                                                            Some((active, false)) if active == metric => " ↑",
+                                                            _ => "",
+                                                        };
+                                                        let button = Button::new(("candidate-sort", index))
+                                                            .label(format!("{}{direction}", metric.label()))
+                                                            .on_click(cx.listener(move |this, _, _window, cx| {
+                                                                this.on_candidate_sort_clicked(metric, cx)
+                                                            }));
+                                                        if direction.is_empty() {
+                                                            button
+                                                        } else {
+                                                            button.primary()
+                                                        }
+                                                    },
+                                                ))
+                                                .child({
+                                                    let button = Button::new("candidate-top-half")
+                                                        .label("Top Half")
+                                                        .disabled(self.candidate_sort.is_none())
+                                                        .on_click(cx.listener(|this, _, _window, cx| {
+                                                            this.on_candidate_top_half_toggled(cx)
+                                                        }));
+                                                    if self.candidate_top_half_only {
+                                                        button.primary()
+                                                    } else {
+                                                        button
+                                                    }
+                                                }),
+                                        )
+                                    })
+                                    .when(has_candidates, |el| {
+                                        el.child(
+                                            div()
+                                                .id("candidate-list")
+                                                .track_focus(&self.candidate_list_focus)
+                                                .on_key_down(cx.listener(|this, event: &KeyDownEvent, window, cx| {
+                                                    this.on_candidate_list_key_down(event, window, cx)
+                                                }))
+                                                .h(px(128.0))
+                                                .vertical_scrollbar(&self.candidate_list_scroll_handle)
+                                                .rounded(radius.control)
+                                                .border_1()
+                                                .border_color(colors.panel_border)
+                                                .focus(|style| style.border_color(colors.primary))
+                                                .bg(colors.input_background)
+                                                .child(
+                                                    uniform_list(
+                                                        "candidate-rows",
+                                                        candidate_order.len(),
+                                                        cx.processor(move |this, range: Range<usize>, _window, cx| {
+                                                            let colors = cx.read_global(|theme: &SonantTheme, _| theme.colors);
+                                                            candidate_order[range]
+                                                                .iter()
+                                                                .map(|index| this.candidate_row(*index, colors, cx))
+                                                                .collect::<Vec<_>>()
+                                                        }),
+                                                    )
+                                                    .track_scroll(self.candidate_list_scroll_handle.clone())
+                                                    .size_full(),
+                                                ),
+                                        )
+                                        .when(self.selected_candidate_index.is_some(), |el| {
+                                            el.child(
+                                                div()
+                                                    .flex()
+                                                    .items_center()
+                                                    .gap_2()
+                                                    .child(
+                                                        div()
+                                                            .flex_1()
+                                                            .child(Input::new(&self.candidate_annotation_input)),
+                                                    )
+                                                    .child(
+                                                        Button::new("save-candidate-annotation")
+                                                            .label("Save Note")
+                                                            .on_click(cx.listener(|this, _, _window, cx| {
+                                                                this.on_save_candidate_annotation_clicked(cx)
+                                                            })),
+                                                    )
+                                                    .child(
+                                                        Button::new("explain-candidate")
+                                                            .label("Explain")
+                                                            .loading(
+                                                                matches!(
+                                                                    self.selected_candidate_explanation(),
+                                                                    Some(CandidateExplanation::Pending)
+                                                                ) && !theme.reduced_motion,
+                                                            )
+                                                            .on_click(cx.listener(|this, _, _window, cx| {
+                                                                this.on_explain_candidate_clicked(cx)
+                                                            })),
+                                                    ),
+                                            )
+                                            .children(self.selected_candidate_explanation().and_then(
+                                                |explanation| match explanation {
+                                                    CandidateExplanation::Pending => None,
+                                                    CandidateExplanation::Ready(text) => Some(
+                                                        div()
+                                                            .p_2()
+                                                            .rounded(radius.control)
+                                                            .border_1()
+                                                            .border_color(colors.panel_border)
+                                                            .bg(colors.input_background)
+                                                            .text_size(px(11.0))
+                                                            .text_color(colors.surface_foreground)
+                                                            .child(text.clone()),
+                                                    ),
+                                                    CandidateExplanation::Failed(message) => Some(
+                                                        div()
+                                                            .text_size(px(11.0))
+                                                            .text_color(colors.error_foreground)
+                                                            .child(format!("Explain failed: {message}")),
+                                                    ),
+                                                },
+                                            ))
+                                            .child(
+                                                div()
+                                                    .flex()
+                                                    .items_center()
+                                                    .gap_1()
+                                                    .child(
+                                                        div()
+                                                            .text_size(px(11.0))
+                                                            .text_color(colors.muted_foreground)
+                                                            .child("Velocity:"),
+                                                    )
+                                                    .children(VelocityProfile::ALL.into_iter().enumerate().map(
+                                                        |(index, profile)| {
+                                                            Button::new(("candidate-velocity-profile", index))
+                                                                .label(profile.label())
+                                                                .on_click(cx.listener(move |this, _, _window, cx| {
+                                                                    this.on_candidate_velocity_profile_clicked(profile, cx)
+                                                                }))
+                                                        },
+                                                    )),
+                                            )
+                                        })
+                                    })
+                                    .children(self.selected_candidate_repair().map(|report| {
+                                        let bypassed = self
+                                            .selected_candidate_index
+                                            .and_then(|index| self.generation_candidates.get(index))
+                                            .is_some_and(GenerationCandidate::is_processing_bypassed);
+                                        div()
+                                            .text_color(colors.muted_foreground)
+                                            .text_size(px(11.0))
+                                            .child(if bypassed {
+                                                format!("Repaired: {} (bypassed, using the raw notes)", report.summary())
+                                            } else {
+                                                format!("Repaired: {}", report.summary())
+                                            })
+                                    }))
+                                    .children(self.selected_candidate_validation_warning().map(|warning| {
+                                        div()
+                                            .text_color(colors.warning_foreground)
+                                            .text_size(px(11.0))
+                                            .child(format!("Corrected: {}", warning.message))
+                                    }))
+                                    .children(self.rejected_candidates_summary().map(|summary| {
+                                        div()
+                                            .text_color(colors.warning_foreground)
+                                            .text_size(px(11.0))
+                                            .child(summary)
+                                    }))
+                                    .children(self.candidate_annotation_error.iter().map(|message| {
+                                        div()
+                                            .text_color(colors.error_foreground)
+                                            .text_size(px(11.0))
+                                            .child(format!("Candidate note: {message}"))
+                                    }))
+                                    .child(
+                                        div().flex().justify_end().child(
+                                            Button::new("export-stems")
+                                                .label("Export Stems")
+                                                .on_click(cx.listener(|this, _, window, cx| {
+                                                    this.on_export_stems_clicked(window, cx)
+                                                })),
+                                        ),
+                                    )
+                                    .children(self.stem_export_error.iter().map(|message| {
+                                        div()
+                                            .text_color(colors.error_foreground)
+                                            .text_size(px(11.0))
+                                            .child(format!("Stems: {message}"))
+                                    }))
+                                                                    .children(self.auto_save_error.iter().map(|message| {
+                                        div()
+                                            .text_color(colors.error_foreground)
+                                            .text_size(px(11.0))
+                                            .child(format!("Auto-save: {message}"))
+                                    }))
+                                    .children(self.apply_error.iter().map(|message| {
+                                        div()
+                                            .text_color(colors.error_foreground)
+                                            .text_size(px(11.0))
+                                            .child(format!("Apply: {message}"))
+                                    }))
+                            })
+                            // Not virtualized: history shows a count, not its entries. A
+                            // future entry list should go through `uniform_list` like the
+                            // candidate rows. The helper has no reference event inspector.
+                            .child(
+                                div()
+                                    .id("history-section")
+                                    .flex()
+                                    .flex_col()
+                                    .gap_2()
+                                    .pt(spacing.panel_padding)
+                                    .border_t_1()
+                                    .border_color(colors.panel_border)
+                                    .child(Self::section_label("History", colors))
+                                    .child(
+                                        div()
+                                            .flex()
+                                            .items_center()
+                                            .justify_between()
+                                            .child(
+                                                div()
+                                                    .text_size(px(11.0))
+                                                    .text_color(colors.muted_foreground)
+                                                    .child(format!(
+                                                        "{} generation(s) recorded",
+                                                        self.generation_history.entries().len()
+                                                    )),
+                                            )
+                                            .child(
+                                                div()
+                                                    .flex()
+                                                    .gap_2()
+                                                    .child(
+                                                        Button::new("export-history-json")
+                                                            .label("Export JSON")
+                                                            .on_click(cx.listener(|this, _, window, cx| {
+                                                                this.on_export_history_clicked(
+                                                                    GenerationHistoryExportFormat::Json,
+                                                                    window,
+                                                                    cx,
+                                                                )
+                                                            })),
+                                                    )
+                                                    .child(
+                                                        Button::new("export-history-csv")
+                                                            .label("Export CSV")
+                                                            .on_click(cx.listener(|this, _, window, cx| {
+                                                                this.on_export_history_clicked(
+                                                                    GenerationHistoryExportFormat::Csv,
+                                                                    window,
+                                                                    cx,
+                                                                )
+                                                            })),
+                                                    ),
+                                            ),
+                                    )
+                                    .children(self.history_export_error.iter().map(|message| {
+                                        div()
+                                            .text_color(colors.error_foreground)
+                                            .text_size(px(11.0))
+                                            .child(format!("History: {message}"))
+                                    })),
+                            )
+                            .child(
+                                div()
+                                    .id("parameter-sliders-section")
+                                    .flex()
+                                    .flex_col()
+                                    .gap_2()
+                                    .pt(spacing.panel_padding)
+                                    .border_t_1()
+                                    .border_color(colors.panel_border)
+                                    .child(Self::section_label("Parameter Sliders", colors))
+                                    .child(
+                                        div()
+                                            .flex()
+                                            .flex_col()
+                                            .gap_3()
+                                            .when(param_spec.complexity, |sliders| {
+                                                sliders.child(Self::parameter_slider_control(
+                                                    "param-slider-complexity",
+                                                    "Complexity",
+                                                    complexity_percent,
+                                                    "Simple",
+                                                    "Chaotic",
+                                                    &self.complexity_slider,
+                                                    &self.complexity_slider_focus,
+                                                    cx.listener(|this, event: &KeyDownEvent, window, cx| {
+                                                        this.on_param_slider_key_down(ParamSlider::Complexity, event, window, cx)
+                                                    }),
+                                                    colors,
+                                                ))
+                                            })
+                                            .when(param_spec.density, |sliders| {
+                                                sliders.child(Self::parameter_slider_control(
+                                                    "param-slider-density",
+                                                    "Note Density",
+                                                    density_percent,
+                                                    "Sparse",
+                                                    "Busy",
+                                                    &self.density_slider,
+                                                    &self.density_slider_focus,
+                                                    cx.listener(|this, event: &KeyDownEvent, window, cx| {
+                                                        this.on_param_slider_key_down(ParamSlider::Density, event, window, cx)
+                                                    }),
+                                                    colors,
+                                                ))
+                                            })
+                                            .when(param_spec.syncopation, |sliders| {
+                                                sliders.child(Self::parameter_slider_control(
+                                                    "param-slider-syncopation",
+                                                    "Syncopation",
+                                                    syncopation_percent,
+                                                    "Straight",
+                                                    "Off-beat",
+                                                    &self.syncopation_slider,
+                                                    &self.syncopation_slider_focus,
+                                                    cx.listener(|this, event: &KeyDownEvent, window, cx| {
+                                                        this.on_param_slider_key_down(ParamSlider::Syncopation, event, window, cx)
+                                                    }),
+                                                    colors,
+                                                ))
+                                            }),
+                                    ),
+                            )
+                            .child({
+                                let advanced_open = self.advanced_sampling_open;
+                                let sampling = self.submission_model.sampling();
+                                let sampling_ranges = self.submission_model.sampling_ranges();
+                                div()
+                                    .id("advanced-sampling-section")
+                                    .flex()
+                                    .flex_col()
+                                    .gap_2()
+                                    .pt(spacing.panel_padding)
+                                    .border_t_1()
+                                    .border_color(colors.panel_border)
+                                    .child(
+                                        div()
+                                            .id("advanced-sampling-header")
+                                            .flex()
+                                            .items_center()
+                                            .justify_between()
+                                            .cursor_pointer()
+                                            .on_click(cx.listener(|this, _, _window, cx| {
+                                                this.on_advanced_sampling_toggled(cx)
+                                            }))
+                                            .child(Self::section_label("Advanced", colors))
+                                            .child(
+                                                div()
+                                                    .text_size(px(12.0))
+                                                    .text_color(colors.muted_foreground)
+                                                    .child(if advanced_open { "▾" } else { "▸" }),
+                                            ),
+                                    )
+                                    .when(advanced_open, |el| {
+                                        el.children(SamplingParam::ALL.into_iter().enumerate().map(
+                                            |(index, param)| {
+                                                let range_label = match param {
+                                                    SamplingParam::Temperature => {
+                                                        format!("0.0–{:.1}", sampling_ranges.temperature_max)
+                                                    }
+                                                    SamplingParam::TopP => "0.0–1.0".to_string(),
+                                                    SamplingParam::MaxTokens => {
+                                                        format!("1–{}", sampling_ranges.max_tokens_max)
+                                                    }
+                                                };
+                                                div()
+                                                    .id(("sampling-param-row", index))
+                                                    .flex()
+                                                    .items_center()
+                                                    .justify_between()
+                                                    .gap_2()
+                                                    .child(
+                                                        div()
+                                                            .flex()
+                                                            .flex_col()
+                                                            .child(div().text_size(px(12.0)).child(param.label()))
+                                                            .child(
                                                                 div()
                                                                     .text_size(px(10.0))
-                                                                    .text_color(colors.primary)
-                                                                    .child("✓"),
+                                                                    .text_color(colors.muted_foreground)
+                                                                    .child(range_label),
+                                                            ),
+                                                    )
+                                                    .child(
+                                                        div()
+                                                            .flex()
+                                                            .items_center()
+                                                            .gap_1()
+                                                            .child(
+                                                                Button::new(("sampling-param-decrement", index))
+                                                                    .label("-")
+                                                                    .on_click(cx.listener(move |this, _, _window, cx| {
+                                                                        this.on_sampling_param_stepped(param, -1, cx)
+                                                                    })),
                                                             )
-                                                        })
-                                                })),
-                                        )
-                                    })
-                                    // Slot type selection menu (shown when a type badge is clicked)
-                                    .when(slot_type_menu_open.is_some(), |el| {
-                                        let open_row = slot_type_menu_open.unwrap_or(0);
-                                        let current_slot = visible_slot_rows.get(open_row).copied().unwrap_or(ReferenceSlot::Melody);
-                                        el.child(
-                                            div()
-                                                .id("slot-type-select-menu")
-                                                .rounded(radius.control)
-                                                .border_1()
-                                                .border_color(colors.panel_active_border)
-                                                .bg(colors.panel_background)
-                                                .overflow_hidden()
-                                                .child(
-                                                    div()
-                                                        .px_3()
-                                                        .py(px(6.0))
-                                                        .border_b_1()
-                                                        .border_color(colors.panel_border)
-                                                        .text_size(px(10.0))
-                                                        .text_color(colors.muted_foreground)
-                                                        .font_weight(gpui::FontWeight::BOLD)
-                                                        .child("SELECT REFERENCE TYPE"),
-                                                )
-                                                .children(Self::reference_slots().iter().copied().map(|slot_opt| {
-                                                    let is_selected = slot_opt == current_slot;
-                                                    let slot_color = colors.slot_color(slot_opt);
-                                                    div()
-                                                        .id(("slot-type-option", slot_opt as usize))
-                                                        .flex()
-                                                        .items_center()
-                                                        .justify_between()
-                                                        .h(px(28.0))
-                                                        .px_3()
-                                                        .bg(if is_selected { colors.panel_active_background } else { colors.panel_background })
-                                                        .cursor_pointer()
-                                                        .hover(|s| s.bg(colors.panel_active_background))
-                                                        .on_click(cx.listener(move |this, _, _window, cx| {
-                                                            this.on_slot_type_selected(open_row, slot_opt, cx);
-                                                        }))
-                                                        .child(
-                                                            div()
-                                                                .flex()
-                                                                .items_center()
-                                                                .gap_2()
-                                                                .child(
-                                                                    div()
-                                                                        .w(px(4.0))
-                                                                        .h(px(14.0))
-                                                                        .rounded(px(2.0))
-                                                                        .bg(slot_color),
-                                                                )
-                                                                .child(
-                                                                    div()
-                                                                        .text_size(px(11.0))
-                                                                        .text_color(if is_selected { colors.surface_foreground } else { colors.muted_foreground })
-                                                                        .font_weight(if is_selected { gpui::FontWeight::BOLD } else { gpui::FontWeight::NORMAL })
-                                                                        .child(Self::reference_slot_label(slot_opt)),
-                                                                ),
-                                                        )
-                                                        .when(is_selected, |el| {
-                                                            el.child(
+                                                            .child(
                                                                 div()
-                                                                    .text_size(px(10.0))
-                                                                    .text_color(colors.primary)
-                                                                    .child("✓"),
+                                                                    .w(px(44.0))
+                                                                    .flex()
+                                                                    .justify_center()
+                                                                    .text_size(px(12.0))
+                                                                    .font_weight(gpui::FontWeight::BOLD)
+                                                                    .text_color(colors.accent_foreground)
+                                                                    .child(sampling.display_value(param)),
                                                             )
-                                                        })
-                                                })),
-                                        )
+                                                            .child(
+                                                                Button::new(("sampling-param-increment", index))
+                                                                    .label("+")
+                                                                    .on_click(cx.listener(move |this, _, _window, cx| {
+                                                                        this.on_sampling_param_stepped(param, 1, cx)
+                                                                    })),
+                                                            )
+                                                            .child(
+                                                                Button::new(("sampling-param-reset", index))
+                                                                    .label("Reset")
+                                                                    .on_click(cx.listener(move |this, _, _window, cx| {
+                                                                        this.on_sampling_param_reset(param, cx)
+                                                                    })),
+                                                            ),
+                                                    )
+                                            },
+                                        ))
                                     })
-                                    .children(self.input_track_error.iter().map(|message| {
-                                        div()
-                                            .text_color(colors.error_foreground)
-                                            .text_size(px(11.0))
-                                            .child(format!("Input Tracks: {message}"))
-                                    }))
-                            }
-                            )
+                            })
                             .child({
-                                let has_candidates = !self.generation_candidates.is_empty();
+                                let arrangement_busy = self.arrangement_request_id.is_some();
+                                let arrangement_progress = self.arrangement_run.as_ref().map(|run| {
+                                    (run.completed_count(), run.sections().len(), run.is_complete())
+                                });
+                                let arrangement_complete =
+                                    matches!(arrangement_progress, Some((_, _, true)));
                                 div()
-                                    .id("generated-patterns-section")
+                                    .id("arrangement-section")
                                     .flex()
                                     .flex_col()
                                     .gap_2()
                                     .pt(spacing.panel_padding)
                                     .border_t_1()
                                     .border_color(colors.panel_border)
-                                    .child(Self::section_label("Generated Patterns", colors))
-                                    .when(!has_candidates, |el| {
-                                        el.child(
+                                    .child(Self::section_label("Arrangement", colors))
+                                    .children(self.arrangement_sections.iter().enumerate().map(
+                                        |(index, section)| {
+                                            let is_done = arrangement_progress
+                                                .is_some_and(|(completed, _, _)| index < completed);
                                             div()
+                                                .id(("arrangement-section-row", index))
                                                 .flex()
                                                 .items_center()
-                                                .justify_center()
-                                                .h(px(64.0))
+                                                .justify_between()
+                                                .gap_2()
+                                                .px(px(8.0))
+                                                .py(px(4.0))
                                                 .rounded(radius.control)
                                                 .border_1()
-                                                .border_color(colors.panel_border)
+                                                .border_color(if is_done {
+                                                    colors.success_foreground
+                                                } else {
+                                                    colors.panel_border
+                                                })
                                                 .bg(colors.input_background)
                                                 .child(
                                                     div()
-                                                        .text_size(px(11.0))
-                                                        .text_color(colors.muted_foreground)
-                                                        .child("No patterns generated yet"),
-                                                ),
-                                        )
-                                    })
-                                    .when(has_candidates, |el| {
-                                        el.child(
-                                            div()
-                                                .id("candidate-list")
-                                                .h(px(128.0))
-                                                .overflow_y_scrollbar()
-                                                .rounded(radius.control)
-                                                .border_1()
-                                                .border_color(colors.panel_border)
-                                                .bg(colors.input_background)
-                                                .children(
-                                                    self.generation_candidates
-                                                        .iter()
-                                                        .enumerate()
-                                                        .map(|(index, _candidate)| {
-                                                            let is_selected =
-                                                                self.selected_candidate_index == Some(index);
-                                                            let is_visible =
-                                                                !self.hidden_candidates.contains(&index);
-                                                            let display_name =
-                                                                Self::candidate_display_name(index);
-                                                            let status_label =
-                                                                Self::candidate_status_label(index);
-
+                                                        .flex()
+                                                        .flex_col()
+                                                        .overflow_hidden()
+                                                        .child(
                                                             div()
-                                                                .id(("candidate-row", index))
-                                                                .flex()
-                                                                .items_center()
-                                                                .h(px(32.0))
-                                                                .bg(if is_selected {
-                                                                    colors.success_foreground.opacity(0.08)
-                                                                } else {
-                                                                    colors.panel_background
-                                                                })
-                                                                .hover(|s| s.bg(colors.input_background))
-                                                                .cursor_pointer()
-                                                                .on_click(cx.listener(move |this, _, _window, cx| {
-                                                                    this.on_candidate_selected(index, cx);
-                                                                }))
-                                                                // Green left border (active only)
-                                                                .child(
-                                                                    div()
-                                                                        .w(px(3.0))
-                                                                        .h_full()
-                                                                        .flex_none()
-                                                                        .bg(if is_selected {
-                                                                            colors.success_foreground
-                                                                        } else {
-                                                                            gpui::transparent_black()
-                                                                        }),
-                                                                )
-                                                                // Drag handle
-                                                                .child(
-                                                                    div()
-                                                                        .w(px(18.0))
-                                                                        .flex()
-                                                                        .items_center()
-                                                                        .justify_center()
-                                                                        .flex_none()
-                                                                        .text_size(px(12.0))
-                                                                        .text_color(colors.muted_foreground)
-                                                                        .child("⠿"),
-                                                                )
-                                                                // Radio indicator
-                                                                .child(
-                                                                    div()
-                                                                        .w(px(16.0))
-                                                                        .flex()
-                                                                        .items_center()
-                                                                        .justify_center()
-                                                                        .flex_none()
-                                                                        .text_size(px(12.0))
-                                                                        .text_color(if is_selected {
-                                                                            colors.success_foreground
-                                                                        } else {
-                                                                            colors.muted_foreground
-                                                                        })
-                                                                        .child(if is_selected { "◉" } else { "◌" }),
-                                                                )
-                                                                // Pattern name + status label
-                                                                .child(
-                                                                    div()
-                                                                        .flex_1()
-                                                                        .flex()
-                                                                        .items_center()
-                                                                        .gap_2()
-                                                                        .min_w(px(0.0))
-                                                                        .child(
-                                                                            div()
-                                                                                .text_size(px(11.0))
-                                                                                .text_color(if is_selected {
-                                                                                    colors.surface_foreground
-                                                                                } else {
-                                                                                    colors.muted_foreground
-                                                                                })
-                                                                                .font_weight(if is_selected {
-                                                                                    gpui::FontWeight::BOLD
-                                                                                } else {
-                                                                                    gpui::FontWeight::NORMAL
-                                                                                })
-                                                                                .overflow_hidden()
-                                                                                .child(display_name),
-                                                                        )
-                                                                        .when(!status_label.is_empty(), |el| {
-                                                                            el.child(
-                                                                                div()
-                                                                                    .flex_none()
-                                                                                    .px(px(4.0))
-                                                                                    .py(px(1.0))
-                                                                                    .rounded(px(3.0))
-                                                                                    .text_size(px(9.0))
-                                                                                    .text_color(if is_selected {
-                                                                                        colors.success_foreground
-                                                                                    } else {
-                                                                                        colors.muted_foreground
-                                                                                    })
-                                                                                    .font_weight(gpui::FontWeight::BOLD)
-                                                                                    .border_1()
-                                                                                    .border_color(if is_selected {
-                                                                                        colors.success_foreground
-                                                                                    } else {
-                                                                                        colors.panel_border
-                                                                                    })
-                                                                                    .child(status_label),
-                                                                            )
-                                                                        }),
-                                                                )
-                                                                // Action buttons
-                                                                .child(
-                                                                    div()
-                                                                        .flex()
-                                                                        .items_center()
-                                                                        .gap_1()
-                                                                        .pr_2()
-                                                                        .pl_2()
-                                                                        .h(px(24.0))
-                                                                        .border_l_1()
-                                                                        .border_color(colors.panel_border)
-                                                                        // Visibility toggle
-                                                                        .child(
-                                                                            div()
-                                                                                .id(("candidate-visible", index))
-                                                                                .w(px(20.0))
-                                                                                .h(px(20.0))
-                                                                                .flex()
-                                                                                .items_center()
-                                                                                .justify_center()
-                                                                                .rounded(px(999.0))
-                                                                                .text_size(px(11.0))
-                                                                                .text_color(if is_visible {
-                                                                                    colors.surface_foreground
-                                                                                } else {
-                                                                                    colors.panel_border
-                                                                                })
-                                                                                .cursor_pointer()
-                                                                                .hover(|s| s.text_color(colors.surface_foreground))
-                                                                                .on_click(cx.listener(move |this, _, _window, cx| {
-                                                                                    this.on_candidate_visibility_toggled(index, cx);
-                                                                                }))
-                                                                                .child(if is_visible { "◉" } else { "◌" }),
-                                                                        )
-                                                                        // More button
-                                                                        .child(
-                                                                            div()
-                                                                                .id(("candidate-more", index))
-                                                                                .w(px(20.0))
-                                                                                .h(px(20.0))
-                                                                                .flex()
-                                                                                .items_center()
-                                                                                .justify_center()
-                                                                                .rounded(px(999.0))
-                                                                                .text_size(px(14.0))
-                                                                                .text_color(colors.muted_foreground)
-                                                                                .cursor_pointer()
-                                                                                .hover(|s| s.text_color(colors.surface_foreground))
-                                                                                .child("⋮"),
-                                                                        ),
-                                                                )
-                                                        }),
-                                                ),
-                                        )
-                                    })
-                            })
-                            .child(
-                                div()
-                                    .id("parameter-sliders-section")
-                                    .flex()
-                                    .flex_col()
-                                    .gap_2()
-                                    .pt(spacing.panel_padding)
-                                    .border_t_1()
-                                    .border_color(colors.panel_border)
-                                    .child(Self::section_label("Parameter Sliders", colors))
+                                                                .text_size(px(12.0))
+                                                                .font_weight(gpui::FontWeight::BOLD)
+                                                                .child(format!(
+                                                                    "{} · {} bars",
+                                                                    section.name, section.bars
+                                                                )),
+                                                        )
+                                                        .child(
+                                                            div()
+                                                                .text_size(px(10.0))
+                                                                .text_color(colors.muted_foreground)
+                                                                .truncate()
+                                                                .child(section.prompt.clone()),
+                                                        ),
+                                                )
+                                                .child(
+                                                    div()
+                                                        .id(("arrangement-section-remove", index))
+                                                        .text_size(px(12.0))
+                                                        .text_color(colors.muted_foreground)
+                                                        .cursor_pointer()
+                                                        .hover(|s| s.text_color(colors.error_foreground))
+                                                        .on_click(cx.listener(move |this, _, _window, cx| {
+                                                            this.on_remove_arrangement_section_clicked(index, cx)
+                                                        }))
+                                                        .child("✕"),
+                                                )
+                                        },
+                                    ))
+                                    .child(div().flex().flex_wrap().gap_1().children(
+                                        ARRANGEMENT_SECTION_NAMES.iter().enumerate().map(
+                                            |(index, name)| {
+                                                let name = *name;
+                                                let is_selected = self.arrangement_section_name == name;
+                                                div()
+                                                    .id(("arrangement-section-name", index))
+                                                    .px(px(8.0))
+                                                    .h(px(20.0))
+                                                    .flex()
+                                                    .items_center()
+                                                    .rounded(radius.control)
+                                                    .border_1()
+                                                    .border_color(if is_selected {
+                                                        colors.primary
+                                                    } else {
+                                                        colors.panel_border
+                                                    })
+                                                    .text_size(px(10.0))
+                                                    .text_color(if is_selected {
+                                                        colors.primary
+                                                    } else {
+                                                        colors.muted_foreground
+                                                    })
+                                                    .cursor_pointer()
+                                                    .hover(|s| s.bg(colors.panel_active_background))
+                                                    .on_click(cx.listener(move |this, _, _window, cx| {
+                                                        this.on_arrangement_section_name_selected(name, cx)
+                                                    }))
+                                                    .child(name)
+                                            },
+                                        ),
+                                    ))
                                     .child(
                                         div()
                                             .flex()
-                                            .flex_col()
-                                            .gap_3()
-                                            .child(Self::parameter_slider_control(
-                                                "param-slider-complexity",
-                                                "Complexity",
-                                                complexity_percent,
-                                                "Simple",
-                                                "Chaotic",
-                                                &self.complexity_slider,
-                                                colors,
-                                            ))
-                                            .child(Self::parameter_slider_control(
-                                                "param-slider-density",
-                                                "Note Density",
-                                                density_percent,
-                                                "Sparse",
-                                                "Busy",
-                                                &self.density_slider,
-                                                colors,
-                                            )),
-                                    ),
-                            ),
+                                            .items_center()
+                                            .gap_2()
+                                            .child(
+                                                Button::new("arrangement-bars-decrement")
+                                                    .label("-")
+                                                    .on_click(cx.listener(|this, _, _window, cx| {
+                                                        this.on_arrangement_section_bars_stepped(-1, cx)
+                                                    })),
+                                            )
+                                            .child(
+                                                div()
+                                                    .text_size(px(12.0))
+                                                    .child(format!("{} bars", self.arrangement_section_bars)),
+                                            )
+                                            .child(
+                                                Button::new("arrangement-bars-increment")
+                                                    .label("+")
+                                                    .on_click(cx.listener(|this, _, _window, cx| {
+                                                        this.on_arrangement_section_bars_stepped(1, cx)
+                                                    })),
+                                            ),
+                                    )
+                                    .child(Input::new(&self.arrangement_prompt_input))
+                                    .child(
+                                        div()
+                                            .flex()
+                                            .items_center()
+                                            .gap_2()
+                                            .child(
+                                                Button::new("arrangement-add-section")
+                                                    .label("Add Section")
+                                                    .disabled(arrangement_busy)
+                                                    .on_click(cx.listener(|this, _, _window, cx| {
+                                                        this.on_add_arrangement_section_clicked(cx)
+                                                    })),
+                                            )
+                                            .child(
+                                                Button::new("arrangement-generate")
+                                                    .primary()
+                                                    .label("Generate Arrangement")
+                                                    .loading(arrangement_busy && !theme.reduced_motion)
+                                                    .disabled(
+                                                        arrangement_busy
+                                                            || generating
+                                                            || self.arrangement_sections.is_empty(),
+                                                    )
+                                                    .on_click(cx.listener(|this, _, window, cx| {
+                                                        this.on_generate_arrangement_clicked(window, cx)
+                                                    })),
+                                            )
+                                            .child(
+                                                Button::new("arrangement-export")
+                                                    .label("Export MIDI")
+                                                    .disabled(!arrangement_complete)
+                                                    .on_click(cx.listener(|this, _, window, cx| {
+                                                        this.on_export_arrangement_clicked(window, cx)
+                                                    })),
+                                            ),
+                                    )
+                                    .children(arrangement_progress.map(|(completed, total, _)| {
+                                        div()
+                                            .text_size(px(11.0))
+                                            .text_color(colors.muted_foreground)
+                                            .child(format!("{completed}/{total} sections generated"))
+                                    }))
+                                    .children(self.arrangement_error.iter().map(|message| {
+                                        div()
+                                            .text_size(px(11.0))
+                                            .text_color(colors.error_foreground)
+                                            .child(message.clone())
+                                    }))
+                            }),
                     )
                     .child(
                         div()
@@ -3695,8 +9994,66 @@ impl Render for SonantMainWindow {
                                     .px(spacing.panel_compact_padding)
                                     .rounded(radius.panel)
                                     .bg(colors.surface_background)
+                                    .when(param_spec.key, |toolbar| {
+                                        toolbar.child(
+                                            // KEY group
+                                            div()
+                                                .flex()
+                                                .items_center()
+                                                .gap(px(6.0))
+                                                .child(
+                                                    div()
+                                                        .text_size(px(11.0))
+                                                        .text_color(colors.muted_foreground)
+                                                        .font_weight(gpui::FontWeight::BOLD)
+                                                        .child("KEY"),
+                                                )
+                                                .child(
+                                                    div()
+                                                        .w(px(80.0))
+                                                        .h(px(36.0))
+                                                        .child(Select::new(&self.key_dropdown).placeholder("Key")),
+                                                ),
+                                        )
+                                    })
+                                    .when(param_spec.scale, |toolbar| {
+                                        toolbar.child(
+                                            // SCALE group
+                                            div()
+                                                .flex()
+                                                .items_center()
+                                                .gap(px(6.0))
+                                                .child(
+                                                    div()
+                                                        .text_size(px(11.0))
+                                                        .text_color(colors.muted_foreground)
+                                                        .font_weight(gpui::FontWeight::BOLD)
+                                                        .child("SCALE"),
+                                                )
+                                                .child(
+                                                    div()
+                                                        .w(px(168.0))
+                                                        .h(px(36.0))
+                                                        .child(Select::new(&self.scale_dropdown).placeholder("Scale")),
+                                                ),
+                                        )
+                                    })
+                                    .when(param_spec.key, |toolbar| {
+                                        let button = Button::new("params-transpose-references")
+                                            .label("Match Refs")
+                                            .tooltip("Transpose reference notes into the selected key before they are sent to the model")
+                                            .on_click(cx.listener(|this, _, _window, cx| {
+                                                this.on_transpose_references_toggled(cx)
+                                            }));
+                                        toolbar.child(if self.submission_model.transpose_references() {
+                                            button.primary()
+                                        } else {
+                                            button
+                                        })
+                                    })
+                                    .child(div().w(px(1.0)).h(px(24.0)).bg(colors.panel_border))
                                     .child(
-                                        // KEY group
+                                        // BPM group
                                         div()
                                             .flex()
                                             .items_center()
@@ -3706,38 +10063,45 @@ impl Render for SonantMainWindow {
                                                     .text_size(px(11.0))
                                                     .text_color(colors.muted_foreground)
                                                     .font_weight(gpui::FontWeight::BOLD)
-                                                    .child("KEY"),
+                                                    .child("BPM"),
+                                            )
+                                            .child(
+                                                Button::new("bpm-nudge-down")
+                                                    .label("−")
+                                                    .tooltip("Slow down by 1 BPM")
+                                                    .on_click(cx.listener(|this, _, window, cx| {
+                                                        this.on_bpm_nudged(-1, window, cx);
+                                                    })),
                                             )
                                             .child(
                                                 div()
                                                     .w(px(80.0))
                                                     .h(px(36.0))
-                                                    .child(Select::new(&self.key_dropdown).placeholder("Key")),
-                                            ),
-                                    )
-                                    .child(
-                                        // SCALE group
-                                        div()
-                                            .flex()
-                                            .items_center()
-                                            .gap(px(6.0))
+                                                    .child(Input::new(&self.bpm_input)),
+                                            )
                                             .child(
-                                                div()
-                                                    .text_size(px(11.0))
-                                                    .text_color(colors.muted_foreground)
-                                                    .font_weight(gpui::FontWeight::BOLD)
-                                                    .child("SCALE"),
+                                                Button::new("bpm-nudge-up")
+                                                    .label("+")
+                                                    .tooltip("Speed up by 1 BPM")
+                                                    .on_click(cx.listener(|this, _, window, cx| {
+                                                        this.on_bpm_nudged(1, window, cx);
+                                                    })),
                                             )
                                             .child(
-                                                div()
-                                                    .w(px(168.0))
-                                                    .h(px(36.0))
-                                                    .child(Select::new(&self.scale_dropdown).placeholder("Scale")),
+                                                Button::new("bpm-tap-tempo")
+                                                    .label(match self.tap_tempo.tap_count() {
+                                                        0 | 1 => "Tap".to_string(),
+                                                        taps => format!("Tap ({taps})"),
+                                                    })
+                                                    .tooltip("Tap along to set the tempo; a pause of a few seconds starts over")
+                                                    .on_click(cx.listener(|this, _, window, cx| {
+                                                        this.on_tap_tempo_clicked(window, cx);
+                                                    })),
                                             ),
                                     )
                                     .child(div().w(px(1.0)).h(px(24.0)).bg(colors.panel_border))
                                     .child(
-                                        // BPM group
+                                        // PROFILE group
                                         div()
                                             .flex()
                                             .items_center()
@@ -3747,35 +10111,102 @@ impl Render for SonantMainWindow {
                                                     .text_size(px(11.0))
                                                     .text_color(colors.muted_foreground)
                                                     .font_weight(gpui::FontWeight::BOLD)
-                                                    .child("BPM"),
+                                                    .child("PROFILE"),
                                             )
-                                            .child(
-                                                div()
-                                                    .w(px(80.0))
-                                                    .h(px(36.0))
-                                                    .child(Input::new(&self.bpm_input)),
+                                            .children(
+                                                self.sampling_profiles
+                                                    .profiles_for_provider(self.submission_model.provider())
+                                                    .enumerate()
+                                                    .map(|(index, profile)| {
+                                                        let name = profile.name.clone();
+                                                        let button = Button::new(("sampling-profile-chip", index))
+                                                            .label(profile.name.clone())
+                                                            .on_click(cx.listener(move |this, _, _window, cx| {
+                                                                this.on_sampling_profile_selected(&name, cx)
+                                                            }));
+                                                        if self.active_sampling_profile.as_deref()
+                                                            == Some(profile.name.as_str())
+                                                        {
+                                                            button.primary()
+                                                        } else {
+                                                            button
+                                                        }
+                                                    }),
                                             ),
                                     ),
                             )
                             .child(
                                 div()
-                                    .id("piano-roll-panel")
-                                    .flex_none()
-                                    .h(px(PIANO_ROLL_VIEWPORT_HEIGHT))
+                                    .id("piano-roll-toolbar")
                                     .flex()
-                                    .flex_col()
-                                    .bg(colors.surface_background)
-                                    .child(Self::piano_roll_grid(
-                                        colors,
-                                        radius.control,
-                                        &self.piano_roll_vertical_scroll_handle,
-                                        &self.piano_roll_horizontal_scroll_handle,
-                                        self.live_capture_playhead_ppq,
-                                        piano_roll_note_color,
-                                        piano_roll_note_glow_color,
-                                        piano_roll_note_rects,
-                                    )),
+                                    .items_center()
+                                    .justify_end()
+                                    .gap_2()
+                                    .when(piano_roll_detached, |el| {
+                                        el.child(
+                                            div()
+                                                .flex_1()
+                                                .text_size(px(11.0))
+                                                .text_color(colors.muted_foreground)
+                                                .child("The piano roll is open in its own window."),
+                                        )
+                                    })
+                                    .children(
+                                        self.selected_candidate_index
+                                            .and_then(|index| self.generation_candidates.get(index))
+                                            .filter(|candidate| candidate.has_raw_notes())
+                                            .map(|candidate| {
+                                                Button::new("piano-roll-processing-ab")
+                                                    .label(if candidate.is_processing_bypassed() {
+                                                        "B: Raw"
+                                                    } else {
+                                                        "A: Processed"
+                                                    })
+                                                    .tooltip("Switch the selected candidate between its processed notes and the raw model output")
+                                                    .on_click(cx.listener(|this, _, _window, cx| {
+                                                        this.on_processing_bypass_toggled(cx)
+                                                    }))
+                                            }),
+                                    )
+                                    .children(self.piano_roll_detach_error.as_ref().map(|message| {
+                                        div()
+                                            .flex_1()
+                                            .text_size(px(11.0))
+                                            .text_color(colors.error_foreground)
+                                            .child(message.clone())
+                                    }))
+                                    .child(if piano_roll_detached {
+                                        Button::new("piano-roll-reattach")
+                                            .label("Reattach Piano Roll")
+                                            .on_click(cx.listener(|this, _, _window, cx| {
+                                                this.on_reattach_piano_roll_clicked(cx)
+                                            }))
+                                    } else {
+                                        Button::new("piano-roll-detach")
+                                            .label("Detach Piano Roll")
+                                            .on_click(cx.listener(|this, _, window, cx| {
+                                                this.on_detach_piano_roll_clicked(window, cx)
+                                            }))
+                                    }),
                             )
+                            .when(!piano_roll_detached, |el| {
+                                el.child(
+                                    div()
+                                        .id("piano-roll-panel")
+                                        .flex_none()
+                                        .h(px(PIANO_ROLL_VIEWPORT_HEIGHT))
+                                        .flex()
+                                        .flex_col()
+                                        .bg(colors.surface_background)
+                                        .child(self.piano_roll_view(
+                                            view.clone(),
+                                            &self.piano_roll_vertical_scroll_handle,
+                                            &self.piano_roll_horizontal_scroll_handle,
+                                            colors,
+                                            radius.control,
+                                        )),
+                                )
+                            })
                             .child(
                                 div()
                                     .id("main-footer")
@@ -3794,10 +10225,125 @@ impl Render for SonantMainWindow {
                                             .flex_col()
                                             .gap_1()
                                             .child(div().text_color(status_color).child(status_label))
+                                            .children(failure_action.map(|action| {
+                                                div()
+                                                    .text_size(px(11.0))
+                                                    .text_color(colors.muted_foreground)
+                                                    .child(action.hint())
+                                            }))
+                                            .when(generation_failed, |el| {
+                                                let can_retry = self.last_failed_request.is_some();
+                                                let counting_down = failure_action
+                                                    .and_then(|action| action.retry_countdown_secs(now))
+                                                    .is_some();
+                                                el.child(
+                                                    div()
+                                                        .id("generation-recovery-actions")
+                                                        .flex()
+                                                        .items_center()
+                                                        .gap_2()
+                                                        .when(can_retry, |el| {
+                                                            el.child(
+                                                                Button::new("generation-retry-button")
+                                                                    .primary()
+                                                                    .label(failure_action.map_or_else(
+                                                                        || "Retry".to_string(),
+                                                                        |action| action.retry_label(now),
+                                                                    ))
+                                                                    .disabled(counting_down)
+                                                                    .on_click(cx.listener(|this, _, window, cx| {
+                                                                        this.on_retry_failed_generation_clicked(false, window, cx)
+                                                                    })),
+                                                            )
+                                                        })
+                                                        .children(retry_alternate_model.as_ref().map(|model| {
+                                                            Button::new("generation-retry-alternate-button")
+                                                                .label(format!(
+                                                                    "Retry with {}",
+                                                                    provider_display_name(&model.provider)
+                                                                ))
+                                                                .on_click(cx.listener(|this, _, window, cx| {
+                                                                    this.on_retry_failed_generation_clicked(true, window, cx)
+                                                                }))
+                                                        }))
+                                                        .when(
+                                                            failure_action == Some(GenerationFailureAction::EditPrompt),
+                                                            |el| {
+                                                                el.child(
+                                                                    Button::new("generation-edit-prompt-button")
+                                                                        .label("Edit Prompt")
+                                                                        .on_click(cx.listener(|this, _, window, cx| {
+                                                                            this.on_edit_prompt_after_failure_clicked(window, cx)
+                                                                        })),
+                                                                )
+                                                            },
+                                                        )
+                                                        .child(
+                                                            Button::new("generation-open-settings-button")
+                                                                .label("Open Settings")
+                                                                .on_click(cx.listener(|this, _, window, cx| {
+                                                                    this.on_open_settings_clicked(window, cx)
+                                                                })),
+                                                        ),
+                                                )
+                                            })
+                                            .when(!self.deferred_requests.is_empty(), |el| {
+                                                el.child(
+                                                    div()
+                                                        .id("deferred-requests")
+                                                        .flex()
+                                                        .items_center()
+                                                        .gap_2()
+                                                        .text_size(px(11.0))
+                                                        .text_color(colors.warning_foreground)
+                                                        .child(format!(
+                                                            "{} deferred request(s): {}",
+                                                            self.deferred_requests.len(),
+                                                            self.deferred_requests
+                                                                .iter()
+                                                                .map(|deferred| prompt_preview(&deferred.request.prompt, 24))
+                                                                .collect::<Vec<_>>()
+                                                                .join(" · ")
+                                                        ))
+                                                        .child(
+                                                            Button::new("deferred-requests-discard-button")
+                                                                .label("Discard Deferred")
+                                                                .on_click(cx.listener(|this, _, _window, cx| {
+                                                                    this.on_discard_deferred_requests_clicked(cx)
+                                                                })),
+                                                        ),
+                                                )
+                                            })
                                             .children(self.startup_notice.iter().map(|notice| {
                                                 div()
                                                     .text_color(colors.muted_foreground)
                                                     .child(format!("Backend: {notice}"))
+                                            }))
+                                            .children(self.provider_demotions.iter().map(|demotion| {
+                                                div()
+                                                    .text_color(colors.warning_foreground)
+                                                    .child(provider_demotion_notice(demotion, Instant::now()))
+                                            }))
+                                            .children(self.audit_log.as_ref().map(|audit_log| {
+                                                div()
+                                                    .flex()
+                                                    .items_center()
+                                                    .gap_2()
+                                                    .text_color(colors.muted_foreground)
+                                                    .child(format!(
+                                                        "Audit log: {}",
+                                                        audit_log.session_path().display()
+                                                    ))
+                                                    .child(
+                                                        Button::new("audit-log-open-last-exchange-button")
+                                                            .label("Open Last Exchange")
+                                                            .on_click(cx.listener(|this, _, _window, cx| {
+                                                                this.on_open_last_audit_exchange_clicked(cx)
+                                                            })),
+                                                    )
+                                                    .children(self.audit_log_notice.iter().map(|notice| {
+                                                        div().child(notice.clone())
+                                                    }))
                                             })),
                                     )
                                     .child(
@@ -3805,10 +10351,114 @@ impl Render for SonantMainWindow {
                                             .flex()
                                             .items_center()
                                             .gap_2()
+                                            .child(
+                                                Button::new("import-request-button")
+                                                    .label("Import Request")
+                                                    .disabled(generating)
+                                                    .on_click(cx.listener(|this, _, window, cx| {
+                                                        this.on_import_request_clicked(false, window, cx)
+                                                    })),
+                                            )
+                                            .child(
+                                                Button::new("replay-request-button")
+                                                    .label("Replay Request")
+                                                    .disabled(generating)
+                                                    .on_click(cx.listener(|this, _, window, cx| {
+                                                        this.on_import_request_clicked(true, window, cx)
+                                                    })),
+                                            )
+                                            .child(
+                                                Button::new("run-batch-button")
+                                                    .label("Run Batch")
+                                                    .tooltip(
+                                                        "Generate once per prompt in a .txt or .csv file with the current settings",
+                                                    )
+                                                    .disabled(generating || self.batch_request_id.is_some())
+                                                    .on_click(cx.listener(|this, _, window, cx| {
+                                                        this.on_run_batch_clicked(window, cx)
+                                                    })),
+                                            )
+                                            .children(self.batch_run.as_ref().map(|run| {
+                                                let failed = run.failures().len();
+                                                let mut label =
+                                                    format!("Batch {}/{}", run.completed_count(), run.len());
+                                                if failed > 0 {
+                                                    label.push_str(&format!(" · {failed} failed"));
+                                                }
+                                                div()
+                                                    .id("batch-progress")
+                                                    .text_size(px(11.0))
+                                                    .text_color(colors.muted_foreground)
+                                                    .when_some(run.failures().last().cloned(), |el, failure| {
+                                                        el.tooltip(move |window, cx| {
+                                                            Tooltip::new(failure.clone()).build(window, cx)
+                                                        })
+                                                    })
+                                                    .child(label)
+                                            }))
+                                            .children(self.batch_error.iter().map(|message| {
+                                                div()
+                                                    .text_color(colors.error_foreground)
+                                                    .text_size(px(11.0))
+                                                    .child(message.clone())
+                                            }))
+                                            .child({
+                                                let button = Button::new("auto-generate-loop-button")
+                                                    .label("Auto on Loop")
+                                                    .on_click(cx.listener(|this, _, _window, cx| {
+                                                        this.on_auto_generate_on_loop_toggled(cx)
+                                                    }));
+                                                if self.auto_generate_on_loop {
+                                                    button.primary()
+                                                } else {
+                                                    button
+                                                }
+                                            })
+                                            .child({
+                                                let button = Button::new("note-expression-button")
+                                                    .label("Expression")
+                                                    .tooltip(
+                                                        "Send applied notes with CLAP volume/brightness expressions",
+                                                    )
+                                                    .on_click(cx.listener(|this, _, _window, cx| {
+                                                        this.on_note_expression_output_toggled(cx)
+                                                    }));
+                                                if self.note_expression_output {
+                                                    button.primary()
+                                                } else {
+                                                    button
+                                                }
+                                            })
+                                            .child({
+                                                let button = Button::new("auto-apply-button")
+                                                    .label("Auto Apply")
+                                                    .on_click(cx.listener(|this, _, _window, cx| {
+                                                        this.on_auto_apply_toggled(cx)
+                                                    }));
+                                                if self.auto_apply_first_candidate {
+                                                    button.primary()
+                                                } else {
+                                                    button
+                                                }
+                                            })
                                             .child(
                                                 Button::new("apply-to-daw-button")
                                                     .label("Apply to DAW")
-                                                    .disabled(true),
+                                                    .tooltip(format!(
+                                                        "Apply Destination: {}",
+                                                        self.settings_ui_state.apply_destination().label()
+                                                    ))
+                                                    .disabled(
+                                                        (self.helper_control_sender.is_none()
+                                                            && self
+                                                                .settings_ui_state
+                                                                .apply_destination()
+                                                                .sends_to_plugin())
+                                                            || self.selected_candidate_index.is_none(),
+                                                    )
+                                                    .on_click(cx.listener(|this, _, _window, cx| {
+                                                        this.on_apply_to_daw_clicked(cx)
+                                                    })),
                                             )
                                             .child(
                                                 Button::new("generate-button")
@@ -3818,7 +10468,7 @@ impl Render for SonantMainWindow {
                                                     } else {
                                                         "Generate"
                                                     })
-                                                    .loading(generating)
+                                                    .loading(generating && !theme.reduced_motion)
                                                     .disabled(generating || !mode_requirement_satisfied)
                                                     .on_click(cx.listener(|this, _, window, cx| {
                                                         this.on_generate_clicked(window, cx)
@@ -3834,18 +10484,60 @@ impl Render for SonantMainWindow {
 #[cfg(test)]
 mod tests {
     use super::{
-        build_live_reference_summary, collect_live_references,
+        NumberFormat, build_daw_context, build_live_reference_summary, collect_live_references,
         first_available_live_channel_for_slot, first_available_live_channel_for_slot_in_model,
-        live_channel_used_by_other_slots, midi_channel_from_status, parse_bpm_input_value,
-        preferred_live_channel_for_slot, recording_enabled_for_channel_array,
-        resolve_live_channel_mapping_for_slot, summarize_live_recording,
+        keyboard_param_level, live_channel_used_by_other_slots, mark_locked_note_rects,
+        midi_channel_from_status, midi_thru_channels, parse_bpm_input_value,
+        parse_input_track_layout, parse_project_model, preferred_live_channel_for_slot,
+        queue_overflow_summary, recording_enabled_for_channel_array, reordered_row_index,
+        resolve_live_channel_mapping_for_slot, stepped_list_index, summarize_live_recording,
+    };
+    use sonant::app::{
+        ChannelMapping, HostTransportContext, InputTrackModel, LiveInputEvent, MidiInputRouter,
+        QueueOverflowMetrics,
     };
-    use sonant::app::{ChannelMapping, InputTrackModel, LiveInputEvent, MidiInputRouter};
     use sonant::domain::{
-        GeneratedNote, GenerationCandidate, GenerationMode, GenerationParams, GenerationRequest,
-        MidiReferenceEvent, MidiReferenceSummary, ModelRef, ReferenceSlot, ReferenceSource,
+        DawTrackRole, GENERATION_CONTRACT_VERSION, GeneratedNote, GenerationCandidate,
+        GenerationMode, GenerationParams, GenerationRequest, MidiReferenceEvent,
+        MidiReferenceSummary, ModelRef, ReferenceSlot, ReferenceSource, TickResolution,
+        TimeSignature,
     };
 
+    #[test]
+    fn arrow_navigation_is_clamped_to_the_list() {
+        assert_eq!(stepped_list_index(None, 3, 1), Some(0));
+        assert_eq!(stepped_list_index(None, 3, -1), Some(2));
+        assert_eq!(stepped_list_index(Some(1), 3, 1), Some(2));
+        assert_eq!(stepped_list_index(Some(2), 3, 1), Some(2));
+        assert_eq!(stepped_list_index(Some(0), 3, -1), Some(0));
+        assert_eq!(stepped_list_index(Some(5), 3, -1), Some(1));
+        assert_eq!(stepped_list_index(Some(0), 0, 1), None);
+    }
+
+    #[test]
+    fn slider_keys_step_and_jump_within_the_parameter_range() {
+        assert_eq!(keyboard_param_level(3, "right"), Some(4));
+        assert_eq!(keyboard_param_level(3, "down"), Some(2));
+        assert_eq!(keyboard_param_level(1, "left"), Some(1));
+        assert_eq!(keyboard_param_level(5, "up"), Some(5));
+        assert_eq!(keyboard_param_level(3, "home"), Some(1));
+        assert_eq!(keyboard_param_level(3, "end"), Some(5));
+        assert_eq!(keyboard_param_level(3, "space"), None);
+    }
+
+    #[test]
+    fn reordering_rows_shifts_the_rows_between_source_and_target() {
+        let moved_down = (0..4)
+            .map(|row_index| reordered_row_index(row_index, 0, 2))
+            .collect::<Vec<_>>();
+        assert_eq!(moved_down, vec![2, 0, 1, 3]);
+
+        let moved_up = (0..4)
+            .map(|row_index| reordered_row_index(row_index, 3, 1))
+            .collect::<Vec<_>>();
+        assert_eq!(moved_up, vec![0, 2, 3, 1]);
+    }
+
     #[test]
     fn used_channel_is_excluded_only_for_other_live_slots() {
         let mut model = InputTrackModel::new();
@@ -4033,6 +10725,69 @@ mod tests {
         assert!(build_live_reference_summary(ReferenceSlot::Melody, &events, 1).is_none());
     }
 
+    #[test]
+    fn daw_context_lists_live_rows_and_loaded_file_rows() {
+        let mut model = InputTrackModel::new();
+        model
+            .set_source_for_slot(ReferenceSlot::Melody, ReferenceSource::Live)
+            .expect("melody should switch to live");
+        let file_bassline = MidiReferenceSummary {
+            slot: ReferenceSlot::Bassline,
+            source: ReferenceSource::File,
+            file: None,
+            bars: 2,
+            note_count: 4,
+            density_hint: 0.2,
+            min_pitch: 36,
+            max_pitch: 43,
+            events: Vec::new(),
+            transposition: None,
+            tempo: None,
+        };
+
+        let context = build_daw_context(
+            Some(HostTransportContext {
+                tempo_bpm: Some(100.0),
+                time_signature: Some((3, 4)),
+                loop_length_beats: None,
+            }),
+            Some("Chorus 2".to_string()),
+            &model,
+            &[
+                ReferenceSlot::Melody,
+                ReferenceSlot::ChordProgression,
+                ReferenceSlot::Bassline,
+            ],
+            &[file_bassline],
+        );
+
+        assert_eq!(context.host_tempo_bpm, Some(100.0));
+        assert_eq!(
+            context.time_signature,
+            Some(TimeSignature {
+                numerator: 3,
+                denominator: 4,
+            })
+        );
+        assert_eq!(context.loop_length_beats, None);
+        assert_eq!(context.host_track_name.as_deref(), Some("Chorus 2"));
+        assert_eq!(
+            context.track_roles,
+            vec![
+                DawTrackRole {
+                    slot: ReferenceSlot::Melody,
+                    source: ReferenceSource::Live,
+                    channel: Some(1),
+                },
+                DawTrackRole {
+                    slot: ReferenceSlot::Bassline,
+                    source: ReferenceSource::File,
+                    channel: None,
+                },
+            ]
+        );
+    }
+
     #[test]
     fn collect_live_references_excludes_recording_disabled_channels() {
         let mut model = InputTrackModel::new();
@@ -4079,7 +10834,7 @@ mod tests {
         recording_channel_enabled[0] = true;
         recording_channel_enabled[1] = false;
 
-        let references = collect_live_references(&model, &recording_channel_enabled, &router);
+        let references = collect_live_references(&model, &recording_channel_enabled, &router, None);
         assert_eq!(references.len(), 1);
         assert_eq!(references[0].slot, ReferenceSlot::Melody);
         assert_eq!(references[0].source, ReferenceSource::Live);
@@ -4114,12 +10869,20 @@ mod tests {
                 scale: "major".to_string(),
                 density: 3,
                 complexity: 3,
+                syncopation: 3,
+                tick_resolution: TickResolution::DEFAULT,
                 temperature: Some(0.7),
                 top_p: Some(0.9),
                 max_tokens: Some(256),
             },
             references: vec![reference],
             variation_count: 1,
+            locked_notes: Vec::new(),
+            contract_version: GENERATION_CONTRACT_VERSION,
+            daw_context: None,
+            constraints: None,
+            validation: None,
+            anonymize_references: false,
         };
 
         assert!(request.validate().is_ok());
@@ -4146,18 +10909,130 @@ mod tests {
 
     #[test]
     fn parse_bpm_input_value_accepts_values_in_supported_range() {
-        assert_eq!(parse_bpm_input_value("20"), Some(20));
-        assert_eq!(parse_bpm_input_value("120"), Some(120));
-        assert_eq!(parse_bpm_input_value("300"), Some(300));
-        assert_eq!(parse_bpm_input_value(" 128 "), Some(128));
+        let format = NumberFormat::DECIMAL_POINT;
+        assert_eq!(parse_bpm_input_value("20", format), Some(20));
+        assert_eq!(parse_bpm_input_value("120", format), Some(120));
+        assert_eq!(parse_bpm_input_value("300", format), Some(300));
+        assert_eq!(parse_bpm_input_value(" 128 ", format), Some(128));
     }
 
     #[test]
     fn parse_bpm_input_value_rejects_invalid_values() {
-        assert_eq!(parse_bpm_input_value(""), None);
-        assert_eq!(parse_bpm_input_value("abc"), None);
-        assert_eq!(parse_bpm_input_value("19"), None);
-        assert_eq!(parse_bpm_input_value("301"), None);
+        let format = NumberFormat::DECIMAL_POINT;
+        assert_eq!(parse_bpm_input_value("", format), None);
+        assert_eq!(parse_bpm_input_value("abc", format), None);
+        assert_eq!(parse_bpm_input_value("19", format), None);
+        assert_eq!(parse_bpm_input_value("301", format), None);
+        assert_eq!(parse_bpm_input_value("300,6", format), None);
+    }
+
+    #[test]
+    fn parse_bpm_input_value_rounds_locale_formatted_decimals() {
+        assert_eq!(
+            parse_bpm_input_value("120,5", NumberFormat::DECIMAL_COMMA),
+            Some(121)
+        );
+        assert_eq!(
+            parse_bpm_input_value("99.4", NumberFormat::DECIMAL_POINT),
+            Some(99)
+        );
+        assert_eq!(
+            parse_bpm_input_value("127,6", NumberFormat::DECIMAL_POINT),
+            Some(128)
+        );
+    }
+
+    #[test]
+    fn parse_input_track_layout_restores_rows_sources_and_channels() {
+        let raw = r#"{
+            "rows": ["melody", "drum_pattern", "melody"],
+            "slot_sources": [{"slot": "drum_pattern", "source": "live"}],
+            "channel_mappings": [{"slot": "drum_pattern", "channel": 12}]
+        }"#;
+
+        let (model, rows) = parse_input_track_layout(raw).expect("layout should parse");
+
+        assert_eq!(
+            rows,
+            vec![
+                ReferenceSlot::Melody,
+                ReferenceSlot::DrumPattern,
+                ReferenceSlot::Melody
+            ]
+        );
+        assert_eq!(
+            model.source_for_slot(ReferenceSlot::DrumPattern),
+            ReferenceSource::Live
+        );
+        assert_eq!(
+            model.live_channel_mappings(),
+            vec![ChannelMapping {
+                slot: ReferenceSlot::DrumPattern,
+                channel: 12,
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_input_track_layout_reports_invalid_payloads() {
+        assert!(parse_input_track_layout("not json").is_err());
+        assert!(
+            parse_input_track_layout(
+                r#"{"channel_mappings": [{"slot": "melody", "channel": 17}]}"#
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn parse_project_model_accepts_only_complete_model_refs() {
+        assert_eq!(
+            parse_project_model(r#"{"provider": "anthropic", "model": "claude-3-5-sonnet"}"#),
+            Some(ModelRef {
+                provider: "anthropic".to_string(),
+                model: "claude-3-5-sonnet".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_project_model(r#"{"provider": "anthropic", "model": " "}"#),
+            None
+        );
+        assert_eq!(parse_project_model("not json"), None);
+    }
+
+    #[test]
+    fn midi_thru_channels_cover_only_visible_live_slots_with_thru_enabled() {
+        let mut model = InputTrackModel::new();
+        model
+            .set_source_for_slot(ReferenceSlot::Melody, ReferenceSource::Live)
+            .expect("melody should switch to live");
+        model
+            .set_source_for_slot(ReferenceSlot::DrumPattern, ReferenceSource::Live)
+            .expect("drums should switch to live");
+        let thru_slots = [
+            ReferenceSlot::Melody,
+            ReferenceSlot::DrumPattern,
+            ReferenceSlot::ChordProgression,
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(
+            midi_thru_channels(
+                &model,
+                &[ReferenceSlot::Melody, ReferenceSlot::ChordProgression],
+                &thru_slots
+            ),
+            vec![1]
+        );
+        assert_eq!(
+            midi_thru_channels(
+                &model,
+                &[ReferenceSlot::DrumPattern, ReferenceSlot::Melody],
+                &thru_slots
+            ),
+            vec![1, 10]
+        );
     }
 
     #[test]
@@ -4197,6 +11072,17 @@ mod tests {
         assert_eq!(super::SonantMainWindow::piano_roll_beat_label(15), "4.4");
     }
 
+    #[test]
+    fn piano_roll_chord_x_places_labels_at_bar_starts_inside_the_grid() {
+        assert_eq!(super::SonantMainWindow::piano_roll_chord_x(1), Some(0.0));
+        assert_eq!(
+            super::SonantMainWindow::piano_roll_chord_x(3),
+            Some(8.0 * super::PIANO_ROLL_BEAT_WIDTH)
+        );
+        assert_eq!(super::SonantMainWindow::piano_roll_chord_x(0), None);
+        assert_eq!(super::SonantMainWindow::piano_roll_chord_x(17), None);
+    }
+
     #[test]
     fn generation_mode_output_slot_maps_modes_to_track_colors() {
         assert_eq!(
@@ -4226,7 +11112,11 @@ mod tests {
                     velocity: 100,
                     channel: 1,
                 }],
+                tick_resolution: TickResolution::DEFAULT,
                 score_hint: Some(0.9),
+                title: None,
+                control_events: Vec::new(),
+                processing: None,
             },
             GenerationCandidate {
                 id: "cand-preview".to_string(),
@@ -4238,7 +11128,11 @@ mod tests {
                     velocity: 96,
                     channel: 1,
                 }],
+                tick_resolution: TickResolution::DEFAULT,
                 score_hint: Some(0.7),
+                title: None,
+                control_events: Vec::new(),
+                processing: None,
             },
         ];
 
@@ -4271,6 +11165,63 @@ mod tests {
         assert!((preview.x - 80.0).abs() < 0.001);
     }
 
+    #[test]
+    fn only_selected_candidate_notes_are_lockable_and_marked() {
+        let note = |pitch, start_tick| GeneratedNote {
+            pitch,
+            start_tick,
+            duration_tick: 240,
+            velocity: 100,
+            channel: 1,
+        };
+        let candidates = vec![
+            GenerationCandidate {
+                id: "cand-selected".to_string(),
+                bars: 4,
+                notes: vec![note(60, 0), note(64, 480)],
+                tick_resolution: TickResolution::DEFAULT,
+                score_hint: None,
+                title: None,
+                control_events: Vec::new(),
+                processing: None,
+            },
+            GenerationCandidate {
+                id: "cand-preview".to_string(),
+                bars: 4,
+                notes: vec![note(64, 480)],
+                tick_resolution: TickResolution::DEFAULT,
+                score_hint: None,
+                title: None,
+                control_events: Vec::new(),
+                processing: None,
+            },
+        ];
+        let mut note_rects = super::SonantMainWindow::piano_roll_note_rects(
+            &[],
+            &[],
+            &std::collections::HashSet::new(),
+            &candidates,
+            Some(0),
+            &std::collections::HashSet::new(),
+            super::SonantTheme::default().colors,
+        );
+
+        mark_locked_note_rects(&mut note_rects, &candidates[0], &[note(64, 480)]);
+
+        let locked = note_rects
+            .iter()
+            .filter(|rect| rect.is_locked)
+            .map(|rect| rect.note_index)
+            .collect::<Vec<_>>();
+        assert_eq!(locked, vec![Some(1)]);
+        assert!(
+            note_rects
+                .iter()
+                .filter(|rect| rect.is_preview)
+                .all(|rect| rect.note_index.is_none())
+        );
+    }
+
     #[test]
     fn piano_roll_note_rects_skip_hidden_candidates() {
         let candidates = vec![
@@ -4284,7 +11235,11 @@ mod tests {
                     velocity: 100,
                     channel: 1,
                 }],
+                tick_resolution: TickResolution::DEFAULT,
                 score_hint: None,
+                title: None,
+                control_events: Vec::new(),
+                processing: None,
             },
             GenerationCandidate {
                 id: "cand-visible".to_string(),
@@ -4296,7 +11251,11 @@ mod tests {
                     velocity: 100,
                     channel: 1,
                 }],
+                tick_resolution: TickResolution::DEFAULT,
                 score_hint: None,
+                title: None,
+                control_events: Vec::new(),
+                processing: None,
             },
         ];
 
@@ -4343,6 +11302,8 @@ mod tests {
                         .to_string(),
                 },
             ],
+            transposition: None,
+            tempo: None,
         }];
 
         let hidden_rows = std::collections::HashSet::new();
@@ -4397,4 +11358,26 @@ mod tests {
             Some("major")
         );
     }
+
+    #[test]
+    fn queue_overflow_summary_is_hidden_until_events_are_dropped() {
+        assert_eq!(queue_overflow_summary(None, 0), None);
+        assert_eq!(
+            queue_overflow_summary(Some(QueueOverflowMetrics::default()), 0),
+            None
+        );
+
+        let summary = queue_overflow_summary(
+            Some(QueueOverflowMetrics {
+                live_input_dropped: 4,
+                app_input_dropped: 0,
+                generated_output_dropped: 1,
+            }),
+            2,
+        )
+        .expect("drops should be summarized");
+        assert!(summary.contains("live input 4"));
+        assert!(summary.contains("helper capture 2"));
+        assert!(summary.contains("output 1"));
+    }
 }
@@ -2,8 +2,8 @@ use midly::num::{u4, u7, u15, u28};
 use midly::{Format, Header, MetaMessage, MidiMessage, Smf, Timing, TrackEvent, TrackEventKind};
 use sonant::app::{LoadMidiCommand, LoadMidiOutcome, LoadMidiUseCase};
 use sonant::domain::{
-    GenerationMode, GenerationParams, GenerationRequest, LlmError, MidiReferenceSummary, ModelRef,
-    ReferenceSlot,
+    GENERATION_CONTRACT_VERSION, GenerationMode, GenerationParams, GenerationRequest, LlmError,
+    MidiReferenceSummary, ModelRef, ReferenceSlot, TickResolution,
 };
 
 #[path = "support/temp_file_fixture.rs"]
@@ -159,12 +159,20 @@ fn valid_request(mode: GenerationMode, references: Vec<MidiReferenceSummary>) ->
             scale: "major".to_string(),
             density: 3,
             complexity: 3,
+            syncopation: 3,
+            tick_resolution: TickResolution::DEFAULT,
             temperature: Some(0.7),
             top_p: Some(0.9),
             max_tokens: Some(512),
         },
         references,
         variation_count: 1,
+        locked_notes: Vec::new(),
+        contract_version: GENERATION_CONTRACT_VERSION,
+        daw_context: None,
+        constraints: None,
+        validation: None,
+        anonymize_references: false,
     }
 }
 
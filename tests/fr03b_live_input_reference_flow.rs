@@ -2,8 +2,9 @@ use sonant::app::{
     ChannelMapping, InputTrackModel, InputTrackModelError, LiveInputEvent, MidiInputRouter,
 };
 use sonant::domain::{
-    GenerationMode, GenerationParams, GenerationRequest, MidiReferenceEvent, MidiReferenceSummary,
-    ModelRef, ReferenceSlot, ReferenceSource, calculate_reference_density_hint,
+    GENERATION_CONTRACT_VERSION, GenerationMode, GenerationParams, GenerationRequest,
+    MidiReferenceEvent, MidiReferenceSummary, ModelRef, ReferenceSlot, ReferenceSource,
+    TickResolution, calculate_reference_density_hint,
 };
 
 const ALL_REFERENCE_SLOTS: [ReferenceSlot; 7] = [
@@ -199,12 +200,20 @@ fn valid_continuation_request(references: Vec<MidiReferenceSummary>) -> Generati
             scale: "major".to_string(),
             density: 3,
             complexity: 3,
+            syncopation: 3,
+            tick_resolution: TickResolution::DEFAULT,
             temperature: Some(0.7),
             top_p: Some(0.9),
             max_tokens: Some(512),
         },
         references,
         variation_count: 1,
+        locked_notes: Vec::new(),
+        contract_version: GENERATION_CONTRACT_VERSION,
+        daw_context: None,
+        constraints: None,
+        validation: None,
+        anonymize_references: false,
     }
 }
 
@@ -288,6 +297,8 @@ fn build_live_reference_summary(
         min_pitch,
         max_pitch,
         events: build_live_reference_events(events),
+        transposition: None,
+        tempo: None,
     };
 
     reference.validate().ok().map(|_| reference)
@@ -7,12 +7,14 @@ use mockito::{Matcher, Server};
 use serde_json::json;
 use sonant::app::{GenerationRetryConfig, GenerationService};
 use sonant::domain::{
-    GeneratedNote, GenerationCandidate, GenerationMetadata, GenerationMode, GenerationParams,
-    GenerationRequest, GenerationResult, GenerationUsage, LlmError, ModelRef,
+    GENERATION_CONTRACT_VERSION, GeneratedNote, GenerationCandidate, GenerationMetadata,
+    GenerationMode, GenerationParams, GenerationRequest, GenerationResult, GenerationUsage,
+    LlmError, ModelRef, TickResolution,
 };
 use sonant::infra::llm::schema_validator::LlmResponseSchemaValidator;
 use sonant::infra::llm::{
-    AnthropicProvider, LlmProvider, OpenAiCompatibleProvider, ProviderRegistry,
+    AnthropicProvider, AuditLog, LlmProvider, OpenAiCompatibleProvider, ProviderRegistry,
+    RemoteServerProvider,
 };
 
 fn valid_request(provider: &str, model: &str) -> GenerationRequest {
@@ -30,12 +32,20 @@ fn valid_request(provider: &str, model: &str) -> GenerationRequest {
             scale: "major".to_string(),
             density: 3,
             complexity: 3,
+            syncopation: 3,
+            tick_resolution: TickResolution::DEFAULT,
             temperature: Some(0.7),
             top_p: Some(0.9),
             max_tokens: Some(512),
         },
         references: Vec::new(),
         variation_count: 1,
+        locked_notes: Vec::new(),
+        contract_version: GENERATION_CONTRACT_VERSION,
+        daw_context: None,
+        constraints: None,
+        validation: None,
+        anonymize_references: false,
     }
 }
 
@@ -53,9 +63,14 @@ fn valid_result(request: &GenerationRequest) -> GenerationResult {
                 velocity: 96,
                 channel: 1,
             }],
+            tick_resolution: TickResolution::DEFAULT,
             score_hint: Some(0.8),
+            title: None,
+            control_events: Vec::new(),
+            processing: None,
         }],
         metadata: GenerationMetadata::default(),
+        contract_version: GENERATION_CONTRACT_VERSION,
     }
 }
 
@@ -299,7 +314,53 @@ fn anthropic_generate_maps_rate_limit_http_error() {
         .expect_err("429 should map to rate-limited error");
 
     mock.assert();
-    assert!(matches!(error, LlmError::RateLimited));
+    assert!(matches!(error, LlmError::RateLimited { .. }));
+}
+
+#[test]
+fn anthropic_generate_records_redacted_exchange_in_audit_log() {
+    let mut server = Server::new();
+    let mock = server
+        .mock("POST", "/v1/messages")
+        .with_status(401)
+        .with_header("content-type", "application/json")
+        .with_body(
+            r#"{"error":{"type":"authentication_error","message":"bad key secret-key-123"}}"#,
+        )
+        .create();
+
+    let audit_dir = std::env::temp_dir().join(format!(
+        "sonant-fr04-audit-{}-{}",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("clock should be after unix epoch")
+            .as_nanos()
+    ));
+    let audit_log = Arc::new(AuditLog::new(&audit_dir, Vec::new()));
+    let provider =
+        AnthropicProvider::with_config("secret-key-123", server.url(), Duration::from_secs(2))
+            .expect("provider should build")
+            .with_audit_log(Arc::clone(&audit_log));
+    let request = valid_request("anthropic", "claude-3-5-sonnet");
+
+    let error = provider
+        .generate(&request)
+        .expect_err("401 should map to an auth error");
+
+    mock.assert();
+    assert_eq!(error, LlmError::Auth);
+    let last = std::fs::read_to_string(
+        audit_log
+            .last_exchange_path()
+            .expect("the exchange should be recorded"),
+    )
+    .expect("last exchange should be readable");
+    assert!(last.contains(&request.prompt));
+    assert!(last.contains("\"http_status\": 401"));
+    assert!(!last.contains("secret-key-123"));
+
+    let _ = std::fs::remove_dir_all(audit_dir);
 }
 
 #[test]
@@ -399,6 +460,90 @@ fn openai_compatible_generate_maps_timeout_http_error() {
     assert!(matches!(error, LlmError::Timeout));
 }
 
+#[test]
+fn remote_server_generate_submits_and_polls_job_through_http_mock() {
+    let mut server = Server::new();
+    let request = valid_request("sonant_server", "studio-large");
+    let submit = server
+        .mock("POST", "/v1/generations")
+        .match_header("authorization", "Bearer studio-token")
+        .match_body(Matcher::PartialJson(json!({
+            "request_id": "req-1",
+            "model": { "provider": "sonant_server", "model": "studio-large" }
+        })))
+        .with_status(202)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"job_id":"job-7"}"#)
+        .create();
+    let poll = server
+        .mock("GET", "/v1/generations/job-7")
+        .match_header("authorization", "Bearer studio-token")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(json!({ "status": "completed", "result": valid_result(&request) }).to_string())
+        .create();
+
+    let provider = RemoteServerProvider::with_config(
+        format!("{}/", server.url()),
+        "studio-token",
+        Duration::from_secs(5),
+    )
+    .expect("provider should build")
+    .with_poll_interval(Duration::from_millis(10));
+
+    let result = provider
+        .generate(&request)
+        .expect("completed job should return its result");
+
+    submit.assert();
+    poll.assert();
+    assert_eq!(result, valid_result(&request));
+}
+
+#[test]
+fn remote_server_generate_maps_rejected_token_and_failed_jobs() {
+    let mut server = Server::new();
+    let request = valid_request("sonant_server", "studio-large");
+    let rejected = server
+        .mock("POST", "/v1/generations")
+        .match_header("authorization", "Bearer wrong-token")
+        .with_status(401)
+        .create();
+    let provider =
+        RemoteServerProvider::with_config(server.url(), "wrong-token", Duration::from_secs(5))
+            .expect("provider should build");
+
+    let error = provider
+        .generate(&request)
+        .expect_err("401 should map to an auth error");
+
+    rejected.assert();
+    assert!(matches!(error, LlmError::Auth));
+
+    server
+        .mock("POST", "/v1/generations")
+        .match_header("authorization", "Bearer studio-token")
+        .with_status(202)
+        .with_body(r#"{"job_id":"job-8"}"#)
+        .create();
+    server
+        .mock("GET", "/v1/generations/job-8")
+        .with_status(200)
+        .with_body(r#"{"status":"failed","error":"GPU out of memory"}"#)
+        .create();
+    let provider =
+        RemoteServerProvider::with_config(server.url(), "studio-token", Duration::from_secs(5))
+            .expect("provider should build");
+
+    let error = provider
+        .generate(&request)
+        .expect_err("failed job should surface its error");
+
+    assert!(
+        matches!(error, LlmError::Transport { message } if message.contains("GPU out of memory"))
+    );
+}
+
 #[test]
 fn generation_service_retries_retryable_errors_until_success() {
     let calls = Arc::new(AtomicUsize::new(0));
@@ -418,6 +563,7 @@ fn generation_service_retries_retryable_errors_until_success() {
             max_attempts: 3,
             initial_backoff: Duration::from_millis(1),
             max_backoff: Duration::from_millis(2),
+            ..GenerationRetryConfig::default()
         },
     )
     .expect("retry config should be valid");
@@ -6,9 +6,10 @@ use mockito::{Matcher, Server};
 use serde_json::json;
 use sonant::app::GenerationService;
 use sonant::domain::{
-    FileReferenceInput, GeneratedNote, GenerationCandidate, GenerationMetadata, GenerationMode,
-    GenerationParams, GenerationRequest, GenerationResult, LlmError, MidiReferenceEvent,
-    MidiReferenceSummary, ModelRef, ReferenceSlot, ReferenceSource,
+    FileReferenceInput, GENERATION_CONTRACT_VERSION, GeneratedNote, GenerationCandidate,
+    GenerationMetadata, GenerationMode, GenerationParams, GenerationRequest, GenerationResult,
+    LlmError, MidiReferenceEvent, MidiReferenceSummary, ModelRef, ReferenceSlot, ReferenceSource,
+    TickResolution,
 };
 use sonant::infra::llm::{
     AnthropicProvider, LlmProvider, OpenAiCompatibleProvider, ProviderRegistry,
@@ -29,12 +30,20 @@ fn valid_request(provider: &str, model: &str, mode: GenerationMode) -> Generatio
             scale: "minor".to_string(),
             density: 3,
             complexity: 3,
+            syncopation: 3,
+            tick_resolution: TickResolution::DEFAULT,
             temperature: Some(0.7),
             top_p: Some(0.9),
             max_tokens: Some(512),
         },
         references: Vec::new(),
         variation_count: 1,
+        locked_notes: Vec::new(),
+        contract_version: GENERATION_CONTRACT_VERSION,
+        daw_context: None,
+        constraints: None,
+        validation: None,
+        anonymize_references: false,
     }
 }
 
@@ -56,6 +65,8 @@ fn sample_reference(slot: ReferenceSlot) -> MidiReferenceSummary {
             delta_tick: 0,
             event: "NoteOn channel=0 key=60 vel=90".to_string(),
         }],
+        transposition: None,
+        tempo: None,
     }
 }
 
@@ -99,9 +110,14 @@ fn valid_result(request: &GenerationRequest) -> GenerationResult {
                 velocity: 96,
                 channel: 1,
             }],
+            tick_resolution: TickResolution::DEFAULT,
             score_hint: None,
+            title: None,
+            control_events: Vec::new(),
+            processing: None,
         }],
         metadata: GenerationMetadata::default(),
+        contract_version: GENERATION_CONTRACT_VERSION,
     }
 }
 
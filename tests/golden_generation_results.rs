@@ -0,0 +1,17 @@
+#[path = "support/golden_harness.rs"]
+mod golden_harness;
+
+use golden_harness::{check_golden_case, golden_case_names};
+
+#[test]
+fn parsed_generation_results_match_golden_files() {
+    let names = golden_case_names();
+    assert!(!names.is_empty(), "at least one golden case is required");
+
+    let failures = names
+        .iter()
+        .filter_map(|name| check_golden_case(name).err())
+        .collect::<Vec<_>>();
+
+    assert!(failures.is_empty(), "{}", failures.join("\n\n"));
+}
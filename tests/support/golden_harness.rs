@@ -0,0 +1,147 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use mockito::Server;
+use sonant::app::GenerationService;
+use sonant::domain::{GenerationRequest, GenerationResult};
+use sonant::infra::llm::{
+    AnthropicProvider, LlmProvider, OpenAiCompatibleProvider, ProviderRegistry,
+};
+
+/// Set to `1` to rewrite `expected_result.json` files from the current parser output.
+pub(crate) const UPDATE_GOLDEN_ENV: &str = "SONANT_UPDATE_GOLDEN";
+
+const REQUEST_FILE_NAME: &str = "request.json";
+const PROVIDER_RESPONSE_FILE_NAME: &str = "provider_response.json";
+const EXPECTED_RESULT_FILE_NAME: &str = "expected_result.json";
+
+pub(crate) struct GoldenCase {
+    pub(crate) name: String,
+    request: GenerationRequest,
+    provider_response: String,
+    expected_path: PathBuf,
+}
+
+impl GoldenCase {
+    pub(crate) fn load(name: &str) -> Self {
+        let dir = golden_root().join(name);
+        let request = fs::read_to_string(dir.join(REQUEST_FILE_NAME)).unwrap_or_else(|error| {
+            panic!("golden case '{name}' needs {REQUEST_FILE_NAME}: {error}")
+        });
+        let request = serde_json::from_str(&request).unwrap_or_else(|error| {
+            panic!("golden case '{name}' has an invalid {REQUEST_FILE_NAME}: {error}")
+        });
+        let provider_response = fs::read_to_string(dir.join(PROVIDER_RESPONSE_FILE_NAME))
+            .unwrap_or_else(|error| {
+                panic!("golden case '{name}' needs {PROVIDER_RESPONSE_FILE_NAME}: {error}")
+            });
+
+        Self {
+            name: name.to_string(),
+            request,
+            provider_response,
+            expected_path: dir.join(EXPECTED_RESULT_FILE_NAME),
+        }
+    }
+}
+
+pub(crate) fn golden_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join("generation")
+}
+
+pub(crate) fn golden_case_names() -> Vec<String> {
+    let mut names = fs::read_dir(golden_root())
+        .expect("golden directory should be readable")
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+/// Serves the canned provider body from a mock server and runs the request through `GenerationService`.
+pub(crate) fn run_golden_case(case: &GoldenCase) -> GenerationResult {
+    let mut server = Server::new();
+    let model = &case.request.model;
+    let (endpoint, registry) = if model.provider == "anthropic" {
+        let provider =
+            AnthropicProvider::with_config("test-key", server.url(), Duration::from_secs(2))
+                .expect("anthropic provider should build");
+        ("/v1/messages", registry_with(provider))
+    } else {
+        let provider = OpenAiCompatibleProvider::with_config(
+            model.provider.clone(),
+            "test-key",
+            server.url(),
+            Duration::from_secs(2),
+            vec![model.model.clone()],
+        )
+        .expect("openai-compatible provider should build");
+        ("/v1/chat/completions", registry_with(provider))
+    };
+
+    let mock = server
+        .mock("POST", endpoint)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(&case.provider_response)
+        .create();
+
+    let mut result = GenerationService::new(registry)
+        .generate(case.request.clone())
+        .unwrap_or_else(|error| panic!("golden case '{}' failed: {error:?}", case.name));
+    mock.assert();
+
+    // Latency is wall-clock time; keeping it would make every golden file flaky.
+    result.metadata.latency_ms = None;
+    result
+}
+
+/// Compares the case against its golden file, or rewrites the file when `UPDATE_GOLDEN_ENV=1`.
+pub(crate) fn check_golden_case(name: &str) -> Result<(), String> {
+    let case = GoldenCase::load(name);
+    let actual = run_golden_case(&case);
+
+    if std::env::var(UPDATE_GOLDEN_ENV).is_ok_and(|value| value == "1") {
+        fs::write(
+            &case.expected_path,
+            format!("{}\n", to_pretty_json(&actual)),
+        )
+        .expect("golden file should be writable");
+        return Ok(());
+    }
+
+    let expected = fs::read_to_string(&case.expected_path).map_err(|error| {
+        format!("golden case '{name}' needs {EXPECTED_RESULT_FILE_NAME}: {error}")
+    })?;
+    let expected: GenerationResult = serde_json::from_str(&expected).map_err(|error| {
+        format!("golden case '{name}' has an invalid {EXPECTED_RESULT_FILE_NAME}: {error}")
+    })?;
+
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(format!(
+            "golden case '{name}' changed (rerun with {UPDATE_GOLDEN_ENV}=1 if intended)\n--- expected\n{}\n--- actual\n{}",
+            to_pretty_json(&expected),
+            to_pretty_json(&actual)
+        ))
+    }
+}
+
+fn registry_with(provider: impl LlmProvider + 'static) -> ProviderRegistry {
+    let mut registry = ProviderRegistry::new();
+    registry
+        .register(provider)
+        .expect("provider registration should succeed");
+    registry
+}
+
+fn to_pretty_json(result: &GenerationResult) -> String {
+    serde_json::to_string_pretty(result).expect("generation result should serialize")
+}